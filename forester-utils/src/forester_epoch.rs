@@ -63,6 +63,8 @@ pub struct TreeAccounts {
     pub is_rolledover: bool,
     pub tree_type: TreeType,
     pub owner: Pubkey,
+    /// Network fee of the tree's rollover metadata.
+    pub network_fee: u64,
 }
 
 impl TreeAccounts {
@@ -72,6 +74,7 @@ impl TreeAccounts {
         tree_type: TreeType,
         is_rolledover: bool,
         owner: Pubkey,
+        network_fee: u64,
     ) -> Self {
         Self {
             merkle_tree,
@@ -79,6 +82,7 @@ impl TreeAccounts {
             tree_type,
             is_rolledover,
             owner,
+            network_fee,
         }
     }
 }
@@ -1,4 +1,12 @@
-use std::{fmt::Debug, num::NonZeroU32, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
 
 use governor::{
     clock::DefaultClock,
@@ -18,35 +26,59 @@ pub enum RateLimiterError {
     RateLimitExceeded,
 }
 
+type DirectGovernor = Governor<NotKeyed, InMemoryState, DefaultClock>;
+
+/// Clones share the limit, so that changing the rate of one clone applies to
+/// all of them.
 #[derive(Clone, Debug)]
 pub struct RateLimiter {
-    governor: Arc<Governor<NotKeyed, InMemoryState, DefaultClock>>,
+    governor: Arc<RwLock<Arc<DirectGovernor>>>,
+    requests_per_second: Arc<AtomicU32>,
+}
+
+fn new_governor(requests_per_second: u32) -> DirectGovernor {
+    // Create a quota that allows exactly one request per 1/requests_per_second seconds
+    let quota = Quota::with_period(Duration::from_secs_f64(1.0 / requests_per_second as f64))
+        .unwrap()
+        .allow_burst(NonZeroU32::new(1).unwrap());
+    Governor::new(quota, InMemoryState::default(), DefaultClock::default())
 }
 
 impl RateLimiter {
     pub fn new(requests_per_second: u32) -> Self {
-        // Create a quota that allows exactly one request per 1/requests_per_second seconds
-        let quota = Quota::with_period(Duration::from_secs_f64(1.0 / requests_per_second as f64))
-            .unwrap()
-            .allow_burst(NonZeroU32::new(1).unwrap());
         RateLimiter {
-            governor: Arc::new(Governor::new(
-                quota,
-                InMemoryState::default(),
-                DefaultClock::default(),
-            )),
+            governor: Arc::new(RwLock::new(Arc::new(new_governor(requests_per_second)))),
+            requests_per_second: Arc::new(AtomicU32::new(requests_per_second)),
         }
     }
 
+    pub fn requests_per_second(&self) -> u32 {
+        self.requests_per_second.load(Ordering::Relaxed)
+    }
+
+    pub fn set_requests_per_second(&self, requests_per_second: u32) {
+        *self.governor.write().unwrap_or_else(|e| e.into_inner()) =
+            Arc::new(new_governor(requests_per_second));
+        self.requests_per_second
+            .store(requests_per_second, Ordering::Relaxed);
+    }
+
+    fn governor(&self) -> Arc<DirectGovernor> {
+        self.governor
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
     pub async fn acquire(&self) -> Result<(), RateLimiterError> {
-        match self.governor.check() {
+        match self.governor().check() {
             Ok(()) => Ok(()),
             Err(_) => Err(RateLimiterError::RateLimitExceeded),
         }
     }
 
     pub async fn acquire_with_wait(&self) {
-        self.governor.until_ready().await;
+        self.governor().until_ready().await;
     }
 }
 
@@ -178,4 +210,16 @@ mod tests {
             elapsed
         );
     }
+
+    #[tokio::test]
+    async fn test_rate_change_applies_to_clones() {
+        let rate_limiter = RateLimiter::new(1);
+        let clone = rate_limiter.clone();
+        assert!(clone.acquire().await.is_ok());
+        assert!(clone.acquire().await.is_err());
+
+        rate_limiter.set_requests_per_second(1000);
+        assert_eq!(clone.requests_per_second(), 1000);
+        assert!(clone.acquire().await.is_ok());
+    }
 }
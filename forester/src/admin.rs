@@ -0,0 +1,503 @@
+//! Runtime control of the forester, served under `/admin` on the API server.
+//!
+//! The admin routes are only served when an admin token is configured and
+//! every request must carry it as `Authorization: Bearer <token>`.
+
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+};
+
+use forester_utils::rate_limiter::RateLimiter;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{mpsc, Notify};
+use tracing::info;
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    reply::{Reply, Response},
+    Filter,
+};
+
+const MAX_BODY_BYTES: u64 = 4 * 1024;
+
+/// Work which the epoch manager runs on request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// Checks the given tree, or all v1 trees, for rollover.
+    RolloverCheck(Option<Pubkey>),
+    /// Re-fetches the accounts of a PDA program and compresses the ready
+    /// ones in the forester's next eligible light slot.
+    CompressionSweep(Pubkey),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdminState {
+    pub paused_trees: Vec<String>,
+    pub draining: bool,
+    pub in_flight_light_slots: usize,
+    /// None when the configured value is used.
+    pub max_batches_per_tree: Option<usize>,
+    /// None when no rate limit is configured.
+    pub rpc_rate_limit: Option<u32>,
+    pub send_tx_rate_limit: Option<u32>,
+}
+
+/// Limits to change; omitted limits are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsUpdate {
+    pub max_batches_per_tree: Option<usize>,
+    pub rpc_rate_limit: Option<u32>,
+    pub send_tx_rate_limit: Option<u32>,
+}
+
+/// Counts a light slot as in flight until dropped.
+#[derive(Debug)]
+pub struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+pub struct AdminControl {
+    paused_trees: RwLock<HashSet<Pubkey>>,
+    draining: AtomicBool,
+    in_flight: Arc<AtomicUsize>,
+    /// Zero when the configured value is used.
+    max_batches_per_tree: AtomicUsize,
+    rpc_rate_limiter: Option<RateLimiter>,
+    send_tx_rate_limiter: Option<RateLimiter>,
+    commands_tx: mpsc::UnboundedSender<AdminCommand>,
+    commands_rx: Mutex<Option<mpsc::UnboundedReceiver<AdminCommand>>>,
+    compression_wakeup: Notify,
+}
+
+impl Default for AdminControl {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl AdminControl {
+    /// The rate limiters are created here so that the admin API can change
+    /// the limits of the RPC pool which uses them.
+    pub fn new(rpc_rate_limit: Option<u32>, send_tx_rate_limit: Option<u32>) -> Self {
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        Self {
+            paused_trees: RwLock::new(HashSet::new()),
+            draining: AtomicBool::new(false),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max_batches_per_tree: AtomicUsize::new(0),
+            rpc_rate_limiter: rpc_rate_limit.map(RateLimiter::new),
+            send_tx_rate_limiter: send_tx_rate_limit.map(RateLimiter::new),
+            commands_tx,
+            commands_rx: Mutex::new(Some(commands_rx)),
+            compression_wakeup: Notify::new(),
+        }
+    }
+
+    pub fn rpc_rate_limiter(&self) -> Option<RateLimiter> {
+        self.rpc_rate_limiter.clone()
+    }
+
+    pub fn send_tx_rate_limiter(&self) -> Option<RateLimiter> {
+        self.send_tx_rate_limiter.clone()
+    }
+
+    pub fn pause_tree(&self, tree: Pubkey) -> bool {
+        self.paused_trees
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tree)
+    }
+
+    pub fn resume_tree(&self, tree: &Pubkey) -> bool {
+        self.paused_trees
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(tree)
+    }
+
+    pub fn is_paused(&self, tree: &Pubkey) -> bool {
+        self.paused_trees
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(tree)
+    }
+
+    /// Stops the forester from starting new light slots and epochs. Light
+    /// slots in flight are finished.
+    pub fn drain(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn start_light_slot(&self) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.in_flight.clone())
+    }
+
+    pub fn max_batches_per_tree(&self) -> Option<usize> {
+        match self.max_batches_per_tree.load(Ordering::Relaxed) {
+            0 => None,
+            value => Some(value),
+        }
+    }
+
+    pub fn update_limits(&self, update: &LimitsUpdate) -> Result<(), String> {
+        if update.max_batches_per_tree == Some(0) {
+            return Err("max_batches_per_tree must be positive".to_string());
+        }
+        for (name, limit, limiter) in [
+            (
+                "rpc_rate_limit",
+                update.rpc_rate_limit,
+                &self.rpc_rate_limiter,
+            ),
+            (
+                "send_tx_rate_limit",
+                update.send_tx_rate_limit,
+                &self.send_tx_rate_limiter,
+            ),
+        ] {
+            match (limit, limiter) {
+                (Some(0), _) => return Err(format!("{} must be positive", name)),
+                (Some(_), None) => {
+                    return Err(format!("{} is not configured, it needs a restart", name))
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(value) = update.max_batches_per_tree {
+            self.max_batches_per_tree.store(value, Ordering::Relaxed);
+        }
+        if let (Some(limit), Some(limiter)) = (update.rpc_rate_limit, &self.rpc_rate_limiter) {
+            limiter.set_requests_per_second(limit);
+        }
+        if let (Some(limit), Some(limiter)) =
+            (update.send_tx_rate_limit, &self.send_tx_rate_limiter)
+        {
+            limiter.set_requests_per_second(limit);
+        }
+        Ok(())
+    }
+
+    pub fn send_command(&self, command: AdminCommand) -> bool {
+        self.commands_tx.send(command).is_ok()
+    }
+
+    /// Taken by the epoch manager, which executes the commands.
+    pub fn take_commands(&self) -> Option<mpsc::UnboundedReceiver<AdminCommand>> {
+        self.commands_rx
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    /// Wakes the compression loop from its idle sleep.
+    pub fn wake_compression(&self) {
+        self.compression_wakeup.notify_waiters();
+    }
+
+    pub async fn compression_requested(&self) {
+        self.compression_wakeup.notified().await;
+    }
+
+    pub fn state(&self) -> AdminState {
+        let mut paused_trees: Vec<String> = self
+            .paused_trees
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        paused_trees.sort();
+        AdminState {
+            paused_trees,
+            draining: self.is_draining(),
+            in_flight_light_slots: self.in_flight.load(Ordering::SeqCst),
+            max_batches_per_tree: self.max_batches_per_tree(),
+            rpc_rate_limit: self
+                .rpc_rate_limiter
+                .as_ref()
+                .map(RateLimiter::requests_per_second),
+            send_tx_rate_limit: self
+                .send_tx_rate_limiter
+                .as_ref()
+                .map(RateLimiter::requests_per_second),
+        }
+    }
+}
+
+/// Compares in constant time, so that the token cannot be guessed byte by
+/// byte from response times.
+fn is_authorized(token: &str, authorization: Option<&str>) -> bool {
+    let Some(provided) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    let (provided, token) = (provided.trim().as_bytes(), token.as_bytes());
+    provided.len() == token.len()
+        && provided
+            .iter()
+            .zip(token)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn error_reply(status: StatusCode, message: impl Into<String>) -> Response {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": message.into() })),
+        status,
+    )
+    .into_response()
+}
+
+fn state_reply(admin: &AdminControl, status: StatusCode) -> Response {
+    warp::reply::with_status(warp::reply::json(&admin.state()), status).into_response()
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, Response> {
+    Pubkey::from_str(value).map_err(|e| {
+        error_reply(
+            StatusCode::BAD_REQUEST,
+            format!("invalid pubkey {}: {}", value, e),
+        )
+    })
+}
+
+fn log_action(action: &str, target: Option<&str>) {
+    info!(
+        event = "admin_action",
+        action,
+        target = target.unwrap_or_default(),
+        "Admin API action"
+    );
+}
+
+fn handle_tree(admin: &AdminControl, tree: &str, pause: bool) -> Response {
+    let tree = match parse_pubkey(tree) {
+        Ok(tree) => tree,
+        Err(reply) => return reply,
+    };
+    if pause {
+        admin.pause_tree(tree);
+        log_action("pause_tree", Some(&tree.to_string()));
+    } else {
+        admin.resume_tree(&tree);
+        log_action("resume_tree", Some(&tree.to_string()));
+    }
+    state_reply(admin, StatusCode::OK)
+}
+
+fn handle_command(admin: &AdminControl, command: AdminCommand) -> Response {
+    let (action, target) = match &command {
+        AdminCommand::RolloverCheck(tree) => ("rollover_check", tree.map(|t| t.to_string())),
+        AdminCommand::CompressionSweep(program) => ("compression_sweep", Some(program.to_string())),
+    };
+    log_action(action, target.as_deref());
+    if admin.send_command(command) {
+        state_reply(admin, StatusCode::ACCEPTED)
+    } else {
+        error_reply(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "forester is not processing epochs",
+        )
+    }
+}
+
+fn handle_limits(admin: &AdminControl, update: LimitsUpdate) -> Response {
+    match admin.update_limits(&update) {
+        Ok(()) => {
+            log_action("update_limits", Some(&format!("{:?}", update)));
+            state_reply(admin, StatusCode::OK)
+        }
+        Err(message) => error_reply(StatusCode::BAD_REQUEST, message),
+    }
+}
+
+/// The `/admin` routes. Requests without the token are answered with 401
+/// before any route is matched.
+pub fn admin_routes(token: String, admin: Arc<AdminControl>) -> BoxedFilter<(Response,)> {
+    let token: Arc<str> = token.into();
+    let with_admin = warp::any().map(move || admin.clone());
+
+    let state = warp::path!("state")
+        .and(warp::get())
+        .and(with_admin.clone())
+        .map(|admin: Arc<AdminControl>| state_reply(&admin, StatusCode::OK));
+
+    let pause = warp::path!("trees" / String / "pause")
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(|tree: String, admin: Arc<AdminControl>| handle_tree(&admin, &tree, true));
+
+    let resume = warp::path!("trees" / String / "resume")
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(|tree: String, admin: Arc<AdminControl>| handle_tree(&admin, &tree, false));
+
+    let rollover_all = warp::path!("rollover-check")
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(|admin: Arc<AdminControl>| handle_command(&admin, AdminCommand::RolloverCheck(None)));
+
+    let rollover_tree = warp::path!("rollover-check" / String)
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(
+            |tree: String, admin: Arc<AdminControl>| match parse_pubkey(&tree) {
+                Ok(tree) => handle_command(&admin, AdminCommand::RolloverCheck(Some(tree))),
+                Err(reply) => reply,
+            },
+        );
+
+    let sweep = warp::path!("compression" / String / "sweep")
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(
+            |program: String, admin: Arc<AdminControl>| match parse_pubkey(&program) {
+                Ok(program) => handle_command(&admin, AdminCommand::CompressionSweep(program)),
+                Err(reply) => reply,
+            },
+        );
+
+    let drain = warp::path!("drain")
+        .and(warp::post())
+        .and(with_admin.clone())
+        .map(|admin: Arc<AdminControl>| {
+            admin.drain();
+            log_action("drain", None);
+            state_reply(&admin, StatusCode::ACCEPTED)
+        });
+
+    let limits = warp::path!("limits")
+        .and(warp::put())
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
+        .and(warp::body::json())
+        .and(with_admin)
+        .map(|update: LimitsUpdate, admin: Arc<AdminControl>| handle_limits(&admin, update));
+
+    let routes = state
+        .or(pause)
+        .unify()
+        .or(resume)
+        .unify()
+        .or(rollover_all)
+        .unify()
+        .or(rollover_tree)
+        .unify()
+        .or(sweep)
+        .unify()
+        .or(drain)
+        .unify()
+        .or(limits)
+        .unify();
+
+    let unauthorized = warp::any().map(|| error_reply(StatusCode::UNAUTHORIZED, "unauthorized"));
+
+    warp::path("admin")
+        .and(
+            warp::header::optional::<String>("authorization")
+                .and_then(move |authorization: Option<String>| {
+                    let authorized = is_authorized(&token, authorization.as_deref());
+                    async move {
+                        if authorized {
+                            Err(warp::reject::not_found())
+                        } else {
+                            Ok(())
+                        }
+                    }
+                })
+                .untuple_one()
+                .and(unauthorized)
+                .or(routes)
+                .unify(),
+        )
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized("secret", Some("Bearer secret")));
+        assert!(!is_authorized("secret", Some("Bearer secreT")));
+        assert!(!is_authorized("secret", Some("Bearer secret2")));
+        assert!(!is_authorized("secret", Some("secret")));
+        assert!(!is_authorized("secret", None));
+    }
+
+    #[test]
+    fn test_update_limits() {
+        let admin = AdminControl::new(Some(10), None);
+        assert!(admin
+            .update_limits(&LimitsUpdate {
+                send_tx_rate_limit: Some(5),
+                ..Default::default()
+            })
+            .is_err());
+        assert!(admin
+            .update_limits(&LimitsUpdate {
+                max_batches_per_tree: Some(0),
+                ..Default::default()
+            })
+            .is_err());
+
+        let limiter = admin.rpc_rate_limiter().unwrap();
+        admin
+            .update_limits(&LimitsUpdate {
+                max_batches_per_tree: Some(2),
+                rpc_rate_limit: Some(50),
+                send_tx_rate_limit: None,
+            })
+            .unwrap();
+        assert_eq!(limiter.requests_per_second(), 50);
+        let state = admin.state();
+        assert_eq!(state.max_batches_per_tree, Some(2));
+        assert_eq!(state.rpc_rate_limit, Some(50));
+        assert_eq!(state.send_tx_rate_limit, None);
+    }
+
+    #[test]
+    fn test_pause_drain_and_commands() {
+        let admin = AdminControl::default();
+        let mut commands = admin.take_commands().unwrap();
+        assert!(admin.take_commands().is_none());
+
+        let tree = Pubkey::new_unique();
+        assert!(admin.pause_tree(tree));
+        assert!(admin.is_paused(&tree));
+        assert_eq!(admin.state().paused_trees, vec![tree.to_string()]);
+        assert!(admin.resume_tree(&tree));
+        assert!(!admin.is_paused(&tree));
+
+        assert!(admin.send_command(AdminCommand::RolloverCheck(Some(tree))));
+        assert_eq!(
+            commands.try_recv().unwrap(),
+            AdminCommand::RolloverCheck(Some(tree))
+        );
+
+        admin.drain();
+        let light_slot = admin.start_light_slot();
+        let state = admin.state();
+        assert!(state.draining);
+        assert_eq!(state.in_flight_light_slots, 1);
+        drop(light_slot);
+        assert_eq!(admin.state().in_flight_light_slots, 0);
+    }
+}
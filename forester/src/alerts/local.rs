@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use super::{AlertAction, AlertEvent, AlertSink};
+
+/// Logs alerts, so that they show up in the forester logs whichever other
+/// sinks are configured.
+#[derive(Debug)]
+pub struct LogSink;
+
+#[async_trait]
+impl AlertSink for LogSink {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    async fn send(&self, event: &AlertEvent) -> crate::Result<()> {
+        match event.action {
+            AlertAction::Trigger => warn!(
+                event = "alert_triggered",
+                rule = %event.rule,
+                dedup_key = %event.dedup_key,
+                severity = event.severity.as_str(),
+                "{}",
+                event.summary
+            ),
+            AlertAction::Resolve => info!(
+                event = "alert_resolved",
+                rule = %event.rule,
+                dedup_key = %event.dedup_key,
+                "{}",
+                event.summary
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Appends alerts as JSON lines to a local file.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl AlertSink for FileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn send(&self, event: &AlertEvent) -> crate::Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        Ok(())
+    }
+}
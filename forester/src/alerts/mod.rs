@@ -0,0 +1,406 @@
+//! Alerting on conditions of the forester, delivered to pluggable sinks.
+//!
+//! Alerts are identified by their rule and a key, e.g. the tree of a failed
+//! rollover. A triggered alert is sent once and repeated after the repeat
+//! interval while it stays active; resolving it sends a resolve event.
+
+mod local;
+mod pagerduty;
+mod webhook;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+pub use local::{FileSink, LogSink};
+pub use pagerduty::PagerDutySink;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
+pub use webhook::WebhookSink;
+
+use crate::{cli::StartArgs, metrics::increment_alerts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertRule {
+    LowBalance,
+    ProverUnhealthy,
+    RolloverFailed,
+    IndexerLag,
+    MissedLightSlots,
+    RegistrationFailed,
+    EpochMonitorDead,
+}
+
+impl AlertRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertRule::LowBalance => "low_balance",
+            AlertRule::ProverUnhealthy => "prover_unhealthy",
+            AlertRule::RolloverFailed => "rollover_failed",
+            AlertRule::IndexerLag => "indexer_lag",
+            AlertRule::MissedLightSlots => "missed_light_slots",
+            AlertRule::RegistrationFailed => "registration_failed",
+            AlertRule::EpochMonitorDead => "epoch_monitor_dead",
+        }
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Severities as defined by PagerDuty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Critical,
+    Error,
+    Warning,
+    Info,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Critical => "critical",
+            AlertSeverity::Error => "error",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    Trigger,
+    Resolve,
+}
+
+impl AlertAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertAction::Trigger => "trigger",
+            AlertAction::Resolve => "resolve",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub action: AlertAction,
+    pub rule: AlertRule,
+    /// Identifies the alert across trigger and resolve events.
+    pub dedup_key: String,
+    pub summary: String,
+    pub severity: AlertSeverity,
+    /// The forester authority.
+    pub source: String,
+    pub timestamp: u64,
+}
+
+#[async_trait]
+pub trait AlertSink: Send + Sync + fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, event: &AlertEvent) -> crate::Result<()>;
+}
+
+/// Thresholds of the alert rules. A threshold of zero disables its rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertThresholds {
+    pub min_balance_sol: f64,
+    pub indexer_lag_slots: u64,
+    /// Consecutive failed light slots of a tree.
+    pub missed_light_slots: u32,
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            min_balance_sol: 1.0,
+            indexer_lag_slots: 150,
+            missed_light_slots: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    pub pagerduty_routing_key: Option<String>,
+    pub webhook_urls: Vec<String>,
+    pub file: Option<PathBuf>,
+    pub thresholds: AlertThresholds,
+    pub repeat_interval: Duration,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            pagerduty_routing_key: None,
+            webhook_urls: Vec::new(),
+            file: None,
+            thresholds: AlertThresholds::default(),
+            repeat_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn new_for_start(args: &StartArgs) -> Self {
+        Self {
+            pagerduty_routing_key: args.pagerduty_routing_key.clone(),
+            webhook_urls: args.alert_webhook_urls.clone(),
+            file: args.alert_file.as_ref().map(PathBuf::from),
+            thresholds: AlertThresholds {
+                min_balance_sol: args.alert_min_balance_sol,
+                indexer_lag_slots: args.alert_indexer_lag_slots,
+                missed_light_slots: args.alert_missed_light_slots,
+            },
+            repeat_interval: Duration::from_secs(args.alert_repeat_interval_seconds),
+        }
+    }
+}
+
+/// Deduplicates alerts and fans them out to the sinks.
+#[derive(Debug)]
+pub struct AlertManager {
+    sinks: Vec<Arc<dyn AlertSink>>,
+    thresholds: AlertThresholds,
+    repeat_interval: Duration,
+    source: String,
+    /// Active alerts by dedup key, with the time they were last sent.
+    active: Mutex<HashMap<String, Instant>>,
+}
+
+impl Default for AlertManager {
+    fn default() -> Self {
+        Self::new(&AlertConfig::default(), &Pubkey::default())
+    }
+}
+
+impl AlertManager {
+    /// Alerts are always logged; the other sinks are added as configured.
+    pub fn new(config: &AlertConfig, authority: &Pubkey) -> Self {
+        let mut sinks: Vec<Arc<dyn AlertSink>> = vec![Arc::new(LogSink)];
+        if let Some(routing_key) = &config.pagerduty_routing_key {
+            sinks.push(Arc::new(PagerDutySink::new(routing_key.clone())));
+        }
+        for url in &config.webhook_urls {
+            sinks.push(Arc::new(WebhookSink::new(url.clone())));
+        }
+        if let Some(path) = &config.file {
+            sinks.push(Arc::new(FileSink::new(path.clone())));
+        }
+        Self::with_sinks(sinks, config, authority)
+    }
+
+    pub fn with_sinks(
+        sinks: Vec<Arc<dyn AlertSink>>,
+        config: &AlertConfig,
+        authority: &Pubkey,
+    ) -> Self {
+        Self {
+            sinks,
+            thresholds: config.thresholds,
+            repeat_interval: config.repeat_interval,
+            source: authority.to_string(),
+            active: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn thresholds(&self) -> &AlertThresholds {
+        &self.thresholds
+    }
+
+    /// Triggers an alert unless it is already active and was sent within the
+    /// repeat interval. Returns whether the alert was sent.
+    pub fn trigger(
+        &self,
+        rule: AlertRule,
+        key: &str,
+        severity: AlertSeverity,
+        summary: impl Into<String>,
+    ) -> bool {
+        let dedup_key = dedup_key(rule, key);
+        {
+            let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+            match active.get(&dedup_key) {
+                Some(sent_at) if sent_at.elapsed() < self.repeat_interval => return false,
+                _ => {
+                    active.insert(dedup_key.clone(), Instant::now());
+                }
+            }
+        }
+        self.dispatch(
+            AlertAction::Trigger,
+            rule,
+            dedup_key,
+            severity,
+            summary.into(),
+        );
+        true
+    }
+
+    /// Resolves an active alert. Returns whether a resolve event was sent.
+    pub fn resolve(&self, rule: AlertRule, key: &str, summary: impl Into<String>) -> bool {
+        let dedup_key = dedup_key(rule, key);
+        if self
+            .active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&dedup_key)
+            .is_none()
+        {
+            return false;
+        }
+        self.dispatch(
+            AlertAction::Resolve,
+            rule,
+            dedup_key,
+            AlertSeverity::Info,
+            summary.into(),
+        );
+        true
+    }
+
+    pub fn is_active(&self, rule: AlertRule, key: &str) -> bool {
+        self.active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(&dedup_key(rule, key))
+    }
+
+    /// Sends the event to all sinks in the background, so that alerting never
+    /// blocks forester work.
+    fn dispatch(
+        &self,
+        action: AlertAction,
+        rule: AlertRule,
+        dedup_key: String,
+        severity: AlertSeverity,
+        summary: String,
+    ) {
+        increment_alerts(rule.as_str(), action.as_str());
+        let event = AlertEvent {
+            action,
+            rule,
+            dedup_key,
+            summary,
+            severity,
+            source: self.source.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("No runtime to send alert {} on", event.dedup_key);
+            return;
+        };
+        for sink in &self.sinks {
+            let sink = sink.clone();
+            let event = event.clone();
+            runtime.spawn(async move {
+                if let Err(e) = sink.send(&event).await {
+                    warn!(
+                        event = "alert_sink_failed",
+                        sink = sink.name(),
+                        dedup_key = %event.dedup_key,
+                        error = ?e,
+                        "Failed to send alert"
+                    );
+                }
+            });
+        }
+    }
+}
+
+fn dedup_key(rule: AlertRule, key: &str) -> String {
+    if key.is_empty() {
+        rule.as_str().to_string()
+    } else {
+        format!("{}:{}", rule.as_str(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct ChannelSink(mpsc::UnboundedSender<AlertEvent>);
+
+    #[async_trait]
+    impl AlertSink for ChannelSink {
+        fn name(&self) -> &'static str {
+            "channel"
+        }
+
+        async fn send(&self, event: &AlertEvent) -> crate::Result<()> {
+            self.0.send(event.clone())?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alerts_are_deduplicated_and_resolved() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let alerts = AlertManager::with_sinks(
+            vec![Arc::new(ChannelSink(tx))],
+            &AlertConfig::default(),
+            &Pubkey::new_unique(),
+        );
+        let tree = Pubkey::new_unique().to_string();
+
+        assert!(alerts.trigger(
+            AlertRule::RolloverFailed,
+            &tree,
+            AlertSeverity::Error,
+            "rollover failed"
+        ));
+        assert!(!alerts.trigger(
+            AlertRule::RolloverFailed,
+            &tree,
+            AlertSeverity::Error,
+            "rollover failed again"
+        ));
+        assert!(alerts.is_active(AlertRule::RolloverFailed, &tree));
+        assert!(!alerts.resolve(AlertRule::LowBalance, "", "balance ok"));
+        assert!(alerts.resolve(AlertRule::RolloverFailed, &tree, "rollover succeeded"));
+        assert!(!alerts.resolve(AlertRule::RolloverFailed, &tree, "rollover succeeded"));
+
+        let trigger = rx.recv().await.unwrap();
+        assert_eq!(trigger.action, AlertAction::Trigger);
+        assert_eq!(trigger.dedup_key, format!("rollover_failed:{}", tree));
+        let resolve = rx.recv().await.unwrap();
+        assert_eq!(resolve.action, AlertAction::Resolve);
+        assert_eq!(resolve.dedup_key, trigger.dedup_key);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_active_alert_is_repeated_after_interval() {
+        let alerts = AlertManager::with_sinks(
+            vec![],
+            &AlertConfig {
+                repeat_interval: Duration::ZERO,
+                ..Default::default()
+            },
+            &Pubkey::new_unique(),
+        );
+        assert!(alerts.trigger(AlertRule::LowBalance, "", AlertSeverity::Critical, "low"));
+        assert!(alerts.trigger(AlertRule::LowBalance, "", AlertSeverity::Critical, "low"));
+    }
+}
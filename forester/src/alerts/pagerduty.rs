@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+
+use super::{AlertEvent, AlertSink};
+use crate::pagerduty::send_pagerduty_event;
+
+/// Sends alerts to the PagerDuty Events API v2, using the dedup key so that
+/// resolve events close the incident.
+pub struct PagerDutySink {
+    routing_key: String,
+}
+
+impl PagerDutySink {
+    pub fn new(routing_key: String) -> Self {
+        Self { routing_key }
+    }
+}
+
+impl std::fmt::Debug for PagerDutySink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PagerDutySink").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl AlertSink for PagerDutySink {
+    fn name(&self) -> &'static str {
+        "pagerduty"
+    }
+
+    async fn send(&self, event: &AlertEvent) -> crate::Result<()> {
+        send_pagerduty_event(
+            &self.routing_key,
+            event.action.as_str(),
+            Some(&event.dedup_key),
+            &event.summary,
+            event.severity.as_str(),
+            &format!("Forester {}", event.source),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
@@ -12,12 +12,16 @@ use tracing::{error, info, warn};
 use warp::Filter;
 
 use crate::{
+    admin::{admin_routes, AdminControl},
     compressible::{
         traits::{CompressibleState, CompressibleTracker},
         CTokenAccountState, CTokenAccountTracker, MintAccountTracker, PdaAccountTracker,
     },
+    earnings::EarningsTracker,
     forester_status::get_forester_status,
     metrics::REGISTRY,
+    sender::DryRunRecorder,
+    shard::ShardCoordinator,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -585,6 +589,15 @@ pub struct ApiServerConfig {
     pub prometheus_url: Option<String>,
     pub helius_rpc: bool,
     pub forester_api_urls: Vec<String>,
+    /// Set in dry-run mode; served on `/dry-run`.
+    pub dry_run_recorder: Option<Arc<DryRunRecorder>>,
+    /// Set when sharding; served on `/shard` for the peers.
+    pub shard_coordinator: Option<Arc<ShardCoordinator>>,
+    /// Served on `/earnings`.
+    pub earnings: Option<Arc<EarningsTracker>>,
+    /// Token required by the `/admin` routes, which are only served when set.
+    pub admin_token: Option<String>,
+    pub admin: Option<Arc<AdminControl>>,
 }
 
 /// Default timeout for status endpoint in seconds
@@ -1210,11 +1223,61 @@ pub fn spawn_api_server(config: ApiServerConfig) -> anyhow::Result<ApiServerHand
                 .and(warp::get())
                 .map(move || warp::reply::json(&*compressible_rx_clone.borrow()));
 
+            // --- Dry-run route (simulated transactions, only in dry-run mode) ---
+            let dry_run_recorder = config.dry_run_recorder.clone();
+            let dry_run_route = warp::path("dry-run").and(warp::get()).and_then(move || {
+                let recorder = dry_run_recorder.clone();
+                async move {
+                    match recorder {
+                        Some(recorder) => Ok(warp::reply::json(&recorder.snapshot())),
+                        None => Err(warp::reject::not_found()),
+                    }
+                }
+            });
+
+            // --- Shard route (heartbeat polled by peer instances) ---
+            let shard_coordinator = config.shard_coordinator.clone();
+            let shard_route = warp::path("shard").and(warp::get()).and_then(move || {
+                let coordinator = shard_coordinator.clone();
+                async move {
+                    match coordinator {
+                        Some(coordinator) => Ok(warp::reply::json(&coordinator.status())),
+                        None => Err(warp::reject::not_found()),
+                    }
+                }
+            });
+
+            // --- Earnings route (earnings and costs per epoch and tree) ---
+            let earnings = config.earnings.clone();
+            let earnings_route = warp::path("earnings").and(warp::get()).and_then(move || {
+                let earnings = earnings.clone();
+                async move {
+                    match earnings {
+                        Some(earnings) => Ok(warp::reply::json(&earnings.snapshot())),
+                        None => Err(warp::reject::not_found()),
+                    }
+                }
+            });
+
+            // --- Admin routes (runtime control, only with an admin token) ---
+            let admin_route = match (config.admin_token.clone(), config.admin.clone()) {
+                (Some(token), Some(admin)) => admin_routes(token, admin),
+                _ => warp::any()
+                    .and_then(|| async {
+                        Err::<warp::reply::Response, _>(warp::reject::not_found())
+                    })
+                    .boxed(),
+            };
+
             let routes = health_route
                 .or(status_route)
                 .or(metrics_route)
                 .or(prometheus_route)
                 .or(compressible_route)
+                .or(dry_run_route)
+                .or(shard_route)
+                .or(earnings_route)
+                .or(admin_route)
                 .with(cors);
 
             let listener = match tokio::net::TcpListener::bind(addr).await {
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
@@ -19,6 +21,13 @@ pub enum Commands {
 
 #[derive(Parser, Clone, Debug)]
 pub struct StartArgs {
+    #[arg(
+        long,
+        env = "CONFIG_FILE",
+        help = "TOML file with the start arguments, grouped in sections. Command line and environment values take precedence. Rate limits, priority fees and PDA programs are reloaded when the file changes"
+    )]
+    pub config: Option<PathBuf>,
+
     #[arg(long, env = "RPC_URL")]
     pub rpc_url: Option<String>,
 
@@ -38,27 +47,31 @@ pub struct StartArgs {
     )]
     pub indexer_url: String,
 
-    #[arg(long, env = "PROVER_URL")]
+    #[arg(
+        long,
+        env = "PROVER_URL",
+        help = "Prover URL. Accepts a comma-separated list of URLs, each optionally suffixed with #weight=N"
+    )]
     pub prover_url: Option<String>,
 
     #[arg(
         long,
         env = "PROVER_APPEND_URL",
-        help = "Prover URL for append operations. If not specified, uses prover_url"
+        help = "Prover URLs for append operations (comma-separated, optionally suffixed with #weight=N). If not specified, uses prover_url"
     )]
     pub prover_append_url: Option<String>,
 
     #[arg(
         long,
         env = "PROVER_UPDATE_URL",
-        help = "Prover URL for update operations. If not specified, uses prover_url"
+        help = "Prover URLs for update operations (comma-separated, optionally suffixed with #weight=N). If not specified, uses prover_url"
     )]
     pub prover_update_url: Option<String>,
 
     #[arg(
         long,
         env = "PROVER_ADDRESS_APPEND_URL",
-        help = "Prover URL for address-append operations. If not specified, uses prover_url"
+        help = "Prover URLs for address-append operations (comma-separated, optionally suffixed with #weight=N). If not specified, uses prover_url"
     )]
     pub prover_address_append_url: Option<String>,
 
@@ -79,6 +92,30 @@ pub struct StartArgs {
     )]
     pub prover_max_wait_time_secs: Option<u64>,
 
+    #[arg(
+        long,
+        env = "PROVER_ROUTING",
+        default_value_t = ProverRoutingStrategy::WeightedRoundRobin,
+        help = "Routing of proof jobs across the provers of a circuit type: weighted-round-robin or least-outstanding"
+    )]
+    pub prover_routing: ProverRoutingStrategy,
+
+    #[arg(
+        long,
+        env = "PROVER_HEALTH_CHECK_INTERVAL_SECS",
+        default_value = "30",
+        help = "Interval in seconds between prover health checks"
+    )]
+    pub prover_health_check_interval_secs: u64,
+
+    #[arg(
+        long,
+        env = "PROVER_MAX_CONSECUTIVE_FAILURES",
+        default_value = "3",
+        help = "Consecutive failures after which a prover is removed from routing until it passes a health check"
+    )]
+    pub prover_max_consecutive_failures: u32,
+
     #[arg(long, env = "PAYER")]
     pub payer: Option<String>,
 
@@ -264,6 +301,37 @@ pub struct StartArgs {
     )]
     pub tree_ids: Vec<String>,
 
+    #[arg(
+        long = "exclude-tree",
+        env = "EXCLUDE_TREE_IDS",
+        help = "Never process the specified trees (Pubkeys). Can be specified multiple times",
+        value_delimiter = ','
+    )]
+    pub exclude_tree_ids: Vec<String>,
+
+    #[arg(
+        long,
+        env = "ENABLE_STATE_MIGRATION",
+        help = "Migrate the live leaves of v1 state trees to v2 state trees while the v1 tree queue is empty",
+        default_value = "false"
+    )]
+    pub enable_state_migration: bool,
+
+    #[arg(
+        long,
+        env = "MIGRATION_OUTPUT_QUEUE",
+        help = "Output queue of the v2 state tree accounts are migrated to. Defaults to the output queue of a tracked v2 state tree with the same owner"
+    )]
+    pub migration_output_queue: Option<String>,
+
+    #[arg(
+        long,
+        env = "MAX_MIGRATIONS_PER_SLOT",
+        help = "Maximum number of compressed accounts migrated per light slot and tree",
+        default_value = "10"
+    )]
+    pub max_migrations_per_slot: usize,
+
     #[arg(
         long,
         env = "ENABLE_COMPRESSIBLE",
@@ -303,6 +371,13 @@ pub struct StartArgs {
     )]
     pub api_server_public_bind: bool,
 
+    #[arg(
+        long,
+        env = "ADMIN_TOKEN",
+        help = "Bearer token for the admin API under /admin. The admin API is disabled when unset."
+    )]
+    pub admin_token: Option<String>,
+
     #[arg(
         long,
         env = "HELIUS_RPC",
@@ -338,6 +413,176 @@ pub struct StartArgs {
         help = "Fallback Photon indexer URL, used when primary indexer is unreachable"
     )]
     pub fallback_indexer_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "STATE_DIR",
+        help = "Directory in which proof caches, pending work reports and processing counters are checkpointed and restored from on startup"
+    )]
+    pub state_dir: Option<String>,
+
+    #[arg(
+        long,
+        env = "STATE_CHECKPOINT_INTERVAL_SECONDS",
+        default_value = "30",
+        help = "Interval between state checkpoints in seconds (requires --state-dir)"
+    )]
+    pub state_checkpoint_interval_seconds: u64,
+
+    #[arg(
+        long,
+        env = "STAKED_RPC_URL",
+        help = "RPC endpoint with stake-weighted QoS, used by operations sent with the staked-rpc sender"
+    )]
+    pub staked_rpc_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "BLOCK_ENGINE_URL",
+        help = "Block engine bundle endpoint (e.g. https://mainnet.block-engine.jito.wtf/api/v1/bundles), used by operations sent with the bundle sender"
+    )]
+    pub block_engine_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "BUNDLE_TIP_ACCOUNT",
+        help = "Tip account pubkey of the block engine, required by the bundle sender"
+    )]
+    pub bundle_tip_account: Option<String>,
+
+    #[arg(
+        long,
+        env = "BUNDLE_TIP_LAMPORTS",
+        default_value = "10000",
+        help = "Tip in lamports added to every transaction sent with the bundle sender"
+    )]
+    pub bundle_tip_lamports: u64,
+
+    #[arg(
+        long,
+        env = "TPU_FANOUT_SLOTS",
+        default_value = "12",
+        help = "Number of upcoming slots whose leaders receive transactions sent with the tpu sender"
+    )]
+    pub tpu_fanout_slots: u64,
+
+    #[arg(
+        long,
+        env = "BATCH_APPEND_SENDER",
+        default_value_t = TransactionSenderKind::Rpc,
+        help = "Transaction sender for v2 batch append transactions: rpc, staked-rpc, tpu, bundle"
+    )]
+    pub batch_append_sender: TransactionSenderKind,
+
+    #[arg(
+        long,
+        env = "BATCH_NULLIFY_SENDER",
+        default_value_t = TransactionSenderKind::Rpc,
+        help = "Transaction sender for v2 batch nullify transactions: rpc, staked-rpc, tpu, bundle"
+    )]
+    pub batch_nullify_sender: TransactionSenderKind,
+
+    #[arg(
+        long,
+        env = "BATCH_ADDRESS_APPEND_SENDER",
+        default_value_t = TransactionSenderKind::Rpc,
+        help = "Transaction sender for v2 batch address append transactions: rpc, staked-rpc, tpu, bundle"
+    )]
+    pub batch_address_append_sender: TransactionSenderKind,
+
+    #[arg(
+        long,
+        env = "COMPRESS_AND_CLOSE_SENDER",
+        default_value_t = TransactionSenderKind::Rpc,
+        help = "Transaction sender for compress-and-close transactions: rpc, staked-rpc, tpu, bundle"
+    )]
+    pub compress_and_close_sender: TransactionSenderKind,
+
+    #[arg(
+        long,
+        env = "ROLLOVER_SENDER",
+        default_value_t = TransactionSenderKind::Rpc,
+        help = "Transaction sender for tree rollover transactions: rpc, staked-rpc, tpu, bundle"
+    )]
+    pub rollover_sender: TransactionSenderKind,
+
+    #[arg(
+        long,
+        env = "DRY_RUN",
+        default_value = "false",
+        help = "Run the full pipeline but simulate transactions instead of sending them. Epochs the forester is not registered for are skipped"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        env = "SHARD_INSTANCE_ID",
+        help = "Id of this instance when several foresters share one registered authority. Enables tree sharding between this instance and --shard-peer"
+    )]
+    pub shard_instance_id: Option<String>,
+
+    #[arg(
+        long = "shard-peer",
+        env = "SHARD_PEERS",
+        value_delimiter = ',',
+        help = "Other instances sharing the authority, as 'instance_id=api_server_url' (comma-separated)"
+    )]
+    pub shard_peers: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SHARD_PEER_TIMEOUT_SECONDS",
+        default_value = "60",
+        help = "Seconds without a peer heartbeat after which its trees are taken over"
+    )]
+    pub shard_peer_timeout_seconds: u64,
+
+    #[arg(
+        long = "alert-webhook-url",
+        env = "ALERT_WEBHOOK_URLS",
+        value_delimiter = ',',
+        help = "Webhook URLs to post alerts to as JSON (comma-separated)"
+    )]
+    pub alert_webhook_urls: Vec<String>,
+
+    #[arg(
+        long,
+        env = "ALERT_FILE",
+        help = "File to append alerts to as JSON lines"
+    )]
+    pub alert_file: Option<String>,
+
+    #[arg(
+        long,
+        env = "ALERT_MIN_BALANCE_SOL",
+        default_value = "1.0",
+        help = "Alert when the forester balance drops below this many SOL (0 disables)"
+    )]
+    pub alert_min_balance_sol: f64,
+
+    #[arg(
+        long,
+        env = "ALERT_INDEXER_LAG_SLOTS",
+        default_value = "150",
+        help = "Alert when the indexer is this many slots behind the RPC (0 disables)"
+    )]
+    pub alert_indexer_lag_slots: u64,
+
+    #[arg(
+        long,
+        env = "ALERT_MISSED_LIGHT_SLOTS",
+        default_value = "3",
+        help = "Alert when this many consecutive eligible light slots of a tree fail (0 disables)"
+    )]
+    pub alert_missed_light_slots: u32,
+
+    #[arg(
+        long,
+        env = "ALERT_REPEAT_INTERVAL_SECONDS",
+        default_value = "3600",
+        help = "Seconds after which a still active alert is sent again"
+    )]
+    pub alert_repeat_interval_seconds: u64,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -469,6 +714,39 @@ pub enum QueuePollingMode {
     OnChain,
 }
 
+/// Backend used to submit transactions of an operation type.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum TransactionSenderKind {
+    /// Send through the RPC pool
+    #[clap(name = "rpc")]
+    #[default]
+    Rpc,
+    /// Send through a dedicated staked RPC endpoint
+    #[clap(name = "staked-rpc")]
+    StakedRpc,
+    /// Forward directly to the TPU of upcoming leaders over QUIC
+    #[clap(name = "tpu")]
+    Tpu,
+    /// Submit as a bundle to a block engine
+    #[clap(name = "bundle")]
+    Bundle,
+    /// Simulate instead of sending; selected for all operations by `--dry-run`
+    #[value(skip)]
+    DryRun,
+}
+
+/// Routing of proof jobs across the provers configured for a circuit type.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProverRoutingStrategy {
+    /// Distribute jobs in proportion to the prover weights
+    #[clap(name = "weighted-round-robin")]
+    #[default]
+    WeightedRoundRobin,
+    /// Send each job to the prover with the fewest outstanding jobs per weight
+    #[clap(name = "least-outstanding")]
+    LeastOutstanding,
+}
+
 impl std::fmt::Display for ProcessorMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -488,6 +766,27 @@ impl std::fmt::Display for QueuePollingMode {
     }
 }
 
+impl std::fmt::Display for TransactionSenderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionSenderKind::Rpc => write!(f, "rpc"),
+            TransactionSenderKind::StakedRpc => write!(f, "staked-rpc"),
+            TransactionSenderKind::Tpu => write!(f, "tpu"),
+            TransactionSenderKind::Bundle => write!(f, "bundle"),
+            TransactionSenderKind::DryRun => write!(f, "dry-run"),
+        }
+    }
+}
+
+impl std::fmt::Display for ProverRoutingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProverRoutingStrategy::WeightedRoundRobin => write!(f, "weighted-round-robin"),
+            ProverRoutingStrategy::LeastOutstanding => write!(f, "least-outstanding"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use clap::Parser;
@@ -574,4 +873,26 @@ mod tests {
         assert_eq!(ProcessorMode::V2.to_string(), "v2");
         assert_eq!(ProcessorMode::All.to_string(), "all");
     }
+
+    #[test]
+    fn test_transaction_sender_parsing() {
+        let args = StartArgs::try_parse_from([
+            "forester",
+            "--rpc-url", "http://test.com",
+            "--indexer-url", "http://indexer.test.com",
+            "--payer", "[1,2,3]",
+            "--derivation", "[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32]",
+            "--batch-nullify-sender", "tpu",
+            "--compress-and-close-sender", "staked-rpc",
+            "--rollover-sender", "bundle"
+        ]).unwrap();
+        assert_eq!(args.batch_append_sender, TransactionSenderKind::Rpc);
+        assert_eq!(args.batch_nullify_sender, TransactionSenderKind::Tpu);
+        assert_eq!(args.batch_address_append_sender, TransactionSenderKind::Rpc);
+        assert_eq!(
+            args.compress_and_close_sender,
+            TransactionSenderKind::StakedRpc
+        );
+        assert_eq!(args.rollover_sender, TransactionSenderKind::Bundle);
+    }
 }
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
@@ -132,6 +132,18 @@ impl FromStr for PdaProgramConfig {
     }
 }
 
+/// Formats as `program_id:discriminator_base58`, the format parsed by `FromStr`.
+impl fmt::Display for PdaProgramConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.program_id,
+            bs58::encode(self.discriminator).into_string()
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressibleConfig {
     /// WebSocket URL for account subscriptions
@@ -40,7 +40,7 @@ impl<R: Rpc + Indexer> Clone for CTokenCompressor<R> {
             rpc_pool: Arc::clone(&self.rpc_pool),
             tracker: Arc::clone(&self.tracker),
             payer_keypair: self.payer_keypair.insecure_clone(),
-            transaction_policy: self.transaction_policy,
+            transaction_policy: self.transaction_policy.clone(),
         }
     }
 }
@@ -254,7 +254,7 @@ impl<R: Rpc + Indexer> CTokenCompressor<R> {
             &mut *rpc,
             &[ix],
             &self.payer_keypair,
-            self.transaction_policy,
+            self.transaction_policy.clone(),
             &*self.tracker,
             &pubkeys,
             "compress_and_close",
@@ -40,7 +40,7 @@ impl<R: Rpc + Indexer> Clone for MintCompressor<R> {
             rpc_pool: Arc::clone(&self.rpc_pool),
             tracker: Arc::clone(&self.tracker),
             payer_keypair: self.payer_keypair.insecure_clone(),
-            transaction_policy: self.transaction_policy,
+            transaction_policy: self.transaction_policy.clone(),
         }
     }
 }
@@ -118,7 +118,7 @@ impl<R: Rpc + Indexer> MintCompressor<R> {
             &mut *rpc,
             &instructions,
             &self.payer_keypair,
-            self.transaction_policy,
+            self.transaction_policy.clone(),
             &*self.tracker,
             &pubkeys,
             "CompressAndCloseMint",
@@ -260,7 +260,7 @@ impl<R: Rpc + Indexer> MintCompressor<R> {
             &mut *rpc,
             &[ix],
             &self.payer_keypair,
-            self.transaction_policy,
+            self.transaction_policy.clone(),
             &*self.tracker,
             &tracked_pubkeys,
             "CompressAndCloseMint",
@@ -82,6 +82,28 @@ pub async fn bootstrap_pda_accounts(
     Ok(())
 }
 
+/// Re-fetches the accounts of one configured program into the tracker.
+pub async fn bootstrap_pda_program(
+    rpc_url: &str,
+    tracker: &PdaAccountTracker,
+    program_config: &PdaProgramConfig,
+    helius_rpc: bool,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    let shutdown_flag = std::sync::atomic::AtomicBool::new(false);
+    bootstrap_program(
+        &client,
+        rpc_url,
+        tracker,
+        program_config,
+        &shutdown_flag,
+        helius_rpc,
+    )
+    .await
+}
+
 /// Bootstrap a single program's accounts
 async fn bootstrap_program(
     client: &reqwest::Client,
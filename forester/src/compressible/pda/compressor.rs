@@ -65,7 +65,7 @@ impl<R: Rpc + Indexer> Clone for PdaCompressor<R> {
             rpc_pool: Arc::clone(&self.rpc_pool),
             tracker: Arc::clone(&self.tracker),
             payer_keypair: self.payer_keypair.insecure_clone(),
-            transaction_policy: self.transaction_policy,
+            transaction_policy: self.transaction_policy.clone(),
         }
     }
 }
@@ -318,7 +318,7 @@ impl<R: Rpc + Indexer> PdaCompressor<R> {
             &mut *rpc,
             &[ix],
             &self.payer_keypair,
-            self.transaction_policy,
+            self.transaction_policy.clone(),
             &*self.tracker,
             &pubkeys,
             "compress_accounts_idempotent",
@@ -407,7 +407,7 @@ impl<R: Rpc + Indexer> PdaCompressor<R> {
                 signers: &signers,
                 address_lookup_tables: &[],
                 priority_fee_accounts,
-                policy: self.transaction_policy,
+                policy: self.transaction_policy.clone(),
                 confirmation_deadline: None,
             },
         )
@@ -3,7 +3,7 @@ mod compressor;
 mod state;
 mod types;
 
-pub use bootstrap::bootstrap_pda_accounts;
+pub use bootstrap::{bootstrap_pda_accounts, bootstrap_pda_program};
 pub use compressor::{CachedProgramConfig, PdaCompressor};
 pub use state::PdaAccountTracker;
 pub use types::PdaAccountState;
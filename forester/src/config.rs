@@ -1,15 +1,24 @@
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use account_compression::utils::constants::{ADDRESS_QUEUE_VALUES, STATE_NULLIFIER_QUEUE_VALUES};
 use anchor_lang::Id;
 use forester_utils::forester_epoch::{Epoch, TreeAccounts, TreeForesterSchedule};
 use light_client::rpc::RetryConfig;
 use light_registry::{EpochPda, ForesterEpochPda};
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
 
 use crate::{
-    cli::{ProcessorMode, QueuePollingMode, StartArgs, StatusArgs},
+    admin::AdminControl,
+    alerts::{AlertConfig, AlertManager},
+    cli::{ProcessorMode, ProverRoutingStrategy, QueuePollingMode, StartArgs, StatusArgs},
+    config_file::ReloadableConfig,
+    earnings::EarningsTracker,
     errors::ConfigError,
+    sender::SenderConfig,
+    shard::{ShardConfig, ShardCoordinator},
     Result,
 };
 
@@ -30,6 +39,14 @@ pub struct ForesterConfig {
     pub compressible_config: Option<crate::compressible::config::CompressibleConfig>,
     /// Address lookup table for versioned transactions. If None, legacy transactions are used.
     pub lookup_table_address: Option<Pubkey>,
+    pub sender_config: SenderConfig,
+    /// Set when trees are sharded between several instances.
+    pub shard_coordinator: Option<Arc<ShardCoordinator>>,
+    pub alert_manager: Arc<AlertManager>,
+    pub earnings: Arc<EarningsTracker>,
+    pub admin: Arc<AdminControl>,
+    /// Settings changed by reloading the config file.
+    pub reloadable: Arc<ReloadableConfig>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +61,9 @@ pub struct ExternalServicesConfig {
     pub prover_api_key: Option<String>,
     pub prover_polling_interval: Option<Duration>,
     pub prover_max_wait_time: Option<Duration>,
+    pub prover_routing: ProverRoutingStrategy,
+    pub prover_health_check_interval: Option<Duration>,
+    pub prover_max_consecutive_failures: Option<u32>,
     pub photon_grpc_url: Option<String>,
     pub pushgateway_url: Option<String>,
     pub pagerduty_routing_key: Option<String>,
@@ -96,12 +116,25 @@ pub struct GeneralConfig {
     pub skip_v2_state_trees: bool,
     pub skip_v2_address_trees: bool,
     pub tree_ids: Vec<Pubkey>,
+    /// Trees which are never processed, even when listed in `tree_ids`.
+    pub exclude_tree_ids: Vec<Pubkey>,
     pub sleep_after_processing_ms: u64,
     pub sleep_when_idle_ms: u64,
     pub queue_polling_mode: QueuePollingMode,
     pub group_authority: Option<Pubkey>,
     /// Use Helius getProgramAccountsV2 instead of standard getProgramAccounts
     pub helius_rpc: bool,
+    /// Directory for state checkpoints. If None, state is kept in memory only.
+    pub state_dir: Option<PathBuf>,
+    pub state_checkpoint_interval_seconds: u64,
+    /// Migrate the live leaves of v1 state trees to v2 state trees while
+    /// the v1 tree queue is empty.
+    pub enable_state_migration: bool,
+    /// Output queue migrated leaves are inserted into. If None, the output
+    /// queue of a tracked v2 state tree with the same owner is used.
+    pub migration_output_queue: Option<Pubkey>,
+    /// Maximum number of leaves migrated per light slot and tree.
+    pub max_migrations_per_slot: usize,
 }
 
 impl Default for GeneralConfig {
@@ -115,11 +148,17 @@ impl Default for GeneralConfig {
             skip_v2_state_trees: false,
             skip_v2_address_trees: false,
             tree_ids: vec![],
+            exclude_tree_ids: vec![],
             sleep_after_processing_ms: 10_000,
             sleep_when_idle_ms: 45_000,
             queue_polling_mode: QueuePollingMode::Indexer,
             group_authority: None,
             helius_rpc: false,
+            state_dir: None,
+            state_checkpoint_interval_seconds: 30,
+            enable_state_migration: false,
+            migration_output_queue: None,
+            max_migrations_per_slot: 10,
         }
     }
 }
@@ -197,6 +236,24 @@ impl Default for TransactionConfig {
         }
     }
 }
+fn parse_pubkeys(field: &'static str, values: &[String]) -> Result<Vec<Pubkey>> {
+    let (valid, invalid): (Vec<_>, Vec<_>) = values
+        .iter()
+        .map(|id| Pubkey::from_str(id).map_err(|_| id.clone()))
+        .partition(|r| r.is_ok());
+
+    if !invalid.is_empty() {
+        let invalid_values: Vec<String> = invalid.into_iter().map(|r| r.unwrap_err()).collect();
+        return Err(ConfigError::InvalidArguments {
+            field,
+            invalid_values,
+        }
+        .into());
+    }
+
+    Ok(valid.into_iter().map(|r| r.unwrap()).collect())
+}
+
 impl ForesterConfig {
     pub fn new_for_start(args: &StartArgs) -> Result<Self> {
         if args.enable_priority_fees && args.priority_fee_microlamports.is_some() {
@@ -226,6 +283,7 @@ impl ForesterConfig {
                 field: "payer",
                 invalid_values: vec![e.to_string()],
             })?;
+        let payer_pubkey = payer.pubkey();
 
         let derivation: Vec<u8> = match &args.derivation {
             Some(derivation_str) => {
@@ -276,6 +334,11 @@ impl ForesterConfig {
                 prover_api_key: args.prover_api_key.clone(),
                 prover_polling_interval: args.prover_polling_interval_ms.map(Duration::from_millis),
                 prover_max_wait_time: args.prover_max_wait_time_secs.map(Duration::from_secs),
+                prover_routing: args.prover_routing,
+                prover_health_check_interval: Some(Duration::from_secs(
+                    args.prover_health_check_interval_secs,
+                )),
+                prover_max_consecutive_failures: Some(args.prover_max_consecutive_failures),
                 photon_grpc_url: args.photon_grpc_url.clone(),
                 pushgateway_url: args.push_gateway_url.clone(),
                 pagerduty_routing_key: args.pagerduty_routing_key.clone(),
@@ -321,25 +384,8 @@ impl ForesterConfig {
                 skip_v2_state_trees: args.processor_mode == ProcessorMode::V1,
                 skip_v1_address_trees: args.processor_mode == ProcessorMode::V2,
                 skip_v2_address_trees: args.processor_mode == ProcessorMode::V1,
-                tree_ids: {
-                    let (valid, invalid): (Vec<_>, Vec<_>) = args
-                        .tree_ids
-                        .iter()
-                        .map(|id| Pubkey::from_str(id).map_err(|_| id.clone()))
-                        .partition(|r| r.is_ok());
-
-                    if !invalid.is_empty() {
-                        let invalid_values: Vec<String> =
-                            invalid.into_iter().map(|r| r.unwrap_err()).collect();
-                        return Err(ConfigError::InvalidArguments {
-                            field: "tree_ids",
-                            invalid_values,
-                        }
-                        .into());
-                    }
-
-                    valid.into_iter().map(|r| r.unwrap()).collect()
-                },
+                tree_ids: parse_pubkeys("tree_ids", &args.tree_ids)?,
+                exclude_tree_ids: parse_pubkeys("exclude_tree_ids", &args.exclude_tree_ids)?,
                 sleep_after_processing_ms: 10_000,
                 sleep_when_idle_ms: 45_000,
                 queue_polling_mode: args.queue_polling_mode,
@@ -354,6 +400,20 @@ impl ForesterConfig {
                     })
                     .transpose()?,
                 helius_rpc: args.helius_rpc,
+                state_dir: args.state_dir.as_ref().map(PathBuf::from),
+                state_checkpoint_interval_seconds: args.state_checkpoint_interval_seconds,
+                enable_state_migration: args.enable_state_migration,
+                migration_output_queue: args
+                    .migration_output_queue
+                    .as_ref()
+                    .map(|s| {
+                        Pubkey::from_str(s).map_err(|e| ConfigError::InvalidArguments {
+                            field: "migration_output_queue",
+                            invalid_values: vec![e.to_string()],
+                        })
+                    })
+                    .transpose()?,
+                max_migrations_per_slot: args.max_migrations_per_slot,
             },
             rpc_pool_config: RpcPoolConfig {
                 max_size: args.rpc_pool_size,
@@ -421,6 +481,19 @@ impl ForesterConfig {
                     })
                 })
                 .transpose()?,
+            sender_config: SenderConfig::new_for_start(args)?,
+            shard_coordinator: ShardConfig::new_for_start(args)?
+                .map(|config| Arc::new(ShardCoordinator::new(config))),
+            alert_manager: Arc::new(AlertManager::new(
+                &AlertConfig::new_for_start(args),
+                &payer_pubkey,
+            )),
+            earnings: Default::default(),
+            admin: Arc::new(AdminControl::new(
+                args.rpc_rate_limit,
+                args.send_tx_rate_limit,
+            )),
+            reloadable: Default::default(),
         })
     }
 
@@ -439,6 +512,9 @@ impl ForesterConfig {
                 prover_api_key: None,
                 prover_polling_interval: None,
                 prover_max_wait_time: None,
+                prover_routing: Default::default(),
+                prover_health_check_interval: None,
+                prover_max_consecutive_failures: None,
                 photon_grpc_url: None,
                 pushgateway_url: args.push_gateway_url.clone(),
                 pagerduty_routing_key: args.pagerduty_routing_key.clone(),
@@ -475,6 +551,12 @@ impl ForesterConfig {
             state_tree_data: vec![],
             compressible_config: None,
             lookup_table_address: None,
+            sender_config: SenderConfig::default(),
+            shard_coordinator: None,
+            alert_manager: Default::default(),
+            earnings: Default::default(),
+            admin: Default::default(),
+            reloadable: Default::default(),
         })
     }
 }
@@ -495,6 +577,12 @@ impl Clone for ForesterConfig {
             state_tree_data: self.state_tree_data.clone(),
             compressible_config: self.compressible_config.clone(),
             lookup_table_address: self.lookup_table_address,
+            sender_config: self.sender_config.clone(),
+            shard_coordinator: self.shard_coordinator.clone(),
+            alert_manager: self.alert_manager.clone(),
+            earnings: self.earnings.clone(),
+            admin: self.admin.clone(),
+            reloadable: self.reloadable.clone(),
         }
     }
 }
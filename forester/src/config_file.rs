@@ -0,0 +1,727 @@
+//! `forester start --config forester.toml`.
+//!
+//! The file groups the `start` arguments in sections, using the argument
+//! names as keys:
+//!
+//! ```toml
+//! [external_services]
+//! rpc_url = "https://api.mainnet-beta.solana.com"
+//!
+//! [transaction]
+//! cu_limit = 1000000
+//! ```
+//!
+//! Values given on the command line or in the environment take precedence
+//! over the file. While the forester runs the file is polled and the keys in
+//! [`RELOADABLE_KEYS`] are applied without a restart.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use clap::{
+    error::{ContextKind, ContextValue},
+    parser::ValueSource,
+    ArgAction, CommandFactory, Parser,
+};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::{
+    admin::LimitsUpdate,
+    cli::{Cli, StartArgs},
+    compressible::config::PdaProgramConfig,
+    errors::ConfigError,
+    priority_fee::PriorityFeeConfig,
+    ForesterConfig, Result,
+};
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The sections of the file and the `start` arguments in each of them.
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "forester",
+        &[
+            "payer",
+            "derivation",
+            "group_authority",
+            "processor_mode",
+            "queue_polling_mode",
+            "lookup_table_address",
+            "slot_update_interval_seconds",
+            "tree_discovery_interval_seconds",
+            "state_dir",
+            "state_checkpoint_interval_seconds",
+            "dry_run",
+        ],
+    ),
+    (
+        "external_services",
+        &[
+            "rpc_url",
+            "ws_rpc_url",
+            "indexer_url",
+            "photon_grpc_url",
+            "fallback_rpc_url",
+            "fallback_indexer_url",
+            "helius_rpc",
+            "push_gateway_url",
+            "pagerduty_routing_key",
+            "prometheus_url",
+            "rpc_rate_limit",
+            "photon_rate_limit",
+            "send_tx_rate_limit",
+        ],
+    ),
+    (
+        "prover",
+        &[
+            "prover_url",
+            "prover_append_url",
+            "prover_update_url",
+            "prover_address_append_url",
+            "prover_api_key",
+            "prover_polling_interval_ms",
+            "prover_max_wait_time_secs",
+            "prover_routing",
+            "prover_health_check_interval_secs",
+            "prover_max_consecutive_failures",
+        ],
+    ),
+    (
+        "rpc_pool",
+        &[
+            "rpc_pool_size",
+            "rpc_pool_connection_timeout_secs",
+            "rpc_pool_idle_timeout_secs",
+            "rpc_pool_max_retries",
+            "rpc_pool_initial_retry_delay_ms",
+            "rpc_pool_max_retry_delay_ms",
+            "rpc_pool_failure_threshold",
+            "rpc_pool_primary_probe_interval_secs",
+        ],
+    ),
+    ("retry", &["max_retries", "retry_delay", "retry_timeout"]),
+    (
+        "indexer",
+        &["indexer_batch_size", "indexer_max_concurrent_batches"],
+    ),
+    (
+        "queue",
+        &[
+            "state_queue_start_index",
+            "state_queue_processing_length",
+            "address_queue_start_index",
+            "address_queue_processing_length",
+        ],
+    ),
+    (
+        "transaction",
+        &[
+            "legacy_ixs_per_tx",
+            "transaction_max_concurrent_batches",
+            "max_concurrent_sends",
+            "max_batches_per_tree",
+            "tx_cache_ttl_seconds",
+            "ops_cache_ttl_seconds",
+            "confirmation_max_attempts",
+            "confirmation_poll_interval_ms",
+            "cu_limit",
+        ],
+    ),
+    (
+        "priority_fee",
+        &["enable_priority_fees", "priority_fee_microlamports"],
+    ),
+    (
+        "senders",
+        &[
+            "staked_rpc_url",
+            "block_engine_url",
+            "bundle_tip_account",
+            "bundle_tip_lamports",
+            "tpu_fanout_slots",
+            "batch_append_sender",
+            "batch_nullify_sender",
+            "batch_address_append_sender",
+            "compress_and_close_sender",
+            "rollover_sender",
+        ],
+    ),
+    (
+        "compressible",
+        &["enable_compressible", "light_pda_programs"],
+    ),
+    ("trees", &["tree_ids", "exclude_tree_ids"]),
+    (
+        "migration",
+        &[
+            "enable_state_migration",
+            "migration_output_queue",
+            "max_migrations_per_slot",
+        ],
+    ),
+    (
+        "api_server",
+        &["api_server_port", "api_server_public_bind", "admin_token"],
+    ),
+    (
+        "shard",
+        &[
+            "shard_instance_id",
+            "shard_peers",
+            "shard_peer_timeout_seconds",
+        ],
+    ),
+    (
+        "alerts",
+        &[
+            "alert_webhook_urls",
+            "alert_file",
+            "alert_min_balance_sol",
+            "alert_indexer_lag_slots",
+            "alert_missed_light_slots",
+            "alert_repeat_interval_seconds",
+        ],
+    ),
+];
+
+/// Keys which are applied when the file changes. Changes of other keys take
+/// effect after a restart.
+pub const RELOADABLE_KEYS: &[&str] = &[
+    "rpc_rate_limit",
+    "send_tx_rate_limit",
+    "max_batches_per_tree",
+    "enable_priority_fees",
+    "priority_fee_microlamports",
+    "light_pda_programs",
+];
+
+/// Settings changed by reloading the config file. None until the first
+/// reload, in which case the startup configuration applies.
+#[derive(Debug, Default)]
+pub struct ReloadableConfig {
+    priority_fee: RwLock<Option<PriorityFeeConfig>>,
+    pda_programs: RwLock<Option<Vec<PdaProgramConfig>>>,
+}
+
+impl ReloadableConfig {
+    pub fn priority_fee(&self) -> Option<PriorityFeeConfig> {
+        *self.priority_fee.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_priority_fee(&self, config: PriorityFeeConfig) {
+        *self.priority_fee.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+    }
+
+    pub fn pda_programs(&self) -> Option<Vec<PdaProgramConfig>> {
+        self.pda_programs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_pda_programs(&self, programs: Vec<PdaProgramConfig>) {
+        *self.pda_programs.write().unwrap_or_else(|e| e.into_inner()) = Some(programs);
+    }
+}
+
+/// The values of a config file, by argument name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFile {
+    path: PathBuf,
+    values: BTreeMap<String, toml::Value>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> std::result::Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| ConfigError::ConfigFile {
+            path: path.display().to_string(),
+            error: e.to_string(),
+        })?;
+        Self::parse(path, &contents)
+    }
+
+    fn parse(path: &Path, contents: &str) -> std::result::Result<Self, ConfigError> {
+        let invalid = |error: String| ConfigError::ConfigFile {
+            path: path.display().to_string(),
+            error,
+        };
+        let table: toml::Table = toml::from_str(contents).map_err(|e| invalid(e.to_string()))?;
+
+        let mut values = BTreeMap::new();
+        for (section, value) in table {
+            let Some((_, keys)) = SECTIONS.iter().find(|(name, _)| *name == section) else {
+                return Err(invalid(format!(
+                    "unknown section [{}], expected one of: {}",
+                    section,
+                    SECTIONS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            };
+            let toml::Value::Table(entries) = value else {
+                return Err(invalid(format!("{} must be a section", section)));
+            };
+            for (key, value) in entries {
+                if !keys.contains(&key.as_str()) {
+                    return Err(invalid(match section_of(&key) {
+                        Some(expected) => {
+                            format!("{} belongs in section [{}]", key, expected)
+                        }
+                        None => format!("unknown key {} in section [{}]", key, section),
+                    }));
+                }
+                values.insert(key, value);
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            values,
+        })
+    }
+
+    /// Keys whose values differ between the two files.
+    fn changed_keys<'a>(&'a self, other: &'a Self) -> Vec<&'a str> {
+        let mut keys: Vec<&str> = self
+            .values
+            .keys()
+            .chain(other.values.keys())
+            .map(String::as_str)
+            .filter(|key| self.values.get(*key) != other.values.get(*key))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    fn key_path(&self, key: &str) -> String {
+        format!("{}.{}", section_of(key).unwrap_or_default(), key)
+    }
+
+    fn invalid(&self, key: &str, error: impl std::fmt::Display) -> ConfigError {
+        ConfigError::ConfigFile {
+            path: self.path.display().to_string(),
+            error: format!("{}: {}", self.key_path(key), error),
+        }
+    }
+
+    /// Command line arguments for the values which are not `explicit`, as
+    /// pairs of key and argument.
+    fn args(
+        &self,
+        explicit: impl Fn(&str) -> bool,
+    ) -> std::result::Result<Vec<(&str, String)>, ConfigError> {
+        let command = StartArgs::command();
+        let mut args = Vec::new();
+        for (key, value) in &self.values {
+            if explicit(key) {
+                continue;
+            }
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == key.as_str())
+                .ok_or_else(|| self.invalid(key, "not a start argument"))?;
+            let long = arg
+                .get_long()
+                .ok_or_else(|| self.invalid(key, "not a start argument"))?;
+
+            match (arg.get_action(), value) {
+                (ArgAction::SetTrue, toml::Value::Boolean(true)) => {
+                    args.push((key.as_str(), format!("--{}", long)))
+                }
+                (ArgAction::SetTrue, toml::Value::Boolean(false)) => {}
+                (ArgAction::SetTrue, _) => return Err(self.invalid(key, "expected true or false")),
+                (ArgAction::Append, toml::Value::Array(items)) => {
+                    for item in items {
+                        args.push((
+                            key.as_str(),
+                            format!("--{}={}", long, self.arg_value(key, item)?),
+                        ));
+                    }
+                }
+                (_, value) => args.push((
+                    key.as_str(),
+                    format!("--{}={}", long, self.arg_value(key, value)?),
+                )),
+            }
+        }
+        Ok(args)
+    }
+
+    fn arg_value(
+        &self,
+        key: &str,
+        value: &toml::Value,
+    ) -> std::result::Result<String, ConfigError> {
+        match value {
+            toml::Value::String(value) => Ok(value.clone()),
+            toml::Value::Integer(value) => Ok(value.to_string()),
+            toml::Value::Float(value) => Ok(value.to_string()),
+            toml::Value::Boolean(value) => Ok(value.to_string()),
+            // Keypairs are given as arrays of bytes, as in the JSON keypair files.
+            toml::Value::Array(_) => serde_json::to_string(value).map_err(|e| self.invalid(key, e)),
+            toml::Value::Table(_) if key == "light_pda_programs" => value
+                .clone()
+                .try_into::<PdaProgramConfig>()
+                .map(|program| program.to_string())
+                .map_err(|e| self.invalid(key, e)),
+            _ => Err(self.invalid(key, "unsupported value")),
+        }
+    }
+
+    /// Attributes a parse error to the key of the file which caused it.
+    fn attribute_error(&self, error: clap::Error, args: &[(&str, String)]) -> ParseError {
+        let Some(ContextValue::String(invalid_arg)) = error.get(ContextKind::InvalidArg) else {
+            return ParseError::Cli(error);
+        };
+        let invalid_long = invalid_arg.split([' ', '=']).next().unwrap_or_default();
+        let from_file = args
+            .iter()
+            .find(|(_, arg)| arg.split('=').next().unwrap_or_default() == invalid_long);
+        match from_file {
+            Some((key, _)) => {
+                let message = error.to_string();
+                let message = message.lines().next().unwrap_or_default();
+                ParseError::Config(self.invalid(key, message.trim_start_matches("error: ")))
+            }
+            None => ParseError::Cli(error),
+        }
+    }
+}
+
+fn section_of(key: &str) -> Option<&'static str> {
+    SECTIONS
+        .iter()
+        .find(|(_, keys)| keys.contains(&key))
+        .map(|(name, _)| *name)
+}
+
+enum ParseError {
+    Cli(clap::Error),
+    Config(ConfigError),
+}
+
+/// Parses the command line, merged with the config file given by `--config`.
+/// Command line errors are printed and exit the process like with
+/// `Cli::parse()`.
+pub fn parse_cli() -> Result<Cli> {
+    match parse_cli_from(&std::env::args_os().collect::<Vec<_>>()) {
+        Ok((cli, _)) => Ok(cli),
+        Err(ParseError::Cli(error)) => error.exit(),
+        Err(ParseError::Config(error)) => Err(error.into()),
+    }
+}
+
+fn parse_cli_from(args: &[OsString]) -> std::result::Result<(Cli, Option<ConfigFile>), ParseError> {
+    // Required arguments may be set in the file, so missing ones are not an
+    // error before it is merged.
+    let matches = Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .map_err(ParseError::Cli)?;
+    let Some((start, path)) = matches
+        .subcommand_matches("start")
+        .and_then(|start| Some((start, start.get_one::<PathBuf>("config")?)))
+    else {
+        return Cli::try_parse_from(args)
+            .map(|cli| (cli, None))
+            .map_err(ParseError::Cli);
+    };
+
+    let file = ConfigFile::load(path).map_err(ParseError::Config)?;
+    let file_args = file
+        .args(|key| {
+            matches!(
+                start.value_source(key),
+                Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable)
+            )
+        })
+        .map_err(ParseError::Config)?;
+    let merged = args
+        .iter()
+        .cloned()
+        .chain(file_args.iter().map(|(_, arg)| OsString::from(arg)));
+    match Cli::try_parse_from(merged) {
+        Ok(cli) => Ok((cli, Some(file))),
+        Err(error) => Err(file.attribute_error(error, &file_args)),
+    }
+}
+
+fn load_start_args(args: &[OsString]) -> std::result::Result<(StartArgs, ConfigFile), ConfigError> {
+    let to_config_error = |error: ParseError| match error {
+        ParseError::Config(error) => error,
+        ParseError::Cli(error) => ConfigError::InvalidArguments {
+            field: "config",
+            invalid_values: vec![error.to_string()],
+        },
+    };
+    match parse_cli_from(args).map_err(to_config_error)? {
+        (
+            Cli {
+                command: crate::cli::Commands::Start(start_args),
+            },
+            Some(file),
+        ) => Ok((start_args, file)),
+        _ => Err(ConfigError::MissingField { field: "config" }),
+    }
+}
+
+/// Polls the config file given by `--config` and applies the changes of
+/// [`RELOADABLE_KEYS`].
+pub fn spawn_config_watcher(config: Arc<ForesterConfig>) -> Option<JoinHandle<()>> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let (mut current_args, mut current_file) = match load_start_args(&args) {
+        Ok(loaded) => loaded,
+        Err(ConfigError::MissingField { .. }) => return None,
+        Err(e) => {
+            error!(
+                event = "config_watcher_start_failed",
+                error = %e,
+                "Failed to load the config file, it is not reloaded"
+            );
+            return None;
+        }
+    };
+
+    Some(tokio::spawn(async move {
+        let mut modified = modified_time(&current_file.path).await;
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            let latest = modified_time(&current_file.path).await;
+            if latest == modified {
+                continue;
+            }
+            modified = latest;
+
+            match load_start_args(&args) {
+                Ok((new_args, new_file)) => {
+                    apply_reload(&config, &current_args, &new_args, &current_file, &new_file);
+                    current_args = new_args;
+                    current_file = new_file;
+                }
+                Err(e) => warn!(
+                    event = "config_reload_failed",
+                    path = %current_file.path.display(),
+                    error = %e,
+                    "Ignoring invalid config file change"
+                ),
+            }
+        }
+    }))
+}
+
+async fn modified_time(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+fn apply_reload(
+    config: &ForesterConfig,
+    old_args: &StartArgs,
+    new_args: &StartArgs,
+    old_file: &ConfigFile,
+    new_file: &ConfigFile,
+) {
+    let (reloaded, restart): (Vec<&str>, Vec<&str>) = old_file
+        .changed_keys(new_file)
+        .into_iter()
+        .partition(|key| RELOADABLE_KEYS.contains(key));
+    if !restart.is_empty() {
+        warn!(
+            event = "config_reload_requires_restart",
+            keys = ?restart,
+            "Changed config keys take effect after a restart"
+        );
+    }
+    if reloaded.is_empty() {
+        return;
+    }
+
+    let changed = |key: &str| reloaded.contains(&key);
+    let limits = LimitsUpdate {
+        max_batches_per_tree: Some(new_args.max_batches_per_tree)
+            .filter(|_| changed("max_batches_per_tree")),
+        rpc_rate_limit: new_args
+            .rpc_rate_limit
+            .filter(|_| changed("rpc_rate_limit")),
+        send_tx_rate_limit: new_args
+            .send_tx_rate_limit
+            .filter(|_| changed("send_tx_rate_limit")),
+    };
+    if limits != LimitsUpdate::default() {
+        if let Err(e) = config.admin.update_limits(&limits) {
+            warn!(
+                event = "config_reload_limits_rejected",
+                error = %e,
+                "Failed to apply reloaded limits"
+            );
+        }
+    }
+    if (old_args.rpc_rate_limit.is_some() && new_args.rpc_rate_limit.is_none())
+        || (old_args.send_tx_rate_limit.is_some() && new_args.send_tx_rate_limit.is_none())
+    {
+        warn!(
+            event = "config_reload_requires_restart",
+            keys = ?["rpc_rate_limit", "send_tx_rate_limit"],
+            "Removing a rate limit takes effect after a restart"
+        );
+    }
+
+    if changed("enable_priority_fees") || changed("priority_fee_microlamports") {
+        config.reloadable.set_priority_fee(PriorityFeeConfig {
+            compute_unit_price: new_args.priority_fee_microlamports,
+            enable_priority_fees: new_args.enable_priority_fees,
+        });
+    }
+
+    if changed("light_pda_programs") {
+        reload_pda_programs(config, &new_args.light_pda_programs);
+    }
+
+    info!(
+        event = "config_reloaded",
+        keys = ?reloaded,
+        "Applied config file changes"
+    );
+}
+
+/// Programs need a subscription started at startup, so only programs which
+/// were configured then can be enabled again.
+fn reload_pda_programs(config: &ForesterConfig, programs: &[String]) {
+    let configured = config
+        .compressible_config
+        .as_ref()
+        .map(|c| c.pda_programs.as_slice())
+        .unwrap_or_default();
+    let mut enabled = Vec::new();
+    for program in programs {
+        match program.parse::<PdaProgramConfig>() {
+            Ok(program)
+                if configured.iter().any(|c| {
+                    c.program_id == program.program_id && c.discriminator == program.discriminator
+                }) =>
+            {
+                enabled.push(program)
+            }
+            Ok(program) => warn!(
+                event = "config_reload_requires_restart",
+                program = %program.program_id,
+                "New PDA programs are tracked after a restart"
+            ),
+            Err(e) => warn!(
+                event = "config_reload_invalid_pda_program",
+                program = %program,
+                error = %e,
+                "Ignoring invalid PDA program"
+            ),
+        }
+    }
+    config.reloadable.set_pda_programs(enabled);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn config_file(contents: &str) -> std::result::Result<ConfigFile, ConfigError> {
+        ConfigFile::parse(Path::new("forester.toml"), contents)
+    }
+
+    #[test]
+    fn test_sections_cover_start_args() {
+        let mut keys = HashSet::new();
+        for (section, section_keys) in SECTIONS {
+            for key in *section_keys {
+                assert!(keys.insert(*key), "{} is listed twice ({})", key, section);
+            }
+        }
+        let args: HashSet<&str> = StartArgs::command()
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| !["config", "help", "version"].contains(id))
+            .collect();
+        assert_eq!(keys, args);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        let error = config_file("[transaction]\nrpc_url = \"http://localhost:8899\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("rpc_url belongs in section [external_services]"));
+
+        let error = config_file("[transactions]\ncu_limit = 1\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown section [transactions]"));
+    }
+
+    #[test]
+    fn test_args() {
+        let file = config_file(
+            r#"
+            [external_services]
+            rpc_url = "http://localhost:8899"
+
+            [transaction]
+            cu_limit = 400000
+
+            [priority_fee]
+            enable_priority_fees = true
+
+            [trees]
+            tree_ids = ["11111111111111111111111111111111"]
+
+            [compressible]
+            light_pda_programs = [
+                { program_id = "11111111111111111111111111111111", discriminator = "11111111" },
+            ]
+
+            [forester]
+            payer = [1, 2, 3]
+            "#,
+        )
+        .unwrap();
+
+        let args: Vec<String> = file
+            .args(|key| key == "cu_limit")
+            .unwrap()
+            .into_iter()
+            .map(|(_, arg)| arg)
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                "--enable-priority-fees".to_string(),
+                "--light-pda-program=11111111111111111111111111111111:11111111".to_string(),
+                "--payer=[1,2,3]".to_string(),
+                "--rpc-url=http://localhost:8899".to_string(),
+                "--tree-id=11111111111111111111111111111111".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let old = config_file("[transaction]\ncu_limit = 1\nmax_batches_per_tree = 4\n").unwrap();
+        let new = config_file(
+            "[transaction]\ncu_limit = 1\n\n[external_services]\nrpc_rate_limit = 10\n",
+        )
+        .unwrap();
+        assert_eq!(
+            old.changed_keys(&new),
+            vec!["max_batches_per_tree", "rpc_rate_limit"]
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Earnings and costs of the forester per epoch and tree.
+//!
+//! Network fees are credited when a transaction is confirmed, using the
+//! network fee of the tree's metadata. The fees paid and the rent rewards of
+//! compress-and-close transactions are only known once the transaction is
+//! on chain; the signatures are kept and reconciled in the background.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::Transaction,
+};
+use solana_transaction_status::UiTransactionEncoding;
+use tracing::{debug, warn};
+
+use crate::metrics::{increment_earnings, increment_fees_paid, increment_prover_seconds};
+
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Network fees below this are not reimbursed by the batched trees.
+const MIN_BATCH_NETWORK_FEE: u64 = 5000;
+
+const DEFAULT_MAX_EPOCHS: usize = 16;
+const MAX_PENDING: usize = 10_000;
+/// Transactions which are not found after this many reconciliations are
+/// dropped; their fees are counted as unreconciled.
+const MAX_RECONCILE_ATTEMPTS: u32 = 10;
+const MAX_RECONCILE_PER_PASS: usize = 200;
+
+/// Compressible accounts have no tree; their transactions are grouped under
+/// these keys, or the program id for PDAs.
+pub const CTOKEN_EARNINGS_KEY: &str = "ctoken";
+pub const MINT_EARNINGS_KEY: &str = "mint";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarningsOperation {
+    BatchAppend,
+    BatchNullify,
+    BatchAddressAppend,
+    StateNullify,
+    AddressUpdate,
+    CompressAndClose,
+}
+
+impl EarningsOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EarningsOperation::BatchAppend => "batch_append",
+            EarningsOperation::BatchNullify => "batch_nullify",
+            EarningsOperation::BatchAddressAppend => "batch_address_append",
+            EarningsOperation::StateNullify => "state_nullify",
+            EarningsOperation::AddressUpdate => "address_update",
+            EarningsOperation::CompressAndClose => "compress_and_close",
+        }
+    }
+
+    /// Network fee earned by one instruction of the operation on a tree with
+    /// the given network fee.
+    pub fn network_fee(&self, tree_network_fee: u64) -> u64 {
+        match self {
+            // A batch append inserts the output queue's network fee of two
+            // transactions.
+            EarningsOperation::BatchAppend if tree_network_fee >= MIN_BATCH_NETWORK_FEE => {
+                tree_network_fee * 2
+            }
+            EarningsOperation::BatchAddressAppend if tree_network_fee >= MIN_BATCH_NETWORK_FEE => {
+                tree_network_fee
+            }
+            EarningsOperation::StateNullify | EarningsOperation::AddressUpdate => tree_network_fee,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TreeEarnings {
+    pub transactions: u64,
+    pub network_fees_earned: u64,
+    pub rent_rewards_claimed: u64,
+    pub base_fees_paid: u64,
+    pub priority_fees_paid: u64,
+    /// Transactions whose fees could not be fetched.
+    pub unreconciled_transactions: u64,
+    pub prover_time_ms: u64,
+}
+
+impl TreeEarnings {
+    /// Earnings minus fees paid, in lamports.
+    pub fn net_lamports(&self) -> i64 {
+        (self.network_fees_earned + self.rent_rewards_claimed) as i64
+            - (self.base_fees_paid + self.priority_fees_paid) as i64
+    }
+
+    fn add(&mut self, other: &TreeEarnings) {
+        self.transactions += other.transactions;
+        self.network_fees_earned += other.network_fees_earned;
+        self.rent_rewards_claimed += other.rent_rewards_claimed;
+        self.base_fees_paid += other.base_fees_paid;
+        self.priority_fees_paid += other.priority_fees_paid;
+        self.unreconciled_transactions += other.unreconciled_transactions;
+        self.prover_time_ms += other.prover_time_ms;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeEarningsEntry {
+    pub tree: String,
+    #[serde(flatten)]
+    pub earnings: TreeEarnings,
+    pub net_lamports: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EpochEarnings {
+    pub epoch: u64,
+    pub total: TreeEarnings,
+    pub net_lamports: i64,
+    pub trees: Vec<TreeEarningsEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EarningsSnapshot {
+    pub pending_transactions: usize,
+    /// Most recent epoch first.
+    pub epochs: Vec<EpochEarnings>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingTransaction {
+    signature: Signature,
+    epoch: u64,
+    tree: String,
+    /// Compress-and-close transactions pay the rent reward to the payer.
+    claims_rent: bool,
+    attempts: u32,
+}
+
+#[derive(Debug, Default)]
+struct EarningsState {
+    epochs: BTreeMap<u64, BTreeMap<String, TreeEarnings>>,
+    pending: VecDeque<PendingTransaction>,
+    pending_signatures: HashSet<Signature>,
+}
+
+impl EarningsState {
+    fn entry(&mut self, epoch: u64, tree: &str, max_epochs: usize) -> &mut TreeEarnings {
+        if !self.epochs.contains_key(&epoch) {
+            while self.epochs.len() >= max_epochs {
+                self.epochs.pop_first();
+            }
+        }
+        self.epochs
+            .entry(epoch)
+            .or_default()
+            .entry(tree.to_string())
+            .or_default()
+    }
+
+    /// Fees arriving after their epoch was evicted are not added back.
+    fn existing_entry(&mut self, epoch: u64, tree: &str) -> Option<&mut TreeEarnings> {
+        self.epochs.get_mut(&epoch)?.get_mut(tree)
+    }
+}
+
+/// Fees of a confirmed transaction, split into base and priority fee, and
+/// the change of the fee payer's balance excluding the fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReconciledFees {
+    base_fee: u64,
+    priority_fee: u64,
+    payer_gain: u64,
+}
+
+impl ReconciledFees {
+    fn new(fee: u64, num_signatures: usize, pre_balance: u64, post_balance: u64) -> Self {
+        let base_fee = (num_signatures as u64 * LAMPORTS_PER_SIGNATURE).min(fee);
+        Self {
+            base_fee,
+            priority_fee: fee - base_fee,
+            payer_gain: (post_balance + fee).saturating_sub(pre_balance),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EarningsTracker {
+    state: Mutex<EarningsState>,
+    max_epochs: usize,
+}
+
+impl Default for EarningsTracker {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(EarningsState::default()),
+            max_epochs: DEFAULT_MAX_EPOCHS,
+        }
+    }
+}
+
+impl EarningsTracker {
+    /// Records a confirmed transaction with the number of instructions per
+    /// operation and queues its signature for fee reconciliation.
+    /// `tree_network_fee` is the network fee of the tree's metadata, 0 for
+    /// compressible accounts. A signature is only recorded once.
+    pub fn record_transaction(
+        &self,
+        epoch: u64,
+        tree: &str,
+        tree_network_fee: u64,
+        signature: Signature,
+        instructions: &[(EarningsOperation, usize)],
+    ) {
+        let network_fee: u64 = instructions
+            .iter()
+            .map(|(operation, count)| operation.network_fee(tree_network_fee) * *count as u64)
+            .sum();
+        let claims_rent = instructions
+            .iter()
+            .any(|(operation, _)| *operation == EarningsOperation::CompressAndClose);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.pending_signatures.insert(signature) {
+            return;
+        }
+        let entry = state.entry(epoch, tree, self.max_epochs);
+        entry.transactions += 1;
+        entry.network_fees_earned += network_fee;
+        if state.pending.len() >= MAX_PENDING {
+            if let Some(dropped) = state.pending.pop_front() {
+                state.pending_signatures.remove(&dropped.signature);
+                warn!(
+                    event = "earnings_pending_dropped",
+                    signature = %dropped.signature,
+                    "Too many transactions pending fee reconciliation, dropping the oldest"
+                );
+                if let Some(entry) = state.existing_entry(dropped.epoch, &dropped.tree) {
+                    entry.unreconciled_transactions += 1;
+                }
+            }
+        }
+        state.pending.push_back(PendingTransaction {
+            signature,
+            epoch,
+            tree: tree.to_string(),
+            claims_rent,
+            attempts: 0,
+        });
+        drop(state);
+        if network_fee > 0 {
+            increment_earnings(tree, "network_fee", network_fee);
+        }
+    }
+
+    pub fn record_prover_time(&self, epoch: u64, tree: &str, duration: Duration) {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(epoch, tree, self.max_epochs)
+            .prover_time_ms += duration.as_millis() as u64;
+        increment_prover_seconds(tree, duration.as_secs_f64());
+    }
+
+    fn apply_fees(&self, pending: &PendingTransaction, fees: ReconciledFees) {
+        let rent_reward = if pending.claims_rent {
+            fees.payer_gain
+        } else {
+            0
+        };
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.pending_signatures.remove(&pending.signature);
+            if let Some(entry) = state.existing_entry(pending.epoch, &pending.tree) {
+                entry.base_fees_paid += fees.base_fee;
+                entry.priority_fees_paid += fees.priority_fee;
+                entry.rent_rewards_claimed += rent_reward;
+            }
+        }
+        increment_fees_paid(&pending.tree, "base_fee", fees.base_fee);
+        increment_fees_paid(&pending.tree, "priority_fee", fees.priority_fee);
+        if rent_reward > 0 {
+            increment_earnings(&pending.tree, "rent_reward", rent_reward);
+        }
+    }
+
+    fn requeue(&self, mut pending: PendingTransaction) {
+        pending.attempts += 1;
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.attempts >= MAX_RECONCILE_ATTEMPTS {
+            state.pending_signatures.remove(&pending.signature);
+            if let Some(entry) = state.existing_entry(pending.epoch, &pending.tree) {
+                entry.unreconciled_transactions += 1;
+            }
+            debug!(
+                "Giving up on fees of transaction {} after {} attempts",
+                pending.signature, pending.attempts
+            );
+        } else {
+            state.pending.push_back(pending);
+        }
+    }
+
+    /// Fetches the fees of pending transactions. Returns the number of
+    /// reconciled transactions.
+    pub async fn reconcile(&self, rpc: &RpcClient) -> usize {
+        let batch: Vec<PendingTransaction> = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let len = state.pending.len().min(MAX_RECONCILE_PER_PASS);
+            state.pending.drain(..len).collect()
+        };
+        let mut reconciled = 0;
+        for pending in batch {
+            match fetch_fees(rpc, &pending.signature).await {
+                Ok(fees) => {
+                    self.apply_fees(&pending, fees);
+                    reconciled += 1;
+                }
+                // Retried on the next pass, the transaction may not be
+                // visible yet.
+                Err(e) => {
+                    debug!(
+                        "Failed to fetch transaction {} for fee reconciliation: {}",
+                        pending.signature, e
+                    );
+                    self.requeue(pending);
+                }
+            }
+        }
+        reconciled
+    }
+
+    pub fn snapshot(&self) -> EarningsSnapshot {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let epochs = state
+            .epochs
+            .iter()
+            .rev()
+            .map(|(epoch, trees)| {
+                let mut total = TreeEarnings::default();
+                let trees = trees
+                    .iter()
+                    .map(|(tree, earnings)| {
+                        total.add(earnings);
+                        TreeEarningsEntry {
+                            tree: tree.clone(),
+                            earnings: *earnings,
+                            net_lamports: earnings.net_lamports(),
+                        }
+                    })
+                    .collect();
+                EpochEarnings {
+                    epoch: *epoch,
+                    total,
+                    net_lamports: total.net_lamports(),
+                    trees,
+                }
+            })
+            .collect();
+        EarningsSnapshot {
+            pending_transactions: state.pending.len(),
+            epochs,
+        }
+    }
+}
+
+/// The tracker bound to one tree and operation in an epoch, for the v1
+/// sender which sends many transactions of the same kind.
+#[derive(Debug, Clone)]
+pub struct TreeEarningsRecorder {
+    tracker: Arc<EarningsTracker>,
+    epoch: u64,
+    tree: String,
+    network_fee: u64,
+    operation: EarningsOperation,
+}
+
+impl TreeEarningsRecorder {
+    pub fn new(
+        tracker: Arc<EarningsTracker>,
+        epoch: u64,
+        tree: String,
+        network_fee: u64,
+        operation: EarningsOperation,
+    ) -> Self {
+        Self {
+            tracker,
+            epoch,
+            tree,
+            network_fee,
+            operation,
+        }
+    }
+
+    pub fn record_transaction(&self, signature: Signature, instructions: usize) {
+        self.tracker.record_transaction(
+            self.epoch,
+            &self.tree,
+            self.network_fee,
+            signature,
+            &[(self.operation, instructions)],
+        );
+    }
+}
+
+/// Counts the registry instructions of a legacy transaction, i.e. the
+/// instructions which earn network fees.
+pub fn count_registry_instructions(transaction: &Transaction) -> usize {
+    let message = &transaction.message;
+    message
+        .instructions
+        .iter()
+        .filter(|ix| {
+            message.account_keys.get(ix.program_id_index as usize) == Some(&light_registry::ID)
+        })
+        .count()
+}
+
+async fn fetch_fees(rpc: &RpcClient, signature: &Signature) -> crate::Result<ReconciledFees> {
+    let transaction = rpc
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} has no status meta", signature))?;
+    let num_signatures = transaction
+        .transaction
+        .transaction
+        .decode()
+        .map(|tx| tx.signatures.len())
+        .unwrap_or(1);
+    Ok(ReconciledFees::new(
+        meta.fee,
+        num_signatures,
+        meta.pre_balances.first().copied().unwrap_or_default(),
+        meta.post_balances.first().copied().unwrap_or_default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    #[test]
+    fn test_network_fees_follow_tree_network_fee() {
+        assert_eq!(EarningsOperation::BatchAppend.network_fee(5000), 10_000);
+        assert_eq!(EarningsOperation::BatchNullify.network_fee(5000), 0);
+        assert_eq!(
+            EarningsOperation::BatchAddressAppend.network_fee(10_000),
+            10_000
+        );
+        assert_eq!(EarningsOperation::StateNullify.network_fee(5000), 5000);
+        assert_eq!(EarningsOperation::AddressUpdate.network_fee(10_000), 10_000);
+        assert_eq!(EarningsOperation::CompressAndClose.network_fee(5000), 0);
+
+        assert_eq!(EarningsOperation::BatchAppend.network_fee(1), 0);
+        assert_eq!(EarningsOperation::BatchAddressAppend.network_fee(1), 0);
+        assert_eq!(EarningsOperation::StateNullify.network_fee(1), 1);
+    }
+
+    #[test]
+    fn test_earnings_per_epoch_and_tree() {
+        let tracker = EarningsTracker::default();
+        let tree = Pubkey::new_unique().to_string();
+        let append = PendingTransaction {
+            signature: Signature::new_unique(),
+            epoch: 3,
+            tree: tree.clone(),
+            claims_rent: false,
+            attempts: 0,
+        };
+        let instructions = [
+            (EarningsOperation::BatchAppend, 2),
+            (EarningsOperation::BatchNullify, 1),
+        ];
+        tracker.record_transaction(3, &tree, 5000, append.signature, &instructions);
+        // Recorded once per signature.
+        tracker.record_transaction(3, &tree, 5000, append.signature, &instructions);
+        let compression = PendingTransaction {
+            signature: Signature::new_unique(),
+            epoch: 4,
+            tree: CTOKEN_EARNINGS_KEY.to_string(),
+            claims_rent: true,
+            attempts: 0,
+        };
+        tracker.record_transaction(
+            4,
+            CTOKEN_EARNINGS_KEY,
+            0,
+            compression.signature,
+            &[(EarningsOperation::CompressAndClose, 5)],
+        );
+        tracker.record_prover_time(3, &tree, Duration::from_millis(1500));
+
+        tracker.apply_fees(&append, ReconciledFees::new(15_000, 1, 1_000_000, 995_000));
+        tracker.apply_fees(
+            &compression,
+            ReconciledFees::new(5000, 1, 1_000_000, 1_095_000),
+        );
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.pending_transactions, 2);
+        assert_eq!(
+            snapshot.epochs.iter().map(|e| e.epoch).collect::<Vec<_>>(),
+            vec![4, 3]
+        );
+        let epoch_3 = &snapshot.epochs[1].trees[0];
+        assert_eq!(epoch_3.tree, tree);
+        assert_eq!(
+            epoch_3.earnings,
+            TreeEarnings {
+                transactions: 1,
+                network_fees_earned: 20_000,
+                rent_rewards_claimed: 0,
+                base_fees_paid: 5000,
+                priority_fees_paid: 10_000,
+                unreconciled_transactions: 0,
+                prover_time_ms: 1500,
+            }
+        );
+        assert_eq!(epoch_3.net_lamports, 5000);
+        let epoch_4 = &snapshot.epochs[0];
+        assert_eq!(epoch_4.total.rent_rewards_claimed, 100_000);
+        assert_eq!(epoch_4.net_lamports, 95_000);
+    }
+
+    #[test]
+    fn test_unreconciled_transactions_are_dropped() {
+        let tracker = EarningsTracker::default();
+        let signature = Signature::new_unique();
+        tracker.record_transaction(
+            1,
+            "tree",
+            5000,
+            signature,
+            &[(EarningsOperation::BatchNullify, 1)],
+        );
+        for _ in 0..MAX_RECONCILE_ATTEMPTS {
+            let pending = tracker.state.lock().unwrap().pending.pop_front().unwrap();
+            tracker.requeue(pending);
+        }
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.pending_transactions, 0);
+        assert_eq!(snapshot.epochs[0].total.unreconciled_transactions, 1);
+        // The signature may be recorded again.
+        assert!(tracker.state.lock().unwrap().pending_signatures.is_empty());
+    }
+}
@@ -16,8 +16,12 @@ use forester_utils::{
 };
 use futures::future::join_all;
 use light_client::{
-    indexer::{Indexer, MerkleProof, NewAddressProofWithContext},
-    rpc::{LightClient, LightClientConfig, RetryConfig, Rpc, RpcError},
+    indexer::{
+        local::{LocalIndexer, LocalIndexerConfig},
+        Indexer, MerkleProof, NewAddressProofWithContext, TreeInfo,
+    },
+    migration::{get_migrate_tree_instructions, MigrateStateConfig},
+    rpc::{LightClientConfig, RetryConfig, Rpc, RpcError},
 };
 use light_compressed_account::TreeType;
 use light_registry::{
@@ -25,16 +29,22 @@ use light_registry::{
         create_batch_append_instruction, create_batch_nullify_instruction,
         create_batch_update_address_tree_instruction,
     },
-    protocol_config::state::{EpochState, ProtocolConfig},
+    protocol_config::state::{EpochState, ProtocolConfig, ProtocolConfigPda},
     sdk::{create_finalize_registration_instruction, create_report_work_instruction},
-    utils::{get_epoch_pda_address, get_forester_epoch_pda_from_authority},
+    utils::{
+        get_epoch_pda_address, get_forester_epoch_pda_from_authority,
+        get_protocol_config_pda_address,
+    },
     EpochPda, ForesterEpochPda,
 };
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::{
     instruction::InstructionError, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
 };
 use solana_sdk::{
     address_lookup_table::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
     signature::{Keypair, Signer},
     transaction::TransactionError,
 };
@@ -46,10 +56,17 @@ use tokio::{
 use tracing::{debug, error, info, info_span, instrument, trace, warn};
 
 use crate::{
+    admin::AdminCommand,
+    alerts::{AlertRule, AlertSeverity},
     compressible::{
+        pda::bootstrap_pda_program,
         traits::{Cancelled, CompressibleTracker, CompressionOutcome, CompressionTaskError},
         CTokenAccountTracker, CTokenCompressor, CompressibleConfig,
     },
+    earnings::{
+        EarningsOperation, EarningsTracker, TreeEarningsRecorder, CTOKEN_EARNINGS_KEY,
+        MINT_EARNINGS_KEY,
+    },
     errors::{
         rpc_is_already_processed, ChannelError, ForesterError, InitializationError,
         RegistrationError, WorkReportError,
@@ -59,7 +76,6 @@ use crate::{
         push_metrics, queue_metric_update, update_epoch_detected, update_epoch_registered,
         update_forester_sol_balance,
     },
-    pagerduty::send_pagerduty_alert,
     priority_fee::PriorityFeeConfig,
     processor::{
         tx_cache::ProcessedHashCache,
@@ -70,20 +86,25 @@ use crate::{
         },
         v2::{
             strategy::{AddressTreeStrategy, StateTreeStrategy},
-            BatchContext, BatchInstruction, ProcessingResult, ProverConfig, QueueProcessor,
-            SharedProofCache,
+            BatchContext, BatchInstruction, ProcessingResult, ProverConfig, ProverPool,
+            QueueProcessor, SharedProofCache,
         },
     },
     queue_helpers::QueueItemData,
     rollover::{
-        is_tree_ready_for_rollover, perform_address_merkle_tree_rollover,
-        perform_state_merkle_tree_rollover_forester,
+        is_tree_ready_for_close, is_tree_ready_for_rollover, perform_address_merkle_tree_rollover,
+        perform_state_merkle_tree_rollover_forester, perform_tree_close,
     },
+    sender::{TransactionOperation, TransactionSenders},
+    shard::ShardCoordinator,
     slot_tracker::{slot_duration, wait_until_slot_reached, SlotTracker},
     smart_transaction::{
-        send_smart_transaction, ComputeBudgetConfig, ConfirmationConfig,
+        send_smart_transaction_with_sender, ComputeBudgetConfig, ConfirmationConfig,
         SendSmartTransactionConfig, TransactionPolicy,
     },
+    state_store::{
+        EpochCounters, ForesterStateSnapshot, HashCacheEntry, ProofCacheSnapshot, StateStore,
+    },
     transaction_timing::{scheduled_confirmation_deadline, scheduled_v1_batch_timeout},
     tree_data_sync::{fetch_protocol_group_authority, fetch_trees},
     ForesterConfig, ForesterEpochInfo, Result,
@@ -148,7 +169,7 @@ impl RegistrationTracker {
 }
 
 /// Timing for a single circuit type (circuit inputs + proof generation)
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CircuitMetrics {
     /// Time spent building circuit inputs
     pub circuit_inputs_duration: std::time::Duration,
@@ -173,7 +194,7 @@ impl std::ops::AddAssign for CircuitMetrics {
 }
 
 /// Timing breakdown by circuit type
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ProcessingMetrics {
     /// State append circuit (output queue processing)
     pub append: CircuitMetrics,
@@ -250,6 +271,86 @@ pub enum MerkleProofType {
     StateProof(MerkleProof),
 }
 
+/// Interval at which the local indexers of migrated trees are synced.
+const MIGRATION_INDEXER_SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Local indexer of a v1 state tree whose leaves are migrated, kept in sync
+/// by a background task outside of the light slots.
+#[derive(Debug)]
+struct MigrationIndexer {
+    /// Set once the first sync has caught up with the history of the tree.
+    indexer: Arc<tokio::sync::OnceCell<Mutex<LocalIndexer>>>,
+    sync_handle: JoinHandle<()>,
+}
+
+impl MigrationIndexer {
+    fn spawn(config: LocalIndexerConfig, run_id: Arc<str>) -> Self {
+        let indexer = Arc::new(tokio::sync::OnceCell::new());
+        let sync_handle = tokio::spawn(Self::sync(config, indexer.clone(), run_id));
+        Self {
+            indexer,
+            sync_handle,
+        }
+    }
+
+    async fn sync(
+        config: LocalIndexerConfig,
+        cell: Arc<tokio::sync::OnceCell<Mutex<LocalIndexer>>>,
+        run_id: Arc<str>,
+    ) {
+        let tree = config.sync_address;
+        let mut indexer = loop {
+            match LocalIndexer::new(config.clone()).await {
+                Ok(indexer) => break indexer,
+                Err(e) => warn!(
+                    event = "state_migration_indexer_init_failed",
+                    run_id = %run_id,
+                    tree = ?tree,
+                    error = ?e,
+                    "Failed to create migration indexer"
+                ),
+            }
+            sleep(MIGRATION_INDEXER_SYNC_INTERVAL).await;
+        };
+        // Migrations only start once the history of the tree is replayed.
+        while let Err(e) = indexer.sync().await {
+            warn!(
+                event = "state_migration_indexer_sync_failed",
+                run_id = %run_id,
+                tree = ?tree,
+                error = ?e,
+                "Failed to sync migration indexer"
+            );
+            sleep(MIGRATION_INDEXER_SYNC_INTERVAL).await;
+        }
+        let indexer = cell.get_or_init(|| async { Mutex::new(indexer) }).await;
+        info!(
+            event = "state_migration_indexer_caught_up",
+            run_id = %run_id,
+            tree = ?tree,
+            "Migration indexer caught up"
+        );
+        loop {
+            sleep(MIGRATION_INDEXER_SYNC_INTERVAL).await;
+            if let Err(e) = indexer.lock().await.sync().await {
+                warn!(
+                    event = "state_migration_indexer_sync_failed",
+                    run_id = %run_id,
+                    tree = ?tree,
+                    error = ?e,
+                    "Failed to sync migration indexer"
+                );
+            }
+        }
+    }
+}
+
+impl Drop for MigrationIndexer {
+    fn drop(&mut self) {
+        self.sync_handle.abort();
+    }
+}
+
 #[derive(Debug)]
 pub struct EpochManager<R: Rpc + Indexer> {
     config: Arc<ForesterConfig>,
@@ -266,6 +367,8 @@ pub struct EpochManager<R: Rpc + Indexer> {
     ops_cache: Arc<Mutex<ProcessedHashCache>>,
     /// Proof caches for pre-warming during idle slots
     proof_caches: Arc<DashMap<Pubkey, Arc<SharedProofCache>>>,
+    /// Local indexers of v1 state trees whose leaves are migrated in idle slots
+    migration_indexers: Arc<DashMap<Pubkey, Arc<MigrationIndexer>>>,
     state_processors: StateBatchProcessorMap<R>,
     address_processors: AddressBatchProcessorMap<R>,
     state_processor_init_locks: ProcessorInitLockMap,
@@ -280,6 +383,9 @@ pub struct EpochManager<R: Rpc + Indexer> {
     run_id: Arc<str>,
     /// Per-epoch registration trackers to coordinate re-finalization when new foresters register mid-epoch
     registration_trackers: Arc<DashMap<u64, Arc<RegistrationTracker>>>,
+    transaction_senders: TransactionSenders,
+    state_store: Option<StateStore>,
+    prover_pool: Arc<ProverPool>,
 }
 
 impl<R: Rpc + Indexer> Clone for EpochManager<R> {
@@ -298,6 +404,7 @@ impl<R: Rpc + Indexer> Clone for EpochManager<R> {
             tx_cache: self.tx_cache.clone(),
             ops_cache: self.ops_cache.clone(),
             proof_caches: self.proof_caches.clone(),
+            migration_indexers: self.migration_indexers.clone(),
             state_processors: self.state_processors.clone(),
             address_processors: self.address_processors.clone(),
             state_processor_init_locks: self.state_processor_init_locks.clone(),
@@ -310,6 +417,9 @@ impl<R: Rpc + Indexer> Clone for EpochManager<R> {
             heartbeat: self.heartbeat.clone(),
             run_id: self.run_id.clone(),
             registration_trackers: self.registration_trackers.clone(),
+            transaction_senders: self.transaction_senders.clone(),
+            state_store: self.state_store.clone(),
+            prover_pool: self.prover_pool.clone(),
         }
     }
 }
@@ -333,6 +443,16 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         run_id: String,
     ) -> Result<Self> {
         let authority = Arc::new(config.payer_keypair.insecure_clone());
+        let transaction_senders = TransactionSenders::new(
+            &config.sender_config,
+            &config.external_services.rpc_url,
+            slot_tracker.clone(),
+        )?;
+        let state_store = config.general_config.state_dir.clone().map(StateStore::new);
+        let prover_pool = Arc::new(ProverPool::new(
+            &ProverConfig::new(&config.external_services)?,
+            config.alert_manager.clone(),
+        ));
         Ok(Self {
             config,
             protocol_config,
@@ -347,6 +467,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             tx_cache,
             ops_cache,
             proof_caches: Arc::new(DashMap::new()),
+            migration_indexers: Arc::new(DashMap::new()),
             state_processors: Arc::new(DashMap::new()),
             address_processors: Arc::new(DashMap::new()),
             state_processor_init_locks: Arc::new(DashMap::new()),
@@ -359,10 +480,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             heartbeat,
             run_id: Arc::<str>::from(run_id),
             registration_trackers: Arc::new(DashMap::new()),
+            transaction_senders,
+            state_store,
+            prover_pool,
         })
     }
 
     pub async fn run(self: Arc<Self>) -> Result<()> {
+        self.restore_state().await;
+
         let (tx, mut rx) = mpsc::channel(100);
         let tx = Arc::new(tx);
 
@@ -393,13 +519,40 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             async move { self_clone.check_sol_balance_periodically().await }
         });
 
+        let prover_health_handle = tokio::spawn(self.prover_pool.clone().monitor_health());
+
+        let indexer_lag_handle = tokio::spawn({
+            let self_clone = Arc::clone(&self);
+            async move { self_clone.check_indexer_lag_periodically().await }
+        });
+
+        let checkpoint_handle = tokio::spawn({
+            let self_clone = Arc::clone(&self);
+            async move { self_clone.checkpoint_state_periodically().await }
+        });
+
+        let earnings_handle = tokio::spawn({
+            let self_clone = Arc::clone(&self);
+            async move { self_clone.reconcile_earnings_periodically().await }
+        });
+
+        let admin_handle = tokio::spawn({
+            let self_clone = Arc::clone(&self);
+            async move { self_clone.process_admin_commands().await }
+        });
+
         let _guard = scopeguard::guard(
             (
                 current_previous_handle,
                 tree_discovery_handle,
                 balance_check_handle,
+                checkpoint_handle,
+                prover_health_handle,
+                indexer_lag_handle,
+                earnings_handle,
+                admin_handle,
             ),
-            |(h2, h3, h4)| {
+            |(h2, h3, h4, h5, h6, h7, h8, h9)| {
                 info!(
                     event = "background_tasks_aborting",
                     run_id = %self.run_id,
@@ -408,6 +561,11 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 h2.abort();
                 h3.abort();
                 h4.abort();
+                h5.abort();
+                h6.abort();
+                h7.abort();
+                h8.abort();
+                h9.abort();
             },
         );
 
@@ -415,6 +573,14 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             tokio::select! {
                 epoch_opt = rx.recv() => {
                     match epoch_opt {
+                        Some(epoch) if self.config.admin.is_draining() => {
+                            info!(
+                                event = "epoch_skipped_draining",
+                                run_id = %self.run_id,
+                                epoch,
+                                "Skipping epoch because the forester is draining"
+                            );
+                        }
                         Some(epoch) => {
                             debug!(
                                 event = "epoch_queued_for_processing",
@@ -473,14 +639,12 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                             );
                         }
                     }
-                    if let Some(pagerduty_key) = &self.config.external_services.pagerduty_routing_key {
-                        let _ = send_pagerduty_alert(
-                            pagerduty_key,
-                            &format!("Forester epoch monitor died unexpectedly on {}", self.config.payer_keypair.pubkey()),
-                            "critical",
-                            "epoch_monitor_dead",
-                        ).await;
-                    }
+                    self.config.alert_manager.trigger(
+                        AlertRule::EpochMonitorDead,
+                        "",
+                        AlertSeverity::Critical,
+                        format!("Forester epoch monitor died unexpectedly on {}", self.config.payer_keypair.pubkey()),
+                    );
                     break Err(anyhow!("Epoch monitor exited unexpectedly - forester cannot function without it"));
                 }
             }
@@ -511,6 +675,27 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                             balance_sol = balance_in_sol,
                             "Current SOL balance updated"
                         );
+                        let alerts = &self.config.alert_manager;
+                        let min_balance_sol = alerts.thresholds().min_balance_sol;
+                        if balance_in_sol < min_balance_sol {
+                            alerts.trigger(
+                                AlertRule::LowBalance,
+                                "",
+                                AlertSeverity::Critical,
+                                format!(
+                                    "Forester {} balance is {:.4} SOL, below {} SOL",
+                                    self.config.payer_keypair.pubkey(),
+                                    balance_in_sol,
+                                    min_balance_sol
+                                ),
+                            );
+                        } else {
+                            alerts.resolve(
+                                AlertRule::LowBalance,
+                                "",
+                                format!("Forester balance is {:.4} SOL", balance_in_sol),
+                            );
+                        }
                     }
                     Err(e) => error!(
                         event = "forester_balance_fetch_failed",
@@ -529,6 +714,368 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         }
     }
 
+    async fn check_indexer_lag_periodically(self: Arc<Self>) -> Result<()> {
+        let lag_threshold = self.config.alert_manager.thresholds().indexer_lag_slots;
+        if lag_threshold == 0 || self.config.external_services.indexer_url.is_none() {
+            return Ok(());
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+
+        loop {
+            interval.tick().await;
+            let slots = async {
+                let rpc = self.rpc_pool.get_connection().await?;
+                let rpc_slot = rpc.get_slot().await?;
+                let indexer_slot = rpc.indexer()?.get_indexer_slot(None).await?;
+                Ok::<_, anyhow::Error>((rpc_slot, indexer_slot))
+            }
+            .await;
+            match slots {
+                Ok((rpc_slot, indexer_slot)) => {
+                    let lag = rpc_slot.saturating_sub(indexer_slot);
+                    let alerts = &self.config.alert_manager;
+                    if lag >= lag_threshold {
+                        alerts.trigger(
+                            AlertRule::IndexerLag,
+                            "",
+                            AlertSeverity::Warning,
+                            format!(
+                                "Indexer is {} slots behind the RPC (rpc slot {}, indexer slot {})",
+                                lag, rpc_slot, indexer_slot
+                            ),
+                        );
+                    } else {
+                        alerts.resolve(
+                            AlertRule::IndexerLag,
+                            "",
+                            format!("Indexer caught up, {} slots behind", lag),
+                        );
+                    }
+                }
+                Err(e) => debug!(
+                    event = "indexer_lag_check_failed",
+                    run_id = %self.run_id,
+                    error = ?e,
+                    "Failed to compare indexer and RPC slots"
+                ),
+            }
+        }
+    }
+
+    async fn reconcile_earnings_periodically(self: Arc<Self>) -> Result<()> {
+        let Some(earnings) = self.earnings() else {
+            return Ok(());
+        };
+        let rpc = RpcClient::new_with_commitment(
+            self.config.external_services.rpc_url.clone(),
+            CommitmentConfig::confirmed(),
+        );
+        let mut interval = tokio::time::interval(Duration::from_secs(15));
+
+        loop {
+            interval.tick().await;
+            let reconciled = earnings.reconcile(&rpc).await;
+            if reconciled > 0 {
+                trace!(
+                    event = "earnings_reconciled",
+                    run_id = %self.run_id,
+                    transactions = reconciled,
+                    "Reconciled transaction fees"
+                );
+            }
+        }
+    }
+
+    async fn process_admin_commands(self: Arc<Self>) -> Result<()> {
+        let Some(mut commands) = self.config.admin.take_commands() else {
+            return Ok(());
+        };
+
+        while let Some(command) = commands.recv().await {
+            match command {
+                AdminCommand::RolloverCheck(tree) => {
+                    let trees: Vec<TreeAccounts> = self
+                        .trees
+                        .lock()
+                        .await
+                        .iter()
+                        .filter(|t| matches!(t.tree_type, TreeType::StateV1 | TreeType::AddressV1))
+                        .filter(|t| tree.is_none_or(|tree| t.merkle_tree == tree))
+                        .cloned()
+                        .collect();
+                    if trees.is_empty() {
+                        warn!(
+                            event = "admin_rollover_check_no_trees",
+                            run_id = %self.run_id,
+                            tree = ?tree,
+                            "No v1 tree to check for rollover"
+                        );
+                    }
+                    for tree_accounts in &trees {
+                        if let Err(e) = self.rollover_if_needed(tree_accounts).await {
+                            error!(
+                                event = "tree_rollover_failed",
+                                run_id = %self.run_id,
+                                tree = %tree_accounts.merkle_tree,
+                                tree_type = ?tree_accounts.tree_type,
+                                error = ?e,
+                                "Failed to rollover tree"
+                            );
+                        }
+                    }
+                }
+                AdminCommand::CompressionSweep(program_id) => {
+                    let program_config = self.config.compressible_config.as_ref().and_then(|c| {
+                        c.pda_programs
+                            .iter()
+                            .find(|program| program.program_id == program_id)
+                    });
+                    let (Some(tracker), Some(program_config)) =
+                        (self.pda_tracker.as_ref(), program_config)
+                    else {
+                        warn!(
+                            event = "admin_compression_sweep_unknown_program",
+                            run_id = %self.run_id,
+                            program = %program_id,
+                            "Program is not configured for PDA compression"
+                        );
+                        continue;
+                    };
+                    match bootstrap_pda_program(
+                        &self.config.external_services.rpc_url,
+                        tracker,
+                        program_config,
+                        self.config.general_config.helius_rpc,
+                    )
+                    .await
+                    {
+                        Ok(()) => self.config.admin.wake_compression(),
+                        Err(e) => error!(
+                            event = "admin_compression_sweep_failed",
+                            run_id = %self.run_id,
+                            program = %program_id,
+                            error = ?e,
+                            "Failed to fetch PDA accounts for the sweep"
+                        ),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn checkpoint_state_periodically(self: Arc<Self>) -> Result<()> {
+        if self.state_store.is_none() {
+            return Ok(());
+        }
+        let interval_duration = Duration::from_secs(
+            self.config
+                .general_config
+                .state_checkpoint_interval_seconds
+                .max(1),
+        );
+        let mut interval = tokio::time::interval(interval_duration);
+        // The first tick completes immediately, right after the restore.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            self.checkpoint_state().await;
+        }
+    }
+
+    async fn snapshot_state(&self) -> ForesterStateSnapshot {
+        let mut snapshot = ForesterStateSnapshot::new();
+
+        let metrics = self.processing_metrics_per_epoch.lock().await.clone();
+        let counts: HashMap<u64, usize> = self
+            .processed_items_per_epoch_count
+            .lock()
+            .await
+            .iter()
+            .map(|(epoch, count)| (*epoch, count.load(Ordering::Relaxed)))
+            .collect();
+        let mut epochs: Vec<u64> = counts.keys().chain(metrics.keys()).copied().collect();
+        epochs.sort_unstable();
+        epochs.dedup();
+        snapshot.epochs = epochs
+            .into_iter()
+            .map(|epoch| EpochCounters {
+                epoch,
+                processed_items: counts.get(&epoch).copied().unwrap_or_default(),
+                metrics: metrics.get(&epoch).copied().unwrap_or_default(),
+            })
+            .collect();
+
+        let caches: Vec<(Pubkey, Arc<SharedProofCache>)> = self
+            .proof_caches
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        for (tree, cache) in caches {
+            let Some((base_root, proofs)) = cache.snapshot().await else {
+                continue;
+            };
+            match ProofCacheSnapshot::new(tree, base_root, &proofs) {
+                Ok(cache_snapshot) => snapshot.proof_caches.push(cache_snapshot),
+                Err(e) => warn!(
+                    event = "state_checkpoint_proof_cache_failed",
+                    run_id = %self.run_id,
+                    tree = %tree,
+                    error = ?e,
+                    "Failed to serialize proof cache"
+                ),
+            }
+        }
+
+        snapshot.tx_cache = HashCacheEntry::from_snapshot(self.tx_cache.lock().await.snapshot());
+        snapshot.ops_cache = HashCacheEntry::from_snapshot(self.ops_cache.lock().await.snapshot());
+        snapshot
+    }
+
+    async fn checkpoint_state(&self) {
+        let Some(state_store) = &self.state_store else {
+            return;
+        };
+        let snapshot = self.snapshot_state().await;
+        match state_store.save(&snapshot).await {
+            Ok(()) => debug!(
+                event = "state_checkpoint_saved",
+                run_id = %self.run_id,
+                epochs = snapshot.epochs.len(),
+                proof_caches = snapshot.proof_caches.len(),
+                "Saved forester state checkpoint"
+            ),
+            Err(e) => warn!(
+                event = "state_checkpoint_failed",
+                run_id = %self.run_id,
+                dir = %state_store.dir().display(),
+                error = ?e,
+                "Failed to save forester state checkpoint"
+            ),
+        }
+    }
+
+    /// Reloads the last state checkpoint. Counters of epochs which ended
+    /// before the previous epoch are dropped, and proof caches are only
+    /// restored if they still chain from the on-chain root of their tree.
+    async fn restore_state(&self) {
+        let Some(state_store) = &self.state_store else {
+            return;
+        };
+        let snapshot = match state_store.load().await {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return,
+            Err(e) => {
+                warn!(
+                    event = "state_restore_failed",
+                    run_id = %self.run_id,
+                    dir = %state_store.dir().display(),
+                    error = ?e,
+                    "Failed to load forester state checkpoint"
+                );
+                return;
+            }
+        };
+
+        let current_epoch = match self.get_current_slot_and_epoch().await {
+            Ok((_, epoch)) => epoch,
+            Err(e) => {
+                warn!(
+                    event = "state_restore_failed",
+                    run_id = %self.run_id,
+                    error = ?e,
+                    "Failed to get current epoch for state restore"
+                );
+                return;
+            }
+        };
+        let mut restored_epochs = 0;
+        for counters in snapshot.epochs {
+            if counters.epoch + 1 < current_epoch {
+                continue;
+            }
+            self.increment_processed_items_count(counters.epoch, counters.processed_items)
+                .await;
+            self.add_processing_metrics(counters.epoch, counters.metrics)
+                .await;
+            restored_epochs += 1;
+        }
+
+        self.tx_cache
+            .lock()
+            .await
+            .restore(HashCacheEntry::into_snapshot(snapshot.tx_cache));
+        self.ops_cache
+            .lock()
+            .await
+            .restore(HashCacheEntry::into_snapshot(snapshot.ops_cache));
+
+        let trees = self.trees.lock().await.clone();
+        let mut restored_caches = 0;
+        for cache_snapshot in snapshot.proof_caches {
+            let Some(tree_accounts) = trees
+                .iter()
+                .find(|tree| tree.merkle_tree == cache_snapshot.tree)
+            else {
+                continue;
+            };
+            let current_root = match self.rpc_pool.get_connection().await {
+                Ok(mut rpc) => self.fetch_current_root(&mut *rpc, tree_accounts).await,
+                Err(e) => Err(e.into()),
+            };
+            let current_root = match current_root {
+                Ok(root) => root,
+                Err(e) => {
+                    warn!(
+                        event = "state_restore_root_fetch_failed",
+                        run_id = %self.run_id,
+                        tree = %cache_snapshot.tree,
+                        error = ?e,
+                        "Failed to fetch current root, dropping restored proof cache"
+                    );
+                    continue;
+                }
+            };
+            if !cache_snapshot.is_valid_for_root(&current_root) {
+                debug!(
+                    event = "state_restore_proof_cache_stale",
+                    run_id = %self.run_id,
+                    tree = %cache_snapshot.tree,
+                    "Restored proof cache does not chain from the on-chain root"
+                );
+                continue;
+            }
+            match cache_snapshot.cached_proofs() {
+                Ok(proofs) => {
+                    let cache = self
+                        .proof_caches
+                        .entry(cache_snapshot.tree)
+                        .or_insert_with(|| Arc::new(SharedProofCache::new(cache_snapshot.tree)))
+                        .clone();
+                    cache.restore(cache_snapshot.base_root, proofs).await;
+                    restored_caches += 1;
+                }
+                Err(e) => warn!(
+                    event = "state_restore_proof_cache_failed",
+                    run_id = %self.run_id,
+                    tree = %cache_snapshot.tree,
+                    error = ?e,
+                    "Failed to deserialize restored proof cache"
+                ),
+            }
+        }
+
+        info!(
+            event = "state_restored",
+            run_id = %self.run_id,
+            dir = %state_store.dir().display(),
+            epochs = restored_epochs,
+            proof_caches = restored_caches,
+            "Restored forester state checkpoint"
+        );
+    }
+
     /// Periodically fetches trees from on-chain and adds newly discovered ones.
     async fn discover_trees_periodically(self: Arc<Self>) -> Result<()> {
         let interval_secs = self.config.general_config.tree_discovery_interval_seconds;
@@ -601,6 +1148,8 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 let tree_ids = &self.config.general_config.tree_ids;
                 fetched_trees.retain(|tree| tree_ids.contains(&tree.merkle_tree));
             }
+            let exclude_tree_ids = &self.config.general_config.exclude_tree_ids;
+            fetched_trees.retain(|tree| !exclude_tree_ids.contains(&tree.merkle_tree));
 
             let known_trees = self.trees.lock().await;
             let known_pubkeys: std::collections::HashSet<Pubkey> =
@@ -1069,6 +1618,17 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         debug!("Recovering registration info for epoch {}", epoch);
         let mut registration_info = match self.recover_registration_info_if_exists(epoch).await {
             Ok(Some(info)) => info,
+            Ok(None) if self.transaction_senders.is_dry_run() => {
+                // A dry run shadows an existing registration and never
+                // registers on its own.
+                info!(
+                    event = "dry_run_epoch_not_registered",
+                    run_id = %self.run_id,
+                    epoch,
+                    "Skipping epoch in dry-run mode because the forester is not registered"
+                );
+                return Ok(());
+            }
             Ok(None) => {
                 debug!(
                     "No existing registration found for epoch {}, will register fresh",
@@ -1156,6 +1716,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             .lock()
             .await
             .remove(&epoch);
+        self.checkpoint_state().await;
 
         info!(
             event = "process_epoch_completed",
@@ -1165,6 +1726,18 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         Ok(())
     }
 
+    /// Dedicated connection with confirmed commitment for registrations and
+    /// work reports, the rpc pool reads at processed commitment.
+    async fn confirmed_rpc(&self) -> std::result::Result<R, RpcError> {
+        R::new(LightClientConfig {
+            url: self.config.external_services.rpc_url.to_string(),
+            photon_url: self.config.external_services.indexer_url.clone(),
+            commitment_config: Some(CommitmentConfig::confirmed()),
+            fetch_active_tree: false,
+        })
+        .await
+    }
+
     async fn get_current_slot_and_epoch(&self) -> Result<(u64, u64)> {
         let slot = self.slot_tracker.estimated_current_slot();
         Ok((slot, self.protocol_config.get_current_epoch(slot)))
@@ -1178,14 +1751,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         max_retries: u32,
         retry_delay: Duration,
     ) -> std::result::Result<ForesterEpochInfo, ForesterError> {
-        let rpc = LightClient::new(LightClientConfig {
-            url: self.config.external_services.rpc_url.to_string(),
-            photon_url: self.config.external_services.indexer_url.clone(),
-            commitment_config: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
-            fetch_active_tree: false,
-        })
-        .await
-        .map_err(ForesterError::Rpc)?;
+        let rpc = self.confirmed_rpc().await.map_err(ForesterError::Rpc)?;
         let slot = rpc.get_slot().await.map_err(ForesterError::Rpc)?;
         let phases = get_epoch_phases(&self.protocol_config, epoch);
 
@@ -1226,29 +1792,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                     if attempt < max_retries - 1 {
                         sleep(retry_delay).await;
                     } else {
-                        if let Some(pagerduty_key) =
-                            self.config.external_services.pagerduty_routing_key.clone()
-                        {
-                            if let Err(alert_err) = send_pagerduty_alert(
-                                &pagerduty_key,
-                                &format!(
-                                    "Forester failed to register for epoch {} after {} attempts",
-                                    epoch, max_retries
-                                ),
-                                "critical",
-                                &format!("Forester {}", self.config.payer_keypair.pubkey()),
-                            )
-                            .await
-                            {
-                                error!(
-                                    event = "pagerduty_alert_failed",
-                                    run_id = %self.run_id,
-                                    epoch,
-                                    error = ?alert_err,
-                                    "Failed to send PagerDuty alert"
-                                );
-                            }
-                        }
+                        self.config.alert_manager.trigger(
+                            AlertRule::RegistrationFailed,
+                            &epoch.to_string(),
+                            AlertSeverity::Critical,
+                            format!(
+                                "Forester failed to register for epoch {} after {} attempts",
+                                epoch, max_retries
+                            ),
+                        );
                         return Err(ForesterError::Other(e));
                     }
                 }
@@ -1269,13 +1821,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             run_id = %self.run_id,
             epoch, "Registering for epoch"
         );
-        let mut rpc = LightClient::new(LightClientConfig {
-            url: self.config.external_services.rpc_url.to_string(),
-            photon_url: self.config.external_services.indexer_url.clone(),
-            commitment_config: Some(solana_sdk::commitment_config::CommitmentConfig::processed()),
-            fetch_active_tree: false,
-        })
-        .await?;
+        let mut rpc = self.confirmed_rpc().await?;
         let slot = rpc.get_slot().await?;
         let phases = get_epoch_phases(&self.protocol_config, epoch);
 
@@ -1514,7 +2060,10 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 };
                 let payer = self.config.payer_keypair.pubkey();
                 let signers = [&self.config.payer_keypair];
-                send_smart_transaction(
+                let sender = self
+                    .transaction_senders
+                    .dry_run_sender("finalize_registration");
+                send_smart_transaction_with_sender(
                     &mut *rpc,
                     SendSmartTransactionConfig {
                         instructions: vec![ix],
@@ -1528,6 +2077,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                         confirmation: Some(self.confirmation_config()),
                         confirmation_deadline: Some(confirmation_deadline),
                     },
+                    sender.as_deref(),
                 )
                 .await
                 .map_err(RpcError::from)?;
@@ -1561,6 +2111,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 tree_type: TreeType::Unknown,
                 is_rolledover: false,
                 owner: solana_sdk::pubkey::Pubkey::default(),
+                network_fee: 0,
             };
             let tree_schedule = TreeForesterSchedule::new_with_schedule(
                 &compression_tree_accounts,
@@ -1746,6 +2297,10 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         );
 
         let mut last_weight_check = Instant::now();
+        let mut failed_light_slots = 0u32;
+        let missed_light_slots_threshold =
+            self.config.alert_manager.thresholds().missed_light_slots;
+        let tree_key = tree_schedule.tree_accounts.merkle_tree.to_string();
         const WEIGHT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
         'outer_slot_loop: while current_slot < epoch_info.phases.active.end {
@@ -1755,7 +2310,48 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 .enumerate()
                 .find_map(|(idx, opt_slot)| opt_slot.as_ref().map(|s| (idx, s.clone())));
 
+            if self.config.admin.is_draining() {
+                info!(
+                    event = "process_queue_drained",
+                    run_id = %self.run_id,
+                    tree = %tree_schedule.tree_accounts.merkle_tree,
+                    "Stopping queue processing because the forester is draining"
+                );
+                break 'outer_slot_loop;
+            }
+
             if let Some((slot_idx, light_slot_details)) = next_slot_to_process {
+                if self
+                    .config
+                    .admin
+                    .is_paused(&tree_schedule.tree_accounts.merkle_tree)
+                {
+                    trace!(
+                        "Skipping light slot {} of paused tree {}",
+                        light_slot_details.slot,
+                        tree_schedule.tree_accounts.merkle_tree
+                    );
+                    tree_schedule.slots[slot_idx] = None;
+                    current_slot = self.slot_tracker.estimated_current_slot();
+                    continue;
+                }
+                if !self
+                    .owns_tree_at_slot(
+                        &tree_schedule.tree_accounts.merkle_tree,
+                        light_slot_details.start_solana_slot,
+                    )
+                    .await?
+                {
+                    trace!(
+                        "Skipping light slot {} of tree {} assigned to another shard",
+                        light_slot_details.slot,
+                        tree_schedule.tree_accounts.merkle_tree
+                    );
+                    tree_schedule.slots[slot_idx] = None;
+                    current_slot = self.slot_tracker.estimated_current_slot();
+                    continue;
+                }
+                let in_flight = self.config.admin.start_light_slot();
                 let result = match tree_type {
                     TreeType::StateV1 | TreeType::AddressV1 | TreeType::Unknown => {
                         self.process_light_slot(
@@ -1780,6 +2376,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                         .await
                     }
                 };
+                drop(in_flight);
 
                 let mut force_refinalize = false;
                 match result {
@@ -1788,8 +2385,28 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                             "Successfully processed light slot {:?}",
                             light_slot_details.slot
                         );
+                        failed_light_slots = 0;
+                        self.config.alert_manager.resolve(
+                            AlertRule::MissedLightSlots,
+                            &tree_key,
+                            format!("Light slots of tree {} are processed again", tree_key),
+                        );
                     }
                     Err(e) => {
+                        failed_light_slots += 1;
+                        if missed_light_slots_threshold > 0
+                            && failed_light_slots >= missed_light_slots_threshold
+                        {
+                            self.config.alert_manager.trigger(
+                                AlertRule::MissedLightSlots,
+                                &tree_key,
+                                AlertSeverity::Error,
+                                format!(
+                                    "{} consecutive eligible light slots of tree {} failed in epoch {}: {}",
+                                    failed_light_slots, tree_key, epoch_info.epoch, e
+                                ),
+                            );
+                        }
                         force_refinalize = e.is_forester_not_eligible();
                         if force_refinalize {
                             warn!(
@@ -1859,6 +2476,23 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         Ok(())
     }
 
+    /// With sharding, waits until the light slot starts and checks whether the
+    /// tree is assigned to this instance at that point, so that trees of a
+    /// peer which went silent are taken over at the next light slot.
+    async fn owns_tree_at_slot(&self, tree: &Pubkey, start_slot: u64) -> Result<bool> {
+        let Some(shard) = &self.config.shard_coordinator else {
+            return Ok(true);
+        };
+        if shard.owns(tree) {
+            return Ok(true);
+        }
+        {
+            let mut rpc = self.rpc_pool.get_connection().await?;
+            wait_until_slot_reached(&mut *rpc, &self.slot_tracker, start_slot).await?;
+        }
+        Ok(shard.owns(tree))
+    }
+
     /// Check if `EpochPda.registered_weight` changed on-chain. If so,
     /// one task sends a `finalize_registration` tx while others wait,
     /// then all tasks refresh their `ForesterEpochPda` and recompute schedules.
@@ -1925,7 +2559,10 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 };
                 let payer = self.config.payer_keypair.pubkey();
                 let signers = [&self.config.payer_keypair];
-                match send_smart_transaction(
+                let sender = self
+                    .transaction_senders
+                    .dry_run_sender("finalize_registration");
+                match send_smart_transaction_with_sender(
                     &mut *rpc,
                     SendSmartTransactionConfig {
                         instructions: vec![ix],
@@ -1939,6 +2576,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                         confirmation: Some(self.confirmation_config()),
                         confirmation_deadline: Some(confirmation_deadline),
                     },
+                    sender.as_deref(),
                 )
                 .await
                 .map_err(RpcError::from)
@@ -2153,7 +2791,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 self.config.general_config.sleep_when_idle_ms
             };
 
-            tokio::time::sleep(Duration::from_millis(sleep_duration_ms)).await;
+            if tree_accounts.tree_type == TreeType::Unknown {
+                // A compression sweep requested through the admin API ends the sleep.
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(sleep_duration_ms)) => {}
+                    _ = self.config.admin.compression_requested() => {}
+                }
+            } else {
+                tokio::time::sleep(Duration::from_millis(sleep_duration_ms)).await;
+            }
         }
         Ok(())
     }
@@ -2397,7 +3043,20 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         consecutive_eligibility_end: u64,
         current_solana_slot: u64,
     ) -> std::result::Result<usize, ForesterError> {
-        match tree_accounts.tree_type {
+        if tree_accounts.is_rolledover {
+            // Rolled over trees are closed once drained, closed trees are
+            // still part of the schedule of the current epoch.
+            let is_tracked = self
+                .trees
+                .lock()
+                .await
+                .iter()
+                .any(|t| t.merkle_tree == tree_accounts.merkle_tree);
+            if !is_tracked {
+                return Ok(0);
+            }
+        }
+        let result = match tree_accounts.tree_type {
             TreeType::Unknown => self
                 .dispatch_compression(
                     epoch_info,
@@ -2426,7 +3085,34 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                     .await;
                 Ok(result.items_processed)
             }
+        };
+        if tree_accounts.is_rolledover && matches!(result, Ok(0)) {
+            if let Err(e) = self.close_if_needed(tree_accounts).await {
+                error!(
+                    event = "tree_close_failed",
+                    run_id = %self.run_id,
+                    tree = %tree_accounts.merkle_tree,
+                    tree_type = ?tree_accounts.tree_type,
+                    error = ?e,
+                    "Failed to close tree"
+                );
+            }
+        } else if tree_accounts.tree_type == TreeType::StateV1
+            && self.config.general_config.enable_state_migration
+            && matches!(result, Ok(0))
+        {
+            // Idle light slots of v1 state trees are used to migrate leaves.
+            if let Err(e) = self.migrate_if_idle(epoch_info, tree_accounts).await {
+                error!(
+                    event = "state_migration_failed",
+                    run_id = %self.run_id,
+                    tree = %tree_accounts.merkle_tree,
+                    error = ?e,
+                    "Failed to migrate state"
+                );
+            }
         }
+        result
     }
 
     async fn dispatch_compression(
@@ -2505,7 +3191,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             self.rpc_pool.clone(),
             tracker.clone(),
             self.config.payer_keypair.insecure_clone(),
-            self.transaction_policy(),
+            self.transaction_policy_for(TransactionOperation::CompressAndClose),
         );
 
         // Derive registered forester PDA once for all batches
@@ -2609,6 +3295,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         for result in results {
             match result {
                 Ok((batch_idx, count, sig)) => {
+                    if let Some(earnings) = self.earnings() {
+                        earnings.record_transaction(
+                            epoch_info.epoch,
+                            CTOKEN_EARNINGS_KEY,
+                            0,
+                            sig,
+                            &[(EarningsOperation::CompressAndClose, count)],
+                        );
+                    }
                     info!(
                         event = "compression_ctoken_batch_succeeded",
                         run_id = %self.run_id,
@@ -2703,7 +3398,12 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             return Ok(0);
         };
 
-        if config.pda_programs.is_empty() {
+        let pda_programs = self
+            .config
+            .reloadable
+            .pda_programs()
+            .unwrap_or_else(|| config.pda_programs.clone());
+        if pda_programs.is_empty() {
             return Ok(0);
         }
 
@@ -2713,7 +3413,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         let cancelled = Arc::new(AtomicBool::new(false));
 
         // Process each configured PDA program
-        for program_config in &config.pda_programs {
+        for program_config in &pda_programs {
             // Check cancellation at program level
             if cancelled.load(Ordering::Relaxed) {
                 break;
@@ -2742,7 +3442,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 self.rpc_pool.clone(),
                 pda_tracker.clone(),
                 self.config.payer_keypair.insecure_clone(),
-                self.transaction_policy(),
+                self.transaction_policy_for(TransactionOperation::CompressAndClose),
             );
 
             // Fetch and cache config once per program
@@ -2796,6 +3496,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                             "Compressed PDA {} for program {}: {}",
                             account_state.pubkey, program_config.program_id, sig
                         );
+                        if let Some(earnings) = self.earnings() {
+                            earnings.record_transaction(
+                                epoch_info.epoch,
+                                &program_config.program_id.to_string(),
+                                0,
+                                sig,
+                                &[(EarningsOperation::CompressAndClose, 1)],
+                            );
+                        }
                         total_compressed += 1;
                     }
                     CompressionOutcome::Failed {
@@ -2866,7 +3575,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             self.rpc_pool.clone(),
             mint_tracker.clone(),
             self.config.payer_keypair.insecure_clone(),
-            self.transaction_policy(),
+            self.transaction_policy_for(TransactionOperation::CompressAndClose),
         );
 
         // Shared cancellation flag
@@ -2886,6 +3595,15 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                     state: mint_state,
                 } => {
                     debug!("Compressed Mint {}: {}", mint_state.pubkey, sig);
+                    if let Some(earnings) = self.earnings() {
+                        earnings.record_transaction(
+                            epoch_info.epoch,
+                            MINT_EARNINGS_KEY,
+                            0,
+                            sig,
+                            &[(EarningsOperation::CompressAndClose, 1)],
+                        );
+                    }
                     total_compressed += 1;
                 }
                 CompressionOutcome::Failed {
@@ -2985,13 +3703,14 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             return Ok(0);
         };
 
+        let priority_fee_config = self.transaction_priority_fee_config();
         let batched_tx_config = SendBatchedTransactionsConfig {
             num_batches: 1,
             build_transaction_batch_config: BuildTransactionBatchConfig {
                 batch_size: self.config.transaction_config.legacy_ixs_per_tx as u64,
-                compute_unit_price: self.config.transaction_config.priority_fee_microlamports,
+                compute_unit_price: priority_fee_config.compute_unit_price,
                 compute_unit_limit: Some(self.config.transaction_config.cu_limit),
-                enable_priority_fees: self.config.transaction_config.enable_priority_fees,
+                enable_priority_fees: priority_fee_config.enable_priority_fees,
                 max_concurrent_sends: Some(self.config.transaction_config.max_concurrent_sends),
             },
             queue_config: self.config.queue_config,
@@ -3020,6 +3739,21 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             &batched_tx_config,
             *tree_accounts,
             transaction_builder,
+            self.transaction_senders.dry_run_sender("v1"),
+            self.earnings().map(|earnings| {
+                let operation = if tree_accounts.tree_type == TreeType::AddressV1 {
+                    EarningsOperation::AddressUpdate
+                } else {
+                    EarningsOperation::StateNullify
+                };
+                TreeEarningsRecorder::new(
+                    earnings,
+                    epoch_info.epoch,
+                    tree_accounts.merkle_tree.to_string(),
+                    tree_accounts.network_fee,
+                    operation,
+                )
+            }),
         )
         .await?;
 
@@ -3057,7 +3791,6 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         output_queue_hint: Option<u64>,
         eligibility_end: Option<u64>,
     ) -> BatchContext<R> {
-        let default_prover_url = "http://127.0.0.1:3001".to_string();
         let eligibility_end = eligibility_end.unwrap_or(0);
         BatchContext {
             rpc_pool: self.rpc_pool.clone(),
@@ -3067,37 +3800,8 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             epoch: epoch_info.epoch,
             merkle_tree: tree_accounts.merkle_tree,
             output_queue: tree_accounts.queue,
-            prover_config: Arc::new(ProverConfig {
-                append_url: self
-                    .config
-                    .external_services
-                    .prover_append_url
-                    .clone()
-                    .unwrap_or_else(|| default_prover_url.clone()),
-                update_url: self
-                    .config
-                    .external_services
-                    .prover_update_url
-                    .clone()
-                    .unwrap_or_else(|| default_prover_url.clone()),
-                address_append_url: self
-                    .config
-                    .external_services
-                    .prover_address_append_url
-                    .clone()
-                    .unwrap_or_else(|| default_prover_url.clone()),
-                api_key: self.config.external_services.prover_api_key.clone(),
-                polling_interval: self
-                    .config
-                    .external_services
-                    .prover_polling_interval
-                    .unwrap_or(Duration::from_secs(1)),
-                max_wait_time: self
-                    .config
-                    .external_services
-                    .prover_max_wait_time
-                    .unwrap_or(Duration::from_secs(600)),
-            }),
+            network_fee: tree_accounts.network_fee,
+            prover_pool: self.prover_pool.clone(),
             ops_cache: self.ops_cache.clone(),
             epoch_phases: epoch_info.phases.clone(),
             slot_tracker: self.slot_tracker.clone(),
@@ -3107,10 +3811,21 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             forester_eligibility_end_slot: Arc::new(AtomicU64::new(eligibility_end)),
             address_lookup_tables: self.address_lookup_tables.clone(),
             transaction_policy: self.transaction_policy(),
-            max_batches_per_tree: self.config.transaction_config.max_batches_per_tree,
+            transaction_senders: self.transaction_senders.clone(),
+            max_batches_per_tree: self
+                .config
+                .admin
+                .max_batches_per_tree()
+                .unwrap_or(self.config.transaction_config.max_batches_per_tree),
+            earnings: self.earnings(),
         }
     }
 
+    /// None in dry-run mode, where nothing is earned or paid.
+    fn earnings(&self) -> Option<Arc<EarningsTracker>> {
+        (!self.transaction_senders.is_dry_run()).then(|| self.config.earnings.clone())
+    }
+
     fn confirmation_config(&self) -> ConfirmationConfig {
         ConfirmationConfig {
             max_attempts: self.config.transaction_config.confirmation_max_attempts,
@@ -3121,10 +3836,13 @@ impl<R: Rpc + Indexer> EpochManager<R> {
     }
 
     fn transaction_priority_fee_config(&self) -> PriorityFeeConfig {
-        PriorityFeeConfig {
-            compute_unit_price: self.config.transaction_config.priority_fee_microlamports,
-            enable_priority_fees: self.config.transaction_config.enable_priority_fees,
-        }
+        self.config
+            .reloadable
+            .priority_fee()
+            .unwrap_or(PriorityFeeConfig {
+                compute_unit_price: self.config.transaction_config.priority_fee_microlamports,
+                enable_priority_fees: self.config.transaction_config.enable_priority_fees,
+            })
     }
 
     fn transaction_policy(&self) -> TransactionPolicy {
@@ -3132,6 +3850,14 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             priority_fee_config: self.transaction_priority_fee_config(),
             compute_unit_limit: Some(self.config.transaction_config.cu_limit),
             confirmation: Some(self.confirmation_config()),
+            sender: None,
+        }
+    }
+
+    fn transaction_policy_for(&self, operation: TransactionOperation) -> TransactionPolicy {
+        TransactionPolicy {
+            sender: self.transaction_senders.for_operation(operation),
+            ..self.transaction_policy()
         }
     }
 
@@ -3842,7 +4568,8 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 let instruction_count = instructions.len();
                 let payer = self.config.payer_keypair.pubkey();
                 let signers = [&self.config.payer_keypair];
-                match send_smart_transaction(
+                let sender = self.transaction_senders.dry_run_sender("cached_proofs");
+                match send_smart_transaction_with_sender(
                     &mut *rpc,
                     SendSmartTransactionConfig {
                         instructions,
@@ -3856,6 +4583,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                         confirmation: Some(self.confirmation_config()),
                         confirmation_deadline: Some(confirmation_deadline),
                     },
+                    sender.as_deref(),
                 )
                 .await
                 .map_err(RpcError::from)
@@ -3902,6 +4630,202 @@ impl<R: Rpc + Indexer> EpochManager<R> {
         Ok(())
     }
 
+    /// Closes a rolled over tree once its close threshold has passed and its
+    /// queues are drained, and stops tracking it.
+    /// The rent is returned to the protocol authority.
+    async fn close_if_needed(&self, tree_account: &TreeAccounts) -> Result<()> {
+        let mut rpc = self.rpc_pool.get_connection().await?;
+        let (current_slot, current_epoch) = self.get_current_slot_and_epoch().await?;
+        if !is_tree_ready_for_close(
+            &mut *rpc,
+            tree_account.merkle_tree,
+            tree_account.tree_type,
+            current_slot,
+        )
+        .await?
+        {
+            return Ok(());
+        }
+        info!(
+            event = "tree_close_started",
+            run_id = %self.run_id,
+            tree = %tree_account.merkle_tree,
+            tree_type = ?tree_account.tree_type,
+            "Starting tree close"
+        );
+
+        let protocol_config_pda = get_protocol_config_pda_address().0;
+        let protocol_authority = rpc
+            .get_anchor_account::<ProtocolConfigPda>(&protocol_config_pda)
+            .await?
+            .ok_or_else(|| anyhow!("Protocol config account {} not found", protocol_config_pda))?
+            .authority;
+        let sender = self
+            .transaction_senders
+            .for_operation(TransactionOperation::Rollover);
+        let signature = perform_tree_close(
+            &self.config.payer_keypair,
+            &self.config.derivation_pubkey,
+            &mut *rpc,
+            tree_account,
+            protocol_authority,
+            current_epoch,
+            sender.as_deref(),
+        )
+        .await?;
+
+        // A dry run only simulates the close, the tree stays open on chain.
+        if self.transaction_senders.is_dry_run() {
+            info!(
+                event = "dry_run_tree_close_simulated",
+                run_id = %self.run_id,
+                tree = %tree_account.merkle_tree,
+                tree_type = ?tree_account.tree_type,
+                signature = %signature,
+                "Simulated tree close in dry-run mode"
+            );
+            return Ok(());
+        }
+        self.trees
+            .lock()
+            .await
+            .retain(|t| t.merkle_tree != tree_account.merkle_tree);
+        info!(
+            event = "tree_close_succeeded",
+            run_id = %self.run_id,
+            tree = %tree_account.merkle_tree,
+            tree_type = ?tree_account.tree_type,
+            signature = %signature,
+            "Tree closed"
+        );
+        Ok(())
+    }
+
+    /// Migrates live leaves of a v1 state tree to a v2 state tree, at most
+    /// `max_migrations_per_slot` per call. Leaves are found with a local
+    /// indexer which replays the transactions of the tree. The first call
+    /// spawns its background sync, migrations start once it has caught up.
+    /// Migrations stop at the first failed transaction, e.g. once the light
+    /// slot has ended, and are resumed in the next idle slot.
+    async fn migrate_if_idle(&self, epoch_info: &Epoch, tree_account: &TreeAccounts) -> Result<()> {
+        let general_config = &self.config.general_config;
+        let output_queue = match general_config.migration_output_queue {
+            Some(output_queue) => output_queue,
+            None => {
+                let output_queue = self
+                    .trees
+                    .lock()
+                    .await
+                    .iter()
+                    .find(|t| {
+                        t.tree_type == TreeType::StateV2
+                            && !t.is_rolledover
+                            && t.owner == tree_account.owner
+                    })
+                    .map(|t| t.queue);
+                let Some(output_queue) = output_queue else {
+                    debug!(
+                        event = "state_migration_skipped_no_output_queue",
+                        run_id = %self.run_id,
+                        tree = %tree_account.merkle_tree,
+                        "No v2 state tree to migrate to"
+                    );
+                    return Ok(());
+                };
+                output_queue
+            }
+        };
+        let config = MigrateStateConfig {
+            authority: self.config.payer_keypair.pubkey(),
+            derivation: self.config.derivation_pubkey,
+            epoch: epoch_info.epoch,
+            output_queue,
+        };
+        let migration_indexer = self
+            .migration_indexers
+            .entry(tree_account.merkle_tree)
+            .or_insert_with(|| {
+                Arc::new(MigrationIndexer::spawn(
+                    LocalIndexerConfig {
+                        sync_address: Some(tree_account.merkle_tree),
+                        ..LocalIndexerConfig::new(
+                            self.config.external_services.rpc_url.clone(),
+                            vec![TreeInfo {
+                                tree: tree_account.merkle_tree,
+                                queue: tree_account.queue,
+                                cpi_context: None,
+                                next_tree_info: None,
+                                tree_type: TreeType::StateV1,
+                            }],
+                        )
+                    },
+                    self.run_id.clone(),
+                ))
+            })
+            .clone();
+        let Some(indexer) = migration_indexer.indexer.get() else {
+            debug!(
+                event = "state_migration_skipped_indexer_catching_up",
+                run_id = %self.run_id,
+                tree = %tree_account.merkle_tree,
+                "Migration indexer has not caught up yet"
+            );
+            return Ok(());
+        };
+        let instructions = get_migrate_tree_instructions(
+            &*indexer.lock().await,
+            tree_account.merkle_tree,
+            &config,
+            general_config.max_migrations_per_slot,
+        )
+        .await?
+        .instructions;
+        if instructions.is_empty() {
+            return Ok(());
+        }
+        info!(
+            event = "state_migration_started",
+            run_id = %self.run_id,
+            tree = %tree_account.merkle_tree,
+            output_queue = %output_queue,
+            leaves = instructions.len(),
+            "Starting state migration"
+        );
+
+        let mut rpc = self.rpc_pool.get_connection().await?;
+        let payer = self.config.payer_keypair.pubkey();
+        let signers = [&self.config.payer_keypair];
+        let sender = self.transaction_senders.dry_run_sender("state_migration");
+        let num_leaves = instructions.len();
+        for (num_migrated, instruction) in instructions.into_iter().enumerate() {
+            send_smart_transaction_with_sender(
+                &mut *rpc,
+                SendSmartTransactionConfig {
+                    instructions: vec![instruction],
+                    payer: &payer,
+                    signers: &signers,
+                    address_lookup_tables: &self.address_lookup_tables,
+                    compute_budget: Default::default(),
+                    confirmation: Some(self.confirmation_config()),
+                    confirmation_deadline: None,
+                },
+                sender.as_deref(),
+            )
+            .await
+            .map_err(RpcError::from)
+            .with_context(|| format!("migrated {} of {} leaves", num_migrated, num_leaves))?;
+        }
+        info!(
+            event = "state_migration_succeeded",
+            run_id = %self.run_id,
+            tree = %tree_account.merkle_tree,
+            output_queue = %output_queue,
+            leaves = num_leaves,
+            "State migrated"
+        );
+        Ok(())
+    }
+
     fn is_in_active_phase(&self, slot: u64, epoch_info: &ForesterEpochInfo) -> Result<bool> {
         let current_epoch = self.protocol_config.get_current_active_epoch(slot)?;
         if current_epoch != epoch_info.epoch.epoch {
@@ -3979,13 +4903,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             epoch = epoch_info.epoch.epoch,
             "Reporting work on-chain"
         );
-        let mut rpc = LightClient::new(LightClientConfig {
-            url: self.config.external_services.rpc_url.to_string(),
-            photon_url: self.config.external_services.indexer_url.clone(),
-            commitment_config: Some(solana_sdk::commitment_config::CommitmentConfig::processed()),
-            fetch_active_tree: false,
-        })
-        .await?;
+        let mut rpc = self.confirmed_rpc().await?;
 
         let forester_epoch_pda_pubkey = get_forester_epoch_pda_from_authority(
             &self.config.derivation_pubkey,
@@ -4017,7 +4935,8 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             .await?;
         let payer = self.config.payer_keypair.pubkey();
         let signers = [&self.config.payer_keypair];
-        match send_smart_transaction(
+        let sender = self.transaction_senders.dry_run_sender("report_work");
+        match send_smart_transaction_with_sender(
             &mut rpc,
             SendSmartTransactionConfig {
                 instructions: vec![ix],
@@ -4031,6 +4950,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                 confirmation: Some(self.confirmation_config()),
                 confirmation_deadline: None,
             },
+            sender.as_deref(),
         )
         .await
         .map_err(RpcError::from)
@@ -4078,6 +4998,9 @@ impl<R: Rpc + Indexer> EpochManager<R> {
     async fn perform_rollover(&self, tree_account: &TreeAccounts) -> Result<()> {
         let mut rpc = self.rpc_pool.get_connection().await?;
         let (_, current_epoch) = self.get_current_slot_and_epoch().await?;
+        let sender = self
+            .transaction_senders
+            .for_operation(TransactionOperation::Rollover);
 
         let result = match tree_account.tree_type {
             TreeType::AddressV1 => {
@@ -4093,6 +5016,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                     &tree_account.merkle_tree,
                     &tree_account.queue,
                     current_epoch,
+                    sender.as_deref(),
                 )
                 .await?;
 
@@ -4121,6 +5045,7 @@ impl<R: Rpc + Indexer> EpochManager<R> {
                     &tree_account.queue,
                     &Pubkey::default(),
                     current_epoch,
+                    sender.as_deref(),
                 )
                 .await?;
 
@@ -4137,12 +5062,31 @@ impl<R: Rpc + Indexer> EpochManager<R> {
             _ => Err(ForesterError::InvalidTreeType(tree_account.tree_type)),
         };
 
+        let tree = tree_account.merkle_tree.to_string();
         match result {
-            Ok(_) => debug!(
-                "{:?} tree rollover completed successfully",
-                tree_account.tree_type
-            ),
-            Err(e) => warn!("{:?} tree rollover failed: {:?}", tree_account.tree_type, e),
+            Ok(_) => {
+                debug!(
+                    "{:?} tree rollover completed successfully",
+                    tree_account.tree_type
+                );
+                self.config.alert_manager.resolve(
+                    AlertRule::RolloverFailed,
+                    &tree,
+                    format!("Rollover of tree {} succeeded", tree),
+                );
+            }
+            Err(e) => {
+                warn!("{:?} tree rollover failed: {:?}", tree_account.tree_type, e);
+                self.config.alert_manager.trigger(
+                    AlertRule::RolloverFailed,
+                    &tree,
+                    AlertSeverity::Error,
+                    format!(
+                        "{:?} tree {} rollover failed: {}",
+                        tree_account.tree_type, tree, e
+                    ),
+                );
+            }
         }
         Ok(())
     }
@@ -4170,6 +5114,7 @@ fn spawn_heartbeat_task(
     heartbeat: Arc<ServiceHeartbeat>,
     slot_tracker: Arc<SlotTracker>,
     protocol_config: Arc<ProtocolConfig>,
+    shard_coordinator: Option<Arc<ShardCoordinator>>,
     run_id: String,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
@@ -4187,6 +5132,9 @@ fn spawn_heartbeat_task(
             let current = heartbeat.snapshot();
             let delta = current.delta_since(&previous);
             previous = current;
+            if let Some(shard) = &shard_coordinator {
+                shard.beat();
+            }
 
             info!(
                 event = "service_heartbeat",
@@ -4247,8 +5195,13 @@ pub async fn run_service<R: Rpc + Indexer>(
         heartbeat.clone(),
         slot_tracker.clone(),
         protocol_config.clone(),
+        config.shard_coordinator.clone(),
         run_id.clone(),
     );
+    let shard_monitor_handle = config
+        .shard_coordinator
+        .clone()
+        .map(|shard| tokio::spawn(shard.monitor_peers()));
 
     let run_id_for_logs = run_id.clone();
     let result = info_span!(
@@ -4377,6 +5330,11 @@ pub async fn run_service<R: Rpc + Indexer>(
                                                             "Processing only explicitly requested trees"
                                                         );
                                                     }
+                                                    let exclude_tree_ids =
+                                                        &config.general_config.exclude_tree_ids;
+                                                    fetched_trees.retain(|tree| {
+                                                        !exclude_tree_ids.contains(&tree.merkle_tree)
+                                                    });
                                                     break fetched_trees;
                                                 }
                                                 Err(e) => {
@@ -4564,6 +5522,9 @@ pub async fn run_service<R: Rpc + Indexer>(
         .await;
 
     heartbeat_handle.abort();
+    if let Some(handle) = shard_monitor_handle {
+        handle.abort();
+    }
     result
 }
 
@@ -4625,6 +5586,9 @@ mod tests {
                 send_tx_rate_limit: None,
                 prover_polling_interval: None,
                 prover_max_wait_time: None,
+                prover_routing: Default::default(),
+                prover_health_check_interval: None,
+                prover_max_consecutive_failures: None,
                 fallback_rpc_url: None,
                 fallback_indexer_url: None,
             },
@@ -4650,6 +5614,12 @@ mod tests {
             state_tree_data: vec![],
             compressible_config: None,
             lookup_table_address: None,
+            sender_config: Default::default(),
+            shard_coordinator: None,
+            alert_manager: Default::default(),
+            earnings: Default::default(),
+            admin: Default::default(),
+            reloadable: Default::default(),
         }
     }
 
@@ -4742,6 +5712,7 @@ mod tests {
             is_rolledover: false,
             tree_type: TreeType::AddressV1,
             owner: Default::default(),
+            network_fee: 5000,
         };
 
         let work_item = WorkItem {
@@ -4764,6 +5735,7 @@ mod tests {
             is_rolledover: false,
             tree_type: TreeType::StateV1,
             owner: Default::default(),
+            network_fee: 5000,
         };
 
         let work_item = WorkItem {
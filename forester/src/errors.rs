@@ -119,6 +119,9 @@ pub enum ConfigError {
         field: &'static str,
         invalid_values: Vec<String>,
     },
+
+    #[error("Invalid config file {path}: {error}")]
+    ConfigFile { path: String, error: String },
 }
 
 #[derive(Error, Debug)]
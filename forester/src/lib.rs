@@ -1,9 +1,13 @@
 pub type Result<T> = anyhow::Result<T>;
 
+pub mod admin;
+pub mod alerts;
 pub mod api_server;
 pub mod cli;
 pub mod compressible;
 pub mod config;
+pub mod config_file;
+pub mod earnings;
 pub mod epoch_manager;
 pub mod errors;
 pub mod forester_status;
@@ -17,8 +21,11 @@ pub mod processor;
 pub mod pubsub_client;
 pub mod queue_helpers;
 pub mod rollover;
+pub mod sender;
+pub mod shard;
 pub mod slot_tracker;
 pub mod smart_transaction;
+pub mod state_store;
 pub mod telemetry;
 pub mod transaction_timing;
 pub mod tree_data_sync;
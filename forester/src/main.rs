@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
-use clap::Parser;
 use forester::{
     api_server::{spawn_api_server, ApiServerConfig, CompressibleDashboardState},
-    cli::{Cli, Commands},
+    cli::Commands,
+    config_file,
     epoch_manager::generate_run_id,
     errors::ForesterError,
     forester_status,
@@ -14,7 +14,6 @@ use forester::{
     telemetry::setup_telemetry,
     ForesterConfig,
 };
-use forester_utils::rate_limiter::RateLimiter;
 use light_client::rpc::LightClient;
 use tokio::{
     signal::ctrl_c,
@@ -60,11 +59,14 @@ async fn main() -> Result<(), ForesterError> {
     dotenvy::dotenv().ok();
     setup_telemetry();
 
-    let cli = Cli::parse();
+    let cli = config_file::parse_cli()?;
 
     match &cli.command {
         Commands::Start(args) => {
             let config = Arc::new(ForesterConfig::new_for_start(args)?);
+            if args.config.is_some() {
+                config_file::spawn_config_watcher(config.clone());
+            }
 
             if config.general_config.enable_metrics {
                 register_metrics();
@@ -79,14 +81,9 @@ async fn main() -> Result<(), ForesterError> {
                 }
             });
 
-            let rpc_rate_limiter = config
-                .external_services
-                .rpc_rate_limit
-                .map(RateLimiter::new);
-            let send_tx_limiter = config
-                .external_services
-                .send_tx_rate_limit
-                .map(RateLimiter::new);
+            // Shared with the admin API, which can change the limits at runtime.
+            let rpc_rate_limiter = config.admin.rpc_rate_limiter();
+            let send_tx_limiter = config.admin.send_tx_rate_limiter();
 
             let mut shutdown_sender_compressible: Option<tokio::sync::broadcast::Sender<()>> = None;
             let mut shutdown_sender_bootstrap: Option<oneshot::Sender<()>> = None;
@@ -134,6 +131,11 @@ async fn main() -> Result<(), ForesterError> {
                 prometheus_url: args.prometheus_url.clone(),
                 helius_rpc: args.helius_rpc,
                 forester_api_urls: Vec::new(),
+                dry_run_recorder: config.sender_config.dry_run.clone(),
+                shard_coordinator: config.shard_coordinator.clone(),
+                earnings: Some(config.earnings.clone()),
+                admin_token: args.admin_token.clone(),
+                admin: Some(config.admin.clone()),
             })?;
 
             spawn_shutdown_handler(
@@ -187,6 +189,11 @@ async fn main() -> Result<(), ForesterError> {
                 prometheus_url: args.prometheus_url.clone(),
                 helius_rpc: false,
                 forester_api_urls: args.forester_api_urls.clone(),
+                dry_run_recorder: None,
+                shard_coordinator: None,
+                earnings: None,
+                admin_token: None,
+                admin: None,
             })?;
 
             // Block until Ctrl+C
@@ -5,7 +5,8 @@ use std::{
 
 use lazy_static::lazy_static;
 use prometheus::{
-    Encoder, GaugeVec, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Registry, TextEncoder,
+    CounterVec, Encoder, GaugeVec, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Registry,
+    TextEncoder,
 };
 use reqwest::Client;
 use tracing::{debug, error, log::trace};
@@ -152,6 +153,107 @@ define_metrics! {
         ),
         &["tree_type", "tree_pubkey", "metric"]
     );
+
+    metric("forester_prover_request_duration_seconds", ["circuit_type", "endpoint"])
+    static ref PROVER_REQUEST_DURATION: HistogramVec = HistogramVec::new(
+        prometheus::HistogramOpts::new(
+            "forester_prover_request_duration_seconds",
+            "Round-trip time of proof jobs per prover endpoint in seconds"
+        )
+        .buckets(vec![0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0]),
+        &["circuit_type", "endpoint"]
+    );
+
+    metric("forester_prover_outstanding_jobs", ["endpoint"])
+    static ref PROVER_OUTSTANDING_JOBS: IntGaugeVec = IntGaugeVec::new(
+        prometheus::opts!(
+            "forester_prover_outstanding_jobs",
+            "Number of proof jobs in flight per prover endpoint"
+        ),
+        &["endpoint"]
+    );
+
+    metric("forester_prover_healthy", ["endpoint"])
+    static ref PROVER_HEALTHY: IntGaugeVec = IntGaugeVec::new(
+        prometheus::opts!(
+            "forester_prover_healthy",
+            "Whether a prover endpoint is used for routing (1) or removed after failures (0)"
+        ),
+        &["endpoint"]
+    );
+
+    metric("forester_prover_failures_total", ["circuit_type", "endpoint"])
+    static ref PROVER_FAILURES: IntCounterVec = IntCounterVec::new(
+        prometheus::opts!(
+            "forester_prover_failures_total",
+            "Total number of failed proof jobs and health checks per prover endpoint"
+        ),
+        &["circuit_type", "endpoint"]
+    );
+
+    metric("forester_shard_live_instances", [])
+    static ref SHARD_LIVE_INSTANCES: IntGauge = IntGauge::new(
+        "forester_shard_live_instances",
+        "Number of live forester instances sharing the trees, including this one"
+    );
+
+    metric("forester_alerts_total", ["rule", "action"])
+    static ref ALERTS: IntCounterVec = IntCounterVec::new(
+        prometheus::opts!(
+            "forester_alerts_total",
+            "Total number of alert events sent, by rule and trigger or resolve"
+        ),
+        &["rule", "action"]
+    );
+
+    metric("forester_dry_run_transactions_total", ["operation", "result"])
+    static ref DRY_RUN_TRANSACTIONS: IntCounterVec = IntCounterVec::new(
+        prometheus::opts!(
+            "forester_dry_run_transactions_total",
+            "Total number of transactions simulated in dry-run mode"
+        ),
+        &["operation", "result"]
+    );
+
+    metric("forester_dry_run_compute_units", ["operation"])
+    static ref DRY_RUN_COMPUTE_UNITS: HistogramVec = HistogramVec::new(
+        prometheus::HistogramOpts::new(
+            "forester_dry_run_compute_units",
+            "Compute units consumed by transactions simulated in dry-run mode"
+        )
+        .buckets(vec![
+            10_000.0, 50_000.0, 100_000.0, 200_000.0, 400_000.0, 600_000.0, 800_000.0,
+            1_000_000.0, 1_400_000.0
+        ]),
+        &["operation"]
+    );
+
+    metric("forester_earnings_lamports_total", ["tree", "source"])
+    static ref EARNINGS: IntCounterVec = IntCounterVec::new(
+        prometheus::opts!(
+            "forester_earnings_lamports_total",
+            "Lamports earned, by tree and network fee or rent reward"
+        ),
+        &["tree", "source"]
+    );
+
+    metric("forester_fees_paid_lamports_total", ["tree", "fee"])
+    static ref FEES_PAID: IntCounterVec = IntCounterVec::new(
+        prometheus::opts!(
+            "forester_fees_paid_lamports_total",
+            "Lamports paid in transaction fees, by tree and base or priority fee"
+        ),
+        &["tree", "fee"]
+    );
+
+    metric("forester_prover_seconds_total", ["tree"])
+    static ref PROVER_SECONDS: CounterVec = CounterVec::new(
+        prometheus::opts!(
+            "forester_prover_seconds_total",
+            "Time spent generating proofs, by tree"
+        ),
+        &["tree"]
+    );
 }
 
 lazy_static! {
@@ -271,6 +373,73 @@ pub fn update_indexer_proof_count(
         .inc_by(received);
 }
 
+pub fn update_prover_request_duration(circuit_type: &str, endpoint: &str, duration_secs: f64) {
+    register_metrics();
+    PROVER_REQUEST_DURATION
+        .with_label_values(&[circuit_type, endpoint])
+        .observe(duration_secs);
+}
+
+pub fn update_prover_outstanding_jobs(endpoint: &str, outstanding: usize) {
+    register_metrics();
+    PROVER_OUTSTANDING_JOBS
+        .with_label_values(&[endpoint])
+        .set(outstanding as i64);
+}
+
+pub fn update_prover_healthy(endpoint: &str, healthy: bool) {
+    register_metrics();
+    PROVER_HEALTHY
+        .with_label_values(&[endpoint])
+        .set(healthy as i64);
+}
+
+pub fn increment_prover_failures(circuit_type: &str, endpoint: &str) {
+    register_metrics();
+    PROVER_FAILURES
+        .with_label_values(&[circuit_type, endpoint])
+        .inc();
+}
+
+pub fn update_shard_live_instances(count: usize) {
+    register_metrics();
+    SHARD_LIVE_INSTANCES.set(count as i64);
+}
+
+pub fn increment_alerts(rule: &str, action: &str) {
+    register_metrics();
+    ALERTS.with_label_values(&[rule, action]).inc();
+}
+
+pub fn increment_dry_run_transactions(operation: &str, result: &str) {
+    register_metrics();
+    DRY_RUN_TRANSACTIONS
+        .with_label_values(&[operation, result])
+        .inc();
+}
+
+pub fn update_dry_run_compute_units(operation: &str, compute_units: u64) {
+    register_metrics();
+    DRY_RUN_COMPUTE_UNITS
+        .with_label_values(&[operation])
+        .observe(compute_units as f64);
+}
+
+pub fn increment_earnings(tree: &str, source: &str, lamports: u64) {
+    register_metrics();
+    EARNINGS.with_label_values(&[tree, source]).inc_by(lamports);
+}
+
+pub fn increment_fees_paid(tree: &str, fee: &str, lamports: u64) {
+    register_metrics();
+    FEES_PAID.with_label_values(&[tree, fee]).inc_by(lamports);
+}
+
+pub fn increment_prover_seconds(tree: &str, seconds: f64) {
+    register_metrics();
+    PROVER_SECONDS.with_label_values(&[tree]).inc_by(seconds);
+}
+
 pub async fn push_metrics(url: &Option<String>) -> Result<()> {
     let url = match url {
         Some(url) => url,
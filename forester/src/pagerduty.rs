@@ -7,6 +7,8 @@ use serde::Serialize;
 struct PagerDutyPayload {
     routing_key: String,
     event_action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedup_key: Option<String>,
     payload: PagerDutyAlertPayload,
 }
 
@@ -22,12 +24,26 @@ pub async fn send_pagerduty_alert(
     summary: &str,
     severity: &str,
     source: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_pagerduty_event(routing_key, "trigger", None, summary, severity, source).await
+}
+
+/// Sends a PagerDuty event. Events with the same `dedup_key` are grouped into
+/// one incident, which an event with action `resolve` closes.
+pub async fn send_pagerduty_event(
+    routing_key: &str,
+    event_action: &str,
+    dedup_key: Option<&str>,
+    summary: &str,
+    severity: &str,
+    source: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
 
     let payload = PagerDutyPayload {
         routing_key: routing_key.to_string(),
-        event_action: "trigger".to_string(),
+        event_action: event_action.to_string(),
+        dedup_key: dedup_key.map(str::to_string),
         payload: PagerDutyAlertPayload {
             summary: summary.to_string(),
             severity: severity.to_string(),
@@ -109,4 +109,26 @@ impl ProcessedHashCache {
     pub fn cleanup_by_key(&mut self, key: &str) {
         self.entries.remove(key);
     }
+
+    /// Returns the live entries with their remaining time to live.
+    pub fn snapshot(&self) -> Vec<(String, Duration)> {
+        let now = Instant::now();
+        self.entries
+            .iter()
+            .filter_map(|(hash, entry)| {
+                let remaining = entry
+                    .timeout
+                    .checked_sub(now.duration_since(entry.timestamp))?;
+                (!remaining.is_zero()).then(|| (hash.clone(), remaining))
+            })
+            .collect()
+    }
+
+    /// Adds entries of a previous snapshot which expire after their
+    /// remaining time to live.
+    pub fn restore(&mut self, entries: impl IntoIterator<Item = (String, Duration)>) {
+        for (hash, remaining) in entries {
+            self.add_with_timeout(&hash, remaining);
+        }
+    }
 }
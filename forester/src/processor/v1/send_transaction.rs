@@ -23,12 +23,14 @@ use tracing::{debug, error, info, trace, warn};
 
 const WORK_ITEM_BATCH_SIZE: usize = 100;
 use crate::{
+    earnings::{count_registry_instructions, TreeEarningsRecorder},
     epoch_manager::WorkItem,
     errors::ForesterError,
     metrics::increment_transactions_failed,
     priority_fee::PriorityFeeConfig,
     processor::v1::{config::SendBatchedTransactionsConfig, tx_builder::TransactionBuilder},
     queue_helpers::fetch_queue_item_data,
+    sender::TransactionSender,
     smart_transaction::{ConfirmationConfig, PreparedTransaction, SmartTransactionError},
     Result,
 };
@@ -49,6 +51,8 @@ struct ChunkSendContext<R: Rpc> {
     cancel_signal: Arc<AtomicBool>,
     num_sent_transactions: Arc<AtomicUsize>,
     confirmation: ConfirmationConfig,
+    sender: Option<Arc<dyn TransactionSender>>,
+    earnings: Option<TreeEarningsRecorder>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -65,6 +69,7 @@ enum TransactionSendResult {
 /// Setting:
 /// 1. We have 1 light slot (n solana slots), and elements in queue
 /// 2. we want to send as many elements from the queue as possible
+#[allow(clippy::too_many_arguments)]
 pub async fn send_batched_transactions<T: TransactionBuilder + Send + Sync + 'static, R: Rpc>(
     payer: &Keypair,
     derivation: &Pubkey,
@@ -72,6 +77,8 @@ pub async fn send_batched_transactions<T: TransactionBuilder + Send + Sync + 'st
     config: &SendBatchedTransactionsConfig,
     tree_accounts: TreeAccounts,
     transaction_builder: Arc<T>,
+    sender: Option<Arc<dyn TransactionSender>>,
+    earnings: Option<TreeEarningsRecorder>,
 ) -> std::result::Result<usize, ForesterError> {
     let function_start_time = Instant::now();
 
@@ -194,6 +201,8 @@ pub async fn send_batched_transactions<T: TransactionBuilder + Send + Sync + 'st
                 max_attempts: config.confirmation_max_attempts as u32,
                 poll_interval: config.confirmation_poll_interval,
             },
+            sender: sender.clone(),
+            earnings: earnings.clone(),
         };
 
         if let Err(e) = execute_transaction_chunk_sending(
@@ -362,6 +371,8 @@ async fn execute_transaction_chunk_sending<R: Rpc>(
         let pool_clone = Arc::clone(&pool);
         let cancel_signal_clone = Arc::clone(&cancel_signal);
         let num_sent_transactions_clone = Arc::clone(&num_sent_transactions);
+        let sender = context.sender.clone();
+        let earnings = context.earnings.clone();
 
         async move {
             if cancel_signal_clone.load(Ordering::SeqCst) || Instant::now() >= timeout_deadline {
@@ -370,6 +381,7 @@ async fn execute_transaction_chunk_sending<R: Rpc>(
 
             let tx_signature = tx.signatures.first().copied().unwrap_or_default();
             let tx_signature_str = tx_signature.to_string();
+            let registry_instructions = count_registry_instructions(&tx);
 
             match pool_clone.get_connection().await {
                 Ok(mut rpc) => {
@@ -382,7 +394,12 @@ async fn execute_transaction_chunk_sending<R: Rpc>(
                     let prepared_transaction =
                         PreparedTransaction::legacy(tx, last_valid_block_height);
                     match prepared_transaction
-                        .send(&mut *rpc, Some(confirmation), Some(timeout_deadline))
+                        .send(
+                            &mut *rpc,
+                            Some(confirmation),
+                            Some(timeout_deadline),
+                            sender.as_deref(),
+                        )
                         .await
                     {
                         Ok(signature) => {
@@ -393,6 +410,9 @@ async fn execute_transaction_chunk_sending<R: Rpc>(
                                     elapsed = ?send_time.elapsed(),
                                     "Transaction sent and confirmed successfully"
                                 );
+                                if let Some(earnings) = &earnings {
+                                    earnings.record_transaction(signature, registry_instructions);
+                                }
                                 TransactionSendResult::Success(signature)
                             } else {
                                 trace!(tx.signature = %signature, "Transaction confirmed but run was cancelled post-send");
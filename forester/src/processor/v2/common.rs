@@ -19,11 +19,19 @@ use solana_sdk::{
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
-use super::{errors::V2Error, proof_worker::ProofJob};
+use super::{
+    errors::V2Error,
+    proof_worker::ProofJob,
+    prover_pool::{parse_prover_urls, ProverEndpointConfig, ProverPool, DEFAULT_PROVER_URL},
+};
 use crate::{
-    errors::ForesterError,
+    cli::ProverRoutingStrategy,
+    config::ExternalServicesConfig,
+    earnings::EarningsTracker,
+    errors::{ConfigError, ForesterError},
     metrics::increment_transactions_failed,
     processor::tx_cache::ProcessedHashCache,
+    sender::{TransactionOperation, TransactionSenders},
     slot_tracker::SlotTracker,
     smart_transaction::{
         send_transaction_with_policy, SendTransactionWithPolicyConfig, SmartTransactionError,
@@ -73,12 +81,45 @@ pub fn get_leaves_hashchain(
 
 #[derive(Debug, Clone)]
 pub struct ProverConfig {
-    pub append_url: String,
-    pub update_url: String,
-    pub address_append_url: String,
+    pub append_urls: Vec<ProverEndpointConfig>,
+    pub update_urls: Vec<ProverEndpointConfig>,
+    pub address_append_urls: Vec<ProverEndpointConfig>,
     pub api_key: Option<String>,
     pub polling_interval: Duration,
     pub max_wait_time: Duration,
+    pub routing: ProverRoutingStrategy,
+    pub health_check_interval: Duration,
+    /// Consecutive failures after which a prover is removed from routing.
+    pub max_consecutive_failures: u32,
+}
+
+impl ProverConfig {
+    pub fn new(config: &ExternalServicesConfig) -> Result<Self, ConfigError> {
+        let urls = |url: &Option<String>, field| match url {
+            Some(url) => parse_prover_urls(url, field),
+            None => parse_prover_urls(DEFAULT_PROVER_URL, field),
+        };
+        Ok(Self {
+            append_urls: urls(&config.prover_append_url, "prover_append_url")?,
+            update_urls: urls(&config.prover_update_url, "prover_update_url")?,
+            address_append_urls: urls(
+                &config.prover_address_append_url,
+                "prover_address_append_url",
+            )?,
+            api_key: config.prover_api_key.clone(),
+            polling_interval: config
+                .prover_polling_interval
+                .unwrap_or(Duration::from_secs(1)),
+            max_wait_time: config
+                .prover_max_wait_time
+                .unwrap_or(Duration::from_secs(600)),
+            routing: config.prover_routing,
+            health_check_interval: config
+                .prover_health_check_interval
+                .unwrap_or(Duration::from_secs(30)),
+            max_consecutive_failures: config.prover_max_consecutive_failures.unwrap_or(3),
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -90,7 +131,9 @@ pub struct BatchContext<R: Rpc> {
     pub epoch: u64,
     pub merkle_tree: Pubkey,
     pub output_queue: Pubkey,
-    pub prover_config: Arc<ProverConfig>,
+    /// Network fee of the tree's metadata.
+    pub network_fee: u64,
+    pub prover_pool: Arc<ProverPool>,
     pub ops_cache: Arc<Mutex<ProcessedHashCache>>,
     pub epoch_phases: EpochPhases,
     pub slot_tracker: Arc<SlotTracker>,
@@ -100,8 +143,11 @@ pub struct BatchContext<R: Rpc> {
     pub forester_eligibility_end_slot: Arc<AtomicU64>,
     pub address_lookup_tables: Arc<Vec<AddressLookupTableAccount>>,
     pub transaction_policy: TransactionPolicy,
+    pub transaction_senders: TransactionSenders,
     /// Maximum batches to process per tree per iteration
     pub max_batches_per_tree: usize,
+    /// None in dry-run mode, where nothing is earned or paid.
+    pub earnings: Option<Arc<EarningsTracker>>,
 }
 
 impl<R: Rpc> Clone for BatchContext<R> {
@@ -114,7 +160,8 @@ impl<R: Rpc> Clone for BatchContext<R> {
             epoch: self.epoch,
             merkle_tree: self.merkle_tree,
             output_queue: self.output_queue,
-            prover_config: self.prover_config.clone(),
+            network_fee: self.network_fee,
+            prover_pool: self.prover_pool.clone(),
             ops_cache: self.ops_cache.clone(),
             epoch_phases: self.epoch_phases.clone(),
             slot_tracker: self.slot_tracker.clone(),
@@ -123,8 +170,10 @@ impl<R: Rpc> Clone for BatchContext<R> {
             num_proof_workers: self.num_proof_workers,
             forester_eligibility_end_slot: self.forester_eligibility_end_slot.clone(),
             address_lookup_tables: self.address_lookup_tables.clone(),
-            transaction_policy: self.transaction_policy,
+            transaction_policy: self.transaction_policy.clone(),
+            transaction_senders: self.transaction_senders.clone(),
             max_batches_per_tree: self.max_batches_per_tree,
+            earnings: self.earnings.clone(),
         }
     }
 }
@@ -143,6 +192,7 @@ impl<R: Rpc> BatchContext<R> {
 
 pub(crate) async fn send_transaction_batch<R: Rpc>(
     context: &BatchContext<R>,
+    operation: TransactionOperation,
     instructions: Vec<Instruction>,
 ) -> std::result::Result<String, ForesterError> {
     let current_slot = context.slot_tracker.estimated_current_slot();
@@ -198,7 +248,10 @@ pub(crate) async fn send_transaction_batch<R: Rpc>(
                 context.output_queue,
                 context.merkle_tree,
             ],
-            policy: context.transaction_policy,
+            policy: TransactionPolicy {
+                sender: context.transaction_senders.for_operation(operation),
+                ..context.transaction_policy.clone()
+            },
             confirmation_deadline: Some(confirmation_deadline),
         },
     )
@@ -5,6 +5,7 @@ mod helpers;
 mod processor;
 pub mod proof_cache;
 mod proof_worker;
+mod prover_pool;
 mod root_guard;
 pub mod strategy;
 mod tx_sender;
@@ -12,6 +13,7 @@ mod tx_sender;
 pub use common::{BatchContext, ProverConfig};
 pub use processor::QueueProcessor;
 pub use proof_cache::{CachedProof, SharedProofCache};
+pub use prover_pool::{ProverEndpoint, ProverEndpointConfig, ProverPool};
 pub use tx_sender::{BatchInstruction, ProofTimings, TxSenderResult};
 
 use crate::epoch_manager::ProcessingMetrics;
@@ -232,6 +232,29 @@ impl ProofCache {
         self.warming_proofs.clear();
         self.is_warming = false;
     }
+
+    /// Returns the base root and the cached proofs, or `None` while the
+    /// cache is warming or empty.
+    pub fn snapshot(&self) -> Option<([u8; 32], Vec<CachedProof>)> {
+        if self.is_warming || self.proofs.is_empty() {
+            return None;
+        }
+        Some((self.base_root, self.proofs.iter().cloned().collect()))
+    }
+
+    /// Replaces the cache contents with proofs of a previous snapshot.
+    pub fn restore(&mut self, base_root: [u8; 32], proofs: Vec<CachedProof>) {
+        self.base_root = base_root;
+        self.warming_proofs.clear();
+        self.is_warming = false;
+        self.proofs = proofs.into_iter().take(self.max_proofs).collect();
+        info!(
+            "Restored {} cached proofs for tree {} with root {:?}",
+            self.proofs.len(),
+            self.tree,
+            &self.base_root[..4]
+        );
+    }
 }
 
 pub struct SharedProofCache {
@@ -291,4 +314,12 @@ impl SharedProofCache {
     pub async fn clear(&self) {
         self.inner.lock().await.clear();
     }
+
+    pub async fn snapshot(&self) -> Option<([u8; 32], Vec<CachedProof>)> {
+        self.inner.lock().await.snapshot()
+    }
+
+    pub async fn restore(&self, base_root: [u8; 32], proofs: Vec<CachedProof>) {
+        self.inner.lock().await.restore(base_root, proofs);
+    }
 }
@@ -19,7 +19,10 @@ use light_prover_client::{
 use tokio::sync::mpsc;
 use tracing::{debug, error, warn};
 
-use crate::processor::v2::{tx_sender::BatchInstruction, ProverConfig};
+use crate::processor::v2::{
+    prover_pool::{ProverLease, ProverPool},
+    tx_sender::BatchInstruction,
+};
 
 #[derive(Debug, Clone)]
 pub enum ProofInput {
@@ -125,74 +128,37 @@ pub struct ProofJobResult {
     pub(crate) submitted_at: std::time::Instant,
 }
 
-struct ProofClients {
-    append_client: ProofClient,
-    nullify_client: ProofClient,
-    address_append_client: ProofClient,
-}
-
-impl ProofClients {
-    fn new(config: &ProverConfig) -> Self {
-        Self {
-            append_client: ProofClient::with_config(
-                config.append_url.clone(),
-                config.polling_interval,
-                config.max_wait_time,
-                config.api_key.clone(),
-            ),
-            nullify_client: ProofClient::with_config(
-                config.update_url.clone(),
-                config.polling_interval,
-                config.max_wait_time,
-                config.api_key.clone(),
-            ),
-            address_append_client: ProofClient::with_config(
-                config.address_append_url.clone(),
-                config.polling_interval,
-                config.max_wait_time,
-                config.api_key.clone(),
-            ),
-        }
-    }
-
-    fn get_client(&self, input: &ProofInput) -> &ProofClient {
-        match input {
-            ProofInput::Append(_) => &self.append_client,
-            ProofInput::Nullify(_) => &self.nullify_client,
-            ProofInput::AddressAppend(_) => &self.address_append_client,
-        }
-    }
-}
-
-pub fn spawn_proof_workers(config: &ProverConfig) -> async_channel::Sender<ProofJob> {
+pub fn spawn_proof_workers(pool: &Arc<ProverPool>) -> async_channel::Sender<ProofJob> {
     let (job_tx, job_rx) = async_channel::bounded::<ProofJob>(256);
-    let clients = Arc::new(ProofClients::new(config));
-    tokio::spawn(async move { run_proof_pipeline(job_rx, clients).await });
+    let pool = pool.clone();
+    tokio::spawn(async move { run_proof_pipeline(job_rx, pool).await });
     job_tx
 }
 
 async fn run_proof_pipeline(
     job_rx: Receiver<ProofJob>,
-    clients: Arc<ProofClients>,
+    pool: Arc<ProverPool>,
 ) -> crate::Result<()> {
     let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_PROOFS));
 
     while let Ok(job) = job_rx.recv().await {
-        let clients = clients.clone();
+        let pool = pool.clone();
         let permit = semaphore.clone().acquire_owned().await;
         // Spawn immediately so we don't block receiving the next job
         // while waiting for HTTP submission. Semaphore bounds concurrency.
         tokio::spawn(async move {
             let _permit = permit;
-            submit_and_poll_proof(clients, job).await;
+            submit_and_poll_proof(pool, job).await;
         });
     }
 
     Ok(())
 }
 
-async fn submit_and_poll_proof(clients: Arc<ProofClients>, job: ProofJob) {
-    let client = clients.get_client(&job.inputs);
+async fn submit_and_poll_proof(pool: Arc<ProverPool>, job: ProofJob) {
+    // The job is polled on the endpoint it was submitted to.
+    let lease = pool.acquire(&job.inputs);
+    let client = lease.client();
     // Use seq as batch_index for ordering in the prover queue
     let inputs_json = job.inputs.to_json(&job.tree_id, job.seq);
     let circuit_type = job.inputs.circuit_type();
@@ -202,12 +168,15 @@ async fn submit_and_poll_proof(clients: Arc<ProofClients>, job: ProofJob) {
     match client.submit_proof_async(inputs_json, circuit_type).await {
         Ok(SubmitProofResult::Queued(job_id)) => {
             debug!(
-                "Submitted proof job seq={} type={} job_id={}",
-                job.seq, circuit_type, job_id
+                "Submitted proof job seq={} type={} job_id={} endpoint={}",
+                job.seq,
+                circuit_type,
+                job_id,
+                lease.endpoint()
             );
 
             poll_and_send_result(
-                clients,
+                &lease,
                 job_id,
                 job.seq,
                 job.inputs,
@@ -223,6 +192,7 @@ async fn submit_and_poll_proof(clients: Arc<ProofClients>, job: ProofJob) {
                 "Got immediate proof for seq={} type={} round_trip={}ms",
                 job.seq, circuit_type, round_trip_ms
             );
+            lease.record_success();
 
             let result =
                 build_proof_result(job.seq, &job.inputs, proof, round_trip_ms, round_trip_start);
@@ -230,9 +200,13 @@ async fn submit_and_poll_proof(clients: Arc<ProofClients>, job: ProofJob) {
         }
         Err(e) => {
             error!(
-                "Failed to submit proof job seq={} type={}: {}",
-                job.seq, circuit_type, e
+                "Failed to submit proof job seq={} type={} endpoint={}: {}",
+                job.seq,
+                circuit_type,
+                lease.endpoint(),
+                e
             );
+            lease.record_failure();
 
             let result = ProofJobResult {
                 seq: job.seq,
@@ -249,7 +223,7 @@ async fn submit_and_poll_proof(clients: Arc<ProofClients>, job: ProofJob) {
 }
 
 async fn poll_and_send_result(
-    clients: Arc<ProofClients>,
+    lease: &ProverLease,
     job_id: String,
     seq: u64,
     inputs: ProofInput,
@@ -257,7 +231,7 @@ async fn poll_and_send_result(
     result_tx: mpsc::Sender<ProofJobResult>,
     round_trip_start: std::time::Instant,
 ) {
-    let client = clients.get_client(&inputs);
+    let client = lease.client();
 
     // Poll; on job_not_found, resubmit once and poll the new job.
     let result = match client.poll_proof_completion(job_id.clone()).await {
@@ -267,6 +241,7 @@ async fn poll_and_send_result(
                 "Proof completed for seq={} job_id={} round_trip={}ms proof={}ms",
                 seq, job_id, round_trip_ms, proof.proof_duration_ms
             );
+            lease.record_success();
             build_proof_result(seq, &inputs, proof, round_trip_ms, round_trip_start)
         }
         Err(e) if is_job_not_found(&e) => {
@@ -291,6 +266,7 @@ async fn poll_and_send_result(
                                 "Proof completed after retry for seq={} job_id={} round_trip={}ms",
                                 seq, new_job_id, round_trip_ms
                             );
+                            lease.record_success();
                             build_proof_result(seq, &inputs, proof, round_trip_ms, round_trip_start)
                         }
                         Err(e2) => ProofJobResult {
@@ -313,17 +289,21 @@ async fn poll_and_send_result(
                         "Immediate proof after retry for seq={} type={} round_trip={}ms",
                         seq, circuit_type, round_trip_ms
                     );
+                    lease.record_success();
                     build_proof_result(seq, &inputs, proof, round_trip_ms, round_trip_start)
                 }
-                Err(e_submit) => ProofJobResult {
-                    seq,
-                    result: Err(format!("Proof retry submit failed: {}", e_submit)),
-                    old_root: [0u8; 32],
-                    new_root: [0u8; 32],
-                    proof_duration_ms: 0,
-                    round_trip_ms: 0,
-                    submitted_at: round_trip_start,
-                },
+                Err(e_submit) => {
+                    lease.record_failure();
+                    ProofJobResult {
+                        seq,
+                        result: Err(format!("Proof retry submit failed: {}", e_submit)),
+                        old_root: [0u8; 32],
+                        new_root: [0u8; 32],
+                        proof_duration_ms: 0,
+                        round_trip_ms: 0,
+                        submitted_at: round_trip_start,
+                    }
+                }
             }
         }
         Err(e) => {
@@ -0,0 +1,522 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use light_prover_client::proof_client::ProofClient;
+use tracing::{debug, info, warn};
+
+use super::{proof_worker::ProofInput, ProverConfig};
+use crate::{
+    alerts::{AlertManager, AlertRule, AlertSeverity},
+    cli::ProverRoutingStrategy,
+    errors::ConfigError,
+    metrics::{
+        increment_prover_failures, update_prover_healthy, update_prover_outstanding_jobs,
+        update_prover_request_duration,
+    },
+};
+
+pub const DEFAULT_PROVER_URL: &str = "http://127.0.0.1:3001";
+const HEALTH_CHECK_PATH: &str = "/health";
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A prover endpoint and its share of the jobs of a circuit type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProverEndpointConfig {
+    pub url: String,
+    pub weight: u32,
+}
+
+/// Parses a comma-separated list of prover URLs. Each URL can be suffixed
+/// with `#weight=N` to receive a larger share of the jobs; the fragment is
+/// stripped before the URL is used.
+pub fn parse_prover_urls(
+    spec: &str,
+    field: &'static str,
+) -> Result<Vec<ProverEndpointConfig>, ConfigError> {
+    let mut endpoints = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (url, weight) = match entry.split_once('#') {
+            Some((url, fragment)) => {
+                let weight = fragment
+                    .strip_prefix("weight=")
+                    .and_then(|w| w.parse::<u32>().ok())
+                    .filter(|w| *w > 0)
+                    .ok_or_else(|| ConfigError::InvalidArguments {
+                        field,
+                        invalid_values: vec![entry.to_string()],
+                    })?;
+                (url.trim_end_matches('/'), weight)
+            }
+            None => (entry.trim_end_matches('/'), 1),
+        };
+        endpoints.push(ProverEndpointConfig {
+            url: url.to_string(),
+            weight,
+        });
+    }
+    if endpoints.is_empty() {
+        return Err(ConfigError::InvalidArguments {
+            field,
+            invalid_values: vec![spec.to_string()],
+        });
+    }
+    Ok(endpoints)
+}
+
+/// Prover URL without query parameters, which may carry credentials.
+fn endpoint_label(url: &str) -> String {
+    url.split('?').next().unwrap_or(url).to_string()
+}
+
+/// A prover server. Endpoints are shared by all circuit types routed to the
+/// same URL, so outstanding jobs and failures are tracked per server.
+pub struct ProverEndpoint {
+    url: String,
+    label: String,
+    client: ProofClient,
+    outstanding: AtomicUsize,
+    consecutive_failures: AtomicU32,
+    healthy: AtomicBool,
+}
+
+impl std::fmt::Debug for ProverEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProverEndpoint")
+            .field("endpoint", &self.label)
+            .field("outstanding", &self.outstanding())
+            .field("healthy", &self.is_healthy())
+            .finish()
+    }
+}
+
+impl ProverEndpoint {
+    fn new(url: String, config: &ProverConfig) -> Self {
+        let label = endpoint_label(&url);
+        update_prover_healthy(&label, true);
+        update_prover_outstanding_jobs(&label, 0);
+        Self {
+            client: ProofClient::with_config(
+                url.clone(),
+                config.polling_interval,
+                config.max_wait_time,
+                config.api_key.clone(),
+            ),
+            url,
+            label,
+            outstanding: AtomicUsize::new(0),
+            consecutive_failures: AtomicU32::new(0),
+            healthy: AtomicBool::new(true),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.load(Ordering::Relaxed)
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    fn record_success(&self, alerts: &AlertManager) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        if !self.healthy.swap(true, Ordering::Relaxed) {
+            info!(
+                event = "prover_endpoint_restored",
+                endpoint = %self.label,
+                "Prover endpoint is healthy again, resuming routing"
+            );
+            update_prover_healthy(&self.label, true);
+            alerts.resolve(
+                AlertRule::ProverUnhealthy,
+                &self.label,
+                format!("Prover {} is healthy again", self.label),
+            );
+        }
+    }
+
+    fn record_failure(&self, max_consecutive_failures: u32, alerts: &AlertManager) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= max_consecutive_failures && self.healthy.swap(false, Ordering::Relaxed) {
+            warn!(
+                event = "prover_endpoint_removed",
+                endpoint = %self.label,
+                failures,
+                "Removing prover endpoint from routing after consecutive failures"
+            );
+            update_prover_healthy(&self.label, false);
+            alerts.trigger(
+                AlertRule::ProverUnhealthy,
+                &self.label,
+                AlertSeverity::Error,
+                format!(
+                    "Prover {} removed from routing after {} consecutive failures",
+                    self.label, failures
+                ),
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CircuitRoutes {
+    circuit_type: &'static str,
+    endpoints: Vec<(Arc<ProverEndpoint>, u32)>,
+    next: AtomicUsize,
+}
+
+impl CircuitRoutes {
+    fn new(
+        circuit_type: &'static str,
+        configs: &[ProverEndpointConfig],
+        endpoints: &mut Vec<Arc<ProverEndpoint>>,
+        config: &ProverConfig,
+    ) -> Self {
+        let routes = configs
+            .iter()
+            .map(|endpoint_config| {
+                let endpoint = match endpoints.iter().find(|e| e.url == endpoint_config.url) {
+                    Some(endpoint) => endpoint.clone(),
+                    None => {
+                        let endpoint =
+                            Arc::new(ProverEndpoint::new(endpoint_config.url.clone(), config));
+                        endpoints.push(endpoint.clone());
+                        endpoint
+                    }
+                };
+                (endpoint, endpoint_config.weight)
+            })
+            .collect();
+        Self {
+            circuit_type,
+            endpoints: routes,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn select(&self, routing: ProverRoutingStrategy) -> Arc<ProverEndpoint> {
+        let healthy: Vec<&(Arc<ProverEndpoint>, u32)> = self
+            .endpoints
+            .iter()
+            .filter(|(endpoint, _)| endpoint.is_healthy())
+            .collect();
+        // Keep sending to all endpoints rather than failing every job if
+        // none of them passes health checks.
+        let candidates = if healthy.is_empty() {
+            warn!(
+                "No healthy prover for circuit type {}, routing across all {} endpoints",
+                self.circuit_type,
+                self.endpoints.len()
+            );
+            self.endpoints.iter().collect()
+        } else {
+            healthy
+        };
+        let n = self.next.fetch_add(1, Ordering::Relaxed);
+
+        match routing {
+            ProverRoutingStrategy::WeightedRoundRobin => {
+                let total_weight: u64 = candidates.iter().map(|(_, w)| *w as u64).sum();
+                let mut slot = n as u64 % total_weight.max(1);
+                for (endpoint, weight) in &candidates {
+                    if slot < *weight as u64 {
+                        return endpoint.clone();
+                    }
+                    slot -= *weight as u64;
+                }
+                candidates[0].0.clone()
+            }
+            ProverRoutingStrategy::LeastOutstanding => {
+                // Start at a rotating offset so that ties are spread evenly.
+                let offset = n % candidates.len();
+                candidates
+                    .iter()
+                    .cycle()
+                    .skip(offset)
+                    .take(candidates.len())
+                    .min_by(|(a, wa), (b, wb)| {
+                        // Compare outstanding / weight without division.
+                        (a.outstanding() as u64 * *wb as u64)
+                            .cmp(&(b.outstanding() as u64 * *wa as u64))
+                    })
+                    .map(|(endpoint, _)| endpoint.clone())
+                    .unwrap_or_else(|| candidates[0].0.clone())
+            }
+        }
+    }
+}
+
+/// Routes proof jobs across the provers configured for each circuit type.
+#[derive(Debug)]
+pub struct ProverPool {
+    append: CircuitRoutes,
+    update: CircuitRoutes,
+    address_append: CircuitRoutes,
+    endpoints: Vec<Arc<ProverEndpoint>>,
+    routing: ProverRoutingStrategy,
+    health_check_interval: Duration,
+    max_consecutive_failures: u32,
+    http_client: reqwest::Client,
+    alerts: Arc<AlertManager>,
+}
+
+impl ProverPool {
+    pub fn new(config: &ProverConfig, alerts: Arc<AlertManager>) -> Self {
+        let mut endpoints = Vec::new();
+        let append = CircuitRoutes::new("append", &config.append_urls, &mut endpoints, config);
+        let update = CircuitRoutes::new("update", &config.update_urls, &mut endpoints, config);
+        let address_append = CircuitRoutes::new(
+            "address_append",
+            &config.address_append_urls,
+            &mut endpoints,
+            config,
+        );
+        let http_client = reqwest::Client::builder()
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            append,
+            update,
+            address_append,
+            endpoints,
+            routing: config.routing,
+            health_check_interval: config.health_check_interval,
+            max_consecutive_failures: config.max_consecutive_failures.max(1),
+            http_client,
+            alerts,
+        }
+    }
+
+    pub fn endpoints(&self) -> &[Arc<ProverEndpoint>] {
+        &self.endpoints
+    }
+
+    fn routes(&self, input: &ProofInput) -> &CircuitRoutes {
+        match input {
+            ProofInput::Append(_) => &self.append,
+            ProofInput::Nullify(_) => &self.update,
+            ProofInput::AddressAppend(_) => &self.address_append,
+        }
+    }
+
+    /// Selects the endpoint for a proof job. The job counts as outstanding
+    /// on the endpoint until the returned lease is dropped.
+    pub(crate) fn acquire(self: &Arc<Self>, input: &ProofInput) -> ProverLease {
+        self.lease(self.routes(input))
+    }
+
+    fn lease(self: &Arc<Self>, routes: &CircuitRoutes) -> ProverLease {
+        let endpoint = routes.select(self.routing);
+        let outstanding = endpoint.outstanding.fetch_add(1, Ordering::Relaxed) + 1;
+        update_prover_outstanding_jobs(&endpoint.label, outstanding);
+        debug!(
+            "Routing {} proof job to {} ({} outstanding)",
+            routes.circuit_type, endpoint.label, outstanding
+        );
+        ProverLease {
+            pool: self.clone(),
+            endpoint,
+            circuit_type: routes.circuit_type,
+            started_at: Instant::now(),
+        }
+    }
+
+    async fn check_endpoint(&self, endpoint: &ProverEndpoint) -> bool {
+        match self
+            .http_client
+            .get(format!("{}{}", endpoint.url, HEALTH_CHECK_PATH))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => true,
+            Ok(response) => {
+                debug!(
+                    "Prover health check of {} returned {}",
+                    endpoint.label,
+                    response.status()
+                );
+                false
+            }
+            Err(e) => {
+                debug!("Prover health check of {} failed: {}", endpoint.label, e);
+                false
+            }
+        }
+    }
+
+    /// Checks the health endpoint of every prover. Removed provers are
+    /// routed to again once they pass a check.
+    pub async fn check_health(&self) {
+        for endpoint in &self.endpoints {
+            if self.check_endpoint(endpoint).await {
+                endpoint.record_success(&self.alerts);
+            } else {
+                increment_prover_failures("health", &endpoint.label);
+                endpoint.record_failure(self.max_consecutive_failures, &self.alerts);
+            }
+        }
+    }
+
+    pub async fn monitor_health(self: Arc<Self>) -> crate::Result<()> {
+        let mut interval =
+            tokio::time::interval(self.health_check_interval.max(Duration::from_secs(1)));
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+}
+
+/// An in-flight proof job on an endpoint.
+pub(crate) struct ProverLease {
+    pool: Arc<ProverPool>,
+    endpoint: Arc<ProverEndpoint>,
+    circuit_type: &'static str,
+    started_at: Instant,
+}
+
+impl ProverLease {
+    pub(crate) fn client(&self) -> &ProofClient {
+        &self.endpoint.client
+    }
+
+    pub(crate) fn endpoint(&self) -> &str {
+        &self.endpoint.label
+    }
+
+    pub(crate) fn record_success(&self) {
+        update_prover_request_duration(
+            self.circuit_type,
+            &self.endpoint.label,
+            self.started_at.elapsed().as_secs_f64(),
+        );
+        self.endpoint.record_success(&self.pool.alerts);
+    }
+
+    /// Records a failure of the endpoint itself, e.g. a rejected submission.
+    /// Failed proofs are not counted, since they would fail on any prover.
+    pub(crate) fn record_failure(&self) {
+        increment_prover_failures(self.circuit_type, &self.endpoint.label);
+        self.endpoint
+            .record_failure(self.pool.max_consecutive_failures, &self.pool.alerts);
+    }
+}
+
+impl Drop for ProverLease {
+    fn drop(&mut self) {
+        let outstanding = self.endpoint.outstanding.fetch_sub(1, Ordering::Relaxed) - 1;
+        update_prover_outstanding_jobs(&self.endpoint.label, outstanding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(routing: ProverRoutingStrategy, append_urls: &str) -> Arc<ProverPool> {
+        let append_urls = parse_prover_urls(append_urls, "prover_append_url").unwrap();
+        Arc::new(ProverPool::new(
+            &ProverConfig {
+                update_urls: append_urls.clone(),
+                address_append_urls: vec![ProverEndpointConfig {
+                    url: "http://c:3001".to_string(),
+                    weight: 1,
+                }],
+                append_urls,
+                api_key: None,
+                polling_interval: Duration::from_secs(1),
+                max_wait_time: Duration::from_secs(600),
+                routing,
+                health_check_interval: Duration::from_secs(30),
+                max_consecutive_failures: 2,
+            },
+            Default::default(),
+        ))
+    }
+
+    #[test]
+    fn test_parse_prover_urls() {
+        let endpoints =
+            parse_prover_urls("http://a:3001/, http://b:3001?key=x#weight=3", "prover_url")
+                .unwrap();
+        assert_eq!(
+            endpoints,
+            vec![
+                ProverEndpointConfig {
+                    url: "http://a:3001".to_string(),
+                    weight: 1
+                },
+                ProverEndpointConfig {
+                    url: "http://b:3001?key=x".to_string(),
+                    weight: 3
+                },
+            ]
+        );
+        assert_eq!(endpoint_label(&endpoints[1].url), "http://b:3001");
+        assert!(parse_prover_urls("http://a:3001#weight=0", "prover_url").is_err());
+        assert!(parse_prover_urls("http://a:3001#w=2", "prover_url").is_err());
+        assert!(parse_prover_urls(" , ", "prover_url").is_err());
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let pool = pool(
+            ProverRoutingStrategy::WeightedRoundRobin,
+            "http://a:3001#weight=3,http://b:3001",
+        );
+        // Endpoints shared between circuit types are created once.
+        assert_eq!(pool.endpoints().len(), 3);
+
+        let selected: Vec<String> = (0..8)
+            .map(|_| pool.lease(&pool.append).endpoint().to_string())
+            .collect();
+        assert_eq!(selected.iter().filter(|e| *e == "http://a:3001").count(), 6);
+        assert_eq!(selected.iter().filter(|e| *e == "http://b:3001").count(), 2);
+    }
+
+    #[test]
+    fn test_least_outstanding_and_failover() {
+        let pool = pool(
+            ProverRoutingStrategy::LeastOutstanding,
+            "http://a:3001,http://b:3001",
+        );
+        let first = pool.lease(&pool.append);
+        let second = pool.lease(&pool.append);
+        assert_ne!(first.endpoint(), second.endpoint());
+        assert_eq!(pool.endpoints()[0].outstanding(), 1);
+        drop(second);
+        assert_eq!(pool.endpoints()[1].outstanding(), 0);
+
+        // b is removed after two consecutive failures, so jobs go to a
+        // although it has more outstanding jobs.
+        let lease = pool.lease(&pool.append);
+        assert_eq!(lease.endpoint(), "http://b:3001");
+        lease.record_failure();
+        lease.record_failure();
+        drop(lease);
+        assert!(!pool.endpoints()[1].is_healthy());
+        assert!(pool
+            .alerts
+            .is_active(AlertRule::ProverUnhealthy, "http://b:3001"));
+        for _ in 0..4 {
+            assert_eq!(pool.lease(&pool.append).endpoint(), "http://a:3001");
+        }
+
+        // A success restores the endpoint.
+        pool.endpoints()[1].record_success(&pool.alerts);
+        assert!(!pool
+            .alerts
+            .is_active(AlertRule::ProverUnhealthy, "http://b:3001"));
+        assert_eq!(pool.lease(&pool.append).endpoint(), "http://b:3001");
+        drop(first);
+    }
+}
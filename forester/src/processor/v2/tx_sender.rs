@@ -1,6 +1,6 @@
 use std::{sync::Arc, time::Duration};
 
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 
 const MAX_BUFFER_SIZE: usize = 1000;
 const V2_IXS_PER_TX_WITH_LUT: usize = 5;
@@ -20,11 +20,13 @@ use tokio::{sync::mpsc, task::JoinHandle};
 use tracing::{debug, info, warn};
 
 use crate::{
+    earnings::EarningsOperation,
     errors::ForesterError,
     processor::v2::{
         common::send_transaction_batch, proof_cache::SharedProofCache,
         proof_worker::ProofJobResult, BatchContext,
     },
+    sender::TransactionOperation,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -72,7 +74,7 @@ pub struct TxSenderResult {
 type TxSenderTaskResult = std::result::Result<TxSenderResult, ForesterError>;
 pub(crate) type TxSenderTask = JoinHandle<TxSenderTaskResult>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub enum BatchInstruction {
     Append(Vec<InstructionDataBatchAppendInputs>),
     Nullify(Vec<InstructionDataBatchNullifyInputs>),
@@ -252,11 +254,14 @@ impl<R: Rpc> TxSender<R> {
                 let mut last_root: Option<[u8; 32]> = None;
                 let mut append_count = 0usize;
                 let mut nullify_count = 0usize;
+                let mut earning_instructions = Vec::with_capacity(batch.len());
 
                 for (instr, _seq, _, _) in &batch {
                     let res = match instr {
                         BatchInstruction::Append(proofs) => {
                             append_count += 1;
+                            earning_instructions
+                                .push((EarningsOperation::BatchAppend, proofs.len()));
                             let ix_res = proofs
                                 .iter()
                                 .map(|data| {
@@ -274,6 +279,8 @@ impl<R: Rpc> TxSender<R> {
                         }
                         BatchInstruction::Nullify(proofs) => {
                             nullify_count += 1;
+                            earning_instructions
+                                .push((EarningsOperation::BatchNullify, proofs.len()));
                             let ix_res = proofs
                                 .iter()
                                 .map(|data| {
@@ -289,6 +296,8 @@ impl<R: Rpc> TxSender<R> {
                             (ix_res, proofs.last().map(|p| p.new_root))
                         }
                         BatchInstruction::AddressAppend(proofs) => {
+                            earning_instructions
+                                .push((EarningsOperation::BatchAddressAppend, proofs.len()));
                             let ix_res = proofs
                                 .iter()
                                 .map(|data| {
@@ -320,10 +329,44 @@ impl<R: Rpc> TxSender<R> {
                     "AddressAppend".to_string()
                 };
 
+                // Mixed batches are routed like appends.
+                let operation = if append_count > 0 {
+                    TransactionOperation::BatchAppend
+                } else if nullify_count > 0 {
+                    TransactionOperation::BatchNullify
+                } else {
+                    TransactionOperation::BatchAddressAppend
+                };
+
                 let send_start = std::time::Instant::now();
-                match send_transaction_batch(&sender_context, all_instructions).await {
+                match send_transaction_batch(&sender_context, operation, all_instructions).await {
                     Ok(sig) => {
                         total_tx_sending_duration += send_start.elapsed();
+                        if let (Some(earnings), Ok(signature)) =
+                            (&sender_context.earnings, sig.parse())
+                        {
+                            earnings.record_transaction(
+                                sender_context.epoch,
+                                &sender_context.merkle_tree.to_string(),
+                                sender_context.network_fee,
+                                signature,
+                                &earning_instructions,
+                            );
+                        }
+                        if let (Some(recorder), Some((_, _, old_root, _)), Some(new_root)) = (
+                            sender_context.transaction_senders.dry_run_recorder(),
+                            batch.first(),
+                            last_root,
+                        ) {
+                            if let Ok(signature) = sig.parse() {
+                                recorder.record_root_transition(
+                                    &signature,
+                                    &sender_context.merkle_tree,
+                                    *old_root,
+                                    new_root,
+                                );
+                            }
+                        }
                         if let Some(root) = last_root {
                             sender_last_root = root;
                         }
@@ -423,6 +466,13 @@ impl<R: Rpc> TxSender<R> {
                         self.proof_timings.address_append_round_trip_ms += result.round_trip_ms;
                     }
                 }
+                if let Some(earnings) = &self.context.earnings {
+                    earnings.record_prover_time(
+                        self.context.epoch,
+                        &self.context.merkle_tree.to_string(),
+                        Duration::from_millis(result.proof_duration_ms),
+                    );
+                }
             }
 
             let instruction = match result.result {
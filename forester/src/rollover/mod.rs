@@ -5,8 +5,9 @@ use std::sync::Arc;
 use forester_utils::forester_epoch::TreeAccounts;
 use light_client::rpc::Rpc;
 pub use operations::{
-    get_tree_fullness, is_tree_ready_for_rollover, perform_address_merkle_tree_rollover,
-    perform_state_merkle_tree_rollover_forester,
+    get_tree_fullness, is_tree_ready_for_close, is_tree_ready_for_rollover,
+    perform_address_merkle_tree_rollover, perform_state_merkle_tree_rollover_forester,
+    perform_tree_close,
 };
 use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 pub use state::RolloverState;
@@ -35,6 +36,7 @@ pub async fn rollover_state_merkle_tree<R: Rpc>(
         &tree_accounts.queue,
         &Pubkey::default(),
         epoch,
+        None,
     )
     .await?;
 
@@ -60,6 +62,7 @@ pub async fn rollover_address_merkle_tree<R: Rpc>(
         &tree_accounts.merkle_tree,
         &tree_accounts.queue,
         epoch,
+        None,
     )
     .await?;
 
@@ -5,22 +5,28 @@ use account_compression::{
 };
 use anyhow::Context;
 use forester_utils::{
-    account_zero_copy::{get_concurrent_merkle_tree, get_indexed_merkle_tree},
+    account_zero_copy::{get_concurrent_merkle_tree, get_hash_set, get_indexed_merkle_tree},
     address_merkle_tree_config::{get_address_bundle_config, get_state_bundle_config},
+    forester_epoch::TreeAccounts,
     instructions::create_account::create_account_instruction,
     registry::RentExemption,
 };
-use light_batched_merkle_tree::merkle_tree::BatchedMerkleTreeAccount;
+use light_batched_merkle_tree::{
+    merkle_tree::BatchedMerkleTreeAccount, queue::BatchedQueueAccount,
+};
 use light_client::{
     indexer::{AddressMerkleTreeAccounts, StateMerkleTreeAccounts},
     rpc::Rpc,
 };
 use light_compressed_account::TreeType;
-use light_hasher::Poseidon;
+use light_hasher::{zero_bytes::poseidon::ZERO_BYTES, Poseidon};
 use light_registry::{
     account_compression_cpi::sdk::{
+        create_close_address_merkle_tree_instruction, create_close_batch_address_tree_instruction,
+        create_close_batch_state_tree_instruction, create_close_state_merkle_tree_instruction,
         create_rollover_address_merkle_tree_instruction,
-        create_rollover_state_merkle_tree_instruction, CreateRolloverMerkleTreeInstructionInputs,
+        create_rollover_state_merkle_tree_instruction, CreateCloseMerkleTreeInstructionInputs,
+        CreateRolloverMerkleTreeInstructionInputs,
     },
     protocol_config::state::ProtocolConfig,
 };
@@ -30,7 +36,13 @@ use solana_sdk::{
 };
 use tracing::{trace, warn};
 
-use crate::errors::ForesterError;
+use crate::{
+    errors::ForesterError,
+    sender::TransactionSender,
+    smart_transaction::{
+        send_smart_transaction_with_sender, ConfirmationConfig, SendSmartTransactionConfig,
+    },
+};
 
 enum TreeAccount {
     State(StateMerkleTreeAccount),
@@ -238,6 +250,169 @@ pub async fn is_tree_ready_for_rollover<R: Rpc>(
     }
 }
 
+/// Returns whether a rolled over tree can be closed in `current_slot`:
+/// its close threshold has passed and no work is left in its queues.
+pub async fn is_tree_ready_for_close<R: Rpc>(
+    rpc: &mut R,
+    tree_pubkey: Pubkey,
+    tree_type: TreeType,
+    current_slot: u64,
+) -> Result<bool, ForesterError> {
+    trace!(
+        "Checking if tree is ready for close: {:?}",
+        tree_pubkey.to_string()
+    );
+
+    match tree_type {
+        TreeType::StateV1 => {
+            let account = rpc
+                .get_anchor_account::<StateMerkleTreeAccount>(&tree_pubkey)
+                .await?
+                .ok_or_else(|| {
+                    ForesterError::Other(anyhow::anyhow!(
+                        "StateV1 merkle tree account not found: {}",
+                        tree_pubkey
+                    ))
+                })?;
+            if account
+                .metadata
+                .rollover_metadata
+                .check_closable(current_slot)
+                .is_err()
+            {
+                return Ok(false);
+            }
+            let merkle_tree =
+                get_concurrent_merkle_tree::<StateMerkleTreeAccount, R, Poseidon, 26>(
+                    rpc,
+                    tree_pubkey,
+                )
+                .await
+                .map_err(|e| ForesterError::Other(anyhow::anyhow!("{}", e)))?;
+            // Every leaf is nullified once the root is the root of an empty tree.
+            Ok(merkle_tree.root() == ZERO_BYTES[merkle_tree.height])
+        }
+        TreeType::AddressV1 => {
+            let account = rpc
+                .get_anchor_account::<AddressMerkleTreeAccount>(&tree_pubkey)
+                .await?
+                .ok_or_else(|| {
+                    ForesterError::Other(anyhow::anyhow!(
+                        "AddressV1 merkle tree account not found: {}",
+                        tree_pubkey
+                    ))
+                })?;
+            if account
+                .metadata
+                .rollover_metadata
+                .check_closable(current_slot)
+                .is_err()
+            {
+                return Ok(false);
+            }
+            let queue_pubkey = account.metadata.associated_queue.into();
+            let queue = unsafe { get_hash_set::<QueueAccount, R>(rpc, queue_pubkey) }
+                .await
+                .map_err(|e| ForesterError::Other(anyhow::anyhow!("{}", e)))?;
+            let has_pending_addresses = queue
+                .first_no_seq()
+                .map_err(|e| ForesterError::Other(anyhow::anyhow!("{:?}", e)))?
+                .is_some();
+            Ok(!has_pending_addresses)
+        }
+        TreeType::StateV2 => {
+            let mut account = rpc.get_account(tree_pubkey).await?.ok_or_else(|| {
+                anyhow::anyhow!("StateV2 tree account not found: {}", tree_pubkey)
+            })?;
+            let merkle_tree =
+                BatchedMerkleTreeAccount::state_from_bytes(&mut account.data, &tree_pubkey.into())
+                    .map_err(|e| anyhow::anyhow!("Failed to parse StateV2 tree: {:?}", e))?;
+            if merkle_tree
+                .metadata
+                .rollover_metadata
+                .check_closable(current_slot)
+                .is_err()
+                || merkle_tree.queue_batches.has_ready_zkp_batches()
+            {
+                return Ok(false);
+            }
+            let queue_pubkey: Pubkey = merkle_tree.metadata.associated_queue.into();
+            let mut queue_account = rpc.get_account(queue_pubkey).await?.ok_or_else(|| {
+                anyhow::anyhow!("StateV2 output queue account not found: {}", queue_pubkey)
+            })?;
+            let output_queue = BatchedQueueAccount::output_from_bytes(&mut queue_account.data)
+                .map_err(|e| anyhow::anyhow!("Failed to parse StateV2 output queue: {:?}", e))?;
+            Ok(!output_queue.batch_metadata.has_ready_zkp_batches()
+                && merkle_tree.all_leaves_nullified(&output_queue))
+        }
+        TreeType::AddressV2 => {
+            let mut account = rpc.get_account(tree_pubkey).await?.ok_or_else(|| {
+                anyhow::anyhow!("AddressV2 tree account not found: {}", tree_pubkey)
+            })?;
+            let merkle_tree = BatchedMerkleTreeAccount::address_from_bytes(
+                &mut account.data,
+                &tree_pubkey.into(),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to parse AddressV2 tree: {:?}", e))?;
+            Ok(merkle_tree
+                .metadata
+                .rollover_metadata
+                .check_closable(current_slot)
+                .is_ok()
+                && !merkle_tree.queue_batches.has_ready_zkp_batches())
+        }
+        TreeType::Unknown => Err(ForesterError::InvalidTreeType(tree_type)),
+    }
+}
+
+/// Closes a rolled over tree and its queues.
+/// The rent of the closed accounts is returned to `recipient`,
+/// which has to be the protocol authority for trees serviced by light foresters.
+pub async fn perform_tree_close<R: Rpc>(
+    payer: &Keypair,
+    derivation: &Pubkey,
+    context: &mut R,
+    tree_accounts: &TreeAccounts,
+    recipient: Pubkey,
+    epoch: u64,
+    sender: Option<&dyn TransactionSender>,
+) -> Result<solana_sdk::signature::Signature, ForesterError> {
+    let instruction = match tree_accounts.tree_type {
+        TreeType::StateV1 | TreeType::AddressV1 => {
+            let inputs = CreateCloseMerkleTreeInstructionInputs {
+                authority: payer.pubkey(),
+                derivation: *derivation,
+                merkle_tree: tree_accounts.merkle_tree,
+                queue: tree_accounts.queue,
+                recipient,
+                is_metadata_forester: false,
+            };
+            if tree_accounts.tree_type == TreeType::StateV1 {
+                create_close_state_merkle_tree_instruction(inputs, epoch)
+            } else {
+                create_close_address_merkle_tree_instruction(inputs, epoch)
+            }
+        }
+        TreeType::StateV2 => create_close_batch_state_tree_instruction(
+            payer.pubkey(),
+            *derivation,
+            tree_accounts.merkle_tree,
+            tree_accounts.queue,
+            recipient,
+            epoch,
+        ),
+        TreeType::AddressV2 => create_close_batch_address_tree_instruction(
+            payer.pubkey(),
+            *derivation,
+            tree_accounts.merkle_tree,
+            recipient,
+            epoch,
+        ),
+        TreeType::Unknown => return Err(ForesterError::InvalidTreeType(tree_accounts.tree_type)),
+    };
+    send_rollover_transaction(context, payer, vec![instruction], &[payer], sender).await
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn perform_state_merkle_tree_rollover_forester<R: Rpc>(
     payer: &Keypair,
@@ -250,6 +425,7 @@ pub async fn perform_state_merkle_tree_rollover_forester<R: Rpc>(
     old_queue_pubkey: &Pubkey,
     old_cpi_context_pubkey: &Pubkey,
     epoch: u64,
+    sender: Option<&dyn TransactionSender>,
 ) -> Result<solana_sdk::signature::Signature, ForesterError> {
     let instructions = create_rollover_state_merkle_tree_instructions(
         context,
@@ -264,22 +440,19 @@ pub async fn perform_state_merkle_tree_rollover_forester<R: Rpc>(
         epoch,
     )
     .await?;
-    let blockhash = context.get_latest_blockhash().await?;
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&payer.pubkey()),
-        &vec![
-            &payer,
-            &new_queue_keypair,
-            &new_address_merkle_tree_keypair,
-            &new_cpi_context_keypair,
+    send_rollover_transaction(
+        context,
+        payer,
+        instructions,
+        &[
+            payer,
+            new_queue_keypair,
+            new_address_merkle_tree_keypair,
+            new_cpi_context_keypair,
         ],
-        blockhash.0,
-    );
-    context
-        .process_transaction(transaction)
-        .await
-        .map_err(Into::into)
+        sender,
+    )
+    .await
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -292,6 +465,7 @@ pub async fn perform_address_merkle_tree_rollover<R: Rpc>(
     old_merkle_tree_pubkey: &Pubkey,
     old_queue_pubkey: &Pubkey,
     epoch: u64,
+    sender: Option<&dyn TransactionSender>,
 ) -> Result<solana_sdk::signature::Signature, ForesterError> {
     let mut instructions = create_rollover_address_merkle_tree_instructions(
         context,
@@ -306,17 +480,53 @@ pub async fn perform_address_merkle_tree_rollover<R: Rpc>(
     .await?;
     let compute_budget_instruction = ComputeBudgetInstruction::set_compute_unit_limit(500_000);
     instructions.insert(0, compute_budget_instruction);
-    let blockhash = context.get_latest_blockhash().await?;
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&payer.pubkey()),
-        &vec![&payer, &new_queue_keypair, &new_address_merkle_tree_keypair],
-        blockhash.0,
-    );
-    context
-        .process_transaction(transaction)
-        .await
-        .map_err(Into::into)
+    send_rollover_transaction(
+        context,
+        payer,
+        instructions,
+        &[payer, new_queue_keypair, new_address_merkle_tree_keypair],
+        sender,
+    )
+    .await
+}
+
+/// Sends a rollover transaction through the RPC connection, or with `sender`
+/// and confirms it through the RPC connection.
+async fn send_rollover_transaction<R: Rpc>(
+    context: &mut R,
+    payer: &Keypair,
+    instructions: Vec<Instruction>,
+    signers: &[&Keypair],
+    sender: Option<&dyn TransactionSender>,
+) -> Result<solana_sdk::signature::Signature, ForesterError> {
+    let Some(sender) = sender else {
+        let blockhash = context.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            signers,
+            blockhash.0,
+        );
+        return context
+            .process_transaction(transaction)
+            .await
+            .map_err(Into::into);
+    };
+    send_smart_transaction_with_sender(
+        context,
+        SendSmartTransactionConfig {
+            instructions,
+            payer: &payer.pubkey(),
+            signers,
+            address_lookup_tables: &[],
+            compute_budget: Default::default(),
+            confirmation: Some(ConfirmationConfig::default()),
+            confirmation_deadline: None,
+        },
+        Some(sender),
+    )
+    .await
+    .map_err(Into::into)
 }
 
 #[allow(clippy::too_many_arguments)]
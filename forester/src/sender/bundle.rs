@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use light_client::rpc::RpcError;
+use serde_json::json;
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Signature, system_instruction,
+    transaction::VersionedTransaction,
+};
+use tracing::debug;
+
+use super::TransactionSender;
+use crate::cli::TransactionSenderKind;
+
+/// Submits every transaction as a single-transaction bundle to a block
+/// engine. The block engine only forwards bundles which pay a tip, so a
+/// transfer of `tip_lamports` to `tip_account` is added to each transaction.
+#[derive(Debug, Clone)]
+pub struct BundleSender {
+    url: String,
+    tip_account: Pubkey,
+    tip_lamports: u64,
+    client: reqwest::Client,
+}
+
+impl BundleSender {
+    pub fn new(url: String, tip_account: Pubkey, tip_lamports: u64) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            url,
+            tip_account,
+            tip_lamports,
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionSender for BundleSender {
+    fn kind(&self) -> TransactionSenderKind {
+        TransactionSenderKind::Bundle
+    }
+
+    fn required_instructions(&self, payer: &Pubkey) -> Vec<Instruction> {
+        vec![system_instruction::transfer(
+            payer,
+            &self.tip_account,
+            self.tip_lamports,
+        )]
+    }
+
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, RpcError> {
+        let signature = transaction
+            .signatures
+            .first()
+            .copied()
+            .ok_or_else(|| RpcError::CustomError("Bundle transaction is not signed".into()))?;
+        let serialized = bincode::serialize(transaction)
+            .map_err(|e| RpcError::CustomError(format!("Failed to serialize transaction: {e}")))?;
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendBundle",
+            "params": [[base64::encode(serialized)], {"encoding": "base64"}]
+        });
+
+        let response: serde_json::Value = self
+            .client
+            .post(&self.url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| RpcError::CustomError(format!("Bundle request failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| RpcError::CustomError(format!("Invalid bundle response: {e}")))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::CustomError(format!(
+                "Block engine rejected bundle: {error}"
+            )));
+        }
+        debug!(
+            "Bundle {} submitted for transaction {}",
+            response.get("result").unwrap_or(&serde_json::Value::Null),
+            signature
+        );
+        Ok(signature)
+    }
+}
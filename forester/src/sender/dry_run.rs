@@ -0,0 +1,273 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use light_client::rpc::RpcError;
+use serde::Serialize;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use tracing::{info, warn};
+
+use super::TransactionSender;
+use crate::{
+    cli::TransactionSenderKind,
+    metrics::{increment_dry_run_transactions, update_dry_run_compute_units},
+};
+
+const DEFAULT_MAX_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RootTransition {
+    pub tree: String,
+    pub old_root: String,
+    pub new_root: String,
+}
+
+/// A transaction which was simulated instead of sent.
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunRecord {
+    pub signature: String,
+    pub operation: &'static str,
+    pub timestamp: u64,
+    pub compute_units: Option<u64>,
+    /// Simulation error, if the transaction would have failed.
+    pub error: Option<String>,
+    pub root_transition: Option<RootTransition>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunSnapshot {
+    pub simulated: u64,
+    pub failed: u64,
+    pub records: Vec<DryRunRecord>,
+}
+
+/// Keeps the most recent simulated transactions of a dry run for the API
+/// server.
+#[derive(Debug)]
+pub struct DryRunRecorder {
+    records: Mutex<VecDeque<DryRunRecord>>,
+    max_records: usize,
+    simulated: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl Default for DryRunRecorder {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RECORDS)
+    }
+}
+
+impl DryRunRecorder {
+    pub fn new(max_records: usize) -> Self {
+        Self {
+            records: Mutex::new(VecDeque::with_capacity(max_records)),
+            max_records,
+            simulated: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, record: DryRunRecord) {
+        self.simulated.fetch_add(1, Ordering::Relaxed);
+        if record.error.is_some() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() >= self.max_records {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Attaches the root transition of a batch update to the record of the
+    /// transaction which carried it.
+    pub fn record_root_transition(
+        &self,
+        signature: &Signature,
+        tree: &Pubkey,
+        old_root: [u8; 32],
+        new_root: [u8; 32],
+    ) {
+        let signature = signature.to_string();
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(record) = records.iter_mut().rev().find(|r| r.signature == signature) {
+            info!(
+                "Dry run: tree {} root {} -> {} in transaction {}",
+                tree,
+                bs58::encode(old_root).into_string(),
+                bs58::encode(new_root).into_string(),
+                signature
+            );
+            record.root_transition = Some(RootTransition {
+                tree: tree.to_string(),
+                old_root: bs58::encode(old_root).into_string(),
+                new_root: bs58::encode(new_root).into_string(),
+            });
+        }
+    }
+
+    pub fn snapshot(&self) -> DryRunSnapshot {
+        DryRunSnapshot {
+            simulated: self.simulated.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            records: self
+                .records
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Simulates transactions instead of sending them. Transactions which would
+/// fail return the simulation error; confirmation is skipped.
+pub struct DryRunSender {
+    operation: &'static str,
+    client: Arc<RpcClient>,
+    recorder: Arc<DryRunRecorder>,
+}
+
+impl DryRunSender {
+    pub fn new(
+        operation: &'static str,
+        client: Arc<RpcClient>,
+        recorder: Arc<DryRunRecorder>,
+    ) -> Self {
+        Self {
+            operation,
+            client,
+            recorder,
+        }
+    }
+}
+
+impl fmt::Debug for DryRunSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DryRunSender")
+            .field("operation", &self.operation)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TransactionSender for DryRunSender {
+    fn kind(&self) -> TransactionSenderKind {
+        TransactionSenderKind::DryRun
+    }
+
+    fn skips_confirmation(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, RpcError> {
+        let signature = transaction
+            .signatures
+            .first()
+            .copied()
+            .ok_or_else(|| RpcError::CustomError("Dry run transaction is not signed".into()))?;
+        let response = self
+            .client
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if let Some(units) = response.units_consumed {
+            update_dry_run_compute_units(self.operation, units);
+        }
+        increment_dry_run_transactions(
+            self.operation,
+            if response.err.is_some() {
+                "failed"
+            } else {
+                "succeeded"
+            },
+        );
+        self.recorder.record(DryRunRecord {
+            signature: signature.to_string(),
+            operation: self.operation,
+            timestamp,
+            compute_units: response.units_consumed,
+            error: response.err.as_ref().map(|e| e.to_string()),
+            root_transition: None,
+        });
+
+        match response.err {
+            Some(error) => {
+                warn!(
+                    "Dry run: {} transaction {} would fail: {}",
+                    self.operation, signature, error
+                );
+                Err(RpcError::TransactionError(error))
+            }
+            None => {
+                info!(
+                    "Dry run: {} transaction {} simulated, {} CU",
+                    self.operation,
+                    signature,
+                    response.units_consumed.unwrap_or_default()
+                );
+                Ok(signature)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(signature: &Signature, error: Option<&str>) -> DryRunRecord {
+        DryRunRecord {
+            signature: signature.to_string(),
+            operation: "batch_append",
+            timestamp: 0,
+            compute_units: Some(200_000),
+            error: error.map(str::to_string),
+            root_transition: None,
+        }
+    }
+
+    #[test]
+    fn test_dry_run_recorder() {
+        let recorder = DryRunRecorder::new(2);
+        let signatures: Vec<Signature> = (0..3).map(|_| Signature::new_unique()).collect();
+        recorder.record(record(&signatures[0], None));
+        recorder.record(record(&signatures[1], Some("InvalidRoot")));
+        recorder.record(record(&signatures[2], None));
+        recorder.record_root_transition(&signatures[2], &Pubkey::new_unique(), [1; 32], [2; 32]);
+        // Evicted records are not updated.
+        recorder.record_root_transition(&signatures[0], &Pubkey::new_unique(), [1; 32], [2; 32]);
+
+        let snapshot = recorder.snapshot();
+        assert_eq!(snapshot.simulated, 3);
+        assert_eq!(snapshot.failed, 1);
+        assert_eq!(snapshot.records.len(), 2);
+        assert_eq!(snapshot.records[0].signature, signatures[1].to_string());
+        let transition = snapshot.records[1].root_transition.as_ref().unwrap();
+        assert_eq!(transition.new_root, bs58::encode([2u8; 32]).into_string());
+    }
+}
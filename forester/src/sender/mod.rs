@@ -0,0 +1,384 @@
+//! Transaction submission backends.
+//!
+//! Transactions are built, signed and confirmed the same way for every
+//! backend; a [`TransactionSender`] only replaces the step which submits the
+//! signed transaction. Confirmation is always polled through the RPC pool.
+
+mod bundle;
+mod dry_run;
+mod staked_rpc;
+mod tpu;
+
+use std::{fmt::Debug, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+pub use bundle::BundleSender;
+pub use dry_run::{DryRunRecord, DryRunRecorder, DryRunSender, DryRunSnapshot, RootTransition};
+use light_client::rpc::RpcError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+pub use staked_rpc::StakedRpcSender;
+pub use tpu::TpuSender;
+
+use crate::{
+    cli::{StartArgs, TransactionSenderKind},
+    errors::ConfigError,
+    slot_tracker::SlotTracker,
+};
+
+/// Submits signed transactions to the cluster.
+#[async_trait]
+pub trait TransactionSender: Debug + Send + Sync {
+    fn kind(&self) -> TransactionSenderKind;
+
+    /// Instructions the backend requires in every transaction it submits,
+    /// e.g. the tip of a bundle. They are appended before signing.
+    fn required_instructions(&self, _payer: &Pubkey) -> Vec<Instruction> {
+        Vec::new()
+    }
+
+    /// Whether [`send`](Self::send) settles the transaction itself, so that
+    /// confirmation is not polled.
+    fn skips_confirmation(&self) -> bool {
+        false
+    }
+
+    /// Submits the transaction without waiting for confirmation.
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, RpcError>;
+}
+
+/// Operation types whose transactions can be routed to a sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionOperation {
+    BatchAppend,
+    BatchNullify,
+    BatchAddressAppend,
+    CompressAndClose,
+    Rollover,
+}
+
+impl TransactionOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionOperation::BatchAppend => "batch_append",
+            TransactionOperation::BatchNullify => "batch_nullify",
+            TransactionOperation::BatchAddressAppend => "batch_address_append",
+            TransactionOperation::CompressAndClose => "compress_and_close",
+            TransactionOperation::Rollover => "rollover",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderRoutes {
+    pub batch_append: TransactionSenderKind,
+    pub batch_nullify: TransactionSenderKind,
+    pub batch_address_append: TransactionSenderKind,
+    pub compress_and_close: TransactionSenderKind,
+    pub rollover: TransactionSenderKind,
+}
+
+impl SenderRoutes {
+    pub fn get(&self, operation: TransactionOperation) -> TransactionSenderKind {
+        match operation {
+            TransactionOperation::BatchAppend => self.batch_append,
+            TransactionOperation::BatchNullify => self.batch_nullify,
+            TransactionOperation::BatchAddressAppend => self.batch_address_append,
+            TransactionOperation::CompressAndClose => self.compress_and_close,
+            TransactionOperation::Rollover => self.rollover,
+        }
+    }
+
+    fn uses(&self, kind: TransactionSenderKind) -> bool {
+        [
+            self.batch_append,
+            self.batch_nullify,
+            self.batch_address_append,
+            self.compress_and_close,
+            self.rollover,
+        ]
+        .contains(&kind)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SenderConfig {
+    pub routes: SenderRoutes,
+    pub staked_rpc_url: Option<String>,
+    pub block_engine_url: Option<String>,
+    pub bundle_tip_account: Option<Pubkey>,
+    pub bundle_tip_lamports: u64,
+    pub tpu_fanout_slots: u64,
+    /// Set in dry-run mode; every transaction is simulated and recorded here.
+    pub dry_run: Option<Arc<DryRunRecorder>>,
+}
+
+impl Default for SenderConfig {
+    fn default() -> Self {
+        Self {
+            routes: SenderRoutes::default(),
+            staked_rpc_url: None,
+            block_engine_url: None,
+            bundle_tip_account: None,
+            bundle_tip_lamports: 10_000,
+            tpu_fanout_slots: 12,
+            dry_run: None,
+        }
+    }
+}
+
+impl SenderConfig {
+    pub fn new_for_start(args: &StartArgs) -> Result<Self, ConfigError> {
+        let config = Self {
+            routes: SenderRoutes {
+                batch_append: args.batch_append_sender,
+                batch_nullify: args.batch_nullify_sender,
+                batch_address_append: args.batch_address_append_sender,
+                compress_and_close: args.compress_and_close_sender,
+                rollover: args.rollover_sender,
+            },
+            staked_rpc_url: args.staked_rpc_url.clone(),
+            block_engine_url: args.block_engine_url.clone(),
+            bundle_tip_account: args
+                .bundle_tip_account
+                .as_ref()
+                .map(|s| {
+                    Pubkey::from_str(s).map_err(|e| ConfigError::InvalidArguments {
+                        field: "bundle_tip_account",
+                        invalid_values: vec![e.to_string()],
+                    })
+                })
+                .transpose()?,
+            bundle_tip_lamports: args.bundle_tip_lamports,
+            tpu_fanout_slots: args.tpu_fanout_slots,
+            dry_run: args.dry_run.then(|| Arc::new(DryRunRecorder::default())),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that every routed backend is configured.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.routes.uses(TransactionSenderKind::StakedRpc) && self.staked_rpc_url.is_none() {
+            return Err(ConfigError::MissingField {
+                field: "staked_rpc_url",
+            });
+        }
+        if self.routes.uses(TransactionSenderKind::Bundle) {
+            if self.block_engine_url.is_none() {
+                return Err(ConfigError::MissingField {
+                    field: "block_engine_url",
+                });
+            }
+            if self.bundle_tip_account.is_none() {
+                return Err(ConfigError::MissingField {
+                    field: "bundle_tip_account",
+                });
+            }
+        }
+        if self.routes.uses(TransactionSenderKind::Tpu) && self.tpu_fanout_slots == 0 {
+            return Err(ConfigError::InvalidArguments {
+                field: "tpu_fanout_slots",
+                invalid_values: vec!["must be greater than 0".to_string()],
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Senders of the configured backends, routed by operation type.
+/// Operations routed to [`TransactionSenderKind::Rpc`] have no sender and
+/// are sent through the RPC pool. In dry-run mode all transactions are
+/// simulated regardless of the routes.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionSenders {
+    routes: SenderRoutes,
+    staked_rpc: Option<Arc<dyn TransactionSender>>,
+    tpu: Option<Arc<dyn TransactionSender>>,
+    bundle: Option<Arc<dyn TransactionSender>>,
+    dry_run: Option<(Arc<RpcClient>, Arc<DryRunRecorder>)>,
+}
+
+impl TransactionSenders {
+    /// Creates the backends which at least one operation is routed to.
+    pub fn new(
+        config: &SenderConfig,
+        rpc_url: &str,
+        slot_tracker: Arc<SlotTracker>,
+    ) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let routes = config.routes;
+        let staked_rpc = match (
+            routes.uses(TransactionSenderKind::StakedRpc),
+            &config.staked_rpc_url,
+        ) {
+            (true, Some(url)) => {
+                Some(Arc::new(StakedRpcSender::new(url.clone())) as Arc<dyn TransactionSender>)
+            }
+            _ => None,
+        };
+        let tpu = routes.uses(TransactionSenderKind::Tpu).then(|| {
+            Arc::new(TpuSender::new(
+                rpc_url.to_string(),
+                slot_tracker,
+                config.tpu_fanout_slots,
+            )) as Arc<dyn TransactionSender>
+        });
+        let bundle = match (
+            routes.uses(TransactionSenderKind::Bundle),
+            &config.block_engine_url,
+            config.bundle_tip_account,
+        ) {
+            (true, Some(url), Some(tip_account)) => Some(Arc::new(BundleSender::new(
+                url.clone(),
+                tip_account,
+                config.bundle_tip_lamports,
+            ))
+                as Arc<dyn TransactionSender>),
+            _ => None,
+        };
+        let dry_run = config
+            .dry_run
+            .clone()
+            .map(|recorder| (Arc::new(RpcClient::new(rpc_url.to_string())), recorder));
+        Ok(Self {
+            routes,
+            staked_rpc,
+            tpu,
+            bundle,
+            dry_run,
+        })
+    }
+
+    pub fn routes(&self) -> SenderRoutes {
+        self.routes
+    }
+
+    /// Returns the sender of `operation`, or `None` if it is sent through
+    /// the RPC pool.
+    pub fn for_operation(
+        &self,
+        operation: TransactionOperation,
+    ) -> Option<Arc<dyn TransactionSender>> {
+        if self.dry_run.is_some() {
+            return self.dry_run_sender(operation.as_str());
+        }
+        match self.routes.get(operation) {
+            TransactionSenderKind::Rpc | TransactionSenderKind::DryRun => None,
+            TransactionSenderKind::StakedRpc => self.staked_rpc.clone(),
+            TransactionSenderKind::Tpu => self.tpu.clone(),
+            TransactionSenderKind::Bundle => self.bundle.clone(),
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    pub fn dry_run_recorder(&self) -> Option<&Arc<DryRunRecorder>> {
+        self.dry_run.as_ref().map(|(_, recorder)| recorder)
+    }
+
+    /// Returns a simulating sender in dry-run mode, for transactions which
+    /// are not routed by operation type, e.g. epoch registration.
+    pub fn dry_run_sender(&self, operation: &'static str) -> Option<Arc<dyn TransactionSender>> {
+        self.dry_run.as_ref().map(|(client, recorder)| {
+            Arc::new(DryRunSender::new(
+                operation,
+                client.clone(),
+                recorder.clone(),
+            )) as Arc<dyn TransactionSender>
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_routes_select_configured_senders() {
+        let config = SenderConfig {
+            routes: SenderRoutes {
+                batch_nullify: TransactionSenderKind::StakedRpc,
+                rollover: TransactionSenderKind::Bundle,
+                ..Default::default()
+            },
+            staked_rpc_url: Some("http://staked.test".to_string()),
+            block_engine_url: Some("http://block-engine.test".to_string()),
+            bundle_tip_account: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let slot_tracker = Arc::new(SlotTracker::new(0, Duration::from_secs(1)));
+        let senders = TransactionSenders::new(&config, "http://rpc.test", slot_tracker).unwrap();
+
+        assert!(senders
+            .for_operation(TransactionOperation::BatchAppend)
+            .is_none());
+        assert_eq!(
+            senders
+                .for_operation(TransactionOperation::BatchNullify)
+                .unwrap()
+                .kind(),
+            TransactionSenderKind::StakedRpc
+        );
+        let bundle = senders
+            .for_operation(TransactionOperation::Rollover)
+            .unwrap();
+        assert_eq!(bundle.kind(), TransactionSenderKind::Bundle);
+        assert_eq!(bundle.required_instructions(&Pubkey::new_unique()).len(), 1);
+        // Backends which no operation is routed to are not created.
+        assert!(senders.tpu.is_none());
+        assert!(!senders.is_dry_run());
+
+        // Dry runs simulate every operation regardless of the routes.
+        let config = SenderConfig {
+            dry_run: Some(Arc::new(DryRunRecorder::default())),
+            ..config
+        };
+        let slot_tracker = Arc::new(SlotTracker::new(0, Duration::from_secs(1)));
+        let senders = TransactionSenders::new(&config, "http://rpc.test", slot_tracker).unwrap();
+        for operation in [
+            TransactionOperation::BatchAppend,
+            TransactionOperation::Rollover,
+        ] {
+            let sender = senders.for_operation(operation).unwrap();
+            assert_eq!(sender.kind(), TransactionSenderKind::DryRun);
+            assert!(sender.skips_confirmation());
+        }
+        assert!(senders.dry_run_sender("report_work").is_some());
+    }
+
+    #[test]
+    fn test_routed_sender_requires_config() {
+        let config = SenderConfig {
+            routes: SenderRoutes {
+                compress_and_close: TransactionSenderKind::Bundle,
+                ..Default::default()
+            },
+            block_engine_url: Some("http://block-engine.test".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingField {
+                field: "bundle_tip_account"
+            })
+        ));
+
+        let config = SenderConfig {
+            routes: SenderRoutes {
+                batch_append: TransactionSenderKind::StakedRpc,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(SenderConfig::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use light_client::rpc::RpcError;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use super::TransactionSender;
+use crate::cli::TransactionSenderKind;
+
+/// Sends through a dedicated RPC endpoint, typically one with stake-weighted
+/// QoS, instead of the shared RPC pool.
+pub struct StakedRpcSender {
+    url: String,
+    client: RpcClient,
+}
+
+impl StakedRpcSender {
+    pub fn new(url: String) -> Self {
+        let client = RpcClient::new(url.clone());
+        Self { url, client }
+    }
+}
+
+impl fmt::Debug for StakedRpcSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StakedRpcSender")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TransactionSender for StakedRpcSender {
+    fn kind(&self) -> TransactionSenderKind {
+        TransactionSenderKind::StakedRpc
+    }
+
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, RpcError> {
+        self.client
+            .send_transaction_with_config(
+                transaction,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    max_retries: Some(0),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(RpcError::from)
+    }
+}
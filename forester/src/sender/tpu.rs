@@ -0,0 +1,222 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use light_client::rpc::RpcError;
+use solana_client::{
+    connection_cache::ConnectionCache,
+    nonblocking::{rpc_client::RpcClient, tpu_connection::TpuConnection},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use super::TransactionSender;
+use crate::{cli::TransactionSenderKind, slot_tracker::SlotTracker};
+
+/// Number of leaders fetched per leader schedule request.
+const LEADER_SCHEDULE_WINDOW: u64 = 400;
+const CLUSTER_NODES_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+const CONNECTION_POOL_SIZE: usize = 4;
+
+#[derive(Debug, Default)]
+struct LeaderCache {
+    first_slot: u64,
+    leaders: Vec<Pubkey>,
+    tpu_addresses: HashMap<Pubkey, SocketAddr>,
+    nodes_refreshed_at: Option<Instant>,
+}
+
+impl LeaderCache {
+    fn covers(&self, start_slot: u64, num_slots: u64) -> bool {
+        !self.leaders.is_empty()
+            && start_slot >= self.first_slot
+            && start_slot + num_slots <= self.first_slot + self.leaders.len() as u64
+    }
+
+    fn nodes_stale(&self) -> bool {
+        self.nodes_refreshed_at
+            .is_none_or(|refreshed_at| refreshed_at.elapsed() >= CLUSTER_NODES_REFRESH_INTERVAL)
+    }
+
+    /// Unique TPU QUIC addresses of the leaders of `num_slots` slots from
+    /// `start_slot` on, in slot order.
+    fn leader_addresses(&self, start_slot: u64, num_slots: u64) -> Vec<SocketAddr> {
+        let mut addresses = Vec::new();
+        let start = start_slot.saturating_sub(self.first_slot) as usize;
+        for leader in self.leaders.iter().skip(start).take(num_slots as usize) {
+            if let Some(address) = self.tpu_addresses.get(leader) {
+                if !addresses.contains(address) {
+                    addresses.push(*address);
+                }
+            }
+        }
+        addresses
+    }
+}
+
+/// Forwards transactions over QUIC directly to the TPU of the leaders of the
+/// next `fanout_slots` slots, estimated with the [`SlotTracker`].
+pub struct TpuSender {
+    rpc_client: RpcClient,
+    connection_cache: ConnectionCache,
+    slot_tracker: Arc<SlotTracker>,
+    fanout_slots: u64,
+    leaders: RwLock<LeaderCache>,
+}
+
+impl TpuSender {
+    pub fn new(rpc_url: String, slot_tracker: Arc<SlotTracker>, fanout_slots: u64) -> Self {
+        Self {
+            rpc_client: RpcClient::new(rpc_url),
+            connection_cache: ConnectionCache::new_quic(
+                "forester_tpu_sender",
+                CONNECTION_POOL_SIZE,
+            ),
+            slot_tracker,
+            fanout_slots,
+            leaders: RwLock::new(LeaderCache::default()),
+        }
+    }
+
+    async fn upcoming_leader_addresses(&self) -> Result<Vec<SocketAddr>, RpcError> {
+        let current_slot = self.slot_tracker.estimated_current_slot();
+        {
+            let cache = self.leaders.read().await;
+            if cache.covers(current_slot, self.fanout_slots) && !cache.nodes_stale() {
+                return Ok(cache.leader_addresses(current_slot, self.fanout_slots));
+            }
+        }
+
+        let mut cache = self.leaders.write().await;
+        if !cache.covers(current_slot, self.fanout_slots) {
+            let window = LEADER_SCHEDULE_WINDOW.max(self.fanout_slots);
+            cache.leaders = self
+                .rpc_client
+                .get_slot_leaders(current_slot, window)
+                .await?;
+            cache.first_slot = current_slot;
+            debug!(
+                "Fetched {} slot leaders from slot {}",
+                cache.leaders.len(),
+                current_slot
+            );
+        }
+        if cache.nodes_stale() {
+            cache.tpu_addresses = self
+                .rpc_client
+                .get_cluster_nodes()
+                .await?
+                .into_iter()
+                .filter_map(|node| {
+                    let pubkey = Pubkey::from_str(&node.pubkey).ok()?;
+                    Some((pubkey, node.tpu_quic?))
+                })
+                .collect();
+            cache.nodes_refreshed_at = Some(Instant::now());
+        }
+        Ok(cache.leader_addresses(current_slot, self.fanout_slots))
+    }
+}
+
+impl fmt::Debug for TpuSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TpuSender")
+            .field("rpc_url", &self.rpc_client.url())
+            .field("fanout_slots", &self.fanout_slots)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TransactionSender for TpuSender {
+    fn kind(&self) -> TransactionSenderKind {
+        TransactionSenderKind::Tpu
+    }
+
+    async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, RpcError> {
+        let signature = transaction
+            .signatures
+            .first()
+            .copied()
+            .ok_or_else(|| RpcError::CustomError("TPU transaction is not signed".into()))?;
+        let addresses = self.upcoming_leader_addresses().await?;
+        if addresses.is_empty() {
+            return Err(RpcError::CustomError(
+                "No TPU address known for upcoming leaders".into(),
+            ));
+        }
+        let wire_transaction = bincode::serialize(transaction)
+            .map_err(|e| RpcError::CustomError(format!("Failed to serialize transaction: {e}")))?;
+
+        let results = join_all(addresses.iter().map(|address| {
+            let connection = self.connection_cache.get_nonblocking_connection(address);
+            let wire_transaction = &wire_transaction;
+            async move { connection.send_data(wire_transaction).await }
+        }))
+        .await;
+
+        let mut last_error = None;
+        let mut num_sent = 0;
+        for (address, result) in addresses.iter().zip(results) {
+            match result {
+                Ok(()) => num_sent += 1,
+                Err(error) => {
+                    warn!(
+                        "Failed to forward transaction to TPU {}: {}",
+                        address, error
+                    );
+                    last_error = Some(error);
+                }
+            }
+        }
+        match last_error {
+            Some(error) if num_sent == 0 => Err(RpcError::CustomError(format!(
+                "Failed to forward transaction to {} leaders: {}",
+                addresses.len(),
+                error
+            ))),
+            _ => Ok(signature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leader_addresses() {
+        let leader_a = Pubkey::new_unique();
+        let leader_b = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+        let address_a: SocketAddr = "10.0.0.1:8009".parse().unwrap();
+        let address_b: SocketAddr = "10.0.0.2:8009".parse().unwrap();
+        let cache = LeaderCache {
+            first_slot: 100,
+            // Leaders are scheduled for 4 consecutive slots.
+            leaders: [leader_a, leader_b, unknown]
+                .iter()
+                .flat_map(|leader| [*leader; 4])
+                .collect(),
+            tpu_addresses: HashMap::from([(leader_a, address_a), (leader_b, address_b)]),
+            nodes_refreshed_at: Some(Instant::now()),
+        };
+
+        assert!(cache.covers(100, 12));
+        assert!(!cache.covers(99, 4));
+        assert!(!cache.covers(104, 9));
+        assert_eq!(cache.leader_addresses(102, 4), vec![address_a, address_b]);
+        assert_eq!(cache.leader_addresses(106, 6), vec![address_b]);
+        assert!(cache.leader_addresses(108, 4).is_empty());
+        assert!(!cache.nodes_stale());
+        assert!(LeaderCache::default().nodes_stale());
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::hashv, pubkey::Pubkey};
+use tracing::{debug, info, warn};
+
+use crate::{cli::StartArgs, errors::ConfigError, metrics::update_shard_live_instances};
+
+/// Path of the shard status on the API server of every instance.
+pub const SHARD_STATUS_PATH: &str = "/shard";
+
+const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Twice the interval of the service heartbeat, so that a single late beat
+/// does not hand a peer's trees over.
+const MIN_PEER_TIMEOUT_SECONDS: u64 = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardPeer {
+    pub id: String,
+    /// API server URL of the peer.
+    pub url: String,
+}
+
+/// Sharding of trees between forester instances which share one registered
+/// authority.
+#[derive(Debug, Clone)]
+pub struct ShardConfig {
+    pub instance_id: String,
+    pub peers: Vec<ShardPeer>,
+    /// A peer whose heartbeat did not advance for this long is considered
+    /// down and its trees are taken over.
+    pub peer_timeout: Duration,
+}
+
+impl ShardConfig {
+    pub fn new_for_start(args: &StartArgs) -> Result<Option<Self>, ConfigError> {
+        let Some(instance_id) = args.shard_instance_id.clone() else {
+            if !args.shard_peers.is_empty() {
+                return Err(ConfigError::MissingField {
+                    field: "shard_instance_id",
+                });
+            }
+            return Ok(None);
+        };
+        let peers = parse_shard_peers(&args.shard_peers)?;
+        if let Some(peer) = peers.iter().find(|peer| peer.id == instance_id) {
+            return Err(ConfigError::InvalidArguments {
+                field: "shard_peers",
+                invalid_values: vec![format!("{} is this instance", peer.id)],
+            });
+        }
+        if args.shard_peer_timeout_seconds < MIN_PEER_TIMEOUT_SECONDS {
+            return Err(ConfigError::InvalidArguments {
+                field: "shard_peer_timeout_seconds",
+                invalid_values: vec![format!(
+                    "{} is below the minimum of {}",
+                    args.shard_peer_timeout_seconds, MIN_PEER_TIMEOUT_SECONDS
+                )],
+            });
+        }
+        Ok(Some(Self {
+            instance_id,
+            peers,
+            peer_timeout: Duration::from_secs(args.shard_peer_timeout_seconds),
+        }))
+    }
+}
+
+/// Parses `id=url` peer specs.
+pub fn parse_shard_peers(specs: &[String]) -> Result<Vec<ShardPeer>, ConfigError> {
+    let mut peers: Vec<ShardPeer> = Vec::with_capacity(specs.len());
+    let mut invalid_values = Vec::new();
+    for spec in specs.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        match spec.split_once('=') {
+            Some((id, url))
+                if !id.trim().is_empty()
+                    && !url.trim().is_empty()
+                    && !peers.iter().any(|peer| peer.id == id.trim()) =>
+            {
+                peers.push(ShardPeer {
+                    id: id.trim().to_string(),
+                    url: url.trim().trim_end_matches('/').to_string(),
+                })
+            }
+            _ => invalid_values.push(spec.to_string()),
+        }
+    }
+    if !invalid_values.is_empty() {
+        return Err(ConfigError::InvalidArguments {
+            field: "shard_peers",
+            invalid_values,
+        });
+    }
+    Ok(peers)
+}
+
+/// Returns the instance a tree is assigned to by rendezvous hashing. When an
+/// instance leaves, only its trees move; they spread over the remaining
+/// instances.
+pub fn assign_tree<'a>(instances: &[&'a str], tree: &Pubkey) -> Option<&'a str> {
+    instances
+        .iter()
+        .map(|instance| {
+            let hash = hashv(&[instance.as_bytes(), tree.as_ref()]);
+            let score = u64::from_le_bytes(hash.as_ref()[..8].try_into().unwrap());
+            (score, *instance)
+        })
+        .max()
+        .map(|(_, instance)| instance)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardStatus {
+    pub instance_id: String,
+    /// Incremented by the service heartbeat.
+    pub heartbeat: u64,
+    pub live_instances: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PeerState {
+    heartbeat: Option<u64>,
+    last_advanced: Instant,
+}
+
+/// Tracks which instances are alive and which trees this instance owns.
+#[derive(Debug)]
+pub struct ShardCoordinator {
+    config: ShardConfig,
+    heartbeat: AtomicU64,
+    peers: Mutex<HashMap<String, PeerState>>,
+    http_client: reqwest::Client,
+}
+
+impl ShardCoordinator {
+    pub fn new(config: ShardConfig) -> Self {
+        // Peers are considered alive until they missed the timeout once, so
+        // that instances starting together do not claim every tree.
+        let now = Instant::now();
+        let peers = config
+            .peers
+            .iter()
+            .map(|peer| {
+                (
+                    peer.id.clone(),
+                    PeerState {
+                        heartbeat: None,
+                        last_advanced: now,
+                    },
+                )
+            })
+            .collect();
+        Self {
+            config,
+            heartbeat: AtomicU64::new(0),
+            peers: Mutex::new(peers),
+            http_client: reqwest::Client::builder()
+                .timeout(PEER_REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.config.instance_id
+    }
+
+    /// Called by the service heartbeat task.
+    pub fn beat(&self) {
+        self.heartbeat.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> ShardStatus {
+        ShardStatus {
+            instance_id: self.config.instance_id.clone(),
+            heartbeat: self.heartbeat.load(Ordering::Relaxed),
+            live_instances: self.live_instances(),
+        }
+    }
+
+    /// This instance and the peers whose heartbeat advanced within the peer
+    /// timeout, sorted by id.
+    pub fn live_instances(&self) -> Vec<String> {
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        let mut instances: Vec<String> = peers
+            .iter()
+            .filter(|(_, state)| state.last_advanced.elapsed() < self.config.peer_timeout)
+            .map(|(id, _)| id.clone())
+            .chain(std::iter::once(self.config.instance_id.clone()))
+            .collect();
+        instances.sort();
+        instances
+    }
+
+    pub fn owns(&self, tree: &Pubkey) -> bool {
+        let instances = self.live_instances();
+        let instances: Vec<&str> = instances.iter().map(String::as_str).collect();
+        assign_tree(&instances, tree) == Some(self.instance_id())
+    }
+
+    async fn fetch_peer_status(&self, peer: &ShardPeer) -> Option<ShardStatus> {
+        let result = async {
+            self.http_client
+                .get(format!("{}{}", peer.url, SHARD_STATUS_PATH))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ShardStatus>()
+                .await
+        }
+        .await;
+        match result {
+            Ok(status) if status.instance_id == peer.id => Some(status),
+            Ok(status) => {
+                warn!(
+                    event = "shard_peer_id_mismatch",
+                    peer = %peer.id,
+                    reported = %status.instance_id,
+                    url = %peer.url,
+                    "Shard peer reported a different instance id"
+                );
+                None
+            }
+            Err(e) => {
+                debug!("Shard status request to {} failed: {}", peer.id, e);
+                None
+            }
+        }
+    }
+
+    pub async fn poll_peers(&self) {
+        for peer in &self.config.peers {
+            let Some(status) = self.fetch_peer_status(peer).await else {
+                continue;
+            };
+            let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(state) = peers.get_mut(&peer.id) {
+                if state.heartbeat != Some(status.heartbeat) {
+                    state.heartbeat = Some(status.heartbeat);
+                    state.last_advanced = Instant::now();
+                }
+            }
+        }
+    }
+
+    pub async fn monitor_peers(self: std::sync::Arc<Self>) -> crate::Result<()> {
+        let mut interval =
+            tokio::time::interval((self.config.peer_timeout / 4).max(Duration::from_secs(1)));
+        let mut previous = self.live_instances();
+        update_shard_live_instances(previous.len());
+        loop {
+            interval.tick().await;
+            self.poll_peers().await;
+            let live = self.live_instances();
+            if live != previous {
+                info!(
+                    event = "shard_membership_changed",
+                    instance = %self.config.instance_id,
+                    previous = ?previous,
+                    live = ?live,
+                    "Shard membership changed, trees are reassigned"
+                );
+                update_shard_live_instances(live.len());
+                previous = live;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shard_peers() {
+        let peers = parse_shard_peers(&[
+            "b=http://10.0.0.2:8080/".to_string(),
+            " c = http://10.0.0.3:8080".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            peers,
+            vec![
+                ShardPeer {
+                    id: "b".to_string(),
+                    url: "http://10.0.0.2:8080".to_string(),
+                },
+                ShardPeer {
+                    id: "c".to_string(),
+                    url: "http://10.0.0.3:8080".to_string(),
+                },
+            ]
+        );
+        assert!(parse_shard_peers(&["http://10.0.0.2:8080".to_string()]).is_err());
+        assert!(parse_shard_peers(&["b=x".to_string(), "b=y".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_assign_tree_moves_only_trees_of_departed_instance() {
+        let trees: Vec<Pubkey> = (0..200).map(|_| Pubkey::new_unique()).collect();
+        let all = ["a", "b", "c"];
+        let survivors = ["a", "c"];
+
+        let mut counts = HashMap::new();
+        for tree in &trees {
+            let owner = assign_tree(&all, tree).unwrap();
+            *counts.entry(owner).or_insert(0) += 1;
+            let new_owner = assign_tree(&survivors, tree).unwrap();
+            if owner != "b" {
+                assert_eq!(owner, new_owner);
+            }
+        }
+        // Every instance gets a share.
+        assert_eq!(counts.len(), 3);
+        assert!(assign_tree(&[], &trees[0]).is_none());
+    }
+
+    #[test]
+    fn test_silent_peer_is_taken_over() {
+        let coordinator = ShardCoordinator::new(ShardConfig {
+            instance_id: "a".to_string(),
+            peers: vec![ShardPeer {
+                id: "b".to_string(),
+                url: "http://127.0.0.1:1".to_string(),
+            }],
+            peer_timeout: Duration::from_secs(60),
+        });
+        assert_eq!(coordinator.live_instances(), vec!["a", "b"]);
+
+        coordinator
+            .peers
+            .lock()
+            .unwrap()
+            .get_mut("b")
+            .unwrap()
+            .last_advanced = Instant::now() - Duration::from_secs(61);
+        assert_eq!(coordinator.live_instances(), vec!["a"]);
+        assert!(coordinator.owns(&Pubkey::new_unique()));
+    }
+}
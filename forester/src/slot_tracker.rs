@@ -1,13 +1,12 @@
-use std::{
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
-    },
-    time::Instant,
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
 };
 
 use light_client::rpc::Rpc;
-use tokio::time::{sleep, Duration};
+// Tokio's `Instant` follows a paused test clock, so slot estimates stay in
+// step with the replay harness.
+use tokio::time::{sleep, Duration, Instant};
 use tracing::{error, trace};
 
 const SLOT_DURATION_MS: u64 = 400;
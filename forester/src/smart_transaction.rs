@@ -1,6 +1,6 @@
 // adapted from https://github.com/helius-labs/helius-rust-sdk/blob/dev/src/optimized_transaction.rs
 // optimized for forester client
-use std::{collections::HashSet, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use light_client::rpc::{Rpc, RpcError};
 use solana_client::rpc_config::RpcSendTransactionConfig;
@@ -18,8 +18,11 @@ use solana_sdk::{
 use solana_transaction_status::TransactionConfirmationStatus;
 use thiserror::Error;
 use tokio::time::{sleep, Instant};
+use tracing::warn;
 
-use crate::{errors::rpc_is_already_processed, priority_fee::PriorityFeeConfig};
+use crate::{
+    errors::rpc_is_already_processed, priority_fee::PriorityFeeConfig, sender::TransactionSender,
+};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ComputeBudgetConfig {
@@ -42,11 +45,14 @@ impl Default for ConfirmationConfig {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct TransactionPolicy {
     pub priority_fee_config: PriorityFeeConfig,
     pub compute_unit_limit: Option<u32>,
     pub confirmation: Option<ConfirmationConfig>,
+    /// Backend which submits the transactions. If None, they are sent
+    /// through the RPC connection.
+    pub sender: Option<Arc<dyn TransactionSender>>,
 }
 
 impl Default for TransactionPolicy {
@@ -55,6 +61,7 @@ impl Default for TransactionPolicy {
             priority_fee_config: PriorityFeeConfig::default(),
             compute_unit_limit: None,
             confirmation: Some(ConfirmationConfig::default()),
+            sender: None,
         }
     }
 }
@@ -233,7 +240,7 @@ pub async fn send_transaction_with_policy<R: Rpc>(
             RpcError::CustomError(format!("Failed to resolve priority fee: {error}"))
         })?;
 
-    send_smart_transaction(
+    send_smart_transaction_with_sender(
         rpc,
         SendSmartTransactionConfig {
             instructions: config.instructions,
@@ -247,6 +254,7 @@ pub async fn send_transaction_with_policy<R: Rpc>(
             confirmation: config.policy.confirmation,
             confirmation_deadline: config.confirmation_deadline,
         },
+        config.policy.sender.as_deref(),
     )
     .await
 }
@@ -282,6 +290,13 @@ impl PreparedTransaction {
         self.last_valid_block_height
     }
 
+    fn versioned(&self) -> VersionedTransaction {
+        match &self.transaction {
+            PreparedTransactionKind::Legacy(transaction) => transaction.clone().into(),
+            PreparedTransactionKind::Versioned(transaction) => transaction.clone(),
+        }
+    }
+
     async fn process<R: Rpc>(&self, rpc: &mut R) -> Result<Signature, RpcError> {
         match &self.transaction {
             PreparedTransactionKind::Legacy(transaction) => {
@@ -311,14 +326,26 @@ impl PreparedTransaction {
         rpc: &mut R,
         confirmation: Option<ConfirmationConfig>,
         confirmation_deadline: Option<Instant>,
+        sender: Option<&dyn TransactionSender>,
     ) -> Result<Signature, SmartTransactionError> {
-        send_prepared_transaction(rpc, self, confirmation, confirmation_deadline).await
+        send_prepared_transaction(rpc, self, confirmation, confirmation_deadline, sender).await
     }
 }
 
 pub async fn send_smart_transaction<R: Rpc>(
     rpc: &mut R,
     config: SendSmartTransactionConfig<'_>,
+) -> Result<Signature, SmartTransactionError> {
+    send_smart_transaction_with_sender(rpc, config, None).await
+}
+
+/// Like [`send_smart_transaction`], but submits the transaction with
+/// `sender` instead of the RPC connection. Instructions the sender requires
+/// are appended before signing; confirmation is polled through `rpc`.
+pub async fn send_smart_transaction_with_sender<R: Rpc>(
+    rpc: &mut R,
+    config: SendSmartTransactionConfig<'_>,
+    sender: Option<&dyn TransactionSender>,
 ) -> Result<Signature, SmartTransactionError> {
     let SendSmartTransactionConfig {
         instructions,
@@ -329,6 +356,10 @@ pub async fn send_smart_transaction<R: Rpc>(
         confirmation,
         confirmation_deadline,
     } = config;
+    let mut instructions = instructions;
+    if let Some(sender) = sender {
+        instructions.extend(sender.required_instructions(payer));
+    }
     let prepared = prepare_transaction(
         rpc,
         instructions,
@@ -340,7 +371,7 @@ pub async fn send_smart_transaction<R: Rpc>(
     .await?;
 
     prepared
-        .send(rpc, confirmation, confirmation_deadline)
+        .send(rpc, confirmation, confirmation_deadline, sender)
         .await
 }
 
@@ -384,9 +415,20 @@ async fn send_prepared_transaction<R: Rpc>(
     transaction: &PreparedTransaction,
     confirmation: Option<ConfirmationConfig>,
     confirmation_deadline: Option<Instant>,
+    sender: Option<&dyn TransactionSender>,
 ) -> Result<Signature, SmartTransactionError> {
+    if let Some(sender) = sender.filter(|sender| sender.skips_confirmation()) {
+        return sender
+            .send(&transaction.versioned())
+            .await
+            .map_err(Into::into);
+    }
     let Some(confirmation) = confirmation else {
-        return transaction.process(rpc).await.map_err(Into::into);
+        return match sender {
+            Some(sender) => sender.send(&transaction.versioned()).await,
+            None => transaction.process(rpc).await,
+        }
+        .map_err(Into::into);
     };
 
     let signature = transaction
@@ -412,11 +454,28 @@ async fn send_prepared_transaction<R: Rpc>(
             });
         }
 
-        match transaction.send_with_confirmation_config(rpc).await {
-            Ok(_) => last_send_error = None,
-            Err(error) if rpc_is_already_processed(&error) => last_send_error = None,
-            Err(error) if rpc.should_retry(&error) => last_send_error = Some(error),
-            Err(error) => return Err(error.into()),
+        if let Some(sender) = sender {
+            // Sender errors are transient from the forester's point of
+            // view; resend until the blockhash expires.
+            match sender.send(&transaction.versioned()).await {
+                Ok(_) => last_send_error = None,
+                Err(error) => {
+                    warn!(
+                        "Failed to send transaction {} with {} sender: {}",
+                        signature,
+                        sender.kind(),
+                        error
+                    );
+                    last_send_error = Some(error);
+                }
+            }
+        } else {
+            match transaction.send_with_confirmation_config(rpc).await {
+                Ok(_) => last_send_error = None,
+                Err(error) if rpc_is_already_processed(&error) => last_send_error = None,
+                Err(error) if rpc.should_retry(&error) => last_send_error = Some(error),
+                Err(error) => return Err(error.into()),
+            }
         }
 
         if let Some(signature) = confirmed_signature_or_error(rpc, signature).await? {
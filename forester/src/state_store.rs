@@ -0,0 +1,263 @@
+//! Local checkpoints of in-memory forester state, so that a restart
+//! mid-epoch keeps pre-warmed proofs and processing counters.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
+
+use crate::{
+    epoch_manager::ProcessingMetrics,
+    processor::v2::{proof_cache::CachedProof, BatchInstruction},
+    Result,
+};
+
+const STATE_FILE_NAME: &str = "forester_state.json";
+const STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ForesterStateSnapshot {
+    pub version: u32,
+    /// Processing counters of epochs whose work has not been reported yet.
+    pub epochs: Vec<EpochCounters>,
+    pub proof_caches: Vec<ProofCacheSnapshot>,
+    pub tx_cache: Vec<HashCacheEntry>,
+    pub ops_cache: Vec<HashCacheEntry>,
+}
+
+impl ForesterStateSnapshot {
+    pub fn new() -> Self {
+        Self {
+            version: STATE_VERSION,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochCounters {
+    pub epoch: u64,
+    pub processed_items: usize,
+    pub metrics: ProcessingMetrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofCacheSnapshot {
+    pub tree: Pubkey,
+    pub base_root: [u8; 32],
+    pub proofs: Vec<CachedProofSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedProofSnapshot {
+    pub seq: u64,
+    pub old_root: [u8; 32],
+    pub new_root: [u8; 32],
+    /// Borsh serialized `BatchInstruction`.
+    pub instruction: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashCacheEntry {
+    pub hash: String,
+    pub remaining: Duration,
+}
+
+impl ProofCacheSnapshot {
+    pub fn new(tree: Pubkey, base_root: [u8; 32], proofs: &[CachedProof]) -> Result<Self> {
+        let proofs = proofs
+            .iter()
+            .map(|proof| {
+                Ok(CachedProofSnapshot {
+                    seq: proof.seq,
+                    old_root: proof.old_root,
+                    new_root: proof.new_root,
+                    instruction: proof.instruction.try_to_vec()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            tree,
+            base_root,
+            proofs,
+        })
+    }
+
+    pub fn cached_proofs(&self) -> Result<Vec<CachedProof>> {
+        self.proofs
+            .iter()
+            .map(|proof| {
+                let instruction = BatchInstruction::try_from_slice(&proof.instruction)?;
+                Ok(CachedProof {
+                    seq: proof.seq,
+                    old_root: proof.old_root,
+                    new_root: proof.new_root,
+                    items: instruction.items_count(),
+                    instruction,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the cached proofs can still be applied to a tree with
+    /// `current_root`, i.e. the on-chain root is the base root or the
+    /// input root of one of the proofs.
+    pub fn is_valid_for_root(&self, current_root: &[u8; 32]) -> bool {
+        !self.proofs.is_empty()
+            && (self.base_root == *current_root
+                || self
+                    .proofs
+                    .iter()
+                    .any(|proof| proof.old_root == *current_root))
+    }
+}
+
+impl HashCacheEntry {
+    pub fn from_snapshot(entries: Vec<(String, Duration)>) -> Vec<Self> {
+        entries
+            .into_iter()
+            .map(|(hash, remaining)| Self { hash, remaining })
+            .collect()
+    }
+
+    pub fn into_snapshot(entries: Vec<Self>) -> Vec<(String, Duration)> {
+        entries
+            .into_iter()
+            .map(|entry| (entry.hash, entry.remaining))
+            .collect()
+    }
+}
+
+/// Directory in which the forester checkpoints its state.
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    dir: PathBuf,
+}
+
+impl StateStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join(STATE_FILE_NAME)
+    }
+
+    /// Loads the last checkpoint. Returns `None` if there is none or it was
+    /// written by an incompatible version.
+    pub async fn load(&self) -> Result<Option<ForesterStateSnapshot>> {
+        let data = match tokio::fs::read(self.state_path()).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let snapshot: ForesterStateSnapshot = serde_json::from_slice(&data)?;
+        if snapshot.version != STATE_VERSION {
+            warn!(
+                "Ignoring forester state checkpoint with version {} (expected {})",
+                snapshot.version, STATE_VERSION
+            );
+            return Ok(None);
+        }
+        Ok(Some(snapshot))
+    }
+
+    /// Writes a checkpoint. The previous checkpoint is replaced atomically.
+    pub async fn save(&self, snapshot: &ForesterStateSnapshot) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let data = serde_json::to_vec(snapshot)?;
+        let tmp_path = self.dir.join(format!("{STATE_FILE_NAME}.tmp"));
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, self.state_path()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use light_batched_merkle_tree::merkle_tree::InstructionDataBatchNullifyInputs;
+    use light_compressed_account::instruction_data::compressed_proof::CompressedProof;
+
+    use super::*;
+
+    fn cached_proof(seq: u64, old_root: [u8; 32], new_root: [u8; 32]) -> CachedProof {
+        let instruction = BatchInstruction::Nullify(vec![InstructionDataBatchNullifyInputs {
+            new_root,
+            compressed_proof: CompressedProof::default(),
+        }]);
+        CachedProof {
+            seq,
+            old_root,
+            new_root,
+            items: instruction.items_count(),
+            instruction,
+        }
+    }
+
+    #[test]
+    fn test_proof_cache_snapshot_validation() {
+        let proofs = vec![
+            cached_proof(0, [1u8; 32], [2u8; 32]),
+            cached_proof(1, [2u8; 32], [3u8; 32]),
+        ];
+        let snapshot = ProofCacheSnapshot::new(Pubkey::new_unique(), [1u8; 32], &proofs).unwrap();
+
+        assert!(snapshot.is_valid_for_root(&[1u8; 32]));
+        // The first proof landed before the restart.
+        assert!(snapshot.is_valid_for_root(&[2u8; 32]));
+        // All proofs landed, or the tree advanced without them.
+        assert!(!snapshot.is_valid_for_root(&[3u8; 32]));
+        assert!(!snapshot.is_valid_for_root(&[9u8; 32]));
+
+        let restored = snapshot.cached_proofs().unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored[1].seq, 1);
+        assert_eq!(restored[1].new_root, [3u8; 32]);
+        assert!(matches!(
+            &restored[1].instruction,
+            BatchInstruction::Nullify(inputs) if inputs[0].new_root == [3u8; 32]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_state_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("forester-state-{}", Pubkey::new_unique()));
+        let store = StateStore::new(&dir);
+        assert!(store.load().await.unwrap().is_none());
+
+        let mut snapshot = ForesterStateSnapshot::new();
+        snapshot.epochs.push(EpochCounters {
+            epoch: 7,
+            processed_items: 42,
+            metrics: ProcessingMetrics {
+                tx_sending_duration: Duration::from_millis(1500),
+                ..Default::default()
+            },
+        });
+        snapshot.ops_cache.push(HashCacheEntry {
+            hash: "hash".to_string(),
+            remaining: Duration::from_secs(30),
+        });
+        store.save(&snapshot).await.unwrap();
+
+        let loaded = store.load().await.unwrap().unwrap();
+        assert_eq!(loaded.epochs[0].epoch, 7);
+        assert_eq!(loaded.epochs[0].processed_items, 42);
+        assert_eq!(
+            loaded.epochs[0].metrics.tx_sending_duration,
+            Duration::from_millis(1500)
+        );
+        assert_eq!(loaded.ops_cache[0].remaining, Duration::from_secs(30));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -332,6 +332,7 @@ fn create_tree_accounts(
         tree_type,
         metadata.rollover_metadata.rolledover_slot != u64::MAX,
         metadata.access_metadata.owner.into(),
+        metadata.rollover_metadata.network_fee,
     );
 
     trace!(
@@ -207,6 +207,9 @@ async fn e2e_test() {
             prover_api_key: get_prover_api_key(),
             prover_polling_interval: None,
             prover_max_wait_time: None,
+            prover_routing: Default::default(),
+            prover_health_check_interval: None,
+            prover_max_consecutive_failures: None,
             photon_grpc_url: get_photon_grpc_url(),
             pushgateway_url: None,
             pagerduty_routing_key: None,
@@ -217,6 +220,12 @@ async fn e2e_test() {
             fallback_indexer_url: None,
         },
         lookup_table_address: None,
+        sender_config: Default::default(),
+        shard_coordinator: None,
+        alert_manager: Default::default(),
+        earnings: Default::default(),
+        admin: Default::default(),
+        reloadable: Default::default(),
         retry_config: Default::default(),
         queue_config: Default::default(),
         indexer_config: Default::default(),
@@ -232,11 +241,17 @@ async fn e2e_test() {
             skip_v1_address_trees: false,
             skip_v2_address_trees: false,
             tree_ids: vec![],
+            exclude_tree_ids: vec![],
             sleep_after_processing_ms: 50,
             sleep_when_idle_ms: 100,
             queue_polling_mode: Default::default(),
             group_authority: None,
             helius_rpc: false,
+            state_dir: None,
+            state_checkpoint_interval_seconds: 30,
+            enable_state_migration: false,
+            migration_output_queue: None,
+            max_migrations_per_slot: 10,
         },
         rpc_pool_config: RpcPoolConfig {
             max_size: 50,
@@ -33,6 +33,7 @@ async fn test_priority_fee_request() {
     init(None).await;
 
     let args = StartArgs {
+        config: None,
         rpc_url: Some(std::env::var("RPC_URL").expect("RPC_URL must be set in environment")),
         push_gateway_url: None,
         pagerduty_routing_key: None,
@@ -49,12 +50,16 @@ async fn test_priority_fee_request() {
         prover_api_key: None,
         prover_polling_interval_ms: None,
         prover_max_wait_time_secs: None,
+        prover_routing: Default::default(),
+        prover_health_check_interval_secs: 30,
+        prover_max_consecutive_failures: 3,
         payer: Some(std::env::var("PAYER").expect("PAYER must be set in environment")),
         derivation: Some(
             std::env::var("DERIVATION_PUBKEY")
                 .expect("DERIVATION_PUBKEY must be set in environment"),
         ),
         api_server_public_bind: false,
+        admin_token: None,
         photon_grpc_url: None,
         indexer_batch_size: 50,
         indexer_max_concurrent_batches: 10,
@@ -90,6 +95,10 @@ async fn test_priority_fee_request() {
         processor_mode: ProcessorMode::All,
         queue_polling_mode: Default::default(),
         tree_ids: vec![],
+        exclude_tree_ids: vec![],
+        enable_state_migration: false,
+        migration_output_queue: None,
+        max_migrations_per_slot: 10,
         enable_compressible: true,
         lookup_table_address: None,
         api_server_port: 8080,
@@ -101,6 +110,28 @@ async fn test_priority_fee_request() {
         fallback_indexer_url: None,
         rpc_pool_failure_threshold: 3,
         rpc_pool_primary_probe_interval_secs: 30,
+        state_dir: None,
+        state_checkpoint_interval_seconds: 30,
+        staked_rpc_url: None,
+        block_engine_url: None,
+        bundle_tip_account: None,
+        bundle_tip_lamports: 10_000,
+        tpu_fanout_slots: 12,
+        batch_append_sender: Default::default(),
+        batch_nullify_sender: Default::default(),
+        batch_address_append_sender: Default::default(),
+        compress_and_close_sender: Default::default(),
+        rollover_sender: Default::default(),
+        dry_run: false,
+        shard_instance_id: None,
+        shard_peers: vec![],
+        shard_peer_timeout_seconds: 60,
+        alert_webhook_urls: vec![],
+        alert_file: None,
+        alert_min_balance_sol: 1.0,
+        alert_indexer_lag_slots: 150,
+        alert_missed_light_slots: 3,
+        alert_repeat_interval_seconds: 3600,
     };
 
     let config = ForesterConfig::new_for_start(&args).expect("Failed to create config");
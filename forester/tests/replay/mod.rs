@@ -0,0 +1,1167 @@
+//! Deterministic replay harness for the forester.
+//!
+//! The harness runs the real forester pipeline against an in-process
+//! `LightProgramTest` (LiteSVM) with its `TestIndexer`, instead of a local
+//! validator, photon and prover:
+//!
+//! - [`SharedTestRpc`] implements `Rpc` and `Indexer` on top of one shared
+//!   `LightProgramTest`. The forester's connection pool opens connections by
+//!   URL, so each harness registers itself under a `litesvm://<id>` URL.
+//! - Time is controlled by the test. Tests run with `start_paused = true`
+//!   and the harness clock advances the SVM by one slot with
+//!   `TestRpc::warp_slot_forward` every `slot_duration()` of virtual time.
+//! - Faults are injected with [`Faults`] (failing RPC and indexer calls),
+//!   [`ReplayHarness::freeze_indexer`] (a stale indexer),
+//!   [`UnresponsiveProver`] (prover timeouts) and [`ReplayHarness::svm`]
+//!   (transactions of a competing forester).
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use borsh::BorshDeserialize;
+use forester::{
+    epoch_manager::WorkReport, run_pipeline, slot_tracker::slot_duration, ForesterConfig,
+};
+use forester_utils::forester_epoch::{get_epoch_phases, EpochPhases};
+use light_batched_merkle_tree::merkle_tree::BatchedMerkleTreeAccount;
+use light_client::{
+    indexer::{
+        Address, AddressWithTree, CompressedAccount, CompressedAccountEvent,
+        CompressedTokenAccount, GetCompressedAccountsByOwnerConfig,
+        GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash, Indexer, IndexerError,
+        IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
+        NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
+        QueueElementsV2Options, QueueInfoResult, Response, RetryConfig, SignatureWithMetadata,
+        TokenAccountEvent, TokenBalance, TreeInfo, ValidityProofWithContext,
+    },
+    interface::{AccountInterface, MintInterface, TokenAccountInterface},
+    rpc::{LightClientConfig, LightSimulationResult, Rpc, RpcError},
+};
+use light_compressed_account::compressed_account::CompressedAccount as OutputAccount;
+use light_event::event::{BatchPublicTransactionEvent, PublicTransactionEvent};
+use light_program_test::{
+    accounts::test_accounts::TestAccounts, indexer::TestIndexer, program_test::TestRpc,
+    utils::register_test_forester::register_test_forester, LightProgramTest, ProgramTestConfig,
+};
+use light_registry::{
+    protocol_config::state::ProtocolConfig, sdk::create_register_forester_epoch_pda_instruction,
+    utils::get_forester_epoch_pda_from_authority, ForesterConfig as RegistryForesterConfig,
+    ForesterEpochPda,
+};
+use light_test_utils::system_program::create_invoke_instruction;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_rpc_client_api::config::RpcSendTransactionConfig;
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::AddressLookupTableAccount,
+    clock::Slot,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use tokio::{
+    sync::{mpsc, oneshot, MutexGuard},
+    task::JoinHandle,
+    time::sleep,
+};
+
+use crate::test_utils::forester_config;
+
+/// Harnesses by URL, looked up by [`SharedTestRpc::new`].
+static HARNESSES: LazyLock<Mutex<HashMap<String, Arc<ReplayState>>>> =
+    LazyLock::new(Default::default);
+static NEXT_HARNESS_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Slots for which a blockhash stays valid, as on a real cluster.
+const BLOCKHASH_VALIDITY_SLOTS: u64 = 150;
+
+/// Runs the `Rpc` method `$method` on the shared `LightProgramTest` unless a
+/// fault is injected into it.
+macro_rules! svm_call {
+    ($self:ident, $method:ident($($arg:expr),*)) => {{
+        $self.state.faults.check_rpc(stringify!($method))?;
+        let mut svm = $self.state.svm.lock().await;
+        <LightProgramTest as Rpc>::$method(&mut *svm, $($arg),*).await
+    }};
+}
+
+/// Runs the `Indexer` method `$method` on the frozen indexer if there is one,
+/// on the live `TestIndexer` otherwise, unless a fault is injected into it.
+macro_rules! indexer_call {
+    ($self:ident, $method:ident($($arg:expr),*)) => {{
+        $self.state.faults.check_indexer(stringify!($method))?;
+        let mut frozen = $self.state.frozen_indexer.lock().await;
+        match frozen.as_mut() {
+            Some(frozen) => <TestIndexer as Indexer>::$method(&mut frozen.indexer, $($arg),*).await,
+            None => {
+                let mut svm = $self.state.svm.lock().await;
+                <LightProgramTest as Indexer>::$method(&mut *svm, $($arg),*).await
+            }
+        }
+    }};
+}
+
+/// Faults injected into RPC and indexer calls, by method name.
+#[derive(Debug, Default)]
+pub struct Faults {
+    pending: Mutex<HashMap<&'static str, usize>>,
+    injected: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl Faults {
+    /// Fails the next `count` calls of the `Rpc` or `Indexer` method `method`.
+    pub fn fail_next(&self, method: &'static str, count: usize) {
+        *self.pending.lock().unwrap().entry(method).or_default() += count;
+    }
+
+    /// Returns how many calls of `method` failed with an injected fault.
+    pub fn injected(&self, method: &str) -> usize {
+        self.injected
+            .lock()
+            .unwrap()
+            .get(method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn take(&self, method: &'static str) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get_mut(method) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                *self.injected.lock().unwrap().entry(method).or_default() += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn check_rpc(&self, method: &'static str) -> Result<(), RpcError> {
+        if self.take(method) {
+            return Err(RpcError::CustomError(format!("injected fault in {method}")));
+        }
+        Ok(())
+    }
+
+    fn check_indexer(&self, method: &'static str) -> Result<(), IndexerError> {
+        if self.take(method) {
+            return Err(IndexerError::CustomError(format!(
+                "injected fault in {method}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Indexer state captured by [`ReplayHarness::freeze_indexer`].
+struct FrozenIndexer {
+    indexer: TestIndexer,
+    slot: Slot,
+}
+
+/// State shared by a harness and all of its connections.
+pub struct ReplayState {
+    svm: tokio::sync::Mutex<LightProgramTest>,
+    frozen_indexer: tokio::sync::Mutex<Option<FrozenIndexer>>,
+    faults: Faults,
+    /// Slot and error of the transactions sent through a connection.
+    statuses: Mutex<HashMap<Signature, (Slot, Option<TransactionError>)>>,
+    payer: Keypair,
+    state_trees: Vec<TreeInfo>,
+    random_state_tree: Option<TreeInfo>,
+    random_state_tree_v1: Option<TreeInfo>,
+    address_tree_v1: TreeInfo,
+    address_tree_v2: TreeInfo,
+}
+
+impl ReplayState {
+    /// Records the outcome of a transaction so that its status can be
+    /// queried. Transaction errors are reported through the status, like a
+    /// validator does for transactions sent without preflight.
+    async fn record(
+        &self,
+        signature: Signature,
+        result: Result<Signature, RpcError>,
+    ) -> Result<Signature, RpcError> {
+        let error = match result {
+            Ok(_) => None,
+            Err(RpcError::TransactionError(error)) => Some(error),
+            Err(error) => return Err(error),
+        };
+        let slot = self.svm.lock().await.get_slot().await?;
+        self.statuses
+            .lock()
+            .unwrap()
+            .insert(signature, (slot, error));
+        Ok(signature)
+    }
+}
+
+/// A connection to the `LightProgramTest` of a [`ReplayHarness`].
+pub struct SharedTestRpc {
+    url: String,
+    state: Arc<ReplayState>,
+    payer: Keypair,
+}
+
+impl fmt::Debug for SharedTestRpc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedTestRpc")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl SharedTestRpc {
+    async fn process(
+        &self,
+        method: &'static str,
+        transaction: VersionedTransaction,
+    ) -> Result<Signature, RpcError> {
+        self.state.faults.check_rpc(method)?;
+        let signature = transaction.signatures[0];
+        let result = self
+            .state
+            .svm
+            .lock()
+            .await
+            .process_versioned_transaction(transaction)
+            .await;
+        self.state.record(signature, result).await
+    }
+}
+
+#[async_trait]
+impl Rpc for SharedTestRpc {
+    async fn new(config: LightClientConfig) -> Result<Self, RpcError>
+    where
+        Self: Sized,
+    {
+        let state = HARNESSES
+            .lock()
+            .unwrap()
+            .get(&config.url)
+            .cloned()
+            .ok_or_else(|| RpcError::CustomError(format!("no replay harness at {}", config.url)))?;
+        Ok(Self {
+            url: config.url,
+            payer: state.payer.insecure_clone(),
+            state,
+        })
+    }
+
+    fn get_payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    fn get_url(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn health(&self) -> Result<(), RpcError> {
+        self.state.faults.check_rpc("health")
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, RpcError> {
+        svm_call!(self, get_program_accounts(program_id))
+    }
+
+    async fn get_program_accounts_with_discriminator(
+        &self,
+        program_id: &Pubkey,
+        discriminator: &[u8],
+    ) -> Result<Vec<(Pubkey, Account)>, RpcError> {
+        svm_call!(
+            self,
+            get_program_accounts_with_discriminator(program_id, discriminator)
+        )
+    }
+
+    async fn confirm_transaction(&self, signature: Signature) -> Result<bool, RpcError> {
+        self.state.faults.check_rpc("confirm_transaction")?;
+        Ok(matches!(
+            self.state.statuses.lock().unwrap().get(&signature),
+            Some((_, None))
+        ))
+    }
+
+    async fn get_account(&self, address: Pubkey) -> Result<Option<Account>, RpcError> {
+        svm_call!(self, get_account(address))
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, RpcError> {
+        svm_call!(self, get_multiple_accounts(addresses))
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        data_len: usize,
+    ) -> Result<u64, RpcError> {
+        svm_call!(self, get_minimum_balance_for_rent_exemption(data_len))
+    }
+
+    async fn airdrop_lamports(
+        &mut self,
+        to: &Pubkey,
+        lamports: u64,
+    ) -> Result<Signature, RpcError> {
+        svm_call!(self, airdrop_lamports(to, lamports))
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, RpcError> {
+        svm_call!(self, get_balance(pubkey))
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<(solana_sdk::hash::Hash, u64), RpcError> {
+        let (blockhash, slot) = svm_call!(self, get_latest_blockhash())?;
+        Ok((blockhash, slot + BLOCKHASH_VALIDITY_SLOTS))
+    }
+
+    async fn get_block_height(&self) -> Result<u64, RpcError> {
+        svm_call!(self, get_block_height())
+    }
+
+    async fn get_slot(&self) -> Result<u64, RpcError> {
+        svm_call!(self, get_slot())
+    }
+
+    async fn get_transaction_slot(&self, signature: &Signature) -> Result<u64, RpcError> {
+        self.state.faults.check_rpc("get_transaction_slot")?;
+        self.state
+            .statuses
+            .lock()
+            .unwrap()
+            .get(signature)
+            .map(|(slot, _)| *slot)
+            .ok_or_else(|| RpcError::CustomError(format!("unknown transaction {signature}")))
+    }
+
+    async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> Result<Vec<Option<TransactionStatus>>, RpcError> {
+        self.state.faults.check_rpc("get_signature_statuses")?;
+        let statuses = self.state.statuses.lock().unwrap();
+        Ok(signatures
+            .iter()
+            .map(|signature| {
+                statuses
+                    .get(signature)
+                    .map(|(slot, error)| TransactionStatus {
+                        slot: *slot,
+                        confirmations: None,
+                        status: error.clone().map_or(Ok(()), Err),
+                        err: error.clone(),
+                        confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+                    })
+            })
+            .collect())
+    }
+
+    async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, RpcError> {
+        self.process("send_transaction", transaction.clone().into())
+            .await
+    }
+
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &Transaction,
+        _config: RpcSendTransactionConfig,
+    ) -> Result<Signature, RpcError> {
+        self.process("send_transaction_with_config", transaction.clone().into())
+            .await
+    }
+
+    async fn send_versioned_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        _config: RpcSendTransactionConfig,
+    ) -> Result<Signature, RpcError> {
+        self.process(
+            "send_versioned_transaction_with_config",
+            transaction.clone(),
+        )
+        .await
+    }
+
+    async fn process_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<Signature, RpcError> {
+        let signature = transaction.signatures[0];
+        let result = svm_call!(self, process_transaction(transaction));
+        self.state.record(signature, result.clone()).await?;
+        result
+    }
+
+    async fn process_versioned_transaction(
+        &mut self,
+        transaction: VersionedTransaction,
+    ) -> Result<Signature, RpcError> {
+        let signature = transaction.signatures[0];
+        let result = svm_call!(self, process_versioned_transaction(transaction));
+        self.state.record(signature, result.clone()).await?;
+        result
+    }
+
+    async fn process_transaction_with_context(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<(Signature, Slot), RpcError> {
+        svm_call!(self, process_transaction_with_context(transaction))
+    }
+
+    async fn create_and_send_transaction_with_event<T>(
+        &mut self,
+        instructions: &[Instruction],
+        authority: &Pubkey,
+        signers: &[&Keypair],
+    ) -> Result<Option<(T, Signature, Slot)>, RpcError>
+    where
+        T: BorshDeserialize + Send + fmt::Debug,
+    {
+        self.state
+            .faults
+            .check_rpc("create_and_send_transaction_with_event")?;
+        let mut svm = self.state.svm.lock().await;
+        // `TestRpc` has a method of the same name.
+        <LightProgramTest as Rpc>::create_and_send_transaction_with_event::<T>(
+            &mut *svm,
+            instructions,
+            authority,
+            signers,
+        )
+        .await
+    }
+
+    async fn create_and_send_versioned_transaction<'a>(
+        &'a mut self,
+        instructions: &'a [Instruction],
+        payer: &'a Pubkey,
+        signers: &'a [&'a Keypair],
+        address_lookup_tables: &'a [AddressLookupTableAccount],
+    ) -> Result<Signature, RpcError> {
+        svm_call!(
+            self,
+            create_and_send_versioned_transaction(
+                instructions,
+                payer,
+                signers,
+                address_lookup_tables
+            )
+        )
+    }
+
+    async fn create_and_send_transaction_with_public_event(
+        &mut self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&Keypair],
+    ) -> Result<Option<(PublicTransactionEvent, Signature, Slot)>, RpcError> {
+        svm_call!(
+            self,
+            create_and_send_transaction_with_public_event(instructions, payer, signers)
+        )
+    }
+
+    async fn create_and_send_transaction_with_batched_event(
+        &mut self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&Keypair],
+    ) -> Result<Option<(Vec<BatchPublicTransactionEvent>, Signature, Slot)>, RpcError> {
+        svm_call!(
+            self,
+            create_and_send_transaction_with_batched_event(instructions, payer, signers)
+        )
+    }
+
+    async fn simulate_light_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<LightSimulationResult, RpcError> {
+        svm_call!(self, simulate_light_transaction(transaction))
+    }
+
+    fn indexer(&self) -> Result<&impl Indexer, RpcError> {
+        Ok(self)
+    }
+
+    fn indexer_mut(&mut self) -> Result<&mut impl Indexer, RpcError> {
+        Ok(self)
+    }
+
+    async fn get_latest_active_state_trees(&mut self) -> Result<Vec<TreeInfo>, RpcError> {
+        svm_call!(self, get_latest_active_state_trees())
+    }
+
+    fn get_state_tree_infos(&self) -> Vec<TreeInfo> {
+        self.state.state_trees.clone()
+    }
+
+    fn get_random_state_tree_info(&self) -> Result<TreeInfo, RpcError> {
+        self.state
+            .random_state_tree
+            .ok_or(RpcError::NoStateTreesAvailable)
+    }
+
+    fn get_random_state_tree_info_v1(&self) -> Result<TreeInfo, RpcError> {
+        self.state
+            .random_state_tree_v1
+            .ok_or(RpcError::NoStateTreesAvailable)
+    }
+
+    fn get_address_tree_v1(&self) -> TreeInfo {
+        self.state.address_tree_v1
+    }
+
+    fn get_address_tree_v2(&self) -> TreeInfo {
+        self.state.address_tree_v2
+    }
+
+    async fn get_account_interface(
+        &self,
+        address: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<AccountInterface>>, RpcError> {
+        svm_call!(self, get_account_interface(address, config))
+    }
+
+    async fn get_token_account_interface(
+        &self,
+        address: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<TokenAccountInterface>>, RpcError> {
+        svm_call!(self, get_token_account_interface(address, config))
+    }
+
+    async fn get_associated_token_account_interface(
+        &self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<TokenAccountInterface>>, RpcError> {
+        svm_call!(
+            self,
+            get_associated_token_account_interface(owner, mint, config)
+        )
+    }
+
+    async fn get_multiple_account_interfaces(
+        &self,
+        addresses: Vec<&Pubkey>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Vec<Option<AccountInterface>>>, RpcError> {
+        svm_call!(self, get_multiple_account_interfaces(addresses, config))
+    }
+
+    async fn get_mint_interface(
+        &self,
+        address: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<MintInterface>>, RpcError> {
+        svm_call!(self, get_mint_interface(address, config))
+    }
+}
+
+#[async_trait]
+impl Indexer for SharedTestRpc {
+    async fn get_compressed_account(
+        &self,
+        address: Address,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        indexer_call!(self, get_compressed_account(address, config))
+    }
+
+    async fn get_compressed_account_by_hash(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        indexer_call!(self, get_compressed_account_by_hash(hash, config))
+    }
+
+    async fn get_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedAccountsByOwnerConfig>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedAccount>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_accounts_by_owner(owner, options, config)
+        )
+    }
+
+    async fn get_compressed_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        indexer_call!(self, get_compressed_balance(address, hash, config))
+    }
+
+    async fn get_compressed_balance_by_owner(
+        &self,
+        owner: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        indexer_call!(self, get_compressed_balance_by_owner(owner, config))
+    }
+
+    async fn get_compressed_mint_token_holders(
+        &self,
+        mint: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_mint_token_holders(mint, options, config)
+        )
+    }
+
+    async fn get_compressed_token_account_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_token_account_balance(address, hash, config)
+        )
+    }
+
+    async fn get_compressed_token_accounts_by_delegate(
+        &self,
+        delegate: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_token_accounts_by_delegate(delegate, options, config)
+        )
+    }
+
+    async fn get_compressed_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_token_accounts_by_owner(owner, options, config)
+        )
+    }
+
+    async fn get_compressed_token_balances_by_owner_v2(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<TokenBalance>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compressed_token_balances_by_owner_v2(owner, options, config)
+        )
+    }
+
+    async fn get_compression_signatures_for_account(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
+        indexer_call!(self, get_compression_signatures_for_account(hash, config))
+    }
+
+    async fn get_compression_signatures_for_address(
+        &self,
+        address: &[u8; 32],
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compression_signatures_for_address(address, options, config)
+        )
+    }
+
+    async fn get_compression_signatures_for_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compression_signatures_for_owner(owner, options, config)
+        )
+    }
+
+    async fn get_compression_signatures_for_token_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_compression_signatures_for_token_owner(owner, options, config)
+        )
+    }
+
+    async fn get_indexer_health(&self, config: Option<RetryConfig>) -> Result<bool, IndexerError> {
+        indexer_call!(self, get_indexer_health(config))
+    }
+
+    /// The live indexer is at the current slot, a frozen one at the slot it
+    /// was frozen at.
+    async fn get_indexer_slot(&self, _config: Option<RetryConfig>) -> Result<u64, IndexerError> {
+        self.state.faults.check_indexer("get_indexer_slot")?;
+        if let Some(frozen) = self.state.frozen_indexer.lock().await.as_ref() {
+            return Ok(frozen.slot);
+        }
+        self.state
+            .svm
+            .lock()
+            .await
+            .get_slot()
+            .await
+            .map_err(|e| IndexerError::CustomError(e.to_string()))
+    }
+
+    async fn get_multiple_compressed_account_proofs(
+        &self,
+        hashes: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<MerkleProof>>, IndexerError> {
+        indexer_call!(self, get_multiple_compressed_account_proofs(hashes, config))
+    }
+
+    async fn get_multiple_compressed_accounts(
+        &self,
+        addresses: Option<Vec<Address>>,
+        hashes: Option<Vec<Hash>>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<Option<CompressedAccount>>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_multiple_compressed_accounts(addresses, hashes, config)
+        )
+    }
+
+    async fn get_multiple_new_address_proofs(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        addresses: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<NewAddressProofWithContext>>, IndexerError> {
+        indexer_call!(
+            self,
+            get_multiple_new_address_proofs(merkle_tree_pubkey, addresses, config)
+        )
+    }
+
+    async fn get_validity_proof(
+        &self,
+        hashes: Vec<Hash>,
+        new_addresses_with_trees: Vec<AddressWithTree>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ValidityProofWithContext>, IndexerError> {
+        indexer_call!(
+            self,
+            get_validity_proof(hashes, new_addresses_with_trees, config)
+        )
+    }
+
+    async fn get_queue_elements(
+        &mut self,
+        merkle_tree_pubkey: [u8; 32],
+        options: QueueElementsV2Options,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueElementsResult>, IndexerError> {
+        indexer_call!(
+            self,
+            get_queue_elements(merkle_tree_pubkey, options, config)
+        )
+    }
+
+    async fn get_queue_info(
+        &self,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueInfoResult>, IndexerError> {
+        indexer_call!(self, get_queue_info(config))
+    }
+
+    async fn get_subtrees(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<[u8; 32]>>, IndexerError> {
+        indexer_call!(self, get_subtrees(merkle_tree_pubkey, config))
+    }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        indexer_call!(self, subscribe_compressed_accounts_by_owner(owner))
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        indexer_call!(self, subscribe_token_accounts_by_owner(owner, mint))
+    }
+}
+
+/// A prover endpoint which accepts connections and never responds.
+pub struct UnresponsiveProver {
+    url: String,
+    connections: Arc<AtomicUsize>,
+}
+
+impl UnresponsiveProver {
+    pub fn spawn() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind prover listener");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        thread::spawn(move || {
+            // Keep the streams open so that requests hang instead of failing.
+            let mut streams = Vec::new();
+            for stream in listener.incoming().flatten() {
+                accepted.fetch_add(1, Ordering::Relaxed);
+                streams.push(stream);
+            }
+        });
+        Self { url, connections }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+}
+
+/// A forester pipeline started by [`ReplayHarness::start_forester`].
+pub struct ReplayForester {
+    pub config: Arc<ForesterConfig>,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<forester::Result<()>>,
+    work_reports: mpsc::Receiver<WorkReport>,
+}
+
+impl ReplayForester {
+    /// Shuts the pipeline down and returns the work reports it sent.
+    pub async fn stop(mut self) -> Vec<WorkReport> {
+        let _ = self.shutdown.send(());
+        self.handle
+            .await
+            .expect("forester task panicked")
+            .expect("forester pipeline failed");
+        let mut reports = Vec::new();
+        while let Ok(report) = self.work_reports.try_recv() {
+            reports.push(report);
+        }
+        reports
+    }
+}
+
+/// Runs foresters against a shared `LightProgramTest` on a virtual clock.
+///
+/// Tests using the harness must run with `#[tokio::test(start_paused = true)]`.
+pub struct ReplayHarness {
+    url: String,
+    state: Arc<ReplayState>,
+    pub test_accounts: TestAccounts,
+    pub protocol_config: ProtocolConfig,
+    /// Authority of the forester, registered with the governance authority.
+    pub forester: Keypair,
+    clock: JoinHandle<()>,
+}
+
+impl ReplayHarness {
+    /// Short epochs, so that tests cross several of them quickly.
+    pub fn default_protocol_config() -> ProtocolConfig {
+        ProtocolConfig {
+            genesis_slot: 0,
+            slot_length: 10,
+            registration_phase_length: 100,
+            active_phase_length: 200,
+            report_work_phase_length: 100,
+            ..ProtocolConfig::default()
+        }
+    }
+
+    pub async fn new() -> Self {
+        let mut config = ProgramTestConfig::default_test_forester(false);
+        config.protocol_config = Self::default_protocol_config();
+        // The forester under test registers itself.
+        config.with_forester = false;
+        Self::with_config(config).await
+    }
+
+    pub async fn with_config(config: ProgramTestConfig) -> Self {
+        let protocol_config = config.protocol_config;
+        let mut svm = LightProgramTest::new(config)
+            .await
+            .expect("LightProgramTest::new");
+        let test_accounts = svm.test_accounts.clone();
+
+        let forester = Keypair::new();
+        svm.airdrop_lamports(&forester.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+        register_test_forester(
+            &mut svm,
+            &test_accounts.protocol.governance_authority,
+            &forester.pubkey(),
+            RegistryForesterConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        let state = Arc::new(ReplayState {
+            frozen_indexer: Default::default(),
+            faults: Faults::default(),
+            statuses: Default::default(),
+            payer: svm.get_payer().insecure_clone(),
+            state_trees: svm.get_state_tree_infos(),
+            random_state_tree: svm.get_random_state_tree_info().ok(),
+            random_state_tree_v1: svm.get_random_state_tree_info_v1().ok(),
+            address_tree_v1: svm.get_address_tree_v1(),
+            address_tree_v2: svm.get_address_tree_v2(),
+            svm: tokio::sync::Mutex::new(svm),
+        });
+        let url = format!(
+            "litesvm://{}",
+            NEXT_HARNESS_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        HARNESSES.lock().unwrap().insert(url.clone(), state.clone());
+
+        let clock = tokio::spawn({
+            let state = state.clone();
+            async move {
+                loop {
+                    sleep(slot_duration()).await;
+                    state
+                        .svm
+                        .lock()
+                        .await
+                        .warp_slot_forward(1)
+                        .await
+                        .expect("warp_slot_forward");
+                }
+            }
+        });
+
+        Self {
+            url,
+            state,
+            test_accounts,
+            protocol_config,
+            forester,
+            clock,
+        }
+    }
+
+    pub fn faults(&self) -> &Faults {
+        &self.state.faults
+    }
+
+    /// Locks the shared `LightProgramTest`, e.g. to send the transactions of
+    /// a competing forester. The clock stops while the lock is held.
+    pub async fn svm(&self) -> MutexGuard<'_, LightProgramTest> {
+        self.state.svm.lock().await
+    }
+
+    pub async fn slot(&self) -> Slot {
+        self.svm().await.get_slot().await.unwrap()
+    }
+
+    pub fn phases(&self, epoch: u64) -> EpochPhases {
+        get_epoch_phases(&self.protocol_config, epoch)
+    }
+
+    /// Lets the clock and the foresters run until `slot` is reached.
+    pub async fn advance_to_slot(&self, slot: Slot) {
+        while self.slot().await < slot {
+            sleep(slot_duration()).await;
+        }
+    }
+
+    /// Serves all indexer calls from a copy of the current indexer state
+    /// until [`Self::unfreeze_indexer`], like an indexer which stopped at
+    /// the current slot.
+    pub async fn freeze_indexer(&self) {
+        let (snapshot, slot) = {
+            let svm = self.svm().await;
+            let snapshot = svm.indexer().unwrap().snapshot().unwrap();
+            (snapshot, svm.get_slot().await.unwrap())
+        };
+        let indexer = TestIndexer::from_snapshot(
+            snapshot,
+            self.test_accounts.protocol.forester.insecure_clone(),
+            self.test_accounts
+                .protocol
+                .governance_authority
+                .insecure_clone(),
+        )
+        .unwrap();
+        *self.state.frozen_indexer.lock().await = Some(FrozenIndexer { indexer, slot });
+    }
+
+    /// Lets the indexer catch up with everything it missed while frozen.
+    pub async fn unfreeze_indexer(&self) {
+        *self.state.frozen_indexer.lock().await = None;
+    }
+
+    /// Config of a forester for [`Self::forester`] connected to this harness.
+    pub fn forester_config(&self) -> ForesterConfig {
+        let mut config = forester_config();
+        config.external_services.rpc_url = self.url.clone();
+        config.external_services.ws_rpc_url = None;
+        config.external_services.indexer_url = Some(self.url.clone());
+        config.external_services.prover_max_wait_time = Some(Duration::from_secs(30));
+        config.payer_keypair = self.forester.insecure_clone();
+        config.derivation_pubkey = self.forester.pubkey();
+        config
+    }
+
+    pub fn start_forester(&self, config: ForesterConfig) -> ReplayForester {
+        let config = Arc::new(config);
+        let (shutdown, shutdown_receiver) = oneshot::channel();
+        let (work_report_sender, work_reports) = mpsc::channel(100);
+        let handle = tokio::spawn(run_pipeline::<SharedTestRpc>(
+            config.clone(),
+            None,
+            None,
+            shutdown_receiver,
+            None,
+            None,
+            work_report_sender,
+        ));
+        ReplayForester {
+            config,
+            shutdown,
+            handle,
+            work_reports,
+        }
+    }
+
+    pub async fn forester_epoch_pda(&self, epoch: u64) -> Option<ForesterEpochPda> {
+        let pda = get_forester_epoch_pda_from_authority(&self.forester.pubkey(), epoch).0;
+        self.svm()
+            .await
+            .get_anchor_account::<ForesterEpochPda>(&pda)
+            .await
+            .unwrap()
+    }
+
+    /// Registers [`Self::forester`] for `epoch` from outside the pipeline,
+    /// like a second forester instance with the same authority.
+    pub async fn register_competing_forester(&self, epoch: u64) {
+        let instruction = create_register_forester_epoch_pda_instruction(
+            &self.forester.pubkey(),
+            &self.forester.pubkey(),
+            epoch,
+        );
+        self.svm()
+            .await
+            .create_and_send_transaction(&[instruction], &self.forester.pubkey(), &[&self.forester])
+            .await
+            .unwrap();
+    }
+
+    /// Compresses `count` accounts into the output queue of the first v2
+    /// state tree and returns the tree.
+    pub async fn fill_output_queue(&self, count: u64) -> Pubkey {
+        let tree = self.test_accounts.v2_state_trees[0];
+        let user = Keypair::new();
+        let mut svm = self.svm().await;
+        svm.airdrop_lamports(&user.pubkey(), LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+        for i in 0..count {
+            // Distinct amounts keep the transactions distinct under the
+            // constant LiteSVM blockhash.
+            let lamports = 1_000_000 + i;
+            let instruction = create_invoke_instruction(
+                &user.pubkey(),
+                &user.pubkey(),
+                &[],
+                &[OutputAccount {
+                    lamports,
+                    owner: user.pubkey().into(),
+                    address: None,
+                    data: None,
+                }],
+                &[],
+                &[tree.output_queue],
+                &[],
+                &[],
+                None,
+                Some(lamports),
+                true,
+                None,
+                true,
+            );
+            svm.create_and_send_transaction(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(1_000_000),
+                    instruction,
+                ],
+                &user.pubkey(),
+                &[&user],
+            )
+            .await
+            .unwrap();
+        }
+        tree.merkle_tree
+    }
+
+    pub async fn state_tree_next_index(&self, tree: Pubkey) -> u64 {
+        let mut account = self.svm().await.get_account(tree).await.unwrap().unwrap();
+        BatchedMerkleTreeAccount::state_from_bytes(account.data.as_mut_slice(), &tree.into())
+            .unwrap()
+            .get_metadata()
+            .next_index
+    }
+}
+
+impl Drop for ReplayHarness {
+    fn drop(&mut self) {
+        self.clock.abort();
+        HARNESSES.lock().unwrap().remove(&self.url);
+    }
+}
@@ -0,0 +1,135 @@
+//! Deterministic forester tests on LiteSVM, see `replay/mod.rs`.
+//!
+//! Run with: cargo test -p forester --test replay_test -- --nocapture
+
+use forester::alerts::AlertRule;
+use serial_test::serial;
+
+mod replay;
+mod test_utils;
+use replay::{ReplayHarness, UnresponsiveProver};
+
+/// The forester registers for consecutive epochs and reports its work for
+/// each of them.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_replay_epoch_transition() {
+    let harness = ReplayHarness::new().await;
+    let forester = harness.start_forester(harness.forester_config());
+
+    harness
+        .advance_to_slot(harness.phases(1).report_work.end)
+        .await;
+    let reports = forester.stop().await;
+
+    for epoch in 0..2 {
+        let pda = harness
+            .forester_epoch_pda(epoch)
+            .await
+            .unwrap_or_else(|| panic!("forester not registered for epoch {epoch}"));
+        assert!(pda.has_reported_work, "work of epoch {epoch} not reported");
+        assert!(
+            reports.iter().any(|report| report.epoch == epoch),
+            "no work report for epoch {epoch}"
+        );
+    }
+}
+
+/// Failed registration transactions are retried within the registration
+/// phase.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_replay_registration_survives_rpc_errors() {
+    let harness = ReplayHarness::new().await;
+    harness.faults().fail_next("process_transaction", 3);
+    let forester = harness.start_forester(harness.forester_config());
+
+    harness
+        .advance_to_slot(harness.phases(0).report_work.end)
+        .await;
+    forester.stop().await;
+
+    assert_eq!(harness.faults().injected("process_transaction"), 3);
+    let pda = harness
+        .forester_epoch_pda(0)
+        .await
+        .expect("forester not registered for epoch 0");
+    assert!(pda.has_reported_work);
+}
+
+/// A registration sent by another instance with the same authority is
+/// recovered instead of failing the epoch.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_replay_competing_registration() {
+    let harness = ReplayHarness::new().await;
+    harness.register_competing_forester(0).await;
+    let forester = harness.start_forester(harness.forester_config());
+
+    harness
+        .advance_to_slot(harness.phases(0).report_work.end)
+        .await;
+    let reports = forester.stop().await;
+
+    assert!(
+        harness
+            .forester_epoch_pda(0)
+            .await
+            .unwrap()
+            .has_reported_work
+    );
+    assert!(reports.iter().any(|report| report.epoch == 0));
+}
+
+/// An indexer which stops following the chain raises the indexer lag alert,
+/// which resolves once the indexer caught up.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_replay_stale_indexer() {
+    let harness = ReplayHarness::new().await;
+    let forester = harness.start_forester(harness.forester_config());
+    let alerts = forester.config.alert_manager.clone();
+    let lag_threshold = alerts.thresholds().indexer_lag_slots;
+
+    harness.freeze_indexer().await;
+    let frozen_at = harness.slot().await;
+    harness.advance_to_slot(frozen_at + 2 * lag_threshold).await;
+    assert!(alerts.is_active(AlertRule::IndexerLag, ""));
+
+    harness.unfreeze_indexer().await;
+    let unfrozen_at = harness.slot().await;
+    harness.advance_to_slot(unfrozen_at + lag_threshold).await;
+    assert!(!alerts.is_active(AlertRule::IndexerLag, ""));
+
+    forester.stop().await;
+}
+
+/// A prover which never answers leaves the batch unprocessed without
+/// stalling the epoch.
+#[tokio::test(start_paused = true)]
+#[serial]
+async fn test_replay_prover_timeout() {
+    let harness = ReplayHarness::new().await;
+    let prover = UnresponsiveProver::spawn();
+    let tree = harness.fill_output_queue(10).await;
+    let mut config = harness.forester_config();
+    config.external_services.prover_url = Some(prover.url());
+    config.external_services.prover_append_url = Some(prover.url());
+    config.external_services.prover_update_url = Some(prover.url());
+    config.external_services.prover_address_append_url = Some(prover.url());
+    let forester = harness.start_forester(config);
+
+    harness
+        .advance_to_slot(harness.phases(0).report_work.end)
+        .await;
+    forester.stop().await;
+
+    assert_eq!(harness.state_tree_next_index(tree).await, 0);
+    assert!(
+        harness
+            .forester_epoch_pda(0)
+            .await
+            .unwrap()
+            .has_reported_work
+    );
+}
@@ -0,0 +1,192 @@
+use std::{sync::Arc, time::Duration};
+
+use forester::{
+    config::{ForesterConfig, GeneralConfig},
+    run_pipeline,
+};
+use light_batched_merkle_tree::queue::BatchedQueueAccount;
+use light_client::{
+    indexer::{
+        local::{LocalIndexer, LocalIndexerConfig},
+        TreeInfo,
+    },
+    local_test_validator::LightValidatorConfig,
+    rpc::{client::RpcUrl, LightClient, LightClientConfig, Rpc},
+};
+use light_compressed_account::{compressed_account::CompressedAccount, TreeType};
+use light_program_test::accounts::test_accounts::TestAccounts;
+use light_test_utils::{register_test_forester, system_program::create_invoke_instruction};
+use serial_test::serial;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::timeout,
+};
+
+mod test_utils;
+use test_utils::{forester_config, init};
+
+/// Leaves of owners the forester knows nothing about are migrated from the
+/// v1 state tree to the v2 output queue while the v1 nullifier queue is empty.
+#[tokio::test(flavor = "multi_thread", worker_threads = 16)]
+#[serial]
+async fn test_state_migration_in_idle_slots() {
+    init(Some(LightValidatorConfig {
+        enable_indexer: false,
+        enable_prover: false,
+        wait_time: 10,
+        sbf_programs: vec![],
+        upgradeable_programs: vec![],
+        limit_ledger_size: None,
+        use_surfpool: true,
+        validator_args: vec![],
+    }))
+    .await;
+
+    let forester_keypair = Keypair::new();
+    let env = TestAccounts::get_local_test_validator_accounts();
+    let v1_state_tree = env.v1_state_trees[0];
+    let output_queue = env.v2_state_trees[0].output_queue;
+
+    let mut config = forester_config();
+    config.payer_keypair = forester_keypair.insecure_clone();
+    config.derivation_pubkey = forester_keypair.pubkey();
+    config.general_config = GeneralConfig {
+        skip_v1_address_trees: true,
+        skip_v2_state_trees: true,
+        skip_v2_address_trees: true,
+        sleep_after_processing_ms: 50,
+        sleep_when_idle_ms: 100,
+        enable_state_migration: true,
+        migration_output_queue: Some(output_queue),
+        ..Default::default()
+    };
+
+    let mut rpc = LightClient::new(LightClientConfig {
+        url: RpcUrl::Localnet.to_string(),
+        photon_url: None,
+        commitment_config: Some(CommitmentConfig::confirmed()),
+        fetch_active_tree: false,
+    })
+    .await
+    .unwrap();
+    rpc.airdrop_lamports(&forester_keypair.pubkey(), LAMPORTS_PER_SOL * 100_000)
+        .await
+        .unwrap();
+    rpc.airdrop_lamports(
+        &env.protocol.governance_authority.pubkey(),
+        LAMPORTS_PER_SOL * 100_000,
+    )
+    .await
+    .unwrap();
+    register_test_forester(
+        &mut rpc,
+        &env.protocol.governance_authority,
+        &forester_keypair.pubkey(),
+        light_registry::ForesterConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    // Accounts of two owners which are not configured anywhere.
+    let num_accounts = 4;
+    let owners = [Keypair::new(), Keypair::new()];
+    for owner in owners.iter() {
+        rpc.airdrop_lamports(&owner.pubkey(), LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+    }
+    for i in 0..num_accounts {
+        let owner = &owners[i % owners.len()];
+        let lamports = 1_000_000;
+        let instruction = create_invoke_instruction(
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &[],
+            &[CompressedAccount {
+                lamports,
+                owner: owner.pubkey().into(),
+                address: None,
+                data: None,
+            }],
+            &[],
+            &[v1_state_tree.merkle_tree],
+            &[],
+            &[],
+            None,
+            Some(lamports),
+            true,
+            None,
+            true,
+        );
+        rpc.create_and_send_transaction(&[instruction], &owner.pubkey(), &[owner])
+            .await
+            .unwrap();
+    }
+
+    let mut indexer = LocalIndexer::new(LocalIndexerConfig {
+        sync_address: Some(v1_state_tree.merkle_tree),
+        ..LocalIndexerConfig::new(
+            RpcUrl::Localnet.to_string(),
+            vec![TreeInfo {
+                tree: v1_state_tree.merkle_tree,
+                queue: v1_state_tree.nullifier_queue,
+                cpi_context: None,
+                next_tree_info: None,
+                tree_type: TreeType::StateV1,
+            }],
+        )
+    })
+    .await
+    .unwrap();
+    indexer.sync().await.unwrap();
+    let live_leaves = indexer.get_live_leaves(&v1_state_tree.merkle_tree).unwrap();
+    assert_eq!(live_leaves.len(), num_accounts);
+    let pre_next_index = get_output_queue_next_index(&rpc, output_queue).await;
+
+    run_forester(&config, Duration::from_secs(240)).await;
+
+    indexer.sync().await.unwrap();
+    assert!(indexer
+        .get_live_leaves(&v1_state_tree.merkle_tree)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        get_output_queue_next_index(&rpc, output_queue).await,
+        pre_next_index + num_accounts as u64
+    );
+}
+
+async fn run_forester(config: &ForesterConfig, duration: Duration) {
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let (shutdown_compressible_sender, shutdown_compressible_receiver) =
+        tokio::sync::broadcast::channel(1);
+    let (work_report_sender, _) = mpsc::channel(100);
+
+    let service_handle = tokio::spawn(run_pipeline::<LightClient>(
+        Arc::from(config.clone()),
+        None,
+        None,
+        shutdown_receiver,
+        Some(shutdown_compressible_receiver),
+        None,
+        work_report_sender,
+    ));
+
+    tokio::time::sleep(duration).await;
+
+    let _ = shutdown_sender.send(());
+    let _ = shutdown_compressible_sender.send(());
+    let _ = timeout(Duration::from_secs(5), service_handle).await;
+}
+
+async fn get_output_queue_next_index(rpc: &LightClient, output_queue: Pubkey) -> u64 {
+    let mut account = rpc.get_account(output_queue).await.unwrap().unwrap();
+    BatchedQueueAccount::output_from_bytes(&mut account.data)
+        .unwrap()
+        .batch_metadata
+        .next_index
+}
@@ -91,6 +91,9 @@ pub fn forester_config() -> ForesterConfig {
             prover_api_key: None,
             prover_polling_interval: None,
             prover_max_wait_time: None,
+            prover_routing: Default::default(),
+            prover_health_check_interval: None,
+            prover_max_consecutive_failures: None,
             photon_grpc_url: None,
             pushgateway_url: None,
             pagerduty_routing_key: None,
@@ -113,11 +116,17 @@ pub fn forester_config() -> ForesterConfig {
             skip_v1_address_trees: false,
             skip_v2_address_trees: false,
             tree_ids: vec![],
+            exclude_tree_ids: vec![],
             sleep_after_processing_ms: 50,
             sleep_when_idle_ms: 100,
             queue_polling_mode: QueuePollingMode::OnChain,
             group_authority: None,
             helius_rpc: false,
+            state_dir: None,
+            state_checkpoint_interval_seconds: 30,
+            enable_state_migration: false,
+            migration_output_queue: None,
+            max_migrations_per_slot: 10,
         },
         rpc_pool_config: RpcPoolConfig {
             max_size: 50,
@@ -136,6 +145,12 @@ pub fn forester_config() -> ForesterConfig {
         state_tree_data: vec![],
         compressible_config: None,
         lookup_table_address: None,
+        sender_config: Default::default(),
+        shard_coordinator: None,
+        alert_manager: Default::default(),
+        earnings: Default::default(),
+        admin: Default::default(),
+        reloadable: Default::default(),
     }
 }
 
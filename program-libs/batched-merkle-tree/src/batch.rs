@@ -1,4 +1,4 @@
-use light_bloom_filter::BloomFilter;
+use light_bloom_filter::{BloomFilter, BloomFilterKind, PartitionedBloomFilter};
 use light_hasher::{Hasher, Poseidon};
 use light_zero_copy::vec::ZeroCopyVecU64;
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
@@ -88,7 +88,9 @@ pub struct Batch {
     pub root_index: u32,
     start_slot_is_set: u8,
     bloom_filter_is_zeroed: u8,
-    _padding: [u8; 2],
+    /// [`BloomFilterKind`] of the bloom filter, set at initialization.
+    bloom_filter_kind: u8,
+    _padding: [u8; 1],
 }
 
 impl Batch {
@@ -114,7 +116,8 @@ impl Batch {
             start_slot: 0,
             start_slot_is_set: 0,
             bloom_filter_is_zeroed: 0,
-            _padding: [0u8; 2],
+            bloom_filter_kind: BloomFilterKind::Standard.into(),
+            _padding: [0u8; 1],
         }
     }
 
@@ -139,6 +142,15 @@ impl Batch {
         self.bloom_filter_is_zeroed = 0;
     }
 
+    pub fn get_bloom_filter_kind(&self) -> Result<BloomFilterKind, BatchedMerkleTreeError> {
+        Ok(BloomFilterKind::try_from(self.bloom_filter_kind)?)
+    }
+
+    /// Must only be set while the bloom filter is empty.
+    pub fn set_bloom_filter_kind(&mut self, kind: BloomFilterKind) {
+        self.bloom_filter_kind = kind.into();
+    }
+
     pub fn start_slot_is_set(&self) -> bool {
         self.start_slot_is_set == 1
     }
@@ -319,16 +331,12 @@ impl Batch {
             let other_bloom_filter_index = if bloom_filter_index == 0 { 1 } else { 0 };
 
             // 3. Insert value into the bloom filter at bloom_filter_index.
-            BloomFilter::new(
-                self.num_iters as usize,
-                self.bloom_filter_capacity,
+            self.insert_into_bloom_filter(
+                bloom_filter_value,
                 bloom_filter_stores[bloom_filter_index],
-            )?
-            .insert(bloom_filter_value)?;
+            )?;
             // 4. Check that value is not in any other bloom filter.
-            Self::check_non_inclusion(
-                self.num_iters as usize,
-                self.bloom_filter_capacity,
+            self.check_bloom_filter_non_inclusion(
                 bloom_filter_value,
                 bloom_filter_stores[other_bloom_filter_index],
             )?;
@@ -382,7 +390,55 @@ impl Batch {
         Ok(())
     }
 
-    /// Checks that value is not in the bloom filter.
+    /// Inserts value into the bloom filter of the batch.
+    fn insert_into_bloom_filter(
+        &self,
+        value: &[u8; 32],
+        store: &mut [u8],
+    ) -> Result<(), BatchedMerkleTreeError> {
+        match self.get_bloom_filter_kind()? {
+            BloomFilterKind::Standard => {
+                BloomFilter::new(self.num_iters as usize, self.bloom_filter_capacity, store)?
+                    .insert(value)?
+            }
+            BloomFilterKind::Partitioned => PartitionedBloomFilter::new(
+                self.num_iters as usize,
+                self.bloom_filter_capacity,
+                store,
+            )?
+            .insert(value)?,
+        }
+        Ok(())
+    }
+
+    /// Checks that value is not in the bloom filter of the batch.
+    pub fn check_bloom_filter_non_inclusion(
+        &self,
+        value: &[u8; 32],
+        store: &mut [u8],
+    ) -> Result<(), BatchedMerkleTreeError> {
+        match self.get_bloom_filter_kind()? {
+            BloomFilterKind::Standard => Self::check_non_inclusion(
+                self.num_iters as usize,
+                self.bloom_filter_capacity,
+                value,
+                store,
+            ),
+            BloomFilterKind::Partitioned => {
+                let mut bloom_filter = PartitionedBloomFilter::new(
+                    self.num_iters as usize,
+                    self.bloom_filter_capacity,
+                    store,
+                )?;
+                if bloom_filter.contains(value) {
+                    return Err(BatchedMerkleTreeError::NonInclusionCheckFailed);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that value is not in the standard bloom filter.
     pub fn check_non_inclusion(
         num_iters: usize,
         bloom_filter_capacity: u64,
@@ -785,6 +841,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_partitioned_bloom_filter() {
+        let mut batch = get_test_batch();
+        assert_eq!(batch.get_bloom_filter_kind(), Ok(BloomFilterKind::Standard));
+        batch.set_bloom_filter_kind(BloomFilterKind::Partitioned);
+        assert_eq!(
+            batch.get_bloom_filter_kind(),
+            Ok(BloomFilterKind::Partitioned)
+        );
+        let current_slot = 1;
+        let mut stores = vec![vec![0u8; 20_000]; 2];
+        let mut bloom_filter_stores = stores
+            .iter_mut()
+            .map(|store| &mut store[..])
+            .collect::<Vec<_>>();
+        let hash_chain_store_bytes_len = ZeroCopyVecU64::<[u8; 32]>::required_size_for_capacity(
+            batch.get_num_hash_chain_store() as u64,
+        );
+        let mut hash_chain_store_bytes = vec![0u8; hash_chain_store_bytes_len];
+        let mut hash_chain_store = ZeroCopyVecU64::<[u8; 32]>::new(
+            batch.get_num_hash_chain_store() as u64,
+            hash_chain_store_bytes.as_mut_slice(),
+        )
+        .unwrap();
+
+        for i in 0..10u64 {
+            let mut value = [0u8; 32];
+            value[24..].copy_from_slice(&i.to_be_bytes());
+            batch
+                .insert(
+                    &value,
+                    &value,
+                    bloom_filter_stores.as_mut_slice(),
+                    &mut hash_chain_store,
+                    0,
+                    &current_slot,
+                )
+                .unwrap();
+            let mut bloom_filter = PartitionedBloomFilter::new(
+                batch.num_iters as usize,
+                batch.bloom_filter_capacity,
+                bloom_filter_stores[0],
+            )
+            .unwrap();
+            assert!(bloom_filter.contains(&value));
+            assert_eq!(
+                batch.check_bloom_filter_non_inclusion(&value, bloom_filter_stores[0]),
+                Err(BatchedMerkleTreeError::NonInclusionCheckFailed)
+            );
+            batch
+                .check_bloom_filter_non_inclusion(&value, bloom_filter_stores[1])
+                .unwrap();
+            // Values inserted into the other batch fail the non-inclusion check.
+            let mut other_batch = get_test_batch();
+            other_batch.set_bloom_filter_kind(BloomFilterKind::Partitioned);
+            let mut other_hash_chain_store_bytes = vec![0u8; hash_chain_store_bytes_len];
+            let mut other_hash_chain_store = ZeroCopyVecU64::<[u8; 32]>::new(
+                other_batch.get_num_hash_chain_store() as u64,
+                other_hash_chain_store_bytes.as_mut_slice(),
+            )
+            .unwrap();
+            assert_eq!(
+                other_batch.insert(
+                    &value,
+                    &value,
+                    bloom_filter_stores.as_mut_slice(),
+                    &mut other_hash_chain_store,
+                    1,
+                    &current_slot,
+                ),
+                Err(BatchedMerkleTreeError::NonInclusionCheckFailed)
+            );
+            bloom_filter_stores[1].iter_mut().for_each(|byte| *byte = 0);
+        }
+
+        batch.bloom_filter_kind = 2;
+        assert_eq!(
+            batch.get_bloom_filter_kind(),
+            Err(BatchedMerkleTreeError::BloomFilter(
+                light_bloom_filter::BloomFilterError::InvalidKind
+            ))
+        );
+    }
+
     #[test]
     fn test_getters() {
         let mut batch = get_test_batch();
@@ -53,6 +53,10 @@ pub enum BatchedMerkleTreeError {
     BloomFilterNotZeroed,
     #[error("Account error {0}")]
     AccountError(#[from] AccountError),
+    #[error("Unsupported tree parameters.")]
+    UnsupportedParameters,
+    #[error("Root history capacity is less than the number of zkp batches per batch.")]
+    InvalidRootHistoryCapacity,
 }
 
 impl From<BatchedMerkleTreeError> for u32 {
@@ -70,6 +74,8 @@ impl From<BatchedMerkleTreeError> for u32 {
             BatchedMerkleTreeError::TreeIsFull => 14310,
             BatchedMerkleTreeError::NonInclusionCheckFailed => 14311,
             BatchedMerkleTreeError::BloomFilterNotZeroed => 14312,
+            BatchedMerkleTreeError::UnsupportedParameters => 14313,
+            BatchedMerkleTreeError::InvalidRootHistoryCapacity => 14314,
             BatchedMerkleTreeError::Hasher(e) => e.into(),
             BatchedMerkleTreeError::ZeroCopy(e) => e.into(),
             BatchedMerkleTreeError::MerkleTreeMetadata(e) => e.into(),
@@ -1,4 +1,5 @@
 use light_account_checks::{checks::check_account_balance_is_rent_exempt, AccountInfoTrait};
+use light_bloom_filter::{BloomFilterKind, BLOCK_SIZE_BITS};
 use light_compressed_account::{pubkey::Pubkey, TreeType};
 use light_merkle_tree_metadata::{
     access::AccessMetadata, fee::compute_rollover_fee, merkle_tree::MerkleTreeMetadata,
@@ -12,12 +13,18 @@ use crate::{
         DEFAULT_ADDRESS_ZKP_BATCH_SIZE, DEFAULT_BATCH_ADDRESS_TREE_HEIGHT,
     },
     errors::BatchedMerkleTreeError,
+    initialize_state_tree::{
+        deserialize_partitioned_bloom_filter, serialize_partitioned_bloom_filter,
+    },
     merkle_tree::{get_merkle_tree_account_size, BatchedMerkleTreeAccount},
     BorshDeserialize, BorshSerialize,
 };
 
+/// Borsh encoding is implemented manually,
+/// `partitioned_bloom_filter` is a trailing byte which is only encoded if set.
+/// Payloads without it decode as trees with standard bloom filters.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InitAddressTreeAccountsInstructionData {
     pub index: u64,
     pub program_owner: Option<Pubkey>,
@@ -31,6 +38,57 @@ pub struct InitAddressTreeAccountsInstructionData {
     pub rollover_threshold: Option<u64>,
     pub close_threshold: Option<u64>,
     pub height: u32,
+    /// Use partitioned bloom filters,
+    /// one keccak hash per insertion instead of `bloom_filter_num_iters`.
+    pub partitioned_bloom_filter: bool,
+}
+
+impl BorshSerialize for InitAddressTreeAccountsInstructionData {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.index.serialize(writer)?;
+        self.program_owner.serialize(writer)?;
+        self.forester.serialize(writer)?;
+        self.input_queue_batch_size.serialize(writer)?;
+        self.input_queue_zkp_batch_size.serialize(writer)?;
+        self.bloom_filter_num_iters.serialize(writer)?;
+        self.bloom_filter_capacity.serialize(writer)?;
+        self.root_history_capacity.serialize(writer)?;
+        self.network_fee.serialize(writer)?;
+        self.rollover_threshold.serialize(writer)?;
+        self.close_threshold.serialize(writer)?;
+        self.height.serialize(writer)?;
+        serialize_partitioned_bloom_filter(self.partitioned_bloom_filter, writer)
+    }
+}
+
+impl BorshDeserialize for InitAddressTreeAccountsInstructionData {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(Self {
+            index: BorshDeserialize::deserialize_reader(reader)?,
+            program_owner: BorshDeserialize::deserialize_reader(reader)?,
+            forester: BorshDeserialize::deserialize_reader(reader)?,
+            input_queue_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            input_queue_zkp_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            bloom_filter_num_iters: BorshDeserialize::deserialize_reader(reader)?,
+            bloom_filter_capacity: BorshDeserialize::deserialize_reader(reader)?,
+            root_history_capacity: BorshDeserialize::deserialize_reader(reader)?,
+            network_fee: BorshDeserialize::deserialize_reader(reader)?,
+            rollover_threshold: BorshDeserialize::deserialize_reader(reader)?,
+            close_threshold: BorshDeserialize::deserialize_reader(reader)?,
+            height: BorshDeserialize::deserialize_reader(reader)?,
+            partitioned_bloom_filter: deserialize_partitioned_bloom_filter(reader)?,
+        })
+    }
+}
+
+impl InitAddressTreeAccountsInstructionData {
+    pub fn bloom_filter_kind(&self) -> BloomFilterKind {
+        if self.partitioned_bloom_filter {
+            BloomFilterKind::Partitioned
+        } else {
+            BloomFilterKind::Standard
+        }
+    }
 }
 
 impl Default for InitAddressTreeAccountsInstructionData {
@@ -48,6 +106,7 @@ impl Default for InitAddressTreeAccountsInstructionData {
             network_fee: Some(10000),
             rollover_threshold: Some(95),
             close_threshold: None,
+            partitioned_bloom_filter: false,
         }
     }
 }
@@ -115,7 +174,7 @@ pub fn init_batched_address_merkle_tree_account(
         ),
         associated_queue: Pubkey::default(),
     };
-    BatchedMerkleTreeAccount::init(
+    let mut merkle_tree = BatchedMerkleTreeAccount::init(
         mt_account_data,
         &pubkey,
         metadata,
@@ -126,50 +185,77 @@ pub fn init_batched_address_merkle_tree_account(
         params.bloom_filter_num_iters,
         params.bloom_filter_capacity,
         TreeType::AddressV2,
-    )
+    )?;
+    merkle_tree.set_bloom_filter_kind(params.bloom_filter_kind())?;
+    Ok(merkle_tree)
 }
 
 /// Only used for testing. For production use the default config.
+/// Panics on parameters rejected by [`check_batched_address_tree_params`].
 pub fn validate_batched_address_tree_params(params: InitAddressTreeAccountsInstructionData) {
-    assert!(params.input_queue_batch_size > 0);
-    assert_eq!(
-        params.input_queue_batch_size % params.input_queue_zkp_batch_size,
-        0,
-        "Input queue batch size must divisible by input_queue_zkp_batch_size."
-    );
-    assert!(
-        match_circuit_size(params.input_queue_zkp_batch_size),
-        "Zkp batch size not supported. Supported: 10, 250"
-    );
-
-    assert!(params.bloom_filter_num_iters > 0);
-    assert!(params.bloom_filter_capacity >= params.input_queue_batch_size * 8);
-    assert_eq!(
-        params.bloom_filter_capacity % 8,
-        0,
-        "Bloom filter capacity must be divisible by 8."
-    );
-    assert!(params.bloom_filter_capacity > 0);
-    assert!(params.root_history_capacity > 0);
-    assert!(params.input_queue_batch_size > 0);
-
-    // Validate root_history_capacity is sufficient for input operations
-    // (address trees only have input queues, no output queues)
-    let required_capacity = params.input_queue_batch_size / params.input_queue_zkp_batch_size;
-    assert!(
-        params.root_history_capacity >= required_capacity as u32,
-        "root_history_capacity ({}) must be >= {} (input_queue_batch_size / input_queue_zkp_batch_size)",
-        params.root_history_capacity,
-        required_capacity
-    );
-
-    assert_eq!(params.close_threshold, None);
-    assert_eq!(params.height, DEFAULT_BATCH_ADDRESS_TREE_HEIGHT);
+    match check_batched_address_tree_params(&params) {
+        Ok(()) => {}
+        Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize) => {
+            panic!("Input queue batch size must divisible by input_queue_zkp_batch_size.")
+        }
+        Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity) => panic!(
+            "root_history_capacity ({}) must be >= input_queue_batch_size / input_queue_zkp_batch_size",
+            params.root_history_capacity
+        ),
+        Err(e) => panic!("Unsupported address tree parameters: {}", e),
+    }
 }
+
 /// Only 10 and 250 are supported.
 pub fn match_circuit_size(size: u64) -> bool {
     matches!(size, 10 | 250)
 }
+
+/// Checks parameters of address trees which are not created
+/// with protocol default parameters, without panicking.
+/// Zkp batch sizes are limited to circuit sizes
+/// with verifying keys in light-verifier.
+pub fn check_batched_address_tree_params(
+    params: &InitAddressTreeAccountsInstructionData,
+) -> Result<(), BatchedMerkleTreeError> {
+    if params.input_queue_batch_size == 0 || params.input_queue_zkp_batch_size == 0 {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.input_queue_batch_size % params.input_queue_zkp_batch_size != 0 {
+        return Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize);
+    }
+    if !match_circuit_size(params.input_queue_zkp_batch_size) {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    let min_bloom_filter_capacity = params
+        .input_queue_batch_size
+        .checked_mul(8)
+        .ok_or(BatchedMerkleTreeError::UnsupportedParameters)?;
+    if params.bloom_filter_num_iters == 0
+        || params.bloom_filter_capacity < min_bloom_filter_capacity
+        || params.bloom_filter_capacity % 8 != 0
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.partitioned_bloom_filter && params.bloom_filter_capacity < BLOCK_SIZE_BITS {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    // Address trees only have an input queue.
+    let required_capacity = params.input_queue_batch_size / params.input_queue_zkp_batch_size;
+    if (params.root_history_capacity as u64) < required_capacity {
+        return Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity);
+    }
+    if params
+        .rollover_threshold
+        .is_some_and(|threshold| threshold > 100)
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.close_threshold.is_some() || params.height != DEFAULT_BATCH_ADDRESS_TREE_HEIGHT {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    Ok(())
+}
 pub fn get_address_merkle_tree_account_size_from_params(
     params: InitAddressTreeAccountsInstructionData,
 ) -> usize {
@@ -206,6 +292,7 @@ pub mod test_utils {
                 network_fee: Some(10000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
 
@@ -223,6 +310,7 @@ pub mod test_utils {
                 network_fee: Some(10000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
         pub fn testnet_default() -> Self {
@@ -239,6 +327,7 @@ pub mod test_utils {
                 network_fee: Some(10000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
     }
@@ -365,3 +454,28 @@ fn test_validate_root_history_capacity_insufficient_address_tree() {
     };
     validate_batched_address_tree_params(params); // Should panic
 }
+
+#[test]
+fn test_init_address_tree_params_partitioned_bloom_filter_encoding() {
+    // Payloads without the trailing flag decode with standard bloom filters.
+    let params = InitAddressTreeAccountsInstructionData::default();
+    let standard_bytes = params.try_to_vec().unwrap();
+    let decoded = InitAddressTreeAccountsInstructionData::try_from_slice(&standard_bytes).unwrap();
+    assert_eq!(decoded, params);
+    assert!(!decoded.partitioned_bloom_filter);
+
+    let params = InitAddressTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..params
+    };
+    let partitioned_bytes = params.try_to_vec().unwrap();
+    assert_eq!(partitioned_bytes.len(), standard_bytes.len() + 1);
+    assert_eq!(
+        InitAddressTreeAccountsInstructionData::try_from_slice(&partitioned_bytes).unwrap(),
+        params
+    );
+
+    let mut invalid_bytes = standard_bytes;
+    invalid_bytes.push(2);
+    assert!(InitAddressTreeAccountsInstructionData::try_from_slice(&invalid_bytes).is_err());
+}
@@ -1,5 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use light_account_checks::{checks::check_account_balance_is_rent_exempt, AccountInfoTrait};
+use light_bloom_filter::{BloomFilterKind, BLOCK_SIZE_BITS};
 use light_compressed_account::{pubkey::Pubkey, QueueType, TreeType};
 use light_merkle_tree_metadata::{
     access::AccessMetadata, fee::compute_rollover_fee, merkle_tree::MerkleTreeMetadata,
@@ -16,8 +17,11 @@ use crate::{
     queue::{get_output_queue_account_size, BatchedQueueAccount},
 };
 
+/// Borsh encoding is implemented manually,
+/// `partitioned_bloom_filter` is a trailing byte which is only encoded if set.
+/// Payloads without it decode as trees with standard bloom filters.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InitStateTreeAccountsInstructionData {
     /// Unchecked identifier of the state tree.
     pub index: u64,
@@ -39,6 +43,86 @@ pub struct InitStateTreeAccountsInstructionData {
     /// Placeholder unimplemented.
     pub close_threshold: Option<u64>,
     pub height: u32,
+    /// Use partitioned bloom filters for the input queue,
+    /// one keccak hash per insertion instead of `bloom_filter_num_iters`.
+    pub partitioned_bloom_filter: bool,
+}
+
+impl BorshSerialize for InitStateTreeAccountsInstructionData {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.index.serialize(writer)?;
+        self.program_owner.serialize(writer)?;
+        self.forester.serialize(writer)?;
+        self.additional_bytes.serialize(writer)?;
+        self.input_queue_batch_size.serialize(writer)?;
+        self.output_queue_batch_size.serialize(writer)?;
+        self.input_queue_zkp_batch_size.serialize(writer)?;
+        self.output_queue_zkp_batch_size.serialize(writer)?;
+        self.bloom_filter_num_iters.serialize(writer)?;
+        self.bloom_filter_capacity.serialize(writer)?;
+        self.root_history_capacity.serialize(writer)?;
+        self.network_fee.serialize(writer)?;
+        self.rollover_threshold.serialize(writer)?;
+        self.close_threshold.serialize(writer)?;
+        self.height.serialize(writer)?;
+        serialize_partitioned_bloom_filter(self.partitioned_bloom_filter, writer)
+    }
+}
+
+impl BorshDeserialize for InitStateTreeAccountsInstructionData {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(Self {
+            index: BorshDeserialize::deserialize_reader(reader)?,
+            program_owner: BorshDeserialize::deserialize_reader(reader)?,
+            forester: BorshDeserialize::deserialize_reader(reader)?,
+            additional_bytes: BorshDeserialize::deserialize_reader(reader)?,
+            input_queue_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            output_queue_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            input_queue_zkp_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            output_queue_zkp_batch_size: BorshDeserialize::deserialize_reader(reader)?,
+            bloom_filter_num_iters: BorshDeserialize::deserialize_reader(reader)?,
+            bloom_filter_capacity: BorshDeserialize::deserialize_reader(reader)?,
+            root_history_capacity: BorshDeserialize::deserialize_reader(reader)?,
+            network_fee: BorshDeserialize::deserialize_reader(reader)?,
+            rollover_threshold: BorshDeserialize::deserialize_reader(reader)?,
+            close_threshold: BorshDeserialize::deserialize_reader(reader)?,
+            height: BorshDeserialize::deserialize_reader(reader)?,
+            partitioned_bloom_filter: deserialize_partitioned_bloom_filter(reader)?,
+        })
+    }
+}
+
+/// Writes the trailing `partitioned_bloom_filter` byte if set.
+pub(crate) fn serialize_partitioned_bloom_filter<W: std::io::Write>(
+    partitioned_bloom_filter: bool,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    if partitioned_bloom_filter {
+        true.serialize(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads the trailing `partitioned_bloom_filter` byte,
+/// defaults to `false` at the end of the payload.
+pub(crate) fn deserialize_partitioned_bloom_filter<R: std::io::Read>(
+    reader: &mut R,
+) -> std::io::Result<bool> {
+    let mut byte = [0u8; 1];
+    if reader.read(&mut byte)? == 0 {
+        return Ok(false);
+    }
+    bool::deserialize(&mut byte.as_slice())
+}
+
+impl InitStateTreeAccountsInstructionData {
+    pub fn bloom_filter_kind(&self) -> BloomFilterKind {
+        if self.partitioned_bloom_filter {
+            BloomFilterKind::Partitioned
+        } else {
+            BloomFilterKind::Standard
+        }
+    }
 }
 
 impl Default for InitStateTreeAccountsInstructionData {
@@ -59,6 +143,7 @@ impl Default for InitStateTreeAccountsInstructionData {
             network_fee: Some(5000),
             rollover_threshold: Some(95),
             close_threshold: None,
+            partitioned_bloom_filter: false,
         }
     }
 }
@@ -185,7 +270,7 @@ pub fn init_batched_state_merkle_tree_accounts<'a>(
     // A nullifier is inserted when compressed state is spent.
     // Spending compressed state requires proving its inclusion,
     // which needs a root from the tree account.
-    BatchedMerkleTreeAccount::init(
+    let mut merkle_tree = BatchedMerkleTreeAccount::init(
         mt_account_data,
         &mt_pubkey,
         metadata,
@@ -196,55 +281,25 @@ pub fn init_batched_state_merkle_tree_accounts<'a>(
         params.bloom_filter_num_iters,
         params.bloom_filter_capacity,
         TreeType::StateV2,
-    )
+    )?;
+    merkle_tree.set_bloom_filter_kind(params.bloom_filter_kind())?;
+    Ok(merkle_tree)
 }
 
 /// Only used for testing. For production use the default config.
+/// Panics on parameters rejected by [`check_batched_tree_params`].
 pub fn validate_batched_tree_params(params: InitStateTreeAccountsInstructionData) {
-    assert!(params.input_queue_batch_size > 0);
-    assert!(params.output_queue_batch_size > 0);
-    assert_eq!(
-        params.input_queue_batch_size % params.input_queue_zkp_batch_size,
-        0,
-        "Input queue batch size must divisible by input_queue_zkp_batch_size."
-    );
-    assert_eq!(
-        params.output_queue_batch_size % params.output_queue_zkp_batch_size,
-        0,
-        "Output queue batch size must divisible by output_queue_zkp_batch_size."
-    );
-    assert!(
-        match_circuit_size(params.input_queue_zkp_batch_size),
-        "Zkp batch size not supported. Supported 10, 500"
-    );
-    assert!(
-        match_circuit_size(params.output_queue_zkp_batch_size),
-        "Zkp batch size not supported. Supported 10, 500"
-    );
-
-    assert!(params.bloom_filter_num_iters > 0);
-    assert!(params.bloom_filter_capacity >= params.input_queue_batch_size * 8);
-    assert_eq!(
-        params.bloom_filter_capacity % 8,
-        0,
-        "Bloom filter capacity must be divisible by 8."
-    );
-    assert!(params.bloom_filter_capacity > 0);
-    assert!(params.root_history_capacity > 0);
-    assert!(params.input_queue_batch_size > 0);
-
-    // Validate root_history_capacity is sufficient for both input and output operations
-    let required_capacity = (params.output_queue_batch_size / params.output_queue_zkp_batch_size)
-        + (params.input_queue_batch_size / params.input_queue_zkp_batch_size);
-    assert!(
-        params.root_history_capacity >= required_capacity as u32,
-        "root_history_capacity ({}) must be >= {} (output_queue_batch_size / output_queue_zkp_batch_size + input_queue_batch_size / input_queue_zkp_batch_size)",
-        params.root_history_capacity,
-        required_capacity
-    );
-
-    assert_eq!(params.close_threshold, None);
-    assert_eq!(params.height, DEFAULT_BATCH_STATE_TREE_HEIGHT);
+    match check_batched_tree_params(&params) {
+        Ok(()) => {}
+        Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize) => panic!(
+            "Input and output queue batch sizes must be divisible by their zkp batch sizes."
+        ),
+        Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity) => panic!(
+            "root_history_capacity ({}) must be >= output_queue_batch_size / output_queue_zkp_batch_size + input_queue_batch_size / input_queue_zkp_batch_size",
+            params.root_history_capacity
+        ),
+        Err(e) => panic!("Unsupported state tree parameters: {}", e),
+    }
 }
 
 /// Only 10 and 500 are supported.
@@ -252,6 +307,61 @@ pub fn match_circuit_size(size: u64) -> bool {
     matches!(size, 10 | 500)
 }
 
+/// Checks parameters of state trees which are not created
+/// with protocol default parameters, without panicking.
+/// Zkp batch sizes are limited to circuit sizes
+/// with verifying keys in light-verifier.
+pub fn check_batched_tree_params(
+    params: &InitStateTreeAccountsInstructionData,
+) -> Result<(), BatchedMerkleTreeError> {
+    if params.input_queue_batch_size == 0
+        || params.output_queue_batch_size == 0
+        || params.input_queue_zkp_batch_size == 0
+        || params.output_queue_zkp_batch_size == 0
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.input_queue_batch_size % params.input_queue_zkp_batch_size != 0
+        || params.output_queue_batch_size % params.output_queue_zkp_batch_size != 0
+    {
+        return Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize);
+    }
+    if !match_circuit_size(params.input_queue_zkp_batch_size)
+        || !match_circuit_size(params.output_queue_zkp_batch_size)
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    let min_bloom_filter_capacity = params
+        .input_queue_batch_size
+        .checked_mul(8)
+        .ok_or(BatchedMerkleTreeError::UnsupportedParameters)?;
+    if params.bloom_filter_num_iters == 0
+        || params.bloom_filter_capacity < min_bloom_filter_capacity
+        || params.bloom_filter_capacity % 8 != 0
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.partitioned_bloom_filter && params.bloom_filter_capacity < BLOCK_SIZE_BITS {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    // The root history must retain the roots of a full input and output batch.
+    let required_capacity = (params.output_queue_batch_size / params.output_queue_zkp_batch_size)
+        + (params.input_queue_batch_size / params.input_queue_zkp_batch_size);
+    if (params.root_history_capacity as u64) < required_capacity {
+        return Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity);
+    }
+    if params
+        .rollover_threshold
+        .is_some_and(|threshold| threshold > 100)
+    {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    if params.close_threshold.is_some() || params.height != DEFAULT_BATCH_STATE_TREE_HEIGHT {
+        return Err(BatchedMerkleTreeError::UnsupportedParameters);
+    }
+    Ok(())
+}
+
 #[test]
 fn test_validate_root_history_capacity_state_tree() {
     // Test with valid params (default should pass)
@@ -273,6 +383,35 @@ fn test_validate_root_history_capacity_insufficient_state_tree() {
     };
     validate_batched_tree_params(params); // Should panic
 }
+
+#[test]
+fn test_init_state_tree_params_partitioned_bloom_filter_encoding() {
+    // Payloads without the trailing flag decode with standard bloom filters.
+    let params = InitStateTreeAccountsInstructionData::default();
+    let standard_bytes = params.try_to_vec().unwrap();
+    let decoded = InitStateTreeAccountsInstructionData::try_from_slice(&standard_bytes).unwrap();
+    assert_eq!(decoded, params);
+    assert!(!decoded.partitioned_bloom_filter);
+
+    let params = InitStateTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..params
+    };
+    let partitioned_bytes = params.try_to_vec().unwrap();
+    assert_eq!(partitioned_bytes.len(), standard_bytes.len() + 1);
+    assert_eq!(
+        InitStateTreeAccountsInstructionData::try_from_slice(&partitioned_bytes).unwrap(),
+        params
+    );
+
+    // Invalid bool and trailing bytes are rejected.
+    let mut invalid_bytes = standard_bytes.clone();
+    invalid_bytes.push(2);
+    assert!(InitStateTreeAccountsInstructionData::try_from_slice(&invalid_bytes).is_err());
+    let mut trailing_bytes = partitioned_bytes;
+    trailing_bytes.push(1);
+    assert!(InitStateTreeAccountsInstructionData::try_from_slice(&trailing_bytes).is_err());
+}
 #[cfg(feature = "test-only")]
 pub mod test_utils {
     use light_compressed_account::hash_to_bn254_field_size_be;
@@ -306,6 +445,7 @@ pub mod test_utils {
                 network_fee: Some(5000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
 
@@ -326,6 +466,7 @@ pub mod test_utils {
                 network_fee: Some(5000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
 
@@ -346,6 +487,7 @@ pub mod test_utils {
                 network_fee: Some(5000),
                 rollover_threshold: Some(95),
                 close_threshold: None,
+                partitioned_bloom_filter: false,
             }
         }
     }
@@ -161,12 +161,14 @@
 //!
 //! ## Error Codes
 //!
-//! All errors are defined in [`src/errors.rs`](../src/errors.rs) and map to u32 error codes (14301-14312 range):
+//! All errors are defined in [`src/errors.rs`](../src/errors.rs) and map to u32 error codes (14301-14314 range):
 //! - `BatchNotReady` (14301) - Batch is not ready to be inserted
 //! - `BatchAlreadyInserted` (14302) - Batch is already inserted
 //! - `TreeIsFull` (14310) - Batched Merkle tree reached capacity
 //! - `NonInclusionCheckFailed` (14311) - Value exists in bloom filter
 //! - `BloomFilterNotZeroed` (14312) - Bloom filter must be zeroed before reuse
+//! - `UnsupportedParameters` (14313) - Tree parameters are not supported
+//! - `InvalidRootHistoryCapacity` (14314) - Root history cannot retain the roots of a full batch
 //! - Additional errors from underlying libraries (hasher, zero-copy, verifier, etc.)
 
 #![allow(unexpected_cfgs)]
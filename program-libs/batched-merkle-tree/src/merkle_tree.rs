@@ -5,6 +5,7 @@ use light_account_checks::{
     discriminator::{Discriminator, DISCRIMINATOR_LEN},
     AccountInfoTrait,
 };
+use light_bloom_filter::{BloomFilterError, BloomFilterKind, BLOCK_SIZE_BITS};
 use light_compressed_account::{
     hash_chain::create_hash_chain_from_array, hash_to_bn254_field_size_be,
     instruction_data::compressed_proof::CompressedProof, nullifier::create_nullifier,
@@ -876,12 +877,25 @@ impl<'a> BatchedMerkleTreeAccount<'a> {
         value: &[u8; 32],
     ) -> Result<(), BatchedMerkleTreeError> {
         for i in 0..self.queue_batches.num_batches as usize {
-            Batch::check_non_inclusion(
-                self.queue_batches.batches[i].num_iters as usize,
-                self.queue_batches.batches[i].bloom_filter_capacity,
-                value,
-                self.bloom_filter_stores[i],
-            )?;
+            let batch = self.queue_batches.batches[i];
+            batch.check_bloom_filter_non_inclusion(value, self.bloom_filter_stores[i])?;
+        }
+        Ok(())
+    }
+
+    /// Sets the bloom filter kind of all batches.
+    /// Only used at initialization, while the bloom filters are empty.
+    pub(crate) fn set_bloom_filter_kind(
+        &mut self,
+        kind: BloomFilterKind,
+    ) -> Result<(), BatchedMerkleTreeError> {
+        if kind == BloomFilterKind::Partitioned
+            && self.queue_batches.bloom_filter_capacity < BLOCK_SIZE_BITS
+        {
+            return Err(BloomFilterError::InvalidStoreCapacity.into());
+        }
+        for batch in self.queue_batches.batches.iter_mut() {
+            batch.set_bloom_filter_kind(kind);
         }
         Ok(())
     }
@@ -913,6 +927,17 @@ impl<'a> BatchedMerkleTreeAccount<'a> {
         Ok(())
     }
 
+    /// Returns true if every leaf appended to the output queue is nullified.
+    /// Every leaf can only be nullified once,
+    /// hence equal counts of inserted nullifiers and appended values
+    /// imply that all leaves are nullified.
+    /// Values and nullifiers of incomplete zkp batches are counted,
+    /// values of an incomplete zkp batch can be spent from the output queue
+    /// but are never inserted into a rolled over tree.
+    pub fn all_leaves_nullified(&self, output_queue: &BatchedQueueAccount) -> bool {
+        self.queue_batches.next_index == output_queue.batch_metadata.next_index
+    }
+
     pub fn get_associated_queue(&self) -> &Pubkey {
         &self.metadata.metadata.associated_queue
     }
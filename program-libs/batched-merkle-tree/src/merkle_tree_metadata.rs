@@ -1,4 +1,5 @@
 use aligned_sized::aligned_sized;
+use light_bloom_filter::BloomFilterKind;
 use light_compressed_account::{hash_to_bn254_field_size_be, pubkey::Pubkey, QueueType, TreeType};
 use light_merkle_tree_metadata::{
     access::AccessMetadata, fee::compute_rollover_fee, merkle_tree::MerkleTreeMetadata,
@@ -124,8 +125,24 @@ impl BatchedMerkleTreeMetadata {
             root_history_capacity,
             height,
             num_iters,
+            bloom_filter_kind,
             tree_pubkey,
         } = params;
+        let mut queue_batches = QueueBatches::new_input_queue(
+            batch_size,
+            bloom_filter_capacity,
+            zkp_batch_size,
+            num_iters,
+            if tree_type == TreeType::AddressV2 {
+                1
+            } else {
+                0
+            },
+        )
+        .unwrap();
+        for batch in queue_batches.batches.iter_mut() {
+            batch.set_bloom_filter_kind(bloom_filter_kind);
+        }
         Self {
             metadata: MerkleTreeMetadata {
                 next_merkle_tree: Pubkey::default(),
@@ -145,18 +162,7 @@ impl BatchedMerkleTreeMetadata {
             next_index: 0,
             height,
             root_history_capacity,
-            queue_batches: QueueBatches::new_input_queue(
-                batch_size,
-                bloom_filter_capacity,
-                zkp_batch_size,
-                num_iters,
-                if tree_type == TreeType::AddressV2 {
-                    1
-                } else {
-                    0
-                },
-            )
-            .unwrap(),
+            queue_batches,
             capacity: 2u64.pow(height),
             hashed_pubkey: hash_to_bn254_field_size_be(&tree_pubkey.to_bytes()),
             nullifier_next_index: 0,
@@ -180,6 +186,10 @@ pub struct CreateTreeParams {
     pub root_history_capacity: u32,
     pub height: u32,
     pub num_iters: u64,
+    /// Bloom filter kind of the input queue batches.
+    /// Callers constructing `CreateTreeParams` directly
+    /// must set `BloomFilterKind::Standard` to keep the previous behavior.
+    pub bloom_filter_kind: BloomFilterKind,
     pub tree_pubkey: Pubkey,
 }
 impl CreateTreeParams {
@@ -201,6 +211,7 @@ impl CreateTreeParams {
             root_history_capacity: data.root_history_capacity,
             height: data.height,
             num_iters: data.bloom_filter_num_iters,
+            bloom_filter_kind: data.bloom_filter_kind(),
             tree_pubkey,
         }
     }
@@ -223,6 +234,7 @@ impl CreateTreeParams {
             root_history_capacity: data.root_history_capacity,
             height: data.height,
             num_iters: data.bloom_filter_num_iters,
+            bloom_filter_kind: data.bloom_filter_kind(),
             tree_pubkey,
         }
     }
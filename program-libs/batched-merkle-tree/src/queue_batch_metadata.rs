@@ -155,6 +155,15 @@ impl QueueBatches {
         })
     }
 
+    /// Returns true if any batch holds a full zkp batch
+    /// which is not inserted into the tree yet.
+    /// Elements of an incomplete zkp batch are not ready to be inserted.
+    pub fn has_ready_zkp_batches(&self) -> bool {
+        self.batches.iter().any(|batch| {
+            batch.get_state() != BatchState::Inserted && batch.get_num_ready_zkp_updates() > 0
+        })
+    }
+
     /// Increment the next full batch index if current state is BatchState::Inserted.
     pub fn increment_pending_batch_index_if_inserted(&mut self, state: BatchState) {
         if state == BatchState::Inserted {
@@ -279,6 +288,30 @@ fn test_increment_currently_processing_batch_index_if_full() {
     assert_eq!(metadata.currently_processing_batch_index, 0);
 }
 
+#[test]
+fn test_has_ready_zkp_batches() {
+    let mut metadata = QueueBatches::new_output_queue(4, 2).unwrap();
+    assert!(!metadata.has_ready_zkp_batches());
+    let mut hash_chain_store_bytes =
+        vec![0u8; ZeroCopyVecU64::<[u8; 32]>::required_size_for_capacity(2)];
+    let mut hash_chain_store =
+        ZeroCopyVecU64::<[u8; 32]>::new(2, &mut hash_chain_store_bytes).unwrap();
+    // Elements of an incomplete zkp batch are not ready.
+    metadata.batches[0]
+        .add_to_hash_chain(&[1u8; 32], &mut hash_chain_store)
+        .unwrap();
+    assert!(!metadata.has_ready_zkp_batches());
+    // A full zkp batch is ready until inserted.
+    metadata.batches[0]
+        .add_to_hash_chain(&[2u8; 32], &mut hash_chain_store)
+        .unwrap();
+    assert!(metadata.has_ready_zkp_batches());
+    metadata.batches[0]
+        .mark_as_inserted_in_merkle_tree(0, 0, 10)
+        .unwrap();
+    assert!(!metadata.has_ready_zkp_batches());
+}
+
 #[test]
 fn test_validate_batch_sizes() {
     assert!(QueueBatches::check_batch_size_divisible_by_zkp_batch_size(10, 3).is_err());
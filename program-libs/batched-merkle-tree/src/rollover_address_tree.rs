@@ -1,4 +1,5 @@
 use light_account_checks::{checks::check_account_balance_is_rent_exempt, AccountInfoTrait};
+use light_bloom_filter::BloomFilterKind;
 use light_compressed_account::pubkey::Pubkey;
 #[cfg(target_os = "solana")]
 use light_merkle_tree_metadata::errors::MerkleTreeMetadataError;
@@ -104,6 +105,10 @@ fn create_batched_address_tree_init_params(
             old_merkle_tree.metadata.rollover_metadata.close_threshold,
             u64::MAX,
         ),
+        partitioned_bloom_filter: matches!(
+            old_merkle_tree.queue_batches.batches[0].get_bloom_filter_kind(),
+            Ok(BloomFilterKind::Partitioned)
+        ),
     }
 }
 
@@ -1,4 +1,5 @@
 use light_account_checks::{checks::check_account_balance_is_rent_exempt, AccountInfoTrait};
+use light_bloom_filter::BloomFilterKind;
 use light_compressed_account::pubkey::Pubkey;
 use light_merkle_tree_metadata::{errors::MerkleTreeMetadataError, utils::if_equals_none};
 
@@ -205,6 +206,10 @@ impl From<&RolloverBatchStateTreeParams<'_>> for InitStateTreeAccountsInstructio
             additional_bytes: params.additional_bytes,
             output_queue_batch_size: params.old_output_queue.batch_metadata.batch_size,
             output_queue_zkp_batch_size: params.old_output_queue.batch_metadata.zkp_batch_size,
+            partitioned_bloom_filter: matches!(
+                params.old_merkle_tree.queue_batches.batches[0].get_bloom_filter_kind(),
+                Ok(BloomFilterKind::Partitioned)
+            ),
         }
     }
 }
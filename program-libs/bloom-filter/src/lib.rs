@@ -9,6 +9,8 @@
 //! | [`BloomFilter::calculate_bloom_filter_size`] | Optimal bit count for given `n` and `p` |
 //! | [`BloomFilter::calculate_optimal_hash_functions`] | Optimal `k` for given `n` and `m` |
 //! | [`BloomFilter::probe_index_keccak`] | Keccak-based probe index for a value |
+//! | [`PartitionedBloomFilter`] | Bloom filter probing one block per value with one keccak hash |
+//! | [`BloomFilterKind`] | Selects the bloom filter variant of a store |
 
 use std::f64::consts::LN_2;
 
@@ -20,6 +22,8 @@ pub enum BloomFilterError {
     Full,
     #[error("Invalid store capacity")]
     InvalidStoreCapacity,
+    #[error("Invalid bloom filter kind")]
+    InvalidKind,
 }
 
 impl From<BloomFilterError> for u32 {
@@ -27,6 +31,7 @@ impl From<BloomFilterError> for u32 {
         match e {
             BloomFilterError::Full => 14201,
             BloomFilterError::InvalidStoreCapacity => 14202,
+            BloomFilterError::InvalidKind => 14203,
         }
     }
 }
@@ -45,6 +50,38 @@ impl From<BloomFilterError> for pinocchio::program_error::ProgramError {
     }
 }
 
+/// Number of bits of a [`PartitionedBloomFilter`] block, one 64 byte cache line.
+pub const BLOCK_SIZE_BITS: u64 = 512;
+
+/// Bloom filter variant of a store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum BloomFilterKind {
+    /// [`BloomFilter`], one keccak hash per probe.
+    #[default]
+    Standard = 0,
+    /// [`PartitionedBloomFilter`], one keccak hash per value.
+    Partitioned = 1,
+}
+
+impl TryFrom<u8> for BloomFilterKind {
+    type Error = BloomFilterError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BloomFilterKind::Standard),
+            1 => Ok(BloomFilterKind::Partitioned),
+            _ => Err(BloomFilterError::InvalidKind),
+        }
+    }
+}
+
+impl From<BloomFilterKind> for u8 {
+    fn from(kind: BloomFilterKind) -> u8 {
+        kind as u8
+    }
+}
+
 #[derive(Debug)]
 pub struct BloomFilter<'a> {
     pub num_iters: usize,
@@ -64,6 +101,12 @@ impl<'a> BloomFilter<'a> {
         k.ceil() as usize
     }
 
+    /// Expected false positive rate after inserting `n` values
+    /// into `m` bits with `k` hash functions.
+    pub fn calculate_false_positive_rate(n: usize, m: usize, k: usize) -> f64 {
+        (1.0 - (-(k as f64) * n as f64 / m as f64).exp()).powi(k as i32)
+    }
+
     pub fn new(
         num_iters: usize,
         capacity: u64,
@@ -129,11 +172,116 @@ impl<'a> BloomFilter<'a> {
     }
 }
 
+/// Blocked bloom filter.
+///
+/// One keccak hash of a value selects a block of [`BLOCK_SIZE_BITS`] bits
+/// and seeds all probes of the value, which stay within the block.
+/// Compared to [`BloomFilter`] inserts and lookups cost one keccak hash
+/// instead of `num_iters`, at a higher false positive rate for the same
+/// store size, see [`PartitionedBloomFilter::calculate_false_positive_rate`].
+/// Trailing bits of the store which don't fill a block are unused.
+#[derive(Debug)]
+pub struct PartitionedBloomFilter<'a> {
+    pub num_iters: usize,
+    pub capacity: u64,
+    pub store: &'a mut [u8],
+}
+
+impl<'a> PartitionedBloomFilter<'a> {
+    pub fn new(
+        num_iters: usize,
+        capacity: u64,
+        store: &'a mut [u8],
+    ) -> Result<Self, BloomFilterError> {
+        // Capacity is in bits while store is in bytes.
+        if store.len() * 8 != capacity as usize || capacity < BLOCK_SIZE_BITS {
+            return Err(BloomFilterError::InvalidStoreCapacity);
+        }
+        Ok(Self {
+            num_iters,
+            capacity,
+            store,
+        })
+    }
+
+    /// Expected false positive rate after inserting `n` values
+    /// into `m` bits with `k` probes per value.
+    /// Block loads are Poisson distributed with mean `n / num_blocks`,
+    /// the rate is the sum of the block false positive rates
+    /// weighted by the probability of each load.
+    pub fn calculate_false_positive_rate(n: usize, m: usize, k: usize) -> f64 {
+        let num_blocks = (m as u64 / BLOCK_SIZE_BITS).max(1);
+        let mean_load = n as f64 / num_blocks as f64;
+        let bit_not_set = 1.0 - 1.0 / BLOCK_SIZE_BITS as f64;
+        let max_load = (mean_load * 4.0).ceil() as usize + 64;
+        let mut load_probability = (-mean_load).exp();
+        let mut rate = 0.0;
+        for load in 0..=max_load {
+            if load > 0 {
+                load_probability *= mean_load / load as f64;
+            }
+            let block_rate = (1.0 - bit_not_set.powi((k * load) as i32)).powi(k as i32);
+            rate += load_probability * block_rate;
+        }
+        rate
+    }
+
+    /// Returns the probe indices of a value.
+    /// The first 8 bytes of the keccak hash select the block,
+    /// the next 16 bytes seed the probes within the block by double hashing.
+    /// The step is odd, hence the first [`BLOCK_SIZE_BITS`] probes are distinct.
+    pub fn probe_indices_keccak(
+        value_bytes: &[u8; 32],
+        num_iters: usize,
+        capacity: &u64,
+    ) -> impl Iterator<Item = usize> {
+        let hash = solana_nostd_keccak::hash(value_bytes);
+        let num_blocks = *capacity / BLOCK_SIZE_BITS;
+        let block = u64::from_le_bytes(hash[..8].try_into().unwrap()) % num_blocks;
+        let start = u64::from_le_bytes(hash[8..16].try_into().unwrap());
+        let step = u64::from_le_bytes(hash[16..24].try_into().unwrap()) | 1;
+        (0..num_iters as u64).map(move |i| {
+            let offset = start.wrapping_add(i.wrapping_mul(step)) % BLOCK_SIZE_BITS;
+            (block * BLOCK_SIZE_BITS + offset) as usize
+        })
+    }
+
+    pub fn insert(&mut self, value: &[u8; 32]) -> Result<(), BloomFilterError> {
+        if self._insert(value, true) {
+            Ok(())
+        } else {
+            Err(BloomFilterError::Full)
+        }
+    }
+
+    pub fn contains(&mut self, value: &[u8; 32]) -> bool {
+        !self._insert(value, false)
+    }
+
+    fn _insert(&mut self, value: &[u8; 32], insert: bool) -> bool {
+        let mut all_bits_set = true;
+        use bitvec::prelude::*;
+
+        let bits = BitSlice::<u8, Msb0>::from_slice_mut(self.store);
+        for probe_index in Self::probe_indices_keccak(value, self.num_iters, &self.capacity) {
+            if bits[probe_index] {
+                continue;
+            } else if insert {
+                all_bits_set = false;
+                bits.set(probe_index, true);
+            } else {
+                return true;
+            }
+        }
+        !all_bits_set
+    }
+}
+
 #[cfg(test)]
 mod test {
     use light_hasher::bigint::bigint_to_be_bytes_array;
     use num_bigint::{RandBigInt, ToBigUint};
-    use rand::thread_rng;
+    use rand::{thread_rng, Rng};
 
     use super::*;
 
@@ -157,6 +305,180 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_partitioned_insert_and_contains() -> Result<(), BloomFilterError> {
+        let capacity = 128_000 * 8;
+        let mut store = [0u8; 128_000];
+        let mut bf = PartitionedBloomFilter::new(3, capacity, &mut store)?;
+
+        let value1 = [1u8; 32];
+        let value2 = [2u8; 32];
+
+        bf.insert(&value1)?;
+        assert!(bf.contains(&value1));
+        assert!(!bf.contains(&value2));
+        assert_eq!(bf.insert(&value1), Err(BloomFilterError::Full));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioned_probes_within_block() {
+        let capacity = 20_000 * 8;
+        let num_iters = 10;
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let value: [u8; 32] = rng.gen();
+            let indices: Vec<usize> =
+                PartitionedBloomFilter::probe_indices_keccak(&value, num_iters, &capacity)
+                    .collect();
+            assert_eq!(indices.len(), num_iters);
+            let block = indices[0] / BLOCK_SIZE_BITS as usize;
+            assert!(block < (capacity / BLOCK_SIZE_BITS) as usize);
+            for (i, index) in indices.iter().enumerate() {
+                assert_eq!(index / BLOCK_SIZE_BITS as usize, block);
+                assert!(!indices[..i].contains(index));
+            }
+        }
+    }
+
+    #[test]
+    fn test_partitioned_invalid_store_capacity() {
+        let mut store = [0u8; 32];
+        assert_eq!(
+            PartitionedBloomFilter::new(3, 32 * 8, &mut store).unwrap_err(),
+            BloomFilterError::InvalidStoreCapacity
+        );
+        let mut store = [0u8; 64];
+        assert_eq!(
+            PartitionedBloomFilter::new(3, 32 * 8, &mut store).unwrap_err(),
+            BloomFilterError::InvalidStoreCapacity
+        );
+        // Trailing bits which don't fill a block are allowed.
+        let mut store = [0u8; 100];
+        assert!(PartitionedBloomFilter::new(3, 100 * 8, &mut store).is_ok());
+    }
+
+    #[test]
+    fn test_bloom_filter_kind() {
+        assert_eq!(BloomFilterKind::default(), BloomFilterKind::Standard);
+        for kind in [BloomFilterKind::Standard, BloomFilterKind::Partitioned] {
+            assert_eq!(BloomFilterKind::try_from(u8::from(kind)), Ok(kind));
+        }
+        assert_eq!(
+            BloomFilterKind::try_from(2),
+            Err(BloomFilterError::InvalidKind)
+        );
+    }
+
+    /// Measured false positive rates are close to the expected rates.
+    #[test]
+    fn test_false_positive_rate() {
+        let num_values = 1_000;
+        let capacity = 2_048 * 8;
+        let num_iters = 3;
+        for kind in [BloomFilterKind::Standard, BloomFilterKind::Partitioned] {
+            let expected = expected_false_positive_rate(kind, num_values, capacity, num_iters);
+            let measured =
+                measure_false_positive_rate(kind, num_values, capacity, num_iters, 100_000);
+            println!("{:?} expected {} measured {}", kind, expected, measured);
+            assert!(measured < expected * 1.5);
+            assert!(measured > expected / 1.5);
+        }
+    }
+
+    /// False positive rates of the default batch parameters.
+    /// The expected rates are too low to be measured,
+    /// measured rates are for 1/100 of the batch size and bloom filter capacity.
+    ///
+    /// Expected results (10 hash functions):
+    /// - state, 15,000 values in 2,301,536 bits: 1.0e-12 standard, 3.1e-10 partitioned
+    /// - address, 30,000 values in 4,603,072 bits: 1.0e-12 standard, 3.1e-10 partitioned
+    #[ignore = "bench"]
+    #[test]
+    fn bench_false_positive_rate() {
+        let num_iters = 10;
+        for (name, num_values, capacity) in
+            [("state", 15_000, 2_301_536), ("address", 30_000, 4_603_072)]
+        {
+            for kind in [BloomFilterKind::Standard, BloomFilterKind::Partitioned] {
+                println!(
+                    "{} {:?} expected {:e}",
+                    name,
+                    kind,
+                    expected_false_positive_rate(kind, num_values, capacity, num_iters)
+                );
+                let (num_values, capacity) = (num_values / 100, capacity / 100 / 8 * 8);
+                println!(
+                    "{} {:?} 1/100 expected {:e} measured {:e}",
+                    name,
+                    kind,
+                    expected_false_positive_rate(kind, num_values, capacity, num_iters),
+                    measure_false_positive_rate(kind, num_values, capacity, num_iters, 10_000_000)
+                );
+            }
+        }
+    }
+
+    fn expected_false_positive_rate(
+        kind: BloomFilterKind,
+        num_values: usize,
+        capacity: usize,
+        num_iters: usize,
+    ) -> f64 {
+        match kind {
+            BloomFilterKind::Standard => {
+                BloomFilter::calculate_false_positive_rate(num_values, capacity, num_iters)
+            }
+            BloomFilterKind::Partitioned => PartitionedBloomFilter::calculate_false_positive_rate(
+                num_values, capacity, num_iters,
+            ),
+        }
+    }
+
+    /// Inserts `num_values` random values and returns
+    /// the share of `num_lookups` other random values which are contained.
+    fn measure_false_positive_rate(
+        kind: BloomFilterKind,
+        num_values: usize,
+        capacity: usize,
+        num_iters: usize,
+        num_lookups: usize,
+    ) -> f64 {
+        let mut rng = thread_rng();
+        let mut store = vec![0u8; capacity / 8];
+        let mut contains: Box<dyn FnMut(&[u8; 32], bool) -> bool + '_> = match kind {
+            BloomFilterKind::Standard => {
+                let mut bf = BloomFilter::new(num_iters, capacity as u64, &mut store).unwrap();
+                Box::new(move |value, insert| {
+                    if insert {
+                        // Values whose bits are all set already can't be inserted.
+                        let _ = bf.insert(value);
+                    }
+                    bf.contains(value)
+                })
+            }
+            BloomFilterKind::Partitioned => {
+                let mut bf =
+                    PartitionedBloomFilter::new(num_iters, capacity as u64, &mut store).unwrap();
+                Box::new(move |value, insert| {
+                    if insert {
+                        // Values whose bits are all set already can't be inserted.
+                        let _ = bf.insert(value);
+                    }
+                    bf.contains(value)
+                })
+            }
+        };
+        for _ in 0..num_values {
+            assert!(contains(&rng.gen(), true));
+        }
+        let num_false_positives = (0..num_lookups)
+            .filter(|_| contains(&rng.gen(), false))
+            .count();
+        num_false_positives as f64 / num_lookups as f64
+    }
+
     #[test]
     fn short_rnd_test() {
         let capacity = 500;
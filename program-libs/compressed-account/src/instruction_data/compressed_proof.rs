@@ -1,3 +1,4 @@
+use light_hasher::{hash_chain::create_hash_chain_from_slice, HasherError};
 use light_zero_copy::{errors::ZeroCopyError, traits::ZeroCopyAt, ZeroCopyMut};
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Ref, Unaligned};
 
@@ -40,6 +41,30 @@ impl Default for CompressedProof {
 }
 
 impl CompressedProof {
+    /// Sentinel `a` and `b` of mock proofs, see [`CompressedProof::mock`].
+    /// Only valid in tests, never accepted by production programs.
+    pub const MOCK: Self = Self {
+        a: [u8::MAX; 32],
+        b: [u8::MAX; 64],
+        c: [u8::MAX; 32],
+    };
+
+    /// Mock proof for `public_inputs`, accepted without Groth16 verification
+    /// by programs built with the `mock-proof` feature of `light-verifier`.
+    /// `c` is the hash chain of the public inputs, hence a mock proof
+    /// for a different root or leaf is rejected.
+    pub fn mock(public_inputs: &[[u8; 32]]) -> Result<Self, HasherError> {
+        Ok(Self {
+            c: create_hash_chain_from_slice(public_inputs)?,
+            ..Self::MOCK
+        })
+    }
+
+    /// Returns true if `a` and `b` are the mock proof sentinel.
+    pub fn is_mock(&self) -> bool {
+        self.a == Self::MOCK.a && self.b == Self::MOCK.b
+    }
+
     /// Convert the proof to a fixed-size byte array [u8; 128]
     pub fn to_array(&self) -> [u8; 128] {
         let mut result = [0u8; 128];
@@ -20,6 +20,10 @@ pub enum MerkleTreeMetadataError {
     InvalidRolloverThreshold,
     #[error("Invalid Height.")]
     InvalidHeight,
+    #[error("Merkle tree not rolled over.")]
+    NotRolledOver,
+    #[error("Merkle tree not ready for close.")]
+    NotReadyForClose,
 }
 
 impl From<MerkleTreeMetadataError> for u32 {
@@ -34,6 +38,8 @@ impl From<MerkleTreeMetadataError> for u32 {
             MerkleTreeMetadataError::InvalidTreeType => 14007,
             MerkleTreeMetadataError::InvalidRolloverThreshold => 14008,
             MerkleTreeMetadataError::InvalidHeight => 14009,
+            MerkleTreeMetadataError::NotRolledOver => 14010,
+            MerkleTreeMetadataError::NotReadyForClose => 14011,
         }
     }
 }
@@ -58,6 +58,19 @@ impl MerkleTreeMetadata {
 
         Ok(())
     }
+
+    /// Checks that the Merkle tree is associated with the queue,
+    /// rolled over, and that its close threshold has passed.
+    pub fn check_closable(
+        &self,
+        associated_queue: Pubkey,
+        current_slot: u64,
+    ) -> Result<(), MerkleTreeMetadataError> {
+        if self.associated_queue != associated_queue {
+            return Err(MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated);
+        }
+        self.rollover_metadata.check_closable(current_slot)
+    }
 }
 
 #[test]
@@ -101,4 +114,23 @@ fn test() {
             Err(MerkleTreeMetadataError::MerkleTreeAlreadyRolledOver)
         );
     }
+    // 5. Failing - close with invalid associated queue
+    {
+        let result = merkle_tree_metadata.check_closable(Pubkey::new_unique(), 1_002);
+        assert_eq!(
+            result,
+            Err(MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated)
+        );
+    }
+    // 6. Failing - close before close threshold passed
+    {
+        let result = merkle_tree_metadata.check_closable(associated_queue, 1_001);
+        assert_eq!(result, Err(MerkleTreeMetadataError::NotReadyForClose));
+    }
+    // 7. Functional - close
+    {
+        merkle_tree_metadata
+            .check_closable(associated_queue, 1_002)
+            .unwrap();
+    }
 }
@@ -72,6 +72,19 @@ impl QueueMetadata {
 
         Ok(())
     }
+
+    /// Checks that the queue is associated with the Merkle tree,
+    /// rolled over, and that its close threshold has passed.
+    pub fn check_closable(
+        &self,
+        associated_merkle_tree: Pubkey,
+        current_slot: u64,
+    ) -> Result<(), MerkleTreeMetadataError> {
+        if self.associated_merkle_tree != associated_merkle_tree {
+            return Err(MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated);
+        }
+        self.rollover_metadata.check_closable(current_slot)
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +225,30 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_check_closable() {
+        let associated_merkle_tree = Pubkey::new_unique();
+        let mut queue_metadata =
+            create_queue_metadata(associated_merkle_tree, QueueType::NullifierV1);
+        assert!(matches!(
+            queue_metadata.check_closable(associated_merkle_tree, 1_000),
+            Err(MerkleTreeMetadataError::NotRolledOver)
+        ));
+
+        queue_metadata.rollover_metadata.rolledover_slot = 10;
+        assert!(matches!(
+            queue_metadata.check_closable(Pubkey::new_unique(), 1_000),
+            Err(MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated)
+        ));
+        assert!(matches!(
+            queue_metadata.check_closable(associated_merkle_tree, 210),
+            Err(MerkleTreeMetadataError::NotReadyForClose)
+        ));
+        assert!(queue_metadata
+            .check_closable(associated_merkle_tree, 211)
+            .is_ok());
+    }
+
     #[test]
     fn test_queue_type_from() {
         assert_eq!(QueueType::NullifierV1, QueueType::from(1));
@@ -3,6 +3,10 @@ use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 use crate::{errors::MerkleTreeMetadataError, AnchorDeserialize, AnchorSerialize};
 
+/// Close threshold in slots used for accounts created without one,
+/// about ten days (5 Solana epochs).
+pub const DEFAULT_CLOSE_THRESHOLD: u64 = 2_160_000;
+
 #[repr(C)]
 #[derive(
     AnchorDeserialize,
@@ -32,8 +36,8 @@ pub struct RolloverMetadata {
     /// The slot when the account was rolled over, a rolled over account should not be written to.
     pub rolledover_slot: u64,
     /// If current slot is greater than rolledover_slot + close_threshold and
-    /// the account is empty it can be closed. No 'close' functionality has been
-    /// implemented yet.
+    /// the account is empty it can be closed. Accounts without a close
+    /// threshold use DEFAULT_CLOSE_THRESHOLD.
     pub close_threshold: u64,
     /// Placeholder for bytes of additional accounts which are tied to the
     /// Merkle trees operation and need to be rolled over as well.
@@ -87,6 +91,23 @@ impl RolloverMetadata {
         }
         Ok(())
     }
+
+    /// Checks that the account is rolled over and that
+    /// more than close_threshold slots passed since.
+    pub fn check_closable(&self, current_slot: u64) -> Result<(), MerkleTreeMetadataError> {
+        if self.rolledover_slot == u64::MAX {
+            return Err(MerkleTreeMetadataError::NotRolledOver);
+        }
+        let close_threshold = if self.close_threshold == u64::MAX {
+            DEFAULT_CLOSE_THRESHOLD
+        } else {
+            self.close_threshold
+        };
+        if current_slot <= self.rolledover_slot.saturating_add(close_threshold) {
+            return Err(MerkleTreeMetadataError::NotReadyForClose);
+        }
+        Ok(())
+    }
 }
 
 pub fn check_rollover_fee_sufficient(
@@ -159,6 +180,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_closable() {
+        let mut metadata = RolloverMetadata::new(0, 0, Some(95), 0, Some(100), None);
+        assert_eq!(
+            metadata.check_closable(1_000),
+            Err(MerkleTreeMetadataError::NotRolledOver)
+        );
+        metadata.rollover().unwrap();
+        let rolledover_slot = metadata.rolledover_slot;
+        assert_eq!(
+            metadata.check_closable(rolledover_slot + 100),
+            Err(MerkleTreeMetadataError::NotReadyForClose)
+        );
+        metadata.check_closable(rolledover_slot + 101).unwrap();
+
+        // Accounts without close threshold fall back to the default.
+        let mut metadata = RolloverMetadata::new(0, 0, Some(95), 0, None, None);
+        metadata.rollover().unwrap();
+        assert_eq!(
+            metadata.check_closable(rolledover_slot + DEFAULT_CLOSE_THRESHOLD),
+            Err(MerkleTreeMetadataError::NotReadyForClose)
+        );
+        metadata
+            .check_closable(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+            .unwrap();
+    }
+
     #[test]
     fn test_check_rollover_fee_sufficient() {
         let queue_rent = 1_000_000_000;
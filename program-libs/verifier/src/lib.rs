@@ -347,6 +347,16 @@ pub fn verify<const N: usize>(
     proof: &CompressedProof,
     vk: &Groth16Verifyingkey,
 ) -> Result<(), VerifierError> {
+    #[cfg(feature = "mock-proof")]
+    if proof.is_mock() {
+        let mock_proof =
+            CompressedProof::mock(public_inputs).map_err(|_| ProofVerificationFailed)?;
+        return if *proof == mock_proof {
+            Ok(())
+        } else {
+            Err(ProofVerificationFailed)
+        };
+    }
     let proof_a = decompress_g1(&proof.a).map_err(|_| DecompressG1Failed)?;
     let proof_b = decompress_g2(&proof.b).map_err(|_| DecompressG2Failed)?;
     let proof_c = decompress_g1(&proof.c).map_err(|_| DecompressG1Failed)?;
@@ -18,17 +18,21 @@ use light_concurrent_merkle_tree::errors::ConcurrentMerkleTreeError;
 use light_hash_set::{HashSet, HashSetError};
 use light_hasher::{bigint::bigint_to_be_bytes_array, Poseidon};
 use light_indexed_merkle_tree::errors::IndexedMerkleTreeError;
-use light_merkle_tree_metadata::errors::MerkleTreeMetadataError;
+use light_merkle_tree_metadata::{
+    errors::MerkleTreeMetadataError, rollover::DEFAULT_CLOSE_THRESHOLD,
+};
 use light_program_test::{
     accounts::address_tree::create_initialize_address_merkle_tree_and_queue_instruction,
-    indexer::address_tree::AddressMerkleTreeBundle, program_test::LightProgramTest,
-    utils::assert::assert_rpc_error, ProgramTestConfig,
+    indexer::address_tree::AddressMerkleTreeBundle,
+    program_test::{LightProgramTest, TestRpc},
+    utils::assert::assert_rpc_error,
+    ProgramTestConfig,
 };
 use light_test_utils::{
     address::insert_addresses,
     address_tree_rollover::{
-        assert_rolled_over_address_merkle_tree_and_queue, perform_address_merkle_tree_roll_over,
-        set_address_merkle_tree_next_index,
+        assert_rolled_over_address_merkle_tree_and_queue, close_address_merkle_tree_and_queue,
+        perform_address_merkle_tree_roll_over, set_address_merkle_tree_next_index,
     },
     airdrop_lamports, create_account_instruction,
     create_address_merkle_tree_and_queue_account_with_assert, get_hash_set,
@@ -1409,6 +1413,207 @@ async fn test_address_merkle_tree_and_queue_rollover_custom() {
     }
 }
 
+/// Sets the next index of the address Merkle tree to the rollover threshold
+/// and rolls over the tree and queue.
+/// Returns the rollover slot.
+async fn roll_over_address_merkle_tree_and_queue(
+    context: &mut LightProgramTest,
+    bundle: &AddressMerkleTreeBundle,
+    merkle_tree_config: &AddressMerkleTreeConfig,
+    queue_config: &AddressQueueConfig,
+) -> u64 {
+    let address_merkle_tree_pubkey = bundle.accounts.merkle_tree;
+    let address_queue_pubkey = bundle.accounts.queue;
+    let address_merkle_tree_lamports = context
+        .get_account(address_merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    let rollover_costs = context
+        .get_account(address_queue_pubkey)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports
+        + address_merkle_tree_lamports;
+    airdrop_lamports(context, &address_queue_pubkey, rollover_costs)
+        .await
+        .unwrap();
+    let required_next_index = 2u64.pow(26) * merkle_tree_config.rollover_threshold.unwrap() / 100;
+    set_address_merkle_tree_next_index(
+        context,
+        &address_merkle_tree_pubkey,
+        required_next_index,
+        address_merkle_tree_lamports,
+    )
+    .await;
+    perform_address_merkle_tree_roll_over(
+        context,
+        &Keypair::new(),
+        &Keypair::new(),
+        &address_merkle_tree_pubkey,
+        &address_queue_pubkey,
+        merkle_tree_config,
+        queue_config,
+    )
+    .await
+    .unwrap();
+    context.get_slot().await.unwrap()
+}
+
+/// Tests:
+/// 1. Should fail: address Merkle tree not rolled over
+/// 2. Should fail: close threshold has not passed
+/// 3. Should fail: Merkle tree and queue not associated
+/// 4. Should succeed: close rolled over address Merkle tree and queue
+#[tokio::test]
+async fn test_close_address_merkle_tree_and_queue() {
+    let merkle_tree_config = AddressMerkleTreeConfig::default();
+    let queue_config = AddressQueueConfig::default();
+    let (mut context, payer, bundle) =
+        test_setup_with_address_merkle_tree(&merkle_tree_config, &queue_config).await;
+    let address_merkle_tree_pubkey = bundle.accounts.merkle_tree;
+    let address_queue_pubkey = bundle.accounts.queue;
+    let address_merkle_tree_keypair_2 = Keypair::new();
+    let address_queue_keypair_2 = Keypair::new();
+    create_address_merkle_tree_and_queue_account_with_assert(
+        &payer,
+        false,
+        &mut context,
+        &address_merkle_tree_keypair_2,
+        &address_queue_keypair_2,
+        None,
+        None,
+        &merkle_tree_config,
+        &queue_config,
+        2,
+    )
+    .await
+    .unwrap();
+    let recipient = Pubkey::new_unique();
+
+    // 1. Should fail: address Merkle tree not rolled over.
+    let result = close_address_merkle_tree_and_queue(
+        &mut context,
+        &address_merkle_tree_pubkey,
+        &address_queue_pubkey,
+        &recipient,
+    )
+    .await;
+    assert_rpc_error(result, 0, MerkleTreeMetadataError::NotRolledOver.into()).unwrap();
+
+    let rolledover_slot = roll_over_address_merkle_tree_and_queue(
+        &mut context,
+        &bundle,
+        &merkle_tree_config,
+        &queue_config,
+    )
+    .await;
+
+    // 2. Should fail: close threshold has not passed.
+    let result = close_address_merkle_tree_and_queue(
+        &mut context,
+        &address_merkle_tree_pubkey,
+        &address_queue_pubkey,
+        &recipient,
+    )
+    .await;
+    assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    // 3. Should fail: Merkle tree and queue not associated.
+    let result = close_address_merkle_tree_and_queue(
+        &mut context,
+        &address_merkle_tree_pubkey,
+        &address_queue_keypair_2.pubkey(),
+        &recipient,
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated.into(),
+    )
+    .unwrap();
+
+    // 4. Should succeed: close rolled over address Merkle tree and queue.
+    let mut expected_lamports = 0;
+    for pubkey in [address_merkle_tree_pubkey, address_queue_pubkey] {
+        expected_lamports += context.get_account(pubkey).await.unwrap().unwrap().lamports;
+    }
+    close_address_merkle_tree_and_queue(
+        &mut context,
+        &address_merkle_tree_pubkey,
+        &address_queue_pubkey,
+        &recipient,
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .get_account(address_merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(context
+        .get_account(address_queue_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    let recipient_account = context.get_account(recipient).await.unwrap().unwrap();
+    assert_eq!(recipient_account.lamports, expected_lamports);
+}
+
+/// Tests:
+/// 1. Should fail: close rolled over address Merkle tree with an address
+///    which has not been inserted into the tree
+#[tokio::test]
+async fn test_close_address_merkle_tree_and_queue_pending_addresses() {
+    let merkle_tree_config = AddressMerkleTreeConfig::default();
+    let queue_config = AddressQueueConfig::default();
+    let (mut context, _, bundle) =
+        test_setup_with_address_merkle_tree(&merkle_tree_config, &queue_config).await;
+    let address_merkle_tree_pubkey = bundle.accounts.merkle_tree;
+    let address_queue_pubkey = bundle.accounts.queue;
+    let address = bigint_to_be_bytes_array(&30_u32.to_biguint().unwrap()).unwrap();
+    insert_addresses(
+        &mut context,
+        address_queue_pubkey,
+        address_merkle_tree_pubkey,
+        vec![address],
+    )
+    .await
+    .unwrap();
+    let rolledover_slot = roll_over_address_merkle_tree_and_queue(
+        &mut context,
+        &bundle,
+        &merkle_tree_config,
+        &queue_config,
+    )
+    .await;
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    // 1. Should fail: the queued address is not inserted into the tree.
+    let result = close_address_merkle_tree_and_queue(
+        &mut context,
+        &address_merkle_tree_pubkey,
+        &address_queue_pubkey,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        AccountCompressionErrorCode::NotAllLeavesProcessed.into(),
+    )
+    .unwrap();
+}
+
 pub async fn test_setup_with_address_merkle_tree(
     merkle_tree_config: &AddressMerkleTreeConfig,
     queue_config: &AddressQueueConfig,
@@ -31,10 +31,12 @@ use light_compressed_account::{
     tx_hash::create_tx_hash,
 };
 use light_hasher::bigint::bigint_to_be_bytes_array;
-use light_merkle_tree_metadata::errors::MerkleTreeMetadataError;
+use light_merkle_tree_metadata::{
+    errors::MerkleTreeMetadataError, rollover::DEFAULT_CLOSE_THRESHOLD,
+};
 use light_program_test::{
     accounts::{state_tree_v2::create_batched_state_merkle_tree, test_accounts::NOOP_PROGRAM_ID},
-    program_test::LightProgramTest,
+    program_test::{LightProgramTest, TestRpc},
     utils::assert::assert_rpc_error,
     ProgramTestConfig,
 };
@@ -1378,6 +1380,282 @@ async fn test_rollover_batch_state_merkle_trees() {
     }
 }
 
+pub async fn perform_close_batched_state_merkle_tree(
+    context: &mut LightProgramTest,
+    payer: &Keypair,
+    merkle_tree_pubkey: Pubkey,
+    output_queue_pubkey: Pubkey,
+    recipient: Pubkey,
+) -> Result<Signature, RpcError> {
+    let instruction = account_compression::instruction::CloseBatchedStateMerkleTree {};
+    let accounts = account_compression::accounts::CloseBatchedStateMerkleTree {
+        authority: payer.pubkey(),
+        registered_program_pda: None,
+        merkle_tree: merkle_tree_pubkey,
+        output_queue: output_queue_pubkey,
+        recipient,
+    };
+    let instruction = Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction.data(),
+    };
+    context
+        .create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+        .await
+}
+
+/// Initializes a batched state Merkle tree which is ready for rollover,
+/// inserts `num_of_leaves` into the output queue and rolls the tree over.
+/// Returns the rollover slot.
+async fn init_and_roll_over_batch_state_merkle_tree(
+    context: &mut LightProgramTest,
+    mock_indexer: &mut MockBatchedForester<32>,
+    merkle_tree_keypair: &Keypair,
+    output_queue_keypair: &Keypair,
+    num_of_leaves: u32,
+) -> u64 {
+    let payer = context.get_payer().insecure_clone();
+    let mut params = InitStateTreeAccountsInstructionData::test_default();
+    params.rollover_threshold = Some(0);
+    perform_init_batch_state_merkle_tree(
+        context,
+        &payer,
+        merkle_tree_keypair,
+        output_queue_keypair,
+        params,
+    )
+    .await
+    .unwrap();
+    perform_insert_into_output_queue(
+        context,
+        mock_indexer,
+        output_queue_keypair.pubkey(),
+        &payer,
+        &mut 0,
+        num_of_leaves,
+    )
+    .await
+    .unwrap();
+    airdrop_lamports(context, &output_queue_keypair.pubkey(), 1_000_000_000_000)
+        .await
+        .unwrap();
+    perform_rollover_batch_state_merkle_tree(
+        context,
+        &payer,
+        merkle_tree_keypair.pubkey(),
+        output_queue_keypair.pubkey(),
+        &Keypair::new(),
+        &Keypair::new(),
+        params.additional_bytes,
+        params.network_fee,
+        BatchStateMerkleTreeRollOverTestMode::Functional,
+    )
+    .await
+    .unwrap();
+    context.get_slot().await.unwrap()
+}
+
+/// Tests:
+/// 1. Should fail: state Merkle tree not rolled over
+/// 2. Should fail: close threshold has not passed
+/// 3. Should fail: Merkle tree and queue not associated
+/// 4. Should fail: leaves of the incomplete output zkp batch are not nullified
+/// 5. Should succeed: close after spending the leaves from the output queue
+#[tokio::test]
+async fn test_close_batch_state_merkle_tree() {
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer = context.get_payer().insecure_clone();
+    let mut mock_indexer = MockBatchedForester::<32>::default();
+    let merkle_tree_keypair = Keypair::new();
+    let merkle_tree_pubkey = merkle_tree_keypair.pubkey();
+    let output_queue_keypair = Keypair::new();
+    let output_queue_pubkey = output_queue_keypair.pubkey();
+    let recipient = Pubkey::new_unique();
+    // 1. Should fail: state Merkle tree not rolled over.
+    {
+        let merkle_tree_keypair = Keypair::new();
+        let output_queue_keypair = Keypair::new();
+        perform_init_batch_state_merkle_tree(
+            &mut context,
+            &payer,
+            &merkle_tree_keypair,
+            &output_queue_keypair,
+            InitStateTreeAccountsInstructionData::test_default(),
+        )
+        .await
+        .unwrap();
+        let result = perform_close_batched_state_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_keypair.pubkey(),
+            output_queue_keypair.pubkey(),
+            recipient,
+        )
+        .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotRolledOver.into()).unwrap();
+    }
+    // Two leaves are an incomplete output zkp batch.
+    let num_of_leaves = 2;
+    let rolledover_slot = init_and_roll_over_batch_state_merkle_tree(
+        &mut context,
+        &mut mock_indexer,
+        &merkle_tree_keypair,
+        &output_queue_keypair,
+        num_of_leaves,
+    )
+    .await;
+    // 2. Should fail: close threshold has not passed.
+    {
+        let result = perform_close_batched_state_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            output_queue_pubkey,
+            recipient,
+        )
+        .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+    }
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+    // 3. Should fail: Merkle tree and queue not associated.
+    {
+        let merkle_tree_keypair_2 = Keypair::new();
+        let output_queue_keypair_2 = Keypair::new();
+        perform_init_batch_state_merkle_tree(
+            &mut context,
+            &payer,
+            &merkle_tree_keypair_2,
+            &output_queue_keypair_2,
+            InitStateTreeAccountsInstructionData::test_default(),
+        )
+        .await
+        .unwrap();
+        let result = perform_close_batched_state_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            output_queue_keypair_2.pubkey(),
+            recipient,
+        )
+        .await;
+        assert_rpc_error(
+            result,
+            0,
+            MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated.into(),
+        )
+        .unwrap();
+    }
+    // 4. Should fail: leaves of the incomplete output zkp batch are not nullified.
+    {
+        let result = perform_close_batched_state_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            output_queue_pubkey,
+            recipient,
+        )
+        .await;
+        assert_rpc_error(
+            result,
+            0,
+            AccountCompressionErrorCode::NotAllLeavesNullified.into(),
+        )
+        .unwrap();
+    }
+    // 5. Should succeed: close after spending the leaves from the output queue.
+    {
+        perform_insert_into_input_queue(
+            &mut context,
+            &mut mock_indexer,
+            &mut 0,
+            num_of_leaves,
+            vec![true; num_of_leaves as usize],
+            output_queue_pubkey,
+            merkle_tree_pubkey,
+            &payer,
+        )
+        .await
+        .unwrap();
+        let mut expected_lamports = 0;
+        for pubkey in [merkle_tree_pubkey, output_queue_pubkey] {
+            expected_lamports += context.get_account(pubkey).await.unwrap().unwrap().lamports;
+        }
+        perform_close_batched_state_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            output_queue_pubkey,
+            recipient,
+        )
+        .await
+        .unwrap();
+        assert!(context
+            .get_account(merkle_tree_pubkey)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(context
+            .get_account(output_queue_pubkey)
+            .await
+            .unwrap()
+            .is_none());
+        let recipient_account = context.get_account(recipient).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, expected_lamports);
+    }
+}
+
+/// Tests:
+/// 1. Should fail: close rolled over state Merkle tree
+///    with a full output zkp batch which has not been appended
+#[tokio::test]
+async fn test_close_batch_state_merkle_tree_pending_zkp_batch() {
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer = context.get_payer().insecure_clone();
+    let mut mock_indexer = MockBatchedForester::<32>::default();
+    let merkle_tree_keypair = Keypair::new();
+    let output_queue_keypair = Keypair::new();
+    let params = InitStateTreeAccountsInstructionData::test_default();
+    let rolledover_slot = init_and_roll_over_batch_state_merkle_tree(
+        &mut context,
+        &mut mock_indexer,
+        &merkle_tree_keypair,
+        &output_queue_keypair,
+        params.output_queue_zkp_batch_size as u32,
+    )
+    .await;
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    let result = perform_close_batched_state_merkle_tree(
+        &mut context,
+        &payer,
+        merkle_tree_keypair.pubkey(),
+        output_queue_keypair.pubkey(),
+        Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        AccountCompressionErrorCode::NotAllLeavesProcessed.into(),
+    )
+    .unwrap();
+}
+
 #[derive(Debug, PartialEq)]
 pub enum BatchStateMerkleTreeRollOverTestMode {
     Functional,
@@ -2022,6 +2300,194 @@ async fn test_batch_address_merkle_trees() {
         assert_rpc_error(result, 1, BatchedMerkleTreeError::InvalidNetworkFee.into()).unwrap();
     }
 }
+pub async fn perform_close_batched_address_merkle_tree(
+    context: &mut LightProgramTest,
+    payer: &Keypair,
+    merkle_tree_pubkey: Pubkey,
+    recipient: Pubkey,
+) -> Result<Signature, RpcError> {
+    let instruction = account_compression::instruction::CloseBatchedAddressMerkleTree {};
+    let accounts = account_compression::accounts::CloseBatchedAddressMerkleTree {
+        authority: payer.pubkey(),
+        registered_program_pda: None,
+        merkle_tree: merkle_tree_pubkey,
+        recipient,
+    };
+    let instruction = Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction.data(),
+    };
+    context
+        .create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+        .await
+}
+
+/// Initializes a batched address Merkle tree which is ready for rollover,
+/// inserts `num_of_addresses` into its queue and rolls the tree over.
+/// Returns the rollover slot.
+async fn init_and_roll_over_batch_address_merkle_tree(
+    context: &mut LightProgramTest,
+    merkle_tree_keypair: &Keypair,
+    num_of_addresses: u32,
+) -> u64 {
+    let payer = context.get_payer().insecure_clone();
+    let merkle_tree_pubkey = merkle_tree_keypair.pubkey();
+    let mut params = InitAddressTreeAccountsInstructionData::test_default();
+    params.rollover_threshold = Some(0);
+    perform_init_batch_address_merkle_tree(context, &params, merkle_tree_keypair)
+        .await
+        .unwrap();
+    let addresses = (1..=num_of_addresses)
+        .map(|i| bigint_to_be_bytes_array(&i.to_biguint().unwrap()).unwrap())
+        .collect::<Vec<[u8; 32]>>();
+    for addresses in addresses.chunks(2) {
+        insert_addresses(
+            context,
+            merkle_tree_pubkey,
+            merkle_tree_pubkey,
+            addresses.to_vec(),
+        )
+        .await
+        .unwrap();
+    }
+    // sent money for rollover reimbursement, rollover fee is paid in system program now.
+    let rent = context
+        .get_account(merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    airdrop_lamports(context, &merkle_tree_pubkey, rent)
+        .await
+        .unwrap();
+    rollover_batched_address_merkle_tree(
+        context,
+        merkle_tree_pubkey,
+        &payer,
+        RolloverBatchAddressTreeTestMode::Functional,
+    )
+    .await
+    .unwrap();
+    context.get_slot().await.unwrap()
+}
+
+/// Tests:
+/// 1. Should fail: address Merkle tree not rolled over
+/// 2. Should fail: close threshold has not passed
+/// 3. Should succeed: close with addresses of an incomplete zkp batch in the queue
+#[tokio::test]
+async fn test_close_batch_address_merkle_tree() {
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer = context.get_payer().insecure_clone();
+    let merkle_tree_keypair = Keypair::new();
+    let merkle_tree_pubkey = merkle_tree_keypair.pubkey();
+    let recipient = Pubkey::new_unique();
+    // 1. Should fail: address Merkle tree not rolled over.
+    {
+        let merkle_tree_keypair = Keypair::new();
+        perform_init_batch_address_merkle_tree(
+            &mut context,
+            &InitAddressTreeAccountsInstructionData::test_default(),
+            &merkle_tree_keypair,
+        )
+        .await
+        .unwrap();
+        let result = perform_close_batched_address_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_keypair.pubkey(),
+            recipient,
+        )
+        .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotRolledOver.into()).unwrap();
+    }
+    let rolledover_slot =
+        init_and_roll_over_batch_address_merkle_tree(&mut context, &merkle_tree_keypair, 2).await;
+    // 2. Should fail: close threshold has not passed.
+    {
+        let result = perform_close_batched_address_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            recipient,
+        )
+        .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+    }
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+    // 3. Should succeed: close with addresses of an incomplete zkp batch in the queue.
+    {
+        let expected_lamports = context
+            .get_account(merkle_tree_pubkey)
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports;
+        perform_close_batched_address_merkle_tree(
+            &mut context,
+            &payer,
+            merkle_tree_pubkey,
+            recipient,
+        )
+        .await
+        .unwrap();
+        assert!(context
+            .get_account(merkle_tree_pubkey)
+            .await
+            .unwrap()
+            .is_none());
+        let recipient_account = context.get_account(recipient).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, expected_lamports);
+    }
+}
+
+/// Tests:
+/// 1. Should fail: close rolled over address Merkle tree
+///    with a full zkp batch of addresses which has not been inserted
+#[tokio::test]
+async fn test_close_batch_address_merkle_tree_pending_zkp_batch() {
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer = context.get_payer().insecure_clone();
+    let merkle_tree_keypair = Keypair::new();
+    let params = InitAddressTreeAccountsInstructionData::test_default();
+    let rolledover_slot = init_and_roll_over_batch_address_merkle_tree(
+        &mut context,
+        &merkle_tree_keypair,
+        params.input_queue_zkp_batch_size as u32,
+    )
+    .await;
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    let result = perform_close_batched_address_merkle_tree(
+        &mut context,
+        &payer,
+        merkle_tree_keypair.pubkey(),
+        Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        AccountCompressionErrorCode::NotAllLeavesProcessed.into(),
+    )
+    .unwrap();
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RolloverBatchAddressTreeTestMode {
     Functional,
@@ -21,7 +21,9 @@ use light_hash_set::HashSetError;
 use light_hasher::{
     bigint::bigint_to_be_bytes_array, zero_bytes::poseidon::ZERO_BYTES, Hasher, Poseidon,
 };
-use light_merkle_tree_metadata::{errors::MerkleTreeMetadataError, QueueType};
+use light_merkle_tree_metadata::{
+    errors::MerkleTreeMetadataError, rollover::DEFAULT_CLOSE_THRESHOLD, QueueType,
+};
 use light_merkle_tree_reference::MerkleTree;
 use light_program_test::{
     accounts::state_tree::{
@@ -39,8 +41,9 @@ use light_test_utils::{
     get_concurrent_merkle_tree, get_hash_set,
     pack::pack_pubkey,
     state_tree_rollover::{
-        assert_rolled_over_pair, perform_state_merkle_tree_roll_over,
-        set_state_merkle_tree_next_index, StateMerkleTreeRolloverMode,
+        assert_rolled_over_pair, close_state_merkle_tree_and_queue,
+        perform_state_merkle_tree_roll_over, set_state_merkle_tree_next_index,
+        StateMerkleTreeRolloverMode,
     },
     Rpc, RpcError,
 };
@@ -852,6 +855,215 @@ async fn test_init_and_rollover_state_merkle_tree_custom() {
     }
 }
 
+/// Tests:
+/// 1. Should fail: merkle tree not rolled over
+/// 2. Should fail: close threshold has not passed
+/// 3. Should fail: merkle tree and queue not associated
+/// 4. Should succeed: close rolled over state merkle tree and nullifier queue
+#[tokio::test]
+async fn test_close_state_merkle_tree_and_queue() {
+    let merkle_tree_config = StateMerkleTreeConfig::default();
+    let queue_config = NullifierQueueConfig::default();
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer_pubkey = context.get_payer().pubkey();
+    let merkle_tree_keypair = Keypair::new();
+    let merkle_tree_pubkey = merkle_tree_keypair.pubkey();
+    let nullifier_queue_keypair = Keypair::new();
+    let nullifier_queue_pubkey = nullifier_queue_keypair.pubkey();
+    functional_1_initialize_state_merkle_tree_and_nullifier_queue(
+        &mut context,
+        &payer_pubkey,
+        &merkle_tree_keypair,
+        &nullifier_queue_keypair,
+        &merkle_tree_config,
+        &queue_config,
+    )
+    .await;
+    let recipient = Pubkey::new_unique();
+
+    // 1. Should fail: merkle tree not rolled over.
+    let result = close_state_merkle_tree_and_queue(
+        &mut context,
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(result, 0, MerkleTreeMetadataError::NotRolledOver.into()).unwrap();
+
+    let required_next_index = 2u64.pow(26) * merkle_tree_config.rollover_threshold.unwrap() / 100;
+    let lamports = context
+        .get_account(merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    set_state_merkle_tree_next_index(
+        &mut context,
+        &merkle_tree_pubkey,
+        required_next_index,
+        lamports,
+    )
+    .await;
+    let new_nullifier_queue_keypair = Keypair::new();
+    let new_state_merkle_tree_keypair = Keypair::new();
+    let (_, rolledover_slot) = perform_state_merkle_tree_roll_over(
+        &mut context,
+        &new_nullifier_queue_keypair,
+        &new_state_merkle_tree_keypair,
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &merkle_tree_config,
+        &queue_config,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // 2. Should fail: close threshold has not passed.
+    let result = close_state_merkle_tree_and_queue(
+        &mut context,
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    // 3. Should fail: merkle tree and queue not associated.
+    let result = close_state_merkle_tree_and_queue(
+        &mut context,
+        &merkle_tree_pubkey,
+        &new_nullifier_queue_keypair.pubkey(),
+        &recipient,
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        MerkleTreeMetadataError::MerkleTreeAndQueueNotAssociated.into(),
+    )
+    .unwrap();
+
+    // 4. Should succeed: close rolled over state merkle tree and nullifier queue.
+    let mut expected_lamports = 0;
+    for pubkey in [merkle_tree_pubkey, nullifier_queue_pubkey] {
+        expected_lamports += context.get_account(pubkey).await.unwrap().unwrap().lamports;
+    }
+    close_state_merkle_tree_and_queue(
+        &mut context,
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &recipient,
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .get_account(merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(context
+        .get_account(nullifier_queue_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    let recipient_account = context.get_account(recipient).await.unwrap().unwrap();
+    assert_eq!(recipient_account.lamports, expected_lamports);
+}
+
+/// Tests:
+/// 1. Should fail: close rolled over state merkle tree with a leaf which is not nullified
+#[tokio::test]
+async fn test_close_state_merkle_tree_and_queue_root_not_empty() {
+    let merkle_tree_config = StateMerkleTreeConfig::default();
+    let queue_config = NullifierQueueConfig::default();
+    let config = ProgramTestConfig {
+        skip_protocol_init: true,
+        with_prover: false,
+        ..Default::default()
+    };
+    let mut context = LightProgramTest::new(config).await.unwrap();
+    let payer = context.get_payer().insecure_clone();
+    let payer_pubkey = payer.pubkey();
+    let merkle_tree_keypair = Keypair::new();
+    let merkle_tree_pubkey = merkle_tree_keypair.pubkey();
+    let nullifier_queue_keypair = Keypair::new();
+    let nullifier_queue_pubkey = nullifier_queue_keypair.pubkey();
+    functional_1_initialize_state_merkle_tree_and_nullifier_queue(
+        &mut context,
+        &payer_pubkey,
+        &merkle_tree_keypair,
+        &nullifier_queue_keypair,
+        &merkle_tree_config,
+        &queue_config,
+    )
+    .await;
+    let instruction = create_insert_leaves_instruction(
+        vec![(0, [1u8; 32])],
+        payer_pubkey,
+        vec![merkle_tree_pubkey],
+    );
+    context
+        .create_and_send_transaction(&[instruction], &payer_pubkey, &[&payer])
+        .await
+        .unwrap();
+
+    let required_next_index = 2u64.pow(26) * merkle_tree_config.rollover_threshold.unwrap() / 100;
+    let lamports = context
+        .get_account(merkle_tree_pubkey)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+    set_state_merkle_tree_next_index(
+        &mut context,
+        &merkle_tree_pubkey,
+        required_next_index,
+        lamports,
+    )
+    .await;
+    let (_, rolledover_slot) = perform_state_merkle_tree_roll_over(
+        &mut context,
+        &Keypair::new(),
+        &Keypair::new(),
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &merkle_tree_config,
+        &queue_config,
+        None,
+    )
+    .await
+    .unwrap();
+    context
+        .warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+
+    // 1. Should fail: the appended leaf is not nullified.
+    let result = close_state_merkle_tree_and_queue(
+        &mut context,
+        &merkle_tree_pubkey,
+        &nullifier_queue_pubkey,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        AccountCompressionErrorCode::NotAllLeavesNullified.into(),
+    )
+    .unwrap();
+}
+
 /// Tests:
 /// 1. Functional: Initialize merkle tree
 /// 2. Failing: mismatching leaf and merkle tree accounts number
@@ -1,13 +1,19 @@
 #![cfg(feature = "test-only")]
 use light_batched_merkle_tree::{
     constants::NUM_BATCHES,
+    errors::BatchedMerkleTreeError,
     initialize_address_tree::{
-        init_batched_address_merkle_tree_account, InitAddressTreeAccountsInstructionData,
+        check_batched_address_tree_params, init_batched_address_merkle_tree_account,
+        InitAddressTreeAccountsInstructionData,
     },
     initialize_state_tree::test_utils::assert_address_mt_zero_copy_initialized,
-    merkle_tree::{get_merkle_tree_account_size, test_utils::get_merkle_tree_account_size_default},
+    merkle_tree::{
+        get_merkle_tree_account_size, test_utils::get_merkle_tree_account_size_default,
+        BatchedMerkleTreeAccount,
+    },
     merkle_tree_metadata::{BatchedMerkleTreeMetadata, CreateTreeParams},
 };
+use light_bloom_filter::BloomFilterKind;
 use light_compressed_account::pubkey::Pubkey;
 use light_zero_copy::{cyclic_vec::ZeroCopyCyclicVecU64, vec::ZeroCopyVecU64};
 use rand::{rngs::StdRng, Rng};
@@ -71,6 +77,7 @@ fn test_rnd_account_init() {
             close_threshold: None,
             root_history_capacity: rng.gen_range(1..1000),
             height: 40,
+            partitioned_bloom_filter: false,
         };
 
         let mt_account_size = get_merkle_tree_account_size(
@@ -116,3 +123,109 @@ fn test_rnd_account_init() {
         assert_address_mt_zero_copy_initialized(&mut mt_account_data, ref_mt_account, &tree_pubkey);
     }
 }
+
+#[test]
+fn test_check_batched_address_tree_params() {
+    for params in [
+        InitAddressTreeAccountsInstructionData::default(),
+        InitAddressTreeAccountsInstructionData::test_default(),
+        InitAddressTreeAccountsInstructionData::e2e_test_default(),
+    ] {
+        assert_eq!(check_batched_address_tree_params(&params), Ok(()));
+    }
+    let params = InitAddressTreeAccountsInstructionData::test_default();
+    // Address trees have no verifying key for zkp batch size 500.
+    let unsupported_zkp_batch_size = InitAddressTreeAccountsInstructionData {
+        input_queue_batch_size: 1000,
+        input_queue_zkp_batch_size: 500,
+        ..params
+    };
+    let not_divisible = InitAddressTreeAccountsInstructionData {
+        input_queue_batch_size: 55,
+        ..params
+    };
+    let small_root_history = InitAddressTreeAccountsInstructionData {
+        root_history_capacity: 4,
+        ..params
+    };
+    let invalid_height = InitAddressTreeAccountsInstructionData {
+        height: 32,
+        ..params
+    };
+    assert_eq!(
+        check_batched_address_tree_params(&not_divisible),
+        Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize)
+    );
+    assert_eq!(
+        check_batched_address_tree_params(&small_root_history),
+        Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity)
+    );
+    for params in [unsupported_zkp_batch_size, invalid_height] {
+        assert_eq!(
+            check_batched_address_tree_params(&params),
+            Err(BatchedMerkleTreeError::UnsupportedParameters)
+        );
+    }
+}
+
+#[test]
+fn test_partitioned_bloom_filter() {
+    use rand::SeedableRng;
+    let owner = Pubkey::new_unique();
+    let tree_pubkey = Pubkey::new_unique();
+
+    let mt_account_size = get_merkle_tree_account_size_default();
+    let mut mt_account_data = vec![0; mt_account_size];
+    let merkle_tree_rent = 1_000_000_000;
+
+    let params = InitAddressTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..InitAddressTreeAccountsInstructionData::test_default()
+    };
+    assert_eq!(check_batched_address_tree_params(&params), Ok(()));
+    let mt_params = CreateTreeParams::from_address_ix_params(params, owner, tree_pubkey);
+    let ref_mt_account = BatchedMerkleTreeMetadata::new_address_tree(mt_params, merkle_tree_rent);
+    init_batched_address_merkle_tree_account(
+        owner,
+        params,
+        &mut mt_account_data,
+        merkle_tree_rent,
+        tree_pubkey,
+    )
+    .unwrap();
+    assert_address_mt_zero_copy_initialized(&mut mt_account_data, ref_mt_account, &tree_pubkey);
+
+    let mut merkle_tree =
+        BatchedMerkleTreeAccount::address_from_bytes(&mut mt_account_data, &tree_pubkey).unwrap();
+    for batch in merkle_tree.queue_batches.batches.iter() {
+        assert_eq!(
+            batch.get_bloom_filter_kind(),
+            Ok(BloomFilterKind::Partitioned)
+        );
+    }
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..10 {
+        let mut address: [u8; 32] = rng.gen();
+        address[0] = 0;
+        merkle_tree
+            .check_input_queue_non_inclusion(&address)
+            .unwrap();
+        merkle_tree.insert_address_into_queue(&address, &1).unwrap();
+        assert_eq!(
+            merkle_tree.check_input_queue_non_inclusion(&address),
+            Err(BatchedMerkleTreeError::NonInclusionCheckFailed)
+        );
+    }
+
+    // Partitioned bloom filters require at least one block.
+    let small_bloom_filter = InitAddressTreeAccountsInstructionData {
+        input_queue_batch_size: 10,
+        input_queue_zkp_batch_size: 10,
+        bloom_filter_capacity: 256,
+        ..params
+    };
+    assert_eq!(
+        check_batched_address_tree_params(&small_bloom_filter),
+        Err(BatchedMerkleTreeError::UnsupportedParameters)
+    );
+}
@@ -1,14 +1,18 @@
 #![cfg(feature = "test-only")]
 use light_batched_merkle_tree::{
     constants::NUM_BATCHES,
+    errors::BatchedMerkleTreeError,
     initialize_state_tree::{
-        init_batched_state_merkle_tree_accounts,
+        check_batched_tree_params, init_batched_state_merkle_tree_accounts,
         test_utils::{
             assert_state_mt_zero_copy_initialized, create_output_queue_account,
             CreateOutputQueueParams, InitStateTreeAccountsInstructionData,
         },
     },
-    merkle_tree::{get_merkle_tree_account_size, test_utils::get_merkle_tree_account_size_default},
+    merkle_tree::{
+        get_merkle_tree_account_size, test_utils::get_merkle_tree_account_size_default,
+        BatchedMerkleTreeAccount,
+    },
     merkle_tree_metadata::{BatchedMerkleTreeMetadata, CreateTreeParams},
     queue::{
         get_output_queue_account_size,
@@ -16,6 +20,7 @@ use light_batched_merkle_tree::{
         BatchedQueueMetadata,
     },
 };
+use light_bloom_filter::BloomFilterKind;
 use light_compressed_account::pubkey::Pubkey;
 use light_zero_copy::{cyclic_vec::ZeroCopyCyclicVecU64, vec::ZeroCopyVecU64};
 use rand::{rngs::StdRng, Rng};
@@ -165,6 +170,7 @@ fn test_rnd_account_init() {
             close_threshold: None,
             root_history_capacity: rng.gen_range(1..1000),
             height: rng.gen_range(1..32),
+            partitioned_bloom_filter: false,
         };
         let queue_account_size = get_output_queue_account_size(
             params.output_queue_batch_size,
@@ -260,3 +266,129 @@ fn test_rnd_account_init() {
         assert_state_mt_zero_copy_initialized(&mut mt_account_data, ref_mt_account, &mt_pubkey);
     }
 }
+
+#[test]
+fn test_check_batched_tree_params() {
+    for params in [
+        InitStateTreeAccountsInstructionData::default(),
+        InitStateTreeAccountsInstructionData::test_default(),
+        InitStateTreeAccountsInstructionData::e2e_test_default(),
+    ] {
+        assert_eq!(check_batched_tree_params(&params), Ok(()));
+    }
+    let params = InitStateTreeAccountsInstructionData::test_default();
+    // No verifying key for zkp batch size 100.
+    let unsupported_zkp_batch_size = InitStateTreeAccountsInstructionData {
+        input_queue_batch_size: 500,
+        input_queue_zkp_batch_size: 100,
+        ..params
+    };
+    let not_divisible = InitStateTreeAccountsInstructionData {
+        output_queue_batch_size: 55,
+        ..params
+    };
+    let small_root_history = InitStateTreeAccountsInstructionData {
+        root_history_capacity: 9,
+        ..params
+    };
+    let small_bloom_filter = InitStateTreeAccountsInstructionData {
+        bloom_filter_capacity: params.input_queue_batch_size * 8 - 8,
+        ..params
+    };
+    let invalid_height = InitStateTreeAccountsInstructionData {
+        height: 26,
+        ..params
+    };
+    let invalid_rollover_threshold = InitStateTreeAccountsInstructionData {
+        rollover_threshold: Some(101),
+        ..params
+    };
+    assert_eq!(
+        check_batched_tree_params(&not_divisible),
+        Err(BatchedMerkleTreeError::BatchSizeNotDivisibleByZkpBatchSize)
+    );
+    assert_eq!(
+        check_batched_tree_params(&small_root_history),
+        Err(BatchedMerkleTreeError::InvalidRootHistoryCapacity)
+    );
+    for params in [
+        unsupported_zkp_batch_size,
+        small_bloom_filter,
+        invalid_height,
+        invalid_rollover_threshold,
+    ] {
+        assert_eq!(
+            check_batched_tree_params(&params),
+            Err(BatchedMerkleTreeError::UnsupportedParameters)
+        );
+    }
+}
+
+#[test]
+fn test_partitioned_bloom_filter() {
+    let owner = Pubkey::new_unique();
+
+    let queue_account_size = get_output_queue_account_size_default();
+    let mut output_queue_account_data = vec![0; queue_account_size];
+    let output_queue_pubkey = Pubkey::new_unique();
+
+    let mt_account_size = get_merkle_tree_account_size_default();
+    let mut mt_account_data = vec![0; mt_account_size];
+    let mt_pubkey = Pubkey::new_unique();
+
+    let params = InitStateTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..InitStateTreeAccountsInstructionData::test_default()
+    };
+    assert_eq!(check_batched_tree_params(&params), Ok(()));
+
+    let merkle_tree_rent = 1_000_000_000;
+    let queue_rent = 1_000_000_000;
+    let additional_bytes_rent = 1000;
+    init_batched_state_merkle_tree_accounts(
+        owner,
+        params,
+        &mut output_queue_account_data,
+        output_queue_pubkey,
+        queue_rent,
+        &mut mt_account_data,
+        mt_pubkey,
+        merkle_tree_rent,
+        additional_bytes_rent,
+    )
+    .unwrap();
+    let mt_params = CreateTreeParams::from_state_ix_params(params, owner, mt_pubkey);
+    let ref_mt_account = BatchedMerkleTreeMetadata::new_state_tree(mt_params, output_queue_pubkey);
+    assert_state_mt_zero_copy_initialized(&mut mt_account_data, ref_mt_account, &mt_pubkey);
+
+    let mut merkle_tree =
+        BatchedMerkleTreeAccount::state_from_bytes(&mut mt_account_data, &mt_pubkey).unwrap();
+    for batch in merkle_tree.queue_batches.batches.iter() {
+        assert_eq!(
+            batch.get_bloom_filter_kind(),
+            Ok(BloomFilterKind::Partitioned)
+        );
+    }
+    for i in 0..10u8 {
+        let compressed_account_hash = [i; 32];
+        merkle_tree
+            .insert_nullifier_into_queue(&compressed_account_hash, i as u64, &[0u8; 32], &1)
+            .unwrap();
+        assert_eq!(
+            merkle_tree.check_input_queue_non_inclusion(&compressed_account_hash),
+            Err(BatchedMerkleTreeError::NonInclusionCheckFailed)
+        );
+    }
+
+    // Partitioned bloom filters require at least one block.
+    let small_bloom_filter = InitStateTreeAccountsInstructionData {
+        input_queue_batch_size: 10,
+        input_queue_zkp_batch_size: 10,
+        bloom_filter_capacity: 256,
+        ..params
+    };
+    assert_eq!(
+        check_batched_tree_params(&small_bloom_filter),
+        Err(BatchedMerkleTreeError::UnsupportedParameters)
+    );
+}
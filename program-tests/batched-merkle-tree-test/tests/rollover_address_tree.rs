@@ -189,6 +189,7 @@ fn test_rnd_rollover() {
             close_threshold: None,
             root_history_capacity: rng.gen_range(1..1000),
             height: 40,
+            partitioned_bloom_filter: false,
         };
         if forester.is_some() {
             params.network_fee = None;
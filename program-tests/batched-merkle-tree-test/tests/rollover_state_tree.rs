@@ -541,6 +541,7 @@ fn test_rnd_rollover() {
             close_threshold: None,
             root_history_capacity: rng.gen_range(1..1000),
             height: rng.gen_range(1..32),
+            partitioned_bloom_filter: false,
         };
 
         let queue_account_size = get_output_queue_account_size(
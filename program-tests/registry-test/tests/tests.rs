@@ -11,17 +11,25 @@ use forester_utils::{
 };
 use light_account_checks::error::AccountError;
 use light_batched_merkle_tree::{
+    errors::BatchedMerkleTreeError,
     initialize_address_tree::InitAddressTreeAccountsInstructionData,
     initialize_state_tree::test_utils::{
         assert_address_mt_zero_copy_initialized, InitStateTreeAccountsInstructionData,
     },
-    merkle_tree::BatchedMerkleTreeAccount,
+    merkle_tree::{get_merkle_tree_account_size, BatchedMerkleTreeAccount},
     merkle_tree_metadata::{BatchedMerkleTreeMetadata, CreateTreeParams},
     queue::BatchedQueueAccount,
 };
-use light_client::{indexer::Indexer, rpc::LightClientConfig};
+use light_client::{
+    indexer::Indexer,
+    migration::{get_migrate_state_instructions, MigrateStateConfig},
+    rpc::LightClientConfig,
+};
 use light_compressed_account::TreeType;
 use light_hasher::Poseidon;
+use light_merkle_tree_metadata::{
+    errors::MerkleTreeMetadataError, rollover::DEFAULT_CLOSE_THRESHOLD,
+};
 use light_program_test::{
     accounts::{
         address_tree::create_address_merkle_tree_and_queue_account,
@@ -43,10 +51,11 @@ use light_program_test::{
 use light_registry::{
     account_compression_cpi::sdk::{
         create_batch_append_instruction, create_batch_nullify_instruction,
-        create_batch_update_address_tree_instruction, create_migrate_state_instruction,
+        create_batch_update_address_tree_instruction, create_close_batch_address_tree_instruction,
+        create_close_batch_state_tree_instruction, create_migrate_state_instruction,
         create_nullify_instruction, create_update_address_merkle_tree_instruction,
-        CreateMigrateStateInstructionInputs, CreateNullifyInstructionInputs,
-        UpdateAddressMerkleTreeInstructionInputs,
+        get_registered_program_pda, CreateMigrateStateInstructionInputs,
+        CreateNullifyInstructionInputs, UpdateAddressMerkleTreeInstructionInputs,
     },
     errors::RegistryError,
     protocol_config::state::{ProtocolConfig, ProtocolConfigPda},
@@ -65,7 +74,7 @@ use light_test_utils::{
         assert_epoch_pda, assert_finalized_epoch_registration, assert_registered_forester_pda,
         assert_report_work, fetch_epoch_and_forester_pdas,
     },
-    create_address_merkle_tree_and_queue_account_with_assert,
+    create_account_instruction, create_address_merkle_tree_and_queue_account_with_assert,
     create_address_test_program_sdk::perform_create_pda_with_event_rnd,
     create_rollover_address_merkle_tree_instructions,
     create_rollover_state_merkle_tree_instructions,
@@ -934,6 +943,7 @@ async fn test_register_and_update_forester_pda() {
             queue: env.v1_state_trees[0].nullifier_queue,
             is_rolledover: false,
             owner: Default::default(),
+            network_fee: 5000,
         },
         TreeAccounts {
             tree_type: TreeType::AddressV1,
@@ -941,6 +951,7 @@ async fn test_register_and_update_forester_pda() {
             queue: env.v1_address_trees[0].queue,
             is_rolledover: false,
             owner: Default::default(),
+            network_fee: 5000,
         },
     ];
 
@@ -1760,11 +1771,114 @@ async fn test_migrate_state() {
         .unwrap();
     }
 }
+/// Test:
+/// 1. light-client migrate state instruction matches the registry sdk instruction
+/// 2. Functional: migrate all v1 accounts of an owner with light-client instructions
+#[serial]
+#[tokio::test]
+async fn test_migrate_state_with_client() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::default_with_batched_trees(true))
+        .await
+        .unwrap();
+
+    rpc.indexer = None;
+    let test_accounts = rpc.test_accounts.clone();
+    let payer = rpc.get_payer().insecure_clone();
+    let forester = test_accounts.protocol.forester.insecure_clone();
+    let mut test_indexer: TestIndexer = TestIndexer::init_from_acounts(
+        &forester,
+        &rpc.test_accounts,
+        ProgramTestConfig::default_with_batched_trees(true)
+            .v2_state_tree_config
+            .unwrap()
+            .output_queue_batch_size as usize,
+    )
+    .await;
+    let num_accounts = 4;
+    for _ in 0..num_accounts {
+        light_test_utils::system_program::compress_sol_test(
+            &mut rpc,
+            &mut test_indexer,
+            &payer,
+            &[],
+            false,
+            1_000_000,
+            &test_accounts.v1_state_trees[0].merkle_tree,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+    let config = MigrateStateConfig {
+        authority: forester.pubkey(),
+        derivation: forester.pubkey(),
+        epoch: 0,
+        output_queue: test_accounts.v2_state_trees[0].output_queue,
+    };
+    let batch = get_migrate_state_instructions(
+        &test_indexer,
+        &payer.pubkey(),
+        Some(test_accounts.v1_state_trees[0].merkle_tree),
+        &config,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(batch.instructions.len(), num_accounts);
+    assert_eq!(batch.hashes.len(), num_accounts);
+    // 1. light-client instruction matches the registry sdk instruction
+    {
+        let proof = test_indexer
+            .get_multiple_compressed_account_proofs(vec![batch.hashes[0]], None)
+            .await
+            .unwrap()
+            .value
+            .items[0]
+            .clone();
+        let params = light_client::migration::MigrateLeafParams::try_from(&proof).unwrap();
+        let instruction = create_migrate_state_instruction(
+            CreateMigrateStateInstructionInputs {
+                authority: forester.pubkey(),
+                merkle_tree: test_accounts.v1_state_trees[0].merkle_tree,
+                output_queue: test_accounts.v2_state_trees[0].output_queue,
+                derivation: forester.pubkey(),
+                inputs: MigrateLeafParams {
+                    change_log_index: params.change_log_index,
+                    leaf: params.leaf,
+                    leaf_index: params.leaf_index,
+                    proof: params.proof,
+                },
+                is_metadata_forester: false,
+            },
+            0,
+        );
+        assert_eq!(batch.instructions[0], instruction);
+    }
+    // 2. Functional: migrate all accounts, proofs are patched with the changelog
+    for instruction in batch.instructions {
+        rpc.create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+            .await
+            .unwrap();
+    }
+    let mut output_queue_account = rpc
+        .get_account(test_accounts.v2_state_trees[0].output_queue)
+        .await
+        .unwrap()
+        .unwrap();
+    let output_queue =
+        BatchedQueueAccount::output_from_bytes(output_queue_account.data_as_mut_slice()).unwrap();
+    assert_eq!(output_queue.value_vecs[0][..num_accounts], batch.hashes[..]);
+}
+
 /// Test:
 /// 1. Failing: rollover with invalid forester
 /// 2. Functional: rollover with network fee
 /// 3. Functional: rollover without network fee and custom forester
 /// 4. failing: create with state tree with custom forester and invalid non-zero network fee
+/// 5. Failing: close before the close threshold has passed
+/// 6. Failing: close with a leaf which is not nullified
+/// 7. Failing: close with a rent recipient other than the protocol authority
+/// 8. Functional: close after spending the leaf, rent goes to the protocol authority
 #[serial]
 #[tokio::test]
 async fn test_rollover_batch_state_tree() {
@@ -1859,6 +1973,109 @@ async fn test_rollover_batch_state_tree() {
             )
             .await;
         }
+        let rolledover_slot = rpc.get_slot().await.unwrap();
+        let forester = test_accounts.protocol.forester.insecure_clone();
+        let merkle_tree = test_accounts.v2_state_trees[0].merkle_tree;
+        let output_queue = test_accounts.v2_state_trees[0].output_queue;
+        let protocol_authority = test_accounts.protocol.governance_authority.pubkey();
+        // 5. failing close threshold has not passed
+        {
+            let instruction = create_close_batch_state_tree_instruction(
+                forester.pubkey(),
+                forester.pubkey(),
+                merkle_tree,
+                output_queue,
+                protocol_authority,
+                0,
+            );
+            let result = rpc
+                .create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+                .await;
+            assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+        }
+        rpc.warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+            .unwrap();
+        // 6. failing leaf is not nullified
+        {
+            let instruction = create_close_batch_state_tree_instruction(
+                forester.pubkey(),
+                forester.pubkey(),
+                merkle_tree,
+                output_queue,
+                protocol_authority,
+                0,
+            );
+            let result = rpc
+                .create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+                .await;
+            assert_rpc_error(
+                result,
+                0,
+                AccountCompressionErrorCode::NotAllLeavesNullified.into(),
+            )
+            .unwrap();
+        }
+        // Spend the leaf from the output queue of the rolled over tree.
+        let compressed_accounts = test_indexer
+            .get_compressed_accounts_with_merkle_context_by_owner(&payer.pubkey())
+            .into_iter()
+            .filter(|x| x.merkle_context.merkle_tree_pubkey.to_bytes() == merkle_tree.to_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(compressed_accounts.len(), 1);
+        light_test_utils::system_program::decompress_sol_test(
+            &mut rpc,
+            &mut test_indexer,
+            &payer,
+            &compressed_accounts,
+            &payer.pubkey(),
+            1_000_000,
+            &test_accounts.v1_state_trees[0].merkle_tree,
+            None,
+        )
+        .await
+        .unwrap();
+        // 7. failing invalid rent recipient
+        {
+            let instruction = create_close_batch_state_tree_instruction(
+                forester.pubkey(),
+                forester.pubkey(),
+                merkle_tree,
+                output_queue,
+                forester.pubkey(),
+                0,
+            );
+            let result = rpc
+                .create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+                .await;
+            assert_rpc_error(result, 0, RegistryError::InvalidRentRecipient.into()).unwrap();
+        }
+        // 8. functional close
+        {
+            let mut expected_lamports = rpc
+                .get_account(protocol_authority)
+                .await
+                .unwrap()
+                .unwrap()
+                .lamports;
+            for pubkey in [merkle_tree, output_queue] {
+                expected_lamports += rpc.get_account(pubkey).await.unwrap().unwrap().lamports;
+            }
+            let instruction = create_close_batch_state_tree_instruction(
+                forester.pubkey(),
+                forester.pubkey(),
+                merkle_tree,
+                output_queue,
+                protocol_authority,
+                0,
+            );
+            rpc.create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+                .await
+                .unwrap();
+            assert!(rpc.get_account(merkle_tree).await.unwrap().is_none());
+            assert!(rpc.get_account(output_queue).await.unwrap().is_none());
+            let recipient_account = rpc.get_account(protocol_authority).await.unwrap().unwrap();
+            assert_eq!(recipient_account.lamports, expected_lamports);
+        }
     }
     {
         let custom_forester = Keypair::new();
@@ -1948,6 +2165,46 @@ async fn test_batch_address_tree() {
         .await;
         assert_rpc_error(result, 3, RegistryError::InvalidSigner.into()).unwrap();
     }
+    // FAIL: public trees do not support partitioned bloom filters
+    {
+        let result = create_batch_address_merkle_tree(
+            &mut rpc,
+            &payer,
+            &env.protocol.governance_authority,
+            &Keypair::new(),
+            InitAddressTreeAccountsInstructionData {
+                partitioned_bloom_filter: true,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_rpc_error(
+            result,
+            1,
+            RegistryError::PartitionedBloomFilterUnsupported.into(),
+        )
+        .unwrap();
+        let result = create_batched_state_merkle_tree(
+            &payer,
+            &env.protocol.governance_authority,
+            true,
+            &mut rpc,
+            &Keypair::new(),
+            &Keypair::new(),
+            &Keypair::new(),
+            InitStateTreeAccountsInstructionData {
+                partitioned_bloom_filter: true,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_rpc_error(
+            result,
+            3,
+            RegistryError::PartitionedBloomFilterUnsupported.into(),
+        )
+        .unwrap();
+    }
 
     for i in 0..tree_params.input_queue_batch_size * 2 {
         println!("tx {}", i);
@@ -2066,7 +2323,7 @@ async fn test_batch_address_tree() {
 }
 
 pub async fn perform_batch_address_merkle_tree_update<
-    R: Rpc,
+    R: Rpc + TestRpc,
     I: Indexer + TestIndexerExtensions,
 >(
     rpc: &mut R,
@@ -2095,6 +2352,212 @@ pub async fn perform_batch_address_merkle_tree_update<
         .await
 }
 
+/// Test:
+/// 1. FAIL: create program owned state tree with zkp batch size without verifying key
+/// 2. FAIL: create program owned address tree with designated forester and network fee
+/// 3. FAIL: create program owned state tree with invalid network fee
+/// 4. FAIL: create program owned trees not signed by the program owner
+/// 5. SUCCESS: create program owned state tree with custom parameters
+/// 6. SUCCESS: create program owned address tree with designated forester
+#[serial]
+#[tokio::test]
+async fn test_program_owned_batched_trees() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::default_test_forester(true))
+        .await
+        .unwrap();
+    rpc.indexer = None;
+    let env = rpc.test_accounts.clone();
+    // The payer owns the trees, it is not the protocol authority.
+    let payer = rpc.get_payer().insecure_clone();
+    let protocol_config = rpc
+        .get_anchor_account::<ProtocolConfigPda>(&env.protocol.governance_authority_pda)
+        .await
+        .unwrap()
+        .unwrap()
+        .config;
+    let program_owner = payer.pubkey();
+    let state_tree_params = InitStateTreeAccountsInstructionData {
+        program_owner: Some(program_owner.into()),
+        input_queue_batch_size: 100,
+        output_queue_batch_size: 200,
+        input_queue_zkp_batch_size: 10,
+        output_queue_zkp_batch_size: 10,
+        root_history_capacity: 30,
+        bloom_filter_capacity: 100 * 8 * 10,
+        bloom_filter_num_iters: 3,
+        network_fee: Some(protocol_config.network_fee),
+        ..Default::default()
+    };
+    let address_tree_params = InitAddressTreeAccountsInstructionData {
+        program_owner: Some(program_owner.into()),
+        forester: Some(payer.pubkey().into()),
+        input_queue_batch_size: 100,
+        input_queue_zkp_batch_size: 10,
+        root_history_capacity: 10,
+        bloom_filter_capacity: 100 * 8 * 10,
+        bloom_filter_num_iters: 3,
+        network_fee: None,
+        ..Default::default()
+    };
+    // 1. FAIL: zkp batch size without verifying key
+    {
+        let params = InitStateTreeAccountsInstructionData {
+            input_queue_batch_size: 200,
+            input_queue_zkp_batch_size: 20,
+            ..state_tree_params
+        };
+        let result = create_batched_state_merkle_tree(
+            &payer,
+            &payer,
+            true,
+            &mut rpc,
+            &Keypair::new(),
+            &Keypair::new(),
+            &Keypair::new(),
+            params,
+        )
+        .await;
+        assert_rpc_error(
+            result,
+            3,
+            BatchedMerkleTreeError::UnsupportedParameters.into(),
+        )
+        .unwrap();
+    }
+    // 2. FAIL: designated forester with network fee
+    {
+        let params = InitAddressTreeAccountsInstructionData {
+            network_fee: Some(protocol_config.address_network_fee),
+            ..address_tree_params
+        };
+        let result =
+            create_batch_address_merkle_tree(&mut rpc, &payer, &payer, &Keypair::new(), params)
+                .await;
+        assert_rpc_error(result, 1, RegistryError::InvalidNetworkFee.into()).unwrap();
+    }
+    // 3. FAIL: network fee differs from protocol network fee
+    {
+        let params = InitStateTreeAccountsInstructionData {
+            network_fee: Some(protocol_config.network_fee + 1),
+            ..state_tree_params
+        };
+        let result = create_batched_state_merkle_tree(
+            &payer,
+            &payer,
+            true,
+            &mut rpc,
+            &Keypair::new(),
+            &Keypair::new(),
+            &Keypair::new(),
+            params,
+        )
+        .await;
+        assert_rpc_error(result, 3, RegistryError::InvalidNetworkFee.into()).unwrap();
+    }
+    // 4. FAIL: authority is neither the program owner nor its cpi signer
+    {
+        let params = InitStateTreeAccountsInstructionData {
+            program_owner: Some(Pubkey::new_unique().into()),
+            ..state_tree_params
+        };
+        let result = create_batched_state_merkle_tree(
+            &payer,
+            &payer,
+            true,
+            &mut rpc,
+            &Keypair::new(),
+            &Keypair::new(),
+            &Keypair::new(),
+            params,
+        )
+        .await;
+        assert_rpc_error(result, 3, RegistryError::InvalidSigner.into()).unwrap();
+
+        let params = InitAddressTreeAccountsInstructionData {
+            program_owner: Some(Pubkey::new_unique().into()),
+            ..address_tree_params
+        };
+        let result =
+            create_batch_address_merkle_tree(&mut rpc, &payer, &payer, &Keypair::new(), params)
+                .await;
+        assert_rpc_error(result, 1, RegistryError::InvalidSigner.into()).unwrap();
+    }
+    // 5. SUCCESS: program owned state tree with custom parameters
+    {
+        let merkle_tree_keypair = Keypair::new();
+        let queue_keypair = Keypair::new();
+        create_batched_state_merkle_tree(
+            &payer,
+            &payer,
+            true,
+            &mut rpc,
+            &merkle_tree_keypair,
+            &queue_keypair,
+            &Keypair::new(),
+            state_tree_params,
+        )
+        .await
+        .unwrap();
+        let mut merkle_tree_account = rpc
+            .get_account(merkle_tree_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let merkle_tree = BatchedMerkleTreeAccount::state_from_bytes(
+            &mut merkle_tree_account.data,
+            &merkle_tree_keypair.pubkey().into(),
+        )
+        .unwrap();
+        let metadata = merkle_tree.get_metadata();
+        assert_eq!(
+            metadata.metadata.access_metadata.program_owner,
+            program_owner.into()
+        );
+        assert_eq!(metadata.queue_batches.batch_size, 100);
+        assert_eq!(metadata.root_history_capacity, 30);
+        let mut queue_account = rpc
+            .get_account(queue_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let queue = BatchedQueueAccount::output_from_bytes(&mut queue_account.data).unwrap();
+        assert_eq!(queue.get_metadata().batch_metadata.batch_size, 200);
+    }
+    // 6. SUCCESS: program owned address tree with designated forester
+    {
+        let merkle_tree_keypair = Keypair::new();
+        create_batch_address_merkle_tree(
+            &mut rpc,
+            &payer,
+            &payer,
+            &merkle_tree_keypair,
+            address_tree_params,
+        )
+        .await
+        .unwrap();
+        let mut merkle_tree_account = rpc
+            .get_account(merkle_tree_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let merkle_tree = BatchedMerkleTreeAccount::address_from_bytes(
+            &mut merkle_tree_account.data,
+            &merkle_tree_keypair.pubkey().into(),
+        )
+        .unwrap();
+        let metadata = merkle_tree.get_metadata();
+        assert_eq!(
+            metadata.metadata.access_metadata.program_owner,
+            program_owner.into()
+        );
+        assert_eq!(
+            metadata.metadata.access_metadata.forester,
+            payer.pubkey().into()
+        );
+        assert_eq!(metadata.metadata.rollover_metadata.network_fee, 0);
+    }
+}
+
 #[serial]
 #[tokio::test]
 async fn test_rollover_batch_address_tree() {
@@ -2128,6 +2591,7 @@ async fn test_rollover_batch_address_tree() {
     )
     .await
     .unwrap();
+    let rolledover_slot = rpc.get_slot().await.unwrap();
     let mut account = rpc
         .get_account(new_merkle_tree_keypair.pubkey())
         .await
@@ -2182,6 +2646,220 @@ async fn test_rollover_batch_address_tree() {
     )
     .await
     .unwrap();
+
+    let forester = env.protocol.forester.insecure_clone();
+    let merkle_tree = env.v2_address_trees[0];
+    let protocol_authority = env.protocol.governance_authority.pubkey();
+    // close threshold has not passed
+    {
+        let instruction = create_close_batch_address_tree_instruction(
+            forester.pubkey(),
+            forester.pubkey(),
+            merkle_tree,
+            protocol_authority,
+            0,
+        );
+        let result = rpc
+            .create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+            .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+    }
+    rpc.warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+    // invalid rent recipient
+    {
+        let instruction = create_close_batch_address_tree_instruction(
+            forester.pubkey(),
+            forester.pubkey(),
+            merkle_tree,
+            forester.pubkey(),
+            0,
+        );
+        let result = rpc
+            .create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+            .await;
+        assert_rpc_error(result, 0, RegistryError::InvalidRentRecipient.into()).unwrap();
+    }
+    // Close with the address of an incomplete zkp batch in the queue,
+    // rent goes to the protocol authority.
+    {
+        let expected_lamports = rpc
+            .get_account(protocol_authority)
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports
+            + rpc
+                .get_account(merkle_tree)
+                .await
+                .unwrap()
+                .unwrap()
+                .lamports;
+        let instruction = create_close_batch_address_tree_instruction(
+            forester.pubkey(),
+            forester.pubkey(),
+            merkle_tree,
+            protocol_authority,
+            0,
+        );
+        rpc.create_and_send_transaction(&[instruction], &forester.pubkey(), &[&forester])
+            .await
+            .unwrap();
+        assert!(rpc.get_account(merkle_tree).await.unwrap().is_none());
+        let recipient_account = rpc.get_account(protocol_authority).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, expected_lamports);
+    }
+}
+
+/// Test:
+/// 1. Functional: rollover program owned address tree by its designated forester
+/// 2. Failing: close before the close threshold has passed
+/// 3. Failing: close with the protocol authority as rent recipient
+/// 4. Functional: close, rent goes to the designated forester
+#[serial]
+#[tokio::test]
+async fn test_close_program_owned_batched_address_tree() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::default_test_forester(true))
+        .await
+        .unwrap();
+    rpc.indexer = None;
+    let env = rpc.test_accounts.clone();
+    // The payer is the designated forester of the tree.
+    let payer = rpc.get_payer().insecure_clone();
+    let fee_payer = env.protocol.forester.insecure_clone();
+    let params = InitAddressTreeAccountsInstructionData {
+        program_owner: Some(payer.pubkey().into()),
+        forester: Some(payer.pubkey().into()),
+        input_queue_batch_size: 100,
+        input_queue_zkp_batch_size: 10,
+        root_history_capacity: 10,
+        bloom_filter_capacity: 100 * 8 * 10,
+        bloom_filter_num_iters: 3,
+        network_fee: None,
+        rollover_threshold: Some(0),
+        ..Default::default()
+    };
+    let merkle_tree_keypair = Keypair::new();
+    let merkle_tree = merkle_tree_keypair.pubkey();
+    create_batch_address_merkle_tree(&mut rpc, &payer, &payer, &merkle_tree_keypair, params)
+        .await
+        .unwrap();
+    let (cpi_authority, bump) = get_cpi_authority_pda();
+    // 1. Functional: rollover by the designated forester
+    {
+        // Sent funds for rollover reimbursement.
+        airdrop_lamports(&mut rpc, &merkle_tree, 100_000_000_000)
+            .await
+            .unwrap();
+        let new_merkle_tree_keypair = Keypair::new();
+        let mt_account_size = get_merkle_tree_account_size(
+            params.input_queue_batch_size,
+            params.bloom_filter_capacity,
+            params.input_queue_zkp_batch_size,
+            params.root_history_capacity,
+            params.height,
+        );
+        let create_mt_account_ix = create_account_instruction(
+            &payer.pubkey(),
+            mt_account_size,
+            rpc.get_minimum_balance_for_rent_exemption(mt_account_size)
+                .await
+                .unwrap(),
+            &account_compression::ID,
+            Some(&new_merkle_tree_keypair),
+        );
+        let accounts = light_registry::accounts::RolloverBatchedAddressMerkleTree {
+            registered_forester_pda: None,
+            authority: payer.pubkey(),
+            new_address_merkle_tree: new_merkle_tree_keypair.pubkey(),
+            old_address_merkle_tree: merkle_tree,
+            registered_program_pda: get_registered_program_pda(&light_registry::ID),
+            cpi_authority,
+            account_compression_program: account_compression::ID,
+            protocol_config_pda: get_protocol_config_pda_address().0,
+        };
+        let instruction = Instruction {
+            program_id: light_registry::ID,
+            accounts: accounts.to_account_metas(Some(true)),
+            data: light_registry::instruction::RolloverBatchedAddressMerkleTree { bump }.data(),
+        };
+        rpc.create_and_send_transaction(
+            &[create_mt_account_ix, instruction],
+            &payer.pubkey(),
+            &[&payer, &new_merkle_tree_keypair],
+        )
+        .await
+        .unwrap();
+    }
+    let rolledover_slot = rpc.get_slot().await.unwrap();
+    let create_close_instruction = |recipient: Pubkey| {
+        let accounts = light_registry::accounts::CloseBatchedAddressMerkleTree {
+            registered_forester_pda: None,
+            authority: payer.pubkey(),
+            merkle_tree,
+            registered_program_pda: get_registered_program_pda(&light_registry::ID),
+            cpi_authority,
+            account_compression_program: account_compression::ID,
+            protocol_config_pda: get_protocol_config_pda_address().0,
+            recipient,
+        };
+        Instruction {
+            program_id: light_registry::ID,
+            accounts: accounts.to_account_metas(Some(true)),
+            data: light_registry::instruction::CloseBatchedAddressMerkleTree { bump }.data(),
+        }
+    };
+    // 2. Failing: close threshold has not passed
+    {
+        let result = rpc
+            .create_and_send_transaction(
+                &[create_close_instruction(payer.pubkey())],
+                &fee_payer.pubkey(),
+                &[&fee_payer, &payer],
+            )
+            .await;
+        assert_rpc_error(result, 0, MerkleTreeMetadataError::NotReadyForClose.into()).unwrap();
+    }
+    rpc.warp_to_slot(rolledover_slot + DEFAULT_CLOSE_THRESHOLD + 1)
+        .unwrap();
+    // 3. Failing: the protocol authority is not the rent recipient of trees with a designated forester
+    {
+        let result = rpc
+            .create_and_send_transaction(
+                &[create_close_instruction(
+                    env.protocol.governance_authority.pubkey(),
+                )],
+                &fee_payer.pubkey(),
+                &[&fee_payer, &payer],
+            )
+            .await;
+        assert_rpc_error(result, 0, RegistryError::InvalidRentRecipient.into()).unwrap();
+    }
+    // 4. Functional: close, rent goes to the designated forester
+    {
+        let expected_lamports = rpc
+            .get_account(payer.pubkey())
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports
+            + rpc
+                .get_account(merkle_tree)
+                .await
+                .unwrap()
+                .unwrap()
+                .lamports;
+        rpc.create_and_send_transaction(
+            &[create_close_instruction(payer.pubkey())],
+            &fee_payer.pubkey(),
+            &[&fee_payer, &payer],
+        )
+        .await
+        .unwrap();
+        assert!(rpc.get_account(merkle_tree).await.unwrap().is_none());
+        let recipient_account = rpc.get_account(payer.pubkey()).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, expected_lamports);
+    }
 }
 
 #[ignore = "requires account compression program without test features"]
@@ -2235,3 +2913,48 @@ async fn test_v2_address_tree_mainnet_init_fail() {
     )
     .unwrap();
 }
+
+#[ignore = "requires account compression program without test features"]
+#[tokio::test]
+async fn test_v2_state_tree_mainnet_init_partitioned_bloom_filter_fail() {
+    let mut config = ProgramTestConfig::default_test_forester(true);
+    config.v2_state_tree_config = Some(InitStateTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..Default::default()
+    });
+    config.v1_state_tree_config = StateMerkleTreeConfig::default();
+    config.v2_address_tree_config = Some(InitAddressTreeAccountsInstructionData::default());
+    config.additional_programs = Some(vec![(
+        "create_address_test_program",
+        CREATE_ADDRESS_TEST_PROGRAM_ID,
+    )]);
+    let result = LightProgramTest::new(config).await;
+    assert_rpc_error(
+        result,
+        3,
+        AccountCompressionErrorCode::UnsupportedParameters.into(),
+    )
+    .unwrap();
+}
+
+#[ignore = "requires account compression program without test features"]
+#[tokio::test]
+async fn test_v2_address_tree_mainnet_init_partitioned_bloom_filter_fail() {
+    let mut config = ProgramTestConfig::default_test_forester(true);
+    config.v2_state_tree_config = Some(InitStateTreeAccountsInstructionData::default());
+    config.v2_address_tree_config = Some(InitAddressTreeAccountsInstructionData {
+        partitioned_bloom_filter: true,
+        ..Default::default()
+    });
+    config.additional_programs = Some(vec![(
+        "create_address_test_program",
+        CREATE_ADDRESS_TEST_PROGRAM_ID,
+    )]);
+    let result = LightProgramTest::new(config).await;
+    assert_rpc_error(
+        result,
+        1,
+        AccountCompressionErrorCode::UnsupportedParameters.into(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "test-sbf")]
+
+use light_client::indexer::Indexer;
+use light_compressed_account::{
+    compressed_account::CompressedAccount, instruction_data::compressed_proof::CompressedProof,
+};
+use light_program_test::{
+    indexer::TestIndexerExtensions, program_test::LightProgramTest, ProgramTestConfig,
+};
+use light_system_program::errors::SystemProgramError;
+use light_test_utils::{
+    assert_custom_error_or_program_error,
+    system_program::{compress_sol_test, create_invoke_instruction, transfer_compressed_sol_test},
+    Rpc,
+};
+use serial_test::serial;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Mock proofs replace Groth16 verification but are bound to the public
+/// inputs, roots and leaves are still checked on chain.
+/// 1. mock proof for an outdated root (ProofVerificationFailed)
+/// 2. mock proof with the sentinel `c` (ProofVerificationFailed)
+/// 3. mock proof from the test indexer (Success)
+#[serial]
+#[ignore = "requires programs built with `just programs build-mock-prover`"]
+#[tokio::test]
+async fn test_mock_prover() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::new(false, None).with_mock_prover())
+        .await
+        .unwrap();
+    let payer = rpc.get_payer().insecure_clone();
+    let payer_pubkey = payer.pubkey();
+    let env = rpc.test_accounts.clone();
+    let merkle_tree_pubkey = env.v1_state_trees[0].merkle_tree;
+
+    let compress_amount = 1_000_000;
+    let mut test_indexer = rpc.clone_indexer().unwrap();
+    compress_sol_test(
+        &mut rpc,
+        &mut test_indexer,
+        &payer,
+        &Vec::new(),
+        false,
+        compress_amount,
+        &merkle_tree_pubkey,
+        None,
+    )
+    .await
+    .unwrap();
+    rpc.indexer = Some(test_indexer);
+
+    let compressed_account_with_context =
+        rpc.get_compressed_accounts_with_merkle_context_by_owner(&payer_pubkey)[0].clone();
+    let proof_rpc_res = rpc
+        .get_validity_proof(
+            vec![compressed_account_with_context.hash().unwrap()],
+            vec![],
+            None,
+        )
+        .await
+        .unwrap();
+    let proof = proof_rpc_res.value.proof.0.unwrap();
+    assert!(proof.is_mock());
+    let root_index = proof_rpc_res.value.accounts[0].root_index.root_index();
+    let output_compressed_accounts = vec![CompressedAccount {
+        lamports: compress_amount,
+        owner: Keypair::new().pubkey().into(),
+        data: None,
+        address: None,
+    }];
+
+    // 1. Root index 0 is the root of the empty tree.
+    // 2. The sentinel passes as a mock proof but is not bound to the inputs.
+    for (root_index, proof) in [(Some(0), proof), (root_index, CompressedProof::MOCK)] {
+        let instruction = create_invoke_instruction(
+            &payer_pubkey,
+            &payer_pubkey,
+            &[compressed_account_with_context.compressed_account.clone()],
+            &output_compressed_accounts,
+            &[compressed_account_with_context.merkle_context],
+            &[merkle_tree_pubkey],
+            &[root_index],
+            &Vec::new(),
+            Some(proof),
+            None,
+            false,
+            None,
+            true,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer_pubkey),
+            &[&payer],
+            rpc.get_latest_blockhash().await.unwrap().0,
+        );
+        let result = rpc.process_transaction(transaction).await;
+        assert_custom_error_or_program_error(
+            result,
+            SystemProgramError::ProofVerificationFailed.into(),
+        )
+        .unwrap();
+    }
+
+    // 3. Functional
+    let mut test_indexer = rpc.clone_indexer().unwrap();
+    transfer_compressed_sol_test(
+        &mut rpc,
+        &mut test_indexer,
+        &payer,
+        &[compressed_account_with_context],
+        &[Keypair::new().pubkey()],
+        &[merkle_tree_pubkey],
+        None,
+    )
+    .await
+    .unwrap();
+    rpc.indexer = Some(test_indexer);
+}
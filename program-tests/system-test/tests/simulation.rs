@@ -0,0 +1,152 @@
+#![cfg(feature = "test-sbf")]
+
+use light_client::indexer::Indexer;
+use light_compressed_account::compressed_account::CompressedAccount;
+use light_program_test::{
+    indexer::TestIndexerExtensions,
+    program_test::{LightProgramTest, TestRpc},
+    ProgramTestConfig,
+};
+use light_test_utils::{
+    system_program::{compress_sol_test, create_invoke_instruction},
+    Rpc,
+};
+use serial_test::serial;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+/// Simulates a compressed sol transfer and compares the result with the
+/// transaction once it is sent.
+/// 1. simulating a transfer which creates lamports fails without events
+/// 2. simulating the transfer returns its event and compressed state diff
+/// 3. the sent transaction emits the simulated event
+#[serial]
+#[tokio::test]
+async fn test_simulate_light_transaction() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::new(false, None))
+        .await
+        .unwrap();
+    let payer = rpc.get_payer().insecure_clone();
+    let recipient = Keypair::new().pubkey();
+    let merkle_tree_pubkey = rpc.test_accounts.v1_state_trees[0].merkle_tree;
+
+    let mut test_indexer = rpc.clone_indexer().unwrap();
+    compress_sol_test(
+        &mut rpc,
+        &mut test_indexer,
+        &payer,
+        &[],
+        false,
+        1_000_000,
+        &merkle_tree_pubkey,
+        None,
+    )
+    .await
+    .unwrap();
+    rpc.indexer = Some(test_indexer);
+
+    let input = rpc
+        .indexer()
+        .unwrap()
+        .get_compressed_accounts_with_merkle_context_by_owner(&payer.pubkey())
+        .remove(0);
+    let input_hash = input.hash().unwrap();
+    let proof = rpc
+        .get_validity_proof(vec![input_hash], vec![], None)
+        .await
+        .unwrap()
+        .value;
+    let root_indices = proof
+        .accounts
+        .iter()
+        .map(|account| account.root_index.root_index())
+        .collect::<Vec<_>>();
+    let transfer = |lamports: u64| {
+        create_invoke_instruction(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &[input.compressed_account.clone()],
+            &[CompressedAccount {
+                lamports,
+                owner: recipient.into(),
+                data: None,
+                address: None,
+            }],
+            &[input.merkle_context],
+            &[merkle_tree_pubkey],
+            &root_indices,
+            &[],
+            proof.proof.0,
+            None,
+            false,
+            None,
+            true,
+        )
+    };
+    let sign = |instruction: &Instruction| {
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[instruction.clone()],
+            Some(&payer.pubkey()),
+            &[&payer],
+            rpc.context.latest_blockhash(),
+        ))
+    };
+
+    // 1. Output lamports exceed the input lamports.
+    let result = rpc
+        .simulate_light_transaction(&sign(&transfer(1_000_001)))
+        .await
+        .unwrap();
+    assert!(result.err.is_some());
+    assert!(result.events.is_empty());
+    assert!(result.diff.created.is_empty());
+
+    // 2. The event is parsed from the inner instructions of the system program.
+    let instruction = transfer(1_000_000);
+    let result = rpc
+        .simulate_light_transaction(&sign(&instruction))
+        .await
+        .unwrap();
+    assert_eq!(result.err, None);
+    assert_eq!(result.events.len(), 1);
+    assert_eq!(result.diff.nullified.len(), 1);
+    assert_eq!(result.diff.nullified[0].hash, input_hash);
+    let nullified = result.diff.nullified[0].account.as_ref().unwrap();
+    assert_eq!(nullified.owner, payer.pubkey());
+    assert_eq!(nullified.lamports, 1_000_000);
+    assert_eq!(result.diff.created.len(), 1);
+    let created = &result.diff.created[0];
+    assert_eq!(created.tree_or_queue, merkle_tree_pubkey);
+    assert_eq!(created.account.owner.to_bytes(), recipient.to_bytes());
+    assert_eq!(created.account.lamports, 1_000_000);
+    assert_eq!(result.diff.compressed_lamports, 0);
+
+    // 3. Simulation does not change state, the sent transaction emits the
+    // simulated event.
+    let (event, _, _) = TestRpc::create_and_send_transaction_with_public_event(
+        &mut rpc,
+        &[instruction],
+        &payer.pubkey(),
+        &[&payer],
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let simulated = &result.events[0].event;
+    assert_eq!(
+        simulated.input_compressed_account_hashes,
+        event.input_compressed_account_hashes
+    );
+    assert_eq!(
+        simulated.output_compressed_account_hashes,
+        event.output_compressed_account_hashes
+    );
+    assert_eq!(simulated.output_leaf_indices, event.output_leaf_indices);
+    assert_eq!(created.hash, event.output_compressed_account_hashes[0]);
+    assert_eq!(created.leaf_index, event.output_leaf_indices[0]);
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "test-sbf")]
+
+use light_program_test::{
+    indexer::{FileTestIndexerStore, TestIndexerExtensions, TestIndexerStore},
+    program_test::LightProgramTest,
+    ProgramTestConfig,
+};
+use light_test_utils::{
+    airdrop_lamports,
+    system_program::{compress_sol_test, transfer_compressed_sol_test},
+    Rpc,
+};
+use serial_test::serial;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+/// Compressed accounts of a snapshot can be spent after resuming from it.
+/// 1. compress sol for the payer and a second owner
+/// 2. save a snapshot including the owner keypair
+/// 3. resume a new LightProgramTest from the snapshot
+/// 4. spend the restored accounts of the payer and the owner
+#[serial]
+#[tokio::test]
+async fn test_resume_from_snapshot() {
+    let mut rpc = LightProgramTest::new(ProgramTestConfig::new(false, None))
+        .await
+        .unwrap();
+    let payer = rpc.get_payer().insecure_clone();
+    let owner = Keypair::new();
+    airdrop_lamports(&mut rpc, &owner.pubkey(), 1_000_000_000)
+        .await
+        .unwrap();
+    let merkle_tree_pubkey = rpc.test_accounts.v1_state_trees[0].merkle_tree;
+
+    // 1. Compress
+    let mut test_indexer = rpc.clone_indexer().unwrap();
+    for authority in [&payer, &owner] {
+        compress_sol_test(
+            &mut rpc,
+            &mut test_indexer,
+            authority,
+            &[],
+            false,
+            1_000_000,
+            &merkle_tree_pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+    rpc.indexer = Some(test_indexer);
+
+    // 2. Save
+    let path = std::env::temp_dir().join(format!(
+        "light-program-test-snapshot-{}.bin",
+        owner.pubkey()
+    ));
+    let store = FileTestIndexerStore::new(path.clone());
+    rpc.save_snapshot(&store, &[], &[&owner]).unwrap();
+
+    // 3. Resume
+    let mut rpc = LightProgramTest::new(
+        ProgramTestConfig::new(false, None).with_indexer_snapshot(path.clone()),
+    )
+    .await
+    .unwrap();
+    let owner = store.load().unwrap().unwrap().keypairs().unwrap().remove(0);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(rpc.get_payer().pubkey(), payer.pubkey());
+
+    // 4. Spend
+    let mut test_indexer = rpc.clone_indexer().unwrap();
+    for authority in [&payer, &owner] {
+        let compressed_accounts =
+            test_indexer.get_compressed_accounts_with_merkle_context_by_owner(&authority.pubkey());
+        assert_eq!(compressed_accounts.len(), 1);
+        transfer_compressed_sol_test(
+            &mut rpc,
+            &mut test_indexer,
+            authority,
+            &compressed_accounts,
+            &[Keypair::new().pubkey()],
+            &[merkle_tree_pubkey],
+            None,
+        )
+        .await
+        .unwrap();
+    }
+    rpc.indexer = Some(test_indexer);
+}
@@ -124,6 +124,35 @@ pub async fn perform_address_merkle_tree_roll_over<R: Rpc>(
     context.process_transaction(transaction).await
 }
 
+pub async fn close_address_merkle_tree_and_queue<R: Rpc>(
+    rpc: &mut R,
+    merkle_tree_pubkey: &Pubkey,
+    queue_pubkey: &Pubkey,
+    recipient: &Pubkey,
+) -> Result<(solana_sdk::signature::Signature, Slot), RpcError> {
+    let instruction_data = instruction::CloseAddressMerkleTreeAndQueue {};
+    let accounts = accounts::CloseAddressMerkleTreeAndQueue {
+        authority: rpc.get_payer().pubkey(),
+        registered_program_pda: None,
+        merkle_tree: *merkle_tree_pubkey,
+        queue: *queue_pubkey,
+        recipient: *recipient,
+    };
+    let instruction = Instruction {
+        program_id: account_compression::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    };
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&rpc.get_payer().pubkey()),
+        &[&rpc.get_payer()],
+        blockhash.0,
+    );
+    rpc.process_transaction_with_context(transaction).await
+}
+
 pub async fn assert_rolled_over_address_merkle_tree_and_queue<R: Rpc>(
     payer: &Pubkey,
     rpc: &mut R,
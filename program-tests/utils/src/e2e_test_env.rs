@@ -1133,6 +1133,7 @@ where
                             queue: state_merkle_tree_bundle.accounts.nullifier_queue,
                             is_rolledover: false,
                             owner: Default::default(),
+                            network_fee: 5000,
                         }
                     })
                     .collect::<Vec<TreeAccounts>>();
@@ -1144,6 +1145,7 @@ where
                             queue: address_merkle_tree_bundle.accounts.queue,
                             is_rolledover: false,
                             owner: Default::default(),
+                            network_fee: 5000,
                         });
                     },
                 );
@@ -56,6 +56,7 @@ pub async fn setup_forester_and_advance_to_epoch<R: Rpc + TestRpc>(
             TreeType::StateV1,
             false,
             Default::default(),
+            5000,
         ),
         TreeAccounts::new(
             test_keypairs.address_merkle_tree.pubkey(),
@@ -63,6 +64,7 @@ pub async fn setup_forester_and_advance_to_epoch<R: Rpc + TestRpc>(
             TreeType::AddressV1,
             false,
             Default::default(),
+            5000,
         ),
     ];
 
@@ -119,6 +119,36 @@ pub async fn perform_state_merkle_tree_roll_over<R: Rpc>(
     rpc.process_transaction_with_context(transaction).await
 }
 
+pub async fn close_state_merkle_tree_and_queue<R: Rpc>(
+    rpc: &mut R,
+    merkle_tree_pubkey: &Pubkey,
+    nullifier_queue_pubkey: &Pubkey,
+    recipient: &Pubkey,
+) -> Result<(solana_sdk::signature::Signature, Slot), RpcError> {
+    let instruction_data =
+        account_compression::instruction::CloseStateMerkleTreeAndNullifierQueue {};
+    let accounts = account_compression::accounts::CloseStateMerkleTreeAndNullifierQueue {
+        authority: rpc.get_payer().pubkey(),
+        registered_program_pda: None,
+        merkle_tree: *merkle_tree_pubkey,
+        nullifier_queue: *nullifier_queue_pubkey,
+        recipient: *recipient,
+    };
+    let instruction = Instruction {
+        program_id: account_compression::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    };
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&rpc.get_payer().pubkey()),
+        &[&rpc.get_payer()],
+        blockhash.0,
+    );
+    rpc.process_transaction_with_context(transaction).await
+}
+
 pub async fn set_state_merkle_tree_next_index<R: Rpc + TestRpc + Indexer>(
     rpc: &mut R,
     merkle_tree_pubkey: &Pubkey,
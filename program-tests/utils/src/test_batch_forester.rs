@@ -25,13 +25,10 @@ use light_compressed_account::{
     hash_chain::create_hash_chain_from_slice, instruction_data::compressed_proof::CompressedProof,
 };
 use light_hasher::{bigint::bigint_to_be_bytes_array, Poseidon};
-use light_prover_client::{
-    proof_client::ProofClient,
-    proof_types::{
-        batch_address_append::{get_batch_address_append_circuit_inputs, to_json},
-        batch_append::{get_batch_append_inputs, BatchAppendInputsJson},
-        batch_update::{get_batch_update_inputs, update_inputs_string},
-    },
+use light_prover_client::proof_types::{
+    batch_address_append::{get_batch_address_append_circuit_inputs, to_json},
+    batch_append::{get_batch_append_inputs, BatchAppendInputsJson},
+    batch_update::{get_batch_update_inputs, update_inputs_string},
 };
 use light_registry::{
     account_compression_cpi::sdk::{
@@ -48,7 +45,7 @@ use solana_sdk::{
     signature::{Keypair, Signature, Signer},
 };
 
-pub async fn perform_batch_append<R: Rpc>(
+pub async fn perform_batch_append<R: TestRpc>(
     rpc: &mut R,
     bundle: &mut StateMerkleTreeBundle,
     forester: &Keypair,
@@ -77,7 +74,7 @@ pub async fn perform_batch_append<R: Rpc>(
     Ok(res)
 }
 
-pub async fn create_append_batch_ix_data<R: Rpc>(
+pub async fn create_append_batch_ix_data<R: TestRpc>(
     rpc: &mut R,
     bundle: &mut StateMerkleTreeBundle,
 ) -> InstructionDataBatchAppendInputs {
@@ -164,7 +161,7 @@ pub async fn create_append_batch_ix_data<R: Rpc>(
             bigint_to_be_bytes_array::<32>(&circuit_inputs.new_root.to_biguint().unwrap()).unwrap(),
             bundle.merkle_tree.root()
         );
-        let proof_client = ProofClient::local();
+        let proof_client = rpc.proof_client();
         let inputs_json = BatchAppendInputsJson::from_inputs(&circuit_inputs).to_string();
 
         match proof_client.generate_proof(inputs_json).await {
@@ -189,7 +186,7 @@ pub async fn create_append_batch_ix_data<R: Rpc>(
     }
 }
 
-pub async fn perform_batch_nullify<R: Rpc>(
+pub async fn perform_batch_nullify<R: TestRpc>(
     rpc: &mut R,
     bundle: &mut StateMerkleTreeBundle,
     forester: &Keypair,
@@ -215,7 +212,7 @@ pub async fn perform_batch_nullify<R: Rpc>(
         .await
 }
 
-pub async fn get_batched_nullify_ix_data<R: Rpc>(
+pub async fn get_batched_nullify_ix_data<R: TestRpc>(
     rpc: &mut R,
     bundle: &mut StateMerkleTreeBundle,
     merkle_tree_pubkey: Pubkey,
@@ -293,7 +290,7 @@ pub async fn get_batched_nullify_ix_data<R: Rpc>(
         &[],
     )
     .unwrap();
-    let proof_client = ProofClient::local();
+    let proof_client = rpc.proof_client();
     let circuit_inputs_new_root =
         bigint_to_be_bytes_array::<32>(&inputs.new_root.to_biguint().unwrap()).unwrap();
     let inputs_json = update_inputs_string(&inputs);
@@ -321,7 +318,7 @@ pub async fn get_batched_nullify_ix_data<R: Rpc>(
 
 use forester_utils::instructions::create_account::create_account_instruction;
 use light_client::indexer::{Indexer, QueueElementsV2Options};
-use light_program_test::indexer::state_tree::StateMerkleTreeBundle;
+use light_program_test::{indexer::state_tree::StateMerkleTreeBundle, program_test::TestRpc};
 use light_sparse_merkle_tree::SparseMerkleTree;
 
 use crate::AccountZeroCopy;
@@ -640,7 +637,10 @@ pub async fn assert_registry_created_batched_address_merkle_tree<R: Rpc>(
     Ok(())
 }
 
-pub async fn create_batch_update_address_tree_instruction_data_with_proof<R: Rpc, I: Indexer>(
+pub async fn create_batch_update_address_tree_instruction_data_with_proof<
+    R: TestRpc,
+    I: Indexer,
+>(
     rpc: &mut R,
     indexer: &mut I,
     merkle_tree_pubkey: Pubkey,
@@ -715,7 +715,7 @@ pub async fn create_batch_update_address_tree_instruction_data_with_proof<R: Rpc
         )
         .unwrap();
 
-    let proof_client = ProofClient::local();
+    let proof_client = rpc.proof_client();
     let circuit_inputs_new_root = bigint_to_be_bytes_array::<32>(&inputs.new_root).unwrap();
     let inputs_json = to_json(&inputs);
 
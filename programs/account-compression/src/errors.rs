@@ -70,4 +70,6 @@ pub enum AccountCompressionErrorCode {
     TooManyAddresses,
     #[msg("Too many nullifiers. The maximum is 10 per instruction.")]
     TooManyNullifiers,
+    #[msg("NotAllLeavesNullified")]
+    NotAllLeavesNullified,
 }
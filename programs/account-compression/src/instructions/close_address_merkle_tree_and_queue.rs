@@ -0,0 +1,84 @@
+use anchor_lang::{prelude::*, solana_program::pubkey::Pubkey};
+
+use crate::{
+    errors::AccountCompressionErrorCode,
+    state::{queue_from_bytes_zero_copy_mut, QueueAccount},
+    utils::{
+        check_signer_is_registered_or_authority::{
+            check_signer_is_registered_or_authority, GroupAccounts,
+        },
+        close_account::close_account,
+    },
+    AddressMerkleTreeAccount, RegisteredProgram,
+};
+
+#[derive(Accounts)]
+pub struct CloseAddressMerkleTreeAndQueue<'info> {
+    pub authority: Signer<'info>,
+    pub registered_program_pda: Option<Account<'info, RegisteredProgram>>,
+    #[account(mut)]
+    pub merkle_tree: AccountLoader<'info, AddressMerkleTreeAccount>,
+    #[account(mut)]
+    pub queue: AccountLoader<'info, QueueAccount>,
+    /// CHECK: receives the lamports of the closed accounts.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+impl<'info> GroupAccounts<'info> for CloseAddressMerkleTreeAndQueue<'info> {
+    fn get_authority(&self) -> &Signer<'info> {
+        &self.authority
+    }
+    fn get_registered_program_pda(&self) -> &Option<Account<'info, RegisteredProgram>> {
+        &self.registered_program_pda
+    }
+}
+
+/// Checks:
+/// 1. Signer is registered or authority
+/// 2. Merkle tree and queue are associated
+/// 3. Merkle tree and queue are rolled over
+///    and the close threshold has passed
+/// 4. All addresses in the queue are inserted into the Merkle tree
+///
+/// Actions:
+/// 1. transfer lamports of both accounts to the recipient
+/// 2. close both accounts
+pub fn process_close_address_merkle_tree_and_queue<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseAddressMerkleTreeAndQueue<'info>>,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    {
+        let merkle_tree_account = ctx.accounts.merkle_tree.load()?;
+        let queue_account = ctx.accounts.queue.load()?;
+        check_signer_is_registered_or_authority::<
+            CloseAddressMerkleTreeAndQueue,
+            AddressMerkleTreeAccount,
+        >(&ctx, &merkle_tree_account)?;
+        merkle_tree_account
+            .metadata
+            .check_closable(ctx.accounts.queue.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+        queue_account
+            .metadata
+            .check_closable(ctx.accounts.merkle_tree.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+    }
+    {
+        let queue = ctx.accounts.queue.to_account_info();
+        let mut queue = queue.try_borrow_mut_data()?;
+        let queue = unsafe { queue_from_bytes_zero_copy_mut(&mut queue)? };
+        if queue
+            .hash_set
+            .first_no_seq()
+            .map_err(ProgramError::from)?
+            .is_some()
+        {
+            return err!(AccountCompressionErrorCode::NotAllLeavesProcessed);
+        }
+    }
+
+    let recipient = ctx.accounts.recipient.to_account_info();
+    close_account(&ctx.accounts.queue.to_account_info(), &recipient)?;
+    close_account(&ctx.accounts.merkle_tree.to_account_info(), &recipient)
+}
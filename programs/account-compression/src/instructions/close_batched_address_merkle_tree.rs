@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use light_batched_merkle_tree::merkle_tree::BatchedMerkleTreeAccount;
+
+use crate::{
+    errors::AccountCompressionErrorCode,
+    utils::{
+        check_signer_is_registered_or_authority::{
+            check_signer_is_registered_or_authority, GroupAccounts,
+        },
+        close_account::close_account,
+    },
+    RegisteredProgram,
+};
+
+#[derive(Accounts)]
+pub struct CloseBatchedAddressMerkleTree<'info> {
+    pub authority: Signer<'info>,
+    pub registered_program_pda: Option<Account<'info, RegisteredProgram>>,
+    /// CHECK: in address_from_account_info.
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    /// CHECK: receives the lamports of the closed account.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+impl<'info> GroupAccounts<'info> for CloseBatchedAddressMerkleTree<'info> {
+    fn get_authority(&self) -> &Signer<'info> {
+        &self.authority
+    }
+    fn get_registered_program_pda(&self) -> &Option<Account<'info, RegisteredProgram>> {
+        &self.registered_program_pda
+    }
+}
+
+/// Close a rolled over batched address Merkle tree.
+/// 1. Check Merkle tree account discriminator, tree type, and program ownership.
+/// 2. Check that signer is registered or authority.
+/// 3. Check that Merkle tree is rolled over,
+///    and that the close threshold has passed.
+/// 4. Check that no full zkp batches of addresses are pending in the queue.
+///    Addresses of an incomplete zkp batch are never inserted
+///    into a rolled over tree and do not prevent closing.
+/// 5. Transfer lamports to the recipient and close the account.
+pub fn process_close_batched_address_merkle_tree<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseBatchedAddressMerkleTree<'info>>,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    {
+        // 1. Check Merkle tree account discriminator, tree type, and program ownership.
+        let merkle_tree =
+            BatchedMerkleTreeAccount::address_from_account_info(&ctx.accounts.merkle_tree)
+                .map_err(ProgramError::from)?;
+
+        // 2. Check that signer is registered or authority.
+        check_signer_is_registered_or_authority::<
+            CloseBatchedAddressMerkleTree,
+            BatchedMerkleTreeAccount,
+        >(&ctx, &merkle_tree)?;
+
+        // 3. Check that Merkle tree is rolled over,
+        //    and that the close threshold has passed.
+        //    The address queue is part of the Merkle tree account.
+        merkle_tree
+            .metadata
+            .rollover_metadata
+            .check_closable(current_slot)
+            .map_err(ProgramError::from)?;
+
+        // 4. Check that no full zkp batches of addresses are pending in the queue.
+        if merkle_tree.queue_batches.has_ready_zkp_batches() {
+            return err!(AccountCompressionErrorCode::NotAllLeavesProcessed);
+        }
+    }
+
+    // 5. Transfer lamports to the recipient and close the account.
+    close_account(&ctx.accounts.merkle_tree, &ctx.accounts.recipient)
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use light_batched_merkle_tree::{
+    merkle_tree::BatchedMerkleTreeAccount, queue::BatchedQueueAccount,
+};
+
+use crate::{
+    errors::AccountCompressionErrorCode,
+    utils::{
+        check_signer_is_registered_or_authority::{
+            check_signer_is_registered_or_authority, GroupAccounts,
+        },
+        close_account::close_account,
+    },
+    RegisteredProgram,
+};
+
+#[derive(Accounts)]
+pub struct CloseBatchedStateMerkleTree<'info> {
+    pub authority: Signer<'info>,
+    pub registered_program_pda: Option<Account<'info, RegisteredProgram>>,
+    /// CHECK: in state_from_account_info.
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    /// CHECK: in output_from_account_info.
+    #[account(mut)]
+    pub output_queue: AccountInfo<'info>,
+    /// CHECK: receives the lamports of the closed accounts.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+impl<'info> GroupAccounts<'info> for CloseBatchedStateMerkleTree<'info> {
+    fn get_authority(&self) -> &Signer<'info> {
+        &self.authority
+    }
+    fn get_registered_program_pda(&self) -> &Option<Account<'info, RegisteredProgram>> {
+        &self.registered_program_pda
+    }
+}
+
+/// Close a rolled over batched state Merkle tree and its output queue.
+/// 1. Check Merkle tree and queue account discriminator, tree type, and program ownership.
+/// 2. Check that signer is registered or authority.
+/// 3. Check that Merkle tree and queue are associated, rolled over,
+///    and that the close threshold has passed.
+/// 4. Check that no full zkp batches are pending in the queues.
+/// 5. Check that every leaf of the output queue is nullified.
+///    Values of an incomplete output zkp batch are never appended
+///    to a rolled over tree, they are closable once spent from the output queue.
+/// 6. Transfer lamports of both accounts to the recipient and close them.
+pub fn process_close_batched_state_merkle_tree<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseBatchedStateMerkleTree<'info>>,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    {
+        // 1. Check Merkle tree and queue account discriminator, tree type, and program ownership.
+        let merkle_tree =
+            BatchedMerkleTreeAccount::state_from_account_info(&ctx.accounts.merkle_tree)
+                .map_err(ProgramError::from)?;
+        let output_queue =
+            BatchedQueueAccount::output_from_account_info(&ctx.accounts.output_queue)
+                .map_err(ProgramError::from)?;
+
+        // 2. Check that signer is registered or authority.
+        check_signer_is_registered_or_authority::<
+            CloseBatchedStateMerkleTree,
+            BatchedMerkleTreeAccount,
+        >(&ctx, &merkle_tree)?;
+
+        // 3. Check that Merkle tree and queue are associated, rolled over,
+        //    and that the close threshold has passed.
+        merkle_tree
+            .metadata
+            .check_closable(ctx.accounts.output_queue.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+        output_queue
+            .metadata
+            .check_closable(ctx.accounts.merkle_tree.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+
+        // 4. Check that no full zkp batches are pending in the queues.
+        if output_queue.batch_metadata.has_ready_zkp_batches()
+            || merkle_tree.queue_batches.has_ready_zkp_batches()
+        {
+            return err!(AccountCompressionErrorCode::NotAllLeavesProcessed);
+        }
+
+        // 5. Check that every leaf of the output queue is nullified.
+        if !merkle_tree.all_leaves_nullified(&output_queue) {
+            return err!(AccountCompressionErrorCode::NotAllLeavesNullified);
+        }
+    }
+
+    // 6. Transfer lamports of both accounts to the recipient and close them.
+    close_account(&ctx.accounts.output_queue, &ctx.accounts.recipient)?;
+    close_account(&ctx.accounts.merkle_tree, &ctx.accounts.recipient)
+}
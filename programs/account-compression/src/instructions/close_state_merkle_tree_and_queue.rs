@@ -0,0 +1,83 @@
+use anchor_lang::{prelude::*, solana_program::pubkey::Pubkey};
+use light_hasher::zero_bytes::poseidon::ZERO_BYTES;
+
+use crate::{
+    errors::AccountCompressionErrorCode,
+    state::{queue::QueueAccount, StateMerkleTreeAccount},
+    state_merkle_tree_from_bytes_zero_copy,
+    utils::{
+        check_signer_is_registered_or_authority::{
+            check_signer_is_registered_or_authority, GroupAccounts,
+        },
+        close_account::close_account,
+    },
+    RegisteredProgram,
+};
+
+#[derive(Accounts)]
+pub struct CloseStateMerkleTreeAndNullifierQueue<'info> {
+    pub authority: Signer<'info>,
+    pub registered_program_pda: Option<Account<'info, RegisteredProgram>>,
+    #[account(mut)]
+    pub merkle_tree: AccountLoader<'info, StateMerkleTreeAccount>,
+    #[account(mut)]
+    pub nullifier_queue: AccountLoader<'info, QueueAccount>,
+    /// CHECK: receives the lamports of the closed accounts.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+impl<'info> GroupAccounts<'info> for CloseStateMerkleTreeAndNullifierQueue<'info> {
+    fn get_authority(&self) -> &Signer<'info> {
+        &self.authority
+    }
+    fn get_registered_program_pda(&self) -> &Option<Account<'info, RegisteredProgram>> {
+        &self.registered_program_pda
+    }
+}
+
+/// Checks:
+/// 1. Signer is registered or authority
+/// 2. Merkle tree and nullifier queue are associated
+/// 3. Merkle tree and nullifier queue are rolled over
+///    and the close threshold has passed
+/// 4. All leaves are nullified, ie the root is the root of an empty tree.
+///    Pending nullifiers have not been inserted into the tree yet,
+///    hence an empty root implies an empty nullifier queue.
+///
+/// Actions:
+/// 1. transfer lamports of both accounts to the recipient
+/// 2. close both accounts
+pub fn process_close_state_merkle_tree_and_nullifier_queue<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseStateMerkleTreeAndNullifierQueue<'info>>,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    {
+        let merkle_tree_account = ctx.accounts.merkle_tree.load()?;
+        let queue_account = ctx.accounts.nullifier_queue.load()?;
+        check_signer_is_registered_or_authority::<
+            CloseStateMerkleTreeAndNullifierQueue,
+            StateMerkleTreeAccount,
+        >(&ctx, &merkle_tree_account)?;
+        merkle_tree_account
+            .metadata
+            .check_closable(ctx.accounts.nullifier_queue.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+        queue_account
+            .metadata
+            .check_closable(ctx.accounts.merkle_tree.key().into(), current_slot)
+            .map_err(ProgramError::from)?;
+    }
+    {
+        let merkle_tree = ctx.accounts.merkle_tree.to_account_info();
+        let merkle_tree = merkle_tree.try_borrow_data()?;
+        let merkle_tree = state_merkle_tree_from_bytes_zero_copy(&merkle_tree)?;
+        if merkle_tree.root() != ZERO_BYTES[merkle_tree.height] {
+            return err!(AccountCompressionErrorCode::NotAllLeavesNullified);
+        }
+    }
+
+    let recipient = ctx.accounts.recipient.to_account_info();
+    close_account(&ctx.accounts.nullifier_queue.to_account_info(), &recipient)?;
+    close_account(&ctx.accounts.merkle_tree.to_account_info(), &recipient)
+}
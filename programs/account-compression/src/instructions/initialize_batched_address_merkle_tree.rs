@@ -44,8 +44,17 @@ pub fn process_initialize_batched_address_merkle_tree<'info>(
     );
     #[cfg(not(feature = "test"))]
     {
+        use light_batched_merkle_tree::initialize_address_tree::check_batched_address_tree_params;
+
         use crate::errors::AccountCompressionErrorCode;
-        if params != InitAddressTreeAccountsInstructionData::default() {
+        // Public trees use default parameters with standard bloom filters.
+        // Program owned trees may use custom parameters with supported circuit sizes
+        // and partitioned bloom filters.
+        if params.program_owner.is_none() {
+            if params != InitAddressTreeAccountsInstructionData::default() {
+                return err!(AccountCompressionErrorCode::UnsupportedParameters);
+            }
+        } else if check_batched_address_tree_params(&params).is_err() {
             return err!(AccountCompressionErrorCode::UnsupportedParameters);
         }
         if let Some(registered_program_pda) = ctx.accounts.registered_program_pda.as_ref() {
@@ -38,8 +38,17 @@ pub fn process_initialize_batched_state_merkle_tree<'info>(
     light_batched_merkle_tree::initialize_state_tree::validate_batched_tree_params(params);
     #[cfg(not(feature = "test"))]
     {
+        use light_batched_merkle_tree::initialize_state_tree::check_batched_tree_params;
+
         use crate::errors::AccountCompressionErrorCode;
-        if params != InitStateTreeAccountsInstructionData::default() {
+        // Public trees use default parameters with standard bloom filters.
+        // Program owned trees may use custom parameters with supported circuit sizes
+        // and partitioned bloom filters.
+        if params.program_owner.is_none() {
+            if params != InitStateTreeAccountsInstructionData::default() {
+                return err!(AccountCompressionErrorCode::UnsupportedParameters);
+            }
+        } else if check_batched_tree_params(&params).is_err() {
             return err!(AccountCompressionErrorCode::UnsupportedParameters);
         }
         if let Some(registered_program_pda) = ctx.accounts.registered_program_pda.as_ref() {
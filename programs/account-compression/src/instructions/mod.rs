@@ -52,6 +52,18 @@ pub use rollover_batched_address_merkle_tree::*;
 pub mod migrate_state;
 pub use migrate_state::*;
 
+pub mod close_state_merkle_tree_and_queue;
+pub use close_state_merkle_tree_and_queue::*;
+
+pub mod close_address_merkle_tree_and_queue;
+pub use close_address_merkle_tree_and_queue::*;
+
+pub mod close_batched_state_merkle_tree;
+pub use close_batched_state_merkle_tree::*;
+
+pub mod close_batched_address_merkle_tree;
+pub use close_batched_address_merkle_tree::*;
+
 pub mod insert_into_queues;
 
 pub mod generic;
@@ -286,6 +286,42 @@ pub mod account_compression {
         process_rollover_batched_state_merkle_tree(ctx, additional_bytes, network_fee)
     }
 
+    /// Close a rolled over state Merkle tree and nullifier queue
+    /// once all leaves are nullified and the close threshold has passed.
+    /// Lamports of both accounts are transferred to the recipient.
+    pub fn close_state_merkle_tree_and_nullifier_queue<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseStateMerkleTreeAndNullifierQueue<'info>>,
+    ) -> Result<()> {
+        process_close_state_merkle_tree_and_nullifier_queue(ctx)
+    }
+
+    /// Close a rolled over address Merkle tree and queue
+    /// once all queued addresses are inserted and the close threshold has passed.
+    /// Lamports of both accounts are transferred to the recipient.
+    pub fn close_address_merkle_tree_and_queue<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseAddressMerkleTreeAndQueue<'info>>,
+    ) -> Result<()> {
+        process_close_address_merkle_tree_and_queue(ctx)
+    }
+
+    /// Close a rolled over batched state Merkle tree and output queue
+    /// once all leaves are nullified and the close threshold has passed.
+    /// Lamports of both accounts are transferred to the recipient.
+    pub fn close_batched_state_merkle_tree<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseBatchedStateMerkleTree<'info>>,
+    ) -> Result<()> {
+        process_close_batched_state_merkle_tree(ctx)
+    }
+
+    /// Close a rolled over batched address Merkle tree
+    /// once all queued addresses are inserted and the close threshold has passed.
+    /// Lamports are transferred to the recipient.
+    pub fn close_batched_address_merkle_tree<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseBatchedAddressMerkleTree<'info>>,
+    ) -> Result<()> {
+        process_close_batched_address_merkle_tree(ctx)
+    }
+
     /// Migrate state from a v1 state Merkle tree
     /// to a v2 state Merkle tree.
     pub fn migrate_state<'a, 'b, 'c: 'info, 'info>(
@@ -0,0 +1,15 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::utils::transfer_lamports::transfer_lamports;
+
+/// Closes a program owned account by transferring all its lamports
+/// to the recipient, and assigning the emptied account to the system program.
+pub fn close_account<'info>(
+    account: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+) -> Result<()> {
+    transfer_lamports(account, recipient, account.lamports())?;
+    account.assign(&system_program::ID);
+    account.realloc(0, false)?;
+    Ok(())
+}
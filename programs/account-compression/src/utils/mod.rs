@@ -1,5 +1,6 @@
 pub mod check_discriminator;
 pub mod check_signer_is_registered_or_authority;
+pub mod close_account;
 pub mod constants;
 pub mod queue;
 pub mod transfer_lamports;
@@ -0,0 +1,96 @@
+use account_compression::{program::AccountCompression, utils::constants::CPI_AUTHORITY_PDA_SEED};
+use anchor_lang::prelude::*;
+
+use crate::{protocol_config::state::ProtocolConfigPda, ForesterEpochPda};
+
+#[derive(Accounts)]
+pub struct CloseBatchedStateMerkleTree<'info> {
+    /// CHECK: only eligible foresters can close trees. Is checked in ix.
+    #[account(mut)]
+    pub registered_forester_pda: Option<Account<'info, ForesterEpochPda>>,
+    pub authority: Signer<'info>,
+    /// CHECK:  in account compression program.
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    /// CHECK:  in account compression program.
+    #[account(mut)]
+    pub output_queue: AccountInfo<'info>,
+    /// CHECK: (account compression program) access control.
+    pub registered_program_pda: AccountInfo<'info>,
+    /// CHECK: (seed constraints) used to invoke account compression program via cpi.
+    #[account(seeds = [CPI_AUTHORITY_PDA_SEED], bump)]
+    pub cpi_authority: AccountInfo<'info>,
+    pub account_compression_program: Program<'info, AccountCompression>,
+    pub protocol_config_pda: Account<'info, ProtocolConfigPda>,
+    /// CHECK: in check_close_rent_recipient.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBatchedAddressMerkleTree<'info> {
+    /// CHECK: only eligible foresters can close trees. Is checked in ix.
+    #[account(mut)]
+    pub registered_forester_pda: Option<Account<'info, ForesterEpochPda>>,
+    pub authority: Signer<'info>,
+    /// CHECK:  in account compression program.
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    /// CHECK: (account compression program) access control.
+    pub registered_program_pda: AccountInfo<'info>,
+    /// CHECK: (seed constraints) used to invoke account compression program via cpi.
+    #[account(seeds = [CPI_AUTHORITY_PDA_SEED], bump)]
+    pub cpi_authority: AccountInfo<'info>,
+    pub account_compression_program: Program<'info, AccountCompression>,
+    pub protocol_config_pda: Account<'info, ProtocolConfigPda>,
+    /// CHECK: in check_close_rent_recipient.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+pub fn process_close_batched_state_merkle_tree(
+    ctx: &Context<CloseBatchedStateMerkleTree>,
+    bump: u8,
+) -> Result<()> {
+    let bump = &[bump];
+    let seeds = [CPI_AUTHORITY_PDA_SEED, bump];
+    let signer_seeds = &[&seeds[..]];
+    let accounts = account_compression::cpi::accounts::CloseBatchedStateMerkleTree {
+        authority: ctx.accounts.cpi_authority.to_account_info(),
+        registered_program_pda: Some(ctx.accounts.registered_program_pda.clone()),
+        merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+        output_queue: ctx.accounts.output_queue.to_account_info(),
+        recipient: ctx.accounts.recipient.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.account_compression_program.to_account_info(),
+        accounts,
+        signer_seeds,
+    );
+
+    account_compression::cpi::close_batched_state_merkle_tree(cpi_ctx)
+}
+
+pub fn process_close_batched_address_merkle_tree(
+    ctx: &Context<CloseBatchedAddressMerkleTree>,
+    bump: u8,
+) -> Result<()> {
+    let bump = &[bump];
+    let seeds = [CPI_AUTHORITY_PDA_SEED, bump];
+    let signer_seeds = &[&seeds[..]];
+    let accounts = account_compression::cpi::accounts::CloseBatchedAddressMerkleTree {
+        authority: ctx.accounts.cpi_authority.to_account_info(),
+        registered_program_pda: Some(ctx.accounts.registered_program_pda.clone()),
+        merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+        recipient: ctx.accounts.recipient.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.account_compression_program.to_account_info(),
+        accounts,
+        signer_seeds,
+    );
+
+    account_compression::cpi::close_batched_address_merkle_tree(cpi_ctx)
+}
@@ -0,0 +1,135 @@
+use account_compression::{
+    program::AccountCompression, utils::constants::CPI_AUTHORITY_PDA_SEED,
+    AddressMerkleTreeAccount, StateMerkleTreeAccount,
+};
+use anchor_lang::prelude::*;
+use light_merkle_tree_metadata::merkle_tree::MerkleTreeMetadata;
+
+use crate::{
+    epoch::register_epoch::ForesterEpochPda, errors::RegistryError,
+    protocol_config::state::ProtocolConfigPda,
+};
+
+#[derive(Accounts)]
+pub struct CloseStateMerkleTreeAndQueue<'info> {
+    /// CHECK: only eligible foresters can close trees. Is checked in ix.
+    #[account(mut)]
+    pub registered_forester_pda: Option<Account<'info, ForesterEpochPda>>,
+    pub authority: Signer<'info>,
+    /// CHECK: (seed constraints) used to invoke account compression program via cpi.
+    #[account(seeds = [CPI_AUTHORITY_PDA_SEED], bump)]
+    pub cpi_authority: AccountInfo<'info>,
+    /// CHECK: (account compression program) group access control.
+    pub registered_program_pda: AccountInfo<'info>,
+    pub account_compression_program: Program<'info, AccountCompression>,
+    /// CHECK: (account compression program).
+    #[account(mut)]
+    pub merkle_tree: AccountLoader<'info, StateMerkleTreeAccount>,
+    /// CHECK: (account compression program).
+    #[account(mut)]
+    pub queue: AccountInfo<'info>,
+    pub protocol_config_pda: Account<'info, ProtocolConfigPda>,
+    /// CHECK: in check_close_rent_recipient.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAddressMerkleTreeAndQueue<'info> {
+    /// CHECK: only eligible foresters can close trees. Is checked in ix.
+    #[account(mut)]
+    pub registered_forester_pda: Option<Account<'info, ForesterEpochPda>>,
+    pub authority: Signer<'info>,
+    /// CHECK: (seed constraints) used to invoke account compression program via cpi.
+    #[account(seeds = [CPI_AUTHORITY_PDA_SEED], bump)]
+    pub cpi_authority: AccountInfo<'info>,
+    /// CHECK: (account compression program) group access control.
+    pub registered_program_pda: AccountInfo<'info>,
+    pub account_compression_program: Program<'info, AccountCompression>,
+    /// CHECK: (account compression program).
+    #[account(mut)]
+    pub merkle_tree: AccountLoader<'info, AddressMerkleTreeAccount>,
+    /// CHECK: (account compression program).
+    #[account(mut)]
+    pub queue: AccountInfo<'info>,
+    pub protocol_config_pda: Account<'info, ProtocolConfigPda>,
+    /// CHECK: in check_close_rent_recipient.
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+}
+
+/// Returns the recipient of the rent of a closed tree.
+/// Trees with a designated forester are rolled over by that forester,
+/// which pays the rent of the new accounts, hence it receives the rent.
+/// The rent of trees serviced by light foresters is funded
+/// by protocol rollover fees and returned to the protocol authority.
+pub fn get_close_rent_recipient(
+    metadata: &MerkleTreeMetadata,
+    protocol_authority: Pubkey,
+) -> Pubkey {
+    let forester: Pubkey = metadata.access_metadata.forester.into();
+    if forester != Pubkey::default() {
+        forester
+    } else {
+        protocol_authority
+    }
+}
+
+pub fn check_close_rent_recipient(
+    metadata: &MerkleTreeMetadata,
+    protocol_config_pda: &ProtocolConfigPda,
+    recipient: Pubkey,
+) -> Result<()> {
+    if recipient != get_close_rent_recipient(metadata, protocol_config_pda.authority) {
+        return err!(RegistryError::InvalidRentRecipient);
+    }
+    Ok(())
+}
+
+pub fn process_close_state_merkle_tree_and_queue(
+    ctx: &Context<CloseStateMerkleTreeAndQueue>,
+    bump: u8,
+) -> Result<()> {
+    let bump = &[bump];
+    let seeds = [CPI_AUTHORITY_PDA_SEED, bump];
+    let signer_seeds = &[&seeds[..]];
+
+    let accounts = account_compression::cpi::accounts::CloseStateMerkleTreeAndNullifierQueue {
+        authority: ctx.accounts.cpi_authority.to_account_info(),
+        registered_program_pda: Some(ctx.accounts.registered_program_pda.to_account_info()),
+        merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+        nullifier_queue: ctx.accounts.queue.to_account_info(),
+        recipient: ctx.accounts.recipient.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.account_compression_program.to_account_info(),
+        accounts,
+        signer_seeds,
+    );
+
+    account_compression::cpi::close_state_merkle_tree_and_nullifier_queue(cpi_ctx)
+}
+
+pub fn process_close_address_merkle_tree_and_queue(
+    ctx: &Context<CloseAddressMerkleTreeAndQueue>,
+    bump: u8,
+) -> Result<()> {
+    let bump = &[bump];
+    let seeds = [CPI_AUTHORITY_PDA_SEED, bump];
+    let signer_seeds = &[&seeds[..]];
+
+    let accounts = account_compression::cpi::accounts::CloseAddressMerkleTreeAndQueue {
+        authority: ctx.accounts.cpi_authority.to_account_info(),
+        registered_program_pda: Some(ctx.accounts.registered_program_pda.to_account_info()),
+        merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+        queue: ctx.accounts.queue.to_account_info(),
+        recipient: ctx.accounts.recipient.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.account_compression_program.to_account_info(),
+        accounts,
+        signer_seeds,
+    );
+
+    account_compression::cpi::close_address_merkle_tree_and_queue(cpi_ctx)
+}
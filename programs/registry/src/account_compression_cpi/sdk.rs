@@ -193,6 +193,81 @@ pub fn create_rollover_state_merkle_tree_instruction(
     }
 }
 
+pub struct CreateCloseMerkleTreeInstructionInputs {
+    pub authority: Pubkey,
+    pub derivation: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub queue: Pubkey,
+    /// Designated forester of the tree or, if the tree has none,
+    /// the protocol authority. Receives the rent of the closed accounts.
+    pub recipient: Pubkey,
+    pub is_metadata_forester: bool,
+}
+
+pub fn create_close_state_merkle_tree_instruction(
+    inputs: CreateCloseMerkleTreeInstructionInputs,
+    epoch: u64,
+) -> Instruction {
+    let (cpi_authority, bump) = get_cpi_authority_pda();
+    let instruction_data = crate::instruction::CloseStateMerkleTreeAndQueue { bump };
+    let registered_program_pda = get_registered_program_pda(&crate::ID);
+    let registered_forester_pda = if inputs.is_metadata_forester {
+        None
+    } else {
+        Some(get_forester_epoch_pda_from_authority(&inputs.derivation, epoch).0)
+    };
+
+    let accounts = crate::accounts::CloseStateMerkleTreeAndQueue {
+        registered_forester_pda,
+        authority: inputs.authority,
+        cpi_authority,
+        registered_program_pda,
+        account_compression_program: account_compression::ID,
+        merkle_tree: inputs.merkle_tree,
+        queue: inputs.queue,
+        protocol_config_pda: get_protocol_config_pda_address().0,
+        recipient: inputs.recipient,
+    };
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
+
+pub fn create_close_address_merkle_tree_instruction(
+    inputs: CreateCloseMerkleTreeInstructionInputs,
+    epoch: u64,
+) -> Instruction {
+    let (cpi_authority, bump) = get_cpi_authority_pda();
+    let instruction_data = crate::instruction::CloseAddressMerkleTreeAndQueue { bump };
+    let registered_program_pda = get_registered_program_pda(&crate::ID);
+    let registered_forester_pda = if inputs.is_metadata_forester {
+        None
+    } else {
+        Some(get_forester_epoch_pda_from_authority(&inputs.derivation, epoch).0)
+    };
+
+    let accounts = crate::accounts::CloseAddressMerkleTreeAndQueue {
+        registered_forester_pda,
+        authority: inputs.authority,
+        cpi_authority,
+        registered_program_pda,
+        account_compression_program: account_compression::ID,
+        merkle_tree: inputs.merkle_tree,
+        queue: inputs.queue,
+        protocol_config_pda: get_protocol_config_pda_address().0,
+        recipient: inputs.recipient,
+    };
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
+
 pub struct UpdateAddressMerkleTreeInstructionInputs {
     pub authority: Pubkey,
     pub derivation: Pubkey,
@@ -358,6 +433,38 @@ pub fn create_initialize_batched_merkle_tree_instruction(
     }
 }
 
+pub fn create_initialize_program_owned_batched_merkle_tree_instruction(
+    authority: Pubkey,
+    merkle_tree_pubkey: Pubkey,
+    queue_pubkey: Pubkey,
+    cpi_context_pubkey: Pubkey,
+    params: InitStateTreeAccountsInstructionData,
+) -> Instruction {
+    let register_program_pda = get_registered_program_pda(&crate::ID);
+    let (cpi_authority, bump) = get_cpi_authority_pda();
+    let protocol_config_pda = get_protocol_config_pda_address().0;
+    let instruction_data = crate::instruction::InitializeProgramOwnedBatchedStateMerkleTree {
+        bump,
+        params: params.try_to_vec().unwrap(),
+    };
+    let accounts = crate::accounts::InitializeBatchedStateMerkleTreeAndQueue {
+        authority,
+        registered_program_pda: register_program_pda,
+        merkle_tree: merkle_tree_pubkey,
+        queue: queue_pubkey,
+        cpi_authority,
+        account_compression_program: account_compression::ID,
+        protocol_config_pda,
+        light_system_program: LightSystemProgram::id(),
+        cpi_context_account: cpi_context_pubkey,
+    };
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
+
 pub fn create_batch_append_instruction(
     forester: Pubkey,
     derivation_pubkey: Pubkey,
@@ -488,6 +595,34 @@ pub fn create_initialize_batched_address_merkle_tree_instruction(
     }
 }
 
+pub fn create_initialize_program_owned_batched_address_merkle_tree_instruction(
+    authority: Pubkey,
+    merkle_tree_pubkey: Pubkey,
+    params: InitAddressTreeAccountsInstructionData,
+) -> Instruction {
+    let register_program_pda = get_registered_program_pda(&crate::ID);
+    let (cpi_authority, bump) = get_cpi_authority_pda();
+
+    let instruction_data = crate::instruction::InitializeProgramOwnedBatchedAddressMerkleTree {
+        bump,
+        params: params.try_to_vec().unwrap(),
+    };
+    let protocol_config_pda = get_protocol_config_pda_address().0;
+    let accounts = crate::accounts::InitializeBatchedAddressTree {
+        authority,
+        registered_program_pda: register_program_pda,
+        merkle_tree: merkle_tree_pubkey,
+        cpi_authority,
+        account_compression_program: account_compression::ID,
+        protocol_config_pda,
+    };
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
+
 pub fn create_batch_update_address_tree_instruction(
     forester: Pubkey,
     derivation_pubkey: Pubkey,
@@ -545,3 +680,63 @@ pub fn create_rollover_batch_address_tree_instruction(
         data: instruction_data.data(),
     }
 }
+
+pub fn create_close_batch_state_tree_instruction(
+    forester: Pubkey,
+    derivation_pubkey: Pubkey,
+    merkle_tree: Pubkey,
+    output_queue: Pubkey,
+    recipient: Pubkey,
+    epoch: u64,
+) -> Instruction {
+    let forester_epoch_pda = get_forester_epoch_pda_from_authority(&derivation_pubkey, epoch).0;
+    let registered_program_pda = get_registered_program_pda(&crate::ID);
+
+    let (cpi_authority_pda, bump) = get_cpi_authority_pda();
+    let accounts = crate::accounts::CloseBatchedStateMerkleTree {
+        registered_forester_pda: Some(forester_epoch_pda),
+        authority: forester,
+        merkle_tree,
+        output_queue,
+        registered_program_pda,
+        cpi_authority: cpi_authority_pda,
+        account_compression_program: account_compression::ID,
+        protocol_config_pda: get_protocol_config_pda_address().0,
+        recipient,
+    };
+    let instruction_data = crate::instruction::CloseBatchedStateMerkleTree { bump };
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
+
+pub fn create_close_batch_address_tree_instruction(
+    forester: Pubkey,
+    derivation_pubkey: Pubkey,
+    merkle_tree: Pubkey,
+    recipient: Pubkey,
+    epoch: u64,
+) -> Instruction {
+    let forester_epoch_pda = get_forester_epoch_pda_from_authority(&derivation_pubkey, epoch).0;
+    let registered_program_pda = get_registered_program_pda(&crate::ID);
+
+    let (cpi_authority_pda, bump) = get_cpi_authority_pda();
+    let accounts = crate::accounts::CloseBatchedAddressMerkleTree {
+        registered_forester_pda: Some(forester_epoch_pda),
+        authority: forester,
+        merkle_tree,
+        registered_program_pda,
+        cpi_authority: cpi_authority_pda,
+        account_compression_program: account_compression::ID,
+        protocol_config_pda: get_protocol_config_pda_address().0,
+        recipient,
+    };
+    let instruction_data = crate::instruction::CloseBatchedAddressMerkleTree { bump };
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(Some(true)),
+        data: instruction_data.data(),
+    }
+}
@@ -42,4 +42,8 @@ pub enum RegistryError {
     BorrowAccountDataFailed,
     #[msg("Failed to serialize instruction data")]
     SerializationFailed,
+    #[msg("Rent recipient must be the designated forester or the protocol authority")]
+    InvalidRentRecipient,
+    #[msg("Partitioned bloom filters are only supported for program owned trees")]
+    PartitionedBloomFilterUnsupported,
 }
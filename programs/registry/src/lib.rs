@@ -12,8 +12,8 @@ use light_merkle_tree_metadata::merkle_tree::MerkleTreeMetadata;
 pub mod account_compression_cpi;
 pub mod errors;
 pub use account_compression_cpi::{
-    batch_append::*, batch_nullify::*, batch_update_address_tree::*,
-    initialize_batched_address_tree::*, initialize_batched_state_tree::*,
+    batch_append::*, batch_nullify::*, batch_update_address_tree::*, close_batched_tree::*,
+    close_tree_and_queue::*, initialize_batched_address_tree::*, initialize_batched_state_tree::*,
     initialize_tree_and_queue::*, migrate_state::*, nullify::*, register_program::*,
     rollover_batched_address_tree::*, rollover_batched_state_tree::*, rollover_state_tree::*,
     update_address_tree::*,
@@ -35,9 +35,12 @@ use account_compression::MigrateLeafParams;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use errors::RegistryError;
 use light_batched_merkle_tree::{
-    initialize_address_tree::InitAddressTreeAccountsInstructionData,
-    initialize_state_tree::InitStateTreeAccountsInstructionData,
-    merkle_tree::BatchedMerkleTreeAccount, queue::BatchedQueueAccount,
+    initialize_address_tree::{
+        check_batched_address_tree_params, InitAddressTreeAccountsInstructionData,
+    },
+    initialize_state_tree::{check_batched_tree_params, InitStateTreeAccountsInstructionData},
+    merkle_tree::BatchedMerkleTreeAccount,
+    queue::BatchedQueueAccount,
 };
 use light_compressible::registry_instructions::CreateCompressibleConfig as CreateCompressibleConfigData;
 use protocol_config::state::ProtocolConfig;
@@ -510,6 +513,9 @@ pub mod light_registry {
             msg!("Program owner must not be defined.");
             return err!(RegistryError::ProgramOwnerDefined);
         }
+        if params.partitioned_bloom_filter {
+            return err!(RegistryError::PartitionedBloomFilterUnsupported);
+        }
         if let Some(network_fee) = params.network_fee {
             if network_fee != ctx.accounts.protocol_config_pda.config.network_fee {
                 return err!(RegistryError::InvalidNetworkFee);
@@ -540,6 +546,60 @@ pub mod light_registry {
         )
     }
 
+    /// Creation of a batched state Merkle tree owned by a program.
+    /// The authority must be the program owner, see [`check_program_owner_signer`].
+    /// Custom parameters are limited to supported circuit sizes.
+    pub fn initialize_program_owned_batched_state_merkle_tree<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeBatchedStateMerkleTreeAndQueue<'info>>,
+        bump: u8,
+        params: Vec<u8>,
+    ) -> Result<()> {
+        let params = InitStateTreeAccountsInstructionData::try_from_slice(&params)?;
+        let Some(program_owner) = params.program_owner else {
+            msg!("Program owner must be defined.");
+            return err!(RegistryError::ProgramOwnerUndefined);
+        };
+        check_program_owner_signer(
+            &Pubkey::new_from_array(program_owner.to_bytes()),
+            &ctx.accounts.authority.key(),
+        )?;
+        // Trees without designated forester are serviced by light foresters
+        // and must pay the protocol network fee.
+        // Trees with designated forester must not have a network fee.
+        let network_fee = params.network_fee.unwrap_or_default();
+        if params.forester.is_some() {
+            if network_fee != 0 {
+                msg!("Network fee must be zero for trees with designated forester.");
+                return err!(RegistryError::InvalidNetworkFee);
+            }
+        } else if network_fee != ctx.accounts.protocol_config_pda.config.network_fee {
+            return err!(RegistryError::InvalidNetworkFee);
+        }
+        if params.additional_bytes != ctx.accounts.protocol_config_pda.config.cpi_context_size {
+            msg!(
+                "Additional bytes {}, expected cpi context size: {}",
+                params.additional_bytes,
+                ctx.accounts.protocol_config_pda.config.cpi_context_size
+            );
+            return err!(RegistryError::CpiContextAccountInvalidDataLen);
+        }
+        check_batched_tree_params(&params).map_err(ProgramError::from)?;
+        check_cpi_context(
+            ctx.accounts.cpi_context_account.to_account_info(),
+            &ctx.accounts.protocol_config_pda.config,
+        )?;
+
+        process_initialize_batched_state_merkle_tree(&ctx, bump, params.try_to_vec()?)?;
+
+        process_initialize_cpi_context(
+            bump,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.cpi_context_account.to_account_info(),
+            ctx.accounts.merkle_tree.to_account_info(),
+            ctx.accounts.light_system_program.to_account_info(),
+        )
+    }
+
     pub fn batch_nullify<'info>(
         ctx: Context<'_, '_, '_, 'info, BatchNullify<'info>>,
         bump: u8,
@@ -596,6 +656,9 @@ pub mod light_registry {
             msg!("Program owner must not be defined.");
             return err!(RegistryError::ProgramOwnerDefined);
         }
+        if params.partitioned_bloom_filter {
+            return err!(RegistryError::PartitionedBloomFilterUnsupported);
+        }
         if let Some(network_fee) = params.network_fee {
             if network_fee != ctx.accounts.protocol_config_pda.config.address_network_fee {
                 msg!(
@@ -618,6 +681,36 @@ pub mod light_registry {
         process_initialize_batched_address_merkle_tree(&ctx, bump, params.try_to_vec()?)
     }
 
+    /// Creation of a batched address Merkle tree owned by a program.
+    /// The authority must be the program owner, see [`check_program_owner_signer`].
+    /// Custom parameters are limited to supported circuit sizes.
+    pub fn initialize_program_owned_batched_address_merkle_tree(
+        ctx: Context<InitializeBatchedAddressTree>,
+        bump: u8,
+        params: Vec<u8>,
+    ) -> Result<()> {
+        let params = InitAddressTreeAccountsInstructionData::try_from_slice(&params)?;
+        let Some(program_owner) = params.program_owner else {
+            msg!("Program owner must be defined.");
+            return err!(RegistryError::ProgramOwnerUndefined);
+        };
+        check_program_owner_signer(
+            &Pubkey::new_from_array(program_owner.to_bytes()),
+            &ctx.accounts.authority.key(),
+        )?;
+        let network_fee = params.network_fee.unwrap_or_default();
+        if params.forester.is_some() {
+            if network_fee != 0 {
+                msg!("Network fee must be zero for trees with designated forester.");
+                return err!(RegistryError::InvalidNetworkFee);
+            }
+        } else if network_fee != ctx.accounts.protocol_config_pda.config.address_network_fee {
+            return err!(RegistryError::InvalidNetworkFee);
+        }
+        check_batched_address_tree_params(&params).map_err(ProgramError::from)?;
+        process_initialize_batched_address_merkle_tree(&ctx, bump, params.try_to_vec()?)
+    }
+
     pub fn batch_update_address_tree<'info>(
         ctx: Context<'_, '_, '_, 'info, BatchUpdateAddressTree<'info>>,
         bump: u8,
@@ -685,6 +778,97 @@ pub mod light_registry {
         )
     }
 
+    /// Closes a rolled over state Merkle tree and nullifier queue,
+    /// and returns the rent to the designated forester or the protocol authority.
+    pub fn close_state_merkle_tree_and_queue<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseStateMerkleTreeAndQueue<'info>>,
+        bump: u8,
+    ) -> Result<()> {
+        let metadata = ctx.accounts.merkle_tree.load()?.metadata;
+        check_forester(
+            &metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.queue.key(),
+            &mut ctx.accounts.registered_forester_pda,
+            DEFAULT_WORK_V1,
+        )?;
+        check_close_rent_recipient(
+            &metadata,
+            &ctx.accounts.protocol_config_pda,
+            ctx.accounts.recipient.key(),
+        )?;
+        process_close_state_merkle_tree_and_queue(&ctx, bump)
+    }
+
+    /// Closes a rolled over address Merkle tree and queue,
+    /// and returns the rent to the designated forester or the protocol authority.
+    pub fn close_address_merkle_tree_and_queue<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseAddressMerkleTreeAndQueue<'info>>,
+        bump: u8,
+    ) -> Result<()> {
+        let metadata = ctx.accounts.merkle_tree.load()?.metadata;
+        check_forester(
+            &metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.queue.key(),
+            &mut ctx.accounts.registered_forester_pda,
+            DEFAULT_WORK_V1,
+        )?;
+        check_close_rent_recipient(
+            &metadata,
+            &ctx.accounts.protocol_config_pda,
+            ctx.accounts.recipient.key(),
+        )?;
+        process_close_address_merkle_tree_and_queue(&ctx, bump)
+    }
+
+    /// Closes a rolled over batched state Merkle tree and output queue,
+    /// and returns the rent to the designated forester or the protocol authority.
+    pub fn close_batched_state_merkle_tree<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBatchedStateMerkleTree<'info>>,
+        bump: u8,
+    ) -> Result<()> {
+        let account = BatchedMerkleTreeAccount::state_from_account_info(&ctx.accounts.merkle_tree)
+            .map_err(ProgramError::from)?;
+        check_forester(
+            &account.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.merkle_tree.key(),
+            &mut ctx.accounts.registered_forester_pda,
+            DEFAULT_WORK_V1,
+        )?;
+        check_close_rent_recipient(
+            &account.metadata,
+            &ctx.accounts.protocol_config_pda,
+            ctx.accounts.recipient.key(),
+        )?;
+        process_close_batched_state_merkle_tree(&ctx, bump)
+    }
+
+    /// Closes a rolled over batched address Merkle tree,
+    /// and returns the rent to the designated forester or the protocol authority.
+    pub fn close_batched_address_merkle_tree<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBatchedAddressMerkleTree<'info>>,
+        bump: u8,
+    ) -> Result<()> {
+        let account =
+            BatchedMerkleTreeAccount::address_from_account_info(&ctx.accounts.merkle_tree)
+                .map_err(ProgramError::from)?;
+        check_forester(
+            &account.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.merkle_tree.key(),
+            &mut ctx.accounts.registered_forester_pda,
+            DEFAULT_WORK_V1,
+        )?;
+        check_close_rent_recipient(
+            &account.metadata,
+            &ctx.accounts.protocol_config_pda,
+            ctx.accounts.recipient.key(),
+        )?;
+        process_close_batched_address_merkle_tree(&ctx, bump)
+    }
+
     pub fn migrate_state<'info>(
         ctx: Context<'_, '_, '_, 'info, MigrateState<'info>>,
         bump: u8,
@@ -850,3 +1034,20 @@ pub fn check_forester(
         err!(RegistryError::InvalidSigner)
     }
 }
+
+/// Program owned trees are initialized by their owner program, which signs
+/// with its cpi signer pda (seed `cpi_authority`) via cpi.
+/// A program owner which is not a program signs itself.
+pub fn check_program_owner_signer(program_owner: &Pubkey, authority: &Pubkey) -> Result<()> {
+    let cpi_signer = Pubkey::find_program_address(&[CPI_AUTHORITY_PDA_SEED], program_owner).0;
+    if *authority != *program_owner && *authority != cpi_signer {
+        msg!(
+            "Authority {:?} is neither program owner {:?} nor its cpi signer {:?}",
+            authority,
+            program_owner,
+            cpi_signer
+        );
+        return err!(RegistryError::InvalidSigner);
+    }
+    Ok(())
+}
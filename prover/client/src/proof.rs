@@ -36,6 +36,17 @@ impl From<ProofCompressed> for CompressedProof {
 }
 
 impl ProofCompressed {
+    /// Returned instead of a real proof by mock proof clients,
+    /// see [`CompressedProof::mock`] and [`crate::proof_client::ProofClient::mock`].
+    pub fn mock(public_inputs: &[[u8; 32]]) -> Result<Self, ProverClientError> {
+        let proof = CompressedProof::mock(public_inputs)?;
+        Ok(Self {
+            a: proof.a,
+            b: proof.b,
+            c: proof.c,
+        })
+    }
+
     pub fn try_decompress(&self) -> Result<Proof, ProverClientError> {
         let proof_a = alt_bn128_g1_decompress_be(&self.a)?;
         let proof_b = alt_bn128_g2_decompress_be(&self.b)?;
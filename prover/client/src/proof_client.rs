@@ -8,6 +8,7 @@ use tracing::{debug, error, info, trace, warn};
 use crate::{
     constants::{PROVE_PATH, SERVER_ADDRESS},
     errors::ProverClientError,
+    helpers::{bigint_to_u8_32, string_to_big_int},
     proof::{
         compress_proof, deserialize_gnark_proof_json, proof_from_json_struct, ProofCompressed,
         ProofResult,
@@ -64,6 +65,7 @@ pub struct ProofClient {
     max_wait_time: Duration,
     api_key: Option<String>,
     initial_poll_delay: Duration,
+    mock: bool,
 }
 
 impl ProofClient {
@@ -75,9 +77,25 @@ impl ProofClient {
             max_wait_time: Duration::from_secs(DEFAULT_MAX_WAIT_TIME_SECS),
             api_key: None,
             initial_poll_delay: Duration::from_millis(INITIAL_POLL_DELAY_SMALL_CIRCUIT_MS),
+            mock: false,
         }
     }
 
+    /// Client which returns [`ProofCompressed::mock`] proofs for the public
+    /// input hash of every request without contacting a prover server.
+    /// Test only, mock proofs are accepted only by programs built with the
+    /// `mock-proof` feature (`just programs build-mock-prover`).
+    pub fn mock() -> Self {
+        Self {
+            mock: true,
+            ..Self::local()
+        }
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.mock
+    }
+
     #[allow(unused)]
     pub fn with_config(
         server_address: String,
@@ -98,6 +116,7 @@ impl ProofClient {
             max_wait_time,
             api_key,
             initial_poll_delay,
+            mock: false,
         }
     }
 
@@ -116,6 +135,7 @@ impl ProofClient {
             max_wait_time,
             api_key,
             initial_poll_delay,
+            mock: false,
         }
     }
 
@@ -169,6 +189,9 @@ impl ProofClient {
         &self,
         inputs_json: String,
     ) -> Result<ProofResult, ProverClientError> {
+        if self.mock {
+            return Self::generate_mock_proof(&inputs_json);
+        }
         let start_time = Instant::now();
         let mut retries = 0;
 
@@ -212,6 +235,29 @@ impl ProofClient {
         }
     }
 
+    /// Mock proofs are bound to the `publicInputHash` of the request,
+    /// which is the single public input of every batched circuit.
+    fn generate_mock_proof(inputs_json: &str) -> Result<ProofResult, ProverClientError> {
+        let inputs: serde_json::Value = serde_json::from_str(inputs_json).map_err(|e| {
+            ProverClientError::InvalidProofData(format!("Failed to parse proof inputs: {}", e))
+        })?;
+        let public_input_hash = inputs
+            .get("publicInputHash")
+            .and_then(|value| value.as_str())
+            .and_then(string_to_big_int)
+            .ok_or_else(|| {
+                ProverClientError::InvalidProofData(
+                    "Mock proofs require a publicInputHash in the proof inputs".to_string(),
+                )
+            })?;
+        let public_input_hash = bigint_to_u8_32(&public_input_hash)
+            .map_err(|e| ProverClientError::InvalidProofData(e.to_string()))?;
+        Ok(ProofResult {
+            proof: ProofCompressed::mock(&[public_input_hash])?,
+            proof_duration_ms: 0,
+        })
+    }
+
     async fn try_generate_proof(
         &self,
         inputs_json: &str,
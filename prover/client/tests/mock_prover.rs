@@ -0,0 +1,32 @@
+use light_compressed_account::instruction_data::compressed_proof::CompressedProof;
+use light_prover_client::proof_client::ProofClient;
+
+#[tokio::test]
+async fn mock_prover_returns_mock_proof_without_server() {
+    assert!(!ProofClient::local().is_mock());
+    assert!(ProofClient::mock().is_mock());
+
+    // No prover server is running, the request must not leave the process.
+    let proof_client = ProofClient::mock();
+    let result = proof_client
+        .generate_proof(r#"{"publicInputHash":"0x1"}"#.to_string())
+        .await
+        .unwrap();
+    assert_eq!(result.proof_duration_ms, 0);
+    let mut public_input_hash = [0u8; 32];
+    public_input_hash[31] = 1;
+    let proof = CompressedProof::from(result.proof);
+    assert!(proof.is_mock());
+    assert_eq!(proof, CompressedProof::mock(&[public_input_hash]).unwrap());
+    // The mock proof is bound to the public input hash.
+    let result = proof_client
+        .generate_proof(r#"{"publicInputHash":"0x2"}"#.to_string())
+        .await
+        .unwrap();
+    assert_ne!(CompressedProof::from(result.proof), proof);
+    // Legacy circuits have no public input hash.
+    assert!(proof_client
+        .generate_proof(r#"{"circuitType":"inclusion"}"#.to_string())
+        .await
+        .is_err());
+}
@@ -0,0 +1,825 @@
+//! Caching decorator for [`Indexer`] implementations.
+//!
+//! [`CachedIndexer`] memoizes responses of read-only account and balance
+//! queries, keyed by method, parameters and the minimum slot requested
+//! through [`IndexerRpcConfig`]. Entries expire after a time to live and the
+//! oldest entries are evicted once the cache is full.
+//!
+//! Entries are tagged with the hashes, owners and mints of the accounts they
+//! depend on. Account events observed through the subscriptions of the cached
+//! indexer, or reported with [`CachedIndexer::invalidate_account`], remove
+//! all entries with a matching tag. Proofs, queue data and signatures are
+//! never cached.
+//!
+//! The cache does not observe transactions by itself. Callers which send a
+//! transaction must invalidate the accounts it spends or creates with
+//! [`CachedIndexer::invalidate_account`],
+//! [`CachedIndexer::invalidate_token_account`] or [`CachedIndexer::clear`],
+//! otherwise stale responses are served until they expire. Responses of
+//! requests in flight during an invalidation are not cached.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_pubkey::Pubkey;
+
+use super::{
+    Address, AddressWithTree, CompressedAccount, CompressedAccountEvent, CompressedTokenAccount,
+    GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash,
+    Indexer, IndexerError, IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
+    NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
+    QueueElementsV2Options, QueueInfoResult, Response, RetryConfig, SignatureWithMetadata,
+    TokenAccountEvent, TokenBalance, ValidityProofWithContext,
+};
+
+#[derive(Debug, Clone)]
+pub struct CachedIndexerConfig {
+    /// Maximum number of cached responses.
+    pub max_entries: usize,
+    /// Time after which a cached response expires.
+    pub ttl: Duration,
+}
+
+impl Default for CachedIndexerConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1024,
+            ttl: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    method: &'static str,
+    params: String,
+    min_slot: u64,
+}
+
+impl CacheKey {
+    fn new(
+        method: &'static str,
+        params: impl fmt::Debug,
+        config: &Option<IndexerRpcConfig>,
+    ) -> Self {
+        Self {
+            method,
+            params: format!("{:?}", params),
+            min_slot: config
+                .as_ref()
+                .map(|config| config.slot)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// State a cached response depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Hash([u8; 32]),
+    /// Owner or delegate of accounts.
+    Owner(Pubkey),
+    Mint(Pubkey),
+}
+
+struct Entry {
+    value: Box<dyn Any + Send>,
+    tags: Vec<Tag>,
+    inserted_at: Instant,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<CacheKey, Entry>,
+    /// Incremented by every invalidation. Responses are only inserted if
+    /// no invalidation happened while they were fetched.
+    generation: u64,
+}
+
+impl Cache {
+    fn get<T: Clone + 'static>(&mut self, key: &CacheKey, ttl: Duration) -> Option<T> {
+        let entry = self.entries.get(key)?;
+        if entry.inserted_at.elapsed() >= ttl {
+            self.entries.remove(key);
+            return None;
+        }
+        entry.value.downcast_ref::<T>().cloned()
+    }
+
+    fn insert<T: Send + 'static>(
+        &mut self,
+        key: CacheKey,
+        value: T,
+        tags: Vec<Tag>,
+        config: &CachedIndexerConfig,
+    ) {
+        if config.max_entries == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= config.max_entries {
+            self.entries
+                .retain(|_, entry| entry.inserted_at.elapsed() < config.ttl);
+            while self.entries.len() >= config.max_entries {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.inserted_at)
+                    .map(|(key, _)| key.clone());
+                match oldest {
+                    Some(oldest) => self.entries.remove(&oldest),
+                    None => break,
+                };
+            }
+        }
+        self.entries.insert(
+            key,
+            Entry {
+                value: Box::new(value),
+                tags,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+
+    fn invalidate(&mut self, tags: &[Tag]) {
+        self.generation += 1;
+        self.entries
+            .retain(|_, entry| !entry.tags.iter().any(|tag| tags.contains(tag)));
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+}
+
+fn account_tags(account: &CompressedAccount) -> Vec<Tag> {
+    vec![Tag::Hash(account.hash), Tag::Owner(account.owner)]
+}
+
+fn token_account_tags(account: &CompressedTokenAccount) -> Vec<Tag> {
+    let mut tags = vec![
+        Tag::Hash(account.account.hash),
+        Tag::Owner(account.token.owner),
+        Tag::Mint(account.token.mint),
+    ];
+    if let Some(delegate) = account.token.delegate {
+        tags.push(Tag::Owner(delegate));
+    }
+    tags
+}
+
+/// Indexer which caches read-only responses of the wrapped indexer.
+pub struct CachedIndexer<I: Indexer> {
+    inner: I,
+    config: CachedIndexerConfig,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl<I: Indexer + fmt::Debug> fmt::Debug for CachedIndexer<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedIndexer")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .field("entries", &self.cache.lock().unwrap().entries.len())
+            .finish()
+    }
+}
+
+impl<I: Indexer> CachedIndexer<I> {
+    pub fn new(inner: I, config: CachedIndexerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            cache: Arc::new(Mutex::new(Cache::default())),
+        }
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    /// Removes all cached responses.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Removes cached responses which depend on the account, e.g. after
+    /// sending a transaction which nullifies it.
+    pub fn invalidate_account(&self, account: &CompressedAccount) {
+        self.cache
+            .lock()
+            .unwrap()
+            .invalidate(&account_tags(account));
+    }
+
+    /// Removes cached responses which depend on the token account.
+    pub fn invalidate_token_account(&self, account: &CompressedTokenAccount) {
+        self.cache
+            .lock()
+            .unwrap()
+            .invalidate(&token_account_tags(account));
+    }
+
+    /// Returns the cached response or fetches and caches it. `tags` are
+    /// the tags derived from the request parameters, tags of the returned
+    /// accounts are added with `value_tags`.
+    async fn cached<T, Fut>(
+        &self,
+        key: CacheKey,
+        mut tags: Vec<Tag>,
+        value_tags: impl FnOnce(&T) -> Vec<Tag>,
+        fetch: Fut,
+    ) -> Result<Response<T>, IndexerError>
+    where
+        T: Clone + PartialEq + Default + fmt::Debug + Send + 'static,
+        Fut: Future<Output = Result<Response<T>, IndexerError>>,
+    {
+        let generation = {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(response) = cache.get::<Response<T>>(&key, self.config.ttl) {
+                return Ok(response);
+            }
+            cache.generation
+        };
+        let response = fetch.await?;
+        tags.extend(value_tags(&response.value));
+        let mut cache = self.cache.lock().unwrap();
+        // The response may predate an invalidation during the fetch.
+        if cache.generation == generation {
+            cache.insert(key, response.clone(), tags, &self.config);
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl<I: Indexer> Indexer for CachedIndexer<I> {
+    async fn get_compressed_account(
+        &self,
+        address: Address,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        self.cached(
+            CacheKey::new("get_compressed_account", address, &config),
+            vec![],
+            |value: &Option<CompressedAccount>| value.iter().flat_map(account_tags).collect(),
+            self.inner.get_compressed_account(address, config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_account_by_hash(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        self.cached(
+            CacheKey::new("get_compressed_account_by_hash", hash, &config),
+            vec![Tag::Hash(hash)],
+            |value: &Option<CompressedAccount>| value.iter().flat_map(account_tags).collect(),
+            self.inner
+                .get_compressed_account_by_hash(hash, config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedAccountsByOwnerConfig>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedAccount>>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_accounts_by_owner",
+                (owner, &options),
+                &config,
+            ),
+            vec![Tag::Owner(*owner)],
+            |value: &ItemsWithCursor<CompressedAccount>| {
+                value.items.iter().flat_map(account_tags).collect()
+            },
+            self.inner
+                .get_compressed_accounts_by_owner(owner, options.clone(), config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.cached(
+            CacheKey::new("get_compressed_balance", (address, hash), &config),
+            hash.map(Tag::Hash).into_iter().collect(),
+            |_: &u64| vec![],
+            self.inner
+                .get_compressed_balance(address, hash, config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_balance_by_owner(
+        &self,
+        owner: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.cached(
+            CacheKey::new("get_compressed_balance_by_owner", owner, &config),
+            vec![Tag::Owner(*owner)],
+            |_: &u64| vec![],
+            self.inner
+                .get_compressed_balance_by_owner(owner, config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_mint_token_holders(
+        &self,
+        mint: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_mint_token_holders",
+                (mint, &options),
+                &config,
+            ),
+            vec![Tag::Mint(*mint)],
+            |_: &ItemsWithCursor<OwnerBalance>| vec![],
+            self.inner
+                .get_compressed_mint_token_holders(mint, options.clone(), config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_token_account_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_token_account_balance",
+                (address, hash),
+                &config,
+            ),
+            hash.map(Tag::Hash).into_iter().collect(),
+            |_: &u64| vec![],
+            self.inner
+                .get_compressed_token_account_balance(address, hash, config.clone()),
+        )
+        .await
+    }
+
+    async fn get_compressed_token_accounts_by_delegate(
+        &self,
+        delegate: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_token_accounts_by_delegate",
+                (delegate, &options),
+                &config,
+            ),
+            vec![Tag::Owner(*delegate)],
+            |value: &ItemsWithCursor<CompressedTokenAccount>| {
+                value.items.iter().flat_map(token_account_tags).collect()
+            },
+            self.inner.get_compressed_token_accounts_by_delegate(
+                delegate,
+                options.clone(),
+                config.clone(),
+            ),
+        )
+        .await
+    }
+
+    async fn get_compressed_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_token_accounts_by_owner",
+                (owner, &options),
+                &config,
+            ),
+            vec![Tag::Owner(*owner)],
+            |value: &ItemsWithCursor<CompressedTokenAccount>| {
+                value.items.iter().flat_map(token_account_tags).collect()
+            },
+            self.inner.get_compressed_token_accounts_by_owner(
+                owner,
+                options.clone(),
+                config.clone(),
+            ),
+        )
+        .await
+    }
+
+    async fn get_compressed_token_balances_by_owner_v2(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<TokenBalance>>, IndexerError> {
+        self.cached(
+            CacheKey::new(
+                "get_compressed_token_balances_by_owner_v2",
+                (owner, &options),
+                &config,
+            ),
+            vec![Tag::Owner(*owner)],
+            |_: &ItemsWithCursor<TokenBalance>| vec![],
+            self.inner.get_compressed_token_balances_by_owner_v2(
+                owner,
+                options.clone(),
+                config.clone(),
+            ),
+        )
+        .await
+    }
+
+    async fn get_compression_signatures_for_account(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
+        self.inner
+            .get_compression_signatures_for_account(hash, config)
+            .await
+    }
+
+    async fn get_compression_signatures_for_address(
+        &self,
+        address: &[u8; 32],
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.inner
+            .get_compression_signatures_for_address(address, options, config)
+            .await
+    }
+
+    async fn get_compression_signatures_for_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.inner
+            .get_compression_signatures_for_owner(owner, options, config)
+            .await
+    }
+
+    async fn get_compression_signatures_for_token_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.inner
+            .get_compression_signatures_for_token_owner(owner, options, config)
+            .await
+    }
+
+    async fn get_indexer_health(&self, config: Option<RetryConfig>) -> Result<bool, IndexerError> {
+        self.inner.get_indexer_health(config).await
+    }
+
+    async fn get_indexer_slot(&self, config: Option<RetryConfig>) -> Result<u64, IndexerError> {
+        self.inner.get_indexer_slot(config).await
+    }
+
+    async fn get_multiple_compressed_account_proofs(
+        &self,
+        hashes: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<MerkleProof>>, IndexerError> {
+        self.inner
+            .get_multiple_compressed_account_proofs(hashes, config)
+            .await
+    }
+
+    async fn get_multiple_compressed_accounts(
+        &self,
+        addresses: Option<Vec<Address>>,
+        hashes: Option<Vec<Hash>>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<Option<CompressedAccount>>>, IndexerError> {
+        let key = CacheKey::new(
+            "get_multiple_compressed_accounts",
+            (&addresses, &hashes),
+            &config,
+        );
+        let tags = hashes.iter().flatten().copied().map(Tag::Hash).collect();
+        self.cached(
+            key,
+            tags,
+            |value: &Items<Option<CompressedAccount>>| {
+                value
+                    .items
+                    .iter()
+                    .flatten()
+                    .flat_map(account_tags)
+                    .collect()
+            },
+            self.inner
+                .get_multiple_compressed_accounts(addresses, hashes, config),
+        )
+        .await
+    }
+
+    async fn get_multiple_new_address_proofs(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        addresses: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<NewAddressProofWithContext>>, IndexerError> {
+        self.inner
+            .get_multiple_new_address_proofs(merkle_tree_pubkey, addresses, config)
+            .await
+    }
+
+    async fn get_validity_proof(
+        &self,
+        hashes: Vec<Hash>,
+        new_addresses_with_trees: Vec<AddressWithTree>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ValidityProofWithContext>, IndexerError> {
+        self.inner
+            .get_validity_proof(hashes, new_addresses_with_trees, config)
+            .await
+    }
+
+    async fn get_queue_elements(
+        &mut self,
+        merkle_tree_pubkey: [u8; 32],
+        options: QueueElementsV2Options,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueElementsResult>, IndexerError> {
+        self.inner
+            .get_queue_elements(merkle_tree_pubkey, options, config)
+            .await
+    }
+
+    async fn get_queue_info(
+        &self,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueInfoResult>, IndexerError> {
+        self.inner.get_queue_info(config).await
+    }
+
+    async fn get_subtrees(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<[u8; 32]>>, IndexerError> {
+        self.inner.get_subtrees(merkle_tree_pubkey, config).await
+    }
+
+    /// Subscribes through the wrapped indexer. Cached responses which depend
+    /// on created or nullified accounts are invalidated as events arrive.
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        let stream = self
+            .inner
+            .subscribe_compressed_accounts_by_owner(owner)
+            .await?;
+        let cache = self.cache.clone();
+        Ok(stream
+            .inspect(move |event| {
+                if let Ok(event) = event {
+                    cache
+                        .lock()
+                        .unwrap()
+                        .invalidate(&account_tags(event.account()));
+                }
+            })
+            .boxed())
+    }
+
+    /// Subscribes through the wrapped indexer. Cached responses which depend
+    /// on created or nullified token accounts are invalidated as events
+    /// arrive.
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        let stream = self
+            .inner
+            .subscribe_token_accounts_by_owner(owner, mint)
+            .await?;
+        let cache = self.cache.clone();
+        Ok(stream
+            .inspect(move |event| {
+                if let Ok(event) = event {
+                    cache
+                        .lock()
+                        .unwrap()
+                        .invalidate(&token_account_tags(event.account()));
+                }
+            })
+            .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::{
+        mock_indexer::{Hold, MockIndexer},
+        Context,
+    };
+
+    fn key(method: &'static str, slot: u64) -> CacheKey {
+        CacheKey::new(method, (), &Some(IndexerRpcConfig::new(slot)))
+    }
+
+    fn response(value: u64) -> Response<u64> {
+        Response {
+            context: Context { slot: 1 },
+            value,
+        }
+    }
+
+    #[test]
+    fn test_cache_key_includes_min_slot() {
+        let config = CachedIndexerConfig::default();
+        let mut cache = Cache::default();
+        cache.insert(key("balance", 1), response(5), vec![], &config);
+
+        assert_eq!(
+            cache.get::<Response<u64>>(&key("balance", 1), config.ttl),
+            Some(response(5))
+        );
+        assert_eq!(
+            cache.get::<Response<u64>>(&key("balance", 2), config.ttl),
+            None
+        );
+        // A value of a different type under the same key is a miss.
+        assert_eq!(cache.get::<u64>(&key("balance", 1), config.ttl), None);
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let config = CachedIndexerConfig {
+            max_entries: 8,
+            ttl: Duration::ZERO,
+        };
+        let mut cache = Cache::default();
+        cache.insert(key("balance", 0), response(5), vec![], &config);
+        assert_eq!(
+            cache.get::<Response<u64>>(&key("balance", 0), config.ttl),
+            None
+        );
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let config = CachedIndexerConfig {
+            max_entries: 2,
+            ttl: Duration::from_secs(60),
+        };
+        let mut cache = Cache::default();
+        for slot in 0..3 {
+            cache.insert(key("balance", slot), response(slot), vec![], &config);
+        }
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(
+            cache.get::<Response<u64>>(&key("balance", 0), config.ttl),
+            None
+        );
+        assert_eq!(
+            cache.get::<Response<u64>>(&key("balance", 2), config.ttl),
+            Some(response(2))
+        );
+    }
+
+    #[test]
+    fn test_cache_invalidate_by_tag() {
+        let config = CachedIndexerConfig::default();
+        let owner = Pubkey::new_unique();
+        let mut cache = Cache::default();
+        cache.insert(
+            key("by_owner", 0),
+            response(1),
+            vec![Tag::Owner(owner)],
+            &config,
+        );
+        cache.insert(
+            key("by_hash", 0),
+            response(2),
+            vec![Tag::Hash([1u8; 32])],
+            &config,
+        );
+
+        cache.invalidate(&[Tag::Hash([2u8; 32]), Tag::Owner(Pubkey::new_unique())]);
+        assert_eq!(cache.entries.len(), 2);
+
+        cache.invalidate(&[Tag::Hash([1u8; 32])]);
+        assert!(cache.entries.contains_key(&key("by_owner", 0)));
+        assert!(!cache.entries.contains_key(&key("by_hash", 0)));
+    }
+
+    fn account(owner: Pubkey, hash: u8) -> CompressedAccount {
+        CompressedAccount {
+            hash: [hash; 32],
+            owner,
+            lamports: 100,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_indexer_invalidate_account() {
+        let owner = Pubkey::new_unique();
+        let mock = MockIndexer::new(1);
+        mock.accounts.lock().unwrap().push(account(owner, 1));
+        let indexer = CachedIndexer::new(mock.clone(), CachedIndexerConfig::default());
+
+        for _ in 0..2 {
+            let response = indexer
+                .get_compressed_accounts_by_owner(&owner, None, None)
+                .await
+                .unwrap();
+            assert_eq!(response.value.items, vec![account(owner, 1)]);
+        }
+        assert_eq!(mock.requests(), 1);
+
+        // Not observed by the cache until it is invalidated.
+        let spent = mock.accounts.lock().unwrap().remove(0);
+        let response = indexer
+            .get_compressed_accounts_by_owner(&owner, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.value.items.len(), 1);
+        assert_eq!(mock.requests(), 1);
+
+        indexer.invalidate_account(&spent);
+        let response = indexer
+            .get_compressed_accounts_by_owner(&owner, None, None)
+            .await
+            .unwrap();
+        assert!(response.value.items.is_empty());
+        assert_eq!(mock.requests(), 2);
+    }
+
+    /// A response fetched before an invalidation must not be cached after it.
+    #[tokio::test]
+    async fn test_cached_indexer_invalidation_during_fetch() {
+        let owner = Pubkey::new_unique();
+        let mock = MockIndexer::new(1);
+        mock.accounts.lock().unwrap().push(account(owner, 1));
+        let indexer = CachedIndexer::new(mock.clone(), CachedIndexerConfig::default());
+
+        let Hold { entered, release } = mock.hold_next_request();
+        let fetch = indexer.get_compressed_accounts_by_owner(&owner, None, None);
+        let invalidate = async {
+            entered.await.unwrap();
+            let created = account(owner, 2);
+            mock.accounts.lock().unwrap().push(created.clone());
+            indexer.invalidate_account(&created);
+            release.send(()).unwrap();
+        };
+        let (stale, ()) = futures::join!(fetch, invalidate);
+        assert_eq!(stale.unwrap().value.items.len(), 1);
+
+        let response = indexer
+            .get_compressed_accounts_by_owner(&owner, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.value.items.len(), 2);
+        assert_eq!(mock.requests(), 2);
+    }
+}
@@ -0,0 +1,616 @@
+//! Consistency checks between an [`Indexer`] and the on-chain Merkle tree
+//! accounts it indexes.
+//!
+//! [`check_tree_consistency`] reads root, root history, next index, sequence
+//! number and queue state of a tree from RPC, rebuilds the indexer's view of
+//! the tree from [`Indexer::get_subtrees`] and [`Indexer::get_queue_elements`]
+//! and reports every divergence. [`TreeConsistencyReport::diagnosis`]
+//! distinguishes an indexer that is behind the chain from one that serves
+//! state the chain never had.
+
+use std::{fmt, mem};
+
+use light_batched_merkle_tree::{
+    batch::BatchState, errors::BatchedMerkleTreeError, merkle_tree::BatchedMerkleTreeAccount,
+    queue::BatchedQueueAccount, queue_batch_metadata::QueueBatches,
+};
+use light_compressed_account::TreeType;
+use light_concurrent_merkle_tree::{
+    copy::ConcurrentMerkleTreeCopy, errors::ConcurrentMerkleTreeError,
+};
+use light_hasher::{zero_bytes::MAX_HEIGHT, Hasher, HasherError, Poseidon};
+use light_indexed_merkle_tree::{copy::IndexedMerkleTreeCopy, errors::IndexedMerkleTreeError};
+use light_merkle_tree_metadata::merkle_tree::MerkleTreeMetadata;
+use solana_pubkey::Pubkey;
+use thiserror::Error;
+
+use super::{Indexer, IndexerError, QueueElementsV2Options};
+use crate::rpc::{Rpc, RpcError};
+
+// BatchedMerkleTreeAccount: b"BatchMta"
+const BATCHED_TREE_DISCRIMINATOR: [u8; 8] = *b"BatchMta";
+// StateMerkleTreeAccount: sha256("account:StateMerkleTreeAccount")[0..8]
+const STATE_V1_DISCRIMINATOR: [u8; 8] = [172, 43, 172, 186, 29, 73, 219, 84];
+// AddressMerkleTreeAccount: sha256("account:AddressMerkleTreeAccount")[0..8]
+const ADDRESS_V1_DISCRIMINATOR: [u8; 8] = [11, 161, 175, 9, 212, 229, 73, 73];
+
+#[derive(Error, Debug)]
+pub enum ConsistencyCheckError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+
+    #[error(transparent)]
+    Indexer(#[from] IndexerError),
+
+    #[error(transparent)]
+    ConcurrentMerkleTree(#[from] ConcurrentMerkleTreeError),
+
+    #[error(transparent)]
+    IndexedMerkleTree(#[from] IndexedMerkleTreeError),
+
+    #[error(transparent)]
+    BatchedMerkleTree(#[from] BatchedMerkleTreeError),
+
+    #[error(transparent)]
+    Hasher(#[from] HasherError),
+
+    #[error("Account {0} is not a Merkle tree account")]
+    NotAMerkleTree(Pubkey),
+}
+
+/// Queue of a Merkle tree whose elements are not yet inserted into the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueKind {
+    /// Output queue of a v2 state tree (leaves to append).
+    Output,
+    /// Input queue of a v2 state tree (nullifiers).
+    Input,
+    /// Queue of a v2 address tree.
+    Address,
+}
+
+impl fmt::Display for QueueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueKind::Output => write!(f, "output queue"),
+            QueueKind::Input => write!(f, "input queue"),
+            QueueKind::Address => write!(f, "address queue"),
+        }
+    }
+}
+
+/// Number of elements in a queue which are not yet inserted into the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingElements {
+    pub queue: QueueKind,
+    pub num_elements: u64,
+}
+
+/// Tree state as stored in the on-chain accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnchainTreeState {
+    pub slot: u64,
+    pub tree_type: TreeType,
+    pub root: [u8; 32],
+    /// Root history in on-chain buffer order, the position of a root is
+    /// the root index proofs against it must reference.
+    pub root_history: Vec<[u8; 32]>,
+    pub next_index: u64,
+    pub sequence_number: u64,
+    /// Pending queue elements, empty for v1 trees.
+    pub pending: Vec<PendingElements>,
+}
+
+/// Tree state as served by the indexer.
+///
+/// Fields are `None` if the indexer does not implement the method they are
+/// read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexerTreeState {
+    pub slot: u64,
+    /// Root the indexer generates proofs against (v2 trees).
+    pub root: Option<[u8; 32]>,
+    pub next_index: Option<u64>,
+    pub sequence_number: Option<u64>,
+    /// Root recomputed from the indexer's subtrees.
+    pub rebuilt_root: Option<[u8; 32]>,
+    pub pending: Vec<PendingElements>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The indexer root is not the current on-chain root.
+    Root {
+        onchain: [u8; 32],
+        indexer: [u8; 32],
+        /// The indexer root is an older root of the on-chain root history.
+        in_root_history: bool,
+    },
+    /// The root reported by the indexer does not match the root rebuilt
+    /// from the indexer's own subtrees.
+    RebuiltRoot {
+        reported: [u8; 32],
+        rebuilt: [u8; 32],
+    },
+    NextIndex {
+        onchain: u64,
+        indexer: u64,
+    },
+    SequenceNumber {
+        onchain: u64,
+        indexer: u64,
+    },
+    PendingElements {
+        queue: QueueKind,
+        onchain: u64,
+        indexer: u64,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::Root {
+                onchain,
+                indexer,
+                in_root_history,
+            } => write!(
+                f,
+                "root mismatch: on-chain {}, indexer {} ({})",
+                bs58::encode(onchain).into_string(),
+                bs58::encode(indexer).into_string(),
+                if *in_root_history {
+                    "found in on-chain root history"
+                } else {
+                    "not in on-chain root history"
+                }
+            ),
+            Divergence::RebuiltRoot { reported, rebuilt } => write!(
+                f,
+                "indexer root {} does not match root {} rebuilt from its subtrees",
+                bs58::encode(reported).into_string(),
+                bs58::encode(rebuilt).into_string(),
+            ),
+            Divergence::NextIndex { onchain, indexer } => {
+                write!(
+                    f,
+                    "next index mismatch: on-chain {onchain}, indexer {indexer}"
+                )
+            }
+            Divergence::SequenceNumber { onchain, indexer } => write!(
+                f,
+                "sequence number mismatch: on-chain {onchain}, indexer {indexer}"
+            ),
+            Divergence::PendingElements {
+                queue,
+                onchain,
+                indexer,
+            } => write!(
+                f,
+                "{queue} pending elements mismatch: on-chain {onchain}, indexer {indexer}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnosis {
+    Consistent,
+    /// The indexer serves an older state of the tree.
+    Lagging,
+    /// The indexer serves state that was never on chain.
+    Corrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeConsistencyReport {
+    pub merkle_tree: Pubkey,
+    pub onchain: OnchainTreeState,
+    pub indexer: IndexerTreeState,
+    pub divergences: Vec<Divergence>,
+}
+
+impl TreeConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    pub fn diagnosis(&self) -> Diagnosis {
+        if self.divergences.is_empty() {
+            return Diagnosis::Consistent;
+        }
+        let corrupted = self.divergences.iter().any(|divergence| match divergence {
+            Divergence::Root {
+                in_root_history, ..
+            } => !in_root_history,
+            Divergence::RebuiltRoot { .. } => true,
+            Divergence::NextIndex { onchain, indexer }
+            | Divergence::SequenceNumber { onchain, indexer } => indexer > onchain,
+            Divergence::PendingElements { .. } => false,
+        });
+        if corrupted {
+            Diagnosis::Corrupted
+        } else {
+            Diagnosis::Lagging
+        }
+    }
+}
+
+/// Compares the indexer's view of `merkle_tree` with the on-chain accounts.
+///
+/// Supports v1 and v2 state and address trees. Queue contents are only
+/// compared for v2 trees, v1 queues are not exposed by the indexer.
+pub async fn check_tree_consistency<R: Rpc, I: Indexer>(
+    rpc: &R,
+    indexer: &mut I,
+    merkle_tree: Pubkey,
+) -> Result<TreeConsistencyReport, ConsistencyCheckError> {
+    let onchain = fetch_onchain_tree_state(rpc, merkle_tree).await?;
+    let indexer_state = fetch_indexer_tree_state(indexer, merkle_tree, &onchain).await?;
+    let divergences = find_divergences(&onchain, &indexer_state);
+    Ok(TreeConsistencyReport {
+        merkle_tree,
+        onchain,
+        indexer: indexer_state,
+        divergences,
+    })
+}
+
+/// Reads the state of a v1 or v2 Merkle tree and its queues from RPC.
+pub async fn fetch_onchain_tree_state<R: Rpc>(
+    rpc: &R,
+    merkle_tree: Pubkey,
+) -> Result<OnchainTreeState, ConsistencyCheckError> {
+    let slot = rpc.get_slot().await?;
+    let mut account = rpc
+        .get_account(merkle_tree)
+        .await?
+        .ok_or_else(|| RpcError::AccountDoesNotExist(merkle_tree.to_string()))?;
+    if account.data.len() < 8 {
+        return Err(ConsistencyCheckError::NotAMerkleTree(merkle_tree));
+    }
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&account.data[..8]);
+    let v1_tree_offset = 8 + mem::size_of::<MerkleTreeMetadata>();
+
+    match discriminator {
+        BATCHED_TREE_DISCRIMINATOR => {
+            let light_pubkey =
+                light_compressed_account::pubkey::Pubkey::new_from_array(merkle_tree.to_bytes());
+            // The tree type is the first field of the batched tree metadata.
+            let tree_type = account
+                .data
+                .get(8..16)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(|bytes| TreeType::from(u64::from_le_bytes(bytes)))
+                .ok_or(ConsistencyCheckError::NotAMerkleTree(merkle_tree))?;
+            let tree = if tree_type == TreeType::StateV2 {
+                BatchedMerkleTreeAccount::state_from_bytes(&mut account.data, &light_pubkey)?
+            } else {
+                BatchedMerkleTreeAccount::address_from_bytes(&mut account.data, &light_pubkey)?
+            };
+            let root_history = tree.root_history.as_slice().to_vec();
+            let root = tree
+                .get_root()
+                .ok_or(ConsistencyCheckError::NotAMerkleTree(merkle_tree))?;
+
+            let mut pending = Vec::new();
+            if tree_type == TreeType::StateV2 {
+                let output_queue =
+                    Pubkey::new_from_array(tree.metadata.associated_queue.to_bytes());
+                let mut queue_account = rpc
+                    .get_account(output_queue)
+                    .await?
+                    .ok_or_else(|| RpcError::AccountDoesNotExist(output_queue.to_string()))?;
+                let queue = BatchedQueueAccount::output_from_bytes(&mut queue_account.data)?;
+                pending.push(PendingElements {
+                    queue: QueueKind::Output,
+                    num_elements: num_pending_elements(&queue.batch_metadata),
+                });
+                pending.push(PendingElements {
+                    queue: QueueKind::Input,
+                    num_elements: num_pending_elements(&tree.queue_batches),
+                });
+            } else {
+                pending.push(PendingElements {
+                    queue: QueueKind::Address,
+                    num_elements: num_pending_elements(&tree.queue_batches),
+                });
+            }
+
+            Ok(OnchainTreeState {
+                slot,
+                tree_type,
+                root,
+                root_history,
+                next_index: tree.next_index,
+                sequence_number: tree.sequence_number,
+                pending,
+            })
+        }
+        STATE_V1_DISCRIMINATOR => {
+            let tree = ConcurrentMerkleTreeCopy::<Poseidon, 26>::from_bytes_copy(
+                &account.data[v1_tree_offset..],
+            )?;
+            Ok(OnchainTreeState {
+                slot,
+                tree_type: TreeType::StateV1,
+                root: tree.root(),
+                root_history: (0..tree.roots.len())
+                    .filter_map(|i| tree.roots.get(i).copied())
+                    .collect(),
+                next_index: tree.next_index() as u64,
+                sequence_number: tree.sequence_number() as u64,
+                pending: Vec::new(),
+            })
+        }
+        ADDRESS_V1_DISCRIMINATOR => {
+            let tree = IndexedMerkleTreeCopy::<Poseidon, usize, 26, 16>::from_bytes_copy(
+                &account.data[v1_tree_offset..],
+            )?;
+            Ok(OnchainTreeState {
+                slot,
+                tree_type: TreeType::AddressV1,
+                root: tree.root(),
+                root_history: (0..tree.roots.len())
+                    .filter_map(|i| tree.roots.get(i).copied())
+                    .collect(),
+                next_index: tree.next_index() as u64,
+                sequence_number: tree.sequence_number() as u64,
+                pending: Vec::new(),
+            })
+        }
+        _ => Err(ConsistencyCheckError::NotAMerkleTree(merkle_tree)),
+    }
+}
+
+/// Reads the indexer's view of `merkle_tree`. Queue limits are derived from
+/// the pending elements in `onchain` so that surplus elements show up.
+pub async fn fetch_indexer_tree_state<I: Indexer>(
+    indexer: &mut I,
+    merkle_tree: Pubkey,
+    onchain: &OnchainTreeState,
+) -> Result<IndexerTreeState, ConsistencyCheckError> {
+    let slot = indexer.get_indexer_slot(None).await?;
+    let subtrees = match indexer.get_subtrees(merkle_tree.to_bytes(), None).await {
+        Ok(response) => Some(response.value.items),
+        Err(IndexerError::NotImplemented(_)) => None,
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut state = IndexerTreeState {
+        slot,
+        root: None,
+        next_index: None,
+        sequence_number: None,
+        rebuilt_root: None,
+        pending: Vec::new(),
+    };
+
+    let queue_limit = |queue: QueueKind| -> Option<u16> {
+        let num_elements = onchain
+            .pending
+            .iter()
+            .find(|pending| pending.queue == queue)
+            .map(|pending| pending.num_elements)
+            .unwrap_or_default();
+        Some(num_elements.saturating_add(1).min(u16::MAX as u64) as u16)
+    };
+    let options = match onchain.tree_type {
+        TreeType::StateV2 => Some(
+            QueueElementsV2Options::new()
+                .with_output_queue(None, queue_limit(QueueKind::Output))
+                .with_input_queue(None, queue_limit(QueueKind::Input)),
+        ),
+        TreeType::AddressV2 => Some(
+            QueueElementsV2Options::new().with_address_queue(None, queue_limit(QueueKind::Address)),
+        ),
+        _ => None,
+    };
+
+    let mut subtrees = subtrees;
+    if let Some(options) = options {
+        match indexer
+            .get_queue_elements(merkle_tree.to_bytes(), options, None)
+            .await
+        {
+            Ok(response) => {
+                if let Some(state_queue) = response.value.state_queue {
+                    state.root = Some(state_queue.initial_root);
+                    state.sequence_number = Some(state_queue.root_seq);
+                    if let Some(output_queue) = state_queue.output_queue {
+                        state.next_index = Some(output_queue.next_index);
+                        state.pending.push(PendingElements {
+                            queue: QueueKind::Output,
+                            num_elements: output_queue.account_hashes.len() as u64,
+                        });
+                    }
+                    if let Some(input_queue) = state_queue.input_queue {
+                        state.pending.push(PendingElements {
+                            queue: QueueKind::Input,
+                            num_elements: input_queue.account_hashes.len() as u64,
+                        });
+                    }
+                }
+                if let Some(address_queue) = response.value.address_queue {
+                    state.root = Some(address_queue.initial_root);
+                    state.sequence_number = Some(address_queue.root_seq);
+                    state.next_index = Some(address_queue.tree_next_insertion_index);
+                    state.pending.push(PendingElements {
+                        queue: QueueKind::Address,
+                        num_elements: address_queue.addresses.len() as u64,
+                    });
+                    if subtrees.is_none() && !address_queue.subtrees.is_empty() {
+                        subtrees = Some(address_queue.subtrees);
+                    }
+                }
+            }
+            Err(IndexerError::NotImplemented(_)) => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    if let Some(subtrees) = subtrees {
+        let next_index = state.next_index.unwrap_or(onchain.next_index);
+        state.rebuilt_root = Some(root_from_subtrees(&subtrees, next_index)?);
+    }
+    Ok(state)
+}
+
+/// Lists every field in which `indexer` differs from `onchain`.
+pub fn find_divergences(onchain: &OnchainTreeState, indexer: &IndexerTreeState) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+
+    if let (Some(reported), Some(rebuilt)) = (indexer.root, indexer.rebuilt_root) {
+        if reported != rebuilt {
+            divergences.push(Divergence::RebuiltRoot { reported, rebuilt });
+        }
+    }
+    if let Some(indexer_root) = indexer.root.or(indexer.rebuilt_root) {
+        if indexer_root != onchain.root {
+            divergences.push(Divergence::Root {
+                onchain: onchain.root,
+                indexer: indexer_root,
+                in_root_history: onchain.root_history.contains(&indexer_root),
+            });
+        }
+    }
+    if let Some(next_index) = indexer.next_index {
+        if next_index != onchain.next_index {
+            divergences.push(Divergence::NextIndex {
+                onchain: onchain.next_index,
+                indexer: next_index,
+            });
+        }
+    }
+    if let Some(sequence_number) = indexer.sequence_number {
+        if sequence_number != onchain.sequence_number {
+            divergences.push(Divergence::SequenceNumber {
+                onchain: onchain.sequence_number,
+                indexer: sequence_number,
+            });
+        }
+    }
+    for pending in onchain.pending.iter() {
+        if let Some(indexer_pending) = indexer
+            .pending
+            .iter()
+            .find(|indexer_pending| indexer_pending.queue == pending.queue)
+        {
+            if indexer_pending.num_elements != pending.num_elements {
+                divergences.push(Divergence::PendingElements {
+                    queue: pending.queue,
+                    onchain: pending.num_elements,
+                    indexer: indexer_pending.num_elements,
+                });
+            }
+        }
+    }
+    divergences
+}
+
+/// Computes the root of an append-only Poseidon Merkle tree from its filled
+/// subtrees (the rightmost left node of each level) and its next index.
+pub fn root_from_subtrees(
+    subtrees: &[[u8; 32]],
+    next_index: u64,
+) -> Result<[u8; 32], ConsistencyCheckError> {
+    if subtrees.len() > MAX_HEIGHT {
+        return Err(HasherError::InvalidInputLength(MAX_HEIGHT, subtrees.len()).into());
+    }
+    let zero_bytes = Poseidon::zero_bytes();
+    let mut node = zero_bytes[0];
+    for (level, subtree) in subtrees.iter().enumerate() {
+        node = if (next_index >> level) & 1 == 1 {
+            Poseidon::hashv(&[subtree.as_slice(), node.as_slice()])?
+        } else {
+            Poseidon::hashv(&[node.as_slice(), zero_bytes[level].as_slice()])?
+        };
+    }
+    Ok(node)
+}
+
+/// Elements inserted into the queue but not yet into the tree.
+fn num_pending_elements(queue_batches: &QueueBatches) -> u64 {
+    queue_batches
+        .batches
+        .iter()
+        .filter(|batch| batch.get_state() != BatchState::Inserted)
+        .map(|batch| {
+            batch
+                .get_num_inserted_elements()
+                .saturating_sub(batch.get_num_elements_inserted_into_tree())
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_from_subtrees_matches_manual_tree() {
+        let zero_bytes = Poseidon::zero_bytes();
+        assert_eq!(
+            root_from_subtrees(&zero_bytes[..4], 0).unwrap(),
+            zero_bytes[4]
+        );
+
+        // Height 2 tree with leaves [1, 2, 3, 0].
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let left = Poseidon::hashv(&[&leaves[0], &leaves[1]]).unwrap();
+        let right = Poseidon::hashv(&[&leaves[2], &zero_bytes[0]]).unwrap();
+        let root = Poseidon::hashv(&[&left, &right]).unwrap();
+        // Level 0: leaf 2 is the rightmost left node, level 1: left is full.
+        let subtrees = [leaves[2], left];
+        assert_eq!(root_from_subtrees(&subtrees, 3).unwrap(), root);
+    }
+
+    #[test]
+    fn diagnosis_distinguishes_lag_from_corruption() {
+        let onchain = OnchainTreeState {
+            slot: 10,
+            tree_type: TreeType::StateV2,
+            root: [2u8; 32],
+            root_history: vec![[1u8; 32], [2u8; 32]],
+            next_index: 20,
+            sequence_number: 2,
+            pending: vec![PendingElements {
+                queue: QueueKind::Output,
+                num_elements: 0,
+            }],
+        };
+        let mut indexer = IndexerTreeState {
+            slot: 9,
+            root: Some([1u8; 32]),
+            next_index: Some(10),
+            sequence_number: Some(1),
+            rebuilt_root: None,
+            pending: vec![PendingElements {
+                queue: QueueKind::Output,
+                num_elements: 10,
+            }],
+        };
+        let report = |indexer: &IndexerTreeState| TreeConsistencyReport {
+            merkle_tree: Pubkey::new_unique(),
+            onchain: onchain.clone(),
+            indexer: indexer.clone(),
+            divergences: find_divergences(&onchain, indexer),
+        };
+        assert_eq!(report(&indexer).divergences.len(), 4);
+        assert_eq!(report(&indexer).diagnosis(), Diagnosis::Lagging);
+
+        indexer.root = Some([3u8; 32]);
+        assert_eq!(report(&indexer).diagnosis(), Diagnosis::Corrupted);
+
+        let consistent = IndexerTreeState {
+            slot: 10,
+            root: Some(onchain.root),
+            next_index: Some(onchain.next_index),
+            sequence_number: Some(onchain.sequence_number),
+            rebuilt_root: Some(onchain.root),
+            pending: onchain.pending.clone(),
+        };
+        assert_eq!(report(&consistent).diagnosis(), Diagnosis::Consistent);
+    }
+}
@@ -4,8 +4,9 @@ use solana_pubkey::Pubkey;
 use super::{
     response::{Items, ItemsWithCursor, Response},
     types::{
-        CompressedAccount, CompressedTokenAccount, OwnerBalance, QueueInfoResult,
-        SignatureWithMetadata, TokenBalance, ValidityProofWithContext,
+        CompressedAccount, CompressedAccountEvent, CompressedTokenAccount, IndexerStream,
+        OwnerBalance, QueueInfoResult, SignatureWithMetadata, TokenAccountEvent, TokenBalance,
+        ValidityProofWithContext,
     },
     Address, AddressWithTree, GetCompressedAccountsByOwnerConfig,
     GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash, IndexerError, IndexerRpcConfig,
@@ -193,4 +194,27 @@ pub trait Indexer: std::marker::Send + std::marker::Sync {
         merkle_tree_pubkey: [u8; 32],
         config: Option<IndexerRpcConfig>,
     ) -> Result<Response<Items<[u8; 32]>>, IndexerError>;
+
+    /// Streams creations and nullifications of the owner’s compressed
+    /// accounts, starting at the current slot.
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        _owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        Err(IndexerError::NotImplemented(
+            "subscribe_compressed_accounts_by_owner".to_string(),
+        ))
+    }
+
+    /// Streams creations and nullifications of the owner’s compressed token
+    /// accounts, optionally filtered by mint, starting at the current slot.
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        _owner: &Pubkey,
+        _mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        Err(IndexerError::NotImplemented(
+            "subscribe_token_accounts_by_owner".to_string(),
+        ))
+    }
 }
@@ -0,0 +1,1696 @@
+use std::{collections::HashMap, fmt, time::Duration};
+
+use async_trait::async_trait;
+use light_batched_merkle_tree::constants::{
+    DEFAULT_BATCH_ADDRESS_TREE_HEIGHT, DEFAULT_BATCH_ROOT_HISTORY_LEN,
+    DEFAULT_BATCH_STATE_TREE_HEIGHT,
+};
+use light_compressed_account::{
+    compressed_account::{CompressedAccountWithMerkleContext, MerkleContext},
+    hash_chain::create_hash_chain_from_slice,
+    instruction_data::compressed_proof::CompressedProof,
+    nullifier::create_nullifier,
+    TreeType,
+};
+use light_event::event::BatchPublicTransactionEvent;
+use light_hasher::{bigint::bigint_to_be_bytes_array, Poseidon};
+use light_merkle_tree_metadata::events::{
+    batch::BatchEvent,
+    concurrent::{IndexedMerkleTreeEvent, NullifierEvent},
+    MerkleTreeEvent,
+};
+use light_merkle_tree_reference::MerkleTree;
+use light_prover_client::{
+    constants::SERVER_ADDRESS,
+    helpers::{big_int_to_string, bigint_to_u8_32, string_to_big_int},
+    proof_client::ProofClient,
+    proof_type::ProofType,
+    proof_types::{
+        combined::{v1::CombinedJsonStruct as CombinedJsonStructLegacy, v2::CombinedJsonStruct},
+        inclusion::{
+            v1::{
+                BatchInclusionJsonStruct as BatchInclusionJsonStructLegacy,
+                InclusionProofInputs as InclusionProofInputsLegacy,
+            },
+            v2::{BatchInclusionJsonStruct, InclusionMerkleProofInputs, InclusionProofInputs},
+        },
+        non_inclusion::{
+            v1::{
+                BatchNonInclusionJsonStruct as BatchNonInclusionJsonStructLegacy,
+                NonInclusionProofInputs as NonInclusionProofInputsLegacy,
+            },
+            v2::{
+                BatchNonInclusionJsonStruct, NonInclusionMerkleProofInputs, NonInclusionProofInputs,
+            },
+        },
+    },
+};
+use light_sdk_types::constants::{
+    ACCOUNT_COMPRESSION_PROGRAM_ID, ADDRESS_MERKLE_TREE_HEIGHT, STATE_MERKLE_TREE_CANOPY_DEPTH,
+    STATE_MERKLE_TREE_HEIGHT, STATE_MERKLE_TREE_ROOTS,
+};
+use light_token::compat::TokenDataWithMerkleContext;
+use num_bigint::{BigInt, BigUint};
+use num_traits::FromBytes;
+use solana_commitment_config::CommitmentConfig;
+use solana_pubkey::Pubkey;
+use solana_rpc_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_rpc_client_api::config::{RpcBlockConfig, RpcTransactionConfig};
+use solana_signature::Signature;
+use solana_transaction_status_client_types::{
+    EncodedTransactionWithStatusMeta, TransactionDetails, UiTransactionEncoding,
+};
+use tracing::warn;
+
+use super::{
+    address_tree::AddressMerkleTreeBundle,
+    state_tree::{LeafIndexInfo, StateMerkleTreeBundle},
+    transaction::decode_transaction,
+};
+use crate::{
+    fee::FeeConfig,
+    indexer::{
+        consistency::fetch_onchain_tree_state, decode_token_data, AccountProofInputs, Address,
+        AddressMerkleTreeAccounts, AddressProofInputs, AddressWithTree, CompressedAccount,
+        CompressedAccountEvent, CompressedTokenAccount, Context,
+        GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions,
+        Hash, Indexer, IndexerError, IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor,
+        MerkleProof, NewAddressProofWithContext, OwnerBalance, PaginatedOptions,
+        QueueElementsResult, QueueElementsV2Options, QueueInfoResult, Response, RetryConfig,
+        RootIndex, SignatureWithMetadata, StateMerkleTreeAccounts, TokenAccountEvent, TokenBalance,
+        TreeInfo, ValidityProofWithContext,
+    },
+    rpc::{LightClient, LightClientConfig, Rpc, RpcError},
+};
+
+/// Maximum number of signatures returned by one `getSignaturesForAddress` request.
+const SIGNATURES_PAGE_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LocalIndexerConfig {
+    /// Solana RPC url.
+    pub url: String,
+    pub commitment_config: Option<CommitmentConfig>,
+    /// Url of the prover server used for validity proofs.
+    pub prover_url: String,
+    pub prover_api_key: Option<String>,
+    /// State and address trees to index.
+    pub trees: Vec<TreeInfo>,
+    /// Account whose transactions [`LocalIndexer::sync`] follows, the account
+    /// compression program if `None`. A single tree can follow its own
+    /// transactions instead of every transaction of the program.
+    pub sync_address: Option<Pubkey>,
+    /// Transaction of the sync address after which the first
+    /// [`LocalIndexer::sync`] starts, e.g. the last transaction before the
+    /// indexed trees were created. Syncs from the oldest transaction if `None`.
+    pub sync_after: Option<Signature>,
+}
+
+impl LocalIndexerConfig {
+    pub fn new(url: String, trees: Vec<TreeInfo>) -> Self {
+        Self {
+            url,
+            commitment_config: Some(CommitmentConfig::confirmed()),
+            prover_url: SERVER_ADDRESS.to_string(),
+            prover_api_key: None,
+            trees,
+            sync_address: None,
+            sync_after: None,
+        }
+    }
+}
+
+/// Indexer which derives compressed state from RPC transactions alone.
+///
+/// The indexer replays the Light events of every transaction of the
+/// configured trees into the same Merkle tree bundles the `TestIndexer` of
+/// `light-program-test` uses. Transactions are read with
+/// [`LocalIndexer::sync`], which follows `getSignaturesForAddress` of the
+/// account compression program, or with [`LocalIndexer::sync_slot_range`].
+/// Use one of the two, not both.
+///
+/// Trees must be indexed from their creation on, a tree whose history is
+/// incomplete diverges from the chain. Divergence is detected when a
+/// forester update produces a different root than the on-chain update.
+///
+/// Queries are answered from the local state, call `sync` before querying.
+/// Signature queries, queue elements and subscriptions are not supported.
+pub struct LocalIndexer {
+    rpc: LightClient,
+    prover: ProofClient,
+    pub state_merkle_trees: Vec<StateMerkleTreeBundle>,
+    pub address_merkle_trees: Vec<AddressMerkleTreeBundle>,
+    pub compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    pub nullified_compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    pub token_compressed_accounts: Vec<TokenDataWithMerkleContext>,
+    pub token_nullified_compressed_accounts: Vec<TokenDataWithMerkleContext>,
+    /// Account whose transactions [`LocalIndexer::sync`] follows.
+    sync_address: Pubkey,
+    /// Newest transaction indexed by [`LocalIndexer::sync`].
+    last_signature: Option<Signature>,
+    /// Slot of the newest indexed transaction.
+    slot: u64,
+}
+
+/// Indexed state restored if a page of transactions fails to apply.
+struct Checkpoint {
+    state_merkle_trees: Vec<StateMerkleTreeBundle>,
+    address_merkle_trees: Vec<AddressMerkleTreeBundle>,
+    compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    nullified_compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    token_compressed_accounts: Vec<TokenDataWithMerkleContext>,
+    token_nullified_compressed_accounts: Vec<TokenDataWithMerkleContext>,
+    slot: u64,
+}
+
+impl fmt::Debug for LocalIndexer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalIndexer")
+            .field("rpc", &self.rpc)
+            .field("state_merkle_trees", &self.state_merkle_trees.len())
+            .field("address_merkle_trees", &self.address_merkle_trees.len())
+            .field("compressed_accounts", &self.compressed_accounts.len())
+            .field(
+                "token_compressed_accounts",
+                &self.token_compressed_accounts.len(),
+            )
+            .field("last_signature", &self.last_signature)
+            .field("slot", &self.slot)
+            .finish()
+    }
+}
+
+impl LocalIndexer {
+    pub async fn new(config: LocalIndexerConfig) -> Result<Self, IndexerError> {
+        let rpc = LightClient::new(LightClientConfig {
+            url: config.url,
+            commitment_config: config.commitment_config,
+            photon_url: None,
+            fetch_active_tree: false,
+        })
+        .await?;
+        let prover = ProofClient::with_config(
+            config.prover_url,
+            Duration::from_millis(100),
+            Duration::from_secs(600),
+            config.prover_api_key,
+        );
+
+        let mut state_merkle_trees = Vec::new();
+        let mut address_merkle_trees = Vec::new();
+        for tree in config.trees {
+            match tree.tree_type {
+                TreeType::StateV1 | TreeType::StateV2 => {
+                    state_merkle_trees.push(new_state_merkle_tree_bundle(&tree)?)
+                }
+                TreeType::AddressV1 => address_merkle_trees.push(AddressMerkleTreeBundle::new_v1(
+                    AddressMerkleTreeAccounts {
+                        merkle_tree: tree.tree,
+                        queue: tree.queue,
+                    },
+                )?),
+                TreeType::AddressV2 => address_merkle_trees.push(AddressMerkleTreeBundle::new_v2(
+                    AddressMerkleTreeAccounts {
+                        merkle_tree: tree.tree,
+                        queue: tree.queue,
+                    },
+                )?),
+                tree_type => {
+                    return Err(IndexerError::InvalidParameters(format!(
+                        "Unsupported tree type {:?} of tree {}",
+                        tree_type, tree.tree
+                    )))
+                }
+            }
+        }
+
+        Ok(Self {
+            rpc,
+            prover,
+            state_merkle_trees,
+            address_merkle_trees,
+            compressed_accounts: Vec::new(),
+            nullified_compressed_accounts: Vec::new(),
+            token_compressed_accounts: Vec::new(),
+            token_nullified_compressed_accounts: Vec::new(),
+            sync_address: config
+                .sync_address
+                .unwrap_or(Pubkey::new_from_array(ACCOUNT_COMPRESSION_PROGRAM_ID)),
+            last_signature: config.sync_after,
+            slot: 0,
+        })
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            state_merkle_trees: self.state_merkle_trees.clone(),
+            address_merkle_trees: self.address_merkle_trees.clone(),
+            compressed_accounts: self.compressed_accounts.clone(),
+            nullified_compressed_accounts: self.nullified_compressed_accounts.clone(),
+            token_compressed_accounts: self.token_compressed_accounts.clone(),
+            token_nullified_compressed_accounts: self.token_nullified_compressed_accounts.clone(),
+            slot: self.slot,
+        }
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.state_merkle_trees = checkpoint.state_merkle_trees;
+        self.address_merkle_trees = checkpoint.address_merkle_trees;
+        self.compressed_accounts = checkpoint.compressed_accounts;
+        self.nullified_compressed_accounts = checkpoint.nullified_compressed_accounts;
+        self.token_compressed_accounts = checkpoint.token_compressed_accounts;
+        self.token_nullified_compressed_accounts = checkpoint.token_nullified_compressed_accounts;
+        self.slot = checkpoint.slot;
+    }
+
+    /// Indexes all transactions of the sync address, by default the account
+    /// compression program, since the last sync, oldest first. Returns the
+    /// number of indexed transactions.
+    ///
+    /// Transactions are indexed in pages of [`SIGNATURES_PAGE_LIMIT`]
+    /// signatures. A page is either indexed completely or not at all, a sync
+    /// which fails resumes after the last indexed page.
+    pub async fn sync(&mut self) -> Result<usize, IndexerError> {
+        let commitment = self.rpc.client.commitment();
+
+        // Signatures are returned newest first.
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self
+                .rpc
+                .client
+                .get_signatures_for_address_with_config(
+                    &self.sync_address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: self.last_signature,
+                        limit: Some(SIGNATURES_PAGE_LIMIT),
+                        commitment: Some(commitment),
+                    },
+                )
+                .map_err(RpcError::from)?;
+            let is_last_page = page.len() < SIGNATURES_PAGE_LIMIT;
+            for status in page {
+                let signature = status
+                    .signature
+                    .parse::<Signature>()
+                    .map_err(|e| IndexerError::decode_error("signature", e))?;
+                before = Some(signature);
+                signatures.push((signature, status.slot, status.err.is_none()));
+            }
+            if is_last_page {
+                break;
+            }
+        }
+        signatures.reverse();
+
+        let mut num_transactions = 0;
+        for page in signatures.chunks(SIGNATURES_PAGE_LIMIT) {
+            // Fetch the whole page before applying it, failed transactions
+            // only advance the cursor.
+            let mut transactions = Vec::with_capacity(page.len());
+            for (signature, slot, _) in page.iter().filter(|(_, _, succeeded)| *succeeded) {
+                let transaction = self
+                    .rpc
+                    .client
+                    .get_transaction_with_config(
+                        signature,
+                        RpcTransactionConfig {
+                            encoding: Some(UiTransactionEncoding::Base64),
+                            commitment: Some(commitment),
+                            max_supported_transaction_version: Some(0),
+                        },
+                    )
+                    .map_err(RpcError::from)?;
+                transactions.push((*slot, transaction.transaction));
+            }
+
+            let checkpoint = self.checkpoint();
+            let num_page_transactions = transactions.len();
+            for (slot, transaction) in transactions {
+                if let Err(e) = self.index_transaction(slot, transaction) {
+                    self.restore(checkpoint);
+                    return Err(e);
+                }
+            }
+            self.last_signature = page.last().map(|(signature, _, _)| *signature);
+            num_transactions += num_page_transactions;
+        }
+        Ok(num_transactions)
+    }
+
+    /// Indexes all transactions of the confirmed blocks in
+    /// `start_slot..=end_slot`. Returns the number of indexed transactions.
+    pub async fn sync_slot_range(
+        &mut self,
+        start_slot: u64,
+        end_slot: u64,
+    ) -> Result<usize, IndexerError> {
+        let commitment = self.rpc.client.commitment();
+        let slots = self
+            .rpc
+            .client
+            .get_blocks(start_slot, Some(end_slot))
+            .map_err(RpcError::from)?;
+
+        let mut num_transactions = 0;
+        for slot in slots {
+            let block = self
+                .rpc
+                .client
+                .get_block_with_config(
+                    slot,
+                    RpcBlockConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        transaction_details: Some(TransactionDetails::Full),
+                        rewards: Some(false),
+                        commitment: Some(commitment),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .map_err(RpcError::from)?;
+            for transaction in block.transactions.unwrap_or_default() {
+                if self.index_transaction(slot, transaction)? {
+                    num_transactions += 1;
+                }
+            }
+        }
+        self.slot = self.slot.max(end_slot);
+        Ok(num_transactions)
+    }
+
+    /// Applies the Light events of a transaction to the local state.
+    /// Returns false if the transaction failed or emitted no Light events.
+    pub fn index_transaction(
+        &mut self,
+        slot: u64,
+        transaction: EncodedTransactionWithStatusMeta,
+    ) -> Result<bool, IndexerError> {
+        let Some(events) = decode_transaction(transaction)? else {
+            return Ok(false);
+        };
+        if events.events.is_empty() && events.tree_events.is_empty() {
+            return Ok(false);
+        }
+        for event in events.events.iter() {
+            self.index_event(event)?;
+        }
+        for event in events.tree_events {
+            self.apply_tree_event(event)?;
+        }
+        self.slot = self.slot.max(slot);
+        Ok(true)
+    }
+
+    /// Hashes of the live leaves of the v1 state tree `merkle_tree` in leaf
+    /// order. Spent accounts and leaves nullified or migrated on chain are
+    /// excluded.
+    pub fn get_live_leaves(&self, merkle_tree: &Pubkey) -> Result<Vec<[u8; 32]>, IndexerError> {
+        let bundle = self
+            .state_bundle(merkle_tree)
+            .filter(|bundle| bundle.tree_type == TreeType::StateV1)
+            .ok_or_else(|| {
+                IndexerError::InvalidParameters(format!(
+                    "v1 state tree {} is not indexed",
+                    merkle_tree
+                ))
+            })?;
+        let accounts = self.compressed_accounts.iter().chain(
+            self.token_compressed_accounts
+                .iter()
+                .map(|account| &account.compressed_account),
+        );
+        Ok(live_leaves(bundle, accounts))
+    }
+
+    /// Nullifies the inputs and inserts the outputs and new addresses of a
+    /// transaction event.
+    pub fn index_event(&mut self, event: &BatchPublicTransactionEvent) -> Result<(), IndexerError> {
+        let public_event = &event.event;
+
+        let mut inputs = Vec::with_capacity(public_event.input_compressed_account_hashes.len());
+        for hash in public_event.input_compressed_account_hashes.iter() {
+            match self.nullify_account(hash) {
+                Some(merkle_context) => inputs.push((*hash, merkle_context)),
+                None => warn!("Input compressed account {:?} is not indexed", hash),
+            }
+        }
+        // Nullifiers of v2 inputs are inserted by foresters in queue order.
+        for context in event.batch_input_accounts.iter() {
+            let Some((_, merkle_context)) = inputs
+                .iter()
+                .find(|(hash, _)| *hash == context.account_hash)
+            else {
+                continue;
+            };
+            if let Some(bundle) =
+                self.state_bundle_mut(&merkle_context.merkle_tree_pubkey.to_bytes().into())
+            {
+                bundle.input_leaf_indices.push(LeafIndexInfo {
+                    leaf_index: merkle_context.leaf_index,
+                    leaf: context.account_hash,
+                    tx_hash: context.tx_hash,
+                });
+            }
+        }
+
+        for (i, account) in public_event.output_compressed_accounts.iter().enumerate() {
+            let tree_or_queue = public_event
+                .pubkey_array
+                .get(account.merkle_tree_index as usize)
+                .ok_or(IndexerError::InvalidResponseData)?;
+            let tree_or_queue = Pubkey::new_from_array(tree_or_queue.to_bytes());
+            let Some(bundle) = self.state_merkle_trees.iter_mut().find(|bundle| {
+                bundle.accounts.merkle_tree == tree_or_queue
+                    || bundle.accounts.nullifier_queue == tree_or_queue
+            }) else {
+                continue;
+            };
+            let hash = public_event.output_compressed_account_hashes[i];
+            let leaf_index = public_event.output_leaf_indices[i];
+            if bundle.tree_type == TreeType::StateV1 {
+                if bundle.merkle_tree.get_next_index() != leaf_index as usize {
+                    return Err(IndexerError::CustomError(format!(
+                        "Missed transactions of tree {}, expected leaf index {} got {}",
+                        bundle.accounts.merkle_tree,
+                        bundle.merkle_tree.get_next_index(),
+                        leaf_index
+                    )));
+                }
+                bundle
+                    .merkle_tree
+                    .append(&hash)
+                    .map_err(|e| tree_error(&bundle.accounts.merkle_tree, e))?;
+            } else {
+                bundle.output_queue_elements.push((hash, leaf_index as u64));
+            }
+            let merkle_context = MerkleContext {
+                merkle_tree_pubkey: bundle.accounts.merkle_tree.into(),
+                queue_pubkey: bundle.accounts.nullifier_queue.into(),
+                leaf_index,
+                prove_by_index: false,
+                tree_type: bundle.tree_type,
+            };
+            self.insert_account(CompressedAccountWithMerkleContext {
+                compressed_account: account.compressed_account.clone(),
+                merkle_context,
+            });
+        }
+
+        for new_address in event.new_addresses.iter() {
+            let tree_or_queue = Pubkey::new_from_array(new_address.mt_pubkey.to_bytes());
+            if let Some(bundle) = self.address_merkle_trees.iter_mut().find(|bundle| {
+                bundle.accounts.merkle_tree == tree_or_queue
+                    || bundle.accounts.queue == tree_or_queue
+            }) {
+                bundle.queue_elements.push(new_address.address);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a tree update emitted by the account compression program.
+    pub fn apply_tree_event(&mut self, event: MerkleTreeEvent) -> Result<(), IndexerError> {
+        match event {
+            // Appends to v1 state trees are indexed from transaction events.
+            MerkleTreeEvent::V1(_) => Ok(()),
+            MerkleTreeEvent::V2(event) => self.nullify_leaves(event),
+            MerkleTreeEvent::V3(event) => self.update_address_tree(event),
+            MerkleTreeEvent::BatchAppend(event) => self.batch_append(event),
+            MerkleTreeEvent::BatchNullify(event) => self.batch_nullify(event),
+            MerkleTreeEvent::BatchAddressAppend(event) => self.batch_address_append(event),
+        }
+    }
+
+    fn nullify_leaves(&mut self, event: NullifierEvent) -> Result<(), IndexerError> {
+        let Some(bundle) = self.state_bundle_mut(&Pubkey::new_from_array(event.id)) else {
+            return Ok(());
+        };
+        for leaf_index in event.nullified_leaves_indices {
+            bundle
+                .merkle_tree
+                .update(&[0u8; 32], leaf_index as usize)
+                .map_err(|e| tree_error(&bundle.accounts.merkle_tree, e))?;
+        }
+        Ok(())
+    }
+
+    fn update_address_tree(&mut self, event: IndexedMerkleTreeEvent) -> Result<(), IndexerError> {
+        let merkle_tree = Pubkey::new_from_array(event.id);
+        let Some(bundle) = self
+            .address_merkle_trees
+            .iter_mut()
+            .find(|bundle| bundle.accounts.merkle_tree == merkle_tree)
+        else {
+            return Ok(());
+        };
+        for update in event.updates {
+            let address = update.new_high_element.value;
+            let value = BigUint::from_bytes_be(&address);
+            let (low_element, _) = bundle.find_low_element_for_nonexistent(&value)?;
+            let element_bundle =
+                bundle.new_element_with_low_element_index(low_element.index, &value)?;
+            if element_bundle.new_element.index as u64 != update.new_high_element.index {
+                return Err(diverged(&merkle_tree, event.seq));
+            }
+            bundle.update(
+                &element_bundle.new_low_element,
+                &element_bundle.new_element,
+                &element_bundle.new_element_next_value,
+            )?;
+            bundle.append_with_low_element_index(low_element.index, &value)?;
+            bundle.queue_elements.retain(|queued| *queued != address);
+        }
+        Ok(())
+    }
+
+    fn batch_append(&mut self, event: BatchEvent) -> Result<(), IndexerError> {
+        match self.state_bundle_mut(&Pubkey::new_from_array(event.merkle_tree_pubkey)) {
+            Some(bundle) => apply_batch_append(bundle, &event),
+            None => Ok(()),
+        }
+    }
+
+    fn batch_nullify(&mut self, event: BatchEvent) -> Result<(), IndexerError> {
+        match self.state_bundle_mut(&Pubkey::new_from_array(event.merkle_tree_pubkey)) {
+            Some(bundle) => apply_batch_nullify(bundle, &event),
+            None => Ok(()),
+        }
+    }
+
+    fn batch_address_append(&mut self, event: BatchEvent) -> Result<(), IndexerError> {
+        let merkle_tree = Pubkey::new_from_array(event.merkle_tree_pubkey);
+        let Some(bundle) = self
+            .address_merkle_trees
+            .iter_mut()
+            .find(|bundle| bundle.accounts.merkle_tree == merkle_tree)
+        else {
+            return Ok(());
+        };
+        let batch_size = event.zkp_batch_size as usize;
+        if bundle.queue_elements.len() < batch_size {
+            return Err(diverged(&merkle_tree, event.sequence_number));
+        }
+        let addresses: Vec<[u8; 32]> = bundle.queue_elements.drain(..batch_size).collect();
+        for address in addresses.iter() {
+            bundle.append(&BigUint::from_bytes_be(address))?;
+        }
+        check_root(&merkle_tree, bundle.root(), &event)
+    }
+
+    /// Moves the account with `hash` to the nullified accounts and returns its
+    /// Merkle context.
+    fn nullify_account(&mut self, hash: &[u8; 32]) -> Option<MerkleContext> {
+        if let Some(index) = self
+            .compressed_accounts
+            .iter()
+            .position(|account| account.hash() == Ok(*hash))
+        {
+            let account = self.compressed_accounts.remove(index);
+            let merkle_context = account.merkle_context;
+            self.nullified_compressed_accounts.push(account);
+            Some(merkle_context)
+        } else if let Some(index) = self
+            .token_compressed_accounts
+            .iter()
+            .position(|account| account.compressed_account.hash() == Ok(*hash))
+        {
+            let account = self.token_compressed_accounts.remove(index);
+            let merkle_context = account.compressed_account.merkle_context;
+            self.token_nullified_compressed_accounts.push(account);
+            Some(merkle_context)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a new account, token accounts are stored with their token data.
+    /// New accounts are inserted in front so that the newest accounts are
+    /// found first.
+    fn insert_account(&mut self, account: CompressedAccountWithMerkleContext) {
+        if let Some(token_data) = decode_token_data(
+            account.compressed_account.owner.to_bytes(),
+            account.compressed_account.data.as_ref(),
+        ) {
+            self.token_compressed_accounts.insert(
+                0,
+                TokenDataWithMerkleContext {
+                    token_data,
+                    compressed_account: account,
+                },
+            );
+            return;
+        }
+        self.compressed_accounts.insert(0, account);
+    }
+
+    fn find_account(&self, hash: &[u8; 32]) -> Option<&CompressedAccountWithMerkleContext> {
+        self.compressed_accounts
+            .iter()
+            .find(|account| account.hash() == Ok(*hash))
+            .or_else(|| {
+                self.token_compressed_accounts
+                    .iter()
+                    .map(|account| &account.compressed_account)
+                    .find(|account| account.hash() == Ok(*hash))
+            })
+    }
+
+    fn state_bundle(&self, merkle_tree: &Pubkey) -> Option<&StateMerkleTreeBundle> {
+        self.state_merkle_trees
+            .iter()
+            .find(|bundle| bundle.accounts.merkle_tree == *merkle_tree)
+    }
+
+    fn state_bundle_mut(&mut self, merkle_tree: &Pubkey) -> Option<&mut StateMerkleTreeBundle> {
+        self.state_merkle_trees
+            .iter_mut()
+            .find(|bundle| bundle.accounts.merkle_tree == *merkle_tree)
+    }
+
+    fn address_bundle(
+        &self,
+        merkle_tree: &Pubkey,
+    ) -> Result<&AddressMerkleTreeBundle, IndexerError> {
+        self.address_merkle_trees
+            .iter()
+            .find(|bundle| bundle.accounts.merkle_tree == *merkle_tree)
+            .ok_or_else(|| {
+                IndexerError::InvalidParameters(format!(
+                    "Address tree {} is not indexed",
+                    merkle_tree
+                ))
+            })
+    }
+
+    fn response<T>(&self, value: T) -> Response<T> {
+        Response {
+            context: Context { slot: self.slot },
+            value,
+        }
+    }
+
+    /// Returns the on-chain root index of `root`. Root histories are fetched
+    /// once per tree and cached in `root_histories`.
+    async fn root_index(
+        &self,
+        root_histories: &mut HashMap<Pubkey, Vec<[u8; 32]>>,
+        merkle_tree: Pubkey,
+        root: [u8; 32],
+    ) -> Result<u16, IndexerError> {
+        if !root_histories.contains_key(&merkle_tree) {
+            let state = fetch_onchain_tree_state(&self.rpc, merkle_tree)
+                .await
+                .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+            root_histories.insert(merkle_tree, state.root_history);
+        }
+        root_histories[&merkle_tree]
+            .iter()
+            .position(|onchain_root| *onchain_root == root)
+            .map(|index| index as u16)
+            .ok_or_else(|| {
+                IndexerError::CustomError(format!(
+                    "Root of tree {} is not in the on-chain root history, sync the local indexer",
+                    merkle_tree
+                ))
+            })
+    }
+
+    async fn validity_proof(
+        &self,
+        hashes: Vec<Hash>,
+        new_addresses_with_trees: Vec<AddressWithTree>,
+    ) -> Result<ValidityProofWithContext, IndexerError> {
+        if hashes.len() > 8 {
+            return Err(IndexerError::CustomError(format!(
+                "compressed_accounts must be of length <= 8, got {}",
+                hashes.len()
+            )));
+        }
+        if new_addresses_with_trees.len() > 8 {
+            return Err(IndexerError::CustomError(format!(
+                "new_addresses must be of length <= 8, got {}",
+                new_addresses_with_trees.len()
+            )));
+        }
+        let mut root_histories = HashMap::new();
+
+        let mut accounts = Vec::with_capacity(hashes.len());
+        let mut inclusion_proofs = Vec::new();
+        let mut state_tree_height = None;
+        for hash in hashes.iter() {
+            let account = self
+                .find_account(hash)
+                .ok_or(IndexerError::AccountNotFound)?;
+            let merkle_tree =
+                Pubkey::new_from_array(account.merkle_context.merkle_tree_pubkey.to_bytes());
+            let bundle = self
+                .state_bundle(&merkle_tree)
+                .ok_or(IndexerError::AccountNotFound)?;
+            let leaf_index = account.merkle_context.leaf_index as usize;
+            let tree_info = TreeInfo {
+                cpi_context: Some(bundle.accounts.cpi_context),
+                tree: bundle.accounts.merkle_tree,
+                queue: bundle.accounts.nullifier_queue,
+                next_tree_info: None,
+                tree_type: bundle.tree_type,
+            };
+
+            // Accounts in v2 output queues are proven by index.
+            if bundle
+                .output_queue_elements
+                .iter()
+                .any(|(queued, _)| queued == hash)
+            {
+                accounts.push(AccountProofInputs {
+                    hash: *hash,
+                    root: [0u8; 32],
+                    root_index: RootIndex::new_none(),
+                    leaf_index: leaf_index as u64,
+                    tree_info,
+                });
+                continue;
+            }
+
+            if bundle.merkle_tree.get_leaf(leaf_index).ok() != Some(*hash) {
+                return Err(IndexerError::CustomError(format!(
+                    "Account {:?} is not in the local tree {}",
+                    hash, merkle_tree
+                )));
+            }
+            let height = bundle.merkle_tree.height;
+            if *state_tree_height.get_or_insert(height) != height {
+                return Err(IndexerError::CustomError(
+                    "All state trees must have the same height".to_string(),
+                ));
+            }
+            let proof = bundle
+                .merkle_tree
+                .get_proof_of_leaf(leaf_index, true)
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+            let root = bundle.merkle_tree.root();
+            let root_index = self
+                .root_index(&mut root_histories, merkle_tree, root)
+                .await?;
+            inclusion_proofs.push(InclusionMerkleProofInputs {
+                root: BigInt::from_be_bytes(root.as_slice()),
+                leaf: BigInt::from_be_bytes(hash),
+                path_index: BigInt::from_be_bytes(leaf_index.to_be_bytes().as_slice()),
+                path_elements: proof.iter().map(|x| BigInt::from_be_bytes(x)).collect(),
+            });
+            accounts.push(AccountProofInputs {
+                hash: *hash,
+                root,
+                root_index: RootIndex::new_some(root_index),
+                leaf_index: leaf_index as u64,
+                tree_info,
+            });
+        }
+
+        let mut addresses = Vec::with_capacity(new_addresses_with_trees.len());
+        let mut non_inclusion_proofs = Vec::new();
+        let mut address_tree_height = None;
+        for address_with_tree in new_addresses_with_trees.iter() {
+            let bundle = self.address_bundle(&address_with_tree.tree)?;
+            let height = bundle.height();
+            if *address_tree_height.get_or_insert(height) != height {
+                return Err(IndexerError::CustomError(
+                    "All address trees must have the same height".to_string(),
+                ));
+            }
+            non_inclusion_proofs
+                .push(bundle.get_non_inclusion_proof_inputs(&address_with_tree.address)?);
+            let root = bundle.root();
+            let root_index = self
+                .root_index(&mut root_histories, address_with_tree.tree, root)
+                .await?;
+            addresses.push(AddressProofInputs {
+                address: address_with_tree.address,
+                root,
+                root_index,
+                tree_info: TreeInfo {
+                    cpi_context: None,
+                    tree: bundle.accounts.merkle_tree,
+                    queue: bundle.accounts.queue,
+                    next_tree_info: None,
+                    tree_type: if height == ADDRESS_MERKLE_TREE_HEIGHT {
+                        TreeType::AddressV1
+                    } else {
+                        TreeType::AddressV2
+                    },
+                },
+            });
+        }
+
+        let proof = if inclusion_proofs.is_empty() && non_inclusion_proofs.is_empty() {
+            None
+        } else {
+            let json_payload = proof_json_payload(
+                state_tree_height,
+                &inclusion_proofs,
+                address_tree_height,
+                &non_inclusion_proofs,
+            )?;
+            let proof = self
+                .prover
+                .generate_proof(json_payload)
+                .await
+                .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+            Some(CompressedProof::from(proof.proof))
+        };
+
+        Ok(ValidityProofWithContext {
+            proof: proof.into(),
+            accounts,
+            addresses,
+        })
+    }
+}
+
+/// Inserts the output queue elements `old_next_index..new_next_index` into
+/// the tree of `bundle`.
+fn apply_batch_append(
+    bundle: &mut StateMerkleTreeBundle,
+    event: &BatchEvent,
+) -> Result<(), IndexerError> {
+    let merkle_tree = bundle.accounts.merkle_tree;
+    for leaf_index in event.old_next_index..event.new_next_index {
+        let position = bundle
+            .output_queue_elements
+            .iter()
+            .position(|(_, index)| *index == leaf_index)
+            .ok_or_else(|| diverged(&merkle_tree, event.sequence_number))?;
+        let (leaf, _) = bundle.output_queue_elements.remove(position);
+        let leaf_index = leaf_index as usize;
+        while bundle.merkle_tree.get_next_index() < leaf_index {
+            bundle
+                .merkle_tree
+                .append(&[0u8; 32])
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+        }
+        if bundle.merkle_tree.get_next_index() == leaf_index {
+            bundle
+                .merkle_tree
+                .append(&leaf)
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+        } else if bundle.merkle_tree.leaf(leaf_index) == [0u8; 32] {
+            // Non-zero leaves have been nullified before they were appended
+            // and keep their nullifier.
+            bundle
+                .merkle_tree
+                .update(&leaf, leaf_index)
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+        }
+    }
+    check_root(&merkle_tree, bundle.merkle_tree.root(), event)
+}
+
+/// Inserts the nullifiers of the next `zkp_batch_size` input queue elements
+/// into the tree of `bundle`.
+fn apply_batch_nullify(
+    bundle: &mut StateMerkleTreeBundle,
+    event: &BatchEvent,
+) -> Result<(), IndexerError> {
+    let merkle_tree = bundle.accounts.merkle_tree;
+    let batch_size = event.zkp_batch_size as usize;
+    if bundle.input_leaf_indices.len() < batch_size {
+        return Err(diverged(&merkle_tree, event.sequence_number));
+    }
+    for leaf_info in bundle.input_leaf_indices.drain(..batch_size) {
+        let leaf_index = leaf_info.leaf_index as usize;
+        // Leaves can be nullified before they are appended.
+        while bundle.merkle_tree.get_next_index() < leaf_index + 2 {
+            bundle
+                .merkle_tree
+                .append(&[0u8; 32])
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+        }
+        let nullifier = create_nullifier(
+            &leaf_info.leaf,
+            leaf_info.leaf_index as u64,
+            &leaf_info.tx_hash,
+        )
+        .map_err(|e| tree_error(&merkle_tree, e))?;
+        bundle
+            .merkle_tree
+            .update(&nullifier, leaf_index)
+            .map_err(|e| tree_error(&merkle_tree, e))?;
+    }
+    check_root(&merkle_tree, bundle.merkle_tree.root(), event)
+}
+
+fn new_state_merkle_tree_bundle(tree: &TreeInfo) -> Result<StateMerkleTreeBundle, IndexerError> {
+    let (merkle_tree, rollover_fee) = if tree.tree_type == TreeType::StateV1 {
+        (
+            MerkleTree::<Poseidon>::new_with_history(
+                STATE_MERKLE_TREE_HEIGHT,
+                STATE_MERKLE_TREE_CANOPY_DEPTH,
+                0,
+                STATE_MERKLE_TREE_ROOTS,
+            ),
+            FeeConfig::default().state_merkle_tree_rollover,
+        )
+    } else {
+        (
+            MerkleTree::<Poseidon>::new_with_history(
+                DEFAULT_BATCH_STATE_TREE_HEIGHT as usize,
+                0,
+                0,
+                DEFAULT_BATCH_ROOT_HISTORY_LEN as usize,
+            ),
+            FeeConfig::test_batched().state_merkle_tree_rollover,
+        )
+    };
+    // The cpi context is only reported in tree infos of validity proofs.
+    let cpi_context = tree.cpi_context.unwrap_or_default();
+    Ok(StateMerkleTreeBundle {
+        rollover_fee: rollover_fee as i64,
+        network_fee: FeeConfig::default().network_fee,
+        merkle_tree: Box::new(merkle_tree),
+        accounts: StateMerkleTreeAccounts {
+            merkle_tree: tree.tree,
+            nullifier_queue: tree.queue,
+            cpi_context,
+            tree_type: tree.tree_type,
+        },
+        tree_type: tree.tree_type,
+        output_queue_elements: Vec::new(),
+        input_leaf_indices: Vec::new(),
+        output_queue_batch_size: None,
+        num_inserted_batches: 0,
+    })
+}
+
+/// Hashes of the `accounts` of the tree of `bundle` whose leaves are still in
+/// the tree, sorted by leaf index.
+fn live_leaves<'a>(
+    bundle: &StateMerkleTreeBundle,
+    accounts: impl Iterator<Item = &'a CompressedAccountWithMerkleContext>,
+) -> Vec<[u8; 32]> {
+    let mut leaves: Vec<(u32, [u8; 32])> = accounts
+        .filter(|account| {
+            Pubkey::new_from_array(account.merkle_context.merkle_tree_pubkey.to_bytes())
+                == bundle.accounts.merkle_tree
+        })
+        .filter_map(|account| {
+            let leaf_index = account.merkle_context.leaf_index;
+            let hash = account.hash().ok()?;
+            (bundle.merkle_tree.get_leaf(leaf_index as usize).ok() == Some(hash))
+                .then_some((leaf_index, hash))
+        })
+        .collect();
+    leaves.sort_unstable_by_key(|(leaf_index, _)| *leaf_index);
+    leaves.into_iter().map(|(_, hash)| hash).collect()
+}
+
+/// Builds the prover request for inclusion, non-inclusion or combined proofs.
+fn proof_json_payload(
+    state_tree_height: Option<usize>,
+    inclusion_proofs: &[InclusionMerkleProofInputs],
+    address_tree_height: Option<usize>,
+    non_inclusion_proofs: &[NonInclusionMerkleProofInputs],
+) -> Result<String, IndexerError> {
+    const V2_STATE_TREE_HEIGHT: usize = DEFAULT_BATCH_STATE_TREE_HEIGHT as usize;
+    const V2_ADDRESS_TREE_HEIGHT: usize = DEFAULT_BATCH_ADDRESS_TREE_HEIGHT as usize;
+    let unsupported_height =
+        |height: usize| IndexerError::CustomError(format!("Unsupported tree height {}", height));
+
+    match (state_tree_height, address_tree_height) {
+        (Some(V2_STATE_TREE_HEIGHT), None) => {
+            let inputs = InclusionProofInputs::new(inclusion_proofs)
+                .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+            Ok(BatchInclusionJsonStruct::from_inclusion_proof_inputs(&inputs).to_string())
+        }
+        (Some(STATE_MERKLE_TREE_HEIGHT), None) => {
+            let inputs = InclusionProofInputsLegacy(inclusion_proofs);
+            Ok(BatchInclusionJsonStructLegacy::from_inclusion_proof_inputs(&inputs).to_string())
+        }
+        (None, Some(V2_ADDRESS_TREE_HEIGHT)) => {
+            let inputs = NonInclusionProofInputs::new(non_inclusion_proofs)
+                .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+            Ok(BatchNonInclusionJsonStruct::from_non_inclusion_proof_inputs(&inputs).to_string())
+        }
+        (None, Some(ADDRESS_MERKLE_TREE_HEIGHT)) => {
+            let inputs = NonInclusionProofInputsLegacy::new(non_inclusion_proofs);
+            Ok(
+                BatchNonInclusionJsonStructLegacy::from_non_inclusion_proof_inputs(&inputs)
+                    .to_string(),
+            )
+        }
+        (Some(V2_STATE_TREE_HEIGHT), Some(V2_ADDRESS_TREE_HEIGHT)) => {
+            let inclusion = BatchInclusionJsonStruct::from_inclusion_proof_inputs(
+                &InclusionProofInputs::new(inclusion_proofs)
+                    .map_err(|e| IndexerError::CustomError(e.to_string()))?,
+            );
+            let non_inclusion = BatchNonInclusionJsonStruct::from_non_inclusion_proof_inputs(
+                &NonInclusionProofInputs::new(non_inclusion_proofs)
+                    .map_err(|e| IndexerError::CustomError(e.to_string()))?,
+            );
+            let public_input_hash = |hash: &str| {
+                string_to_big_int(hash)
+                    .and_then(|hash| bigint_to_u8_32(&hash).ok())
+                    .ok_or_else(|| {
+                        IndexerError::CustomError("Invalid public input hash".to_string())
+                    })
+            };
+            let public_input_hash = create_hash_chain_from_slice(&[
+                public_input_hash(&inclusion.public_input_hash)?,
+                public_input_hash(&non_inclusion.public_input_hash)?,
+            ])
+            .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+            Ok(CombinedJsonStruct {
+                circuit_type: ProofType::Combined.to_string(),
+                state_tree_height: V2_STATE_TREE_HEIGHT as u32,
+                address_tree_height: V2_ADDRESS_TREE_HEIGHT as u32,
+                public_input_hash: big_int_to_string(&BigInt::from_bytes_be(
+                    num_bigint::Sign::Plus,
+                    &public_input_hash,
+                )),
+                inclusion: inclusion.inputs,
+                non_inclusion: non_inclusion.inputs,
+            }
+            .to_string())
+        }
+        (Some(STATE_MERKLE_TREE_HEIGHT), Some(ADDRESS_MERKLE_TREE_HEIGHT)) => {
+            let inclusion = BatchInclusionJsonStructLegacy::from_inclusion_proof_inputs(
+                &InclusionProofInputsLegacy(inclusion_proofs),
+            );
+            let non_inclusion = BatchNonInclusionJsonStructLegacy::from_non_inclusion_proof_inputs(
+                &NonInclusionProofInputsLegacy::new(non_inclusion_proofs),
+            );
+            Ok(CombinedJsonStructLegacy {
+                circuit_type: ProofType::Combined.to_string(),
+                state_tree_height: STATE_MERKLE_TREE_HEIGHT as u32,
+                address_tree_height: ADDRESS_MERKLE_TREE_HEIGHT as u32,
+                inclusion: inclusion.inputs,
+                non_inclusion: non_inclusion.inputs,
+            }
+            .to_string())
+        }
+        (Some(state_tree_height), Some(address_tree_height)) => {
+            Err(IndexerError::MixedTreeVersions {
+                state_version: format!("state tree height {}", state_tree_height),
+                address_version: format!("address tree height {}", address_tree_height),
+            })
+        }
+        (Some(height), None) | (None, Some(height)) => Err(unsupported_height(height)),
+        (None, None) => Err(IndexerError::InvalidParameters(
+            "At least one of compressed_accounts or new_addresses must be provided".to_string(),
+        )),
+    }
+}
+
+fn check_root(
+    merkle_tree: &Pubkey,
+    root: [u8; 32],
+    event: &BatchEvent,
+) -> Result<(), IndexerError> {
+    if root != event.new_root {
+        return Err(diverged(merkle_tree, event.sequence_number));
+    }
+    Ok(())
+}
+
+fn diverged(merkle_tree: &Pubkey, sequence_number: u64) -> IndexerError {
+    IndexerError::CustomError(format!(
+        "Local state of tree {} diverged from the chain at sequence number {}",
+        merkle_tree, sequence_number
+    ))
+}
+
+fn tree_error(merkle_tree: &Pubkey, error: impl fmt::Display) -> IndexerError {
+    IndexerError::CustomError(format!(
+        "Failed to update local tree {}: {}",
+        merkle_tree, error
+    ))
+}
+
+#[async_trait]
+impl Indexer for LocalIndexer {
+    async fn get_compressed_account(
+        &self,
+        address: Address,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        let account = self
+            .compressed_accounts
+            .iter()
+            .find(|account| account.compressed_account.address == Some(address))
+            .map(|account| account.clone().try_into())
+            .transpose()?;
+        Ok(self.response(account))
+    }
+
+    async fn get_compressed_account_by_hash(
+        &self,
+        hash: Hash,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        let account = self
+            .find_account(&hash)
+            .map(|account| account.clone().try_into())
+            .transpose()?;
+        Ok(self.response(account))
+    }
+
+    async fn get_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        _options: Option<GetCompressedAccountsByOwnerConfig>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedAccount>>, IndexerError> {
+        let items = self
+            .compressed_accounts
+            .iter()
+            .filter(|account| account.compressed_account.owner.to_bytes() == owner.to_bytes())
+            .map(|account| account.clone().try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.response(ItemsWithCursor {
+            items,
+            cursor: None,
+        }))
+    }
+
+    async fn get_compressed_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        let account = match (address, hash) {
+            (Some(address), _) => self.get_compressed_account(address, None).await?,
+            (_, Some(hash)) => self.get_compressed_account_by_hash(hash, None).await?,
+            (None, None) => {
+                return Err(IndexerError::InvalidParameters(
+                    "Either address or hash must be provided".to_string(),
+                ))
+            }
+        };
+        let account = account.value.ok_or(IndexerError::AccountNotFound)?;
+        Ok(self.response(account.lamports))
+    }
+
+    async fn get_compressed_balance_by_owner(
+        &self,
+        owner: &Pubkey,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        let balance = self
+            .compressed_accounts
+            .iter()
+            .filter(|account| account.compressed_account.owner.to_bytes() == owner.to_bytes())
+            .map(|account| account.compressed_account.lamports)
+            .sum();
+        Ok(self.response(balance))
+    }
+
+    async fn get_compressed_mint_token_holders(
+        &self,
+        mint: &Pubkey,
+        options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
+        let mut balances = HashMap::<Pubkey, u64>::new();
+        for account in self
+            .token_compressed_accounts
+            .iter()
+            .filter(|account| account.token_data.mint == *mint)
+        {
+            *balances.entry(account.token_data.owner).or_default() += account.token_data.amount;
+        }
+        let mut items: Vec<OwnerBalance> = balances
+            .into_iter()
+            .map(|(owner, balance)| OwnerBalance { balance, owner })
+            .collect();
+        items.sort_by(|a, b| b.balance.cmp(&a.balance));
+        if let Some(limit) = options.and_then(|options| options.limit) {
+            items.truncate(limit as usize);
+        }
+        Ok(self.response(ItemsWithCursor {
+            items,
+            cursor: None,
+        }))
+    }
+
+    async fn get_compressed_token_account_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        let account = match (address, hash) {
+            (Some(address), _) => self.token_compressed_accounts.iter().find(|account| {
+                account.compressed_account.compressed_account.address == Some(address)
+            }),
+            (_, Some(hash)) => self
+                .token_compressed_accounts
+                .iter()
+                .find(|account| account.compressed_account.hash() == Ok(hash)),
+            (None, None) => {
+                return Err(IndexerError::InvalidParameters(
+                    "Either address or hash must be provided".to_string(),
+                ))
+            }
+        };
+        let amount = account
+            .map(|account| account.token_data.amount)
+            .ok_or(IndexerError::AccountNotFound)?;
+        Ok(self.response(amount))
+    }
+
+    async fn get_compressed_token_accounts_by_delegate(
+        &self,
+        delegate: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        let items = token_accounts(
+            self.token_compressed_accounts
+                .iter()
+                .filter(|account| account.token_data.delegate == Some(*delegate)),
+            options,
+        )?;
+        Ok(self.response(ItemsWithCursor {
+            items,
+            cursor: None,
+        }))
+    }
+
+    async fn get_compressed_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        let items = token_accounts(
+            self.token_compressed_accounts
+                .iter()
+                .filter(|account| account.token_data.owner == *owner),
+            options,
+        )?;
+        Ok(self.response(ItemsWithCursor {
+            items,
+            cursor: None,
+        }))
+    }
+
+    async fn get_compressed_token_balances_by_owner_v2(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<TokenBalance>>, IndexerError> {
+        let mint = options.as_ref().and_then(|options| options.mint);
+        let mut balances = HashMap::<Pubkey, u64>::new();
+        for account in self.token_compressed_accounts.iter().filter(|account| {
+            account.token_data.owner == *owner
+                && mint.is_none_or(|mint| account.token_data.mint == mint)
+        }) {
+            *balances.entry(account.token_data.mint).or_default() += account.token_data.amount;
+        }
+        let items = balances
+            .into_iter()
+            .map(|(mint, balance)| TokenBalance { balance, mint })
+            .collect();
+        Ok(self.response(ItemsWithCursor {
+            items,
+            cursor: None,
+        }))
+    }
+
+    async fn get_compression_signatures_for_account(
+        &self,
+        _hash: Hash,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
+        Err(not_implemented("get_compression_signatures_for_account"))
+    }
+
+    async fn get_compression_signatures_for_address(
+        &self,
+        _address: &[u8; 32],
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        Err(not_implemented("get_compression_signatures_for_address"))
+    }
+
+    async fn get_compression_signatures_for_owner(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        Err(not_implemented("get_compression_signatures_for_owner"))
+    }
+
+    async fn get_compression_signatures_for_token_owner(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        Err(not_implemented(
+            "get_compression_signatures_for_token_owner",
+        ))
+    }
+
+    async fn get_indexer_health(&self, _config: Option<RetryConfig>) -> Result<bool, IndexerError> {
+        Ok(true)
+    }
+
+    async fn get_indexer_slot(&self, _config: Option<RetryConfig>) -> Result<u64, IndexerError> {
+        Ok(self.slot)
+    }
+
+    async fn get_multiple_compressed_account_proofs(
+        &self,
+        hashes: Vec<[u8; 32]>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<MerkleProof>>, IndexerError> {
+        let mut items = Vec::with_capacity(hashes.len());
+        for hash in hashes.iter() {
+            let account = self
+                .find_account(hash)
+                .ok_or(IndexerError::AccountNotFound)?;
+            let merkle_tree =
+                Pubkey::new_from_array(account.merkle_context.merkle_tree_pubkey.to_bytes());
+            let bundle = self
+                .state_bundle(&merkle_tree)
+                .ok_or(IndexerError::AccountNotFound)?;
+            let leaf_index = account.merkle_context.leaf_index as usize;
+            if bundle.merkle_tree.get_leaf(leaf_index).ok() != Some(*hash) {
+                return Err(IndexerError::CustomError(format!(
+                    "Account {:?} is not in the local tree {}",
+                    hash, merkle_tree
+                )));
+            }
+            let proof = bundle
+                .merkle_tree
+                .get_proof_of_leaf(leaf_index, true)
+                .map_err(|e| tree_error(&merkle_tree, e))?;
+            items.push(MerkleProof {
+                hash: *hash,
+                leaf_index: leaf_index as u64,
+                merkle_tree,
+                proof: proof.to_vec(),
+                root_seq: bundle.merkle_tree.sequence_number as u64,
+                root: bundle.merkle_tree.root(),
+            });
+        }
+        Ok(self.response(Items { items }))
+    }
+
+    async fn get_multiple_compressed_accounts(
+        &self,
+        addresses: Option<Vec<Address>>,
+        hashes: Option<Vec<Hash>>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<Option<CompressedAccount>>>, IndexerError> {
+        let items = match (addresses, hashes) {
+            (Some(addresses), _) => addresses
+                .iter()
+                .map(|address| {
+                    self.compressed_accounts
+                        .iter()
+                        .find(|account| account.compressed_account.address == Some(*address))
+                        .map(|account| account.clone().try_into())
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            (_, Some(hashes)) => hashes
+                .iter()
+                .map(|hash| {
+                    self.find_account(hash)
+                        .map(|account| account.clone().try_into())
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            (None, None) => {
+                return Err(IndexerError::InvalidParameters(
+                    "Either addresses or hashes must be provided".to_string(),
+                ))
+            }
+        };
+        Ok(self.response(Items { items }))
+    }
+
+    async fn get_multiple_new_address_proofs(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        addresses: Vec<[u8; 32]>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<NewAddressProofWithContext>>, IndexerError> {
+        let merkle_tree = Pubkey::new_from_array(merkle_tree_pubkey);
+        let bundle = self.address_bundle(&merkle_tree)?;
+        let mut items = Vec::with_capacity(addresses.len());
+        for address in addresses.iter() {
+            let value = BigUint::from_bytes_be(address);
+            let (low_element, low_element_next_value) =
+                bundle.find_low_element_for_nonexistent(&value)?;
+            let element_bundle =
+                bundle.new_element_with_low_element_index(low_element.index, &value)?;
+            let low_address_proof = bundle.get_proof_of_leaf(low_element.index, false)?;
+            items.push(NewAddressProofWithContext {
+                merkle_tree,
+                low_address_index: low_element.index as u64,
+                low_address_value: bigint_to_be_bytes_array(&low_element.value)
+                    .map_err(|e| IndexerError::CustomError(e.to_string()))?,
+                low_address_next_index: low_element.next_index as u64,
+                low_address_next_value: bigint_to_be_bytes_array(&low_element_next_value)
+                    .map_err(|e| IndexerError::CustomError(e.to_string()))?,
+                low_address_proof,
+                root: bundle.root(),
+                root_seq: bundle.sequence_number(),
+                new_low_element: Some(element_bundle.new_low_element),
+                new_element: Some(element_bundle.new_element),
+                new_element_next_value: Some(element_bundle.new_element_next_value),
+            });
+        }
+        Ok(self.response(Items { items }))
+    }
+
+    async fn get_validity_proof(
+        &self,
+        hashes: Vec<Hash>,
+        new_addresses_with_trees: Vec<AddressWithTree>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ValidityProofWithContext>, IndexerError> {
+        let proof = self
+            .validity_proof(hashes, new_addresses_with_trees)
+            .await?;
+        Ok(self.response(proof))
+    }
+
+    async fn get_queue_elements(
+        &mut self,
+        _merkle_tree_pubkey: [u8; 32],
+        _options: QueueElementsV2Options,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueElementsResult>, IndexerError> {
+        Err(not_implemented("get_queue_elements"))
+    }
+
+    async fn get_queue_info(
+        &self,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueInfoResult>, IndexerError> {
+        Err(not_implemented("get_queue_info"))
+    }
+
+    async fn get_subtrees(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<[u8; 32]>>, IndexerError> {
+        let merkle_tree = Pubkey::new_from_array(merkle_tree_pubkey);
+        let items = match self.state_bundle(&merkle_tree) {
+            Some(bundle) => bundle.merkle_tree.get_subtrees(),
+            None => self.address_bundle(&merkle_tree)?.get_subtrees(),
+        };
+        Ok(self.response(Items { items }))
+    }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        _owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        Err(not_implemented("subscribe_compressed_accounts_by_owner"))
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        _owner: &Pubkey,
+        _mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        Err(not_implemented("subscribe_token_accounts_by_owner"))
+    }
+}
+
+fn token_accounts<'a>(
+    accounts: impl Iterator<Item = &'a TokenDataWithMerkleContext>,
+    options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+) -> Result<Vec<CompressedTokenAccount>, IndexerError> {
+    let mint = options.as_ref().and_then(|options| options.mint);
+    let limit = options
+        .as_ref()
+        .and_then(|options| options.limit)
+        .map_or(usize::MAX, |limit| limit as usize);
+    accounts
+        .filter(|account| mint.is_none_or(|mint| account.token_data.mint == mint))
+        .take(limit)
+        .map(|account| CompressedTokenAccount::try_from(account.clone()))
+        .collect()
+}
+
+fn not_implemented(method: &str) -> IndexerError {
+    IndexerError::NotImplemented(format!("{} is not supported by the local indexer", method))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_v2_bundle() -> StateMerkleTreeBundle {
+        new_state_merkle_tree_bundle(&TreeInfo {
+            tree: Pubkey::new_unique(),
+            queue: Pubkey::new_unique(),
+            cpi_context: Some(Pubkey::new_unique()),
+            next_tree_info: None,
+            tree_type: TreeType::StateV2,
+        })
+        .unwrap()
+    }
+
+    fn batch_event(
+        bundle: &StateMerkleTreeBundle,
+        old_next_index: u64,
+        zkp_batch_size: u64,
+        new_root: [u8; 32],
+    ) -> BatchEvent {
+        BatchEvent {
+            merkle_tree_pubkey: bundle.accounts.merkle_tree.to_bytes(),
+            batch_index: 0,
+            zkp_batch_index: 0,
+            zkp_batch_size,
+            old_next_index,
+            new_next_index: old_next_index + zkp_batch_size,
+            new_root,
+            root_index: 1,
+            sequence_number: 1,
+            output_queue_pubkey: Some(bundle.accounts.nullifier_queue.to_bytes()),
+        }
+    }
+
+    fn reference_tree(leaves: &[[u8; 32]]) -> MerkleTree<Poseidon> {
+        let mut tree = MerkleTree::<Poseidon>::new(DEFAULT_BATCH_STATE_TREE_HEIGHT as usize, 0);
+        for leaf in leaves {
+            tree.append(leaf).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_batch_append_then_nullify() {
+        let leaves = [[1u8; 32], [2u8; 32]];
+        let tx_hash = [3u8; 32];
+        let nullifier = create_nullifier(&leaves[1], 1, &tx_hash).unwrap();
+        let mut bundle = state_v2_bundle();
+        bundle.output_queue_elements = vec![(leaves[0], 0), (leaves[1], 1)];
+        bundle.input_leaf_indices = vec![LeafIndexInfo {
+            leaf_index: 1,
+            leaf: leaves[1],
+            tx_hash,
+        }];
+
+        let appended = reference_tree(&leaves).root();
+        apply_batch_append(&mut bundle, &batch_event(&bundle, 0, 2, appended)).unwrap();
+        assert!(bundle.output_queue_elements.is_empty());
+
+        // The nullified leaf is followed by a zero leaf which is overwritten
+        // by the next append.
+        let nullified = reference_tree(&[leaves[0], nullifier, [0u8; 32]]).root();
+        apply_batch_nullify(&mut bundle, &batch_event(&bundle, 0, 1, nullified)).unwrap();
+        assert!(bundle.input_leaf_indices.is_empty());
+    }
+
+    /// Leaves nullified before they are appended keep their nullifier.
+    #[test]
+    fn test_batch_nullify_before_append() {
+        let leaves = [[1u8; 32], [2u8; 32]];
+        let tx_hash = [3u8; 32];
+        let nullifier = create_nullifier(&leaves[0], 0, &tx_hash).unwrap();
+        let mut bundle = state_v2_bundle();
+        bundle.output_queue_elements = vec![(leaves[0], 0), (leaves[1], 1)];
+        bundle.input_leaf_indices = vec![LeafIndexInfo {
+            leaf_index: 0,
+            leaf: leaves[0],
+            tx_hash,
+        }];
+
+        let nullified = reference_tree(&[nullifier, [0u8; 32]]).root();
+        apply_batch_nullify(&mut bundle, &batch_event(&bundle, 0, 1, nullified)).unwrap();
+        let appended = reference_tree(&[nullifier, leaves[1]]).root();
+        apply_batch_append(&mut bundle, &batch_event(&bundle, 0, 2, appended)).unwrap();
+        assert_eq!(bundle.merkle_tree.leaf(0), nullifier);
+    }
+
+    #[test]
+    fn test_live_leaves() {
+        let mut bundle = new_state_merkle_tree_bundle(&TreeInfo {
+            tree: Pubkey::new_unique(),
+            queue: Pubkey::new_unique(),
+            cpi_context: None,
+            next_tree_info: None,
+            tree_type: TreeType::StateV1,
+        })
+        .unwrap();
+        let queue = bundle.accounts.nullifier_queue;
+        let new_account =
+            |merkle_tree: Pubkey, leaf_index: u32| CompressedAccountWithMerkleContext {
+                compressed_account:
+                    light_compressed_account::compressed_account::CompressedAccount {
+                        owner: Pubkey::new_unique().into(),
+                        lamports: leaf_index as u64,
+                        address: None,
+                        data: None,
+                    },
+                merkle_context: MerkleContext {
+                    merkle_tree_pubkey: merkle_tree.into(),
+                    queue_pubkey: queue.into(),
+                    leaf_index,
+                    prove_by_index: false,
+                    tree_type: TreeType::StateV1,
+                },
+            };
+        let accounts: Vec<_> = (0..3)
+            .map(|leaf_index| new_account(bundle.accounts.merkle_tree, leaf_index))
+            .collect();
+        for account in accounts.iter() {
+            bundle.merkle_tree.append(&account.hash().unwrap()).unwrap();
+        }
+        // Leaf 1 is nullified by a forester or migrated.
+        bundle.merkle_tree.update(&[0u8; 32], 1).unwrap();
+        let other_tree_account = new_account(Pubkey::new_unique(), 0);
+
+        let leaves = live_leaves(&bundle, accounts.iter().rev().chain([&other_tree_account]));
+        assert_eq!(
+            leaves,
+            vec![accounts[0].hash().unwrap(), accounts[2].hash().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_batch_append_detects_divergence() {
+        let mut bundle = state_v2_bundle();
+        bundle.output_queue_elements = vec![([1u8; 32], 0), ([2u8; 32], 1)];
+        let event = batch_event(&bundle, 0, 2, [9u8; 32]);
+        assert!(apply_batch_append(&mut bundle, &event).is_err());
+
+        // Missing queue elements diverge as well.
+        let mut bundle = state_v2_bundle();
+        let event = batch_event(&bundle, 0, 2, [0u8; 32]);
+        assert!(apply_batch_append(&mut bundle, &event).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! Indexer which builds compressed state from RPC transactions alone.
+//!
+//! [`LocalIndexer`] parses the Light events of confirmed transactions with
+//! [`light_event::parse::event_from_light_transaction`], keeps Merkle trees
+//! and compressed accounts in memory and serves them through the
+//! [`Indexer`](super::Indexer) trait. It requires no Photon instance, only a
+//! Solana RPC and, for validity proofs, a prover server.
+//!
+//! The Merkle tree bundles in [`state_tree`] and [`address_tree`] are shared
+//! with the `TestIndexer` of `light-program-test`.
+
+pub mod address_tree;
+pub mod state_tree;
+
+mod local_indexer;
+pub(crate) mod transaction;
+
+pub use local_indexer::{LocalIndexer, LocalIndexerConfig};
@@ -0,0 +1,150 @@
+use borsh::BorshDeserialize;
+use light_compressed_account::Pubkey as LightPubkey;
+use light_event::{event::BatchPublicTransactionEvent, parse::event_from_light_transaction};
+use light_merkle_tree_metadata::events::MerkleTreeEvent;
+use light_sdk_types::constants::{ACCOUNT_COMPRESSION_PROGRAM_ID, NOOP_PROGRAM_ID};
+use solana_message::compiled_instruction::CompiledInstruction;
+use solana_pubkey::Pubkey;
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedTransactionWithStatusMeta, UiInnerInstructions,
+    UiInstruction,
+};
+
+use crate::indexer::IndexerError;
+
+/// Light events emitted by one transaction.
+#[derive(Debug, Default)]
+pub(crate) struct TransactionEvents {
+    /// Compressed accounts and addresses created and nullified by the
+    /// transaction.
+    pub events: Vec<BatchPublicTransactionEvent>,
+    /// Tree updates emitted through the noop program by the account
+    /// compression program, e.g. by foresters.
+    pub tree_events: Vec<MerkleTreeEvent>,
+}
+
+/// Decodes the Light events of a transaction fetched with base64 encoding.
+/// Returns `None` for failed transactions.
+pub(crate) fn decode_transaction(
+    transaction: EncodedTransactionWithStatusMeta,
+) -> Result<Option<TransactionEvents>, IndexerError> {
+    let EncodedTransactionWithStatusMeta {
+        transaction, meta, ..
+    } = transaction;
+    let meta = meta.ok_or_else(|| {
+        IndexerError::CustomError("Transaction missing metadata information".to_string())
+    })?;
+    if meta.status.is_err() {
+        return Ok(None);
+    }
+    let transaction = transaction.decode().ok_or_else(|| {
+        IndexerError::CustomError("Failed to decode transaction from RPC response".to_string())
+    })?;
+
+    // Static account keys followed by the addresses loaded from lookup tables.
+    let mut account_keys = transaction.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for address in loaded.writable.iter().chain(loaded.readonly.iter()) {
+            let pubkey = address
+                .parse::<Pubkey>()
+                .map_err(|e| IndexerError::decode_error("loaded_addresses", e))?;
+            account_keys.push(pubkey);
+        }
+    }
+    let inner_instructions = match &meta.inner_instructions {
+        OptionSerializer::Some(inner) => {
+            compile_inner_instructions(transaction.message.instructions().len(), inner)?
+        }
+        _ => Vec::new(),
+    };
+    decode_instructions(
+        &account_keys,
+        transaction.message.instructions(),
+        &inner_instructions,
+    )
+    .map(Some)
+}
+
+/// Groups inner instructions by the index of their outer instruction.
+pub(crate) fn compile_inner_instructions(
+    num_instructions: usize,
+    inner_instructions: &[UiInnerInstructions],
+) -> Result<Vec<Vec<CompiledInstruction>>, IndexerError> {
+    let mut groups: Vec<Vec<CompiledInstruction>> =
+        (0..num_instructions).map(|_| Vec::new()).collect();
+    for inner in inner_instructions {
+        let Some(group) = groups.get_mut(inner.index as usize) else {
+            continue;
+        };
+        for instruction in inner.instructions.iter() {
+            if let UiInstruction::Compiled(instruction) = instruction {
+                group.push(CompiledInstruction {
+                    program_id_index: instruction.program_id_index,
+                    accounts: instruction.accounts.clone(),
+                    data: bs58::decode(&instruction.data)
+                        .into_vec()
+                        .map_err(|e| IndexerError::decode_error("instruction data", e))?,
+                });
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Decodes the Light events of the instructions of a transaction.
+/// `inner_instructions` holds the inner instructions of each outer
+/// instruction, `account_keys` the static and loaded account keys.
+pub(crate) fn decode_instructions(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    inner_instructions: &[Vec<CompiledInstruction>],
+) -> Result<TransactionEvents, IndexerError> {
+    let account = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .map(|pubkey| LightPubkey::new_from_array(pubkey.to_bytes()))
+            .ok_or_else(|| {
+                IndexerError::CustomError(format!("Account index {} out of bounds", index))
+            })
+    };
+    let accounts = |instruction: &CompiledInstruction| {
+        instruction
+            .accounts
+            .iter()
+            .map(|index| account(*index))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    // Events are parsed per outer instruction together with its inner
+    // instructions.
+    let mut events = TransactionEvents::default();
+    let no_inner_instructions = Vec::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        let inner = inner_instructions.get(i).unwrap_or(&no_inner_instructions);
+        let mut program_ids = Vec::with_capacity(inner.len() + 1);
+        let mut data = Vec::with_capacity(inner.len() + 1);
+        let mut instruction_accounts = Vec::with_capacity(inner.len() + 1);
+        for instruction in std::iter::once(instruction).chain(inner.iter()) {
+            program_ids.push(account(instruction.program_id_index)?);
+            data.push(instruction.data.clone());
+            instruction_accounts.push(accounts(instruction)?);
+        }
+
+        if program_ids.contains(&LightPubkey::new_from_array(ACCOUNT_COMPRESSION_PROGRAM_ID)) {
+            let noop = LightPubkey::new_from_array(NOOP_PROGRAM_ID);
+            events.tree_events.extend(
+                program_ids
+                    .iter()
+                    .zip(data.iter())
+                    .filter(|(program_id, _)| **program_id == noop)
+                    .filter_map(|(_, data)| MerkleTreeEvent::try_from_slice(data).ok()),
+            );
+        }
+        if let Some(batch) = event_from_light_transaction(&program_ids, &data, instruction_accounts)
+            .map_err(|e| IndexerError::CustomError(format!("Failed to parse event: {e:?}")))?
+        {
+            events.events.extend(batch);
+        }
+    }
+    Ok(events)
+}
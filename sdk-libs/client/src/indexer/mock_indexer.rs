@@ -0,0 +1,345 @@
+//! Indexer with scripted responses for unit tests of indexer wrappers.
+
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use solana_pubkey::Pubkey;
+
+use super::{
+    Address, AddressWithTree, CompressedAccount, CompressedTokenAccount, Context,
+    GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash,
+    Indexer, IndexerError, IndexerRpcConfig, Items, ItemsWithCursor, MerkleProof,
+    NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
+    QueueElementsV2Options, QueueInfoResult, Response, RetryConfig, SignatureWithMetadata,
+    TokenBalance, ValidityProofWithContext,
+};
+
+/// Test side of a held request, see [`MockIndexerState::hold_next_request`].
+#[derive(Debug)]
+pub(crate) struct Hold {
+    /// Resolves once the request has read its response.
+    pub entered: oneshot::Receiver<()>,
+    /// Send to let the request return.
+    pub release: oneshot::Sender<()>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MockIndexerState {
+    pub slot: AtomicU64,
+    pub healthy: AtomicBool,
+    /// Requests and health checks fail while set.
+    pub failing: AtomicBool,
+    pub accounts: Mutex<Vec<CompressedAccount>>,
+    pub validity_proof: Mutex<ValidityProofWithContext>,
+    /// Number of requests, health checks excluded.
+    pub requests: AtomicUsize,
+    pub health_checks: AtomicUsize,
+    hold: Mutex<Option<(oneshot::Sender<()>, oneshot::Receiver<()>)>>,
+}
+
+impl MockIndexerState {
+    /// Holds the next request after it has read its response until
+    /// [`Hold::release`] is sent.
+    pub fn hold_next_request(&self) -> Hold {
+        let (entered_sender, entered) = oneshot::channel();
+        let (release, release_receiver) = oneshot::channel();
+        *self.hold.lock().unwrap() = Some((entered_sender, release_receiver));
+        Hold { entered, release }
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    pub fn health_checks(&self) -> usize {
+        self.health_checks.load(Ordering::SeqCst)
+    }
+}
+
+/// Cloning returns a handle to the same state.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MockIndexer(Arc<MockIndexerState>);
+
+impl Deref for MockIndexer {
+    type Target = MockIndexerState;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl MockIndexer {
+    pub fn new(slot: u64) -> Self {
+        let indexer = Self::default();
+        indexer.slot.store(slot, Ordering::SeqCst);
+        indexer.healthy.store(true, Ordering::SeqCst);
+        indexer
+    }
+
+    async fn respond<T>(&self, value: T) -> Result<Response<T>, IndexerError>
+    where
+        T: Clone + PartialEq + Default + std::fmt::Debug,
+    {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(IndexerError::CustomError(
+                "mock indexer failure".to_string(),
+            ));
+        }
+        let hold = self.hold.lock().unwrap().take();
+        if let Some((entered, release)) = hold {
+            let _ = entered.send(());
+            let _ = release.await;
+        }
+        Ok(Response {
+            context: Context {
+                slot: self.slot.load(Ordering::SeqCst),
+            },
+            value,
+        })
+    }
+}
+
+fn not_implemented<T>(method: &str) -> Result<T, IndexerError> {
+    Err(IndexerError::NotImplemented(method.to_string()))
+}
+
+#[async_trait]
+impl Indexer for MockIndexer {
+    async fn get_compressed_account(
+        &self,
+        address: Address,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        let account = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|account| account.address == Some(address))
+            .cloned();
+        self.respond(account).await
+    }
+
+    async fn get_compressed_account_by_hash(
+        &self,
+        hash: Hash,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        let account = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|account| account.hash == hash)
+            .cloned();
+        self.respond(account).await
+    }
+
+    async fn get_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        _options: Option<GetCompressedAccountsByOwnerConfig>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedAccount>>, IndexerError> {
+        let items = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|account| account.owner == *owner)
+            .cloned()
+            .collect();
+        self.respond(ItemsWithCursor {
+            items,
+            cursor: None,
+        })
+        .await
+    }
+
+    async fn get_compressed_balance(
+        &self,
+        _address: Option<Address>,
+        _hash: Option<Hash>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        not_implemented("get_compressed_balance")
+    }
+
+    async fn get_compressed_balance_by_owner(
+        &self,
+        owner: &Pubkey,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        let balance = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|account| account.owner == *owner)
+            .map(|account| account.lamports)
+            .sum();
+        self.respond(balance).await
+    }
+
+    async fn get_compressed_mint_token_holders(
+        &self,
+        _mint: &Pubkey,
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
+        not_implemented("get_compressed_mint_token_holders")
+    }
+
+    async fn get_compressed_token_account_balance(
+        &self,
+        _address: Option<Address>,
+        _hash: Option<Hash>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        not_implemented("get_compressed_token_account_balance")
+    }
+
+    async fn get_compressed_token_accounts_by_delegate(
+        &self,
+        _delegate: &Pubkey,
+        _options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        not_implemented("get_compressed_token_accounts_by_delegate")
+    }
+
+    async fn get_compressed_token_accounts_by_owner(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        not_implemented("get_compressed_token_accounts_by_owner")
+    }
+
+    async fn get_compressed_token_balances_by_owner_v2(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<TokenBalance>>, IndexerError> {
+        not_implemented("get_compressed_token_balances_by_owner_v2")
+    }
+
+    async fn get_compression_signatures_for_account(
+        &self,
+        _hash: Hash,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
+        not_implemented("get_compression_signatures_for_account")
+    }
+
+    async fn get_compression_signatures_for_address(
+        &self,
+        _address: &[u8; 32],
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        not_implemented("get_compression_signatures_for_address")
+    }
+
+    async fn get_compression_signatures_for_owner(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        not_implemented("get_compression_signatures_for_owner")
+    }
+
+    async fn get_compression_signatures_for_token_owner(
+        &self,
+        _owner: &Pubkey,
+        _options: Option<PaginatedOptions>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        not_implemented("get_compression_signatures_for_token_owner")
+    }
+
+    async fn get_indexer_health(&self, _config: Option<RetryConfig>) -> Result<bool, IndexerError> {
+        self.health_checks.fetch_add(1, Ordering::SeqCst);
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(IndexerError::CustomError(
+                "mock indexer failure".to_string(),
+            ));
+        }
+        Ok(self.healthy.load(Ordering::SeqCst))
+    }
+
+    async fn get_indexer_slot(&self, _config: Option<RetryConfig>) -> Result<u64, IndexerError> {
+        Ok(self.slot.load(Ordering::SeqCst))
+    }
+
+    async fn get_multiple_compressed_account_proofs(
+        &self,
+        _hashes: Vec<[u8; 32]>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<MerkleProof>>, IndexerError> {
+        not_implemented("get_multiple_compressed_account_proofs")
+    }
+
+    async fn get_multiple_compressed_accounts(
+        &self,
+        _addresses: Option<Vec<Address>>,
+        _hashes: Option<Vec<Hash>>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<Option<CompressedAccount>>>, IndexerError> {
+        not_implemented("get_multiple_compressed_accounts")
+    }
+
+    async fn get_multiple_new_address_proofs(
+        &self,
+        _merkle_tree_pubkey: [u8; 32],
+        _addresses: Vec<[u8; 32]>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<NewAddressProofWithContext>>, IndexerError> {
+        not_implemented("get_multiple_new_address_proofs")
+    }
+
+    async fn get_validity_proof(
+        &self,
+        _hashes: Vec<Hash>,
+        _new_addresses_with_trees: Vec<AddressWithTree>,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ValidityProofWithContext>, IndexerError> {
+        let proof = self.validity_proof.lock().unwrap().clone();
+        self.respond(proof).await
+    }
+
+    async fn get_queue_elements(
+        &mut self,
+        _merkle_tree_pubkey: [u8; 32],
+        _options: QueueElementsV2Options,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueElementsResult>, IndexerError> {
+        not_implemented("get_queue_elements")
+    }
+
+    async fn get_queue_info(
+        &self,
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueInfoResult>, IndexerError> {
+        not_implemented("get_queue_info")
+    }
+
+    async fn get_subtrees(
+        &self,
+        _merkle_tree_pubkey: [u8; 32],
+        _config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<[u8; 32]>>, IndexerError> {
+        not_implemented("get_subtrees")
+    }
+}
@@ -1,9 +1,15 @@
+pub mod cached_indexer;
+pub mod consistency;
+pub mod local;
+pub mod multi_indexer;
 pub mod photon_indexer;
 
 mod base58;
 mod config;
 mod error;
 mod indexer_trait;
+#[cfg(test)]
+pub(crate) mod mock_indexer;
 mod response;
 pub(crate) mod tree_info;
 mod types;
@@ -13,14 +19,16 @@ pub use config::{IndexerRpcConfig, RetryConfig};
 pub use error::IndexerError;
 pub use indexer_trait::Indexer;
 pub use response::{Context, Items, ItemsWithCursor, Response};
+pub(crate) use types::decode_token_data;
 pub use types::{
     AccountInterface, AccountProofInputs, Address, AddressMerkleTreeAccounts, AddressProofInputs,
     AddressQueueData, AddressWithTree, ColdContext, ColdData, CompressedAccount,
-    CompressedTokenAccount, Hash, InputQueueData, InterfaceTreeInfo, MerkleProof,
-    MerkleProofWithContext, NewAddressProofWithContext, NextTreeInfo, OutputQueueData,
-    OwnerBalance, ProofOfLeaf, QueueElementsResult, QueueInfo, QueueInfoResult, RootIndex,
-    SignatureWithMetadata, SolanaAccountData, StateMerkleTreeAccounts, StateQueueData,
-    TokenAccountInterface, TokenBalance, TreeInfo, ValidityProofWithContext,
+    CompressedAccountEvent, CompressedTokenAccount, Hash, IndexerStream, InputQueueData,
+    InterfaceTreeInfo, MerkleProof, MerkleProofWithContext, NewAddressProofWithContext,
+    NextTreeInfo, OutputQueueData, OwnerBalance, ProofOfLeaf, QueueElementsResult, QueueInfo,
+    QueueInfoResult, RootIndex, SignatureWithMetadata, SolanaAccountData, StateMerkleTreeAccounts,
+    StateQueueData, TokenAccountEvent, TokenAccountInterface, TokenBalance, TreeInfo,
+    ValidityProofWithContext,
 };
 mod options;
 pub use options::*;
@@ -0,0 +1,991 @@
+//! Composite indexer which fans requests out to several indexers.
+//!
+//! [`MultiIndexer`] queries all healthy indexers concurrently, requires a
+//! quorum of indexers to return the same value and returns the agreeing
+//! response with the highest `context.slot`. Indexers which fail a request
+//! or report themselves unhealthy through [`Indexer::get_indexer_health`]
+//! are skipped until the next health check.
+//!
+//! Validity proofs are not compared, Groth16 proofs of the same inputs
+//! differ. They only require a quorum of successful responses and can be
+//! checked against the root history of the on-chain Merkle trees instead.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::future::{join_all, BoxFuture};
+use solana_pubkey::Pubkey;
+use tracing::warn;
+
+use super::{
+    consistency::fetch_onchain_tree_state, Address, AddressWithTree, CompressedAccount,
+    CompressedAccountEvent, CompressedTokenAccount, GetCompressedAccountsByOwnerConfig,
+    GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash, Indexer, IndexerError,
+    IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
+    NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
+    QueueElementsV2Options, QueueInfoResult, Response, RetryConfig, SignatureWithMetadata,
+    TokenAccountEvent, TokenBalance, ValidityProofWithContext,
+};
+use crate::rpc::Rpc;
+
+#[derive(Debug, Clone)]
+pub struct MultiIndexerConfig {
+    /// Minimum number of indexers which must return the same response.
+    /// For validity proofs, the minimum number of successful responses.
+    pub quorum: usize,
+    /// Time after which the health of an indexer is checked again.
+    pub health_check_interval: Duration,
+    /// Retry config passed to `get_indexer_health`.
+    pub health_check_retry_config: RetryConfig,
+}
+
+impl Default for MultiIndexerConfig {
+    fn default() -> Self {
+        Self {
+            quorum: 1,
+            health_check_interval: Duration::from_secs(30),
+            health_check_retry_config: RetryConfig {
+                num_retries: 1,
+                delay_ms: 400,
+                max_delay_ms: 400,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct IndexerStatus {
+    healthy: bool,
+    checked_at: Option<Instant>,
+}
+
+/// Source of on-chain root histories, implemented for every [`Rpc`].
+#[async_trait]
+trait RootHistories: Send + Sync + fmt::Debug {
+    async fn root_history(&self, merkle_tree: Pubkey) -> Result<Vec<[u8; 32]>, IndexerError>;
+}
+
+#[async_trait]
+impl<R: Rpc> RootHistories for R {
+    async fn root_history(&self, merkle_tree: Pubkey) -> Result<Vec<[u8; 32]>, IndexerError> {
+        fetch_onchain_tree_state(self, merkle_tree)
+            .await
+            .map(|state| state.root_history)
+            .map_err(|e| IndexerError::CustomError(e.to_string()))
+    }
+}
+
+pub struct MultiIndexer {
+    indexers: Vec<Box<dyn Indexer>>,
+    config: MultiIndexerConfig,
+    /// Used to verify validity proof roots, verification is skipped if `None`.
+    root_histories: Option<Box<dyn RootHistories>>,
+    statuses: Mutex<Vec<IndexerStatus>>,
+}
+
+impl fmt::Debug for MultiIndexer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiIndexer")
+            .field("indexers", &self.indexers.len())
+            .field("config", &self.config)
+            .field("root_histories", &self.root_histories)
+            .field("statuses", &self.statuses.lock().unwrap())
+            .finish()
+    }
+}
+
+impl MultiIndexer {
+    pub fn new(
+        indexers: Vec<Box<dyn Indexer>>,
+        config: MultiIndexerConfig,
+    ) -> Result<Self, IndexerError> {
+        if indexers.is_empty() {
+            return Err(IndexerError::InvalidParameters(
+                "MultiIndexer requires at least one indexer".to_string(),
+            ));
+        }
+        if config.quorum == 0 || config.quorum > indexers.len() {
+            return Err(IndexerError::InvalidParameters(format!(
+                "Quorum {} must be between 1 and the number of indexers {}",
+                config.quorum,
+                indexers.len()
+            )));
+        }
+        let statuses = Mutex::new(vec![IndexerStatus::default(); indexers.len()]);
+        Ok(Self {
+            indexers,
+            config,
+            root_histories: None,
+            statuses,
+        })
+    }
+
+    /// Verifies the roots of validity proofs against the on-chain root
+    /// history read with `rpc`. Proofs with an unknown root are rejected.
+    pub fn with_root_verification<R: Rpc>(mut self, rpc: R) -> Self {
+        self.root_histories = Some(Box::new(rpc));
+        self
+    }
+
+    pub fn indexers(&self) -> &[Box<dyn Indexer>] {
+        &self.indexers
+    }
+
+    fn set_healthy(&self, index: usize, healthy: bool) {
+        self.statuses.lock().unwrap()[index] = IndexerStatus {
+            healthy,
+            checked_at: Some(Instant::now()),
+        };
+    }
+
+    /// Marks an indexer unhealthy after a failed request. Errors which do
+    /// not indicate a faulty indexer, e.g. missing accounts, are ignored.
+    fn request_failed(&self, index: usize, error: &IndexerError) {
+        warn!("Indexer {} request failed: {}", index, error);
+        if !matches!(
+            error,
+            IndexerError::AccountNotFound
+                | IndexerError::InvalidParameters(_)
+                | IndexerError::NotImplemented(_)
+        ) {
+            self.set_healthy(index, false);
+        }
+    }
+
+    /// Checks the health of indexers whose last check is older than the
+    /// health check interval and returns the indices of healthy indexers.
+    async fn healthy_indexers(&self) -> Vec<usize> {
+        let due: Vec<usize> = {
+            let statuses = self.statuses.lock().unwrap();
+            statuses
+                .iter()
+                .enumerate()
+                .filter(|(_, status)| {
+                    status
+                        .checked_at
+                        .is_none_or(|at| at.elapsed() >= self.config.health_check_interval)
+                })
+                .map(|(index, _)| index)
+                .collect()
+        };
+        let results = join_all(due.iter().map(|index| {
+            self.indexers[*index]
+                .get_indexer_health(Some(self.config.health_check_retry_config.clone()))
+        }))
+        .await;
+        for (index, result) in due.into_iter().zip(results) {
+            let healthy = match result {
+                Ok(healthy) => healthy,
+                Err(e) => {
+                    warn!("Indexer {} health check failed: {}", index, e);
+                    false
+                }
+            };
+            self.set_healthy(index, healthy);
+        }
+        self.statuses
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, status)| status.healthy)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the indices of the indexers to query. Falls back to all
+    /// indexers if none is healthy.
+    async fn available_indexers(&self) -> Vec<usize> {
+        let healthy = self.healthy_indexers().await;
+        if healthy.is_empty() {
+            (0..self.indexers.len()).collect()
+        } else {
+            healthy
+        }
+    }
+
+    /// Sends a request to all available indexers and returns the successful
+    /// responses ordered by descending slot. Indexers which return an error
+    /// are marked unhealthy.
+    async fn fan_out<'a, T, F>(&'a self, call: F) -> Result<Vec<Response<T>>, IndexerError>
+    where
+        T: Clone + PartialEq + Default + fmt::Debug,
+        F: Fn(&'a dyn Indexer) -> BoxFuture<'a, Result<Response<T>, IndexerError>>,
+    {
+        let available = self.available_indexers().await;
+        let results = join_all(
+            available
+                .iter()
+                .map(|index| call(self.indexers[*index].as_ref())),
+        )
+        .await;
+        for (index, result) in available.iter().zip(results.iter()) {
+            if let Err(e) = result {
+                self.request_failed(*index, e);
+            }
+        }
+        select_responses(results, self.config.quorum)
+    }
+
+    /// Returns the response with the highest slot whose value is returned
+    /// by at least `quorum` indexers.
+    async fn query<'a, T, F>(&'a self, call: F) -> Result<Response<T>, IndexerError>
+    where
+        T: Clone + PartialEq + Default + fmt::Debug,
+        F: Fn(&'a dyn Indexer) -> BoxFuture<'a, Result<Response<T>, IndexerError>>,
+    {
+        let responses = self.fan_out(call).await?;
+        agreeing_response(responses, self.config.quorum)
+    }
+
+    /// Returns true if every root referenced by the proof is in the root
+    /// history of its on-chain tree at the referenced root index.
+    async fn verify_roots(
+        root_histories: &dyn RootHistories,
+        proof: &ValidityProofWithContext,
+    ) -> Result<bool, IndexerError> {
+        let roots = referenced_roots(proof);
+        let mut histories = HashMap::new();
+        for (tree, _, _) in roots.iter() {
+            if histories.contains_key(tree) {
+                continue;
+            }
+            histories.insert(*tree, root_histories.root_history(*tree).await?);
+        }
+        Ok(roots_in_history(&roots, &histories))
+    }
+}
+
+fn no_indexer() -> IndexerError {
+    IndexerError::Unknown("No indexer responded".to_string())
+}
+
+/// Requires `quorum` successful responses and orders them by descending slot.
+fn select_responses<T>(
+    results: Vec<Result<Response<T>, IndexerError>>,
+    quorum: usize,
+) -> Result<Vec<Response<T>>, IndexerError>
+where
+    T: Clone + PartialEq + Default + fmt::Debug,
+{
+    let total = results.len();
+    let mut last_error = None;
+    let mut responses = Vec::with_capacity(total);
+    for result in results {
+        match result {
+            Ok(response) => responses.push(response),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    if responses.len() < quorum {
+        return Err(match (responses.is_empty(), last_error) {
+            (true, Some(e)) => e,
+            _ => IndexerError::CustomError(format!(
+                "{} of {} indexers responded, quorum is {}",
+                responses.len(),
+                total,
+                quorum
+            )),
+        });
+    }
+    responses.sort_by(|a, b| b.context.slot.cmp(&a.context.slot));
+    Ok(responses)
+}
+
+/// Returns the first of `responses`, ordered by descending slot, whose
+/// value is shared by at least `quorum` responses.
+fn agreeing_response<T>(
+    responses: Vec<Response<T>>,
+    quorum: usize,
+) -> Result<Response<T>, IndexerError>
+where
+    T: Clone + PartialEq + Default + fmt::Debug,
+{
+    let index = responses.iter().position(|response| {
+        responses
+            .iter()
+            .filter(|other| other.value == response.value)
+            .count()
+            >= quorum
+    });
+    match index {
+        Some(index) => Ok(responses.into_iter().nth(index).unwrap()),
+        None => Err(IndexerError::CustomError(format!(
+            "No {} of {} indexers returned the same response",
+            quorum,
+            responses.len()
+        ))),
+    }
+}
+
+/// Tree, root index and root of every account and address in the proof.
+/// Accounts proven by index do not reference a root.
+fn referenced_roots(proof: &ValidityProofWithContext) -> Vec<(Pubkey, u16, [u8; 32])> {
+    let accounts = proof.accounts.iter().filter_map(|account| {
+        account
+            .root_index
+            .root_index()
+            .map(|root_index| (account.tree_info.tree, root_index, account.root))
+    });
+    let addresses = proof
+        .addresses
+        .iter()
+        .map(|address| (address.tree_info.tree, address.root_index, address.root));
+    accounts.chain(addresses).collect()
+}
+
+fn roots_in_history(
+    roots: &[(Pubkey, u16, [u8; 32])],
+    histories: &HashMap<Pubkey, Vec<[u8; 32]>>,
+) -> bool {
+    roots.iter().all(|(tree, root_index, root)| {
+        histories
+            .get(tree)
+            .and_then(|history| history.get(*root_index as usize))
+            == Some(root)
+    })
+}
+
+#[async_trait]
+impl Indexer for MultiIndexer {
+    async fn get_compressed_account(
+        &self,
+        address: Address,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        self.query(|indexer| indexer.get_compressed_account(address, config.clone()))
+            .await
+    }
+
+    async fn get_compressed_account_by_hash(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Option<CompressedAccount>>, IndexerError> {
+        self.query(|indexer| indexer.get_compressed_account_by_hash(hash, config.clone()))
+            .await
+    }
+
+    async fn get_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedAccountsByOwnerConfig>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedAccount>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_accounts_by_owner(owner, options.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_compressed_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.query(|indexer| indexer.get_compressed_balance(address, hash, config.clone()))
+            .await
+    }
+
+    async fn get_compressed_balance_by_owner(
+        &self,
+        owner: &Pubkey,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.query(|indexer| indexer.get_compressed_balance_by_owner(owner, config.clone()))
+            .await
+    }
+
+    async fn get_compressed_mint_token_holders(
+        &self,
+        mint: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_mint_token_holders(mint, options.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_compressed_token_account_balance(
+        &self,
+        address: Option<Address>,
+        hash: Option<Hash>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<u64>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_token_account_balance(address, hash, config.clone())
+        })
+        .await
+    }
+
+    async fn get_compressed_token_accounts_by_delegate(
+        &self,
+        delegate: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_token_accounts_by_delegate(
+                delegate,
+                options.clone(),
+                config.clone(),
+            )
+        })
+        .await
+    }
+
+    async fn get_compressed_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_token_accounts_by_owner(owner, options.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_compressed_token_balances_by_owner_v2(
+        &self,
+        owner: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<TokenBalance>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compressed_token_balances_by_owner_v2(
+                owner,
+                options.clone(),
+                config.clone(),
+            )
+        })
+        .await
+    }
+
+    async fn get_compression_signatures_for_account(
+        &self,
+        hash: Hash,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
+        self.query(|indexer| indexer.get_compression_signatures_for_account(hash, config.clone()))
+            .await
+    }
+
+    async fn get_compression_signatures_for_address(
+        &self,
+        address: &[u8; 32],
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compression_signatures_for_address(address, options.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_compression_signatures_for_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compression_signatures_for_owner(owner, options.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_compression_signatures_for_token_owner(
+        &self,
+        owner: &Pubkey,
+        options: Option<PaginatedOptions>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_compression_signatures_for_token_owner(
+                owner,
+                options.clone(),
+                config.clone(),
+            )
+        })
+        .await
+    }
+
+    /// Returns true if at least a quorum of indexers is healthy.
+    async fn get_indexer_health(&self, _config: Option<RetryConfig>) -> Result<bool, IndexerError> {
+        Ok(self.healthy_indexers().await.len() >= self.config.quorum)
+    }
+
+    /// Returns the highest slot of the available indexers.
+    async fn get_indexer_slot(&self, config: Option<RetryConfig>) -> Result<u64, IndexerError> {
+        let available = self.available_indexers().await;
+        let results = join_all(
+            available
+                .iter()
+                .map(|index| self.indexers[*index].get_indexer_slot(config.clone())),
+        )
+        .await;
+        let mut last_error = None;
+        let mut slot = None;
+        for (index, result) in available.into_iter().zip(results) {
+            match result {
+                Ok(indexer_slot) => slot = slot.max(Some(indexer_slot)),
+                Err(e) => {
+                    self.request_failed(index, &e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        slot.ok_or_else(|| last_error.unwrap_or_else(no_indexer))
+    }
+
+    async fn get_multiple_compressed_account_proofs(
+        &self,
+        hashes: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<MerkleProof>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_multiple_compressed_account_proofs(hashes.clone(), config.clone())
+        })
+        .await
+    }
+
+    async fn get_multiple_compressed_accounts(
+        &self,
+        addresses: Option<Vec<Address>>,
+        hashes: Option<Vec<Hash>>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<Option<CompressedAccount>>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_multiple_compressed_accounts(
+                addresses.clone(),
+                hashes.clone(),
+                config.clone(),
+            )
+        })
+        .await
+    }
+
+    async fn get_multiple_new_address_proofs(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        addresses: Vec<[u8; 32]>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<NewAddressProofWithContext>>, IndexerError> {
+        self.query(|indexer| {
+            indexer.get_multiple_new_address_proofs(
+                merkle_tree_pubkey,
+                addresses.clone(),
+                config.clone(),
+            )
+        })
+        .await
+    }
+
+    /// Returns the proof with the highest slot whose roots are in the
+    /// on-chain root history. Roots are only verified if an rpc has been
+    /// set with [`MultiIndexer::with_root_verification`].
+    async fn get_validity_proof(
+        &self,
+        hashes: Vec<Hash>,
+        new_addresses_with_trees: Vec<AddressWithTree>,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<ValidityProofWithContext>, IndexerError> {
+        let responses = self
+            .fan_out(|indexer| {
+                indexer.get_validity_proof(
+                    hashes.clone(),
+                    new_addresses_with_trees.clone(),
+                    config.clone(),
+                )
+            })
+            .await?;
+        let Some(root_histories) = self.root_histories.as_deref() else {
+            return Ok(responses.into_iter().next().unwrap());
+        };
+        let mut last_error = None;
+        for response in responses {
+            match Self::verify_roots(root_histories, &response.value).await {
+                Ok(true) => return Ok(response),
+                Ok(false) => {
+                    warn!(
+                        "Discarding validity proof of slot {}, roots are not in the on-chain root history",
+                        response.context.slot
+                    );
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or(IndexerError::InvalidResponseData))
+    }
+
+    /// Requests queue elements from one indexer at a time and fails over to
+    /// the next available indexer on error.
+    async fn get_queue_elements(
+        &mut self,
+        merkle_tree_pubkey: [u8; 32],
+        options: QueueElementsV2Options,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueElementsResult>, IndexerError> {
+        let mut last_error = None;
+        for index in self.available_indexers().await {
+            match self.indexers[index]
+                .get_queue_elements(merkle_tree_pubkey, options.clone(), config.clone())
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    self.request_failed(index, &e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(no_indexer))
+    }
+
+    async fn get_queue_info(
+        &self,
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<QueueInfoResult>, IndexerError> {
+        self.query(|indexer| indexer.get_queue_info(config.clone()))
+            .await
+    }
+
+    async fn get_subtrees(
+        &self,
+        merkle_tree_pubkey: [u8; 32],
+        config: Option<IndexerRpcConfig>,
+    ) -> Result<Response<Items<[u8; 32]>>, IndexerError> {
+        self.query(|indexer| indexer.get_subtrees(merkle_tree_pubkey, config.clone()))
+            .await
+    }
+
+    /// Subscribes through the first available indexer which accepts the
+    /// subscription.
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        let mut last_error = None;
+        for index in self.available_indexers().await {
+            match self.indexers[index]
+                .subscribe_compressed_accounts_by_owner(owner)
+                .await
+            {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    warn!("Indexer {} subscription failed: {}", index, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(no_indexer))
+    }
+
+    /// Subscribes through the first available indexer which accepts the
+    /// subscription.
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        let mut last_error = None;
+        for index in self.available_indexers().await {
+            match self.indexers[index]
+                .subscribe_token_accounts_by_owner(owner, mint)
+                .await
+            {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    warn!("Indexer {} subscription failed: {}", index, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(no_indexer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::indexer::{
+        mock_indexer::MockIndexer, AccountProofInputs, Context, RootIndex, TreeInfo,
+    };
+
+    fn response(slot: u64, value: u64) -> Result<Response<u64>, IndexerError> {
+        Ok(Response {
+            context: Context { slot },
+            value,
+        })
+    }
+
+    #[test]
+    fn test_select_responses_orders_by_slot() {
+        let results = vec![
+            response(10, 1),
+            Err(IndexerError::AccountNotFound),
+            response(12, 2),
+            response(11, 3),
+        ];
+        let responses = select_responses(results, 2).unwrap();
+        assert_eq!(
+            responses.iter().map(|r| r.value).collect::<Vec<_>>(),
+            vec![2, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_select_responses_quorum() {
+        let results = vec![response(10, 1), Err(IndexerError::AccountNotFound)];
+        assert!(matches!(
+            select_responses(results, 2),
+            Err(IndexerError::CustomError(_))
+        ));
+
+        let results: Vec<Result<Response<u64>, IndexerError>> =
+            vec![Err(IndexerError::AccountNotFound)];
+        assert!(matches!(
+            select_responses(results, 1),
+            Err(IndexerError::AccountNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_agreeing_response() {
+        let responses = |values: &[(u64, u64)]| {
+            values
+                .iter()
+                .map(|(slot, value)| response(*slot, *value).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // The highest slot response is returned if the quorum agrees on it.
+        let agreed = agreeing_response(responses(&[(12, 1), (11, 1), (10, 2)]), 2).unwrap();
+        assert_eq!((agreed.context.slot, agreed.value), (12, 1));
+        // Otherwise the highest slot response the quorum agrees on.
+        let agreed = agreeing_response(responses(&[(12, 1), (11, 2), (10, 2)]), 2).unwrap();
+        assert_eq!((agreed.context.slot, agreed.value), (11, 2));
+        assert!(matches!(
+            agreeing_response(responses(&[(12, 1), (11, 2), (10, 3)]), 2),
+            Err(IndexerError::CustomError(_))
+        ));
+    }
+
+    fn multi_indexer(indexers: &[&MockIndexer], config: MultiIndexerConfig) -> MultiIndexer {
+        let indexers = indexers
+            .iter()
+            .map(|indexer| Box::new((*indexer).clone()) as Box<dyn Indexer>)
+            .collect();
+        MultiIndexer::new(indexers, config).unwrap()
+    }
+
+    fn compressed_account(owner: Pubkey, lamports: u64) -> CompressedAccount {
+        CompressedAccount {
+            owner,
+            lamports,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_indexer_quorum() {
+        let owner = Pubkey::new_unique();
+        let a = MockIndexer::new(10);
+        let b = MockIndexer::new(11);
+        a.accounts
+            .lock()
+            .unwrap()
+            .push(compressed_account(owner, 100));
+        b.accounts
+            .lock()
+            .unwrap()
+            .push(compressed_account(owner, 200));
+        let indexer = multi_indexer(
+            &[&a, &b],
+            MultiIndexerConfig {
+                quorum: 2,
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(
+            indexer.get_compressed_balance_by_owner(&owner, None).await,
+            Err(IndexerError::CustomError(_))
+        ));
+
+        a.accounts.lock().unwrap()[0].lamports = 200;
+        let response = indexer
+            .get_compressed_balance_by_owner(&owner, None)
+            .await
+            .unwrap();
+        assert_eq!(response.value, 200);
+        assert_eq!(response.context.slot, 11);
+    }
+
+    #[tokio::test]
+    async fn test_multi_indexer_failover() {
+        let owner = Pubkey::new_unique();
+        let a = MockIndexer::new(12);
+        let b = MockIndexer::new(10);
+        for indexer in [&a, &b] {
+            indexer
+                .accounts
+                .lock()
+                .unwrap()
+                .push(compressed_account(owner, 100));
+        }
+        let indexer = multi_indexer(&[&a, &b], MultiIndexerConfig::default());
+
+        let response = indexer
+            .get_compressed_balance_by_owner(&owner, None)
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 12);
+
+        // The failing indexer is marked unhealthy and skipped until the
+        // next health check.
+        a.failing.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            let response = indexer
+                .get_compressed_balance_by_owner(&owner, None)
+                .await
+                .unwrap();
+            assert_eq!((response.context.slot, response.value), (10, 100));
+        }
+        assert_eq!(a.requests(), 2);
+        assert_eq!(b.requests(), 3);
+        assert_eq!(a.health_checks(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_multi_indexer_health_tracking() {
+        let owner = Pubkey::new_unique();
+        let a = MockIndexer::new(10);
+        let b = MockIndexer::new(10);
+        let indexer = multi_indexer(
+            &[&a, &b],
+            MultiIndexerConfig {
+                health_check_interval: Duration::ZERO,
+                ..Default::default()
+            },
+        );
+
+        // Unhealthy indexers are not queried.
+        a.healthy.store(false, Ordering::SeqCst);
+        indexer
+            .get_compressed_balance_by_owner(&owner, None)
+            .await
+            .unwrap();
+        assert_eq!((a.requests(), b.requests()), (0, 1));
+
+        // Recovered indexers are queried after their next health check.
+        a.healthy.store(true, Ordering::SeqCst);
+        indexer
+            .get_compressed_balance_by_owner(&owner, None)
+            .await
+            .unwrap();
+        assert_eq!((a.requests(), b.requests()), (1, 2));
+        assert_eq!((a.health_checks(), b.health_checks()), (2, 2));
+
+        // All indexers are queried if none is healthy.
+        a.healthy.store(false, Ordering::SeqCst);
+        b.healthy.store(false, Ordering::SeqCst);
+        indexer
+            .get_compressed_balance_by_owner(&owner, None)
+            .await
+            .unwrap();
+        assert_eq!((a.requests(), b.requests()), (2, 3));
+    }
+
+    #[derive(Debug)]
+    struct TestRootHistories(HashMap<Pubkey, Vec<[u8; 32]>>);
+
+    #[async_trait]
+    impl RootHistories for TestRootHistories {
+        async fn root_history(&self, merkle_tree: Pubkey) -> Result<Vec<[u8; 32]>, IndexerError> {
+            self.0
+                .get(&merkle_tree)
+                .cloned()
+                .ok_or(IndexerError::AccountNotFound)
+        }
+    }
+
+    fn validity_proof(tree: Pubkey, root: [u8; 32]) -> ValidityProofWithContext {
+        ValidityProofWithContext {
+            accounts: vec![AccountProofInputs {
+                root,
+                root_index: RootIndex::new_some(1),
+                tree_info: TreeInfo {
+                    tree,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_indexer_root_verification() {
+        let tree = Pubkey::new_unique();
+        let a = MockIndexer::new(12);
+        let b = MockIndexer::new(10);
+        *a.validity_proof.lock().unwrap() = validity_proof(tree, [3u8; 32]);
+        *b.validity_proof.lock().unwrap() = validity_proof(tree, [2u8; 32]);
+        let mut indexer = multi_indexer(&[&a, &b], MultiIndexerConfig::default());
+
+        // Without root verification the highest slot proof is returned.
+        let response = indexer
+            .get_validity_proof(vec![], vec![], None)
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 12);
+
+        // The proof of slot 12 references a root unknown on chain.
+        indexer.root_histories = Some(Box::new(TestRootHistories(HashMap::from([(
+            tree,
+            vec![[1u8; 32], [2u8; 32]],
+        )]))));
+        let response = indexer
+            .get_validity_proof(vec![], vec![], None)
+            .await
+            .unwrap();
+        assert_eq!(response.context.slot, 10);
+        assert_eq!(response.value.accounts[0].root, [2u8; 32]);
+
+        *b.validity_proof.lock().unwrap() = validity_proof(tree, [3u8; 32]);
+        assert!(matches!(
+            indexer.get_validity_proof(vec![], vec![], None).await,
+            Err(IndexerError::InvalidResponseData)
+        ));
+    }
+
+    #[test]
+    fn test_roots_in_history() {
+        let tree = Pubkey::new_unique();
+        let histories = HashMap::from([(tree, vec![[1u8; 32], [2u8; 32], [3u8; 32]])]);
+
+        assert!(roots_in_history(&[(tree, 1, [2u8; 32])], &histories));
+        assert!(roots_in_history(&[], &histories));
+        // Root exists but at a different index.
+        assert!(!roots_in_history(&[(tree, 0, [2u8; 32])], &histories));
+        // Root index out of bounds.
+        assert!(!roots_in_history(&[(tree, 3, [3u8; 32])], &histories));
+        // Unknown tree.
+        assert!(!roots_in_history(
+            &[(Pubkey::new_unique(), 0, [1u8; 32])],
+            &histories
+        ));
+    }
+}
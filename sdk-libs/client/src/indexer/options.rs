@@ -22,11 +22,13 @@ impl GetCompressedTokenAccountsByOwnerOrDelegateOptions {
 /// **Cursor** is a unique identifier for a page of results by which the next page can be fetched.
 ///
 /// **Limit** is the maximum number of results to return per page.
+#[derive(Debug, Clone)]
 pub struct PaginatedOptions {
     pub cursor: Option<String>,
     pub limit: Option<u16>,
 }
 
+#[derive(Debug, Clone)]
 pub struct GetCompressedAccountsByOwnerConfig {
     pub filters: Option<Vec<GetCompressedAccountsFilter>>,
     pub data_slice: Option<UiDataSliceConfig>,
@@ -34,7 +36,7 @@ pub struct GetCompressedAccountsByOwnerConfig {
     pub limit: Option<u16>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct GetCompressedAccountsFilter {
     pub bytes: Vec<u8>,
     pub offset: u32,
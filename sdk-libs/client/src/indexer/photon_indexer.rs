@@ -1,4 +1,4 @@
-use std::{fmt::Debug, time::Duration};
+use std::{collections::BTreeMap, fmt::Debug, time::Duration};
 
 use async_trait::async_trait;
 use bs58;
@@ -8,8 +8,9 @@ use solana_pubkey::Pubkey;
 use tracing::{error, trace, warn};
 
 use super::types::{
-    AccountInterface, CompressedAccount, CompressedTokenAccount, OwnerBalance,
-    SignatureWithMetadata, TokenAccountInterface, TokenBalance,
+    AccountInterface, CompressedAccount, CompressedAccountEvent, CompressedTokenAccount,
+    IndexerStream, OwnerBalance, SignatureWithMetadata, TokenAccountEvent, TokenAccountInterface,
+    TokenBalance,
 };
 use crate::indexer::{
     base58::Base58Conversions,
@@ -20,9 +21,14 @@ use crate::indexer::{
     IndexerRpcConfig, MerkleProof, NewAddressProofWithContext, PaginatedOptions,
 };
 
+/// Default interval between two polls of a subscription.
+pub const DEFAULT_SUBSCRIPTION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 // Tests are in program-tests/client-test/tests/light-client.rs
+#[derive(Clone)]
 pub struct PhotonIndexer {
     configuration: Configuration,
+    poll_interval: Duration,
 }
 
 impl PhotonIndexer {
@@ -109,11 +115,17 @@ impl PhotonIndexer {
 impl PhotonIndexer {
     pub fn new(url: String) -> Self {
         let configuration = Configuration::new(url);
-        PhotonIndexer { configuration }
+        PhotonIndexer {
+            configuration,
+            poll_interval: DEFAULT_SUBSCRIPTION_POLL_INTERVAL,
+        }
     }
 
     pub fn new_with_config(configuration: Configuration) -> Self {
-        PhotonIndexer { configuration }
+        PhotonIndexer {
+            configuration,
+            poll_interval: DEFAULT_SUBSCRIPTION_POLL_INTERVAL,
+        }
     }
 
     fn extract_result<T>(context: &str, result: Option<T>) -> Result<T, IndexerError> {
@@ -166,6 +178,7 @@ impl Debug for PhotonIndexer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PhotonIndexer")
             .field("configuration", &self.configuration)
+            .field("poll_interval", &self.poll_interval)
             .finish()
     }
 }
@@ -1721,6 +1734,45 @@ impl Indexer for PhotonIndexer {
             solana_pubkey::Pubkey::new_from_array(merkle_tree_pubkey)
         )))
     }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        let owner = *owner;
+        let (_, known) = self.fetch_accounts_by_owner(owner).await?;
+        Ok(self.poll(
+            known,
+            move |indexer| async move { indexer.fetch_accounts_by_owner(owner).await },
+            |account, nullified, slot| {
+                if nullified {
+                    CompressedAccountEvent::Nullified { account, slot }
+                } else {
+                    CompressedAccountEvent::Created { account, slot }
+                }
+            },
+        ))
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        let owner = *owner;
+        let (_, known) = self.fetch_token_accounts_by_owner(owner, mint).await?;
+        Ok(self.poll(
+            known,
+            move |indexer| async move { indexer.fetch_token_accounts_by_owner(owner, mint).await },
+            |account, nullified, slot| {
+                if nullified {
+                    TokenAccountEvent::Nullified { account, slot }
+                } else {
+                    TokenAccountEvent::Created { account, slot }
+                }
+            },
+        ))
+    }
 }
 
 // ============ Interface Methods ============
@@ -1862,6 +1914,132 @@ impl PhotonIndexer {
     }
 }
 
+// ============ Subscriptions ============
+// Photon has no pubsub endpoint for compressed accounts yet. Subscriptions
+// poll the owner's accounts every `poll_interval` and diff consecutive
+// snapshots by hash. Accounts created and spent between two polls are not
+// reported, and events carry the slot of the poll that observed them.
+impl PhotonIndexer {
+    /// Interval between two polls of a subscription, see
+    /// [`DEFAULT_SUBSCRIPTION_POLL_INTERVAL`].
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    async fn fetch_accounts_by_owner(
+        &self,
+        owner: Pubkey,
+    ) -> Result<(u64, BTreeMap<Hash, CompressedAccount>), IndexerError> {
+        let mut accounts = BTreeMap::new();
+        let mut cursor = None;
+        loop {
+            let options = GetCompressedAccountsByOwnerConfig {
+                filters: None,
+                data_slice: None,
+                cursor,
+                limit: None,
+            };
+            let response = self
+                .get_compressed_accounts_by_owner(&owner, Some(options), None)
+                .await?;
+            accounts.extend(response.value.items.into_iter().map(|x| (x.hash, x)));
+            cursor = response.value.cursor;
+            if cursor.is_none() {
+                return Ok((response.context.slot, accounts));
+            }
+        }
+    }
+
+    async fn fetch_token_accounts_by_owner(
+        &self,
+        owner: Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<(u64, BTreeMap<Hash, CompressedTokenAccount>), IndexerError> {
+        let mut accounts = BTreeMap::new();
+        let mut cursor = None;
+        loop {
+            let options = GetCompressedTokenAccountsByOwnerOrDelegateOptions {
+                mint,
+                cursor,
+                limit: None,
+            };
+            let response = self
+                .get_compressed_token_accounts_by_owner(&owner, Some(options), None)
+                .await?;
+            accounts.extend(
+                response
+                    .value
+                    .items
+                    .into_iter()
+                    .map(|x| (x.account.hash, x)),
+            );
+            cursor = response.value.cursor;
+            if cursor.is_none() {
+                return Ok((response.context.slot, accounts));
+            }
+        }
+    }
+
+    /// Polls `fetch` starting from the `known` accounts and yields the
+    /// differences as events.
+    fn poll<T, E, F, Fut>(
+        &self,
+        known: BTreeMap<Hash, T>,
+        fetch: F,
+        to_event: fn(T, bool, u64) -> E,
+    ) -> IndexerStream<E>
+    where
+        T: Clone + Send + Sync + 'static,
+        E: Send + 'static,
+        F: Fn(PhotonIndexer) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<(u64, BTreeMap<Hash, T>), IndexerError>>
+            + Send
+            + 'static,
+    {
+        use futures::StreamExt;
+
+        let indexer = self.clone();
+        futures::stream::unfold(
+            (indexer, known, fetch),
+            move |(indexer, known, fetch)| async move {
+                tokio::time::sleep(indexer.poll_interval).await;
+                let (events, known) = match fetch(indexer.clone()).await {
+                    Ok((slot, current)) => {
+                        let events = diff_accounts(&known, &current, slot, to_event)
+                            .into_iter()
+                            .map(Ok)
+                            .collect();
+                        (events, current)
+                    }
+                    Err(error) => (vec![Err(error)], known),
+                };
+                Some((futures::stream::iter(events), (indexer, known, fetch)))
+            },
+        )
+        .flatten()
+        .boxed()
+    }
+}
+
+/// Events turning `known` into `current`, nullifications first.
+fn diff_accounts<T: Clone, E>(
+    known: &BTreeMap<Hash, T>,
+    current: &BTreeMap<Hash, T>,
+    slot: u64,
+    to_event: fn(T, bool, u64) -> E,
+) -> Vec<E> {
+    let nullified = known
+        .iter()
+        .filter(|(hash, _)| !current.contains_key(*hash))
+        .map(|(_, account)| to_event(account.clone(), true, slot));
+    let created = current
+        .iter()
+        .filter(|(hash, _)| !known.contains_key(*hash))
+        .map(|(_, account)| to_event(account.clone(), false, slot));
+    nullified.chain(created).collect()
+}
+
 /// Parse token data from an indexer AccountInterface.
 /// For compressed (cold) accounts: borsh-deserializes TokenData from the cold data bytes.
 /// For on-chain (hot) accounts: returns default TokenData (downstream conversion re-parses from SPL layout).
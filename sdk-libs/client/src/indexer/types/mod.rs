@@ -3,6 +3,7 @@ mod interface;
 mod proof;
 mod queue;
 mod signature;
+mod subscription;
 mod token;
 mod tree;
 
@@ -19,6 +20,8 @@ pub use queue::{
     AddressQueueData, InputQueueData, OutputQueueData, QueueElementsResult, StateQueueData,
 };
 pub use signature::SignatureWithMetadata;
+pub use subscription::{CompressedAccountEvent, IndexerStream, TokenAccountEvent};
+pub(crate) use token::decode_token_data;
 pub use token::{CompressedTokenAccount, OwnerBalance, TokenBalance};
 pub use tree::{AddressMerkleTreeAccounts, NextTreeInfo, StateMerkleTreeAccounts, TreeInfo};
 
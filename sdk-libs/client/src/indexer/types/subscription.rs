@@ -0,0 +1,69 @@
+use futures::stream::BoxStream;
+
+use super::{CompressedAccount, CompressedTokenAccount};
+use crate::indexer::IndexerError;
+
+/// Stream returned by indexer subscriptions.
+///
+/// The subscription ends when the stream is dropped.
+pub type IndexerStream<T> = BoxStream<'static, Result<T, IndexerError>>;
+
+/// Change of a compressed account delivered by
+/// [`Indexer::subscribe_compressed_accounts_by_owner`](crate::indexer::Indexer::subscribe_compressed_accounts_by_owner).
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompressedAccountEvent {
+    /// The account was created as output of a transaction.
+    Created {
+        account: CompressedAccount,
+        slot: u64,
+    },
+    /// The account was spent as input of a transaction.
+    Nullified {
+        account: CompressedAccount,
+        slot: u64,
+    },
+}
+
+impl CompressedAccountEvent {
+    pub fn account(&self) -> &CompressedAccount {
+        match self {
+            Self::Created { account, .. } | Self::Nullified { account, .. } => account,
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        match self {
+            Self::Created { slot, .. } | Self::Nullified { slot, .. } => *slot,
+        }
+    }
+}
+
+/// Change of a compressed token account delivered by
+/// [`Indexer::subscribe_token_accounts_by_owner`](crate::indexer::Indexer::subscribe_token_accounts_by_owner).
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenAccountEvent {
+    /// The token account was created as output of a transaction.
+    Created {
+        account: CompressedTokenAccount,
+        slot: u64,
+    },
+    /// The token account was spent as input of a transaction.
+    Nullified {
+        account: CompressedTokenAccount,
+        slot: u64,
+    },
+}
+
+impl TokenAccountEvent {
+    pub fn account(&self) -> &CompressedTokenAccount {
+        match self {
+            Self::Created { account, .. } | Self::Nullified { account, .. } => account,
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        match self {
+            Self::Created { slot, .. } | Self::Nullified { slot, .. } => *slot,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use borsh::BorshDeserialize;
-use light_compressed_account::compressed_account::CompressedAccountWithMerkleContext;
+use light_compressed_account::compressed_account::{
+    CompressedAccountData, CompressedAccountWithMerkleContext,
+};
+use light_sdk_types::constants::LIGHT_TOKEN_PROGRAM_ID;
 use light_token::compat::{AccountState, TokenData};
 use light_token_interface::state::ExtensionStruct;
 use solana_pubkey::Pubkey;
@@ -17,6 +20,26 @@ pub struct CompressedTokenAccount {
     pub account: CompressedAccount,
 }
 
+/// Discriminators of compressed token accounts (v1, v2 and sha flat).
+const TOKEN_ACCOUNT_DISCRIMINATORS: [[u8; 8]; 3] = [
+    [2, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 3],
+    [0, 0, 0, 0, 0, 0, 0, 4],
+];
+
+/// Decodes the token data of a compressed account owned by the compressed
+/// token program. Returns `None` for other accounts.
+pub(crate) fn decode_token_data(
+    owner: [u8; 32],
+    data: Option<&CompressedAccountData>,
+) -> Option<TokenData> {
+    if owner != LIGHT_TOKEN_PROGRAM_ID {
+        return None;
+    }
+    let data = data.filter(|data| TOKEN_ACCOUNT_DISCRIMINATORS.contains(&data.discriminator))?;
+    TokenData::deserialize(&mut data.data.as_slice()).ok()
+}
+
 fn parse_token_data(td: &photon_api::types::TokenData) -> Result<TokenData, IndexerError> {
     Ok(TokenData {
         mint: Pubkey::new_from_array(decode_base58_to_fixed_array(&td.mint)?),
@@ -137,7 +137,7 @@ fn estimate_tx_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
 }
 
 #[inline]
-fn compact_len(val: usize) -> usize {
+pub(crate) fn compact_len(val: usize) -> usize {
     if val < 0x80 {
         1
     } else if val < 0x4000 {
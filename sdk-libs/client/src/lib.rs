@@ -14,6 +14,7 @@
 //! - Query compressed accounts and validity proofs from RPC endpoints
 //! - Support for both v1 and v2 merkle trees (with v2 feature)
 //! - Start local test validator with Light Protocol programs
+//! - Migrate compressed accounts from v1 to v2 state trees
 //!
 //! ## Prerequisites
 //!
@@ -92,6 +93,7 @@ pub mod fee;
 pub mod indexer;
 pub mod interface;
 pub mod local_test_validator;
+pub mod migration;
 pub mod rpc;
 
 pub use light_prover_client;
@@ -0,0 +1,284 @@
+//! Migration of compressed accounts from v1 state trees to v2 state trees.
+//!
+//! The registry program's `migrate_state` instruction nullifies a leaf in a v1
+//! state Merkle tree and inserts it into the output queue of a v2 state tree.
+//! Only the forester eligible for the v1 tree in the current light slot can
+//! migrate its leaves, instructions are created for the forester's authority.
+//!
+//! Leaves are selected either by owner, from any [`Indexer`], or for a whole
+//! tree from a [`LocalIndexer`] which replays the tree's transactions and
+//! therefore knows every live leaf regardless of its owner.
+//!
+//! Each instruction migrates one leaf. Proofs fetched together stay valid
+//! while the tree changelog covers the updates in between, send instructions
+//! in order and refetch proofs for more than `STATE_MERKLE_TREE_CHANGELOG` leaves.
+
+use borsh::BorshSerialize;
+use light_compressed_account::{
+    constants::{
+        ACCOUNT_COMPRESSION_PROGRAM_ID, CPI_AUTHORITY_PDA_SEED, LIGHT_REGISTRY_PROGRAM_ID,
+    },
+    TreeType,
+};
+use light_sdk_types::constants::{
+    NOOP_PROGRAM_ID, STATE_MERKLE_TREE_CANOPY_DEPTH, STATE_MERKLE_TREE_CHANGELOG,
+    STATE_MERKLE_TREE_HEIGHT,
+};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::indexer::{
+    local::LocalIndexer, CompressedAccount, GetCompressedAccountsByOwnerConfig, Indexer,
+    IndexerError, MerkleProof,
+};
+
+/// Anchor discriminator of the registry `migrate_state` instruction.
+/// SHA256("global:migrate_state")[..8]
+pub const MIGRATE_STATE_DISCRIMINATOR: [u8; 8] = [34, 189, 226, 222, 218, 156, 19, 213];
+/// Number of proof nodes below the canopy of v1 state trees.
+pub const MIGRATE_STATE_PROOF_LEN: usize =
+    STATE_MERKLE_TREE_HEIGHT - STATE_MERKLE_TREE_CANOPY_DEPTH;
+
+const FORESTER_SEED: &[u8] = b"forester";
+const FORESTER_EPOCH_SEED: &[u8] = b"forester_epoch";
+
+/// Instruction data of account compression `MigrateLeafParams`.
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub struct MigrateLeafParams {
+    pub change_log_index: u64,
+    pub leaf: [u8; 32],
+    pub leaf_index: u64,
+    pub proof: [[u8; 32]; MIGRATE_STATE_PROOF_LEN],
+}
+
+impl TryFrom<&MerkleProof> for MigrateLeafParams {
+    type Error = IndexerError;
+
+    /// Full proofs are truncated to the nodes below the canopy.
+    fn try_from(proof: &MerkleProof) -> Result<Self, Self::Error> {
+        let nodes = proof.proof.get(..MIGRATE_STATE_PROOF_LEN).ok_or_else(|| {
+            IndexerError::InvalidParameters(format!(
+                "Merkle proof length ({}) is less than {}",
+                proof.proof.len(),
+                MIGRATE_STATE_PROOF_LEN
+            ))
+        })?;
+        Ok(Self {
+            change_log_index: proof.root_seq % STATE_MERKLE_TREE_CHANGELOG as u64,
+            leaf: proof.hash,
+            leaf_index: proof.leaf_index,
+            proof: nodes.try_into().unwrap(),
+        })
+    }
+}
+
+/// Forester and destination of migrations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrateStateConfig {
+    /// Signer, forester authority.
+    pub authority: Pubkey,
+    /// Derivation of the forester pda.
+    pub derivation: Pubkey,
+    /// Epoch the forester is registered for.
+    pub epoch: u64,
+    /// Output queue of the v2 state tree leaves are migrated to.
+    pub output_queue: Pubkey,
+}
+
+/// Migrate state instructions for a page of leaves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrateStateBatch {
+    /// One instruction per leaf, in the order of `hashes`.
+    pub instructions: Vec<Instruction>,
+    /// Hashes of the migrated leaves.
+    pub hashes: Vec<[u8; 32]>,
+    /// Cursor of the next page of an owner's accounts, `None` after the last
+    /// page and for tree migrations.
+    pub cursor: Option<String>,
+}
+
+pub fn create_migrate_state_instruction(
+    config: &MigrateStateConfig,
+    merkle_tree: Pubkey,
+    params: MigrateLeafParams,
+) -> Instruction {
+    let registry_program_id = Pubkey::from(LIGHT_REGISTRY_PROGRAM_ID);
+    let account_compression_program_id = Pubkey::from(ACCOUNT_COMPRESSION_PROGRAM_ID);
+    let forester_pda = Pubkey::find_program_address(
+        &[FORESTER_SEED, config.derivation.as_ref()],
+        &registry_program_id,
+    )
+    .0;
+    let registered_forester_pda = Pubkey::find_program_address(
+        &[
+            FORESTER_EPOCH_SEED,
+            forester_pda.as_ref(),
+            config.epoch.to_le_bytes().as_slice(),
+        ],
+        &registry_program_id,
+    )
+    .0;
+    let (cpi_authority, bump) =
+        Pubkey::find_program_address(&[CPI_AUTHORITY_PDA_SEED], &registry_program_id);
+    let registered_program_pda = Pubkey::find_program_address(
+        &[registry_program_id.as_ref()],
+        &account_compression_program_id,
+    )
+    .0;
+
+    let accounts = vec![
+        AccountMeta::new(registered_forester_pda, false),
+        AccountMeta::new_readonly(config.authority, true),
+        AccountMeta::new_readonly(cpi_authority, false),
+        AccountMeta::new_readonly(registered_program_pda, false),
+        AccountMeta::new_readonly(account_compression_program_id, false),
+        AccountMeta::new_readonly(Pubkey::from(NOOP_PROGRAM_ID), false),
+        AccountMeta::new(merkle_tree, false),
+        AccountMeta::new(config.output_queue, false),
+    ];
+
+    let mut data = MIGRATE_STATE_DISCRIMINATOR.to_vec();
+    data.push(bump);
+    params
+        .serialize(&mut data)
+        .expect("Failed to serialize instruction data");
+
+    Instruction {
+        program_id: registry_program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Creates one migrate state instruction per proof.
+pub fn create_migrate_state_instructions(
+    config: &MigrateStateConfig,
+    proofs: &[MerkleProof],
+) -> Result<Vec<Instruction>, IndexerError> {
+    proofs
+        .iter()
+        .map(|proof| {
+            Ok(create_migrate_state_instruction(
+                config,
+                proof.merkle_tree,
+                MigrateLeafParams::try_from(proof)?,
+            ))
+        })
+        .collect()
+}
+
+/// Fetches a page of an owner's compressed accounts and creates migrate state
+/// instructions for the accounts in v1 state trees.
+/// Accounts can be restricted to one v1 state tree with `merkle_tree`.
+pub async fn get_migrate_state_instructions<I: Indexer>(
+    indexer: &I,
+    owner: &Pubkey,
+    merkle_tree: Option<Pubkey>,
+    config: &MigrateStateConfig,
+    options: Option<GetCompressedAccountsByOwnerConfig>,
+) -> Result<MigrateStateBatch, IndexerError> {
+    let accounts = indexer
+        .get_compressed_accounts_by_owner(owner, options, None)
+        .await?
+        .value;
+    let hashes: Vec<[u8; 32]> = accounts
+        .items
+        .iter()
+        .filter(|account| is_migratable(account, merkle_tree))
+        .map(|account| account.hash)
+        .collect();
+    Ok(MigrateStateBatch {
+        cursor: accounts.cursor,
+        ..get_migrate_state_instructions_for_hashes(indexer, hashes, config).await?
+    })
+}
+
+/// Creates migrate state instructions for up to `limit` live leaves of the v1
+/// state tree `merkle_tree`, whatever their owners. `indexer` must index the
+/// tree and be synced, proofs are taken from its local copy of the tree.
+pub async fn get_migrate_tree_instructions(
+    indexer: &LocalIndexer,
+    merkle_tree: Pubkey,
+    config: &MigrateStateConfig,
+    limit: usize,
+) -> Result<MigrateStateBatch, IndexerError> {
+    let mut hashes = indexer.get_live_leaves(&merkle_tree)?;
+    hashes.truncate(limit);
+    get_migrate_state_instructions_for_hashes(indexer, hashes, config).await
+}
+
+/// Fetches proofs of the leaves with `hashes` and creates one migrate state
+/// instruction per leaf. The leaves must be in v1 state trees.
+pub async fn get_migrate_state_instructions_for_hashes<I: Indexer>(
+    indexer: &I,
+    hashes: Vec<[u8; 32]>,
+    config: &MigrateStateConfig,
+) -> Result<MigrateStateBatch, IndexerError> {
+    if hashes.is_empty() {
+        return Ok(MigrateStateBatch::default());
+    }
+    let proofs = indexer
+        .get_multiple_compressed_account_proofs(hashes.clone(), None)
+        .await?
+        .value
+        .items;
+    if proofs.len() != hashes.len() {
+        return Err(IndexerError::InvalidParameters(format!(
+            "Proof count mismatch: requested={}, received={}",
+            hashes.len(),
+            proofs.len()
+        )));
+    }
+    Ok(MigrateStateBatch {
+        instructions: create_migrate_state_instructions(config, &proofs)?,
+        hashes: proofs.iter().map(|proof| proof.hash).collect(),
+        cursor: None,
+    })
+}
+
+fn is_migratable(account: &CompressedAccount, merkle_tree: Option<Pubkey>) -> bool {
+    account.tree_info.tree_type == TreeType::StateV1
+        && merkle_tree.is_none_or(|tree| account.tree_info.tree == tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use light_hasher::{Hasher, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_state_discriminator() {
+        let hash = Sha256::hash(b"global:migrate_state").unwrap();
+        assert_eq!(MIGRATE_STATE_DISCRIMINATOR, hash[..8]);
+    }
+
+    #[test]
+    fn test_migrate_leaf_params_from_proof() {
+        let mut proof = MerkleProof {
+            hash: [1u8; 32],
+            leaf_index: 7,
+            merkle_tree: Pubkey::new_unique(),
+            proof: (0..STATE_MERKLE_TREE_HEIGHT as u8)
+                .map(|i| [i; 32])
+                .collect(),
+            root_seq: STATE_MERKLE_TREE_CHANGELOG as u64 + 3,
+            root: [0u8; 32],
+        };
+        // Full proofs are truncated to the nodes below the canopy.
+        let params = MigrateLeafParams::try_from(&proof).unwrap();
+        assert_eq!(params.change_log_index, 3);
+        assert_eq!(params.leaf, [1u8; 32]);
+        assert_eq!(params.leaf_index, 7);
+        assert_eq!(
+            params.proof.to_vec(),
+            proof.proof[..MIGRATE_STATE_PROOF_LEN]
+        );
+
+        proof.proof.truncate(MIGRATE_STATE_PROOF_LEN - 1);
+        assert!(matches!(
+            MigrateLeafParams::try_from(&proof),
+            Err(IndexerError::InvalidParameters(_))
+        ));
+    }
+}
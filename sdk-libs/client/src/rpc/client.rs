@@ -20,7 +20,9 @@ use solana_keypair::Keypair;
 use solana_message::{v0, AddressLookupTableAccount, VersionedMessage};
 use solana_pubkey::{pubkey, Pubkey};
 use solana_rpc_client::rpc_client::RpcClient;
-use solana_rpc_client_api::config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_rpc_client_api::config::{
+    RpcSendTransactionConfig, RpcSimulateTransactionConfig, RpcTransactionConfig,
+};
 use solana_signature::Signature;
 use solana_transaction::{versioned::VersionedTransaction, Transaction};
 use solana_transaction_status_client_types::{
@@ -36,12 +38,20 @@ use crate::rpc::get_light_state_tree_infos::{
 };
 use crate::{
     indexer::{
-        photon_indexer::PhotonIndexer, AccountInterface as IndexerAccountInterface, Indexer,
-        IndexerRpcConfig, Response, TokenAccountInterface as IndexerTokenAccountInterface,
-        TreeInfo,
+        local::transaction::compile_inner_instructions, photon_indexer::PhotonIndexer,
+        AccountInterface as IndexerAccountInterface, Indexer, IndexerRpcConfig, Response,
+        TokenAccountInterface as IndexerTokenAccountInterface, TreeInfo,
     },
     interface::{AccountInterface, MintInterface, MintState, TokenAccountInterface},
-    rpc::{errors::RpcError, merkle_tree::MerkleTreeExt, Rpc},
+    rpc::{
+        errors::RpcError,
+        load_lookup_table,
+        merkle_tree::MerkleTreeExt,
+        simulation::{
+            events_from_instructions, loaded_addresses, CompressedStateDiff, LightSimulationResult,
+        },
+        Rpc,
+    },
 };
 
 /// V2 batched state trees.
@@ -917,6 +927,62 @@ impl Rpc for LightClient {
             .await
     }
 
+    async fn simulate_light_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<LightSimulationResult, RpcError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.client.commitment()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            inner_instructions: true,
+            ..Default::default()
+        };
+        let result = self
+            .retry(|| async {
+                self.client
+                    .simulate_transaction_with_config(transaction, config.clone())
+                    .map_err(RpcError::from)
+            })
+            .await?
+            .value;
+        let logs = result.logs.unwrap_or_default();
+        if let Some(err) = result.err {
+            return Ok(LightSimulationResult {
+                err: Some(err.into()),
+                logs,
+                units_consumed: result.units_consumed,
+                events: Vec::new(),
+                diff: CompressedStateDiff::default(),
+            });
+        }
+
+        let message = &transaction.message;
+        let mut account_keys = message.static_account_keys().to_vec();
+        if let Some(lookups) = message.address_table_lookups() {
+            let tables = lookups
+                .iter()
+                .map(|lookup| load_lookup_table(&self.client, &lookup.account_key))
+                .collect::<Result<Vec<_>, _>>()?;
+            account_keys.extend(loaded_addresses(lookups, &tables)?);
+        }
+        let inner_instructions = compile_inner_instructions(
+            message.instructions().len(),
+            result.inner_instructions.as_deref().unwrap_or_default(),
+        )?;
+        let events =
+            events_from_instructions(&account_keys, message.instructions(), &inner_instructions)?;
+        let diff = CompressedStateDiff::resolve(&events, self.indexer.as_ref()).await;
+        Ok(LightSimulationResult {
+            err: None,
+            logs,
+            units_consumed: result.units_consumed,
+            events,
+            diff,
+        })
+    }
+
     /// Creates and sends a versioned transaction with address lookup tables.
     ///
     /// `address_lookup_tables` must contain pre-fetched `AddressLookupTableAccount` values
@@ -3,12 +3,12 @@ use solana_pubkey::Pubkey;
 
 use super::LightClient;
 use crate::indexer::{
-    Address, AddressWithTree, CompressedAccount, CompressedTokenAccount,
+    Address, AddressWithTree, CompressedAccount, CompressedAccountEvent, CompressedTokenAccount,
     GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash,
-    Indexer, IndexerError, IndexerRpcConfig, Items, ItemsWithCursor, MerkleProof,
+    Indexer, IndexerError, IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
     NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
     QueueElementsV2Options, QueueInfoResult, Response, RetryConfig, SignatureWithMetadata,
-    TokenBalance, ValidityProofWithContext,
+    TokenAccountEvent, TokenBalance, ValidityProofWithContext,
 };
 
 #[async_trait]
@@ -316,4 +316,29 @@ impl Indexer for LightClient {
             .get_indexer_health(config)
             .await?)
     }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        Ok(self
+            .indexer
+            .as_ref()
+            .ok_or(IndexerError::NotInitialized)?
+            .subscribe_compressed_accounts_by_owner(owner)
+            .await?)
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        Ok(self
+            .indexer
+            .as_ref()
+            .ok_or(IndexerError::NotInitialized)?
+            .subscribe_token_accounts_by_owner(owner, mint)
+            .await?)
+    }
 }
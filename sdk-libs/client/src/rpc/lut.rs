@@ -1,11 +1,19 @@
 pub use solana_address_lookup_table_interface::{
     error, instruction, program, state::AddressLookupTable,
 };
+use solana_keypair::Keypair;
 use solana_message::AddressLookupTableAccount;
 use solana_pubkey::Pubkey;
 use solana_rpc_client::rpc_client::RpcClient;
+use solana_signer::Signer;
 
-use crate::rpc::errors::RpcError;
+use crate::rpc::{errors::RpcError, Rpc};
+
+/// Maximum number of addresses stored in one lookup table.
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// Number of addresses added per extend transaction.
+const EXTEND_CHUNK_SIZE: usize = 20;
 
 /// Gets a lookup table account state from the network.
 ///
@@ -35,3 +43,79 @@ pub fn load_lookup_table(
     };
     Ok(address_lookup_table_account)
 }
+
+/// Gets a lookup table account state through the [`Rpc`] trait.
+pub async fn get_lookup_table<R: Rpc>(
+    rpc: &R,
+    lookup_table_address: Pubkey,
+) -> Result<AddressLookupTableAccount, RpcError> {
+    let raw_account = rpc
+        .get_account(lookup_table_address)
+        .await?
+        .ok_or_else(|| RpcError::AccountDoesNotExist(lookup_table_address.to_string()))?;
+    let address_lookup_table = AddressLookupTable::deserialize(&raw_account.data).map_err(|e| {
+        RpcError::CustomError(format!("Failed to deserialize AddressLookupTable: {e:?}"))
+    })?;
+    Ok(AddressLookupTableAccount {
+        key: lookup_table_address,
+        addresses: address_lookup_table.addresses.to_vec(),
+    })
+}
+
+/// Creates a lookup table owned by `payer` if `lookup_table_address` is
+/// `None` and extends it with the `addresses` it does not contain yet.
+///
+/// Addresses added by an extension can only be used by transactions of
+/// later slots.
+///
+/// # Returns
+///
+/// * `AddressLookupTableAccount` - The lookup table account state after the extension.
+pub async fn create_or_extend_lookup_table<R: Rpc>(
+    rpc: &mut R,
+    payer: &Keypair,
+    lookup_table_address: Option<Pubkey>,
+    addresses: &[Pubkey],
+) -> Result<AddressLookupTableAccount, RpcError> {
+    let (lookup_table_address, existing) = match lookup_table_address {
+        Some(address) => (address, get_lookup_table(rpc, address).await?.addresses),
+        None => {
+            let recent_slot = rpc.get_slot().await?;
+            let (instruction, address) =
+                instruction::create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+            rpc.create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+                .await?;
+            (address, Vec::new())
+        }
+    };
+
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        if !existing.contains(address) && !missing.contains(address) {
+            missing.push(*address);
+        }
+    }
+    if existing.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(RpcError::TransactionBuildError(format!(
+            "Lookup table {} would exceed {} addresses",
+            lookup_table_address, LOOKUP_TABLE_MAX_ADDRESSES
+        )));
+    }
+    for chunk in missing.chunks(EXTEND_CHUNK_SIZE) {
+        let instruction = instruction::extend_lookup_table(
+            lookup_table_address,
+            payer.pubkey(),
+            Some(payer.pubkey()),
+            chunk.to_vec(),
+        );
+        rpc.create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+            .await?;
+    }
+
+    let mut addresses = existing;
+    addresses.extend(missing);
+    Ok(AddressLookupTableAccount {
+        key: lookup_table_address,
+        addresses,
+    })
+}
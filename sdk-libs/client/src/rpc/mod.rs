@@ -5,12 +5,16 @@ pub mod errors;
 pub mod indexer;
 pub mod merkle_tree;
 mod rpc_trait;
+pub mod simulation;
 pub mod state;
+pub mod tx_builder;
 
 pub use client::{LightClient, RetryConfig};
 pub use errors::RpcError;
 pub use rpc_trait::{LightClientConfig, Rpc};
+pub use simulation::{CompressedStateDiff, LightSimulationResult};
 pub mod get_light_state_tree_infos;
 
 pub mod lut;
-pub use lut::load_lookup_table;
+pub use lut::{create_or_extend_lookup_table, get_lookup_table, load_lookup_table};
+pub use tx_builder::VersionedTransactionBuilder;
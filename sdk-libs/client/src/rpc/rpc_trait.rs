@@ -20,7 +20,7 @@ use super::client::RpcUrl;
 use crate::{
     indexer::{Indexer, IndexerRpcConfig, Response, TreeInfo},
     interface::{AccountInterface, AccountToFetch, MintInterface, TokenAccountInterface},
-    rpc::errors::RpcError,
+    rpc::{errors::RpcError, simulation::LightSimulationResult},
 };
 
 #[derive(Debug, Clone)]
@@ -222,6 +222,20 @@ pub trait Rpc: Send + Sync + Debug + 'static {
         signers: &[&Keypair],
     ) -> Result<Option<(Vec<BatchPublicTransactionEvent>, Signature, Slot)>, RpcError>;
 
+    /// Simulates a transaction and returns the compressed state it would
+    /// nullify and create. Nullified accounts are resolved with the indexer
+    /// if one is configured. `LightClient` does not verify signatures and
+    /// replaces the blockhash, so transactions can be simulated before they
+    /// are signed.
+    async fn simulate_light_transaction(
+        &self,
+        _transaction: &VersionedTransaction,
+    ) -> Result<LightSimulationResult, RpcError> {
+        Err(RpcError::CustomError(
+            "simulate_light_transaction is not implemented".to_string(),
+        ))
+    }
+
     fn indexer(&self) -> Result<&impl Indexer, RpcError>;
     fn indexer_mut(&mut self) -> Result<&mut impl Indexer, RpcError>;
 
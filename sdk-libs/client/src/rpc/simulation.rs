@@ -0,0 +1,354 @@
+//! Compressed state changes of simulated transactions.
+//!
+//! [`Rpc::simulate_light_transaction`](super::Rpc::simulate_light_transaction)
+//! simulates a transaction, parses the Light events of its instructions and
+//! returns them together with a [`CompressedStateDiff`].
+
+use std::collections::BTreeMap;
+
+use light_compressed_account::compressed_account::CompressedAccount as ProgramCompressedAccount;
+use light_event::event::BatchPublicTransactionEvent;
+use light_token::compat::TokenData;
+use solana_message::{
+    compiled_instruction::CompiledInstruction, v0::MessageAddressTableLookup,
+    AddressLookupTableAccount,
+};
+use solana_pubkey::Pubkey;
+use solana_transaction_error::TransactionError;
+use tracing::warn;
+
+use crate::{
+    indexer::{
+        decode_token_data, local::transaction::decode_instructions, CompressedAccount, Indexer,
+    },
+    rpc::RpcError,
+};
+
+/// Result of [`Rpc::simulate_light_transaction`](super::Rpc::simulate_light_transaction).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightSimulationResult {
+    /// Error of a failed simulation. Events and diff are empty in this case.
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub events: Vec<BatchPublicTransactionEvent>,
+    pub diff: CompressedStateDiff,
+}
+
+/// Compressed state a transaction nullifies and creates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompressedStateDiff {
+    /// Input accounts nullified by the transaction.
+    pub nullified: Vec<NullifiedAccount>,
+    /// Output accounts created by the transaction.
+    pub created: Vec<CreatedAccount>,
+    pub new_addresses: Vec<NewAddress>,
+    /// Lamports compressed into (positive) or decompressed from (negative)
+    /// compressed accounts.
+    pub compressed_lamports: i128,
+    /// Token balance changes per owner and mint. Only nullified token
+    /// accounts resolved by the indexer are included.
+    pub token_balance_changes: Vec<TokenBalanceChange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullifiedAccount {
+    pub hash: [u8; 32],
+    /// The nullified account, `None` if it could not be resolved by the
+    /// indexer.
+    pub account: Option<CompressedAccount>,
+    pub token_data: Option<TokenData>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedAccount {
+    pub hash: [u8; 32],
+    /// State tree for v1 trees, output queue for v2 trees.
+    pub tree_or_queue: Pubkey,
+    pub leaf_index: u32,
+    pub account: ProgramCompressedAccount,
+    pub token_data: Option<TokenData>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NewAddress {
+    pub address: [u8; 32],
+    pub tree: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBalanceChange {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub delta: i128,
+}
+
+impl CompressedStateDiff {
+    /// Builds the diff of `events`. `inputs` are the nullified accounts known
+    /// to the caller, nullified accounts missing in `inputs` are included
+    /// with their hash only.
+    pub fn from_events(
+        events: &[BatchPublicTransactionEvent],
+        inputs: &[CompressedAccount],
+    ) -> Self {
+        let mut diff = Self::default();
+        let mut token_deltas = BTreeMap::<(Pubkey, Pubkey), i128>::new();
+        for batch in events {
+            let event = &batch.event;
+            for hash in event.input_compressed_account_hashes.iter() {
+                let account = inputs.iter().find(|account| account.hash == *hash).cloned();
+                let token_data = account.as_ref().and_then(|account| {
+                    decode_token_data(account.owner.to_bytes(), account.data.as_ref())
+                });
+                if let Some(token_data) = token_data.as_ref() {
+                    *token_deltas
+                        .entry((token_data.owner, token_data.mint))
+                        .or_default() -= token_data.amount as i128;
+                }
+                diff.nullified.push(NullifiedAccount {
+                    hash: *hash,
+                    account,
+                    token_data,
+                });
+            }
+            for (i, output) in event.output_compressed_accounts.iter().enumerate() {
+                let account = output.compressed_account.clone();
+                let token_data = decode_token_data(account.owner.to_bytes(), account.data.as_ref());
+                if let Some(token_data) = token_data.as_ref() {
+                    *token_deltas
+                        .entry((token_data.owner, token_data.mint))
+                        .or_default() += token_data.amount as i128;
+                }
+                diff.created.push(CreatedAccount {
+                    hash: event
+                        .output_compressed_account_hashes
+                        .get(i)
+                        .copied()
+                        .unwrap_or_default(),
+                    tree_or_queue: event
+                        .pubkey_array
+                        .get(output.merkle_tree_index as usize)
+                        .map(|pubkey| Pubkey::new_from_array(pubkey.to_bytes()))
+                        .unwrap_or_default(),
+                    leaf_index: event
+                        .output_leaf_indices
+                        .get(i)
+                        .copied()
+                        .unwrap_or_default(),
+                    account,
+                    token_data,
+                });
+            }
+            diff.new_addresses
+                .extend(batch.new_addresses.iter().map(|address| NewAddress {
+                    address: address.address,
+                    tree: Pubkey::new_from_array(address.mt_pubkey.to_bytes()),
+                }));
+            if let Some(lamports) = event.compress_or_decompress_lamports {
+                if event.is_compress {
+                    diff.compressed_lamports += lamports as i128;
+                } else {
+                    diff.compressed_lamports -= lamports as i128;
+                }
+            }
+        }
+        diff.token_balance_changes = token_deltas
+            .into_iter()
+            .filter(|(_, delta)| *delta != 0)
+            .map(|((owner, mint), delta)| TokenBalanceChange { owner, mint, delta })
+            .collect();
+        diff
+    }
+
+    /// Builds the diff of `events` and resolves the nullified accounts with
+    /// `indexer`. Nullified accounts are included with their hash only if the
+    /// indexer is `None` or fails.
+    pub async fn resolve<I: Indexer>(
+        events: &[BatchPublicTransactionEvent],
+        indexer: Option<&I>,
+    ) -> Self {
+        let hashes: Vec<[u8; 32]> = events
+            .iter()
+            .flat_map(|batch| batch.event.input_compressed_account_hashes.iter().copied())
+            .collect();
+        let mut inputs = Vec::new();
+        if let (Some(indexer), false) = (indexer, hashes.is_empty()) {
+            match indexer
+                .get_multiple_compressed_accounts(None, Some(hashes), None)
+                .await
+            {
+                Ok(response) => inputs.extend(response.value.items.into_iter().flatten()),
+                Err(e) => warn!("Failed to resolve nullified accounts: {}", e),
+            }
+        }
+        Self::from_events(events, &inputs)
+    }
+}
+
+/// Parses the Light events of the instructions of a transaction.
+/// `inner_instructions` holds the inner instructions of each outer
+/// instruction, `account_keys` the static account keys followed by the
+/// addresses loaded from lookup tables.
+pub fn events_from_instructions(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    inner_instructions: &[Vec<CompiledInstruction>],
+) -> Result<Vec<BatchPublicTransactionEvent>, RpcError> {
+    Ok(decode_instructions(account_keys, instructions, inner_instructions)?.events)
+}
+
+/// Resolves the addresses a v0 message loads from lookup tables, writable
+/// addresses of all lookups followed by readonly addresses.
+pub fn loaded_addresses(
+    lookups: &[MessageAddressTableLookup],
+    tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Pubkey>, RpcError> {
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let table = tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+            .ok_or_else(|| RpcError::AccountDoesNotExist(lookup.account_key.to_string()))?;
+        for (indexes, addresses) in [
+            (&lookup.writable_indexes, &mut writable),
+            (&lookup.readonly_indexes, &mut readonly),
+        ] {
+            for index in indexes {
+                let address = table.addresses.get(*index as usize).ok_or_else(|| {
+                    RpcError::CustomError(format!(
+                        "Lookup table {} has no address at index {}",
+                        table.key, index
+                    ))
+                })?;
+                addresses.push(Pubkey::new_from_array(address.to_bytes()));
+            }
+        }
+    }
+    writable.extend(readonly);
+    Ok(writable)
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use light_compressed_account::{
+        compressed_account::CompressedAccountData,
+        instruction_data::data::OutputCompressedAccountWithPackedContext,
+    };
+    use light_event::event::PublicTransactionEvent;
+    use light_sdk_types::constants::LIGHT_TOKEN_PROGRAM_ID;
+    use light_token::compat::AccountState;
+
+    use super::*;
+
+    fn token_data(owner: Pubkey, mint: Pubkey, amount: u64) -> TokenData {
+        TokenData {
+            mint,
+            owner,
+            amount,
+            delegate: None,
+            state: AccountState::Initialized,
+            tlv: None,
+        }
+    }
+
+    fn token_account_data(token_data: &TokenData) -> CompressedAccountData {
+        CompressedAccountData {
+            discriminator: [2, 0, 0, 0, 0, 0, 0, 0],
+            data: token_data.try_to_vec().unwrap(),
+            data_hash: [0u8; 32],
+        }
+    }
+
+    #[test]
+    fn test_state_diff_from_events() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let tree = Pubkey::new_unique();
+
+        let input = CompressedAccount {
+            hash: [1u8; 32],
+            owner: Pubkey::new_from_array(LIGHT_TOKEN_PROGRAM_ID),
+            data: Some(token_account_data(&token_data(owner, mint, 100))),
+            ..Default::default()
+        };
+        let outputs = [token_data(owner, mint, 60), token_data(recipient, mint, 40)]
+            .iter()
+            .map(|token_data| OutputCompressedAccountWithPackedContext {
+                compressed_account: ProgramCompressedAccount {
+                    owner: LIGHT_TOKEN_PROGRAM_ID.into(),
+                    lamports: 0,
+                    address: None,
+                    data: Some(token_account_data(token_data)),
+                },
+                merkle_tree_index: 0,
+            })
+            .collect();
+        let event = BatchPublicTransactionEvent {
+            event: PublicTransactionEvent {
+                input_compressed_account_hashes: vec![[1u8; 32], [9u8; 32]],
+                output_compressed_account_hashes: vec![[2u8; 32], [3u8; 32]],
+                output_compressed_accounts: outputs,
+                output_leaf_indices: vec![7, 8],
+                is_compress: false,
+                compress_or_decompress_lamports: Some(5),
+                pubkey_array: vec![tree.to_bytes().into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let diff = CompressedStateDiff::from_events(&[event], &[input.clone()]);
+
+        assert_eq!(diff.nullified.len(), 2);
+        assert_eq!(diff.nullified[0].account, Some(input));
+        assert_eq!(diff.nullified[1].account, None);
+        assert_eq!(diff.created.len(), 2);
+        assert_eq!(diff.created[1].hash, [3u8; 32]);
+        assert_eq!(diff.created[1].tree_or_queue, tree);
+        assert_eq!(diff.created[1].leaf_index, 8);
+        assert_eq!(
+            diff.created[1].token_data,
+            Some(token_data(recipient, mint, 40))
+        );
+        assert_eq!(diff.compressed_lamports, -5);
+        let mut expected = vec![
+            TokenBalanceChange {
+                owner,
+                mint,
+                delta: -40,
+            },
+            TokenBalanceChange {
+                owner: recipient,
+                mint,
+                delta: 40,
+            },
+        ];
+        expected.sort_by_key(|change| (change.owner, change.mint));
+        assert_eq!(diff.token_balance_changes, expected);
+    }
+
+    #[test]
+    fn test_loaded_addresses_order() {
+        let addresses: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: addresses.clone(),
+        };
+        let lookup = MessageAddressTableLookup {
+            account_key: table.key,
+            writable_indexes: vec![2],
+            readonly_indexes: vec![0, 3],
+        };
+        assert_eq!(
+            loaded_addresses(&[lookup.clone()], &[table]).unwrap(),
+            vec![addresses[2], addresses[0], addresses[3]]
+        );
+        assert!(matches!(
+            loaded_addresses(&[lookup], &[]),
+            Err(RpcError::AccountDoesNotExist(_))
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+//! Builder for v0 transactions which use address lookup tables.
+
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_hash::Hash;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_message::{v0, AddressLookupTableAccount, VersionedMessage};
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::versioned::VersionedTransaction;
+
+use crate::{
+    interface::{tx_size::compact_len, InstructionTooLargeError, PACKET_DATA_SIZE},
+    rpc::{lut::create_or_extend_lookup_table, Rpc, RpcError},
+};
+
+/// Packs instructions into the minimal number of v0 transactions which fit
+/// the transaction size limit.
+///
+/// Instructions keep their order and are never split. Accounts of the
+/// lookup tables are loaded by index, so instructions which use Light system
+/// accounts, tree accounts and token pools fit many more accounts into one
+/// transaction than legacy transactions do.
+///
+/// ```ignore
+/// let mut builder = VersionedTransactionBuilder::new(payer.pubkey(), instructions)
+///     .with_compute_unit_limit(1_000_000);
+/// let lookup_table = builder.create_or_extend_lookup_table(&mut rpc, &payer, None).await?;
+/// // Extended addresses can be used from the next slot on.
+/// let signatures = builder.send(&mut rpc, &[&payer]).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct VersionedTransactionBuilder {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    compute_unit_limit: Option<u32>,
+    max_size: usize,
+}
+
+impl VersionedTransactionBuilder {
+    pub fn new(payer: Pubkey, instructions: Vec<Instruction>) -> Self {
+        Self {
+            payer,
+            instructions,
+            lookup_tables: Vec::new(),
+            compute_unit_limit: None,
+            max_size: PACKET_DATA_SIZE,
+        }
+    }
+
+    pub fn with_lookup_tables(mut self, lookup_tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+
+    /// Prepends a compute unit limit instruction to every transaction.
+    pub fn with_compute_unit_limit(mut self, compute_unit_limit: u32) -> Self {
+        self.compute_unit_limit = Some(compute_unit_limit);
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn lookup_tables(&self) -> &[AddressLookupTableAccount] {
+        &self.lookup_tables
+    }
+
+    /// Returns the accounts which can be loaded from a lookup table but are
+    /// not in one of the builder's lookup tables yet. Signers and invoked
+    /// programs must be static accounts and are excluded.
+    pub fn lookup_table_candidates(&self) -> Vec<Pubkey> {
+        let mut excluded = vec![self.payer];
+        for instruction in self.instructions.iter() {
+            excluded.push(instruction.program_id);
+            excluded.extend(
+                instruction
+                    .accounts
+                    .iter()
+                    .filter(|meta| meta.is_signer)
+                    .map(|meta| meta.pubkey),
+            );
+        }
+        let mut candidates: Vec<Pubkey> = Vec::new();
+        for meta in self
+            .instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter())
+        {
+            let in_table = self
+                .lookup_tables
+                .iter()
+                .any(|table| table.addresses.contains(&meta.pubkey));
+            if !in_table && !excluded.contains(&meta.pubkey) && !candidates.contains(&meta.pubkey) {
+                candidates.push(meta.pubkey);
+            }
+        }
+        candidates
+    }
+
+    /// Creates a lookup table, or extends `lookup_table_address`, with the
+    /// lookup table candidates and adds it to the builder's lookup tables.
+    /// Addresses of an extension can be used from the next slot on.
+    pub async fn create_or_extend_lookup_table<R: Rpc>(
+        &mut self,
+        rpc: &mut R,
+        payer: &Keypair,
+        lookup_table_address: Option<Pubkey>,
+    ) -> Result<AddressLookupTableAccount, RpcError> {
+        let candidates = self.lookup_table_candidates();
+        let lookup_table =
+            create_or_extend_lookup_table(rpc, payer, lookup_table_address, &candidates).await?;
+        self.lookup_tables
+            .retain(|table| table.key != lookup_table.key);
+        self.lookup_tables.push(lookup_table.clone());
+        Ok(lookup_table)
+    }
+
+    /// Compiles the instructions into v0 messages. Instructions are added to
+    /// a message as long as the transaction fits the size limit. Since
+    /// adding an instruction never shrinks a transaction, this yields the
+    /// minimal number of transactions for the given instruction order.
+    pub fn build_messages(&self, blockhash: Hash) -> Result<Vec<v0::Message>, RpcError> {
+        let prefix: Vec<Instruction> = self
+            .compute_unit_limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit)
+            .into_iter()
+            .collect();
+        let mut messages = Vec::new();
+        let mut current = prefix.clone();
+        let mut current_message = None;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            current.push(instruction.clone());
+            let message = self.compile(&current, blockhash)?;
+            if transaction_size(&message) <= self.max_size {
+                current_message = Some(message);
+                continue;
+            }
+
+            messages.extend(current_message.take());
+            current = prefix.clone();
+            current.push(instruction.clone());
+            let message = self.compile(&current, blockhash)?;
+            let size = transaction_size(&message);
+            if size > self.max_size {
+                return Err(RpcError::TransactionBuildError(
+                    InstructionTooLargeError {
+                        instruction_index: index,
+                        estimated_size: size,
+                        max_size: self.max_size,
+                    }
+                    .to_string(),
+                ));
+            }
+            current_message = Some(message);
+        }
+        messages.extend(current_message);
+        Ok(messages)
+    }
+
+    /// Builds and signs the transactions. Each transaction is signed by the
+    /// `signers` it requires.
+    pub fn build(
+        &self,
+        signers: &[&Keypair],
+        blockhash: Hash,
+    ) -> Result<Vec<VersionedTransaction>, RpcError> {
+        self.build_messages(blockhash)?
+            .into_iter()
+            .map(|message| {
+                let required =
+                    &message.account_keys[..message.header.num_required_signatures as usize];
+                let mut message_signers: Vec<&Keypair> = Vec::with_capacity(required.len());
+                for signer in signers {
+                    let pubkey = signer.pubkey();
+                    if required.contains(&pubkey)
+                        && !message_signers.iter().any(|s| s.pubkey() == pubkey)
+                    {
+                        message_signers.push(signer);
+                    }
+                }
+                VersionedTransaction::try_new(VersionedMessage::V0(message), &message_signers)
+                    .map_err(|e| RpcError::SigningError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Builds the transactions with the latest blockhash and sends them in
+    /// order.
+    pub async fn send<R: Rpc>(
+        &self,
+        rpc: &mut R,
+        signers: &[&Keypair],
+    ) -> Result<Vec<Signature>, RpcError> {
+        let blockhash = rpc.get_latest_blockhash().await?.0;
+        let mut signatures = Vec::new();
+        for transaction in self.build(signers, blockhash)? {
+            signatures.push(rpc.process_versioned_transaction(transaction).await?);
+        }
+        Ok(signatures)
+    }
+
+    fn compile(
+        &self,
+        instructions: &[Instruction],
+        blockhash: Hash,
+    ) -> Result<v0::Message, RpcError> {
+        v0::Message::try_compile(&self.payer, instructions, &self.lookup_tables, blockhash).map_err(
+            |e| RpcError::TransactionBuildError(format!("Failed to compile v0 message: {}", e)),
+        )
+    }
+}
+
+/// Serialized size of a signed v0 transaction.
+pub fn transaction_size(message: &v0::Message) -> usize {
+    let num_signatures = message.header.num_required_signatures as usize;
+    let mut size = compact_len(num_signatures) + num_signatures * 64;
+    // Version prefix and header.
+    size += 1 + 3;
+    size += compact_len(message.account_keys.len()) + message.account_keys.len() * 32;
+    // Blockhash.
+    size += 32;
+    size += compact_len(message.instructions.len());
+    for instruction in message.instructions.iter() {
+        size += 1;
+        size += compact_len(instruction.accounts.len()) + instruction.accounts.len();
+        size += compact_len(instruction.data.len()) + instruction.data.len();
+    }
+    size += compact_len(message.address_table_lookups.len());
+    for lookup in message.address_table_lookups.iter() {
+        size += 32;
+        size += compact_len(lookup.writable_indexes.len()) + lookup.writable_indexes.len();
+        size += compact_len(lookup.readonly_indexes.len()) + lookup.readonly_indexes.len();
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_instruction::AccountMeta;
+
+    use super::*;
+
+    /// Instructions which share 20 tree and system accounts, as transfers
+    /// with several inputs do.
+    fn instructions(shared: &[Pubkey], count: usize) -> Vec<Instruction> {
+        let program_id = Pubkey::new_unique();
+        (0..count)
+            .map(|_| Instruction {
+                program_id,
+                accounts: shared
+                    .iter()
+                    .map(|pubkey| AccountMeta::new(*pubkey, false))
+                    .chain(std::iter::once(AccountMeta::new(
+                        Pubkey::new_unique(),
+                        false,
+                    )))
+                    .collect(),
+                data: vec![1u8; 100],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lookup_table_reduces_transactions() {
+        let payer = Keypair::new();
+        let shared: Vec<Pubkey> = (0..20).map(|_| Pubkey::new_unique()).collect();
+        let builder = VersionedTransactionBuilder::new(payer.pubkey(), instructions(&shared, 6))
+            .with_compute_unit_limit(1_000_000);
+
+        let without_table = builder.build_messages(Hash::default()).unwrap();
+        assert!(without_table.len() > 1);
+
+        let candidates = builder.lookup_table_candidates();
+        // Shared accounts and one unique account per instruction.
+        assert_eq!(candidates.len(), 26);
+        let builder = builder.with_lookup_tables(vec![AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: candidates,
+        }]);
+        assert!(builder.lookup_table_candidates().is_empty());
+        let with_table = builder.build_messages(Hash::default()).unwrap();
+        assert!(with_table.len() < without_table.len());
+
+        for message in with_table.iter() {
+            assert!(transaction_size(message) <= PACKET_DATA_SIZE);
+        }
+        // Instructions keep their order, plus one compute budget instruction
+        // per transaction.
+        let num_instructions: usize = with_table.iter().map(|m| m.instructions.len()).sum();
+        assert_eq!(num_instructions, 6 + with_table.len());
+
+        let transactions = builder.build(&[&payer], Hash::default()).unwrap();
+        assert_eq!(transactions.len(), with_table.len());
+    }
+
+    #[test]
+    fn test_instruction_too_large() {
+        let payer = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![],
+            data: vec![0u8; PACKET_DATA_SIZE],
+        };
+        let builder = VersionedTransactionBuilder::new(payer, vec![instruction]);
+        assert!(matches!(
+            builder.build_messages(Hash::default()),
+            Err(RpcError::TransactionBuildError(_))
+        ));
+    }
+
+    #[test]
+    fn test_lookup_table_candidates_exclude_signers_and_programs() {
+        let payer = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(signer, true),
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(program_id, false),
+            ],
+            data: vec![],
+        };
+        let builder = VersionedTransactionBuilder::new(payer, vec![instruction]);
+        assert_eq!(builder.lookup_table_candidates(), vec![account]);
+    }
+}
@@ -3,7 +3,10 @@ use light_batched_merkle_tree::{
     merkle_tree::get_merkle_tree_account_size,
 };
 use light_client::rpc::{Rpc, RpcError};
-use light_registry::account_compression_cpi::sdk::create_initialize_batched_address_merkle_tree_instruction;
+use light_registry::account_compression_cpi::sdk::{
+    create_initialize_batched_address_merkle_tree_instruction,
+    create_initialize_program_owned_batched_address_merkle_tree_instruction,
+};
 use solana_sdk::signature::{Keypair, Signature, Signer};
 
 use crate::utils::create_account::create_account_instruction;
@@ -34,11 +37,20 @@ pub async fn create_batch_address_merkle_tree<R: Rpc>(
         Some(new_address_merkle_tree_keypair),
     );
 
-    let instruction = create_initialize_batched_address_merkle_tree_instruction(
-        authority.pubkey(),
-        new_address_merkle_tree_keypair.pubkey(),
-        address_tree_params,
-    );
+    // Program owned trees are created by their owner, authority must be the owner.
+    let instruction = if address_tree_params.program_owner.is_some() {
+        create_initialize_program_owned_batched_address_merkle_tree_instruction(
+            authority.pubkey(),
+            new_address_merkle_tree_keypair.pubkey(),
+            address_tree_params,
+        )
+    } else {
+        create_initialize_batched_address_merkle_tree_instruction(
+            authority.pubkey(),
+            new_address_merkle_tree_keypair.pubkey(),
+            address_tree_params,
+        )
+    };
     let mut signers: Vec<&Keypair> = vec![payer, new_address_merkle_tree_keypair];
     if authority.pubkey() != payer.pubkey() {
         signers.push(authority);
@@ -5,7 +5,10 @@ use light_batched_merkle_tree::{
 };
 use light_client::rpc::{Rpc, RpcError};
 use light_registry::{
-    account_compression_cpi::sdk::create_initialize_batched_merkle_tree_instruction,
+    account_compression_cpi::sdk::{
+        create_initialize_batched_merkle_tree_instruction,
+        create_initialize_program_owned_batched_merkle_tree_instruction,
+    },
     protocol_config::state::ProtocolConfig,
 };
 use solana_instruction::Instruction;
@@ -66,7 +69,17 @@ pub async fn create_batched_state_merkle_tree<R: Rpc>(
         &Pubkey::from(light_sdk::constants::LIGHT_SYSTEM_PROGRAM_ID),
         Some(cpi_context_keypair),
     );
-    let instruction = if registry {
+    // Program owned trees are created by their owner via the registry,
+    // authority must be the owner.
+    let instruction = if registry && params.program_owner.is_some() {
+        create_initialize_program_owned_batched_merkle_tree_instruction(
+            authority.pubkey(),
+            merkle_tree_keypair.pubkey(),
+            queue_keypair.pubkey(),
+            cpi_context_keypair.pubkey(),
+            params,
+        )
+    } else if registry {
         create_initialize_batched_merkle_tree_instruction(
             authority.pubkey(),
             merkle_tree_keypair.pubkey(),
@@ -1,7 +1,15 @@
 pub mod address_tree;
 mod extensions;
+#[cfg(feature = "photon-server")]
+pub mod photon_server;
+pub mod snapshot;
 pub mod state_tree;
+mod subscriptions;
 mod test_indexer;
 
 pub use extensions::TestIndexerExtensions;
+#[cfg(feature = "photon-server")]
+pub use photon_server::{PhotonServer, PhotonServerHandle};
+pub use snapshot::{FileTestIndexerStore, TestIndexerSnapshot, TestIndexerStore};
+pub use subscriptions::TestIndexerSubscriptions;
 pub use test_indexer::TestIndexer;
@@ -0,0 +1,1345 @@
+//! Photon-compatible JSON-RPC server backed by a [`TestIndexer`].
+//!
+//! The server speaks the same wire format as `photon-api`, so `PhotonIndexer`
+//! and other Photon clients can use a [`TestIndexer`] as a lightweight local
+//! indexer. Requests are accepted both on `POST /<method>` (the layout used by
+//! `photon-api`) and as plain JSON-RPC on `POST /`.
+//!
+//! Only the methods in [`SUPPORTED_METHODS`] are served. Other methods return
+//! a JSON-RPC "method not found" error. This includes the
+//! `getCompressionSignaturesFor*` methods because a [`TestIndexer`] does not
+//! record transaction signatures.
+
+use std::{future::Future, net::SocketAddr, sync::Arc};
+
+use borsh::BorshSerialize;
+use light_client::indexer::{
+    AddressWithTree, Base58Conversions, CompressedAccount, CompressedTokenAccount,
+    GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions,
+    Indexer, IndexerError, MerkleProof, NewAddressProofWithContext, NextTreeInfo, PaginatedOptions,
+    QueueElementsResult, QueueElementsV2Options, TreeInfo, ValidityProofWithContext,
+};
+use light_compressed_account::compressed_account::CompressedAccountData;
+use light_token::compat::{AccountState, TokenData};
+use photon_api::types as photon;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use tokio::{
+    sync::{oneshot, RwLock},
+    task::JoinHandle,
+};
+use warp::Filter;
+
+use super::{TestIndexer, TestIndexerStore};
+
+/// JSON-RPC methods served by [`PhotonServer`].
+pub const SUPPORTED_METHODS: &[&str] = &[
+    "getIndexerHealth",
+    "getIndexerSlot",
+    "getCompressedAccount",
+    "getCompressedAccountBalance",
+    "getCompressedBalanceByOwner",
+    "getMultipleCompressedAccounts",
+    "getCompressedAccountsByOwner",
+    "getCompressedAccountsByOwnerV2",
+    "getCompressedTokenAccountBalance",
+    "getCompressedTokenAccountsByOwner",
+    "getCompressedTokenAccountsByOwnerV2",
+    "getCompressedTokenBalancesByOwnerV2",
+    "getCompressedTokenAccountsByDelegate",
+    "getCompressedTokenAccountsByDelegateV2",
+    "getCompressedMintTokenHolders",
+    "getCompressedAccountProof",
+    "getMultipleCompressedAccountProofs",
+    "getMultipleNewAddressProofsV2",
+    "getValidityProof",
+    "getValidityProofV2",
+    "getQueueInfo",
+    "getQueueElements",
+];
+
+const JSON_RPC_METHOD_NOT_FOUND: i64 = -32601;
+const JSON_RPC_INVALID_PARAMS: i64 = -32602;
+const JSON_RPC_SERVER_ERROR: i64 = -32000;
+
+/// Serves a [`TestIndexer`] over the Photon JSON-RPC API.
+#[derive(Clone)]
+pub struct PhotonServer {
+    indexer: Arc<RwLock<TestIndexer>>,
+}
+
+impl PhotonServer {
+    pub fn new(indexer: TestIndexer) -> Self {
+        Self::from_shared(Arc::new(RwLock::new(indexer)))
+    }
+
+    /// Serves an indexer that is still being updated by the caller.
+    pub fn from_shared(indexer: Arc<RwLock<TestIndexer>>) -> Self {
+        Self { indexer }
+    }
+
+    /// Loads the indexer from a snapshot, e.g. one written by
+    /// `LightProgramTest::save_snapshot`. The server never signs transactions,
+    /// so the governance authority is a throwaway keypair.
+    pub fn from_store(store: &dyn TestIndexerStore) -> Result<Self, IndexerError> {
+        let snapshot = store.load()?.ok_or_else(|| {
+            IndexerError::CustomError("Indexer snapshot store is empty".to_string())
+        })?;
+        let payer = snapshot.payer()?;
+        Ok(Self::new(TestIndexer::from_snapshot(
+            snapshot,
+            payer,
+            Keypair::new(),
+        )?))
+    }
+
+    pub fn indexer(&self) -> Arc<RwLock<TestIndexer>> {
+        self.indexer.clone()
+    }
+
+    /// Handles a single JSON-RPC request and returns the JSON-RPC response.
+    ///
+    /// `method` overrides the `method` field of the request body, which is how
+    /// `photon-api` addresses endpoints (`POST /<method>`).
+    pub async fn handle_request(&self, method: Option<&str>, request: Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = method
+            .map(str::to_string)
+            .or_else(|| {
+                request
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        match self.dispatch(&method, params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => {
+                let code = match error {
+                    IndexerError::NotImplemented(_) => JSON_RPC_METHOD_NOT_FOUND,
+                    IndexerError::InvalidParameters(_) | IndexerError::Base58DecodeError { .. } => {
+                        JSON_RPC_INVALID_PARAMS
+                    }
+                    _ => JSON_RPC_SERVER_ERROR,
+                };
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": error.to_string() },
+                })
+            }
+        }
+    }
+
+    /// Binds `addr` and serves requests until the returned handle is shut down.
+    ///
+    /// Use port `0` to let the OS pick a free port, then read it from
+    /// [`PhotonServerHandle::url`].
+    pub async fn spawn(self, addr: SocketAddr) -> Result<PhotonServerHandle, IndexerError> {
+        let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+            IndexerError::CustomError(format!("Failed to bind Photon server to {addr}: {e}"))
+        })?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| IndexerError::CustomError(e.to_string()))?;
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let join_handle = tokio::spawn(self.run(listener, async move {
+            let _ = shutdown_rx.await;
+        }));
+        Ok(PhotonServerHandle {
+            local_addr,
+            shutdown_tx,
+            join_handle,
+        })
+    }
+
+    /// Serves requests on `listener` until `shutdown` resolves.
+    pub async fn run(
+        self,
+        listener: tokio::net::TcpListener,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let server = self.clone();
+        let method_route =
+            warp::post()
+                .and(warp::path::param::<String>())
+                .and(warp::path::end())
+                .and(warp::body::json())
+                .then(move |method: String, request: Value| {
+                    let server = server.clone();
+                    async move {
+                        warp::reply::json(&server.handle_request(Some(&method), request).await)
+                    }
+                });
+        let server = self;
+        let root_route = warp::post()
+            .and(warp::path::end())
+            .and(warp::body::json())
+            .then(move |request: Value| {
+                let server = server.clone();
+                async move { warp::reply::json(&server.handle_request(None, request).await) }
+            });
+
+        warp::serve(method_route.or(root_route))
+            .incoming(listener)
+            .graceful(shutdown)
+            .run()
+            .await;
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, IndexerError> {
+        match method {
+            "getIndexerHealth" => {
+                self.indexer.read().await.get_indexer_health(None).await?;
+                Ok(json!("ok"))
+            }
+            "getIndexerSlot" => Ok(json!(
+                self.indexer.read().await.get_indexer_slot(None).await?
+            )),
+            "getCompressedAccount" => {
+                let params: photon::PostGetCompressedAccountBodyParams = parse_params(params)?;
+                let indexer = self.indexer.read().await;
+                let response = match (params.address, params.hash) {
+                    (Some(address), _) => {
+                        indexer
+                            .get_compressed_account(decode_hash(&address.0)?, None)
+                            .await?
+                    }
+                    (None, Some(hash)) => {
+                        indexer
+                            .get_compressed_account_by_hash(decode_hash(&hash.0)?, None)
+                            .await?
+                    }
+                    (None, None) => {
+                        return Err(IndexerError::InvalidParameters(
+                            "either address or hash must be provided".to_string(),
+                        ))
+                    }
+                };
+                to_value(photon::PostGetCompressedAccountResponseResult {
+                    context: context(response.context.slot),
+                    value: response.value.as_ref().map(to_account),
+                })
+            }
+            "getCompressedAccountBalance" => {
+                let params: photon::PostGetCompressedAccountBalanceBodyParams =
+                    parse_params(params)?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_balance(
+                        params.address.map(|x| decode_hash(&x.0)).transpose()?,
+                        params.hash.map(|x| decode_hash(&x.0)).transpose()?,
+                        None,
+                    )
+                    .await?;
+                to_value(photon::PostGetCompressedAccountBalanceResponseResult {
+                    context: context(response.context.slot),
+                    value: photon::UnsignedInteger(response.value),
+                })
+            }
+            "getCompressedBalanceByOwner" => {
+                let params: photon::PostGetCompressedBalanceByOwnerBodyParams =
+                    parse_params(params)?;
+                let owner = decode_pubkey(&params.owner.0)?;
+                let indexer = self.indexer.read().await;
+                to_value(photon::PostGetCompressedBalanceByOwnerResponseResult {
+                    context: context(indexer.get_indexer_slot(None).await?),
+                    value: photon::UnsignedInteger(indexer.get_compressed_balance(&owner)),
+                })
+            }
+            "getMultipleCompressedAccounts" => {
+                let params: photon::PostGetMultipleCompressedAccountsBodyParams =
+                    parse_params(params)?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_multiple_compressed_accounts(
+                        params
+                            .addresses
+                            .map(|x| x.iter().map(|a| decode_hash(&a.0)).collect())
+                            .transpose()?,
+                        params
+                            .hashes
+                            .map(|x| x.iter().map(|h| decode_hash(&h.0)).collect())
+                            .transpose()?,
+                        None,
+                    )
+                    .await?;
+                to_value(photon::PostGetMultipleCompressedAccountsResponseResult {
+                    context: context(response.context.slot),
+                    value: photon::AccountList {
+                        items: response
+                            .value
+                            .items
+                            .iter()
+                            .map(|x| x.as_ref().map(to_account))
+                            .collect(),
+                    },
+                })
+            }
+            "getCompressedAccountsByOwner" | "getCompressedAccountsByOwnerV2" => {
+                let params: photon::PostGetCompressedAccountsByOwnerV2BodyParams =
+                    parse_params(params)?;
+                let owner = decode_pubkey(&params.owner.0)?;
+                let options = GetCompressedAccountsByOwnerConfig {
+                    filters: None,
+                    data_slice: None,
+                    cursor: params.cursor.map(|x| x.0),
+                    limit: params.limit.map(|x| x.0 as u16),
+                };
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_accounts_by_owner(&owner, Some(options), None)
+                    .await?;
+                let cursor = response.value.cursor.map(photon::Hash);
+                if method == "getCompressedAccountsByOwner" {
+                    to_value(photon::PostGetCompressedAccountsByOwnerResponseResult {
+                        context: context(response.context.slot),
+                        value: photon::PaginatedAccountList {
+                            cursor,
+                            items: response.value.items.iter().map(to_account).collect(),
+                        },
+                    })
+                } else {
+                    to_value(photon::PostGetCompressedAccountsByOwnerV2ResponseResult {
+                        context: context(response.context.slot),
+                        value: photon::PaginatedAccountListV2 {
+                            cursor,
+                            items: response.value.items.iter().map(to_account_v2).collect(),
+                        },
+                    })
+                }
+            }
+            "getCompressedTokenAccountBalance" => {
+                let params: photon::PostGetCompressedTokenAccountBalanceBodyParams =
+                    parse_params(params)?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_token_account_balance(
+                        params.address.map(|x| decode_hash(&x.0)).transpose()?,
+                        params.hash.map(|x| decode_hash(&x.0)).transpose()?,
+                        None,
+                    )
+                    .await?;
+                to_value(photon::PostGetCompressedTokenAccountBalanceResponseResult {
+                    context: context(response.context.slot),
+                    value: photon::TokenAccountBalance {
+                        amount: photon::UnsignedInteger(response.value),
+                    },
+                })
+            }
+            "getCompressedTokenAccountsByOwner" | "getCompressedTokenAccountsByOwnerV2" => {
+                let params: photon::PostGetCompressedTokenAccountsByOwnerV2BodyParams =
+                    parse_params(params)?;
+                let owner = decode_pubkey(&params.owner.0)?;
+                let options = GetCompressedTokenAccountsByOwnerOrDelegateOptions {
+                    mint: params.mint.map(|x| decode_pubkey(&x.0)).transpose()?,
+                    cursor: params.cursor.map(|x| x.0),
+                    limit: params.limit.map(|x| x.0 as u16),
+                };
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_token_accounts_by_owner(&owner, Some(options), None)
+                    .await?;
+                let cursor = response.value.cursor.map(photon::Base58String);
+                if method == "getCompressedTokenAccountsByOwner" {
+                    to_value(
+                        photon::PostGetCompressedTokenAccountsByOwnerResponseResult {
+                            context: context(response.context.slot),
+                            value: photon::TokenAccountList {
+                                cursor,
+                                items: response
+                                    .value
+                                    .items
+                                    .iter()
+                                    .map(to_token_account)
+                                    .collect::<Result<_, _>>()?,
+                            },
+                        },
+                    )
+                } else {
+                    to_value(
+                        photon::PostGetCompressedTokenAccountsByOwnerV2ResponseResult {
+                            context: context(response.context.slot),
+                            value: photon::TokenAccountListV2 {
+                                cursor,
+                                items: response
+                                    .value
+                                    .items
+                                    .iter()
+                                    .map(to_token_account_v2)
+                                    .collect::<Result<_, _>>()?,
+                            },
+                        },
+                    )
+                }
+            }
+            "getCompressedTokenBalancesByOwnerV2" => {
+                let params: photon::PostGetCompressedTokenBalancesByOwnerV2BodyParams =
+                    parse_params(params)?;
+                let owner = decode_pubkey(&params.owner.0)?;
+                let options = GetCompressedTokenAccountsByOwnerOrDelegateOptions {
+                    mint: params.mint.map(|x| decode_pubkey(&x.0)).transpose()?,
+                    cursor: params.cursor.map(|x| x.0),
+                    limit: params.limit.map(|x| x.0 as u16),
+                };
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_token_balances_by_owner_v2(&owner, Some(options), None)
+                    .await?;
+                to_value(
+                    photon::PostGetCompressedTokenBalancesByOwnerV2ResponseResult {
+                        context: context(response.context.slot),
+                        value: photon::TokenBalanceListV2 {
+                            cursor: response.value.cursor.map(photon::Base58String),
+                            items: response
+                                .value
+                                .items
+                                .iter()
+                                .map(|x| photon::TokenBalance {
+                                    balance: photon::UnsignedInteger(x.balance),
+                                    mint: photon::SerializablePubkey(x.mint.to_string()),
+                                })
+                                .collect(),
+                        },
+                    },
+                )
+            }
+            "getCompressedTokenAccountsByDelegate" | "getCompressedTokenAccountsByDelegateV2" => {
+                let params: photon::PostGetCompressedTokenAccountsByDelegateV2BodyParams =
+                    parse_params(params)?;
+                let delegate = decode_pubkey(&params.delegate.0)?;
+                let options = GetCompressedTokenAccountsByOwnerOrDelegateOptions {
+                    mint: params.mint.map(|x| decode_pubkey(&x.0)).transpose()?,
+                    cursor: params.cursor.map(|x| x.0),
+                    limit: params.limit.map(|x| x.0 as u16),
+                };
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_token_accounts_by_delegate(&delegate, Some(options), None)
+                    .await?;
+                let cursor = response.value.cursor.map(photon::Base58String);
+                if method == "getCompressedTokenAccountsByDelegate" {
+                    to_value(
+                        photon::PostGetCompressedTokenAccountsByDelegateResponseResult {
+                            context: context(response.context.slot),
+                            value: photon::TokenAccountList {
+                                cursor,
+                                items: response
+                                    .value
+                                    .items
+                                    .iter()
+                                    .map(to_token_account)
+                                    .collect::<Result<_, _>>()?,
+                            },
+                        },
+                    )
+                } else {
+                    to_value(
+                        photon::PostGetCompressedTokenAccountsByDelegateV2ResponseResult {
+                            context: context(response.context.slot),
+                            value: photon::TokenAccountListV2 {
+                                cursor,
+                                items: response
+                                    .value
+                                    .items
+                                    .iter()
+                                    .map(to_token_account_v2)
+                                    .collect::<Result<_, _>>()?,
+                            },
+                        },
+                    )
+                }
+            }
+            "getCompressedMintTokenHolders" => {
+                let params: photon::PostGetCompressedMintTokenHoldersBodyParams =
+                    parse_params(params)?;
+                let mint = decode_pubkey(&params.mint.0)?;
+                let options = PaginatedOptions {
+                    cursor: params.cursor.map(|x| x.0),
+                    limit: params.limit.map(|x| x.0 as u16),
+                };
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_compressed_mint_token_holders(&mint, Some(options), None)
+                    .await?;
+                to_value(photon::PostGetCompressedMintTokenHoldersResponseResult {
+                    context: context(response.context.slot),
+                    value: photon::OwnerBalanceList {
+                        cursor: response.value.cursor.map(photon::Base58String),
+                        items: response
+                            .value
+                            .items
+                            .iter()
+                            .map(|x| photon::OwnerBalance {
+                                balance: photon::UnsignedInteger(x.balance),
+                                owner: photon::SerializablePubkey(x.owner.to_string()),
+                            })
+                            .collect(),
+                    },
+                })
+            }
+            "getCompressedAccountProof" => {
+                let params: photon::PostGetCompressedAccountProofBodyParams = parse_params(params)?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_multiple_compressed_account_proofs(
+                        vec![decode_hash(&params.hash.0)?],
+                        None,
+                    )
+                    .await?;
+                let proof = response.value.items.first().ok_or_else(|| {
+                    IndexerError::InvalidParameters(format!("unknown account {}", params.hash.0))
+                })?;
+                to_value(photon::PostGetCompressedAccountProofResponseResult {
+                    context: context(response.context.slot),
+                    value: to_merkle_proof(proof),
+                })
+            }
+            "getMultipleCompressedAccountProofs" => {
+                let hashes: Vec<photon::Hash> = parse_params(params)?;
+                let hashes = hashes
+                    .iter()
+                    .map(|x| decode_hash(&x.0))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_multiple_compressed_account_proofs(hashes, None)
+                    .await?;
+                to_value(
+                    photon::PostGetMultipleCompressedAccountProofsResponseResult {
+                        context: context(response.context.slot),
+                        value: response.value.items.iter().map(to_merkle_proof).collect(),
+                    },
+                )
+            }
+            "getMultipleNewAddressProofsV2" => {
+                let addresses: Vec<photon::AddressWithTree> = parse_params(params)?;
+                let indexer = self.indexer.read().await;
+                let mut value = Vec::with_capacity(addresses.len());
+                for address in addresses.iter() {
+                    let tree = decode_hash(&address.tree.0)?;
+                    let address = decode_hash(&address.address.0)?;
+                    if !indexer
+                        .address_merkle_trees
+                        .iter()
+                        .any(|x| x.accounts.merkle_tree.to_bytes() == tree)
+                    {
+                        return Err(IndexerError::InvalidParameters(format!(
+                            "unknown address tree {}",
+                            tree.to_base58()
+                        )));
+                    }
+                    // Photon returns full proofs including the canopy.
+                    let proofs = indexer
+                        ._get_multiple_new_address_proofs(tree, vec![address], true)
+                        .await?;
+                    value.extend(proofs.iter().map(|x| to_new_address_proof(address, x)));
+                }
+                to_value(photon::PostGetMultipleNewAddressProofsV2ResponseResult {
+                    context: context(indexer.get_indexer_slot(None).await?),
+                    value,
+                })
+            }
+            "getValidityProof" | "getValidityProofV2" => {
+                let params: photon::PostGetValidityProofV2BodyParams = parse_params(params)?;
+                let hashes = params
+                    .hashes
+                    .iter()
+                    .map(|x| decode_hash(&x.0))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let new_addresses_with_trees = params
+                    .new_addresses_with_trees
+                    .iter()
+                    .map(|x| {
+                        Ok(AddressWithTree {
+                            address: decode_hash(&x.address.0)?,
+                            tree: decode_pubkey(&x.tree.0)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, IndexerError>>()?;
+                let response = self
+                    .indexer
+                    .read()
+                    .await
+                    .get_validity_proof(hashes, new_addresses_with_trees, None)
+                    .await?;
+                if method == "getValidityProof" {
+                    to_value(photon::PostGetValidityProofResponseResult {
+                        context: context(response.context.slot),
+                        value: to_validity_proof_v1(&response.value)?,
+                    })
+                } else {
+                    to_value(photon::PostGetValidityProofV2ResponseResult {
+                        context: context(response.context.slot),
+                        value: to_validity_proof(&response.value),
+                    })
+                }
+            }
+            "getQueueInfo" => {
+                let params: photon::PostGetQueueInfoBodyParams = parse_params(params)?;
+                let trees: Option<Vec<Pubkey>> = params
+                    .trees
+                    .map(|trees| trees.iter().map(|x| decode_pubkey(x)).collect())
+                    .transpose()?;
+                let response = self.indexer.read().await.get_queue_info(None).await?;
+                to_value(photon::PostGetQueueInfoResponseResult {
+                    queues: response
+                        .value
+                        .queues
+                        .iter()
+                        .filter(|x| trees.as_ref().is_none_or(|trees| trees.contains(&x.tree)))
+                        .map(|x| photon::QueueInfo {
+                            queue: x.queue.to_string(),
+                            queue_size: x.queue_size,
+                            queue_type: x.queue_type,
+                            tree: x.tree.to_string(),
+                        })
+                        .collect(),
+                    slot: response.value.slot,
+                })
+            }
+            "getQueueElements" => {
+                let params: photon::PostGetQueueElementsBodyParams = parse_params(params)?;
+                let tree = decode_hash(&params.tree.0)?;
+                let mut options = QueueElementsV2Options::default();
+                if let Some(queue) = params.output_queue {
+                    options = options
+                        .with_output_queue(queue.start_index, Some(queue.limit))
+                        .with_output_queue_batch_size(queue.zkp_batch_size);
+                }
+                if let Some(queue) = params.input_queue {
+                    options = options
+                        .with_input_queue(queue.start_index, Some(queue.limit))
+                        .with_input_queue_batch_size(queue.zkp_batch_size);
+                }
+                if let Some(queue) = params.address_queue {
+                    options = options
+                        .with_address_queue(queue.start_index, Some(queue.limit))
+                        .with_address_queue_batch_size(queue.zkp_batch_size);
+                }
+                let response = self
+                    .indexer
+                    .write()
+                    .await
+                    .get_queue_elements(tree, options, None)
+                    .await?;
+                to_value(to_queue_elements(response.context.slot, response.value))
+            }
+            _ => Err(IndexerError::NotImplemented(method.to_string())),
+        }
+    }
+}
+
+/// Handle to a [`PhotonServer`] started with [`PhotonServer::spawn`].
+pub struct PhotonServerHandle {
+    local_addr: SocketAddr,
+    shutdown_tx: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+
+impl PhotonServerHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Base url to pass to `PhotonIndexer::new`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.local_addr)
+    }
+
+    /// Stops accepting requests and waits for in-flight requests to finish.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(());
+        let _ = self.join_handle.await;
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, IndexerError> {
+    serde_json::from_value(params).map_err(|e| IndexerError::InvalidParameters(e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, IndexerError> {
+    serde_json::to_value(value).map_err(|e| IndexerError::CustomError(e.to_string()))
+}
+
+fn decode_hash(value: &str) -> Result<[u8; 32], IndexerError> {
+    <[u8; 32] as Base58Conversions>::from_base58(value)
+}
+
+fn decode_pubkey(value: &str) -> Result<Pubkey, IndexerError> {
+    decode_hash(value).map(Pubkey::new_from_array)
+}
+
+fn context(slot: u64) -> photon::Context {
+    photon::Context { slot }
+}
+
+fn to_account_data(data: &CompressedAccountData) -> photon::AccountData {
+    photon::AccountData {
+        data: photon::Base64String(base64::encode(&data.data)),
+        data_hash: photon::Hash(data.data_hash.to_base58()),
+        discriminator: photon::UnsignedInteger(u64::from_le_bytes(data.discriminator)),
+    }
+}
+
+fn to_tree_context(tree_info: &NextTreeInfo) -> photon::TreeContextInfo {
+    photon::TreeContextInfo {
+        cpi_context: tree_info
+            .cpi_context
+            .map(|x| photon::SerializablePubkey(x.to_string())),
+        queue: photon::SerializablePubkey(tree_info.queue.to_string()),
+        tree: photon::SerializablePubkey(tree_info.tree.to_string()),
+        tree_type: tree_info.tree_type as u16,
+    }
+}
+
+fn to_merkle_context(tree_info: &TreeInfo) -> photon::MerkleContextV2 {
+    photon::MerkleContextV2 {
+        cpi_context: tree_info
+            .cpi_context
+            .map(|x| photon::SerializablePubkey(x.to_string())),
+        next_tree_context: tree_info.next_tree_info.as_ref().map(to_tree_context),
+        queue: photon::SerializablePubkey(tree_info.queue.to_string()),
+        tree: photon::SerializablePubkey(tree_info.tree.to_string()),
+        tree_type: tree_info.tree_type as u16,
+    }
+}
+
+fn to_account(account: &CompressedAccount) -> photon::Account {
+    photon::Account {
+        address: account
+            .address
+            .map(|x| photon::SerializablePubkey(x.to_base58())),
+        data: account.data.as_ref().map(to_account_data),
+        hash: photon::Hash(account.hash.to_base58()),
+        lamports: photon::UnsignedInteger(account.lamports),
+        leaf_index: photon::UnsignedInteger(account.leaf_index as u64),
+        owner: photon::SerializablePubkey(account.owner.to_string()),
+        seq: account.seq.map(photon::UnsignedInteger),
+        slot_created: photon::UnsignedInteger(account.slot_created),
+        tree: photon::SerializablePubkey(account.tree_info.tree.to_string()),
+    }
+}
+
+fn to_account_v2(account: &CompressedAccount) -> photon::AccountV2 {
+    photon::AccountV2 {
+        address: account
+            .address
+            .map(|x| photon::SerializablePubkey(x.to_base58())),
+        data: account.data.as_ref().map(to_account_data),
+        hash: photon::Hash(account.hash.to_base58()),
+        lamports: photon::UnsignedInteger(account.lamports),
+        leaf_index: photon::UnsignedInteger(account.leaf_index as u64),
+        merkle_context: to_merkle_context(&account.tree_info),
+        owner: photon::SerializablePubkey(account.owner.to_string()),
+        prove_by_index: account.prove_by_index,
+        seq: account.seq.map(photon::UnsignedInteger),
+        slot_created: photon::UnsignedInteger(account.slot_created),
+    }
+}
+
+fn to_token_data(token: &TokenData) -> Result<photon::TokenData, IndexerError> {
+    let tlv = token
+        .tlv
+        .as_ref()
+        .map(|tlv| {
+            tlv.try_to_vec()
+                .map(|bytes| photon::Base64String(base64::encode(bytes)))
+                .map_err(|e| IndexerError::CustomError(e.to_string()))
+        })
+        .transpose()?;
+    Ok(photon::TokenData {
+        amount: photon::UnsignedInteger(token.amount),
+        delegate: token
+            .delegate
+            .map(|x| photon::SerializablePubkey(x.to_string())),
+        mint: photon::SerializablePubkey(token.mint.to_string()),
+        owner: photon::SerializablePubkey(token.owner.to_string()),
+        state: match token.state {
+            AccountState::Initialized => photon::AccountState::Initialized,
+            AccountState::Frozen => photon::AccountState::Frozen,
+        },
+        tlv,
+    })
+}
+
+fn to_token_account(
+    token_account: &CompressedTokenAccount,
+) -> Result<photon::TokenAccount, IndexerError> {
+    Ok(photon::TokenAccount {
+        account: to_account(&token_account.account),
+        token_data: to_token_data(&token_account.token)?,
+    })
+}
+
+fn to_token_account_v2(
+    token_account: &CompressedTokenAccount,
+) -> Result<photon::TokenAccountV2, IndexerError> {
+    Ok(photon::TokenAccountV2 {
+        account: to_account_v2(&token_account.account),
+        token_data: to_token_data(&token_account.token)?,
+    })
+}
+
+fn to_merkle_proof(proof: &MerkleProof) -> photon::GetCompressedAccountProofResponseValue {
+    photon::GetCompressedAccountProofResponseValue {
+        hash: photon::Hash(proof.hash.to_base58()),
+        leaf_index: proof.leaf_index as u32,
+        merkle_tree: photon::SerializablePubkey(proof.merkle_tree.to_string()),
+        proof: proof
+            .proof
+            .iter()
+            .map(|x| photon::Hash(x.to_base58()))
+            .collect(),
+        root: photon::Hash(proof.root.to_base58()),
+        root_seq: proof.root_seq,
+    }
+}
+
+fn to_new_address_proof(
+    address: [u8; 32],
+    proof: &NewAddressProofWithContext,
+) -> photon::MerkleContextWithNewAddressProof {
+    photon::MerkleContextWithNewAddressProof {
+        address: photon::SerializablePubkey(address.to_base58()),
+        higher_range_address: photon::SerializablePubkey(proof.low_address_next_value.to_base58()),
+        low_element_leaf_index: proof.low_address_index as u32,
+        lower_range_address: photon::SerializablePubkey(proof.low_address_value.to_base58()),
+        merkle_tree: photon::SerializablePubkey(proof.merkle_tree.to_string()),
+        next_index: proof.low_address_next_index as u32,
+        proof: proof
+            .low_address_proof
+            .iter()
+            .map(|x| photon::Hash(x.to_base58()))
+            .collect(),
+        root: photon::Hash(proof.root.to_base58()),
+        root_seq: proof.root_seq,
+    }
+}
+
+fn to_validity_proof(proof: &ValidityProofWithContext) -> photon::CompressedProofWithContextV2 {
+    let to_i64 = |bytes: &[u8]| bytes.iter().map(|x| *x as i64).collect::<Vec<_>>();
+    photon::CompressedProofWithContextV2 {
+        accounts: proof
+            .accounts
+            .iter()
+            .map(|x| photon::AccountProofInputs {
+                hash: x.hash.to_base58(),
+                leaf_index: x.leaf_index,
+                merkle_context: to_merkle_context(&x.tree_info),
+                root: x.root.to_base58(),
+                root_index: photon::RootIndex {
+                    prove_by_index: x.root_index.proof_by_index(),
+                    root_index: x.root_index.root_index().unwrap_or_default() as u64,
+                },
+            })
+            .collect(),
+        addresses: proof
+            .addresses
+            .iter()
+            .map(|x| photon::AddressProofInputs {
+                address: x.address.to_base58(),
+                merkle_context: to_merkle_context(&x.tree_info),
+                root: x.root.to_base58(),
+                root_index: x.root_index,
+            })
+            .collect(),
+        compressed_proof: proof.proof.0.as_ref().map(|x| photon::CompressedProof {
+            a: to_i64(&x.a),
+            b: to_i64(&x.b),
+            c: to_i64(&x.c),
+        }),
+    }
+}
+
+/// Flattens a proof into the v1 layout, accounts first, then addresses.
+fn to_validity_proof_v1(
+    proof: &ValidityProofWithContext,
+) -> Result<photon::CompressedProofWithContext, IndexerError> {
+    let to_i64 = |bytes: &[u8]| bytes.iter().map(|x| *x as i64).collect::<Vec<_>>();
+    // v1 has no prove by index, every account is proven against a root.
+    let compressed_proof = proof.proof.0.as_ref().ok_or_else(|| {
+        IndexerError::InvalidParameters(
+            "accounts in batched queues are only supported by getValidityProofV2".to_string(),
+        )
+    })?;
+    let mut value = photon::CompressedProofWithContext {
+        compressed_proof: photon::CompressedProof {
+            a: to_i64(&compressed_proof.a),
+            b: to_i64(&compressed_proof.b),
+            c: to_i64(&compressed_proof.c),
+        },
+        leaf_indices: Vec::with_capacity(proof.accounts.len()),
+        leaves: Vec::new(),
+        merkle_trees: Vec::new(),
+        root_indices: Vec::new(),
+        roots: Vec::new(),
+    };
+    for account in proof.accounts.iter() {
+        let root_index = account.root_index.root_index().ok_or_else(|| {
+            IndexerError::InvalidParameters(
+                "accounts in batched queues are only supported by getValidityProofV2".to_string(),
+            )
+        })?;
+        value.leaf_indices.push(account.leaf_index as u32);
+        value.leaves.push(account.hash.to_base58());
+        value.merkle_trees.push(account.tree_info.tree.to_string());
+        value.root_indices.push(root_index as u64);
+        value.roots.push(account.root.to_base58());
+    }
+    for address in proof.addresses.iter() {
+        value.leaves.push(address.address.to_base58());
+        value.merkle_trees.push(address.tree_info.tree.to_string());
+        value.root_indices.push(address.root_index as u64);
+        value.roots.push(address.root.to_base58());
+    }
+    Ok(value)
+}
+
+fn to_hashes(values: &[[u8; 32]]) -> Vec<photon::Hash> {
+    values.iter().map(|x| photon::Hash(x.to_base58())).collect()
+}
+
+fn to_nodes(indices: &[u64], hashes: &[[u8; 32]]) -> Vec<photon::Node> {
+    indices
+        .iter()
+        .zip(hashes.iter())
+        .map(|(index, hash)| photon::Node {
+            hash: photon::Hash(hash.to_base58()),
+            index: *index,
+        })
+        .collect()
+}
+
+fn to_queue_elements(
+    slot: u64,
+    result: QueueElementsResult,
+) -> photon::PostGetQueueElementsResponseResult {
+    let state_queue = result.state_queue.map(|x| photon::StateQueueData {
+        initial_root: photon::Hash(x.initial_root.to_base58()),
+        input_queue: x.input_queue.map(|x| photon::InputQueueData {
+            account_hashes: to_hashes(&x.account_hashes),
+            first_queue_index: x.first_queue_index,
+            leaf_indices: x.leaf_indices,
+            leaves: to_hashes(&x.current_leaves),
+            leaves_hash_chains: to_hashes(&x.leaves_hash_chains),
+            nullifiers: to_hashes(&x.nullifiers),
+            tx_hashes: to_hashes(&x.tx_hashes),
+        }),
+        nodes: to_nodes(&x.nodes, &x.node_hashes),
+        output_queue: x.output_queue.map(|x| photon::OutputQueueData {
+            account_hashes: to_hashes(&x.account_hashes),
+            first_queue_index: x.first_queue_index,
+            leaf_indices: x.leaf_indices,
+            leaves: to_hashes(&x.old_leaves),
+            leaves_hash_chains: to_hashes(&x.leaves_hash_chains),
+            next_index: x.next_index,
+        }),
+        root_seq: x.root_seq,
+    });
+    let address_queue = result.address_queue.map(|x| photon::AddressQueueData {
+        addresses: x
+            .addresses
+            .iter()
+            .map(|x| photon::SerializablePubkey(x.to_base58()))
+            .collect(),
+        initial_root: photon::Hash(x.initial_root.to_base58()),
+        leaves_hash_chains: to_hashes(&x.leaves_hash_chains),
+        low_element_indices: x.low_element_indices.clone(),
+        low_element_next_indices: x.low_element_next_indices.clone(),
+        low_element_next_values: to_hashes(&x.low_element_next_values),
+        low_element_values: to_hashes(&x.low_element_values),
+        nodes: to_nodes(&x.nodes, &x.node_hashes),
+        queue_indices: (x.start_index..x.start_index + x.addresses.len() as u64).collect(),
+        root_seq: x.root_seq,
+        start_index: x.start_index,
+        subtrees: to_hashes(&x.subtrees),
+    });
+    photon::PostGetQueueElementsResponseResult {
+        address_queue,
+        context: context(slot),
+        state_queue,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use light_client::indexer::{
+        photon_indexer::PhotonIndexer, QueueInfo, StateMerkleTreeAccounts,
+    };
+    use light_compressed_account::{
+        compressed_account::{
+            CompressedAccount as ProgramCompressedAccount, CompressedAccountWithMerkleContext,
+            MerkleContext,
+        },
+        QueueType, TreeType,
+    };
+    use light_hasher::Poseidon;
+    use light_merkle_tree_reference::MerkleTree;
+    use light_token::compat::TokenDataWithMerkleContext;
+
+    use super::*;
+    use crate::indexer::state_tree::StateMerkleTreeBundle;
+
+    fn test_indexer(owner: Pubkey) -> TestIndexer {
+        let merkle_tree = Pubkey::new_unique();
+        let queue = Pubkey::new_unique();
+        let mut compressed_accounts = Vec::new();
+        let mut tree = MerkleTree::<Poseidon>::new_with_history(26, 10, 0, 2400);
+        for leaf_index in 0..3u32 {
+            let account = CompressedAccountWithMerkleContext {
+                compressed_account: ProgramCompressedAccount {
+                    owner: owner.to_bytes().into(),
+                    lamports: 100 + leaf_index as u64,
+                    address: None,
+                    data: None,
+                },
+                merkle_context: MerkleContext {
+                    merkle_tree_pubkey: merkle_tree.to_bytes().into(),
+                    queue_pubkey: queue.to_bytes().into(),
+                    leaf_index,
+                    prove_by_index: false,
+                    tree_type: TreeType::StateV1,
+                },
+            };
+            tree.append(&account.hash().unwrap()).unwrap();
+            compressed_accounts.push(account);
+        }
+
+        TestIndexer {
+            state_merkle_trees: vec![StateMerkleTreeBundle {
+                rollover_fee: 0,
+                network_fee: 0,
+                merkle_tree: Box::new(tree),
+                accounts: StateMerkleTreeAccounts {
+                    merkle_tree,
+                    nullifier_queue: queue,
+                    cpi_context: Pubkey::new_unique(),
+                    tree_type: TreeType::StateV1,
+                },
+                tree_type: TreeType::StateV1,
+                output_queue_elements: vec![],
+                input_leaf_indices: vec![],
+                output_queue_batch_size: None,
+                num_inserted_batches: 0,
+            }],
+            address_merkle_trees: vec![],
+            payer: Keypair::new(),
+            governance_authority: Keypair::new(),
+            group_pda: Pubkey::new_unique(),
+            compressed_accounts,
+            nullified_compressed_accounts: vec![],
+            token_compressed_accounts: vec![],
+            token_nullified_compressed_accounts: vec![],
+            events: vec![],
+            onchain_pubkey_index: HashMap::new(),
+            subscriptions: Default::default(),
+            mock_prover: false,
+        }
+    }
+
+    fn v2_state_tree(output_queue_elements: Vec<([u8; 32], u64)>) -> StateMerkleTreeBundle {
+        StateMerkleTreeBundle {
+            rollover_fee: 0,
+            network_fee: 0,
+            merkle_tree: Box::new(MerkleTree::<Poseidon>::new_with_history(32, 0, 0, 200)),
+            accounts: StateMerkleTreeAccounts {
+                merkle_tree: Pubkey::new_unique(),
+                nullifier_queue: Pubkey::new_unique(),
+                cpi_context: Pubkey::new_unique(),
+                tree_type: TreeType::StateV2,
+            },
+            tree_type: TreeType::StateV2,
+            output_queue_elements,
+            input_leaf_indices: vec![],
+            output_queue_batch_size: Some(10),
+            num_inserted_batches: 0,
+        }
+    }
+
+    fn delegated_token_account(
+        indexer: &TestIndexer,
+        owner: Pubkey,
+        delegate: Pubkey,
+        mint: Pubkey,
+    ) -> TokenDataWithMerkleContext {
+        let accounts = &indexer.state_merkle_trees[0].accounts;
+        TokenDataWithMerkleContext {
+            token_data: TokenData {
+                mint,
+                owner,
+                amount: 1_000,
+                delegate: Some(delegate),
+                state: AccountState::Initialized,
+                tlv: None,
+            },
+            compressed_account: CompressedAccountWithMerkleContext {
+                compressed_account: ProgramCompressedAccount {
+                    owner: Pubkey::new_unique().to_bytes().into(),
+                    lamports: 0,
+                    address: None,
+                    data: None,
+                },
+                merkle_context: MerkleContext {
+                    merkle_tree_pubkey: accounts.merkle_tree.to_bytes().into(),
+                    queue_pubkey: accounts.nullifier_queue.to_bytes().into(),
+                    leaf_index: 3,
+                    prove_by_index: false,
+                    tree_type: TreeType::StateV1,
+                },
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn photon_indexer_matches_test_indexer() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut test_indexer = test_indexer(owner);
+        // Validity proofs without a prover server.
+        test_indexer.mock_prover = true;
+        test_indexer
+            .state_merkle_trees
+            .push(v2_state_tree(vec![([1u8; 32], 0), ([2u8; 32], 1)]));
+        let token_account = delegated_token_account(&test_indexer, owner, delegate, mint);
+        test_indexer.token_compressed_accounts.push(token_account);
+        let v2_tree = test_indexer.state_merkle_trees[1].accounts;
+        let server = PhotonServer::new(test_indexer);
+        let indexer = server.indexer();
+        let handle = server
+            .clone()
+            .spawn(([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        let mut photon_indexer = PhotonIndexer::new(handle.url());
+
+        assert!(photon_indexer.get_indexer_health(None).await.unwrap());
+
+        let expected = indexer
+            .read()
+            .await
+            .get_compressed_accounts_by_owner(&owner, None, None)
+            .await
+            .unwrap();
+        let accounts = photon_indexer
+            .get_compressed_accounts_by_owner(&owner, None, None)
+            .await
+            .unwrap();
+        assert_eq!(accounts.value.items.len(), 3);
+        for (account, expected) in accounts.value.items.iter().zip(expected.value.items.iter()) {
+            assert_eq!(account.hash, expected.hash);
+            assert_eq!(account.lamports, expected.lamports);
+            assert_eq!(account.leaf_index, expected.leaf_index);
+            assert_eq!(account.tree_info.tree, expected.tree_info.tree);
+        }
+
+        let account_hashes: Vec<[u8; 32]> = expected.value.items.iter().map(|x| x.hash).collect();
+        let expected = indexer
+            .read()
+            .await
+            .get_multiple_compressed_account_proofs(account_hashes.clone(), None)
+            .await
+            .unwrap();
+        let hashes = expected.value.items.iter().map(|x| x.hash).collect();
+        let proofs = photon_indexer
+            .get_multiple_compressed_account_proofs(hashes, None)
+            .await
+            .unwrap();
+        for (proof, expected) in proofs.value.items.iter().zip(expected.value.items.iter()) {
+            assert_eq!(proof.root, expected.root);
+            assert_eq!(proof.leaf_index, expected.leaf_index);
+            // PhotonIndexer strips the canopy from the full proof.
+            assert_eq!(proof.proof[..], expected.proof[..proof.proof.len()]);
+        }
+
+        let response = server
+            .handle_request(
+                Some("getCompressedAccountProof"),
+                json!({ "params": { "hash": account_hashes[0].to_base58() } }),
+            )
+            .await;
+        let proof: photon::PostGetCompressedAccountProofResponseResult =
+            serde_json::from_value(response["result"].clone()).unwrap();
+        assert_eq!(proof.value.root.0, expected.value.items[0].root.to_base58());
+        assert_eq!(proof.value.leaf_index, 0);
+
+        // Validity proofs, v2 through PhotonIndexer and the v1 layout directly.
+        let expected = indexer
+            .read()
+            .await
+            .get_validity_proof(account_hashes[..2].to_vec(), vec![], None)
+            .await
+            .unwrap();
+        let proof = photon_indexer
+            .get_validity_proof(account_hashes[..2].to_vec(), vec![], None)
+            .await
+            .unwrap();
+        assert_eq!(proof.value.proof, expected.value.proof);
+        assert_eq!(proof.value.accounts.len(), 2);
+        for (account, expected) in proof
+            .value
+            .accounts
+            .iter()
+            .zip(expected.value.accounts.iter())
+        {
+            assert_eq!(account.hash, expected.hash);
+            assert_eq!(account.root, expected.root);
+            assert_eq!(account.root_index, expected.root_index);
+            assert_eq!(account.leaf_index, expected.leaf_index);
+            assert_eq!(account.tree_info.tree, expected.tree_info.tree);
+        }
+        let response = server
+            .handle_request(
+                Some("getValidityProof"),
+                json!({ "params": { "hashes": [account_hashes[0].to_base58()] } }),
+            )
+            .await;
+        let proof: photon::PostGetValidityProofResponseResult =
+            serde_json::from_value(response["result"].clone()).unwrap();
+        assert_eq!(proof.value.leaves, vec![account_hashes[0].to_base58()]);
+        assert_eq!(
+            proof.value.roots,
+            vec![expected.value.accounts[0].root.to_base58()]
+        );
+        assert_eq!(proof.value.leaf_indices, vec![0]);
+
+        // Token accounts by delegate and mint holders.
+        let accounts = photon_indexer
+            .get_compressed_token_accounts_by_delegate(&delegate, None, None)
+            .await
+            .unwrap();
+        assert_eq!(accounts.value.items.len(), 1);
+        assert_eq!(accounts.value.items[0].token.owner, owner);
+        assert_eq!(accounts.value.items[0].token.delegate, Some(delegate));
+        let holders = photon_indexer
+            .get_compressed_mint_token_holders(&mint, None, None)
+            .await
+            .unwrap();
+        assert_eq!(holders.value.items.len(), 1);
+        assert_eq!(holders.value.items[0].owner, owner);
+        assert_eq!(holders.value.items[0].balance, 1_000);
+
+        // Queues of the v2 tree.
+        let expected = indexer.read().await.get_queue_info(None).await.unwrap();
+        let queue_info = photon_indexer.get_queue_info(None).await.unwrap();
+        assert_eq!(queue_info.value.queues, expected.value.queues);
+        assert!(queue_info.value.queues.contains(&QueueInfo {
+            tree: v2_tree.merkle_tree,
+            queue: v2_tree.nullifier_queue,
+            queue_type: QueueType::OutputStateV2 as u8,
+            queue_size: 2,
+        }));
+
+        let options = QueueElementsV2Options::default().with_output_queue(None, Some(10));
+        let queue_elements = photon_indexer
+            .get_queue_elements(v2_tree.merkle_tree.to_bytes(), options.clone(), None)
+            .await
+            .unwrap()
+            .value
+            .state_queue
+            .unwrap();
+        let expected = indexer
+            .write()
+            .await
+            .get_queue_elements(v2_tree.merkle_tree.to_bytes(), options, None)
+            .await
+            .unwrap()
+            .value
+            .state_queue
+            .unwrap();
+        assert_eq!(queue_elements.initial_root, expected.initial_root);
+        assert_eq!(queue_elements.nodes, expected.nodes);
+        assert_eq!(queue_elements.node_hashes, expected.node_hashes);
+        let (output_queue, expected) = (
+            queue_elements.output_queue.unwrap(),
+            expected.output_queue.unwrap(),
+        );
+        assert_eq!(output_queue.account_hashes, vec![[1u8; 32], [2u8; 32]]);
+        assert_eq!(output_queue.account_hashes, expected.account_hashes);
+        assert_eq!(output_queue.leaf_indices, expected.leaf_indices);
+        assert_eq!(output_queue.old_leaves, expected.old_leaves);
+        assert_eq!(output_queue.next_index, expected.next_index);
+
+        // Signatures are not recorded by the test indexer.
+        let response = server
+            .handle_request(
+                Some("getCompressionSignaturesForOwner"),
+                json!({ "params": { "owner": owner.to_string() } }),
+            )
+            .await;
+        assert_eq!(response["error"]["code"], JSON_RPC_METHOD_NOT_FOUND);
+
+        handle.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn photon_indexer_polls_owner_subscriptions() {
+        use futures::StreamExt;
+        use light_client::indexer::CompressedAccountEvent;
+
+        let owner = Pubkey::new_unique();
+        let server = PhotonServer::new(test_indexer(owner));
+        let indexer = server.indexer();
+        let handle = server
+            .clone()
+            .spawn(([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        let photon_indexer = PhotonIndexer::new(handle.url())
+            .with_poll_interval(std::time::Duration::from_millis(10));
+        let mut stream = photon_indexer
+            .subscribe_compressed_accounts_by_owner(&owner)
+            .await
+            .unwrap();
+
+        let (spent, created) = {
+            let mut indexer = indexer.write().await;
+            let spent = indexer.compressed_accounts.remove(0);
+            let mut created = spent.clone();
+            created.merkle_context.leaf_index = 3;
+            indexer.compressed_accounts.push(created.clone());
+            indexer.nullified_compressed_accounts.push(spent.clone());
+            (spent, created)
+        };
+
+        match stream.next().await.unwrap().unwrap() {
+            CompressedAccountEvent::Nullified { account, .. } => {
+                assert_eq!(account.hash, spent.hash().unwrap())
+            }
+            event => panic!("expected nullification, got {:?}", event),
+        }
+        match stream.next().await.unwrap().unwrap() {
+            CompressedAccountEvent::Created { account, .. } => {
+                assert_eq!(account.hash, created.hash().unwrap())
+            }
+            event => panic!("expected creation, got {:?}", event),
+        }
+        handle.shutdown().await;
+    }
+}
@@ -0,0 +1,748 @@
+//! On-disk snapshots of [`TestIndexer`] state.
+//!
+//! A snapshot captures the reference Merkle trees, compressed (token) accounts
+//! and event history of a [`TestIndexer`] together with the on-chain accounts
+//! of its trees and queues, so that a new `LightProgramTest` can resume from a
+//! warm fixture instead of regenerating it.
+//!
+//! The payer and any additional keypairs passed to
+//! `LightProgramTest::save_snapshot` are stored in plain text so that restored
+//! accounts can be spent. Never snapshot keypairs that hold real funds.
+//!
+//! Storage is pluggable via [`TestIndexerStore`]. [`FileTestIndexerStore`]
+//! persists snapshots as a single borsh-encoded file.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use light_client::indexer::{AddressMerkleTreeAccounts, IndexerError, StateMerkleTreeAccounts};
+use light_compressed_account::{compressed_account::CompressedAccountWithMerkleContext, TreeType};
+use light_event::event::PublicTransactionEvent;
+use light_hasher::{bigint::bigint_to_be_bytes_array, Poseidon};
+use light_merkle_tree_reference::MerkleTree;
+use light_token::compat::{TokenData, TokenDataWithMerkleContext};
+use num_bigint::BigUint;
+use solana_account::Account;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+
+use super::{
+    address_tree::{AddressMerkleTreeBundle, IndexedMerkleTreeVersion},
+    state_tree::{LeafIndexInfo, StateMerkleTreeBundle},
+    TestIndexer,
+};
+
+/// Bumped whenever the snapshot layout changes.
+pub const TEST_INDEXER_SNAPSHOT_VERSION: u8 = 1;
+
+/// Storage backend for [`TestIndexerSnapshot`]s.
+pub trait TestIndexerStore {
+    /// Returns `None` if the store does not contain a snapshot yet.
+    fn load(&self) -> Result<Option<TestIndexerSnapshot>, IndexerError>;
+
+    fn save(&self, snapshot: &TestIndexerSnapshot) -> Result<(), IndexerError>;
+}
+
+/// Stores a snapshot as a single borsh-encoded file.
+///
+/// Writes go to a temporary file next to `path` which is renamed on success,
+/// so an interrupted save never leaves a truncated snapshot behind.
+#[derive(Debug, Clone)]
+pub struct FileTestIndexerStore {
+    path: PathBuf,
+}
+
+impl FileTestIndexerStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl TestIndexerStore for FileTestIndexerStore {
+    fn load(&self) -> Result<Option<TestIndexerSnapshot>, IndexerError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&self.path).map_err(|e| {
+            IndexerError::CustomError(format!(
+                "Failed to read snapshot {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        TestIndexerSnapshot::from_bytes(&bytes).map(Some)
+    }
+
+    fn save(&self, snapshot: &TestIndexerSnapshot) -> Result<(), IndexerError> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    IndexerError::CustomError(format!(
+                        "Failed to create snapshot directory {}: {}",
+                        parent.display(),
+                        e
+                    ))
+                })?;
+            }
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, snapshot.to_bytes()?).map_err(|e| {
+            IndexerError::CustomError(format!(
+                "Failed to write snapshot {}: {}",
+                tmp_path.display(),
+                e
+            ))
+        })?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            IndexerError::CustomError(format!(
+                "Failed to move snapshot to {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// Serializable state of a [`TestIndexer`] and the on-chain accounts it tracks.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct TestIndexerSnapshot {
+    pub version: u8,
+    pub group_pda: Pubkey,
+    pub state_merkle_trees: Vec<StateMerkleTreeBundleSnapshot>,
+    pub address_merkle_trees: Vec<AddressMerkleTreeBundleSnapshot>,
+    pub compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    pub nullified_compressed_accounts: Vec<CompressedAccountWithMerkleContext>,
+    pub token_compressed_accounts: Vec<TokenAccountSnapshot>,
+    pub token_nullified_compressed_accounts: Vec<TokenAccountSnapshot>,
+    pub events: Vec<PublicTransactionEvent>,
+    pub onchain_pubkey_index: Vec<([u8; 32], u64)>,
+    /// On-chain accounts restored into the SVM on resume.
+    pub accounts: Vec<AccountSnapshot>,
+    /// Keypair of the payer, see [`TestIndexerSnapshot::payer`].
+    pub payer: [u8; 64],
+    /// Additional keypairs, e.g. owners of compressed accounts.
+    pub keypairs: Vec<[u8; 64]>,
+    /// Whether the indexer returned mock proofs, see [`TestIndexer::mock_prover`].
+    pub mock_prover: bool,
+}
+
+impl TestIndexerSnapshot {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, IndexerError> {
+        self.try_to_vec()
+            .map_err(|e| IndexerError::CustomError(format!("Failed to encode snapshot: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, IndexerError> {
+        let snapshot = Self::try_from_slice(bytes)
+            .map_err(|e| IndexerError::decode_error("TestIndexerSnapshot", e))?;
+        if snapshot.version != TEST_INDEXER_SNAPSHOT_VERSION {
+            return Err(IndexerError::CustomError(format!(
+                "Unsupported snapshot version {} (expected {})",
+                snapshot.version, TEST_INDEXER_SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn payer(&self) -> Result<Keypair, IndexerError> {
+        keypair_from_bytes(&self.payer)
+    }
+
+    /// Keypairs passed to `LightProgramTest::save_snapshot`, in order.
+    pub fn keypairs(&self) -> Result<Vec<Keypair>, IndexerError> {
+        self.keypairs.iter().map(keypair_from_bytes).collect()
+    }
+
+    /// Pubkeys of all trees, queues and cpi context accounts in the snapshot.
+    pub fn tree_account_pubkeys(&self) -> Vec<Pubkey> {
+        let mut pubkeys = Vec::new();
+        for tree in self.state_merkle_trees.iter() {
+            pubkeys.extend([tree.merkle_tree_pubkey, tree.queue, tree.cpi_context]);
+        }
+        for tree in self.address_merkle_trees.iter() {
+            pubkeys.push(tree.merkle_tree_pubkey);
+            if tree.queue != tree.merkle_tree_pubkey {
+                pubkeys.push(tree.queue);
+            }
+        }
+        pubkeys
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct AccountSnapshot {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+impl AccountSnapshot {
+    pub fn new(pubkey: Pubkey, account: &Account) -> Self {
+        Self {
+            pubkey,
+            lamports: account.lamports,
+            data: account.data.clone(),
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        }
+    }
+
+    pub fn into_account(self) -> (Pubkey, Account) {
+        (
+            self.pubkey,
+            Account {
+                lamports: self.lamports,
+                data: self.data,
+                owner: self.owner,
+                executable: self.executable,
+                rent_epoch: self.rent_epoch,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TokenAccountSnapshot {
+    pub token_data: TokenData,
+    pub compressed_account: CompressedAccountWithMerkleContext,
+}
+
+impl From<&TokenDataWithMerkleContext> for TokenAccountSnapshot {
+    fn from(account: &TokenDataWithMerkleContext) -> Self {
+        Self {
+            token_data: account.token_data.clone(),
+            compressed_account: account.compressed_account.clone(),
+        }
+    }
+}
+
+impl From<TokenAccountSnapshot> for TokenDataWithMerkleContext {
+    fn from(account: TokenAccountSnapshot) -> Self {
+        Self {
+            token_data: account.token_data,
+            compressed_account: account.compressed_account,
+        }
+    }
+}
+
+/// Full state of a reference [`MerkleTree`], including its root history.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MerkleTreeSnapshot {
+    pub height: u64,
+    pub canopy_depth: u64,
+    pub layers: Vec<Vec<[u8; 32]>>,
+    pub roots: Vec<[u8; 32]>,
+    pub rightmost_index: u64,
+    pub num_root_updates: u64,
+    pub sequence_number: u64,
+    pub root_history_start_offset: u64,
+    pub root_history_array_len: Option<u64>,
+}
+
+impl From<&MerkleTree<Poseidon>> for MerkleTreeSnapshot {
+    fn from(tree: &MerkleTree<Poseidon>) -> Self {
+        Self {
+            height: tree.height as u64,
+            canopy_depth: tree.canopy_depth as u64,
+            layers: tree.layers.clone(),
+            roots: tree.roots.clone(),
+            rightmost_index: tree.rightmost_index as u64,
+            num_root_updates: tree.num_root_updates as u64,
+            sequence_number: tree.sequence_number as u64,
+            root_history_start_offset: tree.root_history_start_offset as u64,
+            root_history_array_len: tree.root_history_array_len.map(|len| len as u64),
+        }
+    }
+}
+
+impl From<MerkleTreeSnapshot> for MerkleTree<Poseidon> {
+    fn from(snapshot: MerkleTreeSnapshot) -> Self {
+        let mut tree =
+            MerkleTree::<Poseidon>::new(snapshot.height as usize, snapshot.canopy_depth as usize);
+        tree.layers = snapshot.layers;
+        tree.roots = snapshot.roots;
+        tree.rightmost_index = snapshot.rightmost_index as usize;
+        tree.num_root_updates = snapshot.num_root_updates as usize;
+        tree.sequence_number = snapshot.sequence_number as usize;
+        tree.root_history_start_offset = snapshot.root_history_start_offset as usize;
+        tree.root_history_array_len = snapshot.root_history_array_len.map(|len| len as usize);
+        tree
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct IndexedElementSnapshot {
+    pub index: u64,
+    pub value: [u8; 32],
+    pub next_index: u64,
+}
+
+impl IndexedElementSnapshot {
+    fn new(index: usize, value: &BigUint, next_index: usize) -> Result<Self, IndexerError> {
+        Ok(Self {
+            index: index as u64,
+            value: biguint_to_bytes(value)?,
+            next_index: next_index as u64,
+        })
+    }
+}
+
+/// Indexed array of an address tree.
+///
+/// `highest_value` is only tracked by the v2 indexed array.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct IndexedArraySnapshot {
+    pub elements: Vec<IndexedElementSnapshot>,
+    pub current_node_index: u64,
+    pub highest_element_index: u64,
+    pub highest_value: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum AddressTreeVersion {
+    V1,
+    V2,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct StateMerkleTreeBundleSnapshot {
+    pub rollover_fee: i64,
+    pub network_fee: u64,
+    pub merkle_tree: MerkleTreeSnapshot,
+    pub merkle_tree_pubkey: Pubkey,
+    pub queue: Pubkey,
+    pub cpi_context: Pubkey,
+    pub tree_type: TreeType,
+    pub output_queue_elements: Vec<([u8; 32], u64)>,
+    pub input_leaf_indices: Vec<LeafIndexInfoSnapshot>,
+    pub output_queue_batch_size: Option<u64>,
+    pub num_inserted_batches: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct LeafIndexInfoSnapshot {
+    pub leaf_index: u32,
+    pub leaf: [u8; 32],
+    pub tx_hash: [u8; 32],
+}
+
+impl From<&StateMerkleTreeBundle> for StateMerkleTreeBundleSnapshot {
+    fn from(bundle: &StateMerkleTreeBundle) -> Self {
+        Self {
+            rollover_fee: bundle.rollover_fee,
+            network_fee: bundle.network_fee,
+            merkle_tree: MerkleTreeSnapshot::from(bundle.merkle_tree.as_ref()),
+            merkle_tree_pubkey: bundle.accounts.merkle_tree,
+            queue: bundle.accounts.nullifier_queue,
+            cpi_context: bundle.accounts.cpi_context,
+            tree_type: bundle.tree_type,
+            output_queue_elements: bundle.output_queue_elements.clone(),
+            input_leaf_indices: bundle
+                .input_leaf_indices
+                .iter()
+                .map(|info| LeafIndexInfoSnapshot {
+                    leaf_index: info.leaf_index,
+                    leaf: info.leaf,
+                    tx_hash: info.tx_hash,
+                })
+                .collect(),
+            output_queue_batch_size: bundle.output_queue_batch_size.map(|size| size as u64),
+            num_inserted_batches: bundle.num_inserted_batches as u64,
+        }
+    }
+}
+
+impl From<StateMerkleTreeBundleSnapshot> for StateMerkleTreeBundle {
+    fn from(snapshot: StateMerkleTreeBundleSnapshot) -> Self {
+        Self {
+            rollover_fee: snapshot.rollover_fee,
+            network_fee: snapshot.network_fee,
+            merkle_tree: Box::new(snapshot.merkle_tree.into()),
+            accounts: StateMerkleTreeAccounts {
+                merkle_tree: snapshot.merkle_tree_pubkey,
+                nullifier_queue: snapshot.queue,
+                cpi_context: snapshot.cpi_context,
+                tree_type: snapshot.tree_type,
+            },
+            tree_type: snapshot.tree_type,
+            output_queue_elements: snapshot.output_queue_elements,
+            input_leaf_indices: snapshot
+                .input_leaf_indices
+                .into_iter()
+                .map(|info| LeafIndexInfo {
+                    leaf_index: info.leaf_index,
+                    leaf: info.leaf,
+                    tx_hash: info.tx_hash,
+                })
+                .collect(),
+            output_queue_batch_size: snapshot.output_queue_batch_size.map(|size| size as usize),
+            num_inserted_batches: snapshot.num_inserted_batches as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct AddressMerkleTreeBundleSnapshot {
+    pub rollover_fee: i64,
+    pub version: AddressTreeVersion,
+    pub merkle_tree: MerkleTreeSnapshot,
+    pub indexed_array: IndexedArraySnapshot,
+    pub merkle_tree_pubkey: Pubkey,
+    pub queue: Pubkey,
+    pub queue_elements: Vec<[u8; 32]>,
+}
+
+impl AddressMerkleTreeBundleSnapshot {
+    pub fn accounts(&self) -> AddressMerkleTreeAccounts {
+        AddressMerkleTreeAccounts {
+            merkle_tree: self.merkle_tree_pubkey,
+            queue: self.queue,
+        }
+    }
+}
+
+impl TestIndexer {
+    /// Captures the indexer state.
+    ///
+    /// The returned snapshot does not contain on-chain accounts,
+    /// see `LightProgramTest::save_snapshot` to include them.
+    pub fn snapshot(&self) -> Result<TestIndexerSnapshot, IndexerError> {
+        let address_merkle_trees = self
+            .address_merkle_trees
+            .iter()
+            .map(AddressMerkleTreeBundleSnapshot::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut onchain_pubkey_index: Vec<([u8; 32], u64)> = self
+            .onchain_pubkey_index
+            .iter()
+            .map(|(pubkey, index)| (*pubkey, *index as u64))
+            .collect();
+        // Sort for deterministic snapshot bytes.
+        onchain_pubkey_index.sort_unstable();
+
+        Ok(TestIndexerSnapshot {
+            version: TEST_INDEXER_SNAPSHOT_VERSION,
+            group_pda: self.group_pda,
+            state_merkle_trees: self
+                .state_merkle_trees
+                .iter()
+                .map(StateMerkleTreeBundleSnapshot::from)
+                .collect(),
+            address_merkle_trees,
+            compressed_accounts: self.compressed_accounts.clone(),
+            nullified_compressed_accounts: self.nullified_compressed_accounts.clone(),
+            token_compressed_accounts: self
+                .token_compressed_accounts
+                .iter()
+                .map(TokenAccountSnapshot::from)
+                .collect(),
+            token_nullified_compressed_accounts: self
+                .token_nullified_compressed_accounts
+                .iter()
+                .map(TokenAccountSnapshot::from)
+                .collect(),
+            events: self.events.clone(),
+            onchain_pubkey_index,
+            accounts: vec![],
+            payer: self.payer.to_bytes(),
+            keypairs: vec![],
+            mock_prover: self.mock_prover,
+        })
+    }
+
+    /// Restores an indexer from a snapshot.
+    ///
+    /// The restored indexer signs with `payer`. Pass
+    /// [`TestIndexerSnapshot::payer`] to keep the snapshotted one.
+    pub fn from_snapshot(
+        snapshot: TestIndexerSnapshot,
+        payer: Keypair,
+        governance_authority: Keypair,
+    ) -> Result<Self, IndexerError> {
+        if snapshot.version != TEST_INDEXER_SNAPSHOT_VERSION {
+            return Err(IndexerError::CustomError(format!(
+                "Unsupported snapshot version {} (expected {})",
+                snapshot.version, TEST_INDEXER_SNAPSHOT_VERSION
+            )));
+        }
+        let address_merkle_trees = snapshot
+            .address_merkle_trees
+            .into_iter()
+            .map(AddressMerkleTreeBundle::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            state_merkle_trees: snapshot
+                .state_merkle_trees
+                .into_iter()
+                .map(StateMerkleTreeBundle::from)
+                .collect(),
+            address_merkle_trees,
+            payer,
+            governance_authority,
+            group_pda: snapshot.group_pda,
+            compressed_accounts: snapshot.compressed_accounts,
+            nullified_compressed_accounts: snapshot.nullified_compressed_accounts,
+            token_compressed_accounts: snapshot
+                .token_compressed_accounts
+                .into_iter()
+                .map(TokenDataWithMerkleContext::from)
+                .collect(),
+            token_nullified_compressed_accounts: snapshot
+                .token_nullified_compressed_accounts
+                .into_iter()
+                .map(TokenDataWithMerkleContext::from)
+                .collect(),
+            events: snapshot.events,
+            onchain_pubkey_index: snapshot
+                .onchain_pubkey_index
+                .into_iter()
+                .map(|(pubkey, index)| (pubkey, index as usize))
+                .collect::<HashMap<_, _>>(),
+            subscriptions: Default::default(),
+            mock_prover: snapshot.mock_prover,
+        })
+    }
+}
+
+impl TryFrom<&AddressMerkleTreeBundle> for AddressMerkleTreeBundleSnapshot {
+    type Error = IndexerError;
+
+    fn try_from(bundle: &AddressMerkleTreeBundle) -> Result<Self, Self::Error> {
+        let (version, merkle_tree, indexed_array) = match &bundle.merkle_tree {
+            IndexedMerkleTreeVersion::V1(tree) => {
+                let indexed_array = bundle
+                    .indexed_array_v1()
+                    .ok_or(IndexerError::InvalidResponseData)?;
+                let elements = indexed_array
+                    .elements
+                    .iter()
+                    .map(|e| IndexedElementSnapshot::new(e.index, &e.value, e.next_index))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (
+                    AddressTreeVersion::V1,
+                    MerkleTreeSnapshot::from(&tree.merkle_tree),
+                    IndexedArraySnapshot {
+                        elements,
+                        current_node_index: indexed_array.current_node_index as u64,
+                        highest_element_index: indexed_array.highest_element_index as u64,
+                        highest_value: None,
+                    },
+                )
+            }
+            IndexedMerkleTreeVersion::V2(tree) => {
+                let elements = tree
+                    .indexed_array
+                    .elements
+                    .iter()
+                    .map(|e| IndexedElementSnapshot::new(e.index, &e.value, e.next_index))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (
+                    AddressTreeVersion::V2,
+                    MerkleTreeSnapshot::from(&tree.merkle_tree),
+                    IndexedArraySnapshot {
+                        elements,
+                        current_node_index: tree.indexed_array.current_node_index as u64,
+                        highest_element_index: tree.indexed_array.highest_element_index as u64,
+                        highest_value: Some(biguint_to_bytes(&tree.indexed_array.highest_value)?),
+                    },
+                )
+            }
+        };
+        Ok(AddressMerkleTreeBundleSnapshot {
+            rollover_fee: bundle.rollover_fee,
+            version,
+            merkle_tree,
+            indexed_array,
+            merkle_tree_pubkey: bundle.accounts.merkle_tree,
+            queue: bundle.accounts.queue,
+            queue_elements: bundle.queue_elements.clone(),
+        })
+    }
+}
+
+impl TryFrom<AddressMerkleTreeBundleSnapshot> for AddressMerkleTreeBundle {
+    type Error = IndexerError;
+
+    fn try_from(snapshot: AddressMerkleTreeBundleSnapshot) -> Result<Self, Self::Error> {
+        let accounts = snapshot.accounts();
+        let mut bundle = match snapshot.version {
+            AddressTreeVersion::V1 => {
+                let mut bundle = Self::new_v1(accounts)?;
+                let tree = bundle
+                    .get_v1_indexed_merkle_tree_mut()
+                    .ok_or(IndexerError::InvalidResponseData)?;
+                tree.merkle_tree = snapshot.merkle_tree.into();
+                let indexed_array = bundle
+                    .indexed_array_v1_mut()
+                    .ok_or(IndexerError::InvalidResponseData)?;
+                indexed_array.elements = snapshot
+                    .indexed_array
+                    .elements
+                    .into_iter()
+                    .map(|e| light_indexed_merkle_tree::array::IndexedElement {
+                        index: e.index as usize,
+                        value: BigUint::from_bytes_be(&e.value),
+                        next_index: e.next_index as usize,
+                    })
+                    .collect();
+                indexed_array.current_node_index =
+                    snapshot.indexed_array.current_node_index as usize;
+                indexed_array.highest_element_index =
+                    snapshot.indexed_array.highest_element_index as usize;
+                bundle
+            }
+            AddressTreeVersion::V2 => {
+                let mut bundle = Self::new_v2(accounts)?;
+                let tree = bundle
+                    .get_v2_indexed_merkle_tree_mut()
+                    .ok_or(IndexerError::InvalidResponseData)?;
+                tree.merkle_tree = snapshot.merkle_tree.into();
+                tree.indexed_array.elements = snapshot
+                    .indexed_array
+                    .elements
+                    .into_iter()
+                    .map(|e| light_indexed_array::array::IndexedElement {
+                        index: e.index as usize,
+                        value: BigUint::from_bytes_be(&e.value),
+                        next_index: e.next_index as usize,
+                    })
+                    .collect();
+                tree.indexed_array.current_node_index =
+                    snapshot.indexed_array.current_node_index as usize;
+                tree.indexed_array.highest_element_index =
+                    snapshot.indexed_array.highest_element_index as usize;
+                tree.indexed_array.highest_value = BigUint::from_bytes_be(
+                    &snapshot
+                        .indexed_array
+                        .highest_value
+                        .ok_or(IndexerError::InvalidResponseData)?,
+                );
+                bundle
+            }
+        };
+        bundle.rollover_fee = snapshot.rollover_fee;
+        bundle.queue_elements = snapshot.queue_elements;
+        Ok(bundle)
+    }
+}
+
+fn biguint_to_bytes(value: &BigUint) -> Result<[u8; 32], IndexerError> {
+    bigint_to_be_bytes_array::<32>(value)
+        .map_err(|e| IndexerError::CustomError(format!("Invalid indexed element value: {}", e)))
+}
+
+fn keypair_from_bytes(bytes: &[u8; 64]) -> Result<Keypair, IndexerError> {
+    Keypair::try_from(bytes.as_slice())
+        .map_err(|e| IndexerError::CustomError(format!("Invalid snapshot keypair: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use light_compressed_account::compressed_account::{CompressedAccount, MerkleContext};
+    use solana_sdk::signature::Signer;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_roundtrip_preserves_roots_and_accounts() {
+        let merkle_tree = Pubkey::new_unique();
+        let queue = Pubkey::new_unique();
+        let mut tree = MerkleTree::<Poseidon>::new_with_history(26, 10, 0, 2400);
+        for i in 0..5u8 {
+            tree.append(&[i; 32]).unwrap();
+        }
+
+        let mut address_bundle = AddressMerkleTreeBundle::new_v2(AddressMerkleTreeAccounts {
+            merkle_tree: Pubkey::new_unique(),
+            queue: Pubkey::new_unique(),
+        })
+        .unwrap();
+        address_bundle.append(&BigUint::from(42u32)).unwrap();
+
+        let compressed_account = CompressedAccountWithMerkleContext {
+            compressed_account: CompressedAccount {
+                owner: [1u8; 32].into(),
+                lamports: 7,
+                address: None,
+                data: None,
+            },
+            merkle_context: MerkleContext {
+                merkle_tree_pubkey: merkle_tree.to_bytes().into(),
+                queue_pubkey: queue.to_bytes().into(),
+                leaf_index: 3,
+                prove_by_index: false,
+                tree_type: TreeType::StateV1,
+            },
+        };
+
+        let indexer = TestIndexer {
+            state_merkle_trees: vec![StateMerkleTreeBundle {
+                rollover_fee: 1,
+                network_fee: 2,
+                merkle_tree: Box::new(tree),
+                accounts: StateMerkleTreeAccounts {
+                    merkle_tree,
+                    nullifier_queue: queue,
+                    cpi_context: Pubkey::new_unique(),
+                    tree_type: TreeType::StateV1,
+                },
+                tree_type: TreeType::StateV1,
+                output_queue_elements: vec![],
+                input_leaf_indices: vec![],
+                output_queue_batch_size: None,
+                num_inserted_batches: 0,
+            }],
+            address_merkle_trees: vec![address_bundle],
+            payer: Keypair::new(),
+            governance_authority: Keypair::new(),
+            group_pda: Pubkey::new_unique(),
+            compressed_accounts: vec![compressed_account],
+            nullified_compressed_accounts: vec![],
+            token_compressed_accounts: vec![],
+            token_nullified_compressed_accounts: vec![],
+            events: vec![],
+            onchain_pubkey_index: HashMap::from([([9u8; 32], 0)]),
+            subscriptions: Default::default(),
+            mock_prover: true,
+        };
+
+        let bytes = indexer.snapshot().unwrap().to_bytes().unwrap();
+        let snapshot = TestIndexerSnapshot::from_bytes(&bytes).unwrap();
+        let restored =
+            TestIndexer::from_snapshot(snapshot.clone(), snapshot.payer().unwrap(), Keypair::new())
+                .unwrap();
+
+        let (expected, actual) = (
+            &indexer.state_merkle_trees[0],
+            &restored.state_merkle_trees[0],
+        );
+        assert_eq!(expected.merkle_tree.root(), actual.merkle_tree.root());
+        assert_eq!(expected.merkle_tree.roots, actual.merkle_tree.roots);
+        assert_eq!(expected.accounts, actual.accounts);
+        assert_eq!(
+            indexer.address_merkle_trees[0].root(),
+            restored.address_merkle_trees[0].root()
+        );
+        assert_eq!(
+            indexer.address_merkle_trees[0].right_most_index(),
+            restored.address_merkle_trees[0].right_most_index()
+        );
+        assert_eq!(indexer.compressed_accounts, restored.compressed_accounts);
+        assert_eq!(indexer.onchain_pubkey_index, restored.onchain_pubkey_index);
+        assert_eq!(indexer.group_pda, restored.group_pda);
+        assert_eq!(indexer.payer.pubkey(), restored.payer.pubkey());
+        assert!(restored.mock_prover);
+    }
+}
@@ -0,0 +1,200 @@
+use std::{fmt, sync::Mutex};
+
+use futures::{channel::mpsc, StreamExt};
+use light_client::indexer::{
+    CompressedAccount, CompressedAccountEvent, CompressedTokenAccount, IndexerError, IndexerStream,
+    TokenAccountEvent,
+};
+use light_compressed_account::compressed_account::CompressedAccountWithMerkleContext;
+use light_token::compat::TokenDataWithMerkleContext;
+use solana_sdk::pubkey::Pubkey;
+
+type Sender<T> = mpsc::UnboundedSender<Result<T, IndexerError>>;
+
+enum Subscriber {
+    Accounts {
+        owner: Pubkey,
+        sender: Sender<CompressedAccountEvent>,
+    },
+    TokenAccounts {
+        owner: Pubkey,
+        mint: Option<Pubkey>,
+        sender: Sender<TokenAccountEvent>,
+    },
+}
+
+/// Subscribers of a [`TestIndexer`](super::TestIndexer).
+///
+/// Events are published while the test indexer processes
+/// `PublicTransactionEvent`s, so subscribers observe exactly the accounts the
+/// indexer stores. Subscribers whose stream has been dropped are removed on
+/// the next publish.
+#[derive(Default)]
+pub struct TestIndexerSubscriptions {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl fmt::Debug for TestIndexerSubscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestIndexerSubscriptions")
+            .field("subscribers", &self.subscribers.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl TestIndexerSubscriptions {
+    pub fn subscribe_accounts(&self, owner: Pubkey) -> IndexerStream<CompressedAccountEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber::Accounts { owner, sender });
+        receiver.boxed()
+    }
+
+    pub fn subscribe_token_accounts(
+        &self,
+        owner: Pubkey,
+        mint: Option<Pubkey>,
+    ) -> IndexerStream<TokenAccountEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber::TokenAccounts {
+                owner,
+                mint,
+                sender,
+            });
+        receiver.boxed()
+    }
+
+    /// Publishes the accounts created and nullified by one transaction.
+    /// Nullified accounts are published before created accounts.
+    pub fn publish(
+        &self,
+        slot: u64,
+        created: &[CompressedAccountWithMerkleContext],
+        nullified: &[CompressedAccountWithMerkleContext],
+        created_tokens: &[TokenDataWithMerkleContext],
+        nullified_tokens: &[TokenDataWithMerkleContext],
+    ) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| match subscriber {
+            Subscriber::Accounts { owner, sender } => {
+                let matches = |account: &&CompressedAccountWithMerkleContext| {
+                    account.compressed_account.owner.to_bytes() == owner.to_bytes()
+                };
+                let events = nullified
+                    .iter()
+                    .filter(matches)
+                    .map(|account| {
+                        CompressedAccount::try_from(account.clone())
+                            .map(|account| CompressedAccountEvent::Nullified { account, slot })
+                    })
+                    .chain(created.iter().filter(matches).map(|account| {
+                        CompressedAccount::try_from(account.clone())
+                            .map(|account| CompressedAccountEvent::Created { account, slot })
+                    }));
+                send_all(sender, events)
+            }
+            Subscriber::TokenAccounts {
+                owner,
+                mint,
+                sender,
+            } => {
+                let matches = |account: &&TokenDataWithMerkleContext| {
+                    account.token_data.owner == *owner
+                        && mint.is_none_or(|mint| account.token_data.mint == mint)
+                };
+                let events = nullified_tokens
+                    .iter()
+                    .filter(matches)
+                    .map(|account| {
+                        CompressedTokenAccount::try_from(account.clone())
+                            .map(|account| TokenAccountEvent::Nullified { account, slot })
+                    })
+                    .chain(created_tokens.iter().filter(matches).map(|account| {
+                        CompressedTokenAccount::try_from(account.clone())
+                            .map(|account| TokenAccountEvent::Created { account, slot })
+                    }));
+                send_all(sender, events)
+            }
+        });
+    }
+}
+
+/// Sends all events, returns false if the receiver has been dropped.
+fn send_all<T>(
+    sender: &Sender<T>,
+    mut events: impl Iterator<Item = Result<T, IndexerError>>,
+) -> bool {
+    if sender.is_closed() {
+        return false;
+    }
+    events.all(|event| sender.unbounded_send(event).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on_stream;
+    use light_compressed_account::compressed_account::{
+        CompressedAccount as CompressedAccountData, MerkleContext,
+    };
+
+    use super::*;
+
+    fn account(owner: Pubkey, leaf_index: u32) -> CompressedAccountWithMerkleContext {
+        CompressedAccountWithMerkleContext {
+            compressed_account: CompressedAccountData {
+                owner: owner.to_bytes().into(),
+                lamports: 1,
+                address: None,
+                data: None,
+            },
+            merkle_context: MerkleContext {
+                merkle_tree_pubkey: Pubkey::new_unique().to_bytes().into(),
+                queue_pubkey: Pubkey::new_unique().to_bytes().into(),
+                leaf_index,
+                prove_by_index: false,
+                tree_type: light_compressed_account::TreeType::StateV1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_publish_filters_by_owner() {
+        let subscriptions = TestIndexerSubscriptions::default();
+        let owner = Pubkey::new_unique();
+        let stream = subscriptions.subscribe_accounts(owner);
+
+        let spent = account(owner, 0);
+        let created = [account(owner, 1), account(Pubkey::new_unique(), 2)];
+        subscriptions.publish(7, &created, &[spent], &[], &[]);
+        drop(subscriptions);
+
+        let events = block_on_stream(stream)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            CompressedAccountEvent::Nullified { slot: 7, .. }
+        ));
+        assert!(matches!(
+            events[1],
+            CompressedAccountEvent::Created { slot: 7, .. }
+        ));
+        assert_eq!(events[1].account().leaf_index, 1);
+    }
+
+    #[test]
+    fn test_dropped_subscriber_is_removed() {
+        let subscriptions = TestIndexerSubscriptions::default();
+        let owner = Pubkey::new_unique();
+        drop(subscriptions.subscribe_accounts(owner));
+
+        subscriptions.publish(0, &[account(owner, 0)], &[], &[], &[]);
+        assert!(subscriptions.subscribers.lock().unwrap().is_empty());
+    }
+}
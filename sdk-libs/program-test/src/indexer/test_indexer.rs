@@ -5,6 +5,7 @@ use account_compression::{
     AddressMerkleTreeConfig, AddressQueueConfig, NullifierQueueConfig, StateMerkleTreeConfig,
 };
 
+use super::TestIndexerSubscriptions;
 use crate::accounts::test_accounts::TestAccounts;
 // Constants from account_compression and light_batched_merkle_tree for non-devenv mode
 pub(crate) const STATE_MERKLE_TREE_HEIGHT: u64 = 26;
@@ -24,20 +25,22 @@ use light_client::{
     fee::FeeConfig,
     indexer::{
         AccountProofInputs, Address, AddressMerkleTreeAccounts, AddressProofInputs,
-        AddressWithTree, CompressedAccount, CompressedTokenAccount, Context,
-        GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions,
-        Indexer, IndexerError, IndexerRpcConfig, Items, ItemsWithCursor, MerkleProof,
+        AddressWithTree, CompressedAccount, CompressedAccountEvent, CompressedTokenAccount,
+        Context, GetCompressedAccountsByOwnerConfig,
+        GetCompressedTokenAccountsByOwnerOrDelegateOptions, Indexer, IndexerError,
+        IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
         NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
-        QueueElementsV2Options, Response, RetryConfig, RootIndex, SignatureWithMetadata,
-        StateMerkleTreeAccounts, TokenBalance, ValidityProofWithContext,
+        QueueElementsV2Options, QueueInfo, QueueInfoResult, Response, RetryConfig, RootIndex,
+        SignatureWithMetadata, StateMerkleTreeAccounts, TokenAccountEvent, TokenBalance,
+        ValidityProofWithContext,
     },
 };
 use light_compressed_account::{
     compressed_account::{CompressedAccountWithMerkleContext, MerkleContext},
-    hash_chain::create_hash_chain_from_slice,
+    hash_chain::{create_hash_chain_from_slice, create_two_inputs_hash_chain},
     instruction_data::compressed_proof::CompressedProof,
     tx_hash::create_tx_hash,
-    TreeType,
+    QueueType, TreeType,
 };
 /// Discriminator for compressible accounts that store onchain_pubkey in the first 32 bytes of data.
 /// Re-exported from light_compressible for convenience.
@@ -112,6 +115,11 @@ pub struct TestIndexer {
     pub events: Vec<PublicTransactionEvent>,
     /// Index mapping onchain_pubkey to compressed account index.
     pub onchain_pubkey_index: HashMap<[u8; 32], usize>,
+    /// Subscribers notified about accounts created and nullified by new events.
+    pub subscriptions: TestIndexerSubscriptions,
+    /// Return mock validity proofs instead of requesting proofs from the
+    /// prover server, see [`CompressedProof::mock`].
+    pub mock_prover: bool,
 }
 
 impl Clone for TestIndexer {
@@ -128,6 +136,9 @@ impl Clone for TestIndexer {
             token_nullified_compressed_accounts: self.token_nullified_compressed_accounts.clone(),
             events: self.events.clone(),
             onchain_pubkey_index: self.onchain_pubkey_index.clone(),
+            // Subscribers stay attached to the original indexer.
+            subscriptions: TestIndexerSubscriptions::default(),
+            mock_prover: self.mock_prover,
         }
     }
 }
@@ -436,7 +447,9 @@ impl Indexer for TestIndexer {
         _hash: Hash,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<Items<SignatureWithMetadata>>, IndexerError> {
-        todo!()
+        Err(IndexerError::NotImplemented(
+            "get_compression_signatures_for_account".to_string(),
+        ))
     }
 
     async fn get_multiple_new_address_proofs(
@@ -887,7 +900,41 @@ impl Indexer for TestIndexer {
         &self,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<light_client::indexer::QueueInfoResult>, IndexerError> {
-        unimplemented!("get_queue_info")
+        // Only batched queues are tracked, v1 queues are emptied on insertion.
+        let mut queues = Vec::new();
+        for bundle in self.state_merkle_trees.iter() {
+            if bundle.tree_type != TreeType::StateV2 {
+                continue;
+            }
+            queues.push(QueueInfo {
+                tree: bundle.accounts.merkle_tree,
+                queue: bundle.accounts.nullifier_queue,
+                queue_type: QueueType::OutputStateV2 as u8,
+                queue_size: bundle.output_queue_elements.len() as u64,
+            });
+            queues.push(QueueInfo {
+                tree: bundle.accounts.merkle_tree,
+                queue: bundle.accounts.merkle_tree,
+                queue_type: QueueType::InputStateV2 as u8,
+                queue_size: bundle.input_leaf_indices.len() as u64,
+            });
+        }
+        for bundle in self.address_merkle_trees.iter() {
+            if bundle.get_v2_indexed_merkle_tree().is_none() {
+                continue;
+            }
+            queues.push(QueueInfo {
+                tree: bundle.accounts.merkle_tree,
+                queue: bundle.accounts.merkle_tree,
+                queue_type: QueueType::AddressV2 as u8,
+                queue_size: bundle.queue_elements.len() as u64,
+            });
+        }
+        let slot = self.get_current_slot();
+        Ok(Response {
+            context: Context { slot },
+            value: QueueInfoResult { queues, slot },
+        })
     }
 
     async fn get_subtrees(
@@ -947,20 +994,67 @@ impl Indexer for TestIndexer {
 
     async fn get_compressed_mint_token_holders(
         &self,
-        _mint: &Pubkey,
-        _options: Option<PaginatedOptions>,
+        mint: &Pubkey,
+        options: Option<PaginatedOptions>,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<ItemsWithCursor<OwnerBalance>>, IndexerError> {
-        todo!("get_compressed_mint_token_holders not implemented")
+        let mut holders: Vec<OwnerBalance> = self
+            .token_compressed_accounts
+            .iter()
+            .filter(|acc| acc.token_data.mint == *mint)
+            .fold(HashMap::new(), |mut map, acc| {
+                *map.entry(acc.token_data.owner).or_insert(0) += acc.token_data.amount;
+                map
+            })
+            .into_iter()
+            .map(|(owner, balance)| OwnerBalance { balance, owner })
+            .collect();
+        // Largest holders first, like Photon.
+        holders.sort_by(|a, b| b.balance.cmp(&a.balance).then(a.owner.cmp(&b.owner)));
+        if let Some(limit) = options.and_then(|options| options.limit) {
+            holders.truncate(limit as usize);
+        }
+
+        Ok(Response {
+            context: Context {
+                slot: self.get_current_slot(),
+            },
+            value: ItemsWithCursor {
+                items: holders,
+                cursor: None,
+            },
+        })
     }
 
     async fn get_compressed_token_accounts_by_delegate(
         &self,
-        _delegate: &Pubkey,
-        _options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
+        delegate: &Pubkey,
+        options: Option<GetCompressedTokenAccountsByOwnerOrDelegateOptions>,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<ItemsWithCursor<CompressedTokenAccount>>, IndexerError> {
-        todo!("get_compressed_token_accounts_by_delegate not implemented")
+        let mint = options.as_ref().and_then(|opts| opts.mint);
+        let mut token_accounts = self
+            .token_compressed_accounts
+            .iter()
+            .filter(|acc| {
+                acc.token_data.delegate == Some(*delegate)
+                    && mint.is_none_or(|m| acc.token_data.mint == m)
+            })
+            .map(|acc| CompressedTokenAccount::try_from(acc.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(limit) = options.and_then(|options| options.limit) {
+            token_accounts.truncate(limit as usize);
+        }
+
+        Ok(Response {
+            context: Context {
+                slot: self.get_current_slot(),
+            },
+            value: ItemsWithCursor {
+                items: token_accounts,
+                cursor: None,
+            },
+        })
     }
 
     async fn get_compression_signatures_for_address(
@@ -969,7 +1063,9 @@ impl Indexer for TestIndexer {
         _options: Option<PaginatedOptions>,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
-        todo!("get_compression_signatures_for_address not implemented")
+        Err(IndexerError::NotImplemented(
+            "get_compression_signatures_for_address".to_string(),
+        ))
     }
 
     async fn get_compression_signatures_for_owner(
@@ -978,7 +1074,9 @@ impl Indexer for TestIndexer {
         _options: Option<PaginatedOptions>,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
-        todo!("get_compression_signatures_for_owner not implemented")
+        Err(IndexerError::NotImplemented(
+            "get_compression_signatures_for_owner".to_string(),
+        ))
     }
 
     async fn get_compression_signatures_for_token_owner(
@@ -987,12 +1085,29 @@ impl Indexer for TestIndexer {
         _options: Option<PaginatedOptions>,
         _config: Option<IndexerRpcConfig>,
     ) -> Result<Response<ItemsWithCursor<SignatureWithMetadata>>, IndexerError> {
-        todo!("get_compression_signatures_for_token_owner not implemented")
+        Err(IndexerError::NotImplemented(
+            "get_compression_signatures_for_token_owner".to_string(),
+        ))
     }
 
     async fn get_indexer_health(&self, _config: Option<RetryConfig>) -> Result<bool, IndexerError> {
         Ok(true) // Test indexer is always healthy
     }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        Ok(self.subscriptions.subscribe_accounts(*owner))
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        Ok(self.subscriptions.subscribe_token_accounts(*owner, mint))
+    }
 }
 
 #[async_trait]
@@ -1150,6 +1265,8 @@ impl TestIndexerExtensions for TestIndexer {
     ) {
         let mut compressed_accounts = Vec::new();
         let mut token_compressed_accounts = Vec::new();
+        let num_nullified = self.nullified_compressed_accounts.len();
+        let num_token_nullified = self.token_nullified_compressed_accounts.len();
         let event_inputs_len = event.input_compressed_account_hashes.len();
         let event_outputs_len = event.output_compressed_account_hashes.len();
         for i in 0..std::cmp::max(event_inputs_len, event_outputs_len) {
@@ -1162,6 +1279,13 @@ impl TestIndexerExtensions for TestIndexer {
             );
         }
 
+        self.subscriptions.publish(
+            slot,
+            &compressed_accounts,
+            &self.nullified_compressed_accounts[num_nullified..],
+            &token_compressed_accounts,
+            &self.token_nullified_compressed_accounts[num_token_nullified..],
+        );
         self.events.push(event.clone());
         (compressed_accounts, token_compressed_accounts)
     }
@@ -1349,6 +1473,8 @@ impl TestIndexer {
             token_nullified_compressed_accounts: vec![],
             group_pda,
             onchain_pubkey_index: HashMap::new(),
+            subscriptions: TestIndexerSubscriptions::default(),
+            mock_prover: false,
         }
     }
 
@@ -2016,7 +2142,7 @@ impl TestIndexer {
         }
     }
 
-    async fn _get_multiple_new_address_proofs(
+    pub(crate) async fn _get_multiple_new_address_proofs(
         &self,
         merkle_tree_pubkey: [u8; 32],
         addresses: Vec<[u8; 32]>,
@@ -2156,6 +2282,8 @@ mod tests {
             token_nullified_compressed_accounts: vec![],
             events: vec![],
             onchain_pubkey_index: HashMap::new(),
+            subscriptions: TestIndexerSubscriptions::default(),
+            mock_prover: false,
         };
 
         let response = Indexer::get_validity_proof(&indexer, hashes, vec![], None)
@@ -2375,6 +2503,42 @@ impl TestIndexer {
 }
 
 impl TestIndexer {
+    /// Builds a mock proof over the public inputs the system program
+    /// verifies, so that a wrong root or leaf still fails on chain.
+    fn mock_validity_proof(
+        accounts: &[AccountProofInputs],
+        addresses: &[AddressProofInputs],
+        is_v2: bool,
+    ) -> Result<CompressedProof, IndexerError> {
+        let roots: Vec<[u8; 32]> = accounts.iter().map(|x| x.root).collect();
+        let leaves: Vec<[u8; 32]> = accounts.iter().map(|x| x.hash).collect();
+        let address_roots: Vec<[u8; 32]> = addresses.iter().map(|x| x.root).collect();
+        let new_addresses: Vec<[u8; 32]> = addresses.iter().map(|x| x.address).collect();
+        let to_indexer_error =
+            |error| IndexerError::CustomError(format!("failed to create mock proof: {error}"));
+
+        let public_inputs = if is_v2 {
+            let public_input_hash = if !leaves.is_empty() && !new_addresses.is_empty() {
+                let inclusion_hash =
+                    create_two_inputs_hash_chain(&roots, &leaves).map_err(to_indexer_error)?;
+                let non_inclusion_hash =
+                    create_two_inputs_hash_chain(&address_roots, &new_addresses)
+                        .map_err(to_indexer_error)?;
+                create_hash_chain_from_slice(&[inclusion_hash, non_inclusion_hash])
+                    .map_err(to_indexer_error)?
+            } else if !leaves.is_empty() {
+                create_two_inputs_hash_chain(&roots, &leaves).map_err(to_indexer_error)?
+            } else {
+                create_two_inputs_hash_chain(&address_roots, &new_addresses)
+                    .map_err(to_indexer_error)?
+            };
+            vec![public_input_hash]
+        } else {
+            [roots, leaves, address_roots, new_addresses].concat()
+        };
+        CompressedProof::mock(&public_inputs).map_err(to_indexer_error)
+    }
+
     async fn _get_validity_proof_v1_implementation(
         &self,
         hashes: Vec<[u8; 32]>,
@@ -2443,7 +2607,7 @@ impl TestIndexer {
                         "failed to build prover HTTP client: {error}"
                     ))
                 })?;
-            let (account_proof_inputs, address_proof_inputs, json_payload) =
+            let (account_proof_inputs, address_proof_inputs, json_payload, is_v2) =
                 match (compressed_accounts, new_addresses) {
                     (Some(accounts), None) => {
                         let (payload, payload_legacy, indices) = self
@@ -2453,9 +2617,14 @@ impl TestIndexer {
                             )
                             .await?;
                         if let Some(payload) = payload {
-                            (indices, Vec::new(), payload.to_string())
+                            (indices, Vec::new(), payload.to_string(), true)
                         } else {
-                            (indices, Vec::new(), payload_legacy.unwrap().to_string())
+                            (
+                                indices,
+                                Vec::new(),
+                                payload_legacy.unwrap().to_string(),
+                                false,
+                            )
                         }
                     }
                     (None, Some(addresses)) => {
@@ -2465,12 +2634,13 @@ impl TestIndexer {
                                 addresses,
                             )
                             .await?;
+                        let is_v2 = payload.is_some();
                         let payload_string = if let Some(payload) = payload {
                             payload.to_string()
                         } else {
                             payload_legacy.unwrap().to_string()
                         };
-                        (Vec::new(), indices, payload_string)
+                        (Vec::new(), indices, payload_string, is_v2)
                     }
                     (Some(accounts), Some(addresses)) => {
                         let (inclusion_payload, inclusion_payload_legacy, inclusion_indices) = self
@@ -2512,6 +2682,7 @@ impl TestIndexer {
                             }
                         }
 
+                        let is_v2 = non_inclusion_payload.is_some();
                         let json_payload = if let Some(non_inclusion_payload) =
                             non_inclusion_payload
                         {
@@ -2557,7 +2728,12 @@ impl TestIndexer {
                         } else {
                             panic!("Unsupported tree height")
                         };
-                        (inclusion_indices, non_inclusion_indices, json_payload)
+                        (
+                            inclusion_indices,
+                            non_inclusion_indices,
+                            json_payload,
+                            is_v2,
+                        )
                     }
                     _ => {
                         panic!(
@@ -2566,6 +2742,18 @@ impl TestIndexer {
                     }
                 };
 
+            // Proof inputs are still built above so that roots and indices
+            // are checked on chain, only the Groth16 proof is replaced.
+            if self.mock_prover {
+                let proof =
+                    Self::mock_validity_proof(&account_proof_inputs, &address_proof_inputs, is_v2)?;
+                return Ok(ValidityProofWithContext {
+                    accounts: account_proof_inputs,
+                    addresses: address_proof_inputs,
+                    proof: proof.into(),
+                });
+            }
+
             let mut retries = 3;
             let mut last_error = "Failed to get proof from server".to_string();
             while retries > 0 {
@@ -33,6 +33,12 @@
 //! - `true`: Starts a prover server in the background for generating validity proofs
 //! - `false`: Runs without prover (faster for tests that don't need proofs, or repeated test runs to reduce startup time)
 //!
+//! ### `mock_prover: bool`
+//! - `true`: Skips proof generation and Groth16 verification; validity and batch proofs are
+//!   replaced by `CompressedProof::mock` over their public inputs. Roots, leaves and queues
+//!   are still checked on chain.
+//! - Requires program binaries built with `just programs build-mock-prover`
+//!
 //! ### `additional_programs: Option<Vec<(&str, Pubkey)>>`
 //! - Specify custom programs to deploy alongside the default Light Protocol programs
 //! - Format: `vec![("program_name", program_id)]`
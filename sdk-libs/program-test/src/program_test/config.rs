@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[cfg(feature = "devenv")]
 use account_compression::{
     AddressMerkleTreeConfig, AddressQueueConfig, NullifierQueueConfig, StateMerkleTreeConfig,
@@ -20,6 +22,11 @@ pub struct ProgramTestConfig {
     #[cfg(feature = "devenv")]
     pub protocol_config: ProtocolConfig,
     pub with_prover: bool,
+    /// Replace Groth16 proofs with mock proofs bound to their public inputs,
+    /// see `CompressedProof::mock`, and load the system and account
+    /// compression programs built with `mock-proof`. Roots, leaves, indices
+    /// and queues are still checked on chain.
+    pub mock_prover: bool,
     #[cfg(feature = "devenv")]
     pub auto_register_custom_programs_for_pda_compression: bool,
     #[cfg(feature = "devenv")]
@@ -54,6 +61,9 @@ pub struct ProgramTestConfig {
     pub log_light_protocol_events: bool,
     /// Enhanced transaction logging configuration
     pub enhanced_logging: EnhancedLoggingConfig,
+    /// Resume the payer, the indexer and tree accounts from this snapshot file
+    /// if it exists. Snapshots are written with `LightProgramTest::save_snapshot`.
+    pub indexer_snapshot: Option<PathBuf>,
 }
 
 impl ProgramTestConfig {
@@ -118,6 +128,23 @@ impl ProgramTestConfig {
         self
     }
 
+    /// Resume from an indexer snapshot file, see [`ProgramTestConfig::indexer_snapshot`].
+    pub fn with_indexer_snapshot(mut self, path: impl Into<PathBuf>) -> Self {
+        self.indexer_snapshot = Some(path.into());
+        self
+    }
+
+    /// Skip proof generation and verification, see [`ProgramTestConfig::mock_prover`].
+    ///
+    /// Requires the mock-proof program binaries (`just programs build-mock-prover`).
+    /// The test's indexer returns mock validity proofs and
+    /// [`TestRpc::proof_client`](crate::program_test::TestRpc::proof_client)
+    /// returns a client for mock batch proofs.
+    pub fn with_mock_prover(mut self) -> Self {
+        self.mock_prover = true;
+        self
+    }
+
     /// Register custom instruction decoders for enhanced logging
     ///
     /// This allows registering decoders generated by `#[derive(InstructionDecoder)]`
@@ -160,6 +187,7 @@ impl Default for ProgramTestConfig {
                 ..Default::default()
             },
             with_prover: true,
+            mock_prover: false,
             #[cfg(feature = "devenv")]
             auto_register_custom_programs_for_pda_compression: true,
             #[cfg(feature = "devenv")]
@@ -188,6 +216,7 @@ impl Default for ProgramTestConfig {
             with_forester: true,
             log_light_protocol_events: false, // Disabled by default
             enhanced_logging: EnhancedLoggingConfig::from_env(),
+            indexer_snapshot: None,
         }
     }
 }
@@ -1,11 +1,11 @@
 use async_trait::async_trait;
 use light_client::indexer::{
-    Address, AddressWithTree, CompressedAccount, CompressedTokenAccount,
+    Address, AddressWithTree, CompressedAccount, CompressedAccountEvent, CompressedTokenAccount,
     GetCompressedAccountsByOwnerConfig, GetCompressedTokenAccountsByOwnerOrDelegateOptions, Hash,
-    Indexer, IndexerError, IndexerRpcConfig, Items, ItemsWithCursor, MerkleProof,
+    Indexer, IndexerError, IndexerRpcConfig, IndexerStream, Items, ItemsWithCursor, MerkleProof,
     NewAddressProofWithContext, OwnerBalance, PaginatedOptions, QueueElementsResult,
-    QueueElementsV2Options, Response, RetryConfig, SignatureWithMetadata, TokenBalance,
-    ValidityProofWithContext,
+    QueueElementsV2Options, Response, RetryConfig, SignatureWithMetadata, TokenAccountEvent,
+    TokenBalance, ValidityProofWithContext,
 };
 use solana_sdk::pubkey::Pubkey;
 
@@ -313,4 +313,29 @@ impl Indexer for LightProgramTest {
             .get_indexer_health(config)
             .await?)
     }
+
+    async fn subscribe_compressed_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<IndexerStream<CompressedAccountEvent>, IndexerError> {
+        Ok(self
+            .indexer
+            .as_ref()
+            .ok_or(IndexerError::NotInitialized)?
+            .subscribe_compressed_accounts_by_owner(owner)
+            .await?)
+    }
+
+    async fn subscribe_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<IndexerStream<TokenAccountEvent>, IndexerError> {
+        Ok(self
+            .indexer
+            .as_ref()
+            .ok_or(IndexerError::NotInitialized)?
+            .subscribe_token_accounts_by_owner(owner, mint)
+            .await?)
+    }
 }
@@ -12,7 +12,10 @@ use light_prover_client::prover::spawn_prover;
 use litesvm::LiteSVM;
 #[cfg(feature = "devenv")]
 use solana_account::WritableAccount;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
 
 #[cfg(feature = "devenv")]
 use crate::accounts::initialize::initialize_accounts;
@@ -20,8 +23,11 @@ use crate::accounts::initialize::initialize_accounts;
 use crate::program_test::TestRpc;
 use crate::{
     accounts::{test_accounts::TestAccounts, test_keypairs::TestKeypairs},
-    indexer::TestIndexer,
-    utils::setup_light_programs::setup_light_programs,
+    indexer::{
+        snapshot::AccountSnapshot, FileTestIndexerStore, TestIndexer, TestIndexerSnapshot,
+        TestIndexerStore,
+    },
+    utils::setup_light_programs::{setup_light_programs, setup_light_programs_with_mock_prover},
     ProgramTestConfig,
 };
 
@@ -65,7 +71,11 @@ impl LightProgramTest {
     }
 
     pub async fn new(config: ProgramTestConfig) -> Result<LightProgramTest, RpcError> {
-        let mut context = setup_light_programs(config.additional_programs.clone())?;
+        let mut context = if config.mock_prover {
+            setup_light_programs_with_mock_prover(config.additional_programs.clone())?
+        } else {
+            setup_light_programs(config.additional_programs.clone())?
+        };
         let payer = Keypair::new();
         context
             .airdrop(&payer.pubkey(), 100_000_000_000_000)
@@ -388,15 +398,19 @@ impl LightProgramTest {
             }
         }
 
+        if let Some(path) = context.config.indexer_snapshot.clone() {
+            context.resume_from_snapshot(&FileTestIndexerStore::new(path))?;
+        }
+
         // reset tx counter after program setup.
         context.transaction_counter = 0;
 
         #[cfg(feature = "devenv")]
-        {
+        if !config.mock_prover {
             spawn_prover().await;
         }
         #[cfg(not(feature = "devenv"))]
-        if config.with_prover {
+        if config.with_prover && !config.mock_prover {
             spawn_prover().await;
         }
 
@@ -429,12 +443,13 @@ impl LightProgramTest {
         test_accounts: &TestAccounts,
         batch_size: Option<usize>,
     ) -> Result<(), RpcError> {
-        let indexer = TestIndexer::init_from_acounts(
+        let mut indexer = TestIndexer::init_from_acounts(
             &self.payer,
             test_accounts,
             batch_size.unwrap_or_default(),
         )
         .await;
+        indexer.mock_prover = self.config.mock_prover;
         self.indexer = Some(indexer);
         Ok(())
     }
@@ -447,6 +462,74 @@ impl LightProgramTest {
         .clone())
     }
 
+    /// Snapshots the indexer together with the on-chain accounts of its trees
+    /// and queues, plus any `additional_accounts` (e.g. mints, token pools or
+    /// program accounts a fixture depends on).
+    ///
+    /// The payer and `keypairs` (e.g. owners of compressed accounts) are
+    /// stored with their system accounts, so restored accounts can be spent.
+    /// Read `keypairs` back with [`TestIndexerSnapshot::keypairs`].
+    pub fn snapshot(
+        &self,
+        additional_accounts: &[Pubkey],
+        keypairs: &[&Keypair],
+    ) -> Result<TestIndexerSnapshot, RpcError> {
+        let mut snapshot = self.indexer()?.snapshot()?;
+        snapshot.payer = self.payer.to_bytes();
+        snapshot.keypairs = keypairs.iter().map(|keypair| keypair.to_bytes()).collect();
+        let mut pubkeys = snapshot.tree_account_pubkeys();
+        pubkeys.extend_from_slice(additional_accounts);
+        pubkeys.push(self.payer.pubkey());
+        pubkeys.extend(keypairs.iter().map(|keypair| keypair.pubkey()));
+        pubkeys.sort_unstable();
+        pubkeys.dedup();
+        snapshot.accounts = pubkeys
+            .into_iter()
+            .filter_map(|pubkey| {
+                self.context
+                    .get_account(&pubkey)
+                    .map(|account| AccountSnapshot::new(pubkey, &account))
+            })
+            .collect();
+        Ok(snapshot)
+    }
+
+    pub fn save_snapshot(
+        &self,
+        store: &dyn TestIndexerStore,
+        additional_accounts: &[Pubkey],
+        keypairs: &[&Keypair],
+    ) -> Result<(), RpcError> {
+        store.save(&self.snapshot(additional_accounts, keypairs)?)?;
+        Ok(())
+    }
+
+    /// Replaces the payer, the indexer and the snapshotted on-chain accounts
+    /// with the contents of `store`. Returns `false` if the store is empty.
+    pub fn resume_from_snapshot(&mut self, store: &dyn TestIndexerStore) -> Result<bool, RpcError> {
+        let Some(mut snapshot) = store.load()? else {
+            return Ok(false);
+        };
+        let payer = snapshot.payer()?;
+        for account in std::mem::take(&mut snapshot.accounts) {
+            let (pubkey, account) = account.into_account();
+            self.context.set_account(pubkey, account).map_err(|e| {
+                RpcError::CustomError(format!("Failed to restore account {}: {}", pubkey, e))
+            })?;
+        }
+        let governance_authority = match self.indexer.as_ref() {
+            Some(indexer) => indexer.governance_authority.insecure_clone(),
+            None => TestKeypairs::program_test_default().governance_authority,
+        };
+        let mut indexer =
+            TestIndexer::from_snapshot(snapshot, payer.insecure_clone(), governance_authority)?;
+        // The verifier programs are set up from the config, not the snapshot.
+        indexer.mock_prover = self.config.mock_prover;
+        self.indexer = Some(indexer);
+        self.payer = payer;
+        Ok(true)
+    }
+
     #[cfg(feature = "devenv")]
     pub fn disable_cold_state_mining(&mut self, program_id: solana_sdk::pubkey::Pubkey) {
         self.auto_mine_cold_state_programs
@@ -6,7 +6,11 @@ use borsh::BorshDeserialize;
 use light_client::{
     indexer::{CompressedAccount, CompressedTokenAccount, Context, Indexer, Response, TreeInfo},
     interface::{AccountInterface, MintInterface, MintState, TokenAccountInterface},
-    rpc::{LightClientConfig, Rpc, RpcError},
+    rpc::{
+        lut::AddressLookupTable,
+        simulation::{events_from_instructions, loaded_addresses},
+        CompressedStateDiff, LightClientConfig, LightSimulationResult, Rpc, RpcError,
+    },
 };
 use light_compressed_account::TreeType;
 use light_event::{
@@ -280,6 +284,70 @@ impl Rpc for LightProgramTest {
             .await
     }
 
+    async fn simulate_light_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<LightSimulationResult, RpcError> {
+        let meta = match self.context.simulate_transaction(transaction.clone()) {
+            Ok(result) => result.meta,
+            Err(failed) => {
+                return Ok(LightSimulationResult {
+                    err: Some(failed.err),
+                    logs: failed.meta.logs,
+                    units_consumed: Some(failed.meta.compute_units_consumed),
+                    events: Vec::new(),
+                    diff: CompressedStateDiff::default(),
+                });
+            }
+        };
+
+        let message = &transaction.message;
+        let mut account_keys = message.static_account_keys().to_vec();
+        if let Some(lookups) = message.address_table_lookups() {
+            let tables = lookups
+                .iter()
+                .map(|lookup| {
+                    let account =
+                        self.context
+                            .get_account(&lookup.account_key)
+                            .ok_or_else(|| {
+                                RpcError::AccountDoesNotExist(lookup.account_key.to_string())
+                            })?;
+                    let table = AddressLookupTable::deserialize(&account.data).map_err(|e| {
+                        RpcError::CustomError(format!(
+                            "Failed to deserialize AddressLookupTable: {e:?}"
+                        ))
+                    })?;
+                    Ok(AddressLookupTableAccount {
+                        key: lookup.account_key,
+                        addresses: table.addresses.to_vec(),
+                    })
+                })
+                .collect::<Result<Vec<_>, RpcError>>()?;
+            account_keys.extend(loaded_addresses(lookups, &tables)?);
+        }
+        let inner_instructions: Vec<Vec<_>> = meta
+            .inner_instructions
+            .iter()
+            .map(|inner| {
+                inner
+                    .iter()
+                    .map(|inner| inner.instruction.clone())
+                    .collect()
+            })
+            .collect();
+        let events =
+            events_from_instructions(&account_keys, message.instructions(), &inner_instructions)?;
+        let diff = CompressedStateDiff::resolve(&events, self.indexer.as_ref()).await;
+        Ok(LightSimulationResult {
+            err: None,
+            logs: meta.logs,
+            units_consumed: Some(meta.compute_units_consumed),
+            events,
+            diff,
+        })
+    }
+
     async fn create_and_send_transaction_with_public_event(
         &mut self,
         instruction: &[Instruction],
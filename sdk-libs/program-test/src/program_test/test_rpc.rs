@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use light_client::rpc::{LightClient, Rpc, RpcError};
 use light_compressible::rent::SLOTS_PER_EPOCH;
+use light_prover_client::proof_client::ProofClient;
 use solana_account::Account;
 use solana_sdk::{
     clock::{Clock, Slot},
@@ -104,6 +105,11 @@ pub trait TestRpc: Rpc + Sized {
     fn set_account(&mut self, address: Pubkey, account: Account);
     fn warp_to_slot(&mut self, slot: Slot) -> Result<(), RpcError>;
 
+    /// Client for batch proofs, e.g. of the forester helpers.
+    fn proof_client(&self) -> ProofClient {
+        ProofClient::local()
+    }
+
     /// Warps current slot forward by slots.
     /// Claims and compresses compressible ctoken accounts.
     async fn warp_slot_forward(&mut self, slot: Slot) -> Result<(), RpcError>;
@@ -145,6 +151,17 @@ impl TestRpc for LightProgramTest {
         Ok(())
     }
 
+    /// Returns mock proofs if [`ProgramTestConfig::mock_prover`] is set.
+    ///
+    /// [`ProgramTestConfig::mock_prover`]: crate::ProgramTestConfig::mock_prover
+    fn proof_client(&self) -> ProofClient {
+        if self.config.mock_prover {
+            ProofClient::mock()
+        } else {
+            ProofClient::local()
+        }
+    }
+
     /// Warps current slot forward by slots.
     /// Claims and compresses compressible ctoken accounts and program PDAs (auto compress).
     async fn warp_slot_forward(&mut self, slot: Slot) -> Result<(), RpcError> {
@@ -32,6 +32,22 @@ const LIGHT_COMPRESSED_TOKEN_ID: Pubkey = pubkey!("cTokenmWW8bLPjZEBAUgYy3zKxQZW
 /// 4. light_system_program program
 pub fn setup_light_programs(
     additional_programs: Option<Vec<(&'static str, Pubkey)>>,
+) -> Result<LiteSVM, RpcError> {
+    setup_light_programs_inner(additional_programs, false)
+}
+
+/// Like [`setup_light_programs`] but loads the account_compression and
+/// light_system_program binaries from the `mock-prover` subdirectory. These
+/// are built with the `mock-proof` feature by `just programs build-mock-prover`.
+pub fn setup_light_programs_with_mock_prover(
+    additional_programs: Option<Vec<(&'static str, Pubkey)>>,
+) -> Result<LiteSVM, RpcError> {
+    setup_light_programs_inner(additional_programs, true)
+}
+
+fn setup_light_programs_inner(
+    additional_programs: Option<Vec<(&'static str, Pubkey)>>,
+    mock_prover: bool,
 ) -> Result<LiteSVM, RpcError> {
     let program_test = LiteSVM::new().with_log_bytes_limit(Some(100_000));
     let program_test = program_test.with_compute_budget(ComputeBudget {
@@ -49,13 +65,18 @@ pub fn setup_light_programs(
             "Found invalid light binary path {:?}",
             light_bin_path
         )))?;
+    let verifier_bin_path = if mock_prover {
+        format!("{}/mock-prover", light_bin_path)
+    } else {
+        light_bin_path.to_string()
+    };
     let path = format!("{}/light_registry.so", light_bin_path);
     program_test
         .add_program_from_file(LIGHT_REGISTRY_ID, path.clone())
         .inspect_err(|_| {
             println!("Program light_registry bin not found in {}", path);
         })?;
-    let path = format!("{}/account_compression.so", light_bin_path);
+    let path = format!("{}/account_compression.so", verifier_bin_path);
     program_test
         .add_program_from_file(ACCOUNT_COMPRESSION_ID, path.clone())
         .inspect_err(|_| {
@@ -74,7 +95,7 @@ pub fn setup_light_programs(
             println!("Program spl_noop bin not found in {}", path);
         })?;
 
-    let path = format!("{}/light_system_program_pinocchio.so", light_bin_path);
+    let path = format!("{}/light_system_program_pinocchio.so", verifier_bin_path);
     program_test
         .add_program_from_file(light_sdk::constants::LIGHT_SYSTEM_PROGRAM_ID, path.clone())
         .inspect_err(|_| {
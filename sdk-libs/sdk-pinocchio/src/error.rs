@@ -104,6 +104,10 @@ pub enum LightSdkError {
     NotEnoughAccountKeys,
     #[error("Missing required signature")]
     MissingRequiredSignature,
+    #[error("Account is not a Merkle tree of the expected type")]
+    InvalidMerkleTreeAccount,
+    #[error("Root index is out of bounds of the root history")]
+    InvalidRootIndex,
 }
 
 impl From<ProgramError> for LightSdkError {
@@ -169,6 +173,8 @@ impl From<LightSdkTypesError> for LightSdkError {
             LightSdkTypesError::ProgramError(code) => {
                 LightSdkError::ProgramError(ProgramError::Custom(code))
             }
+            LightSdkTypesError::InvalidMerkleTreeAccount => LightSdkError::InvalidMerkleTreeAccount,
+            LightSdkTypesError::InvalidRootIndex => LightSdkError::InvalidRootIndex,
         }
     }
 }
@@ -224,6 +230,8 @@ impl From<LightSdkError> for u32 {
             LightSdkError::CpiFailed => 16044,
             LightSdkError::NotEnoughAccountKeys => 16045,
             LightSdkError::MissingRequiredSignature => 16046,
+            LightSdkError::InvalidMerkleTreeAccount => 16051,
+            LightSdkError::InvalidRootIndex => 16052,
         }
     }
 }
@@ -10,11 +10,14 @@
 //! | [`address`] | Address derivation (v1 and v2) |
 //! | [`cpi`] | Light System Program CPI invocation |
 //! | [`instruction`] | Instruction types and helpers |
+//! | `merkle_tree` | Read v1 and v2 Merkle tree roots (requires `merkle-tree` feature) |
 
 pub mod address;
 pub mod cpi;
 pub mod error;
 pub mod instruction;
+#[cfg(feature = "merkle-tree")]
+pub mod merkle_tree;
 // TODO: Add tree_info module with helpers for packing/unpacking address tree info
 // Similar to light-sdk's tree_info.rs but adapted for pinocchio (no Anchor dependencies)
 // Should include: pack_address_tree_info, unpack_address_tree_info, AddressTreeInfo struct
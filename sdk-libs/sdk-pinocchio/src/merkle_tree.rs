@@ -0,0 +1,31 @@
+pub use light_sdk_types::merkle_tree::MerkleTreeInfo;
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::error::LightSdkError;
+
+/// Reads a root by index from a v1 or v2 state Merkle tree.
+/// The tree version is detected by the account discriminator.
+pub fn read_state_merkle_tree_root(
+    account_info: &AccountInfo,
+    root_index: u16,
+) -> Result<[u8; 32], ProgramError> {
+    light_sdk_types::merkle_tree::read_state_merkle_tree_root(account_info, root_index)
+        .map_err(|e| LightSdkError::from(e).into())
+}
+
+/// Reads a root by index from a v1 or v2 address Merkle tree.
+/// The tree version is detected by the account discriminator.
+pub fn read_address_merkle_tree_root(
+    account_info: &AccountInfo,
+    root_index: u16,
+) -> Result<[u8; 32], ProgramError> {
+    light_sdk_types::merkle_tree::read_address_merkle_tree_root(account_info, root_index)
+        .map_err(|e| LightSdkError::from(e).into())
+}
+
+/// Reads tree type, next index, sequence number and root history length
+/// of a v1 or v2 state or address Merkle tree.
+pub fn read_merkle_tree_info(account_info: &AccountInfo) -> Result<MerkleTreeInfo, ProgramError> {
+    light_sdk_types::merkle_tree::read_merkle_tree_info(account_info)
+        .map_err(|e| LightSdkError::from(e).into())
+}
@@ -72,6 +72,10 @@ pub enum LightSdkTypesError {
     MissingRequiredSignature,
     #[error("Program error: {0}")]
     ProgramError(u32),
+    #[error("Account is not a Merkle tree of the expected type")]
+    InvalidMerkleTreeAccount,
+    #[error("Root index is out of bounds of the root history")]
+    InvalidRootIndex,
 }
 
 #[cfg(feature = "anchor")]
@@ -138,6 +142,8 @@ impl From<LightSdkTypesError> for u32 {
             LightSdkTypesError::NotEnoughAccountKeys => 14046,
             LightSdkTypesError::MissingRequiredSignature => 14047,
             LightSdkTypesError::ProgramError(code) => code,
+            LightSdkTypesError::InvalidMerkleTreeAccount => 14048,
+            LightSdkTypesError::InvalidRootIndex => 14049,
         }
     }
 }
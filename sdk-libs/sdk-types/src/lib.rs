@@ -9,6 +9,7 @@
 //! | [`CpiSigner`] | Program ID, signer, and bump for CPI invocation |
 //! | [`address`] | Address derivation functions (v1 and v2) |
 //! | [`constants`] | Protocol program IDs and discriminators |
+//! | `merkle_tree` | Read v1 and v2 Merkle tree roots (requires `merkle-tree` feature) |
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -21,6 +22,8 @@ pub mod cpi_accounts;
 pub mod cpi_context_write;
 pub mod error;
 pub mod instruction;
+#[cfg(feature = "merkle-tree")]
+pub mod merkle_tree;
 #[cfg(feature = "std")]
 pub mod pack_accounts;
 
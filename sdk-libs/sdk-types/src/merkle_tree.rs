@@ -0,0 +1,166 @@
+//! Read roots of state and address Merkle trees on-chain.
+//!
+//! Supports v1 concurrent and indexed Merkle trees
+//! and v2 batched Merkle trees.
+//! The tree version is detected by the account discriminator,
+//! so programs can verify inclusion against trees of both versions.
+
+use light_account_checks::{checks::check_owner, error::AccountError, AccountInfoTrait};
+use light_batched_merkle_tree::merkle_tree::BatchedMerkleTreeAccount;
+use light_compressed_account::{TreeType, ADDRESS_MERKLE_TREE_TYPE_V2, STATE_MERKLE_TREE_TYPE_V2};
+use light_concurrent_merkle_tree::zero_copy::ConcurrentMerkleTreeZeroCopy;
+use light_hasher::Poseidon;
+
+use crate::{
+    error::{LightSdkTypesError, Result},
+    ACCOUNT_COMPRESSION_PROGRAM_ID, ADDRESS_MERKLE_TREE_HEIGHT, STATE_MERKLE_TREE_HEIGHT,
+};
+
+/// StateMerkleTreeAccount discriminator
+pub const STATE_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [172, 43, 172, 186, 29, 73, 219, 84];
+/// AddressMerkleTreeAccount discriminator
+pub const ADDRESS_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [11, 161, 175, 9, 212, 229, 73, 73];
+/// BatchedMerkleTreeAccount discriminator
+pub const BATCHED_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = *b"BatchMta";
+/// Length of discriminator and metadata of v1 Merkle tree accounts.
+/// The Merkle tree is serialized after the metadata.
+pub const V1_MERKLE_TREE_ACCOUNT_METADATA_LEN: usize = 224;
+
+// Both v1 tree types are serialized as concurrent Merkle tree of the same height,
+// the indexed Merkle tree only appends its changelog.
+const _: () = assert!(STATE_MERKLE_TREE_HEIGHT == ADDRESS_MERKLE_TREE_HEIGHT);
+
+/// Version-independent view of a Merkle tree account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleTreeInfo {
+    pub tree_type: TreeType,
+    pub height: u32,
+    /// Index of the next leaf to be appended.
+    pub next_index: u64,
+    /// Incremented with every root update.
+    pub sequence_number: u64,
+    /// Number of roots in the root history.
+    pub root_history_len: usize,
+    /// Number of roots the root history retains.
+    pub root_history_capacity: usize,
+}
+
+/// Reads a root by index from a v1 or v2 state Merkle tree.
+pub fn read_state_merkle_tree_root<A: AccountInfoTrait>(
+    account_info: &A,
+    root_index: u16,
+) -> Result<[u8; 32]> {
+    match read_tree_type(account_info)? {
+        TreeType::StateV1 => read_v1_root(account_info, root_index),
+        TreeType::StateV2 => {
+            let tree = BatchedMerkleTreeAccount::state_from_account_info(account_info)
+                .map_err(|e| LightSdkTypesError::ProgramError(e.into()))?;
+            tree.root_history
+                .get(root_index as usize)
+                .copied()
+                .ok_or(LightSdkTypesError::InvalidRootIndex)
+        }
+        _ => Err(LightSdkTypesError::InvalidMerkleTreeAccount),
+    }
+}
+
+/// Reads a root by index from a v1 or v2 address Merkle tree.
+pub fn read_address_merkle_tree_root<A: AccountInfoTrait>(
+    account_info: &A,
+    root_index: u16,
+) -> Result<[u8; 32]> {
+    match read_tree_type(account_info)? {
+        TreeType::AddressV1 => read_v1_root(account_info, root_index),
+        TreeType::AddressV2 => {
+            let tree = BatchedMerkleTreeAccount::address_from_account_info(account_info)
+                .map_err(|e| LightSdkTypesError::ProgramError(e.into()))?;
+            tree.root_history
+                .get(root_index as usize)
+                .copied()
+                .ok_or(LightSdkTypesError::InvalidRootIndex)
+        }
+        _ => Err(LightSdkTypesError::InvalidMerkleTreeAccount),
+    }
+}
+
+/// Reads type, next index, sequence number and root history
+/// of a v1 or v2 state or address Merkle tree.
+pub fn read_merkle_tree_info<A: AccountInfoTrait>(account_info: &A) -> Result<MerkleTreeInfo> {
+    let tree_type = read_tree_type(account_info)?;
+    match tree_type {
+        TreeType::StateV1 | TreeType::AddressV1 => {
+            let account_data = account_info.try_borrow_data()?;
+            let tree = v1_tree_from_bytes(&account_data)?;
+            Ok(MerkleTreeInfo {
+                tree_type,
+                height: tree.height as u32,
+                next_index: tree.next_index() as u64,
+                sequence_number: tree.sequence_number() as u64,
+                root_history_len: tree.roots.len(),
+                root_history_capacity: tree.roots.capacity(),
+            })
+        }
+        TreeType::StateV2 | TreeType::AddressV2 => {
+            let tree = if tree_type == TreeType::StateV2 {
+                BatchedMerkleTreeAccount::state_from_account_info(account_info)
+            } else {
+                BatchedMerkleTreeAccount::address_from_account_info(account_info)
+            }
+            .map_err(|e| LightSdkTypesError::ProgramError(e.into()))?;
+            Ok(MerkleTreeInfo {
+                tree_type,
+                height: tree.height,
+                next_index: tree.next_index,
+                sequence_number: tree.sequence_number,
+                root_history_len: tree.root_history.len(),
+                root_history_capacity: tree.root_history.capacity(),
+            })
+        }
+        TreeType::Unknown => Err(LightSdkTypesError::InvalidMerkleTreeAccount),
+    }
+}
+
+/// Checks the account owner and detects the tree type by discriminator.
+/// Batched Merkle trees store the tree type after the discriminator.
+fn read_tree_type<A: AccountInfoTrait>(account_info: &A) -> Result<TreeType> {
+    check_owner(&ACCOUNT_COMPRESSION_PROGRAM_ID, account_info)?;
+    let account_data = account_info.try_borrow_data()?;
+    if account_data.len() < 8 {
+        return Err(AccountError::InvalidAccountSize.into());
+    }
+    let discriminator: [u8; 8] = account_data[0..8].try_into().unwrap();
+    match discriminator {
+        STATE_MERKLE_TREE_DISCRIMINATOR => Ok(TreeType::StateV1),
+        ADDRESS_MERKLE_TREE_DISCRIMINATOR => Ok(TreeType::AddressV1),
+        BATCHED_MERKLE_TREE_DISCRIMINATOR => {
+            let tree_type = account_data
+                .get(8..16)
+                .ok_or(AccountError::InvalidAccountSize)?;
+            match u64::from_le_bytes(tree_type.try_into().unwrap()) {
+                STATE_MERKLE_TREE_TYPE_V2 => Ok(TreeType::StateV2),
+                ADDRESS_MERKLE_TREE_TYPE_V2 => Ok(TreeType::AddressV2),
+                _ => Err(LightSdkTypesError::InvalidMerkleTreeAccount),
+            }
+        }
+        _ => Err(AccountError::InvalidDiscriminator.into()),
+    }
+}
+
+fn read_v1_root<A: AccountInfoTrait>(account_info: &A, root_index: u16) -> Result<[u8; 32]> {
+    let account_data = account_info.try_borrow_data()?;
+    let tree = v1_tree_from_bytes(&account_data)?;
+    tree.roots
+        .get(root_index as usize)
+        .copied()
+        .ok_or(LightSdkTypesError::InvalidRootIndex)
+}
+
+fn v1_tree_from_bytes(
+    account_data: &[u8],
+) -> Result<ConcurrentMerkleTreeZeroCopy<'_, Poseidon, STATE_MERKLE_TREE_HEIGHT>> {
+    let data = account_data
+        .get(V1_MERKLE_TREE_ACCOUNT_METADATA_LEN..)
+        .ok_or(AccountError::InvalidAccountSize)?;
+    ConcurrentMerkleTreeZeroCopy::from_bytes_zero_copy(data)
+        .map_err(|e| LightSdkTypesError::ProgramError(e.into()))
+}
@@ -0,0 +1,277 @@
+#![cfg(feature = "merkle-tree")]
+
+use light_account_checks::{
+    account_info::test_account_info::solana_program::TestAccount, error::AccountError,
+};
+use light_batched_merkle_tree::merkle_tree::{
+    get_merkle_tree_account_size, BatchedMerkleTreeAccount,
+};
+use light_compressed_account::TreeType;
+use light_concurrent_merkle_tree::zero_copy::ConcurrentMerkleTreeZeroCopyMut;
+use light_hasher::{Hasher, Poseidon};
+use light_indexed_merkle_tree::{zero_copy::IndexedMerkleTreeZeroCopyMut, IndexedMerkleTree};
+use light_merkle_tree_metadata::merkle_tree::MerkleTreeMetadata;
+use light_sdk_types::{
+    error::LightSdkTypesError,
+    merkle_tree::{
+        read_address_merkle_tree_root, read_merkle_tree_info, read_state_merkle_tree_root,
+        MerkleTreeInfo, ADDRESS_MERKLE_TREE_DISCRIMINATOR, STATE_MERKLE_TREE_DISCRIMINATOR,
+        V1_MERKLE_TREE_ACCOUNT_METADATA_LEN,
+    },
+    ACCOUNT_COMPRESSION_PROGRAM_ID,
+};
+use solana_pubkey::Pubkey;
+
+const V1_CHANGELOG: usize = 10;
+const V1_ROOTS: usize = 20;
+const V1_INDEXED_CHANGELOG: usize = 10;
+const V2_ROOTS: u32 = 10;
+
+fn v1_state_tree_account(num_leaves: u8) -> TestAccount {
+    let tree_size =
+        light_concurrent_merkle_tree::ConcurrentMerkleTree::<Poseidon, 26>::size_in_account(
+            26,
+            V1_CHANGELOG,
+            V1_ROOTS,
+            0,
+        );
+    let mut account = TestAccount::new(
+        Pubkey::new_unique(),
+        Pubkey::new_from_array(ACCOUNT_COMPRESSION_PROGRAM_ID),
+        V1_MERKLE_TREE_ACCOUNT_METADATA_LEN + tree_size,
+    );
+    account.data[0..8].copy_from_slice(&STATE_MERKLE_TREE_DISCRIMINATOR);
+    let mut tree = ConcurrentMerkleTreeZeroCopyMut::<Poseidon, 26>::from_bytes_zero_copy_init(
+        &mut account.data[V1_MERKLE_TREE_ACCOUNT_METADATA_LEN..],
+        26,
+        0,
+        V1_CHANGELOG,
+        V1_ROOTS,
+    )
+    .unwrap();
+    tree.init().unwrap();
+    for i in 0..num_leaves {
+        let mut leaf = [0u8; 32];
+        leaf[31] = i + 1;
+        tree.append(&leaf).unwrap();
+    }
+    account
+}
+
+fn v1_address_tree_account() -> TestAccount {
+    let tree_size = IndexedMerkleTree::<Poseidon, usize, 26, 16>::size_in_account(
+        26,
+        V1_CHANGELOG,
+        V1_ROOTS,
+        0,
+        V1_INDEXED_CHANGELOG,
+    );
+    let mut account = TestAccount::new(
+        Pubkey::new_unique(),
+        Pubkey::new_from_array(ACCOUNT_COMPRESSION_PROGRAM_ID),
+        V1_MERKLE_TREE_ACCOUNT_METADATA_LEN + tree_size,
+    );
+    account.data[0..8].copy_from_slice(&ADDRESS_MERKLE_TREE_DISCRIMINATOR);
+    let mut tree =
+        IndexedMerkleTreeZeroCopyMut::<Poseidon, usize, 26, 16>::from_bytes_zero_copy_init(
+            &mut account.data[V1_MERKLE_TREE_ACCOUNT_METADATA_LEN..],
+            26,
+            0,
+            V1_CHANGELOG,
+            V1_ROOTS,
+            V1_INDEXED_CHANGELOG,
+        )
+        .unwrap();
+    tree.init().unwrap();
+    tree.add_highest_element().unwrap();
+    account
+}
+
+fn v2_tree_account(tree_type: TreeType) -> TestAccount {
+    let (batch_size, zkp_batch_size, bloom_filter_capacity) = (10, 5, 8000);
+    let height = if tree_type == TreeType::StateV2 {
+        26
+    } else {
+        40
+    };
+    let size = get_merkle_tree_account_size(
+        batch_size,
+        bloom_filter_capacity,
+        zkp_batch_size,
+        V2_ROOTS,
+        height,
+    );
+    let mut account = TestAccount::new(
+        Pubkey::new_unique(),
+        Pubkey::new_from_array(ACCOUNT_COMPRESSION_PROGRAM_ID),
+        size,
+    );
+    BatchedMerkleTreeAccount::init(
+        &mut account.data,
+        &account.key.to_bytes().into(),
+        MerkleTreeMetadata::default(),
+        V2_ROOTS,
+        batch_size,
+        zkp_batch_size,
+        height,
+        1,
+        bloom_filter_capacity,
+        tree_type,
+    )
+    .unwrap();
+    account
+}
+
+#[test]
+fn test_read_v1_state_tree() {
+    let mut account = v1_state_tree_account(2);
+    let (expected_root, expected_sequence_number) = {
+        let tree = ConcurrentMerkleTreeZeroCopyMut::<Poseidon, 26>::from_bytes_zero_copy_mut(
+            &mut account.data[V1_MERKLE_TREE_ACCOUNT_METADATA_LEN..],
+        )
+        .unwrap();
+        (tree.root(), tree.sequence_number() as u64)
+    };
+    let account_info = account.get_account_info();
+
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, 2).unwrap(),
+        expected_root
+    );
+    assert_eq!(
+        read_merkle_tree_info(&account_info).unwrap(),
+        MerkleTreeInfo {
+            tree_type: TreeType::StateV1,
+            height: 26,
+            next_index: 2,
+            sequence_number: expected_sequence_number,
+            root_history_len: 3,
+            root_history_capacity: V1_ROOTS,
+        }
+    );
+    // Roots which have not been written yet are out of bounds.
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, 3),
+        Err(LightSdkTypesError::InvalidRootIndex)
+    );
+    assert_eq!(
+        read_address_merkle_tree_root(&account_info, 0),
+        Err(LightSdkTypesError::InvalidMerkleTreeAccount)
+    );
+}
+
+#[test]
+fn test_read_v1_address_tree() {
+    let mut account = v1_address_tree_account();
+    let (expected_root, expected_sequence_number, root_history_len) = {
+        let tree =
+            IndexedMerkleTreeZeroCopyMut::<Poseidon, usize, 26, 16>::from_bytes_zero_copy_mut(
+                &mut account.data[V1_MERKLE_TREE_ACCOUNT_METADATA_LEN..],
+            )
+            .unwrap();
+        (tree.root(), tree.sequence_number() as u64, tree.roots.len())
+    };
+    let account_info = account.get_account_info();
+
+    // The indexed Merkle tree is read as the concurrent Merkle tree it wraps,
+    // the indexed changelog appended to it is ignored.
+    assert_eq!(
+        read_address_merkle_tree_root(&account_info, (root_history_len - 1) as u16).unwrap(),
+        expected_root
+    );
+    assert_eq!(
+        read_merkle_tree_info(&account_info).unwrap(),
+        MerkleTreeInfo {
+            tree_type: TreeType::AddressV1,
+            height: 26,
+            // Initialization appends the zero and the highest element.
+            next_index: 2,
+            sequence_number: expected_sequence_number,
+            root_history_len,
+            root_history_capacity: V1_ROOTS,
+        }
+    );
+    assert_eq!(
+        read_address_merkle_tree_root(&account_info, root_history_len as u16),
+        Err(LightSdkTypesError::InvalidRootIndex)
+    );
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, 0),
+        Err(LightSdkTypesError::InvalidMerkleTreeAccount)
+    );
+}
+
+#[test]
+fn test_read_v2_state_tree() {
+    let mut account = v2_tree_account(TreeType::StateV2);
+    let last_index = {
+        let tree = BatchedMerkleTreeAccount::state_from_bytes(
+            &mut account.data,
+            &account.key.to_bytes().into(),
+        )
+        .unwrap();
+        tree.root_history.last_index()
+    };
+    let account_info = account.get_account_info();
+
+    // The latest root is the root of an empty tree.
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, last_index as u16).unwrap(),
+        Poseidon::zero_bytes()[26]
+    );
+    assert_eq!(
+        read_merkle_tree_info(&account_info).unwrap(),
+        MerkleTreeInfo {
+            tree_type: TreeType::StateV2,
+            height: 26,
+            next_index: 0,
+            sequence_number: 0,
+            root_history_len: V2_ROOTS as usize,
+            root_history_capacity: V2_ROOTS as usize,
+        }
+    );
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, V2_ROOTS as u16),
+        Err(LightSdkTypesError::InvalidRootIndex)
+    );
+    assert_eq!(
+        read_address_merkle_tree_root(&account_info, 0),
+        Err(LightSdkTypesError::InvalidMerkleTreeAccount)
+    );
+}
+
+#[test]
+fn test_read_v2_address_tree() {
+    let mut account = v2_tree_account(TreeType::AddressV2);
+    let account_info = account.get_account_info();
+
+    let info = read_merkle_tree_info(&account_info).unwrap();
+    assert_eq!(info.tree_type, TreeType::AddressV2);
+    assert_eq!(info.height, 40);
+    // The initialized indexed Merkle tree contains one element.
+    assert_eq!(info.next_index, 1);
+    assert!(read_address_merkle_tree_root(&account_info, 0).is_ok());
+    assert_eq!(
+        read_state_merkle_tree_root(&account_info, 0),
+        Err(LightSdkTypesError::InvalidMerkleTreeAccount)
+    );
+}
+
+#[test]
+fn test_read_invalid_account() {
+    // Wrong owner.
+    let mut account = v1_state_tree_account(0);
+    account.owner = Pubkey::new_unique();
+    assert_eq!(
+        read_merkle_tree_info(&account.get_account_info()),
+        Err(AccountError::AccountOwnedByWrongProgram.into())
+    );
+
+    // Unknown discriminator.
+    let mut account = v1_state_tree_account(0);
+    account.data[0..8].copy_from_slice(&[1u8; 8]);
+    assert_eq!(
+        read_merkle_tree_info(&account.get_account_info()),
+        Err(AccountError::InvalidDiscriminator.into())
+    );
+}
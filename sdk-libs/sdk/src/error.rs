@@ -121,6 +121,10 @@ pub enum LightSdkError {
     ZeroCopyUnpackedVariant,
     #[error("Rent sponsor account does not match the expected PDA from config")]
     InvalidRentSponsor,
+    #[error("Account is not a Merkle tree of the expected type")]
+    InvalidMerkleTreeAccount,
+    #[error("Root index is out of bounds of the root history")]
+    InvalidRootIndex,
 }
 
 impl From<LightSdkError> for ProgramError {
@@ -151,6 +155,8 @@ impl From<LightSdkError> for LightSdkTypesError {
                 LightSdkTypesError::CompressedAccountError(e)
             }
             LightSdkError::ProgramError(e) => LightSdkTypesError::ProgramError(u64::from(e) as u32),
+            LightSdkError::InvalidMerkleTreeAccount => LightSdkTypesError::InvalidMerkleTreeAccount,
+            LightSdkError::InvalidRootIndex => LightSdkTypesError::InvalidRootIndex,
             _ => LightSdkTypesError::ConstraintViolation,
         }
     }
@@ -204,6 +210,8 @@ impl From<LightSdkTypesError> for LightSdkError {
             LightSdkTypesError::ProgramError(code) => {
                 LightSdkError::ProgramError(ProgramError::Custom(code))
             }
+            LightSdkTypesError::InvalidMerkleTreeAccount => LightSdkError::InvalidMerkleTreeAccount,
+            LightSdkTypesError::InvalidRootIndex => LightSdkError::InvalidRootIndex,
         }
     }
 }
@@ -265,6 +273,8 @@ impl From<LightSdkError> for u32 {
             LightSdkError::TokenPrepareCalled => 16048,
             LightSdkError::ZeroCopyUnpackedVariant => 16049,
             LightSdkError::InvalidRentSponsor => 16050,
+            LightSdkError::InvalidMerkleTreeAccount => 16051,
+            LightSdkError::InvalidRootIndex => 16052,
         }
     }
 }
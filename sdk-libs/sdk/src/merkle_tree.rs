@@ -1,19 +1,49 @@
+pub use light_sdk_types::merkle_tree::MerkleTreeInfo;
 use solana_account_info::AccountInfo;
 use solana_msg::msg;
 use solana_program_error::ProgramError;
 
+use crate::error::LightSdkError;
+
+/// Reads a root by index from a v1 or v2 state Merkle tree.
+/// The tree version is detected by the account discriminator.
+pub fn read_state_merkle_tree_root(
+    account_info: &AccountInfo,
+    root_index: u16,
+) -> Result<[u8; 32], ProgramError> {
+    light_sdk_types::merkle_tree::read_state_merkle_tree_root(account_info, root_index)
+        .map_err(|e| LightSdkError::from(e).into())
+}
+
+/// Reads a root by index from a v1 or v2 address Merkle tree.
+/// The tree version is detected by the account discriminator.
+pub fn read_address_merkle_tree_root(
+    account_info: &AccountInfo,
+    root_index: u16,
+) -> Result<[u8; 32], ProgramError> {
+    light_sdk_types::merkle_tree::read_address_merkle_tree_root(account_info, root_index)
+        .map_err(|e| LightSdkError::from(e).into())
+}
+
+/// Reads tree type, next index, sequence number and root history length
+/// of a v1 or v2 state or address Merkle tree.
+pub fn read_merkle_tree_info(account_info: &AccountInfo) -> Result<MerkleTreeInfo, ProgramError> {
+    light_sdk_types::merkle_tree::read_merkle_tree_info(account_info)
+        .map_err(|e| LightSdkError::from(e).into())
+}
+
 pub mod v1 {
     use light_account_checks::checks::check_owner;
     use light_concurrent_merkle_tree::zero_copy::ConcurrentMerkleTreeZeroCopy;
     use light_hasher::Poseidon;
+    pub use light_sdk_types::merkle_tree::{
+        STATE_MERKLE_TREE_DISCRIMINATOR,
+        V1_MERKLE_TREE_ACCOUNT_METADATA_LEN as STATE_MERKLE_TREE_ACCOUNT_METADATA_LEN,
+    };
     use light_sdk_types::ACCOUNT_COMPRESSION_PROGRAM_ID;
 
     use super::*;
 
-    /// StateMerkleTreeAccount discriminator
-    pub const STATE_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [172, 43, 172, 186, 29, 73, 219, 84];
-    pub const STATE_MERKLE_TREE_ACCOUNT_METADATA_LEN: usize = 224;
-
     /// Reads a root from the concurrent state merkle tree by index
     pub fn read_state_merkle_tree_root(
         account_info: &AccountInfo,
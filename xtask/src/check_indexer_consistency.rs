@@ -0,0 +1,122 @@
+use std::str::FromStr;
+
+use clap::Parser;
+use light_client::{
+    indexer::{
+        consistency::{check_tree_consistency, Diagnosis},
+        photon_indexer::PhotonIndexer,
+    },
+    rpc::{LightClient, LightClientConfig},
+};
+use solana_sdk::{bs58, pubkey::Pubkey};
+
+#[derive(Debug, Parser)]
+pub struct Options {
+    /// Pubkeys of the Merkle trees to check (v1 or v2, state or address)
+    #[clap(long, value_delimiter = ',', required = true)]
+    pubkeys: Vec<String>,
+    /// Network: mainnet, devnet, local, or custom URL
+    #[clap(long, default_value = "mainnet")]
+    network: String,
+    /// Photon indexer URL, including the api key if required
+    #[clap(long, default_value = "http://127.0.0.1:8784")]
+    photon_url: String,
+}
+
+pub async fn check_indexer_consistency(options: Options) -> anyhow::Result<()> {
+    let rpc_url = match options.network.as_str() {
+        "local" => String::from("http://127.0.0.1:8899"),
+        "devnet" => String::from("https://api.devnet.solana.com"),
+        "mainnet" => String::from("https://api.mainnet-beta.solana.com"),
+        _ => options.network.clone(),
+    };
+
+    let rpc = LightClient::new(LightClientConfig {
+        url: rpc_url.clone(),
+        photon_url: None,
+        commitment_config: None,
+        fetch_active_tree: false,
+    })
+    .await?;
+    let mut indexer = PhotonIndexer::new(options.photon_url.clone());
+
+    println!("RPC URL: {}", rpc_url);
+    println!("Photon URL: {}", options.photon_url);
+    println!();
+
+    let mut num_inconsistent = 0;
+    for pubkey in options.pubkeys.iter() {
+        let pubkey = Pubkey::from_str(pubkey)?;
+        let report = check_tree_consistency(&rpc, &mut indexer, pubkey).await?;
+
+        println!("=== {} ({}) ===", pubkey, report.onchain.tree_type);
+        println!(
+            "Slot: on-chain {}, indexer {}",
+            report.onchain.slot, report.indexer.slot
+        );
+        println!(
+            "On-chain root: {}",
+            bs58::encode(report.onchain.root).into_string()
+        );
+        if let Some(root) = report.indexer.root {
+            println!("Indexer root: {}", bs58::encode(root).into_string());
+        }
+        if let Some(root) = report.indexer.rebuilt_root {
+            println!(
+                "Root rebuilt from indexer subtrees: {}",
+                bs58::encode(root).into_string()
+            );
+        }
+        println!(
+            "Next index: on-chain {}, indexer {}",
+            report.onchain.next_index,
+            display_option(report.indexer.next_index)
+        );
+        println!(
+            "Sequence number: on-chain {}, indexer {}",
+            report.onchain.sequence_number,
+            display_option(report.indexer.sequence_number)
+        );
+        for pending in report.onchain.pending.iter() {
+            let indexer_pending = report
+                .indexer
+                .pending
+                .iter()
+                .find(|indexer_pending| indexer_pending.queue == pending.queue)
+                .map(|indexer_pending| indexer_pending.num_elements);
+            println!(
+                "Pending {} elements: on-chain {}, indexer {}",
+                pending.queue,
+                pending.num_elements,
+                display_option(indexer_pending)
+            );
+        }
+        for divergence in report.divergences.iter() {
+            println!("  - {}", divergence);
+        }
+        match report.diagnosis() {
+            Diagnosis::Consistent => println!("Result: consistent"),
+            Diagnosis::Lagging => println!("Result: indexer is lagging behind the chain"),
+            Diagnosis::Corrupted => println!("Result: indexer state is corrupted"),
+        }
+        if !report.is_consistent() {
+            num_inconsistent += 1;
+        }
+        println!();
+    }
+
+    if num_inconsistent > 0 {
+        anyhow::bail!(
+            "{} of {} trees diverge from the indexer",
+            num_inconsistent,
+            options.pubkeys.len()
+        );
+    }
+    Ok(())
+}
+
+fn display_option(value: Option<u64>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "n/a".to_string())
+}
@@ -1,11 +1,14 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
 use clap::Parser;
 use dirs::home_dir;
 use light_batched_merkle_tree::initialize_state_tree::InitStateTreeAccountsInstructionData;
 use light_client::rpc::{LightClient, LightClientConfig, Rpc};
 use light_program_test::accounts::state_tree_v2::create_batched_state_merkle_tree;
-use solana_sdk::signature::{read_keypair_file, write_keypair_file, Keypair, Signer};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+};
 
 #[derive(Debug, Parser)]
 pub struct Options {
@@ -28,6 +31,13 @@ pub struct Options {
     /// mainnet, testnet
     #[clap(long)]
     config: Option<String>,
+    /// Creates a tree owned by this program, does not require the protocol authority.
+    /// The payer must be the program owner, programs create their trees via cpi.
+    #[clap(long)]
+    program_owner: Option<String>,
+    /// Uses partitioned bloom filters, one keccak hash per insertion.
+    #[clap(long, default_value = "false")]
+    partitioned_bloom_filter: bool,
 }
 
 pub async fn create_batch_state_tree(options: Options) -> anyhow::Result<()> {
@@ -103,7 +113,7 @@ pub async fn create_batch_state_tree(options: Options) -> anyhow::Result<()> {
     };
     println!("read payer: {:?}", payer.pubkey());
 
-    let config = if let Some(config) = options.config {
+    let mut config = if let Some(config) = options.config {
         if config == "testnet" {
             InitStateTreeAccountsInstructionData::testnet_default()
         } else {
@@ -112,6 +122,10 @@ pub async fn create_batch_state_tree(options: Options) -> anyhow::Result<()> {
     } else {
         InitStateTreeAccountsInstructionData::default()
     };
+    if let Some(program_owner) = options.program_owner.as_ref() {
+        config.program_owner = Some(Pubkey::from_str(program_owner)?.into());
+    }
+    config.partitioned_bloom_filter = options.partitioned_bloom_filter;
 
     for ((merkle_tree_keypair, nullifier_queue_keypair), cpi_context_keypair) in mt_keypairs
         .iter()
@@ -93,7 +93,7 @@ fn shorten_address(addr: &str) -> String {
 //   7001-7012  : HasherError
 //  10001-10014 : ConcurrentMerkleTreeError
 //  11001-11009 : IndexedMerkleTreeError
-//  14301-14312 : BatchedMerkleTreeError
+//  14301-14314 : BatchedMerkleTreeError
 //  15001-15017 : ZeroCopyError
 //  16001-16050 : LightSdkError
 //  20000-20017 : AccountError (account-checks)
@@ -187,7 +187,7 @@ fn lookup_unique_range(code: u32) -> Option<&'static str> {
         11007 => Some("indexed-mt::ElementDoesNotExist"),
         11008 => Some("indexed-mt::ChangelogBufferSize"),
         11009 => Some("indexed-mt::ArrayFull"),
-        // BatchedMerkleTreeError (14301-14312)
+        // BatchedMerkleTreeError (14301-14314)
         14301 => Some("batched-mt::BatchNotReady"),
         14302 => Some("batched-mt::BatchAlreadyInserted"),
         14303 => Some("batched-mt::BatchInsertFailed"),
@@ -200,6 +200,8 @@ fn lookup_unique_range(code: u32) -> Option<&'static str> {
         14310 => Some("batched-mt::TreeIsFull"),
         14311 => Some("batched-mt::NonInclusionCheckFailed"),
         14312 => Some("batched-mt::BloomFilterNotZeroed"),
+        14313 => Some("batched-mt::UnsupportedParameters"),
+        14314 => Some("batched-mt::InvalidRootHistoryCapacity"),
         // ZeroCopyError (15001-15017, note: 15005 unused)
         15001 => Some("zero-copy::Full"),
         15002 => Some("zero-copy::ArraySize"),
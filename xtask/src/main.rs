@@ -1,6 +1,7 @@
 use clap::{Parser, ValueEnum};
 
 mod bench;
+mod check_indexer_consistency;
 mod close_buffer;
 mod create_batch_address_tree;
 mod create_batch_state_tree;
@@ -16,6 +17,7 @@ mod fetch_block_events;
 mod fetch_failed_txs;
 mod fetch_keypair_txs;
 mod hash_set;
+mod migrate_state;
 mod new_deployment;
 mod print_state_tree;
 mod reinit_cpi_accounts;
@@ -102,6 +104,14 @@ enum Command {
     /// Serializes the Close instruction as a bs58 message for the Squads TX builder.
     /// Example: cargo xtask close-buffer --buffer FMkzXMexKDUKGxAm7oGsjs4LGEMhzk9C6uuYJBwJbjiN
     CloseBuffer(close_buffer::Options),
+    /// Compare Photon's view of Merkle trees with the on-chain accounts
+    /// (root, next index, sequence number, pending queue elements).
+    /// Example: cargo xtask check-indexer-consistency --pubkeys <TREE_PUBKEY> --network devnet --photon-url <URL>
+    CheckIndexerConsistency(check_indexer_consistency::Options),
+    /// Migrate the live leaves of a v1 state tree, optionally of some owners only, to a v2 state tree.
+    /// Must be run by the forester eligible for the v1 tree, progress is resumed from a file.
+    /// Example: cargo xtask migrate-state --merkle-tree <TREE_PUBKEY> --output-queue <QUEUE_PUBKEY> --network devnet
+    MigrateState(migrate_state::Options),
 }
 
 #[tokio::main]
@@ -147,5 +157,9 @@ async fn main() -> Result<(), anyhow::Error> {
             create_ctoken_account::create_ctoken_account(opts).await
         }
         Command::CloseBuffer(opts) => close_buffer::close_buffer(opts),
+        Command::CheckIndexerConsistency(opts) => {
+            check_indexer_consistency::check_indexer_consistency(opts).await
+        }
+        Command::MigrateState(opts) => migrate_state::migrate_state(opts).await,
     }
 }
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use account_compression::StateMerkleTreeAccount;
+use clap::Parser;
+use dirs::home_dir;
+use light_client::{
+    indexer::{
+        local::{LocalIndexer, LocalIndexerConfig},
+        photon_indexer::PhotonIndexer,
+        GetCompressedAccountsByOwnerConfig, TreeInfo,
+    },
+    migration::{
+        get_migrate_state_instructions, get_migrate_tree_instructions, MigrateStateBatch,
+        MigrateStateConfig,
+    },
+    rpc::{LightClient, LightClientConfig, Rpc},
+};
+use light_compressed_account::TreeType;
+use light_registry::{
+    protocol_config::state::ProtocolConfigPda, utils::get_protocol_config_pda_address,
+};
+use solana_sdk::{
+    bs58,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+
+/// Accounts fetched per page, proofs of a page are patched with the
+/// changelog of the v1 tree (1400 entries).
+const PAGE_LIMIT: u16 = 500;
+
+#[derive(Debug, Parser)]
+pub struct Options {
+    /// v1 state Merkle tree to migrate leaves from
+    #[clap(long)]
+    merkle_tree: String,
+    /// Output queue of the v2 state tree to migrate leaves to
+    #[clap(long)]
+    output_queue: String,
+    /// Only migrate the compressed accounts of these owners, found with the
+    /// photon indexer. Default: all live leaves of the tree, found by replaying
+    /// the tree's transactions from the rpc
+    #[clap(long, value_delimiter = ',')]
+    owners: Vec<String>,
+    /// Forester keypair, must be eligible for the v1 tree in the current light slot,
+    /// default: ~/.config/solana/id.json
+    #[clap(long)]
+    payer: Option<PathBuf>,
+    /// Forester pda derivation, default: payer
+    #[clap(long)]
+    derivation: Option<String>,
+    /// Network: mainnet, devnet, local, or custom URL
+    #[clap(long, default_value = "mainnet")]
+    network: String,
+    /// Photon indexer URL, including the api key if required, used with `owners`
+    #[clap(long, default_value = "http://127.0.0.1:8784")]
+    photon_url: String,
+    /// File of migrated leaf hashes, migrations are resumed from it,
+    /// default: ./target/migrate-state-<merkle_tree>.txt
+    #[clap(long)]
+    progress_file: Option<PathBuf>,
+    /// Only count the leaves to migrate
+    #[clap(long, default_value = "false")]
+    dry_run: bool,
+}
+
+pub async fn migrate_state(options: Options) -> anyhow::Result<()> {
+    let rpc_url = match options.network.as_str() {
+        "local" => String::from("http://127.0.0.1:8899"),
+        "devnet" => String::from("https://api.devnet.solana.com"),
+        "mainnet" => String::from("https://api.mainnet-beta.solana.com"),
+        _ => options.network.clone(),
+    };
+    let mut rpc = LightClient::new(LightClientConfig {
+        url: rpc_url.clone(),
+        photon_url: None,
+        commitment_config: None,
+        fetch_active_tree: false,
+    })
+    .await?;
+
+    let payer = if let Some(payer) = options.payer.as_ref() {
+        read_keypair_file(payer).unwrap_or_else(|_| panic!("{:?}", options.payer))
+    } else {
+        let keypair_path: PathBuf = home_dir()
+            .expect("Could not find home directory")
+            .join(".config/solana/id.json");
+        read_keypair_file(keypair_path.clone())
+            .unwrap_or_else(|_| panic!("Keypair not found in default path {:?}", keypair_path))
+    };
+    let merkle_tree = Pubkey::from_str(&options.merkle_tree)?;
+    let derivation = match options.derivation.as_ref() {
+        Some(derivation) => Pubkey::from_str(derivation)?,
+        None => payer.pubkey(),
+    };
+    let owners = options
+        .owners
+        .iter()
+        .map(|owner| Pubkey::from_str(owner))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let protocol_config_pda = get_protocol_config_pda_address().0;
+    let protocol_config = rpc
+        .get_anchor_account::<ProtocolConfigPda>(&protocol_config_pda)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Protocol config {} not found", protocol_config_pda))?
+        .config;
+    let slot = rpc.get_slot().await?;
+    let epoch = protocol_config
+        .get_current_active_epoch(slot)
+        .map_err(|e| anyhow::anyhow!("Failed to get current active epoch: {:?}", e))?;
+    let config = MigrateStateConfig {
+        authority: payer.pubkey(),
+        derivation,
+        epoch,
+        output_queue: Pubkey::from_str(&options.output_queue)?,
+    };
+
+    let progress_file = options
+        .progress_file
+        .unwrap_or_else(|| PathBuf::from(format!("./target/migrate-state-{}.txt", merkle_tree)));
+    let mut progress = Progress::open(&progress_file)?;
+    println!("Merkle tree: {}", merkle_tree);
+    println!("Output queue: {}", config.output_queue);
+    println!("Forester: {}, epoch {}", payer.pubkey(), epoch);
+    println!(
+        "Progress file: {:?}, {} leaves migrated previously",
+        progress_file,
+        progress.migrated.len()
+    );
+
+    if owners.is_empty() {
+        let queue = rpc
+            .get_anchor_account::<StateMerkleTreeAccount>(&merkle_tree)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("v1 state tree {} not found", merkle_tree))?
+            .metadata
+            .associated_queue
+            .into();
+        let mut indexer = LocalIndexer::new(LocalIndexerConfig {
+            sync_address: Some(merkle_tree),
+            ..LocalIndexerConfig::new(
+                rpc_url,
+                vec![TreeInfo {
+                    tree: merkle_tree,
+                    queue,
+                    cpi_context: None,
+                    next_tree_info: None,
+                    tree_type: TreeType::StateV1,
+                }],
+            )
+        })
+        .await?;
+        // Each page is migrated with proofs of the synced tree, the next page
+        // is fetched after indexing the migrations of the previous page.
+        loop {
+            let num_transactions = indexer.sync().await?;
+            println!("Indexed {} transactions of the tree", num_transactions);
+            if options.dry_run {
+                progress.num_pending = indexer.get_live_leaves(&merkle_tree)?.len();
+                break;
+            }
+            let batch =
+                get_migrate_tree_instructions(&indexer, merkle_tree, &config, PAGE_LIMIT as usize)
+                    .await?;
+            if batch.instructions.is_empty() {
+                break;
+            }
+            let num_migrated = progress.num_migrated;
+            progress.send(&mut rpc, &payer, batch, false).await?;
+            println!(
+                "page done, total: {} pending, {} migrated, {} failed",
+                progress.num_pending, progress.num_migrated, progress.num_failed
+            );
+            if progress.num_migrated == num_migrated {
+                break;
+            }
+        }
+    } else {
+        let indexer = PhotonIndexer::new(options.photon_url.clone());
+        for owner in owners.iter() {
+            let mut cursor = None;
+            loop {
+                let batch = get_migrate_state_instructions(
+                    &indexer,
+                    owner,
+                    Some(merkle_tree),
+                    &config,
+                    Some(GetCompressedAccountsByOwnerConfig {
+                        filters: None,
+                        data_slice: None,
+                        cursor,
+                        limit: Some(PAGE_LIMIT),
+                    }),
+                )
+                .await?;
+                cursor = batch.cursor.clone();
+                progress
+                    .send(&mut rpc, &payer, batch, options.dry_run)
+                    .await?;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            println!(
+                "owner {} done, total: {} pending, {} migrated, {} failed",
+                owner, progress.num_pending, progress.num_migrated, progress.num_failed
+            );
+        }
+    }
+
+    if options.dry_run {
+        println!("{} leaves to migrate", progress.num_pending);
+        return Ok(());
+    }
+    println!(
+        "Migrated {} of {} leaves, {} failed",
+        progress.num_migrated, progress.num_pending, progress.num_failed
+    );
+    if progress.num_failed > 0 {
+        anyhow::bail!(
+            "{} migrations failed, rerun to resume, the forester must be eligible for the tree",
+            progress.num_failed
+        );
+    }
+    Ok(())
+}
+
+/// Migrated leaves, persisted to the progress file, and counters.
+struct Progress {
+    migrated: HashSet<String>,
+    file: File,
+    num_pending: usize,
+    num_migrated: usize,
+    num_failed: usize,
+}
+
+impl Progress {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let migrated = fs::read_to_string(path)
+            .map(|progress| progress.lines().map(String::from).collect())
+            .unwrap_or_default();
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            migrated,
+            file,
+            num_pending: 0,
+            num_migrated: 0,
+            num_failed: 0,
+        })
+    }
+
+    /// Sends the instructions of `batch` one per transaction, failed
+    /// migrations are counted and skipped.
+    async fn send(
+        &mut self,
+        rpc: &mut LightClient,
+        payer: &Keypair,
+        batch: MigrateStateBatch,
+        dry_run: bool,
+    ) -> anyhow::Result<()> {
+        for (instruction, hash) in batch.instructions.into_iter().zip(batch.hashes.iter()) {
+            let hash = bs58::encode(hash).into_string();
+            // The indexer may not have caught up with previous migrations.
+            if self.migrated.contains(&hash) {
+                continue;
+            }
+            self.num_pending += 1;
+            if dry_run {
+                continue;
+            }
+            match rpc
+                .create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+                .await
+            {
+                Ok(signature) => {
+                    self.num_migrated += 1;
+                    println!("migrated {}: {}", hash, signature);
+                    writeln!(self.file, "{}", hash)?;
+                    self.migrated.insert(hash);
+                }
+                Err(e) => {
+                    self.num_failed += 1;
+                    println!("failed to migrate {}: {:?}", hash, e);
+                }
+            }
+        }
+        Ok(())
+    }
+}